@@ -9,7 +9,7 @@ use askar_crypto::{
     },
     buffer::{SecretBytes, WriteBuffer},
     encrypt::{KeyAeadInPlace, KeyAeadMeta},
-    random::fill_random,
+    random::{fill_random, fill_random_buffered},
     repr::KeyGen,
 };
 
@@ -26,6 +26,10 @@ fn criterion_benchmark(c: &mut Criterion) {
     c.bench_function("random nonce", move |b| {
         b.iter(AesKey::<A128Gcm>::random_nonce)
     });
+    c.bench_function("random nonce buffered", move |b| {
+        let mut nonce = [0u8; 24];
+        b.iter(|| fill_random_buffered(black_box(&mut nonce)))
+    });
     c.bench_function("aes128gcm encrypt", move |b| {
         let key = AesKey::<A128Gcm>::random().unwrap();
         let nonce = AesKey::<A128Gcm>::random_nonce();
@@ -5,6 +5,7 @@ use core::fmt::{self, Debug, Formatter};
 use aead::{generic_array::ArrayLength, AeadCore, AeadInPlace, KeyInit, KeySizeUser};
 use aes_gcm::{Aes128Gcm, Aes256Gcm};
 use serde::{Deserialize, Serialize};
+use subtle::{Choice, ConstantTimeEq};
 use zeroize::Zeroize;
 
 use super::{AesTypes, HasKeyAlg, KeyAlg};
@@ -72,9 +73,16 @@ impl<T: AesType> Debug for AesKey<T> {
     }
 }
 
+impl<T: AesType> ConstantTimeEq for AesKey<T> {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.0.ct_eq(&other.0)
+    }
+}
+
 impl<T: AesType> PartialEq for AesKey<T> {
+    #[inline]
     fn eq(&self, other: &Self) -> bool {
-        other.0 == self.0
+        self.ct_eq(other).into()
     }
 }
 
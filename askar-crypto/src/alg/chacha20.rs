@@ -5,6 +5,7 @@ use core::fmt::{self, Debug, Formatter};
 use aead::{AeadCore, AeadInPlace, KeyInit, KeySizeUser};
 use chacha20poly1305::{ChaCha20Poly1305, XChaCha20Poly1305};
 use serde::{Deserialize, Serialize};
+use subtle::{Choice, ConstantTimeEq};
 use zeroize::Zeroize;
 
 use super::{Chacha20Types, HasKeyAlg, KeyAlg};
@@ -97,9 +98,16 @@ impl<T: Chacha20Type> Debug for Chacha20Key<T> {
     }
 }
 
+impl<T: Chacha20Type> ConstantTimeEq for Chacha20Key<T> {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.0.ct_eq(&other.0)
+    }
+}
+
 impl<T: Chacha20Type> PartialEq for Chacha20Key<T> {
+    #[inline]
     fn eq(&self, other: &Self) -> bool {
-        other.0 == self.0
+        self.ct_eq(other).into()
     }
 }
 
@@ -261,6 +269,20 @@ mod tests {
         test_encrypt::<XC20P>();
     }
 
+    #[test]
+    fn key_ct_eq() {
+        fn test_ct_eq<T: Chacha20Type>() {
+            let key = Chacha20Key::<T>::random().unwrap();
+            let other = Chacha20Key::<T>::random().unwrap();
+            assert!(bool::from(key.ct_eq(&key.clone())));
+            assert!(!bool::from(key.ct_eq(&other)));
+            assert_eq!(key, key.clone());
+            assert_ne!(key, other);
+        }
+        test_ct_eq::<C20P>();
+        test_ct_eq::<XC20P>();
+    }
+
     #[test]
     fn serialize_round_trip() {
         fn test_serialize<T: Chacha20Type>() {
@@ -21,6 +21,7 @@ pub use self::error::{Error, ErrorKind};
 
 // re-export
 pub use aead::generic_array;
+pub use subtle;
 
 pub mod alg;
 
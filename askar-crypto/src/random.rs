@@ -8,6 +8,8 @@ use chacha20::{
     ChaCha20,
 };
 use rand::{CryptoRng, RngCore, SeedableRng};
+#[cfg(all(feature = "std", feature = "getrandom"))]
+use zeroize::Zeroize;
 
 #[cfg(all(feature = "alloc", feature = "getrandom"))]
 use crate::buffer::SecretBytes;
@@ -34,11 +36,23 @@ impl<C: CryptoRng + RngCore> KeyMaterial for C {
     }
 }
 
+#[cfg(feature = "test_rng")]
+std::thread_local! {
+    static THREAD_RNG: core::cell::RefCell<Option<std::boxed::Box<dyn RngCore>>> =
+        core::cell::RefCell::new(None);
+}
+
 #[cfg(feature = "getrandom")]
 #[cfg_attr(docsrs, doc(cfg(feature = "getrandom")))]
 #[inline]
 /// Obtain an instance of the default random number generator
 pub fn default_rng() -> impl CryptoRng + RngCore + Debug + Clone {
+    DefaultRng
+}
+
+#[cfg(feature = "getrandom")]
+#[inline]
+fn system_rng() -> impl CryptoRng + RngCore {
     #[cfg(feature = "std_rng")]
     {
         rand::rngs::ThreadRng::default()
@@ -49,6 +63,86 @@ pub fn default_rng() -> impl CryptoRng + RngCore + Debug + Clone {
     }
 }
 
+/// The default random number generator, which reads from the generator
+/// installed by `with_rng` if any, and otherwise from the system generator
+#[cfg(feature = "getrandom")]
+#[derive(Clone, Copy, Debug)]
+struct DefaultRng;
+
+#[cfg(feature = "getrandom")]
+impl CryptoRng for DefaultRng {}
+
+#[cfg(feature = "getrandom")]
+impl RngCore for DefaultRng {
+    #[inline]
+    fn next_u32(&mut self) -> u32 {
+        let mut buf = [0; 4];
+        self.fill_bytes(&mut buf[..]);
+        u32::from_le_bytes(buf)
+    }
+
+    #[inline]
+    fn next_u64(&mut self) -> u64 {
+        let mut buf = [0; 8];
+        self.fill_bytes(&mut buf[..]);
+        u64::from_le_bytes(buf)
+    }
+
+    #[inline]
+    fn fill_bytes(&mut self, bytes: &mut [u8]) {
+        // a re-entrant call from within the installed generator finds it
+        // already borrowed, and reads from the system generator instead
+        #[cfg(feature = "test_rng")]
+        if THREAD_RNG.with(|rng| match rng.try_borrow_mut().as_deref_mut() {
+            Ok(Some(rng)) => {
+                rng.fill_bytes(bytes);
+                true
+            }
+            _ => false,
+        }) {
+            return;
+        }
+        system_rng().fill_bytes(bytes)
+    }
+
+    #[inline]
+    fn try_fill_bytes(&mut self, bytes: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(bytes);
+        Ok(())
+    }
+}
+
+/// Run a function with the default random number generator replaced by `rng`
+/// on the current thread
+///
+/// Key generation, nonces and ephemeral keys produced by `f` using the default
+/// generator are derived from `rng`, such that operations may be reproduced
+/// exactly when it is seeded with a fixed value, for instance using
+/// `RandomDet`. Operations performed on other threads are not affected. This
+/// is intended for testing only.
+///
+/// If `rng` itself draws from the default generator, it receives output from
+/// the system generator. Calling `with_rng` from within `rng` panics.
+#[cfg(feature = "test_rng")]
+#[cfg_attr(docsrs, doc(cfg(feature = "test_rng")))]
+pub fn with_rng<T>(rng: impl CryptoRng + RngCore + 'static, f: impl FnOnce() -> T) -> T {
+    struct Restore(Option<std::boxed::Box<dyn RngCore>>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            let prev = self.0.take();
+            THREAD_RNG.with(|rng| *rng.borrow_mut() = prev);
+        }
+    }
+
+    let _restore = Restore(THREAD_RNG.with(|cur| {
+        cur.try_borrow_mut()
+            .expect("with_rng called from within the installed generator")
+            .replace(std::boxed::Box::new(rng))
+    }));
+    f()
+}
+
 /// Fill a mutable slice with random data using the
 /// system random number generator.
 #[cfg(feature = "getrandom")]
@@ -57,6 +151,102 @@ pub fn fill_random(value: &mut [u8]) {
     default_rng().fill_bytes(value);
 }
 
+/// The number of random bytes drawn from the system generator at once
+/// by `fill_random_buffered`
+#[cfg(all(feature = "std", feature = "getrandom"))]
+const RANDOM_BUFFER_SIZE: usize = 1024;
+
+/// The largest output served from the buffer of random bytes
+#[cfg(all(feature = "std", feature = "getrandom"))]
+const RANDOM_BUFFER_MAX_OUTPUT: usize = 64;
+
+#[cfg(all(feature = "std", feature = "getrandom"))]
+std::thread_local! {
+    static RANDOM_BUFFER: core::cell::RefCell<RandomBuffer> =
+        const { core::cell::RefCell::new(RandomBuffer::new()) };
+}
+
+/// Random bytes drawn in advance from the system generator
+///
+/// Each byte is handed out at most once: bytes are zeroed as they are
+/// consumed, and the buffer is discarded when the process identifier
+/// changes, such that a forked child process never repeats the output
+/// of its parent.
+#[cfg(all(feature = "std", feature = "getrandom"))]
+struct RandomBuffer {
+    bytes: [u8; RANDOM_BUFFER_SIZE],
+    pos: usize,
+    pid: u32,
+}
+
+#[cfg(all(feature = "std", feature = "getrandom"))]
+impl RandomBuffer {
+    const fn new() -> Self {
+        Self {
+            bytes: [0u8; RANDOM_BUFFER_SIZE],
+            pos: RANDOM_BUFFER_SIZE,
+            pid: 0,
+        }
+    }
+
+    fn fill(&mut self, output: &mut [u8]) {
+        let pid = std::process::id();
+        if self.pid != pid || RANDOM_BUFFER_SIZE - self.pos < output.len() {
+            system_rng().fill_bytes(&mut self.bytes);
+            self.pos = 0;
+            self.pid = pid;
+        }
+        let end = self.pos + output.len();
+        output.copy_from_slice(&self.bytes[self.pos..end]);
+        self.bytes[self.pos..end].zeroize();
+        self.pos = end;
+    }
+}
+
+#[cfg(all(feature = "std", feature = "getrandom"))]
+impl Drop for RandomBuffer {
+    fn drop(&mut self) {
+        self.bytes.zeroize();
+    }
+}
+
+/// Check whether a generator is installed by `with_rng` on the current thread
+/// and available for use
+#[cfg(all(feature = "std", feature = "getrandom"))]
+#[inline]
+fn rng_installed() -> bool {
+    #[cfg(feature = "test_rng")]
+    {
+        THREAD_RNG.with(|rng| rng.try_borrow().map_or(false, |rng| rng.is_some()))
+    }
+    #[cfg(not(feature = "test_rng"))]
+    {
+        false
+    }
+}
+
+/// Fill a small mutable slice with random data, such as a nonce.
+///
+/// Output is taken from a per-thread buffer which is refilled from the
+/// system random number generator in batches, avoiding a call to the
+/// system generator for each value. Larger outputs, and outputs produced
+/// while a generator is installed by `with_rng`, are read directly from
+/// the default generator.
+#[cfg(feature = "getrandom")]
+#[inline]
+pub fn fill_random_buffered(value: &mut [u8]) {
+    #[cfg(feature = "std")]
+    if value.len() <= RANDOM_BUFFER_MAX_OUTPUT
+        && !rng_installed()
+        && RANDOM_BUFFER
+            .try_with(|buf| buf.borrow_mut().fill(value))
+            .is_ok()
+    {
+        return;
+    }
+    fill_random(value)
+}
+
 /// Written to be compatible with randombytes_deterministic in libsodium,
 /// used to generate a deterministic symmetric encryption key
 pub fn fill_random_deterministic(seed: &[u8], output: &mut [u8]) -> Result<(), Error> {
@@ -144,6 +334,77 @@ mod tests {
     use crate::buffer::HexRepr;
     use std::string::ToString;
 
+    #[cfg(feature = "test_rng")]
+    #[test]
+    fn with_rng_deterministic() {
+        let seed = b"testseed000000000000000000000001";
+        let mut expected = [0u8; 32];
+        fill_random_deterministic(seed, &mut expected).unwrap();
+
+        let mut output = [0u8; 32];
+        with_rng(RandomDet::new(seed), || fill_random(&mut output));
+        assert_eq!(output, expected);
+
+        // the system generator is restored afterward
+        fill_random(&mut output);
+        assert_ne!(output, expected);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn fill_random_buffered_unique() {
+        let mut outputs = std::collections::HashSet::new();
+        let mut output = [0u8; 24];
+        for _ in 0..(2 * RANDOM_BUFFER_SIZE / output.len()) {
+            fill_random_buffered(&mut output);
+            assert!(outputs.insert(output));
+        }
+    }
+
+    #[cfg(feature = "test_rng")]
+    #[test]
+    fn fill_random_buffered_with_rng() {
+        // the buffer is bypassed when a generator is installed
+        let mut output = [0u8; 24];
+        let seed = b"testseed000000000000000000000001";
+        let mut expected = [0u8; 24];
+        fill_random_deterministic(seed, &mut expected).unwrap();
+        with_rng(RandomDet::new(seed), || fill_random_buffered(&mut output));
+        assert_eq!(output, expected);
+    }
+
+    #[cfg(feature = "test_rng")]
+    #[test]
+    fn with_rng_reentrant() {
+        #[derive(Debug)]
+        struct Reentrant;
+
+        impl CryptoRng for Reentrant {}
+
+        impl RngCore for Reentrant {
+            fn next_u32(&mut self) -> u32 {
+                DefaultRng.next_u32()
+            }
+
+            fn next_u64(&mut self) -> u64 {
+                DefaultRng.next_u64()
+            }
+
+            fn fill_bytes(&mut self, dest: &mut [u8]) {
+                fill_random(dest)
+            }
+
+            fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+                self.fill_bytes(dest);
+                Ok(())
+            }
+        }
+
+        let mut output = [0u8; 32];
+        with_rng(Reentrant, || fill_random(&mut output));
+        assert_ne!(output, [0u8; 32]);
+    }
+
     #[test]
     fn fill_random_det_expected() {
         let seed = b"testseed000000000000000000000001";
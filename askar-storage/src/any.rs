@@ -1,22 +1,36 @@
 //! Generic backend support
 
-use std::{fmt::Debug, sync::Arc};
+use std::{fmt::Debug, future::Future, sync::Arc, time::Duration};
 
-use super::{Backend, BackendSession, ManageBackend};
+use super::{
+    AuditRecord, Backend, BackendSession, IntegrityReport, IsolationLevel, ManageBackend,
+    ProfileDetails, ProfileLimits, QueryPlan, RekeyCallback, RepairReport, SavepointOperation,
+};
 use crate::{
-    entry::{Entry, EntryKind, EntryOperation, EntryTag, Scan, TagFilter},
+    entry::{
+        Entry, EntryCount, EntryEvent, EntryKind, EntryOperation, EntryPrecondition, EntryTag,
+        OrderBy, Scan, Subscription, TagFilter, TagSelection,
+    },
     error::Error,
-    future::BoxFuture,
-    options::IntoOptions,
-    protect::{PassKey, StoreKeyMethod},
+    future::{sleep, BoxFuture},
+    options::{IntoOptions, Options},
+    protect::{kdf::KdfMethod, PassKey, StoreKeyMethod},
 };
 
+use super::backend::custom;
+
 #[cfg(feature = "postgres")]
 use super::postgres;
 
 #[cfg(feature = "sqlite")]
 use super::sqlite;
 
+/// The delay before the first retry when opening a store
+const DEFAULT_OPEN_RETRY_DELAY: Duration = Duration::from_millis(250);
+
+/// The maximum delay between retries when opening a store
+const DEFAULT_OPEN_RETRY_MAX_DELAY: Duration = Duration::from_secs(10);
+
 /// A dynamic store backend instance
 #[derive(Clone, Debug)]
 pub struct AnyBackend(Arc<dyn Backend<Session = AnyBackendSession>>);
@@ -58,6 +72,49 @@ impl<B: Backend> Backend for WrapBackend<B> {
         self.0.list_profiles()
     }
 
+    #[inline]
+    fn list_profile_details(&self) -> BoxFuture<'_, Result<Vec<ProfileDetails>, Error>> {
+        self.0.list_profile_details()
+    }
+
+    #[inline]
+    fn set_profile_metadata(
+        &self,
+        name: String,
+        metadata: Option<Vec<u8>>,
+    ) -> BoxFuture<'_, Result<(), Error>> {
+        self.0.set_profile_metadata(name, metadata)
+    }
+
+    #[inline]
+    fn set_profile_limits(
+        &self,
+        name: String,
+        limits: ProfileLimits,
+    ) -> BoxFuture<'_, Result<(), Error>> {
+        self.0.set_profile_limits(name, limits)
+    }
+
+    #[inline]
+    fn set_profile_access_key(
+        &self,
+        name: String,
+        method: StoreKeyMethod,
+        pass_key: PassKey<'_>,
+    ) -> BoxFuture<'_, Result<(), Error>> {
+        self.0.set_profile_access_key(name, method, pass_key)
+    }
+
+    #[inline]
+    fn remove_profile_access_key(&self, name: String) -> BoxFuture<'_, Result<(), Error>> {
+        self.0.remove_profile_access_key(name)
+    }
+
+    #[inline]
+    fn rename_profile(&self, name: String, new_name: String) -> BoxFuture<'_, Result<(), Error>> {
+        self.0.rename_profile(name, new_name)
+    }
+
     #[inline]
     fn remove_profile(&self, name: String) -> BoxFuture<'_, Result<bool, Error>> {
         self.0.remove_profile(name)
@@ -72,9 +129,24 @@ impl<B: Backend> Backend for WrapBackend<B> {
         tag_filter: Option<TagFilter>,
         offset: Option<i64>,
         limit: Option<i64>,
+        order_by: Option<OrderBy>,
+        descending: bool,
+        after: Option<String>,
+        tags: TagSelection,
     ) -> BoxFuture<'_, Result<Scan<'static, Entry>, Error>> {
-        self.0
-            .scan(profile, kind, category, tag_filter, offset, limit)
+        self.0.scan(
+            profile, kind, category, tag_filter, offset, limit, order_by, descending, after, tags,
+        )
+    }
+
+    #[inline]
+    fn subscribe(
+        &self,
+        profile: Option<String>,
+        kind: Option<EntryKind>,
+        category: Option<String>,
+    ) -> BoxFuture<'_, Result<Subscription<'static>, Error>> {
+        self.0.subscribe(profile, kind, category)
     }
 
     #[inline]
@@ -85,12 +157,48 @@ impl<B: Backend> Backend for WrapBackend<B> {
     }
 
     #[inline]
-    fn rekey(
+    fn transaction(
+        &self,
+        profile: Option<String>,
+        isolation: IsolationLevel,
+    ) -> Result<Self::Session, Error> {
+        Ok(AnyBackendSession(Box::new(
+            self.0.transaction(profile, isolation)?,
+        )))
+    }
+
+    #[inline]
+    fn rekey_with_progress(
         &mut self,
         method: StoreKeyMethod,
         key: PassKey<'_>,
+        progress: Option<RekeyCallback>,
     ) -> BoxFuture<'_, Result<(), Error>> {
-        self.0.rekey(method, key)
+        self.0.rekey_with_progress(method, key, progress)
+    }
+
+    #[inline]
+    fn upgrade_kdf(
+        &mut self,
+        minimum: KdfMethod,
+        pass_key: PassKey<'_>,
+    ) -> BoxFuture<'_, Result<bool, Error>> {
+        self.0.upgrade_kdf(minimum, pass_key)
+    }
+
+    #[inline]
+    fn snapshot<'q>(&'q self, target: &'q str) -> BoxFuture<'q, Result<(), Error>> {
+        self.0.snapshot(target)
+    }
+
+    #[inline]
+    fn verify_integrity(&self, quarantine: bool) -> BoxFuture<'_, Result<IntegrityReport, Error>> {
+        self.0.verify_integrity(quarantine)
+    }
+
+    #[inline]
+    fn repair(&self, dry_run: bool) -> BoxFuture<'_, Result<RepairReport, Error>> {
+        self.0.repair(dry_run)
     }
 
     #[inline]
@@ -128,6 +236,49 @@ impl Backend for AnyBackend {
         self.0.list_profiles()
     }
 
+    #[inline]
+    fn list_profile_details(&self) -> BoxFuture<'_, Result<Vec<ProfileDetails>, Error>> {
+        self.0.list_profile_details()
+    }
+
+    #[inline]
+    fn set_profile_metadata(
+        &self,
+        name: String,
+        metadata: Option<Vec<u8>>,
+    ) -> BoxFuture<'_, Result<(), Error>> {
+        self.0.set_profile_metadata(name, metadata)
+    }
+
+    #[inline]
+    fn set_profile_limits(
+        &self,
+        name: String,
+        limits: ProfileLimits,
+    ) -> BoxFuture<'_, Result<(), Error>> {
+        self.0.set_profile_limits(name, limits)
+    }
+
+    #[inline]
+    fn set_profile_access_key(
+        &self,
+        name: String,
+        method: StoreKeyMethod,
+        pass_key: PassKey<'_>,
+    ) -> BoxFuture<'_, Result<(), Error>> {
+        self.0.set_profile_access_key(name, method, pass_key)
+    }
+
+    #[inline]
+    fn remove_profile_access_key(&self, name: String) -> BoxFuture<'_, Result<(), Error>> {
+        self.0.remove_profile_access_key(name)
+    }
+
+    #[inline]
+    fn rename_profile(&self, name: String, new_name: String) -> BoxFuture<'_, Result<(), Error>> {
+        self.0.rename_profile(name, new_name)
+    }
+
     #[inline]
     fn remove_profile(&self, name: String) -> BoxFuture<'_, Result<bool, Error>> {
         self.0.remove_profile(name)
@@ -142,9 +293,24 @@ impl Backend for AnyBackend {
         tag_filter: Option<TagFilter>,
         offset: Option<i64>,
         limit: Option<i64>,
+        order_by: Option<OrderBy>,
+        descending: bool,
+        after: Option<String>,
+        tags: TagSelection,
     ) -> BoxFuture<'_, Result<Scan<'static, Entry>, Error>> {
-        self.0
-            .scan(profile, kind, category, tag_filter, offset, limit)
+        self.0.scan(
+            profile, kind, category, tag_filter, offset, limit, order_by, descending, after, tags,
+        )
+    }
+
+    #[inline]
+    fn subscribe(
+        &self,
+        profile: Option<String>,
+        kind: Option<EntryKind>,
+        category: Option<String>,
+    ) -> BoxFuture<'_, Result<Subscription<'static>, Error>> {
+        self.0.subscribe(profile, kind, category)
     }
 
     #[inline]
@@ -155,19 +321,60 @@ impl Backend for AnyBackend {
     }
 
     #[inline]
-    fn rekey(
+    fn transaction(
+        &self,
+        profile: Option<String>,
+        isolation: IsolationLevel,
+    ) -> Result<Self::Session, Error> {
+        Ok(AnyBackendSession(Box::new(
+            self.0.transaction(profile, isolation)?,
+        )))
+    }
+
+    #[inline]
+    fn rekey_with_progress(
         &mut self,
         method: StoreKeyMethod,
         key: PassKey<'_>,
+        progress: Option<RekeyCallback>,
     ) -> BoxFuture<'_, Result<(), Error>> {
         match Arc::get_mut(&mut self.0) {
-            Some(inner) => inner.rekey(method, key),
+            Some(inner) => inner.rekey_with_progress(method, key, progress),
+            None => Box::pin(std::future::ready(Err(err_msg!(
+                "Cannot re-key a store with multiple references"
+            )))),
+        }
+    }
+
+    #[inline]
+    fn upgrade_kdf(
+        &mut self,
+        minimum: KdfMethod,
+        pass_key: PassKey<'_>,
+    ) -> BoxFuture<'_, Result<bool, Error>> {
+        match Arc::get_mut(&mut self.0) {
+            Some(inner) => inner.upgrade_kdf(minimum, pass_key),
             None => Box::pin(std::future::ready(Err(err_msg!(
                 "Cannot re-key a store with multiple references"
             )))),
         }
     }
 
+    #[inline]
+    fn snapshot<'q>(&'q self, target: &'q str) -> BoxFuture<'q, Result<(), Error>> {
+        self.0.snapshot(target)
+    }
+
+    #[inline]
+    fn verify_integrity(&self, quarantine: bool) -> BoxFuture<'_, Result<IntegrityReport, Error>> {
+        self.0.verify_integrity(quarantine)
+    }
+
+    #[inline]
+    fn repair(&self, dry_run: bool) -> BoxFuture<'_, Result<RepairReport, Error>> {
+        self.0.repair(dry_run)
+    }
+
     #[inline]
     fn close(&self) -> BoxFuture<'_, Result<(), Error>> {
         self.0.close()
@@ -189,6 +396,27 @@ impl BackendSession for AnyBackendSession {
         self.0.count(kind, category, tag_filter)
     }
 
+    /// Count the number of matching records in the store, grouped by category
+    fn count_grouped<'q>(
+        &'q mut self,
+        kind: Option<EntryKind>,
+        category: Option<&'q str>,
+        tag_filter: Option<TagFilter>,
+        group_tag: Option<&'q str>,
+    ) -> BoxFuture<'q, Result<Vec<EntryCount>, Error>> {
+        self.0.count_grouped(kind, category, tag_filter, group_tag)
+    }
+
+    /// Describe the query and execution plan used when scanning for matching records
+    fn explain_scan<'q>(
+        &'q mut self,
+        kind: Option<EntryKind>,
+        category: Option<&'q str>,
+        tag_filter: Option<TagFilter>,
+    ) -> BoxFuture<'q, Result<QueryPlan, Error>> {
+        self.0.explain_scan(kind, category, tag_filter)
+    }
+
     /// Fetch a single record from the store by category and name
     fn fetch<'q>(
         &'q mut self,
@@ -200,7 +428,18 @@ impl BackendSession for AnyBackendSession {
         self.0.fetch(kind, category, name, for_update)
     }
 
-    /// Fetch all matching records from the store
+    /// Fetch multiple records from the store by category and name
+    fn fetch_many<'q>(
+        &'q mut self,
+        kind: EntryKind,
+        category: &'q str,
+        names: &'q [&'q str],
+        for_update: bool,
+    ) -> BoxFuture<'q, Result<Vec<Entry>, Error>> {
+        self.0.fetch_many(kind, category, names, for_update)
+    }
+
+    /// Fetch all matching records from the store, in the order they were inserted
     fn fetch_all<'q>(
         &'q mut self,
         kind: Option<EntryKind>,
@@ -213,6 +452,18 @@ impl BackendSession for AnyBackendSession {
             .fetch_all(kind, category, tag_filter, limit, for_update)
     }
 
+    /// Search for records matching a full-text query
+    fn search<'q>(
+        &'q mut self,
+        kind: Option<EntryKind>,
+        category: Option<&'q str>,
+        text: &'q str,
+        tag_filter: Option<TagFilter>,
+        limit: Option<i64>,
+    ) -> BoxFuture<'q, Result<Vec<Entry>, Error>> {
+        self.0.search(kind, category, text, tag_filter, limit)
+    }
+
     /// Remove all matching records from the store
     fn remove_all<'q>(
         &'q mut self,
@@ -223,6 +474,16 @@ impl BackendSession for AnyBackendSession {
         self.0.remove_all(kind, category, tag_filter)
     }
 
+    /// Remove all matching records from the store, returning the removed records
+    fn remove_all_returning<'q>(
+        &'q mut self,
+        kind: Option<EntryKind>,
+        category: Option<&'q str>,
+        tag_filter: Option<TagFilter>,
+    ) -> BoxFuture<'q, Result<Vec<EntryEvent>, Error>> {
+        self.0.remove_all_returning(kind, category, tag_filter)
+    }
+
     /// Insert or replace a record in the store
     #[allow(clippy::too_many_arguments)]
     fn update<'q>(
@@ -239,6 +500,159 @@ impl BackendSession for AnyBackendSession {
             .update(kind, operation, category, name, value, tags, expiry_ms)
     }
 
+    /// Replace or remove a record in the store if a precondition holds
+    fn update_if<'q>(
+        &'q mut self,
+        kind: EntryKind,
+        operation: EntryOperation,
+        category: &'q str,
+        name: &'q str,
+        value: Option<&'q [u8]>,
+        tags: Option<&'q [EntryTag]>,
+        expiry_ms: Option<i64>,
+        precondition: &'q EntryPrecondition,
+    ) -> BoxFuture<'q, Result<(), Error>> {
+        self.0.update_if(
+            kind,
+            operation,
+            category,
+            name,
+            value,
+            tags,
+            expiry_ms,
+            precondition,
+        )
+    }
+
+    /// Insert a batch of new records into the store
+    fn insert_many<'q>(
+        &'q mut self,
+        entries: &'q [Entry],
+        expiry_ms: Option<i64>,
+    ) -> BoxFuture<'q, Result<(), Error>> {
+        self.0.insert_many(entries, expiry_ms)
+    }
+
+    /// Move all records in a category to another category
+    fn move_category<'q>(
+        &'q mut self,
+        kind: EntryKind,
+        category: &'q str,
+        new_category: &'q str,
+        rename_tags: &'q [(&'q str, &'q str)],
+    ) -> BoxFuture<'q, Result<i64, Error>> {
+        self.0
+            .move_category(kind, category, new_category, rename_tags)
+    }
+
+    /// Rename an existing record, preserving its value, tags and expiry
+    fn rename<'q>(
+        &'q mut self,
+        kind: EntryKind,
+        category: &'q str,
+        name: &'q str,
+        new_name: &'q str,
+    ) -> BoxFuture<'q, Result<(), Error>> {
+        self.0.rename(kind, category, name, new_name)
+    }
+
+    /// Add and remove tags on an existing record without rewriting its value
+    fn update_tags<'q>(
+        &'q mut self,
+        kind: EntryKind,
+        category: &'q str,
+        name: &'q str,
+        add_tags: &'q [EntryTag],
+        remove_tags: &'q [&'q str],
+    ) -> BoxFuture<'q, Result<(), Error>> {
+        self.0
+            .update_tags(kind, category, name, add_tags, remove_tags)
+    }
+
+    /// Store a chunk of opaque data associated with an existing record
+    fn write_chunk<'q>(
+        &'q mut self,
+        kind: EntryKind,
+        category: &'q str,
+        name: &'q str,
+        index: u32,
+        data: &'q [u8],
+    ) -> BoxFuture<'q, Result<(), Error>> {
+        self.0.write_chunk(kind, category, name, index, data)
+    }
+
+    /// Fetch the chunks associated with a record in index order
+    fn fetch_chunks<'q>(
+        &'q mut self,
+        kind: EntryKind,
+        category: &'q str,
+        name: &'q str,
+        start: u32,
+        limit: u32,
+    ) -> BoxFuture<'q, Result<Vec<Vec<u8>>, Error>> {
+        self.0.fetch_chunks(kind, category, name, start, limit)
+    }
+
+    /// Append a record of an operation to the audit log
+    fn append_audit<'q>(
+        &'q mut self,
+        operation: &'q str,
+        kind: Option<EntryKind>,
+        category: Option<&'q str>,
+        name: Option<&'q str>,
+        error: Option<&'q str>,
+    ) -> BoxFuture<'q, Result<(), Error>> {
+        self.0.append_audit(operation, kind, category, name, error)
+    }
+
+    /// Fetch records from the audit log of the profile
+    fn fetch_audit_log<'q>(
+        &'q mut self,
+        category: Option<&'q str>,
+        since: Option<i64>,
+        limit: Option<i64>,
+    ) -> BoxFuture<'q, Result<Vec<AuditRecord>, Error>> {
+        self.0.fetch_audit_log(category, since, limit)
+    }
+
+    /// Restore a record which has been marked as deleted
+    fn restore<'q>(
+        &'q mut self,
+        kind: EntryKind,
+        category: &'q str,
+        name: &'q str,
+    ) -> BoxFuture<'q, Result<(), Error>> {
+        self.0.restore(kind, category, name)
+    }
+
+    /// Permanently remove a record which has been marked as deleted
+    fn purge<'q>(
+        &'q mut self,
+        kind: EntryKind,
+        category: &'q str,
+        name: &'q str,
+    ) -> BoxFuture<'q, Result<(), Error>> {
+        self.0.purge(kind, category, name)
+    }
+
+    /// Permanently remove all matching records which have been marked as deleted
+    fn purge_all<'q>(
+        &'q mut self,
+        kind: Option<EntryKind>,
+        category: Option<&'q str>,
+    ) -> BoxFuture<'q, Result<i64, Error>> {
+        self.0.purge_all(kind, category)
+    }
+
+    /// Create, release or roll back to a named savepoint within the current transaction
+    fn savepoint<'q>(
+        &'q mut self,
+        name: &'q str,
+        operation: SavepointOperation,
+    ) -> BoxFuture<'q, Result<(), Error>> {
+        self.0.savepoint(name, operation)
+    }
+
     /// Test the connection to the store
     fn ping(&mut self) -> BoxFuture<'_, Result<(), Error>> {
         self.0.ping()
@@ -250,6 +664,188 @@ impl BackendSession for AnyBackendSession {
     }
 }
 
+/// The policy for retrying the opening or provisioning of a store following a
+/// transient failure, such as a database server which is not yet accepting
+/// connections
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct OpenRetryPolicy {
+    /// The maximum number of retries
+    retries: u32,
+    /// The delay before the first retry, doubled for each subsequent retry
+    delay: Duration,
+    /// The maximum delay between retries
+    max_delay: Duration,
+}
+
+impl OpenRetryPolicy {
+    /// Extract the retry policy from the store options
+    fn from_options(opts: &mut Options<'_>) -> Result<Self, Error> {
+        let retries = if let Some(retries) = opts.query.remove("open_retries") {
+            retries
+                .parse()
+                .map_err(err_map!(Input, "Error parsing 'open_retries' parameter"))?
+        } else {
+            0
+        };
+        let delay = if let Some(delay) = opts.query.remove("open_retry_delay") {
+            Duration::from_millis(delay.parse().map_err(err_map!(
+                Input,
+                "Error parsing 'open_retry_delay' parameter"
+            ))?)
+        } else {
+            DEFAULT_OPEN_RETRY_DELAY
+        };
+        let max_delay = if let Some(delay) = opts.query.remove("open_retry_max_delay") {
+            Duration::from_millis(delay.parse().map_err(err_map!(
+                Input,
+                "Error parsing 'open_retry_max_delay' parameter"
+            ))?)
+        } else {
+            DEFAULT_OPEN_RETRY_MAX_DELAY
+        };
+        Ok(Self {
+            retries,
+            delay,
+            max_delay: max_delay.max(delay),
+        })
+    }
+
+    /// The delay before a retry, starting from zero for the first retry
+    fn backoff(&self, attempt: u32) -> Duration {
+        self.delay
+            .saturating_mul(1u32.checked_shl(attempt).unwrap_or(u32::MAX))
+            .min(self.max_delay)
+    }
+
+    /// Run an operation, retrying it while it fails with a transient error
+    async fn run<T, F, Fut>(&self, mut f: F) -> Result<T, Error>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, Error>>,
+    {
+        let mut attempt = 0;
+        loop {
+            match f().await {
+                Err(err) if attempt < self.retries && err.is_transient() => {
+                    let delay = self.backoff(attempt);
+                    warn!(
+                        "Retrying store open in {:?} after transient error: {}",
+                        delay, err
+                    );
+                    sleep(delay).await;
+                    attempt += 1;
+                }
+                res => return res,
+            }
+        }
+    }
+}
+
+async fn open_any_backend(
+    opts: Options<'_>,
+    method: Option<StoreKeyMethod>,
+    pass_key: PassKey<'_>,
+    profile: Option<String>,
+) -> Result<AnyBackend, Error> {
+    match opts.scheme.as_ref() {
+        #[cfg(feature = "postgres")]
+        "postgres" => {
+            let opts = postgres::PostgresStoreOptions::new(opts)?;
+            let mgr = opts.open(method, pass_key, profile).await?;
+            Ok(into_any_backend(mgr))
+        }
+
+        #[cfg(feature = "sqlite")]
+        "sqlite" => {
+            let opts = sqlite::SqliteStoreOptions::new(opts)?;
+            let mgr = opts.open(method, pass_key, profile).await?;
+            Ok(into_any_backend(mgr))
+        }
+
+        scheme if custom::is_registered(scheme) => {
+            let opts = custom::CustomStoreOptions::new(opts)?;
+            let mgr = opts.open(method, pass_key, profile).await?;
+            Ok(into_any_backend(mgr))
+        }
+
+        _ => Err(err_msg!(
+            Unsupported,
+            "Unsupported backend: {}",
+            &opts.scheme
+        )),
+    }
+}
+
+#[cfg_attr(not(any(feature = "postgres", feature = "sqlite")), allow(unused))]
+async fn open_any_profile_backend(
+    opts: Options<'_>,
+    profile: String,
+    pass_key: PassKey<'_>,
+) -> Result<AnyBackend, Error> {
+    match opts.scheme.as_ref() {
+        #[cfg(feature = "postgres")]
+        "postgres" => {
+            let opts = postgres::PostgresStoreOptions::new(opts)?;
+            let mgr = opts.open_profile(profile, pass_key).await?;
+            Ok(into_any_backend(mgr))
+        }
+
+        #[cfg(feature = "sqlite")]
+        "sqlite" => {
+            let opts = sqlite::SqliteStoreOptions::new(opts)?;
+            let mgr = opts.open_profile(profile, pass_key).await?;
+            Ok(into_any_backend(mgr))
+        }
+
+        scheme if custom::is_registered(scheme) => Err(err_msg!(
+            Unsupported,
+            "Profile access keys are not supported by custom stores"
+        )),
+
+        _ => Err(err_msg!(
+            Unsupported,
+            "Unsupported backend: {}",
+            &opts.scheme
+        )),
+    }
+}
+
+async fn provision_any_backend(
+    opts: Options<'_>,
+    method: StoreKeyMethod,
+    pass_key: PassKey<'_>,
+    profile: Option<String>,
+    recreate: bool,
+) -> Result<AnyBackend, Error> {
+    match opts.scheme.as_ref() {
+        #[cfg(feature = "postgres")]
+        "postgres" => {
+            let opts = postgres::PostgresStoreOptions::new(opts)?;
+            let mgr = opts.provision(method, pass_key, profile, recreate).await?;
+            Ok(into_any_backend(mgr))
+        }
+
+        #[cfg(feature = "sqlite")]
+        "sqlite" => {
+            let opts = sqlite::SqliteStoreOptions::new(opts)?;
+            let mgr = opts.provision(method, pass_key, profile, recreate).await?;
+            Ok(into_any_backend(mgr))
+        }
+
+        scheme if custom::is_registered(scheme) => {
+            let opts = custom::CustomStoreOptions::new(opts)?;
+            let mgr = opts.provision(method, pass_key, profile, recreate).await?;
+            Ok(into_any_backend(mgr))
+        }
+
+        _ => Err(err_msg!(
+            Unsupported,
+            "Unsupported backend: {}",
+            &opts.scheme
+        )),
+    }
+}
+
 impl<'a> ManageBackend<'a> for &'a str {
     type Backend = AnyBackend;
 
@@ -260,30 +856,34 @@ impl<'a> ManageBackend<'a> for &'a str {
         profile: Option<String>,
     ) -> BoxFuture<'a, Result<Self::Backend, Error>> {
         Box::pin(async move {
-            let opts = self.into_options()?;
+            let mut opts = self.into_options()?;
             debug!("Open store with options: {:?}", &opts);
+            let retry = OpenRetryPolicy::from_options(&mut opts)?;
+            retry
+                .run(|| {
+                    open_any_backend(
+                        opts.clone(),
+                        method.clone(),
+                        pass_key.clone(),
+                        profile.clone(),
+                    )
+                })
+                .await
+        })
+    }
 
-            match opts.scheme.as_ref() {
-                #[cfg(feature = "postgres")]
-                "postgres" => {
-                    let opts = postgres::PostgresStoreOptions::new(opts)?;
-                    let mgr = opts.open(method, pass_key, profile).await?;
-                    Ok(into_any_backend(mgr))
-                }
-
-                #[cfg(feature = "sqlite")]
-                "sqlite" => {
-                    let opts = sqlite::SqliteStoreOptions::new(opts)?;
-                    let mgr = opts.open(method, pass_key, profile).await?;
-                    Ok(into_any_backend(mgr))
-                }
-
-                _ => Err(err_msg!(
-                    Unsupported,
-                    "Unsupported backend: {}",
-                    &opts.scheme
-                )),
-            }
+    fn open_profile_backend(
+        self,
+        profile: String,
+        pass_key: PassKey<'a>,
+    ) -> BoxFuture<'a, Result<Self::Backend, Error>> {
+        Box::pin(async move {
+            let mut opts = self.into_options()?;
+            debug!("Open store profile with options: {:?}", &opts);
+            let retry = OpenRetryPolicy::from_options(&mut opts)?;
+            retry
+                .run(|| open_any_profile_backend(opts.clone(), profile.clone(), pass_key.clone()))
+                .await
         })
     }
 
@@ -295,30 +895,20 @@ impl<'a> ManageBackend<'a> for &'a str {
         recreate: bool,
     ) -> BoxFuture<'a, Result<Self::Backend, Error>> {
         Box::pin(async move {
-            let opts = self.into_options()?;
+            let mut opts = self.into_options()?;
             debug!("Provision store with options: {:?}", &opts);
-
-            match opts.scheme.as_ref() {
-                #[cfg(feature = "postgres")]
-                "postgres" => {
-                    let opts = postgres::PostgresStoreOptions::new(opts)?;
-                    let mgr = opts.provision(method, pass_key, profile, recreate).await?;
-                    Ok(into_any_backend(mgr))
-                }
-
-                #[cfg(feature = "sqlite")]
-                "sqlite" => {
-                    let opts = sqlite::SqliteStoreOptions::new(opts)?;
-                    let mgr = opts.provision(method, pass_key, profile, recreate).await?;
-                    Ok(into_any_backend(mgr))
-                }
-
-                _ => Err(err_msg!(
-                    Unsupported,
-                    "Unsupported backend: {}",
-                    &opts.scheme
-                )),
-            }
+            let retry = OpenRetryPolicy::from_options(&mut opts)?;
+            retry
+                .run(|| {
+                    provision_any_backend(
+                        opts.clone(),
+                        method.clone(),
+                        pass_key.clone(),
+                        profile.clone(),
+                        recreate,
+                    )
+                })
+                .await
         })
     }
 
@@ -340,6 +930,11 @@ impl<'a> ManageBackend<'a> for &'a str {
                     Ok(opts.remove().await?)
                 }
 
+                scheme if custom::is_registered(scheme) => {
+                    let opts = custom::CustomStoreOptions::new(opts)?;
+                    Ok(opts.remove().await?)
+                }
+
                 _ => Err(err_msg!(
                     Unsupported,
                     "Unsupported backend: {}",
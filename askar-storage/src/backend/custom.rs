@@ -0,0 +1,1936 @@
+//! Storage backed by a record store provided by the application
+//!
+//! A custom backend keeps its profiles and entries in an ordered key-value
+//! [`RecordStore`] registered by the application for a URI scheme, such as a
+//! platform database or keychain accessed through language bindings. Entries
+//! are encrypted in the same manner as the database backends, so the record
+//! store only receives opaque keys and values.
+//!
+//! The host and path of the store URI prefix the keys of all its records, so
+//! that a single record store may hold multiple stores. Entries are filtered
+//! and ordered in memory after decryption. Transactions are serialized by the
+//! backend and buffer their changes until they are committed, at which point
+//! the changes are written to the record store one record at a time.
+//!
+//! Full-text search, subscriptions, chunks, soft deletion, savepoints,
+//! snapshots, integrity checks, profile limits and profile access keys are
+//! not supported.
+
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, HashSet},
+    fmt::{self, Debug, Formatter},
+    sync::{Arc, Mutex, RwLock},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use async_lock::{Mutex as AsyncMutex, MutexGuardArc};
+use futures_lite::stream;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    AuditRecord, Backend, BackendSession, IntegrityReport, IsolationLevel, ProfileDetails,
+    ProfileLimits, QueryPlan, RekeyCallback, RekeyProgress, RepairReport, SavepointOperation,
+};
+use crate::{
+    crypto::buffer::SecretBytes,
+    entry::{
+        EncEntryTag, Entry, EntryCount, EntryEvent, EntryKind, EntryOperation, EntryPrecondition,
+        EntryTag, OrderBy, Scan, Subscription, TagFilter, TagSelection,
+    },
+    error::Error,
+    future::{unblock, unblock_crypto, BoxFuture},
+    options::IntoOptions,
+    protect::{
+        kdf::KdfMethod, EntryEncryptor, KeyCache, PassKey, ProfileId, ProfileKey, StoreKeyMethod,
+        StoreKeyReference,
+    },
+    wql::tags::{is_numeric_value, tag_query, TagName, TagQuery},
+};
+
+/// The schema version of custom stores
+const CUSTOM_STORE_VERSION: &str = "1";
+
+/// The number of entries in each page of a scan
+const PAGE_SIZE: usize = 32;
+
+/// The record type prefix of store configuration values
+const CONFIG_RECORD: u8 = b'c';
+/// The record type prefix of profiles
+const PROFILE_RECORD: u8 = b'p';
+/// The record type prefix of entries
+const ENTRY_RECORD: u8 = b'e';
+
+static RECORD_STORES: Lazy<RwLock<BTreeMap<String, Arc<dyn RecordStore>>>> =
+    Lazy::new(|| RwLock::new(BTreeMap::new()));
+
+/// A record key along with its value
+pub type Record = (Vec<u8>, Vec<u8>);
+
+/// An ordered key-value store holding the records of custom stores
+///
+/// The methods of the record store are called synchronously from the
+/// executor, and should not block for extended periods.
+pub trait RecordStore: Debug + Send + Sync {
+    /// Fetch the value of a record
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Error>;
+
+    /// Insert or replace the value of a record
+    fn put(&self, key: &[u8], value: &[u8]) -> Result<(), Error>;
+
+    /// Remove a record, returning `true` if it was found
+    fn remove(&self, key: &[u8]) -> Result<bool, Error>;
+
+    /// Fetch all records whose keys begin with `prefix`, in any order
+    fn scan(&self, prefix: &[u8]) -> Result<Vec<Record>, Error>;
+}
+
+/// Register a record store for store URIs using the scheme `scheme`,
+/// replacing any record store previously registered for the scheme
+pub fn register_record_store(scheme: &str, store: Arc<dyn RecordStore>) {
+    RECORD_STORES
+        .write()
+        .unwrap()
+        .insert(scheme.to_ascii_lowercase(), store);
+}
+
+/// Remove the record store registered for the URI scheme `scheme`
+pub fn unregister_record_store(scheme: &str) -> bool {
+    RECORD_STORES
+        .write()
+        .unwrap()
+        .remove(&scheme.to_ascii_lowercase())
+        .is_some()
+}
+
+/// Check whether a record store is registered for the URI scheme `scheme`
+pub fn is_registered(scheme: &str) -> bool {
+    RECORD_STORES
+        .read()
+        .unwrap()
+        .contains_key(&scheme.to_ascii_lowercase())
+}
+
+fn record_store(scheme: &str) -> Result<Arc<dyn RecordStore>, Error> {
+    RECORD_STORES
+        .read()
+        .unwrap()
+        .get(&scheme.to_ascii_lowercase())
+        .cloned()
+        .ok_or_else(|| {
+            err_msg!(
+                Unsupported,
+                "No record store registered for scheme: {}",
+                scheme
+            )
+        })
+}
+
+/// Configuration options for a custom store
+#[derive(Debug)]
+pub struct CustomStoreOptions {
+    records: Arc<dyn RecordStore>,
+    path: String,
+}
+
+impl CustomStoreOptions {
+    /// Initialize `CustomStoreOptions` from a generic set of options, using
+    /// the record store registered for the URI scheme
+    pub fn new<'a>(options: impl IntoOptions<'a>) -> Result<Self, Error> {
+        let opts = options.into_options()?;
+        let records = record_store(&opts.scheme)?;
+        let mut path = opts.host.to_string();
+        path.push_str(&opts.path);
+        Ok(Self { records, path })
+    }
+
+    fn into_records(self) -> StoreRecords {
+        let mut prefix = (self.path.len() as u32).to_be_bytes().to_vec();
+        prefix.extend_from_slice(self.path.as_bytes());
+        StoreRecords {
+            records: self.records,
+            prefix,
+            lock: Arc::new(AsyncMutex::new(())),
+        }
+    }
+
+    /// Provision a new custom store, or open the existing store unless
+    /// `recreate` is set
+    pub async fn provision(
+        self,
+        method: StoreKeyMethod,
+        pass_key: PassKey<'_>,
+        profile: Option<String>,
+        recreate: bool,
+    ) -> Result<CustomBackend, Error> {
+        let records = self.into_records();
+        if recreate {
+            records.clear()?;
+        } else if records.get_config("key", &Pending::new())?.is_some() {
+            return open_store(records, Some(method), pass_key, profile).await;
+        }
+
+        if method == StoreKeyMethod::RawKey && pass_key.is_empty() {
+            // disallow random key for a new store
+            return Err(err_msg!(
+                Input,
+                "Cannot create a store with a blank raw key"
+            ));
+        }
+        let default_profile = profile.unwrap_or_else(random_profile_name);
+        let pass_key = pass_key.into_owned();
+        let (store_key, store_key_ref, profile_key, enc_key) = unblock_crypto(move || {
+            let (store_key, store_key_ref) = method.resolve(pass_key)?;
+            let profile_key = ProfileKey::new()?;
+            let enc_key = store_key.wrap_data(profile_key.to_bytes()?)?;
+            Result::<_, Error>::Ok((store_key, store_key_ref, profile_key, enc_key))
+        })
+        .await?;
+
+        let mut pending = Pending::new();
+        records.put_config(&mut pending, "version", CUSTOM_STORE_VERSION);
+        records.put_config(&mut pending, "key", &store_key_ref.into_uri());
+        records.put_config(&mut pending, "default_profile", &default_profile);
+        let profile_id = records.next_sequence(&mut pending, "profile_seq")?;
+        records.put_profile(
+            &mut pending,
+            &default_profile,
+            &ProfileRecord::new(profile_id, enc_key),
+        )?;
+        records.commit(&mut pending)?;
+
+        let mut key_cache = KeyCache::new(store_key);
+        key_cache.add_profile_mut(default_profile.clone(), profile_id, profile_key);
+        Ok(CustomBackend::new(records, default_profile, key_cache))
+    }
+
+    /// Open an existing custom store
+    pub async fn open(
+        self,
+        method: Option<StoreKeyMethod>,
+        pass_key: PassKey<'_>,
+        profile: Option<String>,
+    ) -> Result<CustomBackend, Error> {
+        open_store(self.into_records(), method, pass_key, profile).await
+    }
+
+    /// Remove an existing custom store, returning `true` if any of its
+    /// records were found
+    pub async fn remove(self) -> Result<bool, Error> {
+        self.into_records().clear()
+    }
+}
+
+async fn open_store(
+    records: StoreRecords,
+    method: Option<StoreKeyMethod>,
+    pass_key: PassKey<'_>,
+    profile: Option<String>,
+) -> Result<CustomBackend, Error> {
+    let pending = Pending::new();
+    let store_key_ref = records
+        .get_config("key", &pending)?
+        .ok_or_else(|| err_msg!(NotFound, "Store not found"))?;
+    if records.get_config("version", &pending)?.as_deref() != Some(CUSTOM_STORE_VERSION) {
+        return Err(err_msg!(Unsupported, "Unsupported store version"));
+    }
+    let profile = match profile {
+        Some(profile) => profile,
+        None => records
+            .get_config("default_profile", &pending)?
+            .ok_or_else(|| err_msg!(Unsupported, "Default store profile not found"))?,
+    };
+    let wrap_ref = StoreKeyReference::parse_uri(&store_key_ref)?;
+    if let Some(method) = method {
+        if !wrap_ref.compare_method(&method) {
+            return Err(err_msg!(Input, "Store key method mismatch"));
+        }
+    }
+    let store_key = unblock_crypto({
+        let pass_key = pass_key.into_owned();
+        move || wrap_ref.resolve(pass_key)
+    })
+    .await?;
+
+    let mut key_cache = KeyCache::new(store_key);
+    let found = records
+        .get_profile(&profile, &pending)?
+        .ok_or_else(|| err_msg!(NotFound, "Profile not found"))?;
+    let profile_key = key_cache.load_key(found.key).await?;
+    key_cache.add_profile_mut(profile.clone(), found.id, profile_key);
+    Ok(CustomBackend::new(records, profile, key_cache))
+}
+
+/// Record changes buffered until they are committed to the record store
+type Pending = BTreeMap<Vec<u8>, Option<Vec<u8>>>;
+
+/// Access to the records of a single store within a record store
+struct StoreRecords {
+    records: Arc<dyn RecordStore>,
+    prefix: Vec<u8>,
+    lock: Arc<AsyncMutex<()>>,
+}
+
+impl StoreRecords {
+    fn record_key(&self, record_type: u8, parts: &[&[u8]]) -> Vec<u8> {
+        let mut key = self.prefix.clone();
+        key.push(record_type);
+        for part in parts {
+            key.extend_from_slice(part);
+        }
+        key
+    }
+
+    fn entries_prefix(
+        &self,
+        profile_id: ProfileId,
+        kind: Option<EntryKind>,
+        enc_category: Option<&[u8]>,
+    ) -> Vec<u8> {
+        let mut key = self.record_key(ENTRY_RECORD, &[&profile_id.to_be_bytes()]);
+        if let Some(kind) = kind {
+            key.push(kind as u8);
+            if let Some(enc_category) = enc_category {
+                key.extend_from_slice(&(enc_category.len() as u32).to_be_bytes());
+                key.extend_from_slice(enc_category);
+            }
+        }
+        key
+    }
+
+    fn entry_key(
+        &self,
+        profile_id: ProfileId,
+        kind: EntryKind,
+        enc_category: &[u8],
+        enc_name: &[u8],
+    ) -> Vec<u8> {
+        let mut key = self.entries_prefix(profile_id, Some(kind), Some(enc_category));
+        key.extend_from_slice(enc_name);
+        key
+    }
+
+    /// Split an entry record key into the entry kind and the encrypted
+    /// category and name
+    fn parse_entry_key(&self, key: &[u8]) -> Result<(EntryKind, Vec<u8>, Vec<u8>), Error> {
+        let invalid = || err_msg!(Backend, "Invalid entry record key");
+        let rest = key
+            .get(self.prefix.len() + 9..)
+            .filter(|rest| rest.len() >= 5)
+            .ok_or_else(invalid)?;
+        let kind = EntryKind::try_from(rest[0] as usize)?;
+        let cat_len = u32::from_be_bytes(rest[1..5].try_into().unwrap()) as usize;
+        let rest = &rest[5..];
+        if rest.len() < cat_len {
+            return Err(invalid());
+        }
+        Ok((kind, rest[..cat_len].to_vec(), rest[cat_len..].to_vec()))
+    }
+
+    fn get(&self, key: &[u8], pending: &Pending) -> Result<Option<Vec<u8>>, Error> {
+        match pending.get(key) {
+            Some(value) => Ok(value.clone()),
+            None => self.records.get(key),
+        }
+    }
+
+    fn scan(&self, prefix: &[u8], pending: &Pending) -> Result<Vec<Record>, Error> {
+        let mut found: BTreeMap<_, _> = self
+            .records
+            .scan(prefix)?
+            .into_iter()
+            .filter(|(key, _)| key.starts_with(prefix))
+            .collect();
+        for (key, value) in pending
+            .range(prefix.to_vec()..)
+            .take_while(|(key, _)| key.starts_with(prefix))
+        {
+            match value {
+                Some(value) => found.insert(key.clone(), value.clone()),
+                None => found.remove(key),
+            };
+        }
+        Ok(found.into_iter().collect())
+    }
+
+    /// Write the buffered changes to the record store
+    fn commit(&self, pending: &mut Pending) -> Result<(), Error> {
+        for (key, value) in std::mem::take(pending) {
+            match value {
+                Some(value) => self.records.put(&key, &value)?,
+                None => {
+                    self.records.remove(&key)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Remove all records of the store
+    fn clear(&self) -> Result<bool, Error> {
+        let found = self.records.scan(&self.prefix)?;
+        for (key, _) in &found {
+            self.records.remove(key)?;
+        }
+        Ok(!found.is_empty())
+    }
+
+    fn get_config(&self, name: &str, pending: &Pending) -> Result<Option<String>, Error> {
+        self.get(&self.record_key(CONFIG_RECORD, &[name.as_bytes()]), pending)?
+            .map(|value| {
+                String::from_utf8(value).map_err(err_map!(Backend, "Invalid configuration value"))
+            })
+            .transpose()
+    }
+
+    fn put_config(&self, pending: &mut Pending, name: &str, value: &str) {
+        pending.insert(
+            self.record_key(CONFIG_RECORD, &[name.as_bytes()]),
+            Some(value.as_bytes().to_vec()),
+        );
+    }
+
+    /// Increment and return the value of a sequence stored in the configuration
+    fn next_sequence(&self, pending: &mut Pending, name: &str) -> Result<i64, Error> {
+        let value = match self.get_config(name, pending)? {
+            Some(value) => value
+                .parse::<i64>()
+                .map_err(err_map!(Backend, "Invalid sequence value"))?,
+            None => 0,
+        } + 1;
+        self.put_config(pending, name, &value.to_string());
+        Ok(value)
+    }
+
+    fn get_profile(&self, name: &str, pending: &Pending) -> Result<Option<ProfileRecord>, Error> {
+        self.get(
+            &self.record_key(PROFILE_RECORD, &[name.as_bytes()]),
+            pending,
+        )?
+        .map(|value| decode_record(&value))
+        .transpose()
+    }
+
+    fn put_profile(
+        &self,
+        pending: &mut Pending,
+        name: &str,
+        profile: &ProfileRecord,
+    ) -> Result<(), Error> {
+        pending.insert(
+            self.record_key(PROFILE_RECORD, &[name.as_bytes()]),
+            Some(encode_record(profile)?),
+        );
+        Ok(())
+    }
+
+    fn list_profiles(&self, pending: &Pending) -> Result<Vec<(String, ProfileRecord)>, Error> {
+        let prefix = self.record_key(PROFILE_RECORD, &[]);
+        self.scan(&prefix, pending)?
+            .into_iter()
+            .map(|(key, value)| {
+                let name = String::from_utf8(key[prefix.len()..].to_vec())
+                    .map_err(err_map!(Backend, "Invalid profile name"))?;
+                Ok((name, decode_record(&value)?))
+            })
+            .collect()
+    }
+}
+
+impl Debug for StoreRecords {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StoreRecords")
+            .field("records", &self.records)
+            .field("path", &String::from_utf8_lossy(&self.prefix[4..]))
+            .finish()
+    }
+}
+
+/// The stored representation of a profile
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct ProfileRecord {
+    id: ProfileId,
+    #[serde(with = "record_bytes")]
+    key: Vec<u8>,
+    created: i64,
+    #[serde(default, with = "record_bytes_opt")]
+    metadata: Option<Vec<u8>>,
+}
+
+impl ProfileRecord {
+    fn new(id: ProfileId, key: Vec<u8>) -> Self {
+        Self {
+            id,
+            key,
+            created: now_millis() / 1000,
+            metadata: None,
+        }
+    }
+}
+
+/// The stored representation of an entry
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct EntryRecord {
+    seq: i64,
+    version: i64,
+    #[serde(with = "record_bytes")]
+    value: Vec<u8>,
+    tags: Vec<TagRecord>,
+    expiry: Option<i64>,
+}
+
+impl EntryRecord {
+    fn is_expired(&self, now: i64) -> bool {
+        matches!(self.expiry, Some(expiry) if expiry <= now)
+    }
+}
+
+/// The stored representation of an entry tag
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct TagRecord {
+    #[serde(with = "record_bytes")]
+    name: Vec<u8>,
+    #[serde(with = "record_bytes")]
+    value: Vec<u8>,
+    plaintext: bool,
+}
+
+impl From<EncEntryTag> for TagRecord {
+    fn from(tag: EncEntryTag) -> Self {
+        Self {
+            name: tag.name,
+            value: tag.value,
+            plaintext: tag.plaintext,
+        }
+    }
+}
+
+impl From<TagRecord> for EncEntryTag {
+    fn from(tag: TagRecord) -> Self {
+        Self {
+            name: tag.name,
+            value: tag.value,
+            plaintext: tag.plaintext,
+        }
+    }
+}
+
+/// Serialize byte vectors as CBOR byte strings
+mod record_bytes {
+    use serde::{de, Deserializer, Serializer};
+    use std::fmt;
+
+    pub fn serialize<S: Serializer>(value: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(value)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        deserializer.deserialize_byte_buf(BytesVisitor)
+    }
+
+    pub(super) struct BytesVisitor;
+
+    impl<'de> de::Visitor<'de> for BytesVisitor {
+        type Value = Vec<u8>;
+
+        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("a byte string")
+        }
+
+        fn visit_bytes<E: de::Error>(self, value: &[u8]) -> Result<Vec<u8>, E> {
+            Ok(value.to_vec())
+        }
+
+        fn visit_byte_buf<E: de::Error>(self, value: Vec<u8>) -> Result<Vec<u8>, E> {
+            Ok(value)
+        }
+    }
+}
+
+/// Serialize optional byte vectors as CBOR byte strings
+mod record_bytes_opt {
+    use serde::{de, Deserializer, Serializer};
+    use std::fmt;
+
+    pub fn serialize<S: Serializer>(
+        value: &Option<Vec<u8>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match value {
+            Some(value) => serializer.serialize_bytes(value),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Vec<u8>>, D::Error> {
+        deserializer.deserialize_option(OptionVisitor)
+    }
+
+    struct OptionVisitor;
+
+    impl<'de> de::Visitor<'de> for OptionVisitor {
+        type Value = Option<Vec<u8>>;
+
+        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("an optional byte string")
+        }
+
+        fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+            Ok(None)
+        }
+
+        fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+            Ok(None)
+        }
+
+        fn visit_some<D: Deserializer<'de>>(
+            self,
+            deserializer: D,
+        ) -> Result<Self::Value, D::Error> {
+            super::record_bytes::deserialize(deserializer).map(Some)
+        }
+    }
+}
+
+fn encode_record(value: &impl Serialize) -> Result<Vec<u8>, Error> {
+    serde_cbor::to_vec(value).map_err(err_map!(Unexpected, "Error encoding record"))
+}
+
+fn decode_record<T: for<'de> Deserialize<'de>>(value: &[u8]) -> Result<T, Error> {
+    serde_cbor::from_slice(value).map_err(err_map!(Backend, "Error decoding record"))
+}
+
+fn now_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default()
+}
+
+fn random_profile_name() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+/// Validate the values of numeric tags
+fn check_tags(tags: &[EntryTag]) -> Result<(), Error> {
+    for tag in tags {
+        if let EntryTag::Numeric(name, value) = tag {
+            if !is_numeric_value(value) {
+                return Err(err_msg!(Input, "Invalid value for numeric tag: {}", name));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Evaluate a tag query against the decrypted tags of an entry
+fn tag_query_matches(query: &TagQuery, tags: &[EntryTag]) -> bool {
+    fn tag_values<'t>(
+        name: &'t TagName,
+        tags: &'t [EntryTag],
+    ) -> impl Iterator<Item = &'t str> + 't {
+        tags.iter().filter_map(move |tag| match (name, tag) {
+            (TagName::Encrypted(name), EntryTag::Encrypted(tag_name, value))
+            | (TagName::Plaintext(name), EntryTag::Plaintext(tag_name, value))
+            | (TagName::Numeric(name), EntryTag::Numeric(tag_name, value))
+                if name == tag_name =>
+            {
+                Some(value.as_str())
+            }
+            _ => None,
+        })
+    }
+
+    fn compare(name: &TagName, tags: &[EntryTag], f: impl Fn(&str) -> bool) -> bool {
+        tag_values(name, tags).any(f)
+    }
+
+    fn compare_range(
+        name: &TagName,
+        target: &str,
+        tags: &[EntryTag],
+        f: impl Fn(Ordering) -> bool,
+    ) -> bool {
+        if matches!(name, TagName::Numeric(_)) {
+            let Ok(target) = target.parse::<f64>() else {
+                return false;
+            };
+            compare(name, tags, |value| {
+                value
+                    .parse::<f64>()
+                    .ok()
+                    .and_then(|value| value.partial_cmp(&target))
+                    .map_or(false, &f)
+            })
+        } else {
+            compare(name, tags, |value| f(value.cmp(target)))
+        }
+    }
+
+    fn matches(query: &TagQuery, tags: &[EntryTag], negate: bool) -> bool {
+        let result = match query {
+            TagQuery::And(subqueries) if negate => {
+                return subqueries.iter().any(|q| matches(q, tags, true))
+            }
+            TagQuery::And(subqueries) => subqueries.iter().all(|q| matches(q, tags, false)),
+            TagQuery::Or(subqueries) if negate => {
+                return subqueries.iter().all(|q| matches(q, tags, true))
+            }
+            TagQuery::Or(subqueries) => subqueries.iter().any(|q| matches(q, tags, false)),
+            TagQuery::Not(subquery) => return matches(subquery, tags, !negate),
+            TagQuery::Eq(name, target) => compare_range(name, target, tags, |ord| ord.is_eq()),
+            TagQuery::Neq(name, target) => compare_range(name, target, tags, |ord| ord.is_ne()),
+            TagQuery::Gt(name, target) => compare_range(name, target, tags, |ord| ord.is_gt()),
+            TagQuery::Gte(name, target) => compare_range(name, target, tags, |ord| ord.is_ge()),
+            TagQuery::Lt(name, target) => compare_range(name, target, tags, |ord| ord.is_lt()),
+            TagQuery::Lte(name, target) => compare_range(name, target, tags, |ord| ord.is_le()),
+            TagQuery::Like(name, target) => {
+                compare(name, tags, |value| like_matches(target, value))
+            }
+            TagQuery::Prefix(name, target) => {
+                compare(name, tags, |value| value.starts_with(target.as_str()))
+            }
+            TagQuery::In(name, targets) => {
+                compare(name, tags, |value| targets.iter().any(|t| t == value))
+            }
+            // a negated existence check requires that none of the tags exist
+            TagQuery::Exist(names) if negate => {
+                return names
+                    .iter()
+                    .all(|name| tag_values(name, tags).next().is_none())
+            }
+            TagQuery::Exist(names) => names
+                .iter()
+                .all(|name| tag_values(name, tags).next().is_some()),
+        };
+        result != negate
+    }
+
+    matches(query, tags, false)
+}
+
+/// Match a value against a SQL `LIKE` pattern, ignoring ASCII case
+fn like_matches(pattern: &str, value: &str) -> bool {
+    fn matches(pattern: &[char], value: &[char]) -> bool {
+        match pattern.split_first() {
+            None => value.is_empty(),
+            Some(('%', rest)) => (0..=value.len()).any(|idx| matches(rest, &value[idx..])),
+            Some(('_', rest)) => !value.is_empty() && matches(rest, &value[1..]),
+            Some((c, rest)) => value.split_first().map_or(false, |(v, value)| {
+                v.eq_ignore_ascii_case(c) && matches(rest, value)
+            }),
+        }
+    }
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let value = value.chars().collect::<Vec<_>>();
+    matches(&pattern, &value)
+}
+
+/// A decrypted entry along with its stored record
+struct StoredEntry {
+    key: Vec<u8>,
+    seq: i64,
+    expiry: Option<i64>,
+    entry: Entry,
+}
+
+/// The records of a profile accessed within a session
+struct ProfileScope<'s> {
+    records: &'s StoreRecords,
+    pending: &'s mut Pending,
+    profile_id: ProfileId,
+    key: &'s ProfileKey,
+}
+
+impl ProfileScope<'_> {
+    fn enc_category(&self, category: &str) -> Result<Vec<u8>, Error> {
+        self.key
+            .encrypt_entry_category(ProfileKey::prepare_input(category.as_bytes()))
+    }
+
+    fn entry_key(&self, kind: EntryKind, category: &str, name: &str) -> Result<Vec<u8>, Error> {
+        let enc_category = self.enc_category(category)?;
+        let enc_name = self
+            .key
+            .encrypt_entry_name(ProfileKey::prepare_input(name.as_bytes()))?;
+        Ok(self
+            .records
+            .entry_key(self.profile_id, kind, &enc_category, &enc_name))
+    }
+
+    fn decode_entry(
+        &self,
+        kind: EntryKind,
+        category: String,
+        name: String,
+        record: EntryRecord,
+    ) -> Result<Entry, Error> {
+        let value =
+            self.key
+                .decrypt_entry_value(category.as_bytes(), name.as_bytes(), record.value)?;
+        let tags = self
+            .key
+            .decrypt_entry_tags(record.tags.into_iter().map(Into::into).collect())?;
+        Ok(Entry::new(kind, category, name, value, tags).with_version(record.version))
+    }
+
+    /// Fetch an unexpired entry by kind, category and name
+    fn fetch(
+        &self,
+        kind: EntryKind,
+        category: &str,
+        name: &str,
+    ) -> Result<Option<StoredEntry>, Error> {
+        let key = self.entry_key(kind, category, name)?;
+        let Some(value) = self.records.get(&key, self.pending)? else {
+            return Ok(None);
+        };
+        let record: EntryRecord = decode_record(&value)?;
+        if record.is_expired(now_millis()) {
+            return Ok(None);
+        }
+        let (seq, expiry) = (record.seq, record.expiry);
+        let entry = self.decode_entry(kind, category.to_string(), name.to_string(), record)?;
+        Ok(Some(StoredEntry {
+            key,
+            seq,
+            expiry,
+            entry,
+        }))
+    }
+
+    /// Find the unexpired entries matching a kind, category and tag filter, in
+    /// the order of their creation
+    fn find(
+        &self,
+        kind: Option<EntryKind>,
+        category: Option<&str>,
+        tag_filter: Option<TagFilter>,
+    ) -> Result<Vec<StoredEntry>, Error> {
+        let tag_query = tag_filter
+            .map(|filter| tag_query(filter.query))
+            .transpose()?;
+        let enc_category = category.map(|c| self.enc_category(c)).transpose()?;
+        let prefixes = match kind {
+            Some(kind) => vec![self.records.entries_prefix(
+                self.profile_id,
+                Some(kind),
+                enc_category.as_deref(),
+            )],
+            None if enc_category.is_some() => [EntryKind::Kms, EntryKind::Item]
+                .into_iter()
+                .map(|kind| {
+                    self.records.entries_prefix(
+                        self.profile_id,
+                        Some(kind),
+                        enc_category.as_deref(),
+                    )
+                })
+                .collect(),
+            None => vec![self.records.entries_prefix(self.profile_id, None, None)],
+        };
+        let now = now_millis();
+        let mut found = Vec::new();
+        for prefix in prefixes {
+            for (key, value) in self.records.scan(&prefix, self.pending)? {
+                let record: EntryRecord = decode_record(&value)?;
+                if record.is_expired(now) {
+                    continue;
+                }
+                let (kind, enc_category, enc_name) = self.records.parse_entry_key(&key)?;
+                let category = self.key.decrypt_entry_category(enc_category)?;
+                let name = self.key.decrypt_entry_name(enc_name)?;
+                let (seq, expiry) = (record.seq, record.expiry);
+                let entry = self.decode_entry(kind, category, name, record)?;
+                if let Some(query) = &tag_query {
+                    if !tag_query_matches(query, &entry.tags) {
+                        continue;
+                    }
+                }
+                found.push(StoredEntry {
+                    key,
+                    seq,
+                    expiry,
+                    entry,
+                });
+            }
+        }
+        found.sort_by_key(|stored| stored.seq);
+        Ok(found)
+    }
+
+    /// Encrypt and write an entry record
+    #[allow(clippy::too_many_arguments)]
+    fn write(
+        &mut self,
+        kind: EntryKind,
+        category: &str,
+        name: &str,
+        value: &[u8],
+        tags: &[EntryTag],
+        seq: i64,
+        version: i64,
+        expiry: Option<i64>,
+    ) -> Result<(), Error> {
+        check_tags(tags)?;
+        let key = self.entry_key(kind, category, name)?;
+        let record = EntryRecord {
+            seq,
+            version,
+            value: self.key.encrypt_entry_value(
+                category.as_bytes(),
+                name.as_bytes(),
+                ProfileKey::prepare_input(value),
+            )?,
+            tags: self
+                .key
+                .encrypt_entry_tags(tags.to_vec())?
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+            expiry,
+        };
+        self.pending.insert(key, Some(encode_record(&record)?));
+        Ok(())
+    }
+
+    /// Write an existing entry after modification, retaining its position
+    fn rewrite(&mut self, stored: &StoredEntry, version: i64) -> Result<(), Error> {
+        let entry = &stored.entry;
+        self.write(
+            entry.kind,
+            &entry.category,
+            &entry.name,
+            &entry.value,
+            &entry.tags,
+            stored.seq,
+            version,
+            stored.expiry,
+        )
+    }
+
+    fn insert(
+        &mut self,
+        kind: EntryKind,
+        category: &str,
+        name: &str,
+        value: &[u8],
+        tags: &[EntryTag],
+        expiry_ms: Option<i64>,
+    ) -> Result<(), Error> {
+        if self.fetch(kind, category, name)?.is_some() {
+            return Err(err_msg!(Duplicate, "Duplicate entry"));
+        }
+        let seq = self.records.next_sequence(self.pending, "entry_seq")?;
+        let expiry = expiry_ms.map(|ms| now_millis() + ms);
+        self.write(kind, category, name, value, tags, seq, 1, expiry)
+    }
+
+    fn replace(
+        &mut self,
+        current: StoredEntry,
+        value: &[u8],
+        tags: Option<&[EntryTag]>,
+        expiry_ms: Option<i64>,
+    ) -> Result<(), Error> {
+        let entry = current.entry;
+        let expiry = expiry_ms.map(|ms| now_millis() + ms);
+        self.write(
+            entry.kind,
+            &entry.category,
+            &entry.name,
+            value,
+            tags.unwrap_or_default(),
+            current.seq,
+            entry.version + 1,
+            expiry,
+        )
+    }
+
+    fn remove(&mut self, stored: &StoredEntry) {
+        self.pending.insert(stored.key.clone(), None);
+    }
+}
+
+/// A custom store backed by a registered record store
+#[derive(Debug)]
+pub struct CustomBackend {
+    records: Arc<StoreRecords>,
+    active_profile: Mutex<String>,
+    key_cache: Arc<KeyCache>,
+}
+
+impl CustomBackend {
+    fn new(records: StoreRecords, active_profile: String, key_cache: KeyCache) -> Self {
+        Self {
+            records: Arc::new(records),
+            active_profile: Mutex::new(active_profile),
+            key_cache: Arc::new(key_cache),
+        }
+    }
+
+    fn unsupported<T: Send + 'static>(operation: &str) -> BoxFuture<'static, Result<T, Error>> {
+        let err = err_msg!(
+            Unsupported,
+            "{} is not supported by custom stores",
+            operation
+        );
+        Box::pin(std::future::ready(Err(err)))
+    }
+}
+
+impl Backend for CustomBackend {
+    type Session = CustomSession;
+
+    fn create_profile(&self, name: Option<String>) -> BoxFuture<'_, Result<String, Error>> {
+        let name = name.unwrap_or_else(random_profile_name);
+        Box::pin(async move {
+            let store_key = self.key_cache.store_key()?;
+            let (profile_key, enc_key) = unblock(move || {
+                let profile_key = ProfileKey::new()?;
+                let enc_key = store_key.wrap_data(profile_key.to_bytes()?)?;
+                Result::<_, Error>::Ok((profile_key, enc_key))
+            })
+            .await?;
+            let _lock = self.records.lock.lock().await;
+            let mut pending = Pending::new();
+            if self.records.get_profile(&name, &pending)?.is_some() {
+                return Err(err_msg!(Duplicate, "Duplicate profile name"));
+            }
+            let profile_id = self.records.next_sequence(&mut pending, "profile_seq")?;
+            self.records.put_profile(
+                &mut pending,
+                &name,
+                &ProfileRecord::new(profile_id, enc_key),
+            )?;
+            self.records.commit(&mut pending)?;
+            self.key_cache
+                .add_profile(name.clone(), profile_id, Arc::new(profile_key))
+                .await;
+            Ok(name)
+        })
+    }
+
+    fn get_active_profile(&self) -> String {
+        self.active_profile.lock().unwrap().clone()
+    }
+
+    fn get_default_profile(&self) -> BoxFuture<'_, Result<String, Error>> {
+        Box::pin(async move {
+            Ok(self
+                .records
+                .get_config("default_profile", &Pending::new())?
+                .unwrap_or_default())
+        })
+    }
+
+    fn set_default_profile(&self, profile: String) -> BoxFuture<'_, Result<(), Error>> {
+        Box::pin(async move {
+            self.key_cache.store_key()?;
+            let _lock = self.records.lock.lock().await;
+            let mut pending = Pending::new();
+            self.records
+                .put_config(&mut pending, "default_profile", &profile);
+            self.records.commit(&mut pending)
+        })
+    }
+
+    fn list_profiles(&self) -> BoxFuture<'_, Result<Vec<String>, Error>> {
+        Box::pin(async move {
+            self.key_cache.store_key()?;
+            Ok(self
+                .records
+                .list_profiles(&Pending::new())?
+                .into_iter()
+                .map(|(name, _)| name)
+                .collect())
+        })
+    }
+
+    fn list_profile_details(&self) -> BoxFuture<'_, Result<Vec<ProfileDetails>, Error>> {
+        Box::pin(async move {
+            let store_key = self.key_cache.store_key()?;
+            let pending = Pending::new();
+            let now = now_millis();
+            let mut details = Vec::new();
+            for (name, profile) in self.records.list_profiles(&pending)? {
+                let mut counts = [0i64; 2];
+                for (idx, kind) in [EntryKind::Item, EntryKind::Kms].into_iter().enumerate() {
+                    let prefix = self.records.entries_prefix(profile.id, Some(kind), None);
+                    for (_, value) in self.records.scan(&prefix, &pending)? {
+                        let record: EntryRecord = decode_record(&value)?;
+                        if !record.is_expired(now) {
+                            counts[idx] += 1;
+                        }
+                    }
+                }
+                let metadata = profile
+                    .metadata
+                    .map(|metadata| store_key.unwrap_data(metadata))
+                    .transpose()?
+                    .map(SecretBytes::into_vec);
+                details.push(ProfileDetails {
+                    id: profile.id,
+                    name,
+                    created: Some(profile.created),
+                    item_count: counts[0],
+                    key_count: counts[1],
+                    metadata,
+                    limits: ProfileLimits::default(),
+                });
+            }
+            Ok(details)
+        })
+    }
+
+    fn set_profile_metadata(
+        &self,
+        name: String,
+        metadata: Option<Vec<u8>>,
+    ) -> BoxFuture<'_, Result<(), Error>> {
+        Box::pin(async move {
+            let store_key = self.key_cache.store_key()?;
+            let _lock = self.records.lock.lock().await;
+            let mut pending = Pending::new();
+            let mut profile = self
+                .records
+                .get_profile(&name, &pending)?
+                .ok_or_else(|| err_msg!(NotFound, "Profile not found"))?;
+            profile.metadata = metadata
+                .map(|metadata| store_key.wrap_data(SecretBytes::from(metadata)))
+                .transpose()?;
+            self.records.put_profile(&mut pending, &name, &profile)?;
+            self.records.commit(&mut pending)
+        })
+    }
+
+    fn set_profile_limits(
+        &self,
+        _name: String,
+        _limits: ProfileLimits,
+    ) -> BoxFuture<'_, Result<(), Error>> {
+        Self::unsupported("Profile limits")
+    }
+
+    fn set_profile_access_key(
+        &self,
+        _name: String,
+        _method: StoreKeyMethod,
+        _pass_key: PassKey<'_>,
+    ) -> BoxFuture<'_, Result<(), Error>> {
+        Self::unsupported("Profile access keys")
+    }
+
+    fn remove_profile_access_key(&self, _name: String) -> BoxFuture<'_, Result<(), Error>> {
+        Self::unsupported("Profile access keys")
+    }
+
+    fn rename_profile(&self, name: String, new_name: String) -> BoxFuture<'_, Result<(), Error>> {
+        Box::pin(async move {
+            self.key_cache.store_key()?;
+            let _lock = self.records.lock.lock().await;
+            let mut pending = Pending::new();
+            if self.records.get_profile(&new_name, &pending)?.is_some() {
+                return Err(err_msg!(Duplicate, "Duplicate profile name"));
+            }
+            let profile = self
+                .records
+                .get_profile(&name, &pending)?
+                .ok_or_else(|| err_msg!(NotFound, "Profile not found"))?;
+            pending.insert(
+                self.records.record_key(PROFILE_RECORD, &[name.as_bytes()]),
+                None,
+            );
+            self.records
+                .put_profile(&mut pending, &new_name, &profile)?;
+            if self
+                .records
+                .get_config("default_profile", &pending)?
+                .as_deref()
+                == Some(name.as_str())
+            {
+                self.records
+                    .put_config(&mut pending, "default_profile", &new_name);
+            }
+            self.records.commit(&mut pending)?;
+            self.key_cache.rename_profile(&name, new_name.clone()).await;
+            let mut active = self.active_profile.lock().unwrap();
+            if *active == name {
+                *active = new_name;
+            }
+            Ok(())
+        })
+    }
+
+    fn remove_profile(&self, name: String) -> BoxFuture<'_, Result<bool, Error>> {
+        Box::pin(async move {
+            self.key_cache.store_key()?;
+            let _lock = self.records.lock.lock().await;
+            let mut pending = Pending::new();
+            let Some(profile) = self.records.get_profile(&name, &pending)? else {
+                return Ok(false);
+            };
+            pending.insert(
+                self.records.record_key(PROFILE_RECORD, &[name.as_bytes()]),
+                None,
+            );
+            let prefix = self.records.entries_prefix(profile.id, None, None);
+            for (key, _) in self.records.scan(&prefix, &pending)? {
+                pending.insert(key, None);
+            }
+            self.records.commit(&mut pending)?;
+            Ok(true)
+        })
+    }
+
+    fn scan(
+        &self,
+        profile: Option<String>,
+        kind: Option<EntryKind>,
+        category: Option<String>,
+        tag_filter: Option<TagFilter>,
+        offset: Option<i64>,
+        limit: Option<i64>,
+        order_by: Option<OrderBy>,
+        descending: bool,
+        after: Option<String>,
+        tags: TagSelection,
+    ) -> BoxFuture<'_, Result<Scan<'static, Entry>, Error>> {
+        Box::pin(async move {
+            if after.is_some() {
+                return Err(err_msg!(
+                    Unsupported,
+                    "Scan continuation is not supported by custom stores"
+                ));
+            }
+            let mut session = self.session(profile, false)?;
+            let mut found = session
+                .run(false, move |scope| {
+                    scope.find(kind, category.as_deref(), tag_filter)
+                })
+                .await?;
+            order_entries(&mut found, order_by.unwrap_or_default(), descending);
+            let entries = found
+                .into_iter()
+                .skip(offset.unwrap_or_default().max(0) as usize)
+                .take(limit.map_or(usize::MAX, |limit| limit.max(0) as usize))
+                .map(|stored| {
+                    let mut entry = stored.entry;
+                    tags.retain_tags(&mut entry.tags);
+                    entry
+                })
+                .collect::<Vec<_>>();
+            let pages = entries
+                .chunks(PAGE_SIZE)
+                .map(|page| Ok((page.to_vec(), None)))
+                .collect::<Vec<_>>();
+            Ok(Scan::new(stream::iter(pages), PAGE_SIZE))
+        })
+    }
+
+    fn subscribe(
+        &self,
+        _profile: Option<String>,
+        _kind: Option<EntryKind>,
+        _category: Option<String>,
+    ) -> BoxFuture<'_, Result<Subscription<'static>, Error>> {
+        Self::unsupported("Subscription")
+    }
+
+    fn session(&self, profile: Option<String>, transaction: bool) -> Result<Self::Session, Error> {
+        Ok(CustomSession {
+            records: self.records.clone(),
+            key_cache: self.key_cache.clone(),
+            profile: profile.unwrap_or_else(|| self.get_active_profile()),
+            transaction,
+            lock: None,
+            pending: Pending::new(),
+        })
+    }
+
+    fn transaction(
+        &self,
+        profile: Option<String>,
+        _isolation: IsolationLevel,
+    ) -> Result<Self::Session, Error> {
+        // transactions are always serialized
+        self.session(profile, true)
+    }
+
+    fn rekey_with_progress(
+        &mut self,
+        method: StoreKeyMethod,
+        pass_key: PassKey<'_>,
+        progress: Option<RekeyCallback>,
+    ) -> BoxFuture<'_, Result<(), Error>> {
+        let pass_key = pass_key.into_owned();
+        Box::pin(async move {
+            let prev_key = self.key_cache.store_key()?;
+            let (store_key, store_key_ref) =
+                unblock_crypto(move || method.resolve(pass_key)).await?;
+            let _lock = self.records.lock.lock().await;
+            let mut pending = Pending::new();
+            let profiles = self.records.list_profiles(&pending)?;
+            let total = profiles.len() as i64;
+            for (name, mut profile) in profiles {
+                profile.key = store_key.wrap_data(prev_key.unwrap_data(profile.key)?)?;
+                profile.metadata = profile
+                    .metadata
+                    .map(|metadata| store_key.wrap_data(prev_key.unwrap_data(metadata)?))
+                    .transpose()?;
+                self.records.put_profile(&mut pending, &name, &profile)?;
+            }
+            self.records
+                .put_config(&mut pending, "key", &store_key_ref.into_uri());
+            self.records.commit(&mut pending)?;
+            if let Some(progress) = progress {
+                progress(RekeyProgress {
+                    completed: total,
+                    total,
+                });
+            }
+            self.key_cache = Arc::new(KeyCache::new(store_key));
+            Ok(())
+        })
+    }
+
+    fn upgrade_kdf(
+        &mut self,
+        _minimum: KdfMethod,
+        _pass_key: PassKey<'_>,
+    ) -> BoxFuture<'_, Result<bool, Error>> {
+        Self::unsupported("Key derivation upgrade")
+    }
+
+    fn snapshot<'q>(&'q self, _target: &'q str) -> BoxFuture<'q, Result<(), Error>> {
+        Self::unsupported("Snapshot")
+    }
+
+    fn verify_integrity(&self, _quarantine: bool) -> BoxFuture<'_, Result<IntegrityReport, Error>> {
+        Self::unsupported("Integrity verification")
+    }
+
+    fn repair(&self, _dry_run: bool) -> BoxFuture<'_, Result<RepairReport, Error>> {
+        Self::unsupported("Store repair")
+    }
+
+    fn close(&self) -> BoxFuture<'_, Result<(), Error>> {
+        Box::pin(std::future::ready(Ok(())))
+    }
+}
+
+/// Sort entries for a scan in the same order as the database backends.
+/// Entries without the ordering tag are returned last.
+fn order_entries(entries: &mut [StoredEntry], order_by: OrderBy, descending: bool) {
+    let directed = |ord: Ordering| if descending { ord.reverse() } else { ord };
+    match order_by {
+        OrderBy::Created => entries.sort_by(|a, b| directed(a.seq.cmp(&b.seq))),
+        OrderBy::Name => entries.sort_by(|a, b| {
+            directed(
+                a.entry
+                    .name
+                    .cmp(&b.entry.name)
+                    .then_with(|| a.entry.category.cmp(&b.entry.category)),
+            )
+        }),
+        OrderBy::Tag(tag) => {
+            let tag_value = |stored: &StoredEntry| {
+                stored
+                    .entry
+                    .tags
+                    .iter()
+                    .filter_map(|t| match t {
+                        EntryTag::Plaintext(name, value) if *name == tag => Some(value.clone()),
+                        _ => None,
+                    })
+                    .min()
+            };
+            entries.sort_by(|a, b| match (tag_value(a), tag_value(b)) {
+                (Some(a_val), Some(b_val)) => directed(a_val.cmp(&b_val).then(a.seq.cmp(&b.seq))),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => directed(a.seq.cmp(&b.seq)),
+            });
+        }
+    }
+}
+
+/// A session against a custom store
+#[derive(Debug)]
+pub struct CustomSession {
+    records: Arc<StoreRecords>,
+    key_cache: Arc<KeyCache>,
+    profile: String,
+    transaction: bool,
+    lock: Option<MutexGuardArc<()>>,
+    pending: Pending,
+}
+
+impl CustomSession {
+    /// Resolve the identifier and key of the session profile
+    async fn profile_key(&self) -> Result<(ProfileId, Arc<ProfileKey>), Error> {
+        let profile = self
+            .records
+            .get_profile(&self.profile, &self.pending)?
+            .ok_or_else(|| err_msg!(NotFound, "Profile not found"))?;
+        if let Some((pid, key)) = self.key_cache.get_profile(&self.profile).await {
+            if pid == profile.id {
+                return Ok((pid, key));
+            }
+        }
+        let key = Arc::new(self.key_cache.load_key(profile.key).await?);
+        self.key_cache
+            .add_profile(self.profile.clone(), profile.id, key.clone())
+            .await;
+        Ok((profile.id, key))
+    }
+
+    /// Perform an operation on the records of the session profile. Within a
+    /// transaction, the store is locked on the first operation until the
+    /// transaction is closed. Otherwise, a write operation locks the store
+    /// and its changes are committed once it completes.
+    fn run<'q, T: Send + 'q>(
+        &'q mut self,
+        write: bool,
+        f: impl FnOnce(&mut ProfileScope<'_>) -> Result<T, Error> + Send + 'q,
+    ) -> BoxFuture<'q, Result<T, Error>> {
+        Box::pin(async move {
+            let op_lock = if self.transaction {
+                if self.lock.is_none() {
+                    self.lock.replace(self.records.lock.lock_arc().await);
+                }
+                None
+            } else if write {
+                Some(self.records.lock.lock_arc().await)
+            } else {
+                None
+            };
+            let (profile_id, key) = self.profile_key().await?;
+            let mut result = f(&mut ProfileScope {
+                records: &self.records,
+                pending: &mut self.pending,
+                profile_id,
+                key: &key,
+            });
+            if !self.transaction {
+                if result.is_ok() {
+                    if let Err(err) = self.records.commit(&mut self.pending) {
+                        result = Err(err);
+                    }
+                }
+                self.pending.clear();
+            }
+            drop(op_lock);
+            result
+        })
+    }
+
+    fn unsupported<T: Send + 'static>(operation: &str) -> BoxFuture<'static, Result<T, Error>> {
+        CustomBackend::unsupported(operation)
+    }
+}
+
+impl BackendSession for CustomSession {
+    fn count<'q>(
+        &'q mut self,
+        kind: Option<EntryKind>,
+        category: Option<&'q str>,
+        tag_filter: Option<TagFilter>,
+    ) -> BoxFuture<'q, Result<i64, Error>> {
+        self.run(false, move |scope| {
+            Ok(scope.find(kind, category, tag_filter)?.len() as i64)
+        })
+    }
+
+    fn count_grouped<'q>(
+        &'q mut self,
+        kind: Option<EntryKind>,
+        category: Option<&'q str>,
+        tag_filter: Option<TagFilter>,
+        group_tag: Option<&'q str>,
+    ) -> BoxFuture<'q, Result<Vec<EntryCount>, Error>> {
+        self.run(false, move |scope| {
+            let group_tag = group_tag.map(|tag| match tag.strip_prefix('~') {
+                Some(tag) => TagName::Plaintext(tag.to_string()),
+                None => TagName::Encrypted(tag.to_string()),
+            });
+            let mut counts = BTreeMap::<(String, Option<String>), i64>::new();
+            for stored in scope.find(kind, category, tag_filter)? {
+                let Entry { category, tags, .. } = stored.entry;
+                let tag_value = group_tag.as_ref().and_then(|group_tag| {
+                    tags.into_iter().find_map(|tag| match (group_tag, tag) {
+                        (TagName::Encrypted(group), EntryTag::Encrypted(name, value))
+                        | (TagName::Plaintext(group), EntryTag::Plaintext(name, value))
+                            if *group == name =>
+                        {
+                            Some(value)
+                        }
+                        _ => None,
+                    })
+                });
+                *counts.entry((category, tag_value)).or_default() += 1;
+            }
+            Ok(counts
+                .into_iter()
+                .map(|((category, tag_value), count)| EntryCount {
+                    category,
+                    tag_value,
+                    count,
+                })
+                .collect())
+        })
+    }
+
+    fn explain_scan<'q>(
+        &'q mut self,
+        _kind: Option<EntryKind>,
+        _category: Option<&'q str>,
+        _tag_filter: Option<TagFilter>,
+    ) -> BoxFuture<'q, Result<QueryPlan, Error>> {
+        CustomBackend::unsupported("Query plan inspection")
+    }
+
+    fn fetch<'q>(
+        &'q mut self,
+        kind: EntryKind,
+        category: &'q str,
+        name: &'q str,
+        _for_update: bool,
+    ) -> BoxFuture<'q, Result<Option<Entry>, Error>> {
+        self.run(false, move |scope| {
+            Ok(scope
+                .fetch(kind, category, name)?
+                .map(|stored| stored.entry))
+        })
+    }
+
+    fn fetch_many<'q>(
+        &'q mut self,
+        kind: EntryKind,
+        category: &'q str,
+        names: &'q [&'q str],
+        _for_update: bool,
+    ) -> BoxFuture<'q, Result<Vec<Entry>, Error>> {
+        self.run(false, move |scope| {
+            let mut found = Vec::with_capacity(names.len());
+            for (idx, name) in names.iter().enumerate() {
+                if names[..idx].contains(name) {
+                    continue;
+                }
+                if let Some(stored) = scope.fetch(kind, category, name)? {
+                    found.push(stored.entry);
+                }
+            }
+            Ok(found)
+        })
+    }
+
+    fn fetch_all<'q>(
+        &'q mut self,
+        kind: Option<EntryKind>,
+        category: Option<&'q str>,
+        tag_filter: Option<TagFilter>,
+        limit: Option<i64>,
+        _for_update: bool,
+    ) -> BoxFuture<'q, Result<Vec<Entry>, Error>> {
+        self.run(false, move |scope| {
+            let mut found = scope.find(kind, category, tag_filter)?;
+            found.sort_by_key(|stored| stored.seq);
+            Ok(found
+                .into_iter()
+                .map(|stored| stored.entry)
+                .take(limit.map_or(usize::MAX, |limit| limit.max(0) as usize))
+                .collect())
+        })
+    }
+
+    fn search<'q>(
+        &'q mut self,
+        _kind: Option<EntryKind>,
+        _category: Option<&'q str>,
+        _text: &'q str,
+        _tag_filter: Option<TagFilter>,
+        _limit: Option<i64>,
+    ) -> BoxFuture<'q, Result<Vec<Entry>, Error>> {
+        Self::unsupported("Full-text search")
+    }
+
+    fn remove_all<'q>(
+        &'q mut self,
+        kind: Option<EntryKind>,
+        category: Option<&'q str>,
+        tag_filter: Option<TagFilter>,
+    ) -> BoxFuture<'q, Result<i64, Error>> {
+        self.run(true, move |scope| {
+            let found = scope.find(kind, category, tag_filter)?;
+            for stored in &found {
+                scope.remove(stored);
+            }
+            Ok(found.len() as i64)
+        })
+    }
+
+    fn remove_all_returning<'q>(
+        &'q mut self,
+        kind: Option<EntryKind>,
+        category: Option<&'q str>,
+        tag_filter: Option<TagFilter>,
+    ) -> BoxFuture<'q, Result<Vec<EntryEvent>, Error>> {
+        self.run(true, move |scope| {
+            let found = scope.find(kind, category, tag_filter)?;
+            Ok(found
+                .into_iter()
+                .map(|stored| {
+                    scope.remove(&stored);
+                    EntryEvent {
+                        operation: EntryOperation::Remove,
+                        kind: stored.entry.kind,
+                        category: stored.entry.category,
+                        name: stored.entry.name,
+                    }
+                })
+                .collect())
+        })
+    }
+
+    fn update<'q>(
+        &'q mut self,
+        kind: EntryKind,
+        operation: EntryOperation,
+        category: &'q str,
+        name: &'q str,
+        value: Option<&'q [u8]>,
+        tags: Option<&'q [EntryTag]>,
+        expiry_ms: Option<i64>,
+    ) -> BoxFuture<'q, Result<(), Error>> {
+        self.run(true, move |scope| match operation {
+            EntryOperation::Insert => scope.insert(
+                kind,
+                category,
+                name,
+                value.unwrap_or_default(),
+                tags.unwrap_or_default(),
+                expiry_ms,
+            ),
+            EntryOperation::Replace => {
+                let current = scope
+                    .fetch(kind, category, name)?
+                    .ok_or_else(|| err_msg!(NotFound, "Error updating existing entry"))?;
+                scope.replace(current, value.unwrap_or_default(), tags, expiry_ms)
+            }
+            EntryOperation::Remove => {
+                let current = scope
+                    .fetch(kind, category, name)?
+                    .ok_or_else(|| err_msg!(NotFound, "Entry not found"))?;
+                scope.remove(&current);
+                Ok(())
+            }
+        })
+    }
+
+    fn update_if<'q>(
+        &'q mut self,
+        kind: EntryKind,
+        operation: EntryOperation,
+        category: &'q str,
+        name: &'q str,
+        value: Option<&'q [u8]>,
+        tags: Option<&'q [EntryTag]>,
+        expiry_ms: Option<i64>,
+        precondition: &'q EntryPrecondition,
+    ) -> BoxFuture<'q, Result<(), Error>> {
+        if operation == EntryOperation::Insert {
+            return Box::pin(std::future::ready(Err(err_msg!(
+                Input,
+                "Preconditions are not supported for inserts"
+            ))));
+        }
+        self.run(true, move |scope| {
+            let current = scope
+                .fetch(kind, category, name)?
+                .ok_or_else(|| err_msg!(NotFound, "Entry not found"))?;
+            let entry = &current.entry;
+            if !precondition.check(&entry.value, &entry.tags, entry.version) {
+                return Err(err_msg!(Conflict, "Entry precondition failed"));
+            }
+            if operation == EntryOperation::Remove {
+                scope.remove(&current);
+                Ok(())
+            } else {
+                scope.replace(current, value.unwrap_or_default(), tags, expiry_ms)
+            }
+        })
+    }
+
+    fn insert_many<'q>(
+        &'q mut self,
+        entries: &'q [Entry],
+        expiry_ms: Option<i64>,
+    ) -> BoxFuture<'q, Result<(), Error>> {
+        self.run(true, move |scope| {
+            let mut names = HashSet::new();
+            for entry in entries {
+                if !names.insert((entry.kind as u8, &entry.category, &entry.name))
+                    || scope
+                        .fetch(entry.kind, &entry.category, &entry.name)?
+                        .is_some()
+                {
+                    return Err(err_msg!(Duplicate, "Duplicate entry"));
+                }
+                check_tags(&entry.tags)?;
+            }
+            for entry in entries {
+                scope.insert(
+                    entry.kind,
+                    &entry.category,
+                    &entry.name,
+                    &entry.value,
+                    &entry.tags,
+                    expiry_ms,
+                )?;
+            }
+            Ok(())
+        })
+    }
+
+    fn move_category<'q>(
+        &'q mut self,
+        kind: EntryKind,
+        category: &'q str,
+        new_category: &'q str,
+        rename_tags: &'q [(&'q str, &'q str)],
+    ) -> BoxFuture<'q, Result<i64, Error>> {
+        if category == new_category {
+            return Box::pin(std::future::ready(Err(err_msg!(
+                Input,
+                "Target category must differ from the source"
+            ))));
+        }
+        self.run(true, move |scope| {
+            let found = scope.find(Some(kind), Some(category), None)?;
+            for stored in &found {
+                if scope
+                    .fetch(kind, new_category, &stored.entry.name)?
+                    .is_some()
+                {
+                    return Err(err_msg!(Duplicate, "Duplicate entry in target category"));
+                }
+            }
+            for mut stored in found.iter().map(|stored| StoredEntry {
+                key: stored.key.clone(),
+                seq: stored.seq,
+                expiry: stored.expiry,
+                entry: stored.entry.clone(),
+            }) {
+                scope.remove(&stored);
+                stored.entry.category = new_category.to_string();
+                for tag in stored.entry.tags.iter_mut() {
+                    let (EntryTag::Encrypted(name, _)
+                    | EntryTag::Plaintext(name, _)
+                    | EntryTag::Numeric(name, _)) = tag;
+                    if let Some((_, new_name)) = rename_tags.iter().find(|(n, _)| n == name) {
+                        *name = new_name.to_string();
+                    }
+                }
+                let version = stored.entry.version;
+                scope.rewrite(&stored, version)?;
+            }
+            Ok(found.len() as i64)
+        })
+    }
+
+    fn rename<'q>(
+        &'q mut self,
+        kind: EntryKind,
+        category: &'q str,
+        name: &'q str,
+        new_name: &'q str,
+    ) -> BoxFuture<'q, Result<(), Error>> {
+        self.run(true, move |scope| {
+            let mut stored = scope
+                .fetch(kind, category, name)?
+                .ok_or_else(|| err_msg!(NotFound, "Entry not found"))?;
+            if scope.fetch(kind, category, new_name)?.is_some() {
+                return Err(err_msg!(Duplicate, "Duplicate entry"));
+            }
+            scope.remove(&stored);
+            stored.entry.name = new_name.to_string();
+            let version = stored.entry.version + 1;
+            scope.rewrite(&stored, version)
+        })
+    }
+
+    fn update_tags<'q>(
+        &'q mut self,
+        kind: EntryKind,
+        category: &'q str,
+        name: &'q str,
+        add_tags: &'q [EntryTag],
+        remove_tags: &'q [&'q str],
+    ) -> BoxFuture<'q, Result<(), Error>> {
+        self.run(true, move |scope| {
+            let mut stored = scope
+                .fetch(kind, category, name)?
+                .ok_or_else(|| err_msg!(NotFound, "Entry not found"))?;
+            stored.entry.tags.retain(|tag| {
+                let tag_name = tag.name();
+                !remove_tags.contains(&tag_name)
+                    && !add_tags.iter().any(|added| added.name() == tag_name)
+            });
+            stored.entry.tags.extend_from_slice(add_tags);
+            let version = stored.entry.version + 1;
+            scope.rewrite(&stored, version)
+        })
+    }
+
+    fn write_chunk<'q>(
+        &'q mut self,
+        _kind: EntryKind,
+        _category: &'q str,
+        _name: &'q str,
+        _index: u32,
+        _data: &'q [u8],
+    ) -> BoxFuture<'q, Result<(), Error>> {
+        Self::unsupported("Chunk storage")
+    }
+
+    fn fetch_chunks<'q>(
+        &'q mut self,
+        _kind: EntryKind,
+        _category: &'q str,
+        _name: &'q str,
+        _start: u32,
+        _limit: u32,
+    ) -> BoxFuture<'q, Result<Vec<Vec<u8>>, Error>> {
+        Self::unsupported("Chunk storage")
+    }
+
+    fn append_audit<'q>(
+        &'q mut self,
+        _operation: &'q str,
+        _kind: Option<EntryKind>,
+        _category: Option<&'q str>,
+        _name: Option<&'q str>,
+        _error: Option<&'q str>,
+    ) -> BoxFuture<'q, Result<(), Error>> {
+        // auditing is never enabled for custom stores
+        Box::pin(std::future::ready(Ok(())))
+    }
+
+    fn fetch_audit_log<'q>(
+        &'q mut self,
+        _category: Option<&'q str>,
+        _since: Option<i64>,
+        _limit: Option<i64>,
+    ) -> BoxFuture<'q, Result<Vec<AuditRecord>, Error>> {
+        Box::pin(std::future::ready(Ok(Vec::new())))
+    }
+
+    fn restore<'q>(
+        &'q mut self,
+        _kind: EntryKind,
+        _category: &'q str,
+        _name: &'q str,
+    ) -> BoxFuture<'q, Result<(), Error>> {
+        Self::unsupported("Soft deletion")
+    }
+
+    fn purge<'q>(
+        &'q mut self,
+        _kind: EntryKind,
+        _category: &'q str,
+        _name: &'q str,
+    ) -> BoxFuture<'q, Result<(), Error>> {
+        Self::unsupported("Soft deletion")
+    }
+
+    fn purge_all<'q>(
+        &'q mut self,
+        _kind: Option<EntryKind>,
+        _category: Option<&'q str>,
+    ) -> BoxFuture<'q, Result<i64, Error>> {
+        // entries are always removed immediately
+        Box::pin(std::future::ready(Ok(0)))
+    }
+
+    fn savepoint<'q>(
+        &'q mut self,
+        _name: &'q str,
+        _operation: SavepointOperation,
+    ) -> BoxFuture<'q, Result<(), Error>> {
+        Self::unsupported("Savepoints")
+    }
+
+    fn ping(&mut self) -> BoxFuture<'_, Result<(), Error>> {
+        self.run(false, |_| Ok(()))
+    }
+
+    fn close(&mut self, commit: bool) -> BoxFuture<'_, Result<(), Error>> {
+        Box::pin(async move {
+            let result = if commit {
+                self.records.commit(&mut self.pending)
+            } else {
+                Ok(())
+            };
+            self.pending.clear();
+            self.lock.take();
+            result
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tags() -> Vec<EntryTag> {
+        vec![
+            EntryTag::Encrypted("color".to_string(), "red".to_string()),
+            EntryTag::Plaintext("~ignored".to_string(), "x".to_string()),
+            EntryTag::Numeric("size".to_string(), "10".to_string()),
+            EntryTag::Plaintext("#label".to_string(), "ten".to_string()),
+            EntryTag::Plaintext("title".to_string(), "Hello World".to_string()),
+        ]
+    }
+
+    fn matches(query: serde_json::Value) -> bool {
+        let filter: TagFilter = query.to_string().parse().unwrap();
+        tag_query_matches(&tag_query(filter.query).unwrap(), &tags())
+    }
+
+    #[test]
+    fn tag_query_match() {
+        use serde_json::json;
+        assert!(matches(json!({"color": "red"})));
+        assert!(!matches(json!({"~color": "red"})));
+        assert!(matches(json!({"color": {"$neq": "blue"}})));
+        assert!(!matches(json!({"$not": {"color": "red"}})));
+        assert!(matches(json!({"color": {"$in": ["blue", "red"]}})));
+        assert!(matches(json!({"~size": {"$gt": 9}})));
+        assert!(!matches(json!({"~size": {"$lt": 9.5}})));
+        assert!(matches(json!({"~size": 10.0})));
+        assert!(!matches(json!({"~size": "10"})));
+        assert!(matches(json!({"~#label": "ten"})));
+        assert!(matches(json!({"~title": {"$like": "hello%"}})));
+        assert!(matches(json!({"~title": {"$prefix": "Hello"}})));
+        assert!(matches(json!({"$exist": ["color", "~title"]})));
+        assert!(!matches(json!({"$exist": ["missing"]})));
+        assert!(matches(
+            json!({"$or": [{"color": "blue"}, {"~title": "Hello World"}]})
+        ));
+        assert!(!matches(
+            json!({"$and": [{"color": "red"}, {"color": "blue"}]})
+        ));
+    }
+
+    #[test]
+    fn like_pattern() {
+        assert!(like_matches("a%c", "abbbc"));
+        assert!(like_matches("a_c", "AbC"));
+        assert!(!like_matches("a_c", "ac"));
+        assert!(like_matches("%", ""));
+    }
+}
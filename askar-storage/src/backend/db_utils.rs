@@ -1,34 +1,189 @@
+use std::collections::HashMap;
+use std::fmt::Write;
 use std::future::Future;
 use std::ops::{Deref, DerefMut};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
+use async_lock::{Semaphore, SemaphoreGuardArc};
+use async_stream::try_stream;
+use futures_lite::{
+    pin,
+    stream::{Stream, StreamExt},
+};
+use sha2::{Digest, Sha256};
 use sqlx::{
     database::HasArguments, pool::PoolConnection, Arguments, Database, Encode, Error as SqlxError,
     IntoArguments, Pool, TransactionManager, Type,
 };
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
 
+use super::{
+    AuditRecord, Compression, IntegrityIssue, IsolationLevel, ProfileDetails, ProfileLimits,
+    SavepointOperation,
+};
 use crate::{
-    entry::{EncEntryTag, Entry, EntryKind, EntryTag, TagFilter},
+    crypto::{
+        alg::chacha20::{Chacha20Key, XC20P},
+        buffer::{ArrayKey, ResizeBuffer, SecretBytes},
+        encrypt::{KeyAeadInPlace, KeyAeadMeta},
+        generic_array::typenum::Unsigned,
+        repr::KeySecretBytes,
+        subtle::ConstantTimeEq,
+    },
+    entry::{
+        decode_plaintext_tag, EncEntryTag, Entry, EntryCount, EntryEvent, EntryKind,
+        EntryOperation, EntryPrecondition, EntryTag, OrderBy, TagFilter, TagSelection,
+    },
     error::Error,
-    future::BoxFuture,
-    protect::{EntryEncryptor, KeyCache, PassKey, ProfileId, ProfileKey, StoreKey, StoreKeyMethod},
+    future::{sleep, spawn_ok, timeout, unblock_crypto_chunks, BoxFuture},
+    protect::{
+        kdf::KdfMethod, parse_raw_store_key, EntryEncryptor, KeyCache, PassKey, ProfileId,
+        ProfileKey, StoreKey, StoreKeyMethod, StoreKeyReference,
+    },
     wql::{
-        sql::TagSqlEncoder,
-        tags::{tag_query, TagQueryEncoder},
+        sql::{self, TagSqlEncoder},
+        tags::{is_numeric_value, tag_query, TagQueryEncoder},
     },
 };
 
 /// cbindgen:ignore
 pub const PAGE_SIZE: usize = 32;
 
+/// The number of profiles re-encrypted within each transaction of a rekey
+pub const REKEY_BATCH_SIZE: i64 = 64;
+
+/// The configuration entries recording the checkpoint of a rekey in progress
+pub const REKEY_CONFIG_NAMES: [&str; 4] =
+    ["rekey_ref", "rekey_next", "rekey_prev", "rekey_profile"];
+
+/// The number of entries written by each statement of a bulk insert
+/// cbindgen:ignore
+pub const INSERT_BATCH_SIZE: usize = 256;
+
+/// The number of entries encrypted or decrypted by each concurrent task when
+/// processing a batch of entries
+/// cbindgen:ignore
+pub const CRYPTO_CHUNK_SIZE: usize = 8;
+
+/// The number of tags written by each statement of a bulk insert
+/// cbindgen:ignore
+pub const INSERT_TAG_BATCH_SIZE: usize = 1024;
+
+/// The number of names matched by each statement of a bulk fetch
+/// cbindgen:ignore
+pub const FETCH_BATCH_SIZE: usize = 512;
+
+/// An encrypted row returned by a bulk fetch: the entry name, value, tags,
+/// version, value compression and shared value
+pub type EncFetchRow = (Vec<u8>, Vec<u8>, Option<Vec<u8>>, i64, i16, Option<Vec<u8>>);
+
+/// An encrypted row of the audit log: the identifier, timestamp, operation,
+/// entry kind, category, name and error
+pub type EncAuditRow = (
+    i64,
+    i64,
+    String,
+    Option<i16>,
+    Option<Vec<u8>>,
+    Option<Vec<u8>>,
+    Option<String>,
+);
+
+/// The compression flag of a record value stored without compression
+pub const COMPRESSION_NONE: i16 = 0;
+
+/// The compression flag of a record value compressed using zstd
+#[cfg(feature = "zstd")]
+pub const COMPRESSION_ZSTD: i16 = 1;
+
+/// The minimum length of a record value considered for compression
+#[cfg(feature = "zstd")]
+const COMPRESSION_MIN_LENGTH: usize = 128;
+
+/// The key type used to encrypt shared record values
+type SharedValueKey = Chacha20Key<XC20P>;
+
+/// The domain separator for deriving the key of a shared record value
+const SHARED_KEY_PREFIX: &[u8] = b"askar:shared:key:";
+
+/// The domain separator for deriving the hash of a shared record value
+const SHARED_HASH_PREFIX: &[u8] = b"askar:shared:hash:";
+
 pub type Expiry = chrono::DateTime<chrono::Utc>;
 
 pub(crate) type Connection<DB> = <DB as Database>::Connection;
 
+/// The configuration of the audit log for a store
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct AuditPolicy {
+    /// The time after which audit records are removed
+    pub retention: Option<Duration>,
+}
+
+/// Limits the number of sessions holding a pool connection for each profile,
+/// so that a single profile cannot occupy all connections of the pool
+#[derive(Debug)]
+pub(crate) struct ProfileLimiter {
+    max_sessions: usize,
+    profiles: Mutex<HashMap<String, Arc<Semaphore>>>,
+}
+
+impl ProfileLimiter {
+    pub fn new(max_sessions: usize) -> Self {
+        Self {
+            max_sessions,
+            profiles: Mutex::default(),
+        }
+    }
+
+    /// The maximum number of concurrent sessions for each profile
+    #[inline]
+    pub fn max_sessions(&self) -> usize {
+        self.max_sessions
+    }
+
+    /// Wait for a session slot to become available for a profile, returning
+    /// a `Busy` error if none is released within the timeout
+    pub async fn acquire(&self, profile: &str, wait: Duration) -> Result<SemaphoreGuardArc, Error> {
+        let sem = {
+            let mut profiles = self.profiles.lock().unwrap();
+            if let Some(sem) = profiles.get(profile) {
+                sem.clone()
+            } else {
+                // drop the entries of profiles without any active sessions
+                profiles.retain(|_, sem| Arc::strong_count(sem) > 1);
+                let sem = Arc::new(Semaphore::new(self.max_sessions));
+                profiles.insert(profile.to_string(), sem.clone());
+                sem
+            }
+        };
+        if let Some(guard) = sem.try_acquire_arc() {
+            return Ok(guard);
+        }
+        debug!("Wait for session slot for profile: {}", profile);
+        timeout(wait, sem.acquire_arc()).await.ok_or_else(|| {
+            err_msg!(
+                Busy,
+                "Exceeded the maximum number of concurrent sessions for the profile"
+            )
+        })
+    }
+}
+
 #[derive(Debug)]
 pub(crate) enum DbSessionState<DB: ExtDatabase> {
-    Active { conn: PoolConnection<DB> },
-    Pending { pool: Pool<DB>, transaction: bool },
+    Active {
+        conn: PoolConnection<DB>,
+    },
+    Idle {
+        slot: Arc<Mutex<Option<PoolConnection<DB>>>>,
+    },
+    Pending {
+        pool: Pool<DB>,
+        transaction: bool,
+    },
+    Expired,
     Closed,
 }
 
@@ -37,8 +192,21 @@ unsafe impl<DB: ExtDatabase> Sync for DbSessionState<DB> where DB::Connection: S
 #[derive(Debug)]
 pub struct DbSession<DB: ExtDatabase> {
     profile_key: DbSessionKey,
+    pool: Pool<DB>,
     state: DbSessionState<DB>,
     txn_depth: usize,
+    isolation: Option<IsolationLevel>,
+    soft_delete: bool,
+    compression: Compression,
+    dedup_threshold: Option<usize>,
+    audit: Option<AuditPolicy>,
+    idle_timeout: Option<Duration>,
+    statement_timeout: Option<Duration>,
+    limiter: Option<Arc<ProfileLimiter>>,
+    permit: Option<SemaphoreGuardArc>,
+    notifier: EntryNotifier,
+    pending_events: Vec<EncEntryEvent>,
+    savepoints: Vec<(String, usize)>,
 }
 
 impl<DB: ExtDatabase> DbSession<DB> {
@@ -47,17 +215,73 @@ impl<DB: ExtDatabase> DbSession<DB> {
         cache: Arc<KeyCache>,
         profile: String,
         transaction: bool,
+        soft_delete: bool,
+        idle_timeout: Option<Duration>,
+        notifier: EntryNotifier,
     ) -> Self
     where
         DB: Database,
     {
         Self {
             profile_key: DbSessionKey::Pending { cache, profile },
+            pool: pool.clone(),
             state: DbSessionState::Pending { pool, transaction },
             txn_depth: 0,
+            isolation: None,
+            soft_delete,
+            compression: Compression::None,
+            dedup_threshold: None,
+            audit: None,
+            idle_timeout,
+            statement_timeout: None,
+            limiter: None,
+            permit: None,
+            notifier,
+            pending_events: Vec::new(),
+            savepoints: Vec::new(),
         }
     }
 
+    /// Set the isolation level used when starting the session transaction
+    pub(crate) fn with_isolation(mut self, isolation: IsolationLevel) -> Self {
+        self.isolation = Some(isolation);
+        self
+    }
+
+    /// Set the compression applied to record values written by the session
+    pub(crate) fn with_compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Set the minimum length of record values written by the session to
+    /// the table of shared values
+    pub(crate) fn with_dedup_threshold(mut self, dedup_threshold: Option<usize>) -> Self {
+        self.dedup_threshold = dedup_threshold;
+        self
+    }
+
+    /// Enable the audit log for operations performed by the session
+    pub(crate) fn with_audit(mut self, audit: Option<AuditPolicy>) -> Self {
+        self.audit = audit;
+        self
+    }
+
+    /// Limit the time spent executing statements for each operation of the
+    /// session, for backends which do not enforce a statement timeout on the
+    /// server
+    pub(crate) fn with_statement_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.statement_timeout = timeout;
+        self
+    }
+
+    /// Limit the number of concurrent sessions holding a connection for the
+    /// profile of the session
+    pub(crate) fn with_profile_limiter(mut self, limiter: Option<Arc<ProfileLimiter>>) -> Self {
+        self.limiter = limiter;
+        self
+    }
+
     #[inline]
     fn connection_mut(&mut self) -> Option<&mut PoolConnection<DB>> {
         if let DbSessionState::Active { conn } = &mut self.state {
@@ -81,6 +305,46 @@ impl<DB: ExtDatabase> DbSession<DB> {
         false
     }
 
+    /// Whether removed entries are marked as deleted rather than removed
+    #[inline]
+    pub(crate) fn soft_delete(&self) -> bool {
+        self.soft_delete
+    }
+
+    /// The compression applied to record values written by the session
+    #[inline]
+    pub(crate) fn compression(&self) -> Compression {
+        self.compression
+    }
+
+    /// The minimum length of record values written to the table of shared values
+    #[inline]
+    pub(crate) fn dedup_threshold(&self) -> Option<usize> {
+        self.dedup_threshold
+    }
+
+    /// The audit log configuration, if auditing is enabled
+    #[inline]
+    pub(crate) fn audit(&self) -> Option<AuditPolicy> {
+        self.audit
+    }
+
+    #[inline]
+    pub(crate) fn notifier(&self) -> &EntryNotifier {
+        &self.notifier
+    }
+
+    /// Queue change events for local subscribers, to be delivered once
+    /// any active transaction has been committed
+    pub(crate) fn notify(&mut self, events: impl IntoIterator<Item = EncEntryEvent>) {
+        if self.notifier.has_subscribers() {
+            self.pending_events.extend(events);
+            if !self.in_transaction() {
+                self.notifier.publish(self.pending_events.drain(..));
+            }
+        }
+    }
+
     pub(crate) fn profile_and_key(&mut self) -> Option<(ProfileId, Arc<ProfileKey>)> {
         if let DbSessionKey::Active {
             profile_id,
@@ -100,7 +364,30 @@ impl<DB: ExtDatabase> DbSession<DB> {
     where
         I: for<'a> GetProfileKey<'a, DB>,
     {
+        if let DbSessionState::Idle { slot } = &self.state {
+            let conn = slot.lock().unwrap().take();
+            if let Some(conn) = conn {
+                self.state = DbSessionState::Active { conn };
+            } else if self.txn_depth > 0 {
+                self.state = DbSessionState::Expired;
+            } else {
+                // the connection was released while idle, acquire another
+                self.state = DbSessionState::Pending {
+                    pool: self.pool.clone(),
+                    transaction: false,
+                };
+            }
+        }
+        if let DbSessionState::Expired = &self.state {
+            return Err(self.expire());
+        }
         if let DbSessionState::Pending { pool, transaction } = &self.state {
+            if let (Some(limiter), DbSessionKey::Pending { profile, .. }) =
+                (&self.limiter, &self.profile_key)
+            {
+                let wait = pool.options().get_acquire_timeout();
+                self.permit.replace(limiter.acquire(profile, wait).await?);
+            }
             debug!("Acquire pool connection");
             let mut conn = pool
                 .acquire()
@@ -108,13 +395,18 @@ impl<DB: ExtDatabase> DbSession<DB> {
                 .map_err(err_map!(Backend, "Error acquiring pool connection"))?;
             if *transaction {
                 debug!("Start transaction");
-                DB::start_transaction(&mut conn, false)
+                DB::start_transaction(&mut conn, false, self.isolation)
                     .await
                     .map_err(err_map!(Backend, "Error starting transaction"))?;
                 self.txn_depth += 1;
             }
             self.state = DbSessionState::Active { conn };
         }
+        if let Some(timeout) = self.statement_timeout {
+            DB::start_operation(self.connection_mut().unwrap(), timeout)
+                .await
+                .map_err(err_map!(Backend, "Error setting statement timeout"))?;
+        }
         let profile_id = match &mut self.profile_key {
             DbSessionKey::Pending { cache, profile } => {
                 let cache = cache.clone();
@@ -142,11 +434,72 @@ impl<DB: ExtDatabase> DbSession<DB> {
 
     #[inline]
     pub(crate) fn owned_ref(self) -> DbSessionRef<'static, DB> {
-        DbSessionRef::Owned(self)
+        DbSessionRef::Owned(Box::new(self))
+    }
+
+    /// Return the connection to an idle slot after an operation completes,
+    /// starting a timer which rolls back any transaction and releases the
+    /// connection to the pool if the session is not used again in time.
+    /// Only a session holding a transaction is expired by the timer, others
+    /// acquire a new connection when next used.
+    fn park(&mut self) {
+        let timeout = match self.idle_timeout {
+            Some(timeout) => timeout,
+            None => return,
+        };
+        let conn = match std::mem::replace(&mut self.state, DbSessionState::Closed) {
+            DbSessionState::Active { conn } => conn,
+            state => {
+                self.state = state;
+                return;
+            }
+        };
+        let slot = Arc::new(Mutex::new(Some(conn)));
+        let idle = Arc::downgrade(&slot);
+        let in_txn = self.txn_depth > 0;
+        self.state = DbSessionState::Idle { slot };
+        spawn_ok(async move {
+            sleep(timeout).await;
+            let conn = idle.upgrade().and_then(|slot| slot.lock().unwrap().take());
+            if let Some(mut conn) = conn {
+                if in_txn {
+                    debug!("Roll-back transaction for idle session");
+                    if let Err(err) = DB::TransactionManager::rollback(&mut conn).await {
+                        warn!("Error rolling back idle transaction: {}", err);
+                    }
+                } else {
+                    debug!("Release connection for idle session");
+                }
+            }
+        });
+    }
+
+    /// Mark the session as expired following the idle timeout, discarding
+    /// the uncommitted transaction
+    fn expire(&mut self) -> Error {
+        self.state = DbSessionState::Expired;
+        self.permit = None;
+        self.pending_events.clear();
+        self.savepoints.clear();
+        err_msg!(Timeout, "Session expired after exceeding the idle timeout")
     }
 
     pub(crate) async fn close(&mut self, commit: bool) -> Result<(), Error> {
-        let state = std::mem::replace(&mut self.state, DbSessionState::Closed);
+        let state = match std::mem::replace(&mut self.state, DbSessionState::Closed) {
+            DbSessionState::Idle { slot } => {
+                let conn = slot.lock().unwrap().take();
+                conn.map(|conn| DbSessionState::Active { conn })
+                    .unwrap_or(DbSessionState::Expired)
+            }
+            state => state,
+        };
+        if let DbSessionState::Expired = state {
+            let in_txn = self.txn_depth > 0;
+            let err = self.expire();
+            self.state = DbSessionState::Closed;
+            self.txn_depth = 0;
+            return if commit && in_txn { Err(err) } else { Ok(()) };
+        }
         if self.txn_depth > 0 {
             self.txn_depth = 0;
             if let DbSessionState::Active { mut conn, .. } = state {
@@ -159,16 +512,28 @@ impl<DB: ExtDatabase> DbSession<DB> {
                 }
                 .map_err(err_map!(Backend, "Error closing transaction"))?;
                 conn.return_to_pool().await;
+                if commit {
+                    self.notifier.publish(self.pending_events.drain(..));
+                }
             } else {
                 warn!("Could not close out transaction: session not active");
             }
         }
+        self.permit = None;
+        self.pending_events.clear();
+        self.savepoints.clear();
         Ok(())
     }
 }
 
 impl<DB: ExtDatabase> Drop for DbSession<DB> {
     fn drop(&mut self) {
+        if let DbSessionState::Idle { slot } = &self.state {
+            let conn = slot.lock().unwrap().take();
+            self.state = conn
+                .map(|conn| DbSessionState::Active { conn })
+                .unwrap_or(DbSessionState::Expired);
+        }
         if self.txn_depth > 0 {
             self.txn_depth = 0;
             if let Some(conn) = self.connection_mut() {
@@ -225,13 +590,23 @@ pub trait ExtDatabase: Database {
     fn start_transaction(
         conn: &mut Connection<Self>,
         _nested: bool,
+        _isolation: Option<IsolationLevel>,
     ) -> BoxFuture<'_, Result<(), SqlxError>> {
         <Self as Database>::TransactionManager::begin(conn)
     }
+
+    /// Start the time limit for the statements executed by an operation,
+    /// when the timeout is not enforced by the database server
+    fn start_operation(
+        _conn: &mut Connection<Self>,
+        _timeout: Duration,
+    ) -> BoxFuture<'_, Result<(), SqlxError>> {
+        Box::pin(std::future::ready(Ok(())))
+    }
 }
 
 pub enum DbSessionRef<'q, DB: ExtDatabase> {
-    Owned(DbSession<DB>),
+    Owned(Box<DbSession<DB>>),
     Borrowed(&'q mut DbSession<DB>),
 }
 
@@ -266,6 +641,12 @@ pub(crate) struct DbSessionActive<'a, DB: ExtDatabase> {
     pub(crate) profile_id: ProfileId,
 }
 
+impl<DB: ExtDatabase> Drop for DbSessionActive<'_, DB> {
+    fn drop(&mut self) {
+        self.inner.park();
+    }
+}
+
 impl<'q, DB: ExtDatabase> DbSessionActive<'q, DB> {
     #[inline]
     pub fn connection_mut(&mut self) -> &mut Connection<DB> {
@@ -280,13 +661,76 @@ impl<'q, DB: ExtDatabase> DbSessionActive<'q, DB> {
         self.inner.in_transaction()
     }
 
+    #[allow(unused)]
+    #[inline]
+    pub fn notify(&mut self, events: impl IntoIterator<Item = EncEntryEvent>) {
+        self.inner.notify(events)
+    }
+
+    /// Validate a savepoint operation and produce the SQL statement to perform it
+    pub fn savepoint_statement(
+        &self,
+        name: &str,
+        operation: SavepointOperation,
+    ) -> Result<String, Error> {
+        if !self.inner.in_transaction() {
+            return Err(err_msg!(
+                Input,
+                "Savepoints may only be used within a transaction"
+            ));
+        }
+        let mut chars = name.chars();
+        if !chars
+            .next()
+            .map(|c| c.is_ascii_alphabetic() || c == '_')
+            .unwrap_or(false)
+            || !chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+            || name.len() > 63
+            || name.starts_with("_sqlx")
+        {
+            return Err(err_msg!(Input, "Invalid savepoint name"));
+        }
+        if operation != SavepointOperation::Create
+            && !self.inner.savepoints.iter().any(|(sp, _)| sp == name)
+        {
+            return Err(err_msg!(NotFound, "Unknown savepoint"));
+        }
+        Ok(match operation {
+            SavepointOperation::Create => format!("SAVEPOINT {}", name),
+            SavepointOperation::Release => format!("RELEASE SAVEPOINT {}", name),
+            SavepointOperation::Rollback => format!("ROLLBACK TO SAVEPOINT {}", name),
+        })
+    }
+
+    /// Update the session state after a savepoint operation has been performed
+    pub fn savepoint_applied(&mut self, name: &str, operation: SavepointOperation) {
+        let inner = &mut *self.inner;
+        let found = inner.savepoints.iter().rposition(|(sp, _)| sp == name);
+        match (operation, found) {
+            (SavepointOperation::Create, _) => {
+                inner
+                    .savepoints
+                    .push((name.to_string(), inner.pending_events.len()));
+            }
+            (SavepointOperation::Release, Some(idx)) => {
+                inner.savepoints.truncate(idx);
+            }
+            (SavepointOperation::Rollback, Some(idx)) => {
+                // events queued after the savepoint will never be committed
+                inner.pending_events.truncate(inner.savepoints[idx].1);
+                inner.savepoints.truncate(idx + 1);
+            }
+            _ => (),
+        }
+    }
+
     #[allow(unused)]
     pub async fn begin<'t>(&'t mut self) -> Result<DbSessionTxn<'t, DB>, Error>
     where
         'q: 't,
     {
         debug!("Start nested transaction");
-        DB::start_transaction(self.connection_mut(), true)
+        DB::start_transaction(self.connection_mut(), true, None)
             .await
             .map_err(err_map!(Backend, "Error starting nested transaction"))?;
         self.inner.txn_depth += 1;
@@ -303,7 +747,7 @@ impl<'q, DB: ExtDatabase> DbSessionActive<'q, DB> {
     {
         if self.inner.txn_depth == 0 {
             debug!("Start transaction");
-            DB::start_transaction(self.connection_mut(), false)
+            DB::start_transaction(self.connection_mut(), false, None)
                 .await
                 .map_err(err_map!(Backend, "Error starting transaction"))?;
             self.inner.txn_depth += 1;
@@ -373,12 +817,519 @@ where
     }
 }
 
+/// An entry change event with encrypted category and name
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct EncEntryEvent {
+    pub profile_id: ProfileId,
+    pub operation: EntryOperation,
+    pub kind: EntryKind,
+    pub category: Vec<u8>,
+    pub name: Vec<u8>,
+}
+
+impl EncEntryEvent {
+    pub fn matches(
+        &self,
+        profile_id: ProfileId,
+        kind: Option<EntryKind>,
+        enc_category: Option<&[u8]>,
+    ) -> bool {
+        self.profile_id == profile_id
+            && kind.map(|k| k == self.kind).unwrap_or(true)
+            && enc_category
+                .map(|c| c == self.category.as_slice())
+                .unwrap_or(true)
+    }
+
+    /// Encode the event as a notification payload
+    pub fn to_payload(&self) -> String {
+        format!(
+            "{}:{}:{}:{}:{}",
+            self.profile_id,
+            match self.operation {
+                EntryOperation::Insert => 0,
+                EntryOperation::Replace => 1,
+                EntryOperation::Remove => 2,
+            },
+            self.kind as usize,
+            hex::encode(&self.category),
+            hex::encode(&self.name)
+        )
+    }
+
+    /// Decode an event from a notification payload
+    pub fn from_payload(payload: &str) -> Result<Self, Error> {
+        let mut parts = payload.splitn(5, ':');
+        let mut next_part = || {
+            parts
+                .next()
+                .ok_or_else(|| err_msg!(Unexpected, "Invalid entry event payload"))
+        };
+        let profile_id = next_part()?
+            .parse()
+            .map_err(err_map!(Unexpected, "Invalid entry event profile"))?;
+        let operation = match next_part()? {
+            "0" => EntryOperation::Insert,
+            "1" => EntryOperation::Replace,
+            "2" => EntryOperation::Remove,
+            _ => return Err(err_msg!(Unexpected, "Invalid entry event operation")),
+        };
+        let kind = EntryKind::try_from(
+            next_part()?
+                .parse::<usize>()
+                .map_err(err_map!(Unexpected, "Invalid entry event kind"))?,
+        )?;
+        let category = hex::decode(next_part()?)
+            .map_err(err_map!(Unexpected, "Invalid entry event category"))?;
+        let name =
+            hex::decode(next_part()?).map_err(err_map!(Unexpected, "Invalid entry event name"))?;
+        Ok(Self {
+            profile_id,
+            operation,
+            kind,
+            category,
+            name,
+        })
+    }
+}
+
+pub(crate) fn decrypt_entry_event(
+    event: EncEntryEvent,
+    key: &ProfileKey,
+) -> Result<EntryEvent, Error> {
+    Ok(EntryEvent {
+        operation: event.operation,
+        kind: event.kind,
+        category: key.decrypt_entry_category(event.category)?,
+        name: key.decrypt_entry_name(event.name)?,
+    })
+}
+
+/// Distributes entry change events to subscribers
+#[derive(Clone, Debug)]
+pub(crate) struct EntryNotifier {
+    channel: Arc<str>,
+    subscribers: Arc<Mutex<Vec<UnboundedSender<EncEntryEvent>>>>,
+}
+
+impl EntryNotifier {
+    /// Create a new notifier, using the given channel name for
+    /// database notifications where supported
+    pub fn new(channel: impl Into<Arc<str>>) -> Self {
+        Self {
+            channel: channel.into(),
+            subscribers: Arc::default(),
+        }
+    }
+
+    #[inline]
+    pub fn channel(&self) -> &str {
+        &self.channel
+    }
+
+    pub fn has_subscribers(&self) -> bool {
+        !self.subscribers.lock().unwrap().is_empty()
+    }
+
+    pub fn subscribe(&self) -> UnboundedReceiver<EncEntryEvent> {
+        let (sender, receiver) = unbounded_channel();
+        self.subscribers.lock().unwrap().push(sender);
+        receiver
+    }
+
+    pub fn publish(&self, events: impl IntoIterator<Item = EncEntryEvent>) {
+        let mut subscribers = self.subscribers.lock().unwrap();
+        for event in events {
+            subscribers.retain(|sub| sub.send(event.clone()).is_ok());
+        }
+    }
+
+    /// Drop all subscribers, ending their event streams
+    pub fn close(&self) {
+        self.subscribers.lock().unwrap().clear();
+    }
+}
+
 pub struct EncScanEntry {
+    pub id: i64,
     pub kind: EntryKind,
     pub category: Vec<u8>,
     pub name: Vec<u8>,
     pub value: Vec<u8>,
     pub tags: Vec<u8>,
+    pub version: i64,
+    pub compression: i16,
+    pub shared: Option<Vec<u8>>,
+}
+
+/// An entry encrypted for insertion into the store
+pub struct EncInsertEntry {
+    pub kind: EntryKind,
+    pub category: Vec<u8>,
+    pub name: Vec<u8>,
+    pub value: EncValue,
+    pub tags: Vec<EncEntryTag>,
+}
+
+/// A record value encrypted for storage
+pub struct EncValue {
+    /// The value stored with the row. For shared values, this holds the
+    /// encrypted content key of the shared value.
+    pub value: Vec<u8>,
+    /// The compression flag stored with the row
+    pub compression: i16,
+    /// The value to be stored in the table of shared values
+    pub shared: Option<EncSharedValue>,
+}
+
+/// A record value stored once in the table of shared values, identified by
+/// a hash of its content
+pub struct EncSharedValue {
+    pub hash: Vec<u8>,
+    pub value: Vec<u8>,
+}
+
+/// Compress and encrypt a record value, returning the encrypted value along
+/// with the compression flag to be stored with the row.
+///
+/// Values of at least `dedup_threshold` bytes after compression are
+/// encrypted with a key derived from their content, so that identical values
+/// written by any profile are stored only once. The row holds the content key
+/// encrypted with the profile key. As with any content-addressed storage, the
+/// presence of a known value can be confirmed by anyone with access to the
+/// database, so this mode is intended for public data such as revocation
+/// tails files and ledger objects.
+pub fn encrypt_value(
+    key: &ProfileKey,
+    compression: Compression,
+    dedup_threshold: Option<usize>,
+    category: &[u8],
+    name: &[u8],
+    value: SecretBytes,
+) -> Result<EncValue, Error> {
+    encrypt_value_with(compression, dedup_threshold, value, |value| {
+        key.encrypt_entry_value(category, name, value)
+    })
+}
+
+/// Compress a record value and prepare it for storage, using `encrypt` to
+/// encrypt either the value or the content key of a shared value
+fn encrypt_value_with(
+    compression: Compression,
+    dedup_threshold: Option<usize>,
+    value: SecretBytes,
+    encrypt: impl FnOnce(SecretBytes) -> Result<Vec<u8>, Error>,
+) -> Result<EncValue, Error> {
+    let (value, flag) = compress_value(compression, value)?;
+    if matches!(dedup_threshold, Some(min_length) if value.len() >= min_length) {
+        let (content_key, shared) = encrypt_shared_value(value, flag)?;
+        Ok(EncValue {
+            value: encrypt(content_key)?,
+            compression: flag,
+            shared: Some(shared),
+        })
+    } else {
+        Ok(EncValue {
+            value: encrypt(value)?,
+            compression: flag,
+            shared: None,
+        })
+    }
+}
+
+/// Decrypt a record value, reversing the compression indicated by its flag
+pub fn decrypt_value(
+    key: &ProfileKey,
+    category: &[u8],
+    name: &[u8],
+    enc_value: Vec<u8>,
+    flag: i16,
+    shared: Option<Vec<u8>>,
+) -> Result<SecretBytes, Error> {
+    let value = key.decrypt_entry_value(category, name, enc_value)?;
+    let value = match shared {
+        Some(enc_shared) => decrypt_shared_value(value, enc_shared)?,
+        None => value,
+    };
+    decompress_value(value, flag)
+}
+
+/// Decrypt a record value borrowed from a result row, reversing the
+/// compression indicated by its flag
+///
+/// The ciphertext is copied once, directly into the buffer which receives
+/// the plaintext, rather than being copied out of the row before decryption.
+pub fn decrypt_row_value(
+    key: &ProfileKey,
+    category: &[u8],
+    name: &[u8],
+    enc_value: &[u8],
+    flag: i16,
+    shared: Option<&[u8]>,
+) -> Result<SecretBytes, Error> {
+    let value = key.decrypt_entry_value_slice(category, name, enc_value)?;
+    let value = match shared {
+        Some(enc_shared) => decrypt_shared_value_slice(value, enc_shared)?,
+        None => value,
+    };
+    decompress_value(value, flag)
+}
+
+fn encrypt_shared_value(
+    value: SecretBytes,
+    flag: i16,
+) -> Result<(SecretBytes, EncSharedValue), Error> {
+    let content_key = SecretBytes::from_slice(
+        Sha256::new()
+            .chain_update(SHARED_KEY_PREFIX)
+            .chain_update(flag.to_be_bytes())
+            .chain_update(&value)
+            .finalize()
+            .as_slice(),
+    );
+    let hash = Sha256::new()
+        .chain_update(SHARED_HASH_PREFIX)
+        .chain_update(&content_key)
+        .finalize()
+        .to_vec();
+    let enc_key = SharedValueKey::from_secret_bytes(content_key.as_ref())?;
+    let nonce = ArrayKey::<<SharedValueKey as KeyAeadMeta>::NonceSize>::random();
+    let mut buffer = value;
+    enc_key.encrypt_in_place(&mut buffer, nonce.as_ref(), &[])?;
+    buffer.buffer_insert(0, nonce.as_ref())?;
+    Ok((
+        content_key,
+        EncSharedValue {
+            hash,
+            value: buffer.into_vec(),
+        },
+    ))
+}
+
+fn decrypt_shared_value(
+    content_key: SecretBytes,
+    enc_shared: Vec<u8>,
+) -> Result<SecretBytes, Error> {
+    let nonce_len = <SharedValueKey as KeyAeadMeta>::NonceSize::USIZE;
+    if enc_shared.len() < nonce_len {
+        return Err(err_msg!(Encryption, "Invalid shared entry value"));
+    }
+    let enc_key = SharedValueKey::from_secret_bytes(content_key.as_ref())?;
+    let mut buffer = SecretBytes::from(enc_shared);
+    let nonce = ArrayKey::<<SharedValueKey as KeyAeadMeta>::NonceSize>::from_slice(
+        &buffer.as_ref()[..nonce_len],
+    );
+    buffer.buffer_remove(0..nonce_len)?;
+    enc_key.decrypt_in_place(&mut buffer, nonce.as_ref(), &[])?;
+    Ok(buffer)
+}
+
+fn decrypt_shared_value_slice(
+    content_key: SecretBytes,
+    enc_shared: &[u8],
+) -> Result<SecretBytes, Error> {
+    let nonce_len = <SharedValueKey as KeyAeadMeta>::NonceSize::USIZE;
+    if enc_shared.len() < nonce_len {
+        return Err(err_msg!(Encryption, "Invalid shared entry value"));
+    }
+    let enc_key = SharedValueKey::from_secret_bytes(content_key.as_ref())?;
+    let (nonce, ciphertext) = enc_shared.split_at(nonce_len);
+    let mut buffer = SecretBytes::from_slice(ciphertext);
+    enc_key.decrypt_in_place(&mut buffer, nonce, &[])?;
+    Ok(buffer)
+}
+
+fn compress_value(
+    compression: Compression,
+    value: SecretBytes,
+) -> Result<(SecretBytes, i16), Error> {
+    match compression {
+        #[cfg(feature = "zstd")]
+        Compression::Zstd if value.len() >= COMPRESSION_MIN_LENGTH => {
+            let compressed = zstd::bulk::compress(value.as_ref(), zstd::DEFAULT_COMPRESSION_LEVEL)
+                .map_err(err_map!(Unexpected, "Error compressing entry value"))?;
+            if compressed.len() < value.len() {
+                return Ok((SecretBytes::from(compressed), COMPRESSION_ZSTD));
+            }
+        }
+        _ => (),
+    }
+    Ok((value, COMPRESSION_NONE))
+}
+
+fn decompress_value(value: SecretBytes, flag: i16) -> Result<SecretBytes, Error> {
+    match flag {
+        COMPRESSION_NONE => Ok(value),
+        #[cfg(feature = "zstd")]
+        COMPRESSION_ZSTD => {
+            // allocate the output once when the frame records its content size
+            let result = match zstd::zstd_safe::get_frame_content_size(value.as_ref()) {
+                Ok(Some(size)) => zstd::bulk::decompress(value.as_ref(), size as usize),
+                _ => zstd::stream::decode_all(value.as_ref()),
+            };
+            result
+                .map(SecretBytes::from)
+                .map_err(err_map!(Unexpected, "Error decompressing entry value"))
+        }
+        _ => Err(err_msg!(
+            Unsupported,
+            "Unsupported entry value compression: {}",
+            flag
+        )),
+    }
+}
+
+/// Encrypt a batch of entries for insertion into the store
+pub fn encrypt_entries(
+    entries: &[Entry],
+    compression: Compression,
+    dedup_threshold: Option<usize>,
+    key: &ProfileKey,
+) -> Result<Vec<EncInsertEntry>, Error> {
+    let mut batch = key.batch_encryptor()?;
+    entries
+        .iter()
+        .map(|entry| {
+            let category = ProfileKey::prepare_input(entry.category.as_bytes());
+            let name = ProfileKey::prepare_input(entry.name.as_bytes());
+            let value = ProfileKey::prepare_input(entry.value.as_ref());
+            let tags = prepare_tags(&entry.tags)?;
+            let value = encrypt_value_with(compression, dedup_threshold, value, |value| {
+                batch.encrypt_entry_value(category.as_ref(), name.as_ref(), value)
+            })?;
+            Ok(EncInsertEntry {
+                kind: entry.kind,
+                category: batch.encrypt_entry_category(category)?,
+                name: batch.encrypt_entry_name(name)?,
+                value,
+                tags: batch.encrypt_entry_tags(tags)?,
+            })
+        })
+        .collect()
+}
+
+/// Encrypt a batch of entries for insertion into the store, dividing the
+/// entries between concurrent tasks
+pub async fn encrypt_entries_chunked(
+    entries: Vec<Entry>,
+    compression: Compression,
+    dedup_threshold: Option<usize>,
+    key: Arc<ProfileKey>,
+) -> Result<Vec<EncInsertEntry>, Error> {
+    unblock_crypto_chunks(entries, CRYPTO_CHUNK_SIZE, move |entries| {
+        encrypt_entries(&entries, compression, dedup_threshold, &key)
+    })
+    .await
+}
+
+/// Encrypt the category and names for a bulk fetch
+pub fn encrypt_fetch_names(
+    category: &str,
+    names: &[String],
+    key: &ProfileKey,
+) -> Result<(Vec<u8>, Vec<Vec<u8>>), Error> {
+    let enc_category =
+        key.encrypt_entry_category(ProfileKey::prepare_input(category.as_bytes()))?;
+    let enc_names = names
+        .iter()
+        .map(|name| key.encrypt_entry_name(ProfileKey::prepare_input(name.as_bytes())))
+        .collect::<Result<_, _>>()?;
+    Ok((enc_category, enc_names))
+}
+
+/// Decrypt the results of a bulk fetch, returning entries in the order of
+/// the requested names.
+pub fn decrypt_fetch_rows(
+    kind: EntryKind,
+    category: &str,
+    names: Vec<String>,
+    enc_names: Vec<Vec<u8>>,
+    rows: Vec<EncFetchRow>,
+    key: &ProfileKey,
+) -> Result<Vec<Entry>, Error> {
+    let mut found = rows
+        .into_iter()
+        .map(|(name, value, tags, version, compression, shared)| {
+            (name, (value, tags, version, compression, shared))
+        })
+        .collect::<HashMap<_, _>>();
+    let mut entries = Vec::with_capacity(found.len());
+    for (name, enc_name) in names.into_iter().zip(enc_names) {
+        if let Some((value, tags, version, compression, shared)) = found.remove(&enc_name) {
+            let value = decrypt_value(
+                key,
+                category.as_bytes(),
+                name.as_bytes(),
+                value,
+                compression,
+                shared,
+            )?;
+            let tags = if let Some(enc_tags) = tags {
+                decrypt_tags(key, &enc_tags)?
+            } else {
+                Vec::new()
+            };
+            entries.push(Entry::new(kind, category, name, value, tags).with_version(version));
+        }
+    }
+    Ok(entries)
+}
+
+/// Decrypt the current value and tags of an entry and check them against
+/// a precondition, producing a `Conflict` error if it does not hold
+#[allow(clippy::too_many_arguments)]
+pub fn check_entry_precondition(
+    precondition: &EntryPrecondition,
+    category: &[u8],
+    name: &[u8],
+    enc_value: Vec<u8>,
+    enc_tags: Option<Vec<u8>>,
+    version: i64,
+    compression: i16,
+    shared: Option<Vec<u8>>,
+    key: &ProfileKey,
+) -> Result<(), Error> {
+    let value = decrypt_value(key, category, name, enc_value, compression, shared)?;
+    let tags = if let Some(enc_tags) = enc_tags {
+        decrypt_tags(key, &enc_tags)?
+    } else {
+        Vec::new()
+    };
+    if precondition.check(value.as_ref(), &tags, version) {
+        Ok(())
+    } else {
+        Err(err_msg!(Conflict, "Entry precondition failed"))
+    }
+}
+
+/// Build the list of row placeholders for a multi-row statement, where each
+/// row starts with an optional fixed expression followed by `columns` parameters
+pub fn multi_row_values<Q: QueryPrepare>(
+    prefix: Option<&str>,
+    rows: usize,
+    columns: usize,
+    start_index: i64,
+) -> String {
+    let mut index = start_index;
+    let mut values = String::with_capacity(rows * (columns + 1) * 5);
+    for row in 0..rows {
+        if row > 0 {
+            values.push_str(", ");
+        }
+        values.push('(');
+        if let Some(prefix) = prefix {
+            values.push_str(prefix);
+        }
+        for col in 0..columns {
+            if col > 0 || prefix.is_some() {
+                values.push_str(", ");
+            }
+            Q::push_placeholder(&mut values, index);
+            index += 1;
+        }
+        values.push(')');
+    }
+    values
 }
 
 pub struct QueryParams<'q, DB: Database> {
@@ -431,8 +1382,22 @@ where
 pub trait QueryPrepare {
     type DB: Database;
 
-    fn placeholder(index: i64) -> String {
-        format!("?{}", index)
+    /// Append a numbered argument placeholder to a query
+    fn push_placeholder(query: &mut String, index: i64) {
+        write!(query, "?{}", index).unwrap();
+    }
+
+    fn numeric_value(expr: &str) -> String {
+        sql::numeric_value(expr)
+    }
+
+    /// Encode full-text search terms, each matching a word prefix
+    fn search_query(terms: &[&str]) -> String {
+        terms
+            .iter()
+            .map(|term| format!("\"{}\"*", term))
+            .collect::<Vec<_>>()
+            .join(" ")
     }
 
     fn limit_query<'q>(
@@ -483,7 +1448,7 @@ pub fn replace_arg_placeholders<Q: QueryPrepare + ?Sized>(
             _ => None,
         }) {
             buffer.push_str(&remain[..start_offs]);
-            buffer.push_str(&Q::placeholder(sub_index));
+            Q::push_placeholder(&mut buffer, sub_index);
             remain = &remain[end_offs..];
             index += 1;
         } else {
@@ -495,76 +1460,199 @@ pub fn replace_arg_placeholders<Q: QueryPrepare + ?Sized>(
     buffer
 }
 
-pub(crate) fn decode_tags(tags: Vec<u8>) -> Result<Vec<EncEntryTag>, ()> {
-    let mut idx = 0;
-    let mut plaintext;
-    let mut name_start;
-    let mut name_end;
-    let mut enc_tags = vec![];
-    let end = tags.len();
-    loop {
-        if idx >= end {
-            break;
-        }
-        plaintext = tags[idx] == b'1';
-        // assert ':' at idx + 1
-        idx += 2;
-        name_start = idx;
-        name_end = 0;
-        loop {
-            if idx >= end || tags[idx] == b',' {
-                if name_end == 0 {
-                    return Err(());
-                }
-                let name = hex::decode(&tags[(name_start)..(name_end)]).map_err(|_| ())?;
-                let value = hex::decode(&tags[(name_end + 1)..(idx)]).map_err(|_| ())?;
-                enc_tags.push(EncEntryTag {
-                    name,
-                    value,
-                    plaintext,
-                });
-                break;
+/// Decode and decrypt the tags column of a result row
+///
+/// Each tag is encoded as `plaintext:name:value` with hex encoded names and
+/// values, separated by commas. The encoded names and values are borrowed
+/// from the column and decoded into a shared buffer, so that only the
+/// decrypted tags themselves are allocated.
+pub(crate) fn decrypt_tags(key: &ProfileKey, tags: &[u8]) -> Result<Vec<EntryTag>, Error> {
+    decrypt_selected_tags(key, tags, None)
+}
+
+/// Decrypt the stored tags of an entry. When `tag_names` is provided, only
+/// the tags with a matching encrypted name are decrypted.
+pub(crate) fn decrypt_selected_tags(
+    key: &ProfileKey,
+    tags: &[u8],
+    tag_names: Option<&[Vec<u8>]>,
+) -> Result<Vec<EntryTag>, Error> {
+    if tags.is_empty() || tag_names.map_or(false, |names| names.is_empty()) {
+        return Ok(Vec::new());
+    }
+    let mut result = Vec::with_capacity(tags.iter().filter(|c| **c == b',').count() + 1);
+    let mut buf = Vec::new();
+    for tag in tags.split(|c| *c == b',') {
+        let mut parts = tag.splitn(3, |c| *c == b':');
+        let (plaintext, name, value) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(plaintext), Some(name), Some(value)) if !value.contains(&b':') => {
+                (plaintext == b"1", name, value)
             }
-            if tags[idx] == b':' {
-                if name_end != 0 {
-                    return Err(());
-                }
-                name_end = idx;
+            _ => return Err(err_msg!(Unexpected, "Error decoding entry tags")),
+        };
+        let enc_name = hex_decode_into(name, &mut buf)?;
+        if let Some(names) = tag_names {
+            if !names
+                .iter()
+                .any(|n| bool::from(n.as_slice().ct_eq(enc_name)))
+            {
+                continue;
             }
-            idx += 1;
         }
-        idx += 1;
+        let name = key.decrypt_tag_name_slice(enc_name)?.into_vec();
+        result.push(if plaintext {
+            let mut plain = vec![0; value.len() / 2];
+            hex::decode_to_slice(value, &mut plain)
+                .map_err(|_| err_msg!(Unexpected, "Error decoding entry tags"))?;
+            decode_plaintext_tag(name, plain)?
+        } else {
+            let name = decode_tag_str(name)?;
+            let value = key.decrypt_tag_value_slice(hex_decode_into(value, &mut buf)?)?;
+            EntryTag::Encrypted(name, decode_tag_str(value.into_vec())?)
+        });
     }
-    Ok(enc_tags)
+    Ok(result)
+}
+
+#[inline]
+fn hex_decode_into<'b>(hex: &[u8], buf: &'b mut Vec<u8>) -> Result<&'b [u8], Error> {
+    buf.resize(hex.len() / 2, 0);
+    hex::decode_to_slice(hex, buf.as_mut_slice())
+        .map_err(|_| err_msg!(Unexpected, "Error decoding entry tags"))?;
+    Ok(buf.as_slice())
+}
+
+#[inline]
+fn decode_tag_str(value: Vec<u8>) -> Result<String, Error> {
+    String::from_utf8(value).map_err(err_map!(Encryption, "Error decoding entry tag"))
 }
 
 pub fn decrypt_scan_batch(
     category: Option<String>,
     enc_rows: Vec<EncScanEntry>,
     key: &ProfileKey,
+    tag_names: Option<&[Vec<u8>]>,
 ) -> Result<Vec<Entry>, Error> {
     let mut batch = Vec::with_capacity(enc_rows.len());
     for enc_entry in enc_rows {
-        batch.push(decrypt_scan_entry(category.as_deref(), enc_entry, key)?);
+        batch.push(decrypt_scan_entry(
+            category.as_deref(),
+            enc_entry,
+            key,
+            tag_names,
+        )?);
     }
     Ok(batch)
 }
 
+/// Decrypt the category and name of audit log rows
+pub fn decrypt_audit_records(
+    rows: Vec<EncAuditRow>,
+    key: &ProfileKey,
+) -> Result<Vec<AuditRecord>, Error> {
+    rows.into_iter()
+        .map(
+            |(id, timestamp, operation, kind, enc_category, enc_name, error)| {
+                Ok(AuditRecord {
+                    id,
+                    timestamp,
+                    operation,
+                    kind: kind
+                        .map(|kind| EntryKind::try_from(kind as usize))
+                        .transpose()?,
+                    category: enc_category
+                        .map(|c| key.decrypt_entry_category(c))
+                        .transpose()?,
+                    name: enc_name.map(|n| key.decrypt_entry_name(n)).transpose()?,
+                    error,
+                })
+            },
+        )
+        .collect()
+}
+
+/// Decrypt grouped entry counts, returning them in order of category and tag value
+pub fn decrypt_entry_counts(
+    rows: Vec<(Vec<u8>, Option<Vec<u8>>, i64)>,
+    plaintext: bool,
+    key: &ProfileKey,
+) -> Result<Vec<EntryCount>, Error> {
+    let mut counts = Vec::with_capacity(rows.len());
+    for (enc_category, enc_value, count) in rows {
+        let category = key.decrypt_entry_category(enc_category)?;
+        let tag_value = enc_value
+            .map(|value| {
+                let value = if plaintext {
+                    value
+                } else {
+                    key.decrypt_tag_value(value)?.into_vec()
+                };
+                String::from_utf8(value).map_err(err_map!(Encryption, "Error decoding tag value"))
+            })
+            .transpose()?;
+        counts.push(EntryCount {
+            category,
+            tag_value,
+            count,
+        });
+    }
+    counts.sort_by(|a, b| {
+        a.category
+            .cmp(&b.category)
+            .then_with(|| a.tag_value.cmp(&b.tag_value))
+    });
+    Ok(counts)
+}
+
 pub fn decrypt_scan_entry(
     category: Option<&str>,
     enc_entry: EncScanEntry,
     key: &ProfileKey,
+    tag_names: Option<&[Vec<u8>]>,
 ) -> Result<Entry, Error> {
     let category = match category {
         Some(c) => c.to_owned(),
         None => key.decrypt_entry_category(enc_entry.category)?,
     };
     let name = key.decrypt_entry_name(enc_entry.name)?;
-    let value = key.decrypt_entry_value(category.as_bytes(), name.as_bytes(), enc_entry.value)?;
-    let tags = key.decrypt_entry_tags(
-        decode_tags(enc_entry.tags).map_err(|_| err_msg!(Unexpected, "Error decoding tags"))?,
+    let value = decrypt_value(
+        key,
+        category.as_bytes(),
+        name.as_bytes(),
+        enc_entry.value,
+        enc_entry.compression,
+        enc_entry.shared,
     )?;
-    Ok(Entry::new(enc_entry.kind, category, name, value, tags))
+    let tags = decrypt_selected_tags(key, &enc_entry.tags, tag_names)?;
+    Ok(Entry::new(enc_entry.kind, category, name, value, tags).with_version(enc_entry.version))
+}
+
+/// Check that a batch of entries can be decrypted, returning an issue for
+/// each entry which cannot
+pub fn verify_scan_batch(
+    profile: &str,
+    enc_rows: Vec<EncScanEntry>,
+    key: &ProfileKey,
+) -> Vec<IntegrityIssue> {
+    let mut issues = Vec::new();
+    for enc_entry in enc_rows {
+        let id = enc_entry.id;
+        let kind = enc_entry.kind;
+        let category = key.decrypt_entry_category(enc_entry.category.clone()).ok();
+        let name = key.decrypt_entry_name(enc_entry.name.clone()).ok();
+        if let Err(err) = decrypt_scan_entry(None, enc_entry, key, None) {
+            issues.push(IntegrityIssue {
+                profile: profile.to_string(),
+                id: Some(id),
+                kind: Some(kind),
+                category,
+                name,
+                reason: err.to_string(),
+                quarantined: false,
+            });
+        }
+    }
+    issues
 }
 
 pub fn expiry_timestamp(expire_ms: i64) -> Result<Expiry, Error> {
@@ -582,9 +1670,10 @@ pub fn encode_tag_filter<Q: QueryPrepare>(
     if let Some(tag_filter) = tag_filter {
         let tag_query = tag_query(tag_filter.query)?;
         let mut enc = TagSqlEncoder::new(
-            |name| key.encrypt_tag_name(ProfileKey::prepare_input(name.as_bytes())),
+            |name| key.encrypt_tag_name(ProfileKey::prepare_input(name)),
             |value| key.encrypt_tag_value(ProfileKey::prepare_input(value.as_bytes())),
         );
+        enc.numeric_value = Q::numeric_value;
         if let Some(filter) = enc.encode_query(&tag_query)? {
             let filter = replace_arg_placeholders::<Q>(&filter, (offset as i64) + 1);
             Ok(Some((filter, enc.arguments)))
@@ -596,12 +1685,369 @@ pub fn encode_tag_filter<Q: QueryPrepare>(
     }
 }
 
+/// A position within an ordered scan, allowing it to be resumed from the
+/// last returned row even when other rows have been added or removed
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ScanToken {
+    Created { id: i64 },
+    Tag { id: i64, value: Option<String> },
+    Name { category: String, name: String },
+}
+
+impl ScanToken {
+    /// Parse a continuation token, checking that it was issued for the
+    /// profile and matches the scan ordering
+    pub fn parse(token: &str, order_by: Option<&OrderBy>, key: &ProfileKey) -> Result<Self, Error> {
+        let invalid = || err_msg!(Input, "Invalid continuation token");
+        let token = key
+            .decrypt_scan_token(&hex::decode(token).map_err(|_| invalid())?)
+            .map_err(|_| invalid())?;
+        let token = std::str::from_utf8(token.as_ref()).map_err(|_| invalid())?;
+        let decode_str = |s: &str| {
+            hex::decode(s)
+                .ok()
+                .and_then(|s| String::from_utf8(s).ok())
+                .ok_or_else(invalid)
+        };
+        let mut parts = token.split(':');
+        let result = match (parts.next(), order_by) {
+            (Some("c"), Some(OrderBy::Created)) => Self::Created {
+                id: parts
+                    .next()
+                    .ok_or_else(invalid)?
+                    .parse()
+                    .map_err(|_| invalid())?,
+            },
+            (Some("t"), Some(OrderBy::Tag(_))) => Self::Tag {
+                id: parts
+                    .next()
+                    .ok_or_else(invalid)?
+                    .parse()
+                    .map_err(|_| invalid())?,
+                value: parts.next().map(decode_str).transpose()?,
+            },
+            (Some("n"), Some(OrderBy::Name)) => Self::Name {
+                category: decode_str(parts.next().ok_or_else(invalid)?)?,
+                name: decode_str(parts.next().ok_or_else(invalid)?)?,
+            },
+            _ => {
+                return Err(err_msg!(
+                    Input,
+                    "Continuation token does not match the scan ordering"
+                ))
+            }
+        };
+        if parts.next().is_some() {
+            return Err(invalid());
+        }
+        Ok(result)
+    }
+
+    /// Create a continuation token for the last row of a page of scan results
+    pub fn for_entry(order_by: Option<&OrderBy>, id: i64, entry: &Entry) -> Option<Self> {
+        match order_by? {
+            OrderBy::Created => Some(Self::Created { id }),
+            OrderBy::Tag(name) => Some(Self::Tag {
+                id,
+                value: entry
+                    .tags
+                    .iter()
+                    .filter_map(|tag| match tag {
+                        EntryTag::Plaintext(tag_name, value) if tag_name == name => Some(value),
+                        _ => None,
+                    })
+                    .min()
+                    .cloned(),
+            }),
+            OrderBy::Name => Some(Self::Name {
+                category: entry.category.clone(),
+                name: entry.name.clone(),
+            }),
+        }
+    }
+
+    /// Encode the token as an opaque string, encrypted with the profile key
+    pub fn encode(&self, key: &ProfileKey) -> Result<String, Error> {
+        let token = match self {
+            Self::Created { id } => format!("c:{id}"),
+            Self::Tag { id, value: None } => format!("t:{id}"),
+            Self::Tag {
+                id,
+                value: Some(value),
+            } => format!("t:{id}:{}", hex::encode(value)),
+            Self::Name { category, name } => {
+                format!("n:{}:{}", hex::encode(category), hex::encode(name))
+            }
+        };
+        Ok(hex::encode(key.encrypt_scan_token(token.as_bytes())?))
+    }
+}
+
+/// The tags selected for scan results, along with their encrypted names
+#[derive(Debug)]
+pub struct ScanTags {
+    selection: TagSelection,
+    enc_names: Option<Vec<Vec<u8>>>,
+}
+
+impl ScanTags {
+    /// Encrypt the names of the selected tags. The tag used to order the
+    /// results is always decrypted, as it is required to produce
+    /// continuation tokens.
+    pub fn new(
+        selection: TagSelection,
+        order_by: Option<&OrderBy>,
+        key: &ProfileKey,
+    ) -> Result<Self, Error> {
+        let enc_names = match selection.names() {
+            Some(names) => {
+                let order_tag = match order_by {
+                    Some(OrderBy::Tag(name)) => Some(name.as_str()),
+                    _ => None,
+                };
+                Some(
+                    names
+                        .chain(order_tag)
+                        .map(|name| {
+                            key.encrypt_tag_name(ProfileKey::prepare_input(name.as_bytes()))
+                        })
+                        .collect::<Result<_, _>>()?,
+                )
+            }
+            None => None,
+        };
+        Ok(Self {
+            selection,
+            enc_names,
+        })
+    }
+}
+
+/// Decrypt a page of scan results, dividing the rows between concurrent tasks,
+/// and encode the continuation token for the last entry
+pub async fn decrypt_scan_page(
+    category: Option<String>,
+    enc_rows: Vec<EncScanEntry>,
+    key: Arc<ProfileKey>,
+    order_by: Option<OrderBy>,
+    tags: Arc<ScanTags>,
+) -> Result<(Vec<Entry>, Option<String>), Error> {
+    let last_id = enc_rows.last().map(|row| row.id);
+    let mut batch = unblock_crypto_chunks(enc_rows, CRYPTO_CHUNK_SIZE, {
+        let key = key.clone();
+        let tags = tags.clone();
+        move |rows| decrypt_scan_batch(category.clone(), rows, &key, tags.enc_names.as_deref())
+    })
+    .await?;
+    let token = last_id
+        .zip(batch.last())
+        .and_then(|(id, entry)| ScanToken::for_entry(order_by.as_ref(), id, entry))
+        .map(|token| token.encode(&key))
+        .transpose()?;
+    if let Some(OrderBy::Tag(name)) = &order_by {
+        if !tags.selection.includes(name) {
+            for entry in batch.iter_mut() {
+                tags.selection.retain_tags(&mut entry.tags);
+            }
+        }
+    }
+    Ok((batch, token))
+}
+
+// select the (lowest) value of a plaintext tag for the current row
+const ORDER_TAG_VALUE: &str = "(SELECT it.value FROM items_tags it
+    WHERE it.item_id = i.id AND it.plaintext = 1 AND it.name = $$
+    ORDER BY it.value LIMIT 1)";
+
+/// Encode the ordering for a scan query, along with a filter restricting
+/// the results to those following a continuation token
+#[allow(clippy::type_complexity)]
+pub fn encode_order_by<Q: QueryPrepare>(
+    order_by: Option<&OrderBy>,
+    descending: bool,
+    after: Option<&ScanToken>,
+    key: &ProfileKey,
+    offset: usize,
+) -> Result<
+    (
+        Option<(String, Vec<Vec<u8>>)>,
+        Option<(String, Vec<Vec<u8>>)>,
+    ),
+    Error,
+> {
+    let (dir, cmp) = if descending {
+        ("DESC", "<")
+    } else {
+        ("ASC", ">")
+    };
+    match order_by {
+        Some(OrderBy::Created) => {
+            let filter = match after {
+                Some(ScanToken::Created { id }) => Some((format!("i.id {cmp} {id}"), Vec::new())),
+                _ => None,
+            };
+            Ok((filter, Some((format!("i.id {dir}"), Vec::new()))))
+        }
+        Some(OrderBy::Tag(name)) => {
+            let enc_name = key.encrypt_tag_name(ProfileKey::prepare_input(name.as_bytes()))?;
+            let mut filter_args = Vec::new();
+            let filter = match after {
+                Some(ScanToken::Tag {
+                    id,
+                    value: Some(value),
+                }) => {
+                    let value = value.as_bytes().to_vec();
+                    filter_args.extend([
+                        enc_name.clone(),
+                        value.clone(),
+                        enc_name.clone(),
+                        value,
+                        enc_name.clone(),
+                    ]);
+                    Some(format!(
+                        "({ORDER_TAG_VALUE} {cmp} $$ OR ({ORDER_TAG_VALUE} = $$ AND i.id {cmp} {id}) \
+                        OR {ORDER_TAG_VALUE} IS NULL)"
+                    ))
+                }
+                Some(ScanToken::Tag { id, value: None }) => {
+                    filter_args.push(enc_name.clone());
+                    Some(format!("({ORDER_TAG_VALUE} IS NULL AND i.id {cmp} {id})"))
+                }
+                _ => None,
+            };
+            let filter = filter.map(|filter| {
+                (
+                    replace_arg_placeholders::<Q>(&filter, (offset as i64) + 1),
+                    filter_args,
+                )
+            });
+            let order_offset = offset + filter.as_ref().map(|f| f.1.len()).unwrap_or(0);
+            let order = replace_arg_placeholders::<Q>(
+                &format!("{ORDER_TAG_VALUE} {dir} NULLS LAST, i.id {dir}"),
+                (order_offset as i64) + 1,
+            );
+            Ok((filter, Some((order, vec![enc_name]))))
+        }
+        // entry names are encrypted, so these are ordered after decryption
+        Some(OrderBy::Name) | None => Ok((None, None)),
+    }
+}
+
+/// Combine two encoded query filters, with arguments in order
+#[allow(clippy::type_complexity)]
+pub fn and_filters(
+    first: Option<(String, Vec<Vec<u8>>)>,
+    second: Option<(String, Vec<Vec<u8>>)>,
+) -> Option<(String, Vec<Vec<u8>>)> {
+    match (first, second) {
+        (Some((first, mut args)), Some((second, second_args))) => {
+            args.extend(second_args);
+            Some((format!("({first}) AND {second}"), args))
+        }
+        (first, None) => first,
+        (None, second) => second,
+    }
+}
+
+/// Order a stream of decrypted scan results by entry name.
+///
+/// The complete result set must be collected and sorted before the
+/// continuation token, offset and limit can be applied.
+pub fn order_scan_by_name<'s>(
+    stream: impl Stream<Item = Result<(Vec<Entry>, Option<String>), Error>> + Send + 's,
+    descending: bool,
+    after: Option<ScanToken>,
+    offset: Option<i64>,
+    limit: Option<i64>,
+    key: Arc<ProfileKey>,
+) -> impl Stream<Item = Result<(Vec<Entry>, Option<String>), Error>> + Send + 's {
+    try_stream! {
+        pin!(stream);
+        let mut entries = Vec::new();
+        while let Some((batch, _)) = stream.try_next().await? {
+            entries.extend(batch);
+        }
+        entries.sort_by(|a: &Entry, b: &Entry| {
+            a.name.cmp(&b.name).then_with(|| a.category.cmp(&b.category))
+        });
+        if descending {
+            entries.reverse();
+        }
+        if let Some(ScanToken::Name { category, name }) = after {
+            let last = (name, category);
+            entries.retain(|entry| {
+                let pos = (&entry.name, &entry.category);
+                if descending {
+                    pos < (&last.0, &last.1)
+                } else {
+                    pos > (&last.0, &last.1)
+                }
+            });
+        }
+        let offset = offset.unwrap_or(0).max(0) as usize;
+        let limit = match limit {
+            Some(limit) if limit >= 0 => limit as usize,
+            _ => usize::MAX,
+        };
+        let mut entries: Vec<Entry> = entries.into_iter().skip(offset).take(limit).collect();
+        while !entries.is_empty() {
+            let remain = entries.split_off(entries.len().min(PAGE_SIZE));
+            let token = entries
+                .last()
+                .and_then(|entry| ScanToken::for_entry(Some(&OrderBy::Name), 0, entry))
+                .map(|token| token.encode(&key))
+                .transpose()?;
+            yield (std::mem::replace(&mut entries, remain), token);
+        }
+    }
+}
+
 // allocate a String while ensuring there is sufficient capacity to reuse during encryption
 fn _prepare_string(value: &str) -> String {
     let buf = ProfileKey::prepare_input(value.as_bytes()).into_vec();
     unsafe { String::from_utf8_unchecked(buf) }
 }
 
+/// An encrypted pair of existing and new tag names
+pub type EncTagRename = (Vec<u8>, Vec<u8>);
+
+/// Encrypt the pairs of existing and new tag names for a tag rename
+pub fn encrypt_tag_renames(
+    key: &ProfileKey,
+    rename_tags: Vec<(String, String)>,
+) -> Result<Vec<EncTagRename>, Error> {
+    rename_tags
+        .into_iter()
+        .map(|(name, new_name)| {
+            Ok((
+                key.encrypt_tag_name(_prepare_string(&name).into())?,
+                key.encrypt_tag_name(_prepare_string(&new_name).into())?,
+            ))
+        })
+        .collect()
+}
+
+/// Encrypt the tags added by a tag update along with the names of the existing
+/// tags to be removed, which include the names of all added tags
+pub fn encrypt_tag_update(
+    key: &ProfileKey,
+    add_tags: Vec<EntryTag>,
+    remove_tags: Vec<String>,
+) -> Result<(Vec<EncEntryTag>, Vec<Vec<u8>>), Error> {
+    let mut names = remove_tags
+        .iter()
+        .map(|name| _prepare_string(name))
+        .chain(add_tags.iter().map(|tag| tag.name().to_string()))
+        .collect::<Vec<_>>();
+    names.sort();
+    names.dedup();
+    let enc_names = names
+        .into_iter()
+        .map(|name| key.encrypt_tag_name(name.into()))
+        .collect::<Result<Vec<_>, Error>>()?;
+    Ok((key.encrypt_entry_tags(add_tags)?, enc_names))
+}
+
 // convert a slice of tags into a Vec, while ensuring there is
 // adequate space in the allocations to reuse them during encryption
 pub fn prepare_tags(tags: &[EntryTag]) -> Result<Vec<EntryTag>, Error> {
@@ -611,6 +2057,12 @@ pub fn prepare_tags(tags: &[EntryTag]) -> Result<Vec<EntryTag>, Error> {
             EntryTag::Plaintext(name, value) => {
                 EntryTag::Plaintext(_prepare_string(name), value.clone())
             }
+            EntryTag::Numeric(name, value) => {
+                if !is_numeric_value(value) {
+                    return Err(err_msg!(Input, "Invalid value for numeric tag: {}", name));
+                }
+                EntryTag::Numeric(name.clone(), value.clone())
+            }
             EntryTag::Encrypted(name, value) => {
                 EntryTag::Encrypted(_prepare_string(name), _prepare_string(value))
             }
@@ -619,10 +2071,25 @@ pub fn prepare_tags(tags: &[EntryTag]) -> Result<Vec<EntryTag>, Error> {
     Ok(result)
 }
 
+/// Split free text into alphanumeric search terms, returning the encoded
+/// full-text query or `None` if there is nothing to search for
+pub fn encode_search<Q: QueryPrepare>(text: &str) -> Option<String> {
+    let terms = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|term| !term.is_empty())
+        .collect::<Vec<_>>();
+    if terms.is_empty() {
+        None
+    } else {
+        Some(Q::search_query(&terms))
+    }
+}
+
 pub fn extend_query<'q, Q: QueryPrepare>(
     query: &str,
     args: &mut QueryParams<'q, Q::DB>,
     tag_filter: Option<(String, Vec<Vec<u8>>)>,
+    order_by: Option<(String, Vec<Vec<u8>>)>,
     offset: Option<i64>,
     limit: Option<i64>,
 ) -> Result<String, Error>
@@ -636,6 +2103,11 @@ where
         query.push_str(" AND "); // assumes WHERE already occurs
         query.push_str(&filter_clause);
     };
+    if let Some((order_clause, order_args)) = order_by {
+        args.extend(order_args);
+        query.push_str(" ORDER BY ");
+        query.push_str(&order_clause);
+    };
     if offset.is_some() || limit.is_some() {
         query = Q::limit_query(query, args, offset, limit);
     };
@@ -671,6 +2143,255 @@ pub fn encode_profile_key(
     store_key.wrap_data(profile_key.to_bytes()?)
 }
 
+pub fn encode_profile_metadata(metadata: &[u8], store_key: &StoreKey) -> Result<Vec<u8>, Error> {
+    store_key.wrap_data(SecretBytes::from_slice(metadata))
+}
+
+/// Encrypt a profile key for access using a profile-specific pass key,
+/// returning the encrypted key and the access key reference
+pub fn encode_profile_access_key(
+    profile_key: &ProfileKey,
+    method: StoreKeyMethod,
+    pass_key: PassKey<'_>,
+) -> Result<(Vec<u8>, String), Error> {
+    if method == StoreKeyMethod::Unprotected || pass_key.is_empty() {
+        return Err(err_msg!(Input, "Profile access key not provided"));
+    }
+    let (access_key, access_key_ref) = method.resolve(pass_key)?;
+    Ok((
+        encode_profile_key(profile_key, &access_key)?,
+        access_key_ref.into_uri(),
+    ))
+}
+
+/// Decrypt a profile key using a profile-specific pass key
+pub fn decode_profile_access_key(
+    enc_key: Option<Vec<u8>>,
+    access_key_ref: Option<String>,
+    pass_key: PassKey<'_>,
+) -> Result<ProfileKey, Error> {
+    let (enc_key, access_key_ref) = match (enc_key, access_key_ref) {
+        (Some(enc_key), Some(key_ref)) => (enc_key, StoreKeyReference::parse_uri(&key_ref)?),
+        _ => return Err(err_msg!(NotFound, "Profile access key not found")),
+    };
+    let access_key = access_key_ref.resolve(pass_key)?;
+    let data = access_key
+        .unwrap_data(enc_key)
+        .map_err(err_map!(Encryption, "Error decrypting profile key"))?;
+    ProfileKey::from_slice(data.as_ref())
+}
+
+/// Decrypt the metadata of profile details loaded from the database
+pub fn decrypt_profile_details(
+    mut details: Vec<ProfileDetails>,
+    store_key: &StoreKey,
+) -> Result<Vec<ProfileDetails>, Error> {
+    for profile in details.iter_mut() {
+        if let Some(enc_metadata) = profile.metadata.take() {
+            profile.metadata = Some(store_key.unwrap_data(enc_metadata)?.into_vec());
+        }
+    }
+    Ok(details)
+}
+
+/// Check that inserting new entries would not exceed the storage limits of
+/// a profile, given the current number and total value size of its entries
+pub fn check_profile_limits(
+    limits: ProfileLimits,
+    count: i64,
+    value_bytes: i64,
+    add_count: usize,
+    add_value_bytes: usize,
+) -> Result<(), Error> {
+    if let Some(max) = limits.max_entries {
+        if count + add_count as i64 > max {
+            return Err(err_msg!(QuotaExceeded, "Profile entry limit exceeded"));
+        }
+    }
+    if let Some(max) = limits.max_value_bytes {
+        if value_bytes + add_value_bytes as i64 > max {
+            return Err(err_msg!(QuotaExceeded, "Profile value size limit exceeded"));
+        }
+    }
+    Ok(())
+}
+
+/// Re-encrypt profile metadata when changing the store key
+pub fn rekey_profile_metadata(
+    enc_metadata: Vec<u8>,
+    prev_key: &StoreKey,
+    store_key: &StoreKey,
+) -> Result<Vec<u8>, Error> {
+    store_key.wrap_data(prev_key.unwrap_data(enc_metadata)?)
+}
+
+/// Determine whether the store key must be re-derived in order to satisfy a
+/// minimum key derivation method, checking that the pass key corresponds to
+/// the current store key
+pub fn check_kdf_upgrade(
+    key_ref: &str,
+    minimum: &KdfMethod,
+    pass_key: PassKey<'_>,
+    store_key: &StoreKey,
+) -> Result<bool, Error> {
+    let key_ref = StoreKeyReference::parse_uri(key_ref)?;
+    match &key_ref {
+        StoreKeyReference::DeriveKey(method, _) if method.is_below(minimum) => {
+            if key_ref.resolve(pass_key)?.0 != store_key.0 {
+                return Err(err_msg!(Input, "Pass key does not match the store key"));
+            }
+            Ok(true)
+        }
+        _ => Ok(false),
+    }
+}
+
+/// Re-encrypt the profile key and metadata of a profile when changing the
+/// store key
+pub fn rekey_profile(
+    enc_key: Vec<u8>,
+    enc_metadata: Option<Vec<u8>>,
+    prev_key: &StoreKey,
+    store_key: &StoreKey,
+) -> Result<(Vec<u8>, Option<Vec<u8>>), Error> {
+    let profile_key = prev_key
+        .unwrap_data(enc_key)
+        .map_err(err_map!(Encryption, "Error decrypting profile key"))?;
+    Ok((
+        store_key.wrap_data(profile_key)?,
+        enc_metadata
+            .map(|m| rekey_profile_metadata(m, prev_key, store_key))
+            .transpose()?,
+    ))
+}
+
+/// The checkpoint of a rekey in progress, recorded in the store configuration
+/// so that an interrupted rekey may be completed using either store key
+#[derive(Clone, Debug)]
+pub struct RekeyCheckpoint {
+    /// The reference of the new store key
+    pub key_ref: String,
+    /// The new store key, encrypted by the previous store key
+    pub next_key: String,
+    /// The previous store key, encrypted by the new store key
+    pub prev_key: String,
+    /// The last profile re-encrypted under the new store key
+    pub profile_id: ProfileId,
+}
+
+impl RekeyCheckpoint {
+    /// Create a checkpoint for a rekey which has not yet re-encrypted any
+    /// profiles
+    pub fn new(key_ref: String, prev_key: &StoreKey, store_key: &StoreKey) -> Result<Self, Error> {
+        Ok(Self {
+            key_ref,
+            next_key: wrap_store_key(store_key, prev_key)?,
+            prev_key: wrap_store_key(prev_key, store_key)?,
+            profile_id: 0,
+        })
+    }
+
+    /// Load a checkpoint from the rekey configuration entries of the store,
+    /// if any are present
+    pub fn from_config(
+        entries: impl IntoIterator<Item = (String, String)>,
+    ) -> Result<Option<Self>, Error> {
+        let (mut key_ref, mut next_key, mut prev_key, mut profile_id) = (None, None, None, None);
+        for (name, value) in entries {
+            match name.as_str() {
+                "rekey_ref" => key_ref = Some(value),
+                "rekey_next" => next_key = Some(value),
+                "rekey_prev" => prev_key = Some(value),
+                "rekey_profile" => {
+                    profile_id = Some(
+                        value
+                            .parse()
+                            .map_err(|_| err_msg!(Unexpected, "Invalid rekey checkpoint"))?,
+                    )
+                }
+                _ => (),
+            }
+        }
+        match (key_ref, next_key, prev_key, profile_id) {
+            (None, None, None, None) => Ok(None),
+            (Some(key_ref), Some(next_key), Some(prev_key), Some(profile_id)) => Ok(Some(Self {
+                key_ref,
+                next_key,
+                prev_key,
+                profile_id,
+            })),
+            _ => Err(err_msg!(Unexpected, "Incomplete rekey checkpoint")),
+        }
+    }
+
+    /// The configuration entries recording the checkpoint
+    pub fn config_entries(&self) -> [(&'static str, String); 4] {
+        [
+            (REKEY_CONFIG_NAMES[0], self.key_ref.clone()),
+            (REKEY_CONFIG_NAMES[1], self.next_key.clone()),
+            (REKEY_CONFIG_NAMES[2], self.prev_key.clone()),
+            (REKEY_CONFIG_NAMES[3], self.profile_id.to_string()),
+        ]
+    }
+
+    /// Obtain the new store key in order to resume the rekey, checking
+    /// that the key method and pass key correspond to the new store key
+    pub fn resume(
+        &self,
+        method: &StoreKeyMethod,
+        pass_key: PassKey<'_>,
+        prev_key: &StoreKey,
+    ) -> Result<StoreKey, Error> {
+        let key_ref = StoreKeyReference::parse_uri(&self.key_ref)?;
+        let store_key = unwrap_store_key(&self.next_key, prev_key)?;
+        if !key_ref.compare_method(method) || key_ref.resolve(pass_key)?.0 != store_key.0 {
+            return Err(err_msg!(
+                Input,
+                "A rekey of the store with a different pass key is in progress"
+            ));
+        }
+        Ok(store_key)
+    }
+
+    /// Resolve the previous and new store keys given the reference of the
+    /// previous store key and a pass key for either of the store keys
+    pub fn resolve(
+        &self,
+        key_ref: &str,
+        pass_key: PassKey<'_>,
+    ) -> Result<(StoreKey, StoreKey), Error> {
+        let prev_ref = StoreKeyReference::parse_uri(key_ref)?;
+        if let Ok(prev_key) = prev_ref.resolve(pass_key.as_ref()) {
+            if let Ok(store_key) = unwrap_store_key(&self.next_key, &prev_key) {
+                return Ok((prev_key, store_key));
+            }
+        }
+        let next_ref = StoreKeyReference::parse_uri(&self.key_ref)?;
+        let store_key = next_ref.resolve(pass_key)?;
+        let prev_key = unwrap_store_key(&self.prev_key, &store_key)?;
+        Ok((prev_key, store_key))
+    }
+}
+
+fn wrap_store_key(key: &StoreKey, wrap_key: &StoreKey) -> Result<String, Error> {
+    let enc = wrap_key.wrap_data(SecretBytes::from_slice(key.to_passkey().as_bytes()))?;
+    Ok(bs58::encode(enc).into_string())
+}
+
+fn unwrap_store_key(enc_key: &str, wrap_key: &StoreKey) -> Result<StoreKey, Error> {
+    let enc_key = bs58::decode(enc_key)
+        .into_vec()
+        .map_err(|_| err_msg!(Unexpected, "Invalid rekey checkpoint"))?;
+    let key = wrap_key
+        .unwrap_data(enc_key)
+        .map_err(err_map!(Encryption, "Error decrypting store key"))?;
+    match std::str::from_utf8(key.as_ref()) {
+        Ok("") => Ok(StoreKey::empty()),
+        Ok(key) => parse_raw_store_key(key),
+        Err(_) => Err(err_msg!(Encryption, "Error decrypting store key")),
+    }
+}
+
 #[inline]
 pub fn random_profile_name() -> String {
     uuid::Uuid::new_v4().to_string()
@@ -1,14 +1,22 @@
 //! Storage backends supported by aries-askar
 
-use std::fmt::Debug;
+use std::{fmt::Debug, sync::Arc};
+
+use futures_lite::future::zip;
 
 use crate::{
-    entry::{Entry, EntryKind, EntryOperation, EntryTag, Scan, TagFilter},
+    entry::{
+        Entry, EntryCount, EntryEvent, EntryKind, EntryOperation, EntryPrecondition, EntryTag,
+        OrderBy, Scan, Subscription, TagFilter, TagSelection,
+    },
     error::{Error, ErrorKind},
     future::BoxFuture,
-    protect::{PassKey, StoreKeyMethod},
+    protect::{kdf::KdfMethod, PassKey, StoreKeyMethod},
 };
 
+/// Custom record store support
+pub mod custom;
+
 #[cfg(any(feature = "postgres", feature = "sqlite"))]
 pub(crate) mod db_utils;
 
@@ -22,6 +30,211 @@ pub mod postgres;
 /// Sqlite database support
 pub mod sqlite;
 
+/// Supported operations for savepoints within a transaction
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SavepointOperation {
+    /// Create a new savepoint
+    Create,
+    /// Release a savepoint, keeping all changes made since it was created
+    Release,
+    /// Discard all changes made since the savepoint was created
+    Rollback,
+}
+
+/// Isolation levels which may be requested when starting a transaction
+///
+/// PostgreSQL applies the corresponding `SET TRANSACTION ISOLATION LEVEL`.
+/// SQLite transactions are always serializable: `Serializable` acquires the
+/// database write lock when the transaction starts, as the default behaviour
+/// does, while the other levels defer acquiring the lock until the first write.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IsolationLevel {
+    /// Each statement sees only data committed before it began
+    ReadCommitted,
+    /// All statements see only data committed before the transaction began
+    RepeatableRead,
+    /// Transactions behave as if they were executed one at a time
+    Serializable,
+}
+
+/// Compression which may be applied to record values before encryption
+///
+/// The compression applied to each record is stored alongside it, so that
+/// existing records remain readable when the store option is changed.
+/// Values are only stored compressed when this reduces their length.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Compression {
+    /// Store record values without compression
+    #[default]
+    None,
+    /// Compress record values using zstd (requires the `zstd` feature)
+    Zstd,
+}
+
+impl Compression {
+    /// Parse the `compression` store option
+    pub fn from_option(value: &str) -> Result<Self, Error> {
+        match value {
+            "none" => Ok(Self::None),
+            #[cfg(feature = "zstd")]
+            "zstd" => Ok(Self::Zstd),
+            #[cfg(not(feature = "zstd"))]
+            "zstd" => Err(err_msg!(Unsupported, "zstd compression is not enabled")),
+            _ => Err(err_msg!(Input, "Unknown compression method: {}", value)),
+        }
+    }
+}
+
+/// Details of a store profile
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProfileDetails {
+    /// The unique identifier of the profile within the store
+    pub id: i64,
+    /// The profile name
+    pub name: String,
+    /// The creation time of the profile in seconds since the Unix epoch,
+    /// if known. Profiles created by older versions have no creation time.
+    pub created: Option<i64>,
+    /// The number of item entries in the profile
+    pub item_count: i64,
+    /// The number of key entries in the profile
+    pub key_count: i64,
+    /// Custom metadata associated with the profile
+    pub metadata: Option<Vec<u8>>,
+    /// The storage limits of the profile
+    pub limits: ProfileLimits,
+}
+
+/// The progress of a store rekey operation
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RekeyProgress {
+    /// The number of profiles re-encrypted under the new store key
+    pub completed: i64,
+    /// The total number of profiles in the store
+    pub total: i64,
+}
+
+/// A callback receiving the progress of a store rekey operation
+pub type RekeyCallback = Arc<dyn Fn(RekeyProgress) + Send + Sync>;
+
+/// A record of an operation performed within a profile, read from the
+/// audit log of the store
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AuditRecord {
+    /// The position of the record within the audit log
+    pub id: i64,
+    /// The time the record was appended in seconds since the Unix epoch
+    pub timestamp: i64,
+    /// The name of the operation performed
+    pub operation: String,
+    /// The kind of entries affected by the operation, if applicable
+    pub kind: Option<EntryKind>,
+    /// The category affected by the operation, if applicable
+    pub category: Option<String>,
+    /// The name of the entry affected by the operation, if applicable
+    pub name: Option<String>,
+    /// The kind of error produced if the operation failed
+    pub error: Option<String>,
+}
+
+impl AuditRecord {
+    /// Whether the recorded operation completed successfully
+    pub fn is_success(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// The result of verifying the integrity of the entries in a store
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct IntegrityReport {
+    /// The number of profiles checked
+    pub profiles: i64,
+    /// The number of entries checked
+    pub entries: i64,
+    /// The problems found with profiles and entries
+    pub issues: Vec<IntegrityIssue>,
+}
+
+impl IntegrityReport {
+    /// Whether no problems were found
+    pub fn is_valid(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// A problem found when verifying the integrity of a store
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IntegrityIssue {
+    /// The name of the affected profile
+    pub profile: String,
+    /// The row identifier of the affected entry, or `None` when the problem
+    /// affects the whole profile
+    pub id: Option<i64>,
+    /// The kind of the affected entry, if known
+    pub kind: Option<EntryKind>,
+    /// The category of the affected entry, if it could be decrypted
+    pub category: Option<String>,
+    /// The name of the affected entry, if it could be decrypted
+    pub name: Option<String>,
+    /// A description of the problem
+    pub reason: String,
+    /// Whether the affected entry was quarantined
+    pub quarantined: bool,
+}
+
+/// The result of checking and repairing the structure of a store
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RepairReport {
+    /// The number of entries associated with a missing profile
+    pub orphan_entries: i64,
+    /// The number of tag rows without an associated entry
+    pub orphan_tags: i64,
+    /// The number of chunk rows without an associated entry
+    pub orphan_chunks: i64,
+    /// The number of audit records associated with a missing profile
+    pub orphan_audit_records: i64,
+    /// The number of shared values with an incorrect reference count
+    pub shared_refs: i64,
+    /// The profile selected as the new default, when the default profile
+    /// does not exist
+    pub default_profile: Option<String>,
+    /// Whether the indexes of the store were rebuilt
+    pub reindexed: bool,
+}
+
+impl RepairReport {
+    /// Whether no problems were found
+    pub fn is_clean(&self) -> bool {
+        self.orphan_entries == 0
+            && self.orphan_tags == 0
+            && self.orphan_chunks == 0
+            && self.orphan_audit_records == 0
+            && self.shared_refs == 0
+            && self.default_profile.is_none()
+    }
+}
+
+/// The query generated for a scan of a profile, along with the plan selected
+/// by the database to execute it
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct QueryPlan {
+    /// The generated SQL query
+    pub query: String,
+    /// The number of arguments bound to the query
+    pub arguments: usize,
+    /// The lines of the execution plan reported by the database
+    pub plan: Vec<String>,
+}
+
+/// Storage limits enforced when inserting entries into a profile
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ProfileLimits {
+    /// The maximum number of stored entries
+    pub max_entries: Option<i64>,
+    /// The maximum total size of the stored (encrypted) entry values in bytes
+    pub max_value_bytes: Option<i64>,
+}
+
 /// Represents a generic backend implementation
 pub trait Backend: Debug + Send + Sync {
     /// The type of session managed by this backend
@@ -42,10 +255,46 @@ pub trait Backend: Debug + Send + Sync {
     /// Get the details of all store profiles
     fn list_profiles(&self) -> BoxFuture<'_, Result<Vec<String>, Error>>;
 
+    /// Get the details of all store profiles
+    fn list_profile_details(&self) -> BoxFuture<'_, Result<Vec<ProfileDetails>, Error>>;
+
+    /// Replace the custom metadata associated with a profile. The metadata
+    /// is encrypted using the store key.
+    fn set_profile_metadata(
+        &self,
+        name: String,
+        metadata: Option<Vec<u8>>,
+    ) -> BoxFuture<'_, Result<(), Error>>;
+
+    /// Replace the storage limits of a profile. Entries which have been
+    /// removed but not yet purged count towards the limits.
+    fn set_profile_limits(
+        &self,
+        name: String,
+        limits: ProfileLimits,
+    ) -> BoxFuture<'_, Result<(), Error>>;
+
+    /// Allow the profile to be opened using a profile-specific pass key,
+    /// without knowledge of the store key
+    fn set_profile_access_key(
+        &self,
+        name: String,
+        method: StoreKeyMethod,
+        pass_key: PassKey<'_>,
+    ) -> BoxFuture<'_, Result<(), Error>>;
+
+    /// Remove the profile-specific pass key of a profile
+    fn remove_profile_access_key(&self, name: String) -> BoxFuture<'_, Result<(), Error>>;
+
+    /// Rename an existing profile, updating the default profile name if
+    /// it refers to the same profile
+    fn rename_profile(&self, name: String, new_name: String) -> BoxFuture<'_, Result<(), Error>>;
+
     /// Remove an existing profile
     fn remove_profile(&self, name: String) -> BoxFuture<'_, Result<bool, Error>>;
 
     /// Create a [`Scan`] against the store
+    #[allow(clippy::too_many_arguments)]
     fn scan(
         &self,
         profile: Option<String>,
@@ -54,18 +303,79 @@ pub trait Backend: Debug + Send + Sync {
         tag_filter: Option<TagFilter>,
         offset: Option<i64>,
         limit: Option<i64>,
+        order_by: Option<OrderBy>,
+        descending: bool,
+        after: Option<String>,
+        tags: TagSelection,
     ) -> BoxFuture<'_, Result<Scan<'static, Entry>, Error>>;
 
+    /// Subscribe to insert, replace and remove events for entries in a profile
+    fn subscribe(
+        &self,
+        profile: Option<String>,
+        kind: Option<EntryKind>,
+        category: Option<String>,
+    ) -> BoxFuture<'_, Result<Subscription<'static>, Error>>;
+
     /// Create a new session against the store
     fn session(&self, profile: Option<String>, transaction: bool) -> Result<Self::Session, Error>;
 
+    /// Create a new transaction session using a specific isolation level
+    fn transaction(
+        &self,
+        profile: Option<String>,
+        isolation: IsolationLevel,
+    ) -> Result<Self::Session, Error>;
+
     /// Replace the wrapping key of the store
     fn rekey(
         &mut self,
         method: StoreKeyMethod,
         key: PassKey<'_>,
+    ) -> BoxFuture<'_, Result<(), Error>> {
+        self.rekey_with_progress(method, key, None)
+    }
+
+    /// Replace the wrapping key of the store, reporting the progress of the
+    /// re-encryption of the store profiles to `progress`. The profiles are
+    /// re-encrypted in batches, recording a checkpoint after each batch so
+    /// that an interrupted rekey may be resumed by repeating the operation
+    /// with the same key method and pass key, or is otherwise completed
+    /// when the store is next opened.
+    fn rekey_with_progress(
+        &mut self,
+        method: StoreKeyMethod,
+        key: PassKey<'_>,
+        progress: Option<RekeyCallback>,
     ) -> BoxFuture<'_, Result<(), Error>>;
 
+    /// Re-derive the store key from its passphrase using the key derivation
+    /// method `minimum`, if the current method of the store is weaker. The
+    /// pass key must match the current store key. Returns `true` if the
+    /// store key was replaced.
+    fn upgrade_kdf(
+        &mut self,
+        minimum: KdfMethod,
+        pass_key: PassKey<'_>,
+    ) -> BoxFuture<'_, Result<bool, Error>>;
+
+    /// Write a consistent point-in-time copy of the store to a new location.
+    /// The copy retains the keys of the original store.
+    fn snapshot<'q>(&'q self, target: &'q str) -> BoxFuture<'q, Result<(), Error>>;
+
+    /// Check that the key of every profile and every entry in the store can
+    /// be decrypted, including entry values, tags and shared value references.
+    /// When `quarantine` is set, entries which fail verification are marked
+    /// as deleted so that they are no longer returned, but may still be
+    /// restored or purged.
+    fn verify_integrity(&self, quarantine: bool) -> BoxFuture<'_, Result<IntegrityReport, Error>>;
+
+    /// Check the structure of the store, removing rows which refer to missing
+    /// profiles or entries, correcting shared value reference counts and the
+    /// default profile, and rebuilding the indexes. When `dry_run` is set, the
+    /// problems are reported without modifying the store.
+    fn repair(&self, dry_run: bool) -> BoxFuture<'_, Result<RepairReport, Error>>;
+
     /// Close the store instance
     fn close(&self) -> BoxFuture<'_, Result<(), Error>>;
 }
@@ -83,6 +393,14 @@ pub trait ManageBackend<'a> {
         profile: Option<String>,
     ) -> BoxFuture<'a, Result<Self::Backend, Error>>;
 
+    /// Open an existing store scoped to a single profile, using the
+    /// profile-specific pass key
+    fn open_profile_backend(
+        self,
+        profile: String,
+        pass_key: PassKey<'a>,
+    ) -> BoxFuture<'a, Result<Self::Backend, Error>>;
+
     /// Provision a new store
     fn provision_backend(
         self,
@@ -106,6 +424,25 @@ pub trait BackendSession: Debug + Send {
         tag_filter: Option<TagFilter>,
     ) -> BoxFuture<'q, Result<i64, Error>>;
 
+    /// Count the number of matching records in the store, grouped by category
+    /// and optionally by the value of a tag
+    fn count_grouped<'q>(
+        &'q mut self,
+        kind: Option<EntryKind>,
+        category: Option<&'q str>,
+        tag_filter: Option<TagFilter>,
+        group_tag: Option<&'q str>,
+    ) -> BoxFuture<'q, Result<Vec<EntryCount>, Error>>;
+
+    /// Describe the query and execution plan used when scanning for matching
+    /// records, without fetching any results
+    fn explain_scan<'q>(
+        &'q mut self,
+        kind: Option<EntryKind>,
+        category: Option<&'q str>,
+        tag_filter: Option<TagFilter>,
+    ) -> BoxFuture<'q, Result<QueryPlan, Error>>;
+
     /// Fetch a single record from the store by category and name
     fn fetch<'q>(
         &'q mut self,
@@ -115,7 +452,18 @@ pub trait BackendSession: Debug + Send {
         for_update: bool,
     ) -> BoxFuture<'q, Result<Option<Entry>, Error>>;
 
-    /// Fetch all matching records from the store
+    /// Fetch multiple records from the store by category and name.
+    /// Entries are returned in the order of the requested names, and
+    /// names without a matching record are skipped.
+    fn fetch_many<'q>(
+        &'q mut self,
+        kind: EntryKind,
+        category: &'q str,
+        names: &'q [&'q str],
+        for_update: bool,
+    ) -> BoxFuture<'q, Result<Vec<Entry>, Error>>;
+
+    /// Fetch all matching records from the store, in the order they were inserted
     fn fetch_all<'q>(
         &'q mut self,
         kind: Option<EntryKind>,
@@ -125,25 +473,28 @@ pub trait BackendSession: Debug + Send {
         for_update: bool,
     ) -> BoxFuture<'q, Result<Vec<Entry>, Error>>;
 
-    /// Insert scan results from another profile or store
+    /// Search for records with plaintext tag values matching a full-text query
+    fn search<'q>(
+        &'q mut self,
+        kind: Option<EntryKind>,
+        category: Option<&'q str>,
+        text: &'q str,
+        tag_filter: Option<TagFilter>,
+        limit: Option<i64>,
+    ) -> BoxFuture<'q, Result<Vec<Entry>, Error>>;
+
+    /// Insert scan results from another profile or store. Each page of
+    /// results is fetched while the previous page is being written.
     fn import_scan<'q>(
         &'q mut self,
         mut scan: Scan<'q, Entry>,
     ) -> BoxFuture<'_, Result<(), Error>> {
         Box::pin(async move {
-            while let Some(rows) = scan.fetch_next().await? {
-                for entry in rows {
-                    self.update(
-                        entry.kind,
-                        EntryOperation::Insert,
-                        entry.category.as_str(),
-                        entry.name.as_str(),
-                        Some(entry.value.as_ref()),
-                        Some(entry.tags.as_ref()),
-                        None,
-                    )
-                    .await?;
-                }
+            let mut rows = scan.fetch_next().await?;
+            while let Some(batch) = rows {
+                let (inserted, next) = zip(self.insert_many(&batch, None), scan.fetch_next()).await;
+                inserted?;
+                rows = next?;
             }
             Ok(())
         })
@@ -157,6 +508,15 @@ pub trait BackendSession: Debug + Send {
         tag_filter: Option<TagFilter>,
     ) -> BoxFuture<'q, Result<i64, Error>>;
 
+    /// Remove all matching records from the store, returning an event
+    /// describing each removed record
+    fn remove_all_returning<'q>(
+        &'q mut self,
+        kind: Option<EntryKind>,
+        category: Option<&'q str>,
+        tag_filter: Option<TagFilter>,
+    ) -> BoxFuture<'q, Result<Vec<EntryEvent>, Error>>;
+
     /// Insert or replace a record in the store
     #[allow(clippy::too_many_arguments)]
     fn update<'q>(
@@ -170,6 +530,144 @@ pub trait BackendSession: Debug + Send {
         expiry_ms: Option<i64>,
     ) -> BoxFuture<'q, Result<(), Error>>;
 
+    /// Replace or remove a record in the store if its current state satisfies
+    /// a precondition, failing with a `Conflict` error otherwise
+    #[allow(clippy::too_many_arguments)]
+    fn update_if<'q>(
+        &'q mut self,
+        kind: EntryKind,
+        operation: EntryOperation,
+        category: &'q str,
+        name: &'q str,
+        value: Option<&'q [u8]>,
+        tags: Option<&'q [EntryTag]>,
+        expiry_ms: Option<i64>,
+        precondition: &'q EntryPrecondition,
+    ) -> BoxFuture<'q, Result<(), Error>>;
+
+    /// Insert a batch of new records into the store
+    ///
+    /// Records are written using multi-row statements within a single
+    /// transaction, failing if any one of the records already exists.
+    fn insert_many<'q>(
+        &'q mut self,
+        entries: &'q [Entry],
+        expiry_ms: Option<i64>,
+    ) -> BoxFuture<'q, Result<(), Error>>;
+
+    /// Move all records in a category to another category, returning the number
+    /// of records moved
+    ///
+    /// Tags on the moved records may be renamed by providing pairs of existing
+    /// and new tag names. The move fails if any record of the same name already
+    /// exists in the target category.
+    fn move_category<'q>(
+        &'q mut self,
+        kind: EntryKind,
+        category: &'q str,
+        new_category: &'q str,
+        rename_tags: &'q [(&'q str, &'q str)],
+    ) -> BoxFuture<'q, Result<i64, Error>>;
+
+    /// Rename an existing record, preserving its value, tags and expiry
+    fn rename<'q>(
+        &'q mut self,
+        kind: EntryKind,
+        category: &'q str,
+        name: &'q str,
+        new_name: &'q str,
+    ) -> BoxFuture<'q, Result<(), Error>>;
+
+    /// Add and remove tags on an existing record without rewriting its value
+    ///
+    /// Tags named in `remove_tags` are removed regardless of whether they are
+    /// plaintext or encrypted, and the tags in `add_tags` replace any existing
+    /// tags with the same name.
+    fn update_tags<'q>(
+        &'q mut self,
+        kind: EntryKind,
+        category: &'q str,
+        name: &'q str,
+        add_tags: &'q [EntryTag],
+        remove_tags: &'q [&'q str],
+    ) -> BoxFuture<'q, Result<(), Error>>;
+
+    /// Store a chunk of opaque data associated with an existing record
+    ///
+    /// Chunks are kept in a separate table and removed along with the record.
+    /// Writing the chunk at index zero discards any chunks previously stored
+    /// for the record.
+    fn write_chunk<'q>(
+        &'q mut self,
+        kind: EntryKind,
+        category: &'q str,
+        name: &'q str,
+        index: u32,
+        data: &'q [u8],
+    ) -> BoxFuture<'q, Result<(), Error>>;
+
+    /// Fetch the chunks associated with a record in index order, starting
+    /// from the chunk at index `start`
+    fn fetch_chunks<'q>(
+        &'q mut self,
+        kind: EntryKind,
+        category: &'q str,
+        name: &'q str,
+        start: u32,
+        limit: u32,
+    ) -> BoxFuture<'q, Result<Vec<Vec<u8>>, Error>>;
+
+    /// Append a record of an operation performed within the profile to the
+    /// audit log. This has no effect unless auditing is enabled for the store.
+    fn append_audit<'q>(
+        &'q mut self,
+        operation: &'q str,
+        kind: Option<EntryKind>,
+        category: Option<&'q str>,
+        name: Option<&'q str>,
+        error: Option<&'q str>,
+    ) -> BoxFuture<'q, Result<(), Error>>;
+
+    /// Fetch records from the audit log of the profile in the order they were
+    /// appended, optionally limited to a `category` and to records appended
+    /// at or after the timestamp `since`
+    fn fetch_audit_log<'q>(
+        &'q mut self,
+        category: Option<&'q str>,
+        since: Option<i64>,
+        limit: Option<i64>,
+    ) -> BoxFuture<'q, Result<Vec<AuditRecord>, Error>>;
+
+    /// Restore a record which has been marked as deleted
+    fn restore<'q>(
+        &'q mut self,
+        kind: EntryKind,
+        category: &'q str,
+        name: &'q str,
+    ) -> BoxFuture<'q, Result<(), Error>>;
+
+    /// Permanently remove a record which has been marked as deleted
+    fn purge<'q>(
+        &'q mut self,
+        kind: EntryKind,
+        category: &'q str,
+        name: &'q str,
+    ) -> BoxFuture<'q, Result<(), Error>>;
+
+    /// Permanently remove all matching records which have been marked as deleted
+    fn purge_all<'q>(
+        &'q mut self,
+        kind: Option<EntryKind>,
+        category: Option<&'q str>,
+    ) -> BoxFuture<'q, Result<i64, Error>>;
+
+    /// Create, release or roll back to a named savepoint within the current transaction
+    fn savepoint<'q>(
+        &'q mut self,
+        name: &'q str,
+        operation: SavepointOperation,
+    ) -> BoxFuture<'q, Result<(), Error>>;
+
     /// Test the connection to the store
     fn ping(&mut self) -> BoxFuture<'_, Result<(), Error>>;
 
@@ -185,7 +683,18 @@ pub async fn copy_profile<A: Backend, B: Backend>(
     to_profile: &str,
 ) -> Result<(), Error> {
     let scan = from_backend
-        .scan(Some(from_profile.into()), None, None, None, None, None)
+        .scan(
+            Some(from_profile.into()),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            false,
+            None,
+            TagSelection::All,
+        )
         .await?;
     if let Err(e) = to_backend.create_profile(Some(to_profile.into())).await {
         if e.kind() != ErrorKind::Duplicate {
@@ -202,6 +711,57 @@ pub async fn copy_profile<A: Backend, B: Backend>(
     Ok(())
 }
 
+/// Insert the records of a profile into a profile of another store, which
+/// is created if necessary. Records are streamed from the source and written
+/// within a single transaction, so that a failure (including an existing
+/// record with the same name) leaves the target profile unchanged.
+///
+/// When `categories` is provided, only item records within those categories
+/// are copied. Key entries are always included.
+pub async fn copy_profile_entries<A: Backend, B: Backend>(
+    from_backend: &A,
+    to_backend: &B,
+    from_profile: &str,
+    to_profile: &str,
+    categories: Option<&[String]>,
+) -> Result<(), Error> {
+    let filters = match categories {
+        Some(categories) => std::iter::once((Some(EntryKind::Kms), None))
+            .chain(
+                categories
+                    .iter()
+                    .map(|cat| (Some(EntryKind::Item), Some(cat.clone()))),
+            )
+            .collect(),
+        None => vec![(None, None)],
+    };
+    if let Err(e) = to_backend.create_profile(Some(to_profile.into())).await {
+        if e.kind() != ErrorKind::Duplicate {
+            return Err(e);
+        }
+    }
+    let mut txn = to_backend.session(Some(to_profile.into()), true)?;
+    for (kind, category) in filters {
+        let scan = from_backend
+            .scan(
+                Some(from_profile.into()),
+                kind,
+                category,
+                None,
+                None,
+                None,
+                None,
+                false,
+                None,
+                TagSelection::All,
+            )
+            .await?;
+        txn.import_scan(scan).await?;
+    }
+    txn.close(true).await?;
+    Ok(())
+}
+
 /// Export an entire Store to another location
 pub async fn copy_store<'m, B: Backend, M: ManageBackend<'m>>(
     source: &B,
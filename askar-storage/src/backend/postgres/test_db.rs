@@ -77,12 +77,15 @@ impl TestDB {
 
         let mut key_cache = KeyCache::new(store_key);
         key_cache.add_profile_mut(default_profile.clone(), profile_id, profile_key);
+        let channel = opts.notify_channel();
         let inst = into_any_backend(PostgresBackend::new(
             conn_pool,
             default_profile,
             key_cache,
             opts.host,
             opts.name,
+            opts.soft_delete,
+            channel,
         ));
 
         Ok(TestDB {
@@ -1,6 +1,7 @@
-use std::collections::BTreeMap;
+use std::collections::HashMap;
 use std::fmt::{self, Debug, Formatter};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use async_stream::try_stream;
 use futures_lite::{
@@ -10,71 +11,255 @@ use futures_lite::{
 
 use sqlx::{
     pool::PoolConnection,
-    sqlite::{Sqlite, SqlitePool},
-    Acquire, Database, Error as SqlxError, Row, TransactionManager,
+    sqlite::{Sqlite, SqliteConnection, SqlitePool},
+    Acquire, Database, Error as SqlxError, Executor, Row, TransactionManager,
 };
 
 use super::{
     db_utils::{
-        decode_tags, decrypt_scan_batch, encode_profile_key, encode_tag_filter, expiry_timestamp,
-        extend_query, prepare_tags, random_profile_name, Connection, DbSession, DbSessionActive,
-        DbSessionRef, DbSessionTxn, EncScanEntry, ExtDatabase, QueryParams, QueryPrepare,
-        PAGE_SIZE,
+        and_filters, check_entry_precondition, check_kdf_upgrade, check_profile_limits,
+        decrypt_audit_records, decrypt_entry_counts, decrypt_entry_event, decrypt_fetch_rows,
+        decrypt_profile_details, decrypt_row_value, decrypt_scan_batch, decrypt_scan_page,
+        decrypt_tags, encode_order_by, encode_profile_access_key, encode_profile_key,
+        encode_profile_metadata, encode_search, encode_tag_filter, encrypt_entries_chunked,
+        encrypt_fetch_names, encrypt_tag_renames, encrypt_tag_update, encrypt_value,
+        expiry_timestamp, extend_query, multi_row_values, order_scan_by_name, prepare_tags,
+        random_profile_name, rekey_profile, verify_scan_batch, AuditPolicy, Connection, DbSession,
+        DbSessionActive, DbSessionRef, DbSessionTxn, EncAuditRow, EncEntryEvent, EncInsertEntry,
+        EncScanEntry, EncSharedValue, EncValue, EntryNotifier, ExtDatabase, ProfileLimiter,
+        QueryParams, QueryPrepare, RekeyCheckpoint, ScanTags, ScanToken, FETCH_BATCH_SIZE,
+        INSERT_BATCH_SIZE, INSERT_TAG_BATCH_SIZE, PAGE_SIZE, REKEY_BATCH_SIZE, REKEY_CONFIG_NAMES,
     },
-    Backend, BackendSession,
+    AuditRecord, Backend, BackendSession, Compression, IntegrityIssue, IntegrityReport,
+    IsolationLevel, ProfileDetails, ProfileLimits, QueryPlan, RekeyCallback, RekeyProgress,
+    RepairReport, SavepointOperation,
 };
 use crate::{
-    entry::{EncEntryTag, Entry, EntryKind, EntryOperation, EntryTag, Scan, TagFilter},
+    entry::{
+        EncEntryTag, Entry, EntryCount, EntryEvent, EntryKind, EntryOperation, EntryPrecondition,
+        EntryTag, OrderBy, Scan, Subscription, TagFilter, TagSelection,
+    },
     error::Error,
-    future::{unblock, BoxFuture},
-    protect::{EntryEncryptor, KeyCache, PassKey, ProfileId, ProfileKey, StoreKeyMethod},
+    future::{unblock, unblock_crypto, BoxFuture},
+    protect::{
+        kdf::KdfMethod, EntryEncryptor, KeyCache, PassKey, ProfileId, ProfileKey, StoreKey,
+        StoreKeyMethod,
+    },
 };
 
 mod provision;
 pub use provision::SqliteStoreOptions;
 
+/// The approximate number of virtual machine instructions executed between
+/// checks of the statement timeout
+const PROGRESS_HANDLER_OPS: i32 = 1000;
+
+const PROFILE_DETAILS_QUERY: &str = "SELECT p.id, p.name,
+    CAST(STRFTIME('%s', p.created) AS INTEGER), p.metadata,
+    (SELECT COUNT(*) FROM items i WHERE i.profile_id = p.id AND i.kind = 2
+        AND (i.expiry IS NULL OR i.expiry > DATETIME('now')) AND i.deleted IS NULL),
+    (SELECT COUNT(*) FROM items i WHERE i.profile_id = p.id AND i.kind = 1
+        AND (i.expiry IS NULL OR i.expiry > DATETIME('now')) AND i.deleted IS NULL),
+    p.max_entries, p.max_value_bytes
+    FROM profiles p ORDER BY p.id";
+const PROFILE_LIMITS_QUERY: &str = "SELECT p.max_entries, p.max_value_bytes,
+    CASE WHEN p.max_entries IS NULL THEN 0
+        ELSE (SELECT COUNT(*) FROM items i WHERE i.profile_id = p.id) END,
+    CASE WHEN p.max_value_bytes IS NULL THEN 0
+        ELSE (SELECT COALESCE(SUM(LENGTH(i.value)), 0) FROM items i
+            WHERE i.profile_id = p.id) END
+    FROM profiles p WHERE p.id = ?1";
 const CONFIG_FETCH_QUERY: &str = "SELECT value FROM config WHERE name = ?1";
 const CONFIG_UPDATE_QUERY: &str = "INSERT OR REPLACE INTO config (name, value) VALUES (?1, ?2)";
 const COUNT_QUERY: &str = "SELECT COUNT(*) FROM items i
     WHERE profile_id = ?1
     AND (kind = ?2 OR ?2 IS NULL)
     AND (category = ?3 OR ?3 IS NULL)
-    AND (expiry IS NULL OR expiry > DATETIME('now'))";
+    AND (expiry IS NULL OR expiry > DATETIME('now'))
+    AND deleted IS NULL";
+const COUNT_GROUPED_QUERY: &str = "SELECT i.category,
+    (SELECT it.value FROM items_tags it WHERE it.item_id = i.id
+        AND it.name = ?4 AND it.plaintext = ?5 ORDER BY it.value LIMIT 1) AS tag_value,
+    COUNT(*) FROM items i
+    WHERE profile_id = ?1
+    AND (kind = ?2 OR ?2 IS NULL)
+    AND (category = ?3 OR ?3 IS NULL)
+    AND (expiry IS NULL OR expiry > DATETIME('now'))
+    AND deleted IS NULL";
 const DELETE_QUERY: &str = "DELETE FROM items
-    WHERE profile_id = ?1 AND kind = ?2 AND category = ?3 AND name = ?4";
+    WHERE profile_id = ?1 AND kind = ?2 AND category = ?3 AND name = ?4
+    AND deleted IS NULL";
+const SOFT_DELETE_QUERY: &str = "UPDATE items SET deleted = DATETIME('now')
+    WHERE profile_id = ?1 AND kind = ?2 AND category = ?3 AND name = ?4
+    AND deleted IS NULL";
+const AUDIT_INSERT_QUERY: &str = "INSERT INTO audit_log
+    (profile_id, created, operation, kind, category, name, error)
+    VALUES (?1, DATETIME('now'), ?2, ?3, ?4, ?5, ?6)";
+const AUDIT_PURGE_QUERY: &str = "DELETE FROM audit_log
+    WHERE created < DATETIME('now', '-' || ?1 || ' seconds')";
+const AUDIT_FETCH_QUERY: &str = "SELECT id, CAST(STRFTIME('%s', created) AS INTEGER),
+    operation, kind, category, name, error FROM audit_log
+    WHERE profile_id = ?1 AND (category = ?2 OR ?2 IS NULL)
+    AND (created >= DATETIME(?3, 'unixepoch') OR ?3 IS NULL)
+    ORDER BY id LIMIT ?4";
+const VERIFY_QUERY: &str = "SELECT i.id, i.kind, i.category, i.name, i.value,
+    (SELECT GROUP_CONCAT(it.plaintext || ':' || HEX(it.name) || ':' || HEX(it.value))
+        FROM items_tags it WHERE it.item_id = i.id) AS tags, i.version, i.compression,
+    (SELECT s.value FROM items_shared s WHERE s.id = i.shared_id) AS shared, i.shared_id
+    FROM items i WHERE i.profile_id = ?1 AND i.id > ?2 AND i.deleted IS NULL
+    ORDER BY i.id LIMIT ?3";
+const QUARANTINE_QUERY: &str = "UPDATE items SET deleted = DATETIME('now')
+    WHERE id = ?1 AND deleted IS NULL";
+const SHARED_REFS_COUNT_QUERY: &str = "SELECT COUNT(*) FROM items_shared s
+    WHERE s.refs != (SELECT COUNT(*) FROM items i WHERE i.shared_id = s.id)";
+const SHARED_REFS_UPDATE_QUERY: &str = "UPDATE items_shared
+    SET refs = (SELECT COUNT(*) FROM items i WHERE i.shared_id = items_shared.id)
+    WHERE refs != (SELECT COUNT(*) FROM items i WHERE i.shared_id = items_shared.id)";
+const DEFAULT_PROFILE_CHECK_QUERY: &str = "SELECT (SELECT name FROM profiles ORDER BY id LIMIT 1)
+    FROM config c WHERE c.name = 'default_profile'
+    AND NOT EXISTS (SELECT 1 FROM profiles p WHERE p.name = c.value)";
+const RESTORE_QUERY: &str = "UPDATE items SET deleted = NULL, version = version + 1
+    WHERE profile_id = ?1 AND kind = ?2 AND category = ?3 AND name = ?4
+    AND deleted IS NOT NULL";
+const PURGE_QUERY: &str = "DELETE FROM items
+    WHERE profile_id = ?1 AND kind = ?2 AND category = ?3 AND name = ?4
+    AND deleted IS NOT NULL";
+const PURGE_ALL_QUERY: &str = "DELETE FROM items
+    WHERE profile_id = ?1
+    AND (kind = ?2 OR ?2 IS NULL)
+    AND (category = ?3 OR ?3 IS NULL)
+    AND deleted IS NOT NULL";
 const FETCH_QUERY: &str = "SELECT i.id, i.value,
     (SELECT GROUP_CONCAT(it.plaintext || ':' || HEX(it.name) || ':' || HEX(it.value))
-        FROM items_tags it WHERE it.item_id = i.id) AS tags
+        FROM items_tags it WHERE it.item_id = i.id) AS tags, i.version, i.compression,
+    (SELECT s.value FROM items_shared s WHERE s.id = i.shared_id) AS shared
     FROM items i WHERE i.profile_id = ?1 AND i.kind = ?2
     AND i.category = ?3 AND i.name = ?4
-    AND (i.expiry IS NULL OR i.expiry > DATETIME('now'))";
+    AND (i.expiry IS NULL OR i.expiry > DATETIME('now'))
+    AND i.deleted IS NULL";
+const FETCH_MANY_QUERY: &str = "SELECT i.name, i.value,
+    (SELECT GROUP_CONCAT(it.plaintext || ':' || HEX(it.name) || ':' || HEX(it.value))
+        FROM items_tags it WHERE it.item_id = i.id) AS tags, i.version, i.compression,
+    (SELECT s.value FROM items_shared s WHERE s.id = i.shared_id) AS shared
+    FROM items i WHERE i.profile_id = ?1 AND i.kind = ?2 AND i.category = ?3
+    AND (i.expiry IS NULL OR i.expiry > DATETIME('now'))
+    AND i.deleted IS NULL AND i.name IN";
 const INSERT_QUERY: &str =
-    "INSERT OR IGNORE INTO items (profile_id, kind, category, name, value, expiry)
-    VALUES (?1, ?2, ?3, ?4, ?5, ?6)";
-const UPDATE_QUERY: &str = "UPDATE items SET value=?5, expiry=?6 WHERE profile_id=?1 AND kind=?2
-    AND category=?3 AND name=?4 RETURNING id";
+    "INSERT OR IGNORE INTO items (profile_id, kind, category, name, value, expiry, compression,
+    shared_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)";
+const INSERT_MANY_QUERY: &str = "INSERT OR IGNORE INTO items
+    (profile_id, kind, category, name, value, expiry, compression, shared_id) VALUES";
+const SHARED_INSERT_QUERY: &str = "INSERT INTO items_shared (hash, value) VALUES (?1, ?2)
+    ON CONFLICT (hash) DO UPDATE SET hash = excluded.hash RETURNING id";
+const SHARED_RELEASE_QUERY: &str = "DELETE FROM items_shared WHERE id = ?1 AND refs <= 0";
+const PURGE_MANY_QUERY: &str = "DELETE FROM items
+    WHERE profile_id = ?1 AND deleted IS NOT NULL AND (kind, category, name) IN (VALUES";
+const UPDATE_QUERY: &str = "UPDATE items SET value=?5, expiry=?6, compression=?7,
+    shared_id=?8, version = version + 1
+    WHERE profile_id=?1 AND kind=?2 AND category=?3 AND name=?4
+    AND deleted IS NULL RETURNING id";
 const SCAN_QUERY: &str = "SELECT i.id, i.kind, i.category, i.name, i.value,
     (SELECT GROUP_CONCAT(it.plaintext || ':' || HEX(it.name) || ':' || HEX(it.value))
-        FROM items_tags it WHERE it.item_id = i.id) AS tags
+        FROM items_tags it WHERE it.item_id = i.id) AS tags, i.version, i.compression,
+    (SELECT s.value FROM items_shared s WHERE s.id = i.shared_id) AS shared
     FROM items i WHERE i.profile_id = ?1
     AND (i.kind = ?2 OR ?2 IS NULL)
     AND (i.category = ?3 OR ?3 IS NULL)
-    AND (i.expiry IS NULL OR i.expiry > DATETIME('now'))";
+    AND (i.expiry IS NULL OR i.expiry > DATETIME('now'))
+    AND i.deleted IS NULL";
+const SEARCH_QUERY: &str = "SELECT i.id, i.kind, i.category, i.name, i.value,
+    (SELECT GROUP_CONCAT(it.plaintext || ':' || HEX(it.name) || ':' || HEX(it.value))
+        FROM items_tags it WHERE it.item_id = i.id) AS tags, i.version, i.compression,
+    (SELECT s.value FROM items_shared s WHERE s.id = i.shared_id) AS shared
+    FROM items i
+    JOIN (SELECT it.item_id, MIN(items_tags_fts.rank) AS rank FROM items_tags_fts
+        JOIN items_tags it ON it.id = items_tags_fts.rowid
+        WHERE items_tags_fts MATCH ?4 GROUP BY it.item_id) m ON m.item_id = i.id
+    WHERE i.profile_id = ?1
+    AND (i.kind = ?2 OR ?2 IS NULL)
+    AND (i.category = ?3 OR ?3 IS NULL)
+    AND (i.expiry IS NULL OR i.expiry > DATETIME('now'))
+    AND i.deleted IS NULL";
+const SEARCH_ORDER: &str = "m.rank, i.id";
 const DELETE_ALL_QUERY: &str = "DELETE FROM items AS i
     WHERE i.profile_id = ?1
     AND (i.kind = ?2 OR ?2 IS NULL)
-    AND (i.category = ?3 OR ?3 IS NULL)";
+    AND (i.category = ?3 OR ?3 IS NULL)
+    AND i.deleted IS NULL";
+const SOFT_DELETE_ALL_QUERY: &str = "UPDATE items AS i SET deleted = DATETIME('now')
+    WHERE i.profile_id = ?1
+    AND (i.kind = ?2 OR ?2 IS NULL)
+    AND (i.category = ?3 OR ?3 IS NULL)
+    AND i.deleted IS NULL";
 const TAG_INSERT_QUERY: &str = "INSERT INTO items_tags
     (item_id, name, value, plaintext) VALUES (?1, ?2, ?3, ?4)";
+const TAG_INSERT_MANY_QUERY: &str = "INSERT INTO items_tags
+    (item_id, name, value, plaintext) VALUES";
 const TAG_DELETE_QUERY: &str = "DELETE FROM items_tags
     WHERE item_id=?1";
+const MOVE_EXISTS_QUERY: &str = "SELECT COUNT(*) FROM items a
+    JOIN items b ON b.profile_id = a.profile_id AND b.kind = a.kind AND b.name = a.name
+    WHERE a.profile_id = ?1 AND a.kind = ?2 AND a.category = ?3 AND b.category = ?4";
+const MOVE_FETCH_QUERY: &str = "SELECT id, name, value FROM items
+    WHERE profile_id = ?1 AND kind = ?2 AND category = ?3
+    ORDER BY id LIMIT ?4";
+const MOVE_QUERY: &str = "UPDATE items SET category = ?2, value = ?3, version = version + 1
+    WHERE id = ?1";
+const MOVE_TAG_RENAME_QUERY: &str = "UPDATE items_tags SET name = ?5
+    WHERE name = ?4 AND item_id IN (SELECT id FROM items
+        WHERE profile_id = ?1 AND kind = ?2 AND category = ?3)";
+const RENAME_FETCH_QUERY: &str = "SELECT id, value FROM items
+    WHERE profile_id = ?1 AND kind = ?2 AND category = ?3 AND name = ?4
+    AND (expiry IS NULL OR expiry > DATETIME('now'))
+    AND deleted IS NULL";
+const RENAME_EXISTS_QUERY: &str = "SELECT COUNT(*) FROM items
+    WHERE profile_id = ?1 AND kind = ?2 AND category = ?3 AND name = ?4";
+const RENAME_QUERY: &str = "UPDATE items SET name = ?2, value = ?3, version = version + 1
+    WHERE id = ?1";
+const TAG_DELETE_NAME_QUERY: &str = "DELETE FROM items_tags
+    WHERE item_id=?1 AND name=?2";
+const TAG_UPDATE_ITEM_QUERY: &str = "UPDATE items SET version = version + 1
+    WHERE profile_id=?1 AND kind=?2 AND category=?3 AND name=?4
+    AND deleted IS NULL RETURNING id";
+const CHUNK_ITEM_QUERY: &str = "SELECT id FROM items
+    WHERE profile_id = ?1 AND kind = ?2 AND category = ?3 AND name = ?4
+    AND (expiry IS NULL OR expiry > DATETIME('now'))
+    AND deleted IS NULL";
+const CHUNK_CLEAR_QUERY: &str = "DELETE FROM items_chunks WHERE item_id = ?1";
+const CHUNK_INSERT_QUERY: &str = "INSERT OR REPLACE INTO items_chunks
+    (item_id, idx, value) VALUES (?1, ?2, ?3)";
+const CHUNK_FETCH_QUERY: &str = "SELECT c.value FROM items_chunks c
+    JOIN items i ON i.id = c.item_id
+    WHERE i.profile_id = ?1 AND i.kind = ?2 AND i.category = ?3 AND i.name = ?4
+    AND (i.expiry IS NULL OR i.expiry > DATETIME('now'))
+    AND i.deleted IS NULL AND c.idx >= ?5
+    ORDER BY c.idx LIMIT ?6";
+
+/// Statements prepared in advance on each connection of the pool, when the
+/// store is opened with pre-warming enabled
+const PREPARED_QUERIES: &[&str] = &[
+    FETCH_QUERY,
+    INSERT_QUERY,
+    UPDATE_QUERY,
+    DELETE_QUERY,
+    TAG_INSERT_QUERY,
+    TAG_DELETE_QUERY,
+];
 
 /// A Sqlite database store
 pub struct SqliteBackend {
     conn_pool: SqlitePool,
-    active_profile: String,
+    active_profile: Mutex<String>,
     key_cache: Arc<KeyCache>,
     path: String,
+    soft_delete: bool,
+    compression: Compression,
+    dedup_threshold: Option<usize>,
+    audit: Option<AuditPolicy>,
+    session_idle_timeout: Option<Duration>,
+    statement_timeout: Option<Duration>,
+    profile_limiter: Option<Arc<ProfileLimiter>>,
+    notifier: EntryNotifier,
 }
 
 impl SqliteBackend {
@@ -83,21 +268,78 @@ impl SqliteBackend {
         active_profile: String,
         key_cache: KeyCache,
         path: String,
+        soft_delete: bool,
     ) -> Self {
         Self {
             conn_pool,
-            active_profile,
+            active_profile: Mutex::new(active_profile),
             key_cache: Arc::new(key_cache),
             path,
+            soft_delete,
+            compression: Compression::None,
+            dedup_threshold: None,
+            audit: None,
+            session_idle_timeout: None,
+            statement_timeout: None,
+            profile_limiter: None,
+            notifier: EntryNotifier::new("askar"),
         }
     }
+
+    /// Set the time after which an unused session releases its connection,
+    /// expiring any open transaction
+    pub(crate) fn with_session_idle_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.session_idle_timeout = timeout;
+        self
+    }
+
+    /// Set the maximum time spent executing statements for each operation
+    pub(crate) fn with_statement_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.statement_timeout = timeout;
+        self
+    }
+
+    /// Set the maximum number of concurrent sessions holding a connection
+    /// for each profile
+    pub(crate) fn with_max_profile_sessions(mut self, max_sessions: Option<usize>) -> Self {
+        self.profile_limiter = max_sessions.map(|max| Arc::new(ProfileLimiter::new(max)));
+        self
+    }
+
+    /// Set the compression applied to record values written by sessions
+    pub(crate) fn with_compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Set the minimum length of record values stored once and shared
+    /// between entries with identical values
+    pub(crate) fn with_dedup_threshold(mut self, dedup_threshold: Option<usize>) -> Self {
+        self.dedup_threshold = dedup_threshold;
+        self
+    }
+
+    /// Enable the audit log for operations performed by sessions
+    pub(crate) fn with_audit(mut self, audit: Option<AuditPolicy>) -> Self {
+        self.audit = audit;
+        self
+    }
 }
 
 impl Debug for SqliteBackend {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         f.debug_struct("SqliteStore")
-            .field("active_profile", &self.active_profile)
+            .field("active_profile", &*self.active_profile.lock().unwrap())
             .field("path", &self.path)
+            .field("soft_delete", &self.soft_delete)
+            .field("compression", &self.compression)
+            .field("dedup_threshold", &self.dedup_threshold)
+            .field("audit", &self.audit)
+            .field("session_idle_timeout", &self.session_idle_timeout)
+            .field(
+                "max_profile_sessions",
+                &self.profile_limiter.as_ref().map(|l| l.max_sessions()),
+            )
             .finish()
     }
 }
@@ -112,7 +354,7 @@ impl Backend for SqliteBackend {
     fn create_profile(&self, name: Option<String>) -> BoxFuture<'_, Result<String, Error>> {
         let name = name.unwrap_or_else(random_profile_name);
         Box::pin(async move {
-            let store_key = self.key_cache.store_key.clone();
+            let store_key = self.key_cache.store_key()?;
             let (profile_key, enc_key) = unblock(move || {
                 let profile_key = ProfileKey::new()?;
                 let enc_key = encode_profile_key(&profile_key, &store_key)?;
@@ -120,12 +362,14 @@ impl Backend for SqliteBackend {
             })
             .await?;
             let mut conn = self.conn_pool.acquire().await?;
-            let done =
-                sqlx::query("INSERT OR IGNORE INTO profiles (name, profile_key) VALUES (?1, ?2)")
-                    .bind(&name)
-                    .bind(enc_key)
-                    .execute(conn.as_mut())
-                    .await?;
+            let done = sqlx::query(
+                "INSERT OR IGNORE INTO profiles (name, profile_key, created)
+                    VALUES (?1, ?2, DATETIME('now'))",
+            )
+            .bind(&name)
+            .bind(enc_key)
+            .execute(conn.as_mut())
+            .await?;
             conn.return_to_pool().await;
             if done.rows_affected() == 0 {
                 return Err(err_msg!(Duplicate, "Duplicate profile name"));
@@ -142,7 +386,7 @@ impl Backend for SqliteBackend {
     }
 
     fn get_active_profile(&self) -> String {
-        self.active_profile.clone()
+        self.active_profile.lock().unwrap().clone()
     }
 
     fn get_default_profile(&self) -> BoxFuture<'_, Result<String, Error>> {
@@ -160,6 +404,7 @@ impl Backend for SqliteBackend {
 
     fn set_default_profile(&self, profile: String) -> BoxFuture<'_, Result<(), Error>> {
         Box::pin(async move {
+            self.key_cache.store_key()?;
             let mut conn = self.conn_pool.acquire().await?;
             sqlx::query(CONFIG_UPDATE_QUERY)
                 .bind("default_profile")
@@ -174,6 +419,7 @@ impl Backend for SqliteBackend {
 
     fn list_profiles(&self) -> BoxFuture<'_, Result<Vec<String>, Error>> {
         Box::pin(async move {
+            self.key_cache.store_key()?;
             let mut conn = self.conn_pool.acquire().await?;
             let rows = sqlx::query("SELECT name FROM profiles")
                 .fetch_all(conn.as_mut())
@@ -185,8 +431,185 @@ impl Backend for SqliteBackend {
         })
     }
 
+    fn list_profile_details(&self) -> BoxFuture<'_, Result<Vec<ProfileDetails>, Error>> {
+        Box::pin(async move {
+            self.key_cache.store_key()?;
+            let mut conn = self.conn_pool.acquire().await?;
+            let rows = sqlx::query(PROFILE_DETAILS_QUERY)
+                .fetch_all(conn.as_mut())
+                .await
+                .map_err(err_map!(Backend, "Error fetching profile details"))?;
+            conn.return_to_pool().await;
+            let details = rows
+                .into_iter()
+                .map(|row| {
+                    Ok(ProfileDetails {
+                        id: row.try_get(0)?,
+                        name: row.try_get(1)?,
+                        created: row.try_get(2)?,
+                        metadata: row.try_get(3)?,
+                        item_count: row.try_get(4)?,
+                        key_count: row.try_get(5)?,
+                        limits: ProfileLimits {
+                            max_entries: row.try_get(6)?,
+                            max_value_bytes: row.try_get(7)?,
+                        },
+                    })
+                })
+                .collect::<Result<Vec<_>, SqlxError>>()?;
+            let store_key = self.key_cache.store_key()?;
+            unblock(move || decrypt_profile_details(details, &store_key)).await
+        })
+    }
+
+    fn set_profile_metadata(
+        &self,
+        name: String,
+        metadata: Option<Vec<u8>>,
+    ) -> BoxFuture<'_, Result<(), Error>> {
+        Box::pin(async move {
+            self.key_cache.store_key()?;
+            let enc_metadata = match metadata {
+                Some(metadata) => {
+                    let store_key = self.key_cache.store_key()?;
+                    Some(unblock(move || encode_profile_metadata(&metadata, &store_key)).await?)
+                }
+                None => None,
+            };
+            let mut conn = self.conn_pool.acquire().await?;
+            let done = sqlx::query("UPDATE profiles SET metadata=?1 WHERE name=?2")
+                .bind(enc_metadata)
+                .bind(&name)
+                .execute(conn.as_mut())
+                .await
+                .map_err(err_map!(Backend, "Error updating profile metadata"))?;
+            conn.return_to_pool().await;
+            if done.rows_affected() == 0 {
+                return Err(err_msg!(NotFound, "Profile not found"));
+            }
+            Ok(())
+        })
+    }
+
+    fn set_profile_limits(
+        &self,
+        name: String,
+        limits: ProfileLimits,
+    ) -> BoxFuture<'_, Result<(), Error>> {
+        Box::pin(async move {
+            self.key_cache.store_key()?;
+            let mut conn = self.conn_pool.acquire().await?;
+            let done =
+                sqlx::query("UPDATE profiles SET max_entries=?1, max_value_bytes=?2 WHERE name=?3")
+                    .bind(limits.max_entries)
+                    .bind(limits.max_value_bytes)
+                    .bind(&name)
+                    .execute(conn.as_mut())
+                    .await
+                    .map_err(err_map!(Backend, "Error updating profile limits"))?;
+            conn.return_to_pool().await;
+            if done.rows_affected() == 0 {
+                return Err(err_msg!(NotFound, "Profile not found"));
+            }
+            Ok(())
+        })
+    }
+
+    fn set_profile_access_key(
+        &self,
+        name: String,
+        method: StoreKeyMethod,
+        pass_key: PassKey<'_>,
+    ) -> BoxFuture<'_, Result<(), Error>> {
+        let pass_key = pass_key.into_owned();
+        Box::pin(async move {
+            self.key_cache.store_key()?;
+            let mut conn = self.conn_pool.acquire().await?;
+            let enc_key: Option<Vec<u8>> =
+                sqlx::query_scalar("SELECT profile_key FROM profiles WHERE name=?1")
+                    .bind(&name)
+                    .fetch_optional(conn.as_mut())
+                    .await?
+                    .flatten();
+            let enc_key = enc_key.ok_or_else(|| err_msg!(NotFound, "Profile not found"))?;
+            let profile_key = self.key_cache.load_key(enc_key).await?;
+            let (access_key, access_key_ref) =
+                unblock_crypto(move || encode_profile_access_key(&profile_key, method, pass_key))
+                    .await?;
+            sqlx::query("UPDATE profiles SET access_key=?1, access_key_ref=?2 WHERE name=?3")
+                .bind(access_key)
+                .bind(access_key_ref)
+                .bind(&name)
+                .execute(conn.as_mut())
+                .await
+                .map_err(err_map!(Backend, "Error updating profile access key"))?;
+            conn.return_to_pool().await;
+            Ok(())
+        })
+    }
+
+    fn remove_profile_access_key(&self, name: String) -> BoxFuture<'_, Result<(), Error>> {
+        Box::pin(async move {
+            self.key_cache.store_key()?;
+            let mut conn = self.conn_pool.acquire().await?;
+            let done = sqlx::query(
+                "UPDATE profiles SET access_key=NULL, access_key_ref=NULL WHERE name=?1",
+            )
+            .bind(&name)
+            .execute(conn.as_mut())
+            .await
+            .map_err(err_map!(Backend, "Error removing profile access key"))?;
+            conn.return_to_pool().await;
+            if done.rows_affected() == 0 {
+                return Err(err_msg!(NotFound, "Profile not found"));
+            }
+            Ok(())
+        })
+    }
+
+    fn rename_profile(&self, name: String, new_name: String) -> BoxFuture<'_, Result<(), Error>> {
+        Box::pin(async move {
+            self.key_cache.store_key()?;
+            let mut conn = self.conn_pool.acquire().await?;
+            let mut txn = conn.begin().await?;
+            let found: Option<i64> = sqlx::query_scalar("SELECT id FROM profiles WHERE name=?1")
+                .bind(&new_name)
+                .fetch_optional(txn.as_mut())
+                .await?;
+            if found.is_some() {
+                return Err(err_msg!(Duplicate, "Duplicate profile name"));
+            }
+            if sqlx::query("UPDATE profiles SET name=?1 WHERE name=?2")
+                .bind(&new_name)
+                .bind(&name)
+                .execute(txn.as_mut())
+                .await
+                .map_err(err_map!(Backend, "Error renaming profile"))?
+                .rows_affected()
+                == 0
+            {
+                return Err(err_msg!(NotFound, "Profile not found"));
+            }
+            sqlx::query("UPDATE config SET value=?1 WHERE name='default_profile' AND value=?2")
+                .bind(&new_name)
+                .bind(&name)
+                .execute(txn.as_mut())
+                .await
+                .map_err(err_map!(Backend, "Error updating default profile name"))?;
+            txn.commit().await?;
+            conn.return_to_pool().await;
+            self.key_cache.rename_profile(&name, new_name.clone()).await;
+            let mut active = self.active_profile.lock().unwrap();
+            if *active == name {
+                *active = new_name;
+            }
+            Ok(())
+        })
+    }
+
     fn remove_profile(&self, name: String) -> BoxFuture<'_, Result<bool, Error>> {
         Box::pin(async move {
+            self.key_cache.store_key()?;
             let mut conn = self.conn_pool.acquire().await?;
             let ret = sqlx::query("DELETE FROM profiles WHERE name=?")
                 .bind(&name)
@@ -200,60 +623,93 @@ impl Backend for SqliteBackend {
         })
     }
 
-    fn rekey(
+    fn rekey_with_progress(
         &mut self,
         method: StoreKeyMethod,
         pass_key: PassKey<'_>,
+        progress: Option<RekeyCallback>,
     ) -> BoxFuture<'_, Result<(), Error>> {
         let pass_key = pass_key.into_owned();
         Box::pin(async move {
-            let (store_key, store_key_ref) = unblock(move || method.resolve(pass_key)).await?;
-            let store_key = Arc::new(store_key);
+            let prev_key = self.key_cache.store_key()?;
             let mut conn = self.conn_pool.acquire().await?;
-            let mut txn = conn.begin().await?;
-            let mut rows = sqlx::query("SELECT id, profile_key FROM profiles").fetch(txn.as_mut());
-            let mut upd_keys = BTreeMap::<ProfileId, Vec<u8>>::new();
-            while let Some(row) = rows.next().await {
-                let row = row?;
-                let pid = row.try_get(0)?;
-                let enc_key = row.try_get(1)?;
-                let profile_key = self.key_cache.load_key(enc_key).await?;
-                let upd_key = unblock({
-                    let store_key = store_key.clone();
-                    move || encode_profile_key(&profile_key, &store_key)
+            let checkpoint = load_rekey_checkpoint(&mut conn).await?;
+            conn.return_to_pool().await;
+            let (store_key, checkpoint) = if let Some(checkpoint) = checkpoint {
+                unblock_crypto({
+                    let prev_key = prev_key.clone();
+                    move || {
+                        let store_key = checkpoint.resume(&method, pass_key, &prev_key)?;
+                        Result::<_, Error>::Ok((store_key, checkpoint))
+                    }
+                })
+                .await?
+            } else {
+                let (store_key, checkpoint) = unblock_crypto({
+                    let prev_key = prev_key.clone();
+                    move || {
+                        let (store_key, store_key_ref) = method.resolve(pass_key)?;
+                        let checkpoint =
+                            RekeyCheckpoint::new(store_key_ref.into_uri(), &prev_key, &store_key)?;
+                        Result::<_, Error>::Ok((store_key, checkpoint))
+                    }
                 })
                 .await?;
-                upd_keys.insert(pid, upd_key);
-            }
-            drop(rows);
-            for (pid, key) in upd_keys {
-                if sqlx::query("UPDATE profiles SET profile_key=?1 WHERE id=?2")
-                    .bind(key)
-                    .bind(pid)
-                    .execute(txn.as_mut())
-                    .await?
-                    .rows_affected()
-                    != 1
-                {
-                    return Err(err_msg!(Backend, "Error updating profile key"));
+                let mut conn = self.conn_pool.acquire().await?;
+                let mut txn = conn.begin().await?;
+                for (name, value) in checkpoint.config_entries() {
+                    sqlx::query(CONFIG_UPDATE_QUERY)
+                        .bind(name)
+                        .bind(value)
+                        .execute(txn.as_mut())
+                        .await?;
                 }
-            }
-            if sqlx::query("UPDATE config SET value=?1 WHERE name='key'")
-                .bind(store_key_ref.into_uri())
-                .execute(txn.as_mut())
-                .await?
-                .rows_affected()
-                != 1
-            {
-                return Err(err_msg!(Backend, "Error updating store key"));
-            }
-            txn.commit().await?;
-            conn.return_to_pool().await;
+                txn.commit().await?;
+                conn.return_to_pool().await;
+                (store_key, checkpoint)
+            };
+            let store_key = Arc::new(store_key);
+            perform_rekey(
+                &self.conn_pool,
+                prev_key,
+                store_key.clone(),
+                checkpoint,
+                progress,
+            )
+            .await?;
             self.key_cache = Arc::new(KeyCache::new(store_key));
             Ok(())
         })
     }
 
+    fn upgrade_kdf(
+        &mut self,
+        minimum: KdfMethod,
+        pass_key: PassKey<'_>,
+    ) -> BoxFuture<'_, Result<bool, Error>> {
+        let pass_key = pass_key.into_owned();
+        Box::pin(async move {
+            let mut conn = self.conn_pool.acquire().await?;
+            let key_ref: Option<String> =
+                sqlx::query_scalar("SELECT value FROM config WHERE name='key'")
+                    .fetch_optional(conn.as_mut())
+                    .await?;
+            conn.return_to_pool().await;
+            let key_ref = key_ref.ok_or_else(|| err_msg!(Unsupported, "Store key not found"))?;
+            let store_key = self.key_cache.store_key()?;
+            let upgrade = unblock_crypto({
+                let pass_key = pass_key.clone();
+                move || check_kdf_upgrade(&key_ref, &minimum, pass_key, &store_key)
+            })
+            .await?;
+            if upgrade {
+                self.rekey(StoreKeyMethod::DeriveKey(minimum), pass_key)
+                    .await?;
+            }
+            Ok(upgrade)
+        })
+    }
+
     fn scan(
         &self,
         profile: Option<String>,
@@ -262,11 +718,27 @@ impl Backend for SqliteBackend {
         tag_filter: Option<TagFilter>,
         offset: Option<i64>,
         limit: Option<i64>,
+        order_by: Option<OrderBy>,
+        descending: bool,
+        after: Option<String>,
+        tags: TagSelection,
     ) -> BoxFuture<'_, Result<Scan<'static, Entry>, Error>> {
         Box::pin(async move {
             let session = self.session(profile, false)?;
             let mut active = session.owned_ref();
             let (profile_id, key) = acquire_key(&mut active).await?;
+            let after = after
+                .map(|token| ScanToken::parse(&token, order_by.as_ref(), &key))
+                .transpose()?;
+            let tags = Arc::new(
+                unblock({
+                    let key = key.clone();
+                    let order_by = order_by.clone();
+                    move || ScanTags::new(tags, order_by.as_ref(), &key)
+                })
+                .await?,
+            );
+            let by_name = order_by == Some(OrderBy::Name);
             let scan = perform_scan(
                 active,
                 profile_id,
@@ -274,15 +746,58 @@ impl Backend for SqliteBackend {
                 kind,
                 category.clone(),
                 tag_filter,
-                offset,
-                limit,
+                if by_name { None } else { offset },
+                if by_name { None } else { limit },
+                order_by.clone(),
+                descending,
+                if by_name { None } else { after.clone() },
             );
-            let stream = scan.then(move |enc_rows| {
-                let category = category.clone();
+            let stream = scan.then({
                 let key = key.clone();
-                unblock(move || decrypt_scan_batch(category, enc_rows?, &key))
+                move |enc_rows| {
+                    let category = category.clone();
+                    let key = key.clone();
+                    let order_by = order_by.clone();
+                    let tags = tags.clone();
+                    async move { decrypt_scan_page(category, enc_rows?, key, order_by, tags).await }
+                }
             });
-            Ok(Scan::new(stream, PAGE_SIZE))
+            if by_name {
+                let stream = order_scan_by_name(stream, descending, after, offset, limit, key);
+                Ok(Scan::new(stream, PAGE_SIZE))
+            } else {
+                Ok(Scan::new(stream, PAGE_SIZE))
+            }
+        })
+    }
+
+    fn subscribe(
+        &self,
+        profile: Option<String>,
+        kind: Option<EntryKind>,
+        category: Option<String>,
+    ) -> BoxFuture<'_, Result<Subscription<'static>, Error>> {
+        Box::pin(async move {
+            let mut session = self.session(profile, false)?;
+            let (profile_id, key) = acquire_key(&mut session).await?;
+            session.close(false).await?;
+            drop(session);
+            let enc_category = unblock({
+                let key = key.clone();
+                let category = category.map(|c| ProfileKey::prepare_input(c.as_bytes()));
+                move || category.map(|c| key.encrypt_entry_category(c)).transpose()
+            })
+            .await?;
+            let mut receiver = self.notifier.subscribe();
+            let stream = try_stream! {
+                while let Some(event) = receiver.recv().await {
+                    if event.matches(profile_id, kind, enc_category.as_deref()) {
+                        let key = key.clone();
+                        yield unblock(move || decrypt_entry_event(event, &key)).await?;
+                    }
+                }
+            };
+            Ok(Subscription::new(stream))
         })
     }
 
@@ -290,13 +805,244 @@ impl Backend for SqliteBackend {
         Ok(DbSession::new(
             self.conn_pool.clone(),
             self.key_cache.clone(),
-            profile.unwrap_or_else(|| self.active_profile.clone()),
+            profile.unwrap_or_else(|| self.get_active_profile()),
             transaction,
-        ))
+            self.soft_delete,
+            self.session_idle_timeout,
+            self.notifier.clone(),
+        )
+        .with_compression(self.compression)
+        .with_dedup_threshold(self.dedup_threshold)
+        .with_audit(self.audit)
+        .with_statement_timeout(self.statement_timeout)
+        .with_profile_limiter(self.profile_limiter.clone()))
+    }
+
+    fn transaction(
+        &self,
+        profile: Option<String>,
+        isolation: IsolationLevel,
+    ) -> Result<Self::Session, Error> {
+        Ok(self.session(profile, true)?.with_isolation(isolation))
+    }
+
+    fn snapshot<'q>(&'q self, target: &'q str) -> BoxFuture<'q, Result<(), Error>> {
+        Box::pin(async move {
+            self.key_cache.store_key()?;
+            let target = SqliteStoreOptions::new(target)?;
+            if target.in_memory {
+                return Err(err_msg!(
+                    Input,
+                    "Cannot write a snapshot to an in-memory database"
+                ));
+            }
+            if std::path::Path::new(&target.path).exists() {
+                return Err(err_msg!(Duplicate, "Snapshot target already exists"));
+            }
+            // VACUUM INTO reads the database within a single transaction,
+            // producing a consistent copy while other connections are active
+            let mut conn = self.conn_pool.acquire().await?;
+            sqlx::query("VACUUM INTO ?1")
+                .bind(target.path)
+                .execute(conn.as_mut())
+                .await
+                .map_err(err_map!(Backend, "Error writing store snapshot"))?;
+            conn.return_to_pool().await;
+            Ok(())
+        })
+    }
+
+    fn verify_integrity(&self, quarantine: bool) -> BoxFuture<'_, Result<IntegrityReport, Error>> {
+        Box::pin(async move {
+            self.key_cache.store_key()?;
+            let mut conn = self.conn_pool.acquire().await?;
+            let profiles: Vec<(ProfileId, String, Vec<u8>)> =
+                sqlx::query_as("SELECT id, name, profile_key FROM profiles ORDER BY id")
+                    .fetch_all(conn.as_mut())
+                    .await
+                    .map_err(err_map!(Backend, "Error fetching profiles"))?;
+            let mut report = IntegrityReport::default();
+            for (profile_id, profile, enc_key) in profiles {
+                report.profiles += 1;
+                let key = match self.key_cache.load_key(enc_key).await {
+                    Ok(key) => Arc::new(key),
+                    Err(err) => {
+                        report.issues.push(IntegrityIssue {
+                            profile,
+                            id: None,
+                            kind: None,
+                            category: None,
+                            name: None,
+                            reason: err.to_string(),
+                            quarantined: false,
+                        });
+                        continue;
+                    }
+                };
+                let mut last_id = 0;
+                loop {
+                    let rows = sqlx::query(VERIFY_QUERY)
+                        .bind(profile_id)
+                        .bind(last_id)
+                        .bind(PAGE_SIZE as i64)
+                        .fetch_all(conn.as_mut())
+                        .await
+                        .map_err(err_map!(Backend, "Error fetching entries"))?;
+                    if rows.is_empty() {
+                        break;
+                    }
+                    report.entries += rows.len() as i64;
+                    let mut batch = Vec::with_capacity(rows.len());
+                    let mut issues = Vec::new();
+                    for row in rows {
+                        last_id = row.try_get(0)?;
+                        let kind: i64 = row.try_get(1)?;
+                        let shared: Option<Vec<u8>> = row.try_get(8)?;
+                        let shared_id: Option<i64> = row.try_get(9)?;
+                        let reason = match EntryKind::try_from(kind as usize) {
+                            Err(_) => "Invalid entry kind",
+                            Ok(_) if shared_id.is_some() && shared.is_none() => {
+                                "Missing shared entry value"
+                            }
+                            Ok(kind) => {
+                                batch.push(EncScanEntry {
+                                    id: last_id,
+                                    kind,
+                                    category: row.try_get(2)?,
+                                    name: row.try_get(3)?,
+                                    value: row.try_get(4)?,
+                                    tags: row.try_get::<Option<Vec<u8>>, _>(5)?.unwrap_or_default(),
+                                    version: row.try_get(6)?,
+                                    compression: row.try_get(7)?,
+                                    shared,
+                                });
+                                continue;
+                            }
+                        };
+                        issues.push(IntegrityIssue {
+                            profile: profile.clone(),
+                            id: Some(last_id),
+                            kind: EntryKind::try_from(kind as usize).ok(),
+                            category: key.decrypt_entry_category(row.try_get(2)?).ok(),
+                            name: key.decrypt_entry_name(row.try_get(3)?).ok(),
+                            reason: reason.to_string(),
+                            quarantined: false,
+                        });
+                    }
+                    issues.extend(
+                        unblock({
+                            let key = key.clone();
+                            let profile = profile.clone();
+                            move || verify_scan_batch(&profile, batch, &key)
+                        })
+                        .await,
+                    );
+                    issues.sort_by_key(|issue| issue.id);
+                    report.issues.append(&mut issues);
+                }
+            }
+            if quarantine {
+                for issue in report.issues.iter_mut() {
+                    if let Some(id) = issue.id {
+                        sqlx::query(QUARANTINE_QUERY)
+                            .bind(id)
+                            .execute(conn.as_mut())
+                            .await
+                            .map_err(err_map!(Backend, "Error quarantining entry"))?;
+                        issue.quarantined = true;
+                    }
+                }
+            }
+            conn.return_to_pool().await;
+            Ok(report)
+        })
+    }
+
+    fn repair(&self, dry_run: bool) -> BoxFuture<'_, Result<RepairReport, Error>> {
+        Box::pin(async move {
+            self.key_cache.store_key()?;
+            let mut conn = self.conn_pool.acquire().await?;
+            let mut txn = conn.begin().await?;
+            let mut report = RepairReport {
+                orphan_entries: repair_rows(
+                    txn.as_mut(),
+                    "items",
+                    "profile_id NOT IN (SELECT id FROM profiles)",
+                    dry_run,
+                )
+                .await?,
+                orphan_tags: repair_rows(
+                    txn.as_mut(),
+                    "items_tags",
+                    "item_id NOT IN (SELECT id FROM items)",
+                    dry_run,
+                )
+                .await?,
+                orphan_chunks: repair_rows(
+                    txn.as_mut(),
+                    "items_chunks",
+                    "item_id NOT IN (SELECT id FROM items)",
+                    dry_run,
+                )
+                .await?,
+                orphan_audit_records: repair_rows(
+                    txn.as_mut(),
+                    "audit_log",
+                    "profile_id NOT IN (SELECT id FROM profiles)",
+                    dry_run,
+                )
+                .await?,
+                ..Default::default()
+            };
+            report.shared_refs = sqlx::query_scalar(SHARED_REFS_COUNT_QUERY)
+                .fetch_one(txn.as_mut())
+                .await
+                .map_err(err_map!(Backend, "Error checking shared value references"))?;
+            if !dry_run && report.shared_refs > 0 {
+                sqlx::query(SHARED_REFS_UPDATE_QUERY)
+                    .execute(txn.as_mut())
+                    .await
+                    .map_err(err_map!(Backend, "Error updating shared value references"))?;
+                sqlx::query("DELETE FROM items_shared WHERE refs <= 0")
+                    .execute(txn.as_mut())
+                    .await
+                    .map_err(err_map!(Backend, "Error removing shared values"))?;
+            }
+            let default_profile: Option<String> = sqlx::query_scalar(DEFAULT_PROFILE_CHECK_QUERY)
+                .fetch_optional(txn.as_mut())
+                .await
+                .map_err(err_map!(Backend, "Error checking default profile"))?
+                .flatten();
+            if let Some(profile) = default_profile {
+                if !dry_run {
+                    sqlx::query(CONFIG_UPDATE_QUERY)
+                        .bind("default_profile")
+                        .bind(&profile)
+                        .execute(txn.as_mut())
+                        .await
+                        .map_err(err_map!(Backend, "Error setting default profile name"))?;
+                }
+                report.default_profile = Some(profile);
+            }
+            if !dry_run {
+                txn.as_mut()
+                    .execute(
+                        "REINDEX;
+            INSERT INTO items_tags_fts (items_tags_fts) VALUES ('rebuild');",
+                    )
+                    .await
+                    .map_err(err_map!(Backend, "Error rebuilding indexes"))?;
+                report.reindexed = true;
+            }
+            txn.commit().await?;
+            conn.return_to_pool().await;
+            Ok(report)
+        })
     }
 
     fn close(&self) -> BoxFuture<'_, Result<(), Error>> {
         Box::pin(async move {
+            self.notifier.close();
             self.conn_pool.close().await;
             Ok(())
         })
@@ -330,8 +1076,14 @@ impl BackendSession for DbSession<Sqlite> {
             })
             .await?;
             params.push(enc_category);
-            let query =
-                extend_query::<SqliteBackend>(COUNT_QUERY, &mut params, tag_filter, None, None)?;
+            let query = extend_query::<SqliteBackend>(
+                COUNT_QUERY,
+                &mut params,
+                tag_filter,
+                None,
+                None,
+                None,
+            )?;
             let mut active = acquire_session(&mut *self).await?;
             let count = sqlx::query_scalar_with(query.as_str(), params)
                 .fetch_one(active.connection_mut())
@@ -341,21 +1093,118 @@ impl BackendSession for DbSession<Sqlite> {
         })
     }
 
-    fn fetch(
-        &mut self,
-        kind: EntryKind,
-        category: &str,
-        name: &str,
-        _for_update: bool,
-    ) -> BoxFuture<'_, Result<Option<Entry>, Error>> {
-        let category = category.to_string();
-        let name = name.to_string();
+    fn count_grouped<'q>(
+        &'q mut self,
+        kind: Option<EntryKind>,
+        category: Option<&'q str>,
+        tag_filter: Option<TagFilter>,
+        group_tag: Option<&'q str>,
+    ) -> BoxFuture<'q, Result<Vec<EntryCount>, Error>> {
+        let enc_category = category.map(|c| ProfileKey::prepare_input(c.as_bytes()));
+        let (group_tag, plaintext) = match group_tag {
+            Some(tag) => match tag.strip_prefix('~') {
+                Some(plain) => (Some(plain), true),
+                None => (Some(tag), false),
+            },
+            None => (None, false),
+        };
+        let group_tag = group_tag.map(|t| ProfileKey::prepare_input(t.as_bytes()));
 
         Box::pin(async move {
             let (profile_id, key) = acquire_key(&mut *self).await?;
-            let (enc_category, enc_name) = unblock({
+            let mut params = QueryParams::new();
+            params.push(profile_id);
+            params.push(kind.map(|k| k as i16));
+            let (enc_category, enc_group_tag, tag_filter) = unblock({
                 let key = key.clone();
-                let category = ProfileKey::prepare_input(category.as_bytes());
+                let params_len = params.len() + 3; // plus category and group tag
+                move || {
+                    Result::<_, Error>::Ok((
+                        enc_category
+                            .map(|c| key.encrypt_entry_category(c))
+                            .transpose()?,
+                        group_tag.map(|t| key.encrypt_tag_name(t)).transpose()?,
+                        encode_tag_filter::<SqliteBackend>(tag_filter, &key, params_len)?,
+                    ))
+                }
+            })
+            .await?;
+            params.push(enc_category);
+            params.push(enc_group_tag);
+            params.push(plaintext as i16);
+            let query = extend_query::<SqliteBackend>(
+                COUNT_GROUPED_QUERY,
+                &mut params,
+                tag_filter,
+                None,
+                None,
+                None,
+            )?;
+            let query = format!("{query} GROUP BY i.category, tag_value");
+            let mut active = acquire_session(&mut *self).await?;
+            let rows = sqlx::query_as_with(query.as_str(), params)
+                .fetch_all(active.connection_mut())
+                .await
+                .map_err(err_map!(Backend, "Error performing grouped count query"))?;
+            unblock(move || decrypt_entry_counts(rows, plaintext, &key)).await
+        })
+    }
+
+    fn explain_scan<'q>(
+        &'q mut self,
+        kind: Option<EntryKind>,
+        category: Option<&'q str>,
+        tag_filter: Option<TagFilter>,
+    ) -> BoxFuture<'q, Result<QueryPlan, Error>> {
+        let category = category.map(str::to_string);
+
+        Box::pin(async move {
+            let (profile_id, key) = acquire_key(&mut *self).await?;
+            let (query, params) = scan_query(
+                profile_id, key, kind, category, tag_filter, None, None, None, false, None,
+            )
+            .await?;
+            let arguments = params.len();
+            let mut active = acquire_session(&mut *self).await?;
+            let rows = sqlx::query_with(&format!("EXPLAIN QUERY PLAN {query}"), params)
+                .fetch_all(active.connection_mut())
+                .await
+                .map_err(err_map!(Backend, "Error explaining scan query"))?;
+            // each step of the plan refers to its parent step, which is
+            // reported before it
+            let mut depths = HashMap::new();
+            let mut plan = Vec::with_capacity(rows.len());
+            for row in rows {
+                let id: i64 = row.try_get(0)?;
+                let parent: i64 = row.try_get(1)?;
+                let detail: String = row.try_get(3)?;
+                let depth = depths.get(&parent).map(|d| d + 1).unwrap_or(0);
+                depths.insert(id, depth);
+                plan.push(format!("{}{}", "  ".repeat(depth), detail));
+            }
+            Ok(QueryPlan {
+                query,
+                arguments,
+                plan,
+            })
+        })
+    }
+
+    fn fetch(
+        &mut self,
+        kind: EntryKind,
+        category: &str,
+        name: &str,
+        _for_update: bool,
+    ) -> BoxFuture<'_, Result<Option<Entry>, Error>> {
+        let category = category.to_string();
+        let name = name.to_string();
+
+        Box::pin(async move {
+            let (profile_id, key) = acquire_key(&mut *self).await?;
+            let (enc_category, enc_name) = unblock({
+                let key = key.clone();
+                let category = ProfileKey::prepare_input(category.as_bytes());
                 let name = ProfileKey::prepare_input(name.as_bytes());
                 move || {
                     Result::<_, Error>::Ok((
@@ -375,23 +1224,89 @@ impl BackendSession for DbSession<Sqlite> {
                 .await
                 .map_err(err_map!(Backend, "Error performing fetch query"))?
             {
-                let value = row.try_get(1)?;
-                let tags = row.try_get(2)?;
+                let version = row.try_get(3)?;
                 let (category, name, value, tags) = unblock(move || {
-                    let value = key.decrypt_entry_value(category.as_ref(), name.as_ref(), value)?;
-                    let enc_tags = decode_tags(tags)
-                        .map_err(|_| err_msg!(Unexpected, "Error decoding entry tags"))?;
-                    let tags = key.decrypt_entry_tags(enc_tags)?;
+                    // decrypt directly from the row data to avoid copying the value
+                    let value = decrypt_row_value(
+                        &key,
+                        category.as_ref(),
+                        name.as_ref(),
+                        row.try_get(1)?,
+                        row.try_get(4)?,
+                        row.try_get(5)?,
+                    )?;
+                    let tags = decrypt_tags(&key, row.try_get(2)?)?;
                     Result::<_, Error>::Ok((category, name, value, tags))
                 })
                 .await?;
-                Ok(Some(Entry::new(kind, category, name, value, tags)))
+                Ok(Some(
+                    Entry::new(kind, category, name, value, tags).with_version(version),
+                ))
             } else {
                 Ok(None)
             }
         })
     }
 
+    fn fetch_many<'q>(
+        &'q mut self,
+        kind: EntryKind,
+        category: &'q str,
+        names: &'q [&'q str],
+        _for_update: bool,
+    ) -> BoxFuture<'q, Result<Vec<Entry>, Error>> {
+        let category = category.to_string();
+        let names: Vec<String> = names.iter().map(|name| name.to_string()).collect();
+
+        Box::pin(async move {
+            if names.is_empty() {
+                return Ok(Vec::new());
+            }
+            let (profile_id, key) = acquire_key(&mut *self).await?;
+            let (enc_category, enc_names, names) = unblock({
+                let key = key.clone();
+                let category = category.clone();
+                move || {
+                    let (enc_category, enc_names) = encrypt_fetch_names(&category, &names, &key)?;
+                    Result::<_, Error>::Ok((enc_category, enc_names, names))
+                }
+            })
+            .await?;
+            let mut active = acquire_session(&mut *self).await?;
+            let mut rows = Vec::new();
+            for batch in enc_names.chunks(FETCH_BATCH_SIZE) {
+                let mut params = QueryParams::new();
+                params.push(profile_id);
+                params.push(kind as i16);
+                params.push(enc_category.as_slice());
+                for enc_name in batch {
+                    params.push(enc_name.as_slice());
+                }
+                let query = format!(
+                    "{FETCH_MANY_QUERY} ({})",
+                    multi_row_values::<SqliteBackend>(None, batch.len(), 1, 4)
+                );
+                let mut batch_rows =
+                    sqlx::query_with(query.as_str(), params).fetch(active.connection_mut());
+                while let Some(row) = batch_rows
+                    .try_next()
+                    .await
+                    .map_err(err_map!(Backend, "Error performing fetch query"))?
+                {
+                    rows.push((
+                        row.try_get(0)?,
+                        row.try_get(1)?,
+                        row.try_get(2)?,
+                        row.try_get(3)?,
+                        row.try_get(4)?,
+                        row.try_get(5)?,
+                    ));
+                }
+            }
+            unblock(move || decrypt_fetch_rows(kind, &category, names, enc_names, rows, &key)).await
+        })
+    }
+
     fn fetch_all<'q>(
         &'q mut self,
         kind: Option<EntryKind>,
@@ -413,31 +1328,42 @@ impl BackendSession for DbSession<Sqlite> {
                 tag_filter,
                 None,
                 limit,
+                Some(OrderBy::Created),
+                false,
+                None,
             );
             pin!(scan);
             let mut enc_rows = vec![];
             while let Some(rows) = scan.try_next().await? {
                 enc_rows.extend(rows)
             }
-            unblock(move || decrypt_scan_batch(category, enc_rows, &key)).await
+            unblock(move || decrypt_scan_batch(category, enc_rows, &key, None)).await
         })
     }
 
-    fn remove_all<'q>(
+    fn search<'q>(
         &'q mut self,
         kind: Option<EntryKind>,
         category: Option<&'q str>,
+        text: &'q str,
         tag_filter: Option<TagFilter>,
-    ) -> BoxFuture<'q, Result<i64, Error>> {
+        limit: Option<i64>,
+    ) -> BoxFuture<'q, Result<Vec<Entry>, Error>> {
         let enc_category = category.map(|c| ProfileKey::prepare_input(c.as_bytes()));
+        let category = category.map(|c| c.to_string());
+        let search = encode_search::<SqliteBackend>(text);
 
         Box::pin(async move {
+            let Some(search) = search else {
+                return Ok(Vec::new());
+            };
             let (profile_id, key) = acquire_key(&mut *self).await?;
             let mut params = QueryParams::new();
             params.push(profile_id);
             params.push(kind.map(|k| k as i16));
             let (enc_category, tag_filter) = unblock({
-                let params_len = params.len() + 1; // plus category
+                let key = key.clone();
+                let params_len = params.len() + 2; // plus category and search
                 move || {
                     Result::<_, Error>::Ok((
                         enc_category
@@ -449,20 +1375,68 @@ impl BackendSession for DbSession<Sqlite> {
             })
             .await?;
             params.push(enc_category);
+            params.push(search);
             let query = extend_query::<SqliteBackend>(
-                DELETE_ALL_QUERY,
+                SEARCH_QUERY,
                 &mut params,
                 tag_filter,
+                Some((SEARCH_ORDER.to_string(), Vec::new())),
                 None,
-                None,
+                limit,
             )?;
-
             let mut active = acquire_session(&mut *self).await?;
-            let removed = sqlx::query_with(query.as_str(), params)
-                .execute(active.connection_mut())
-                .await?
-                .rows_affected();
-            Ok(removed as i64)
+            let rows = sqlx::query_with(query.as_str(), params)
+                .fetch_all(active.connection_mut())
+                .await
+                .map_err(err_map!(Backend, "Error performing search query"))?;
+            let enc_rows = rows
+                .into_iter()
+                .map(|row| {
+                    let kind: u32 = row.try_get(1)?;
+                    Ok(EncScanEntry {
+                        id: row.try_get(0)?,
+                        kind: EntryKind::try_from(kind as usize)?,
+                        category: row.try_get(2)?,
+                        name: row.try_get(3)?,
+                        value: row.try_get(4)?,
+                        tags: row.try_get(5)?,
+                        version: row.try_get(6)?,
+                        compression: row.try_get(7)?,
+                        shared: row.try_get(8)?,
+                    })
+                })
+                .collect::<Result<Vec<_>, Error>>()?;
+            unblock(move || decrypt_scan_batch(category, enc_rows, &key, None)).await
+        })
+    }
+
+    fn remove_all<'q>(
+        &'q mut self,
+        kind: Option<EntryKind>,
+        category: Option<&'q str>,
+        tag_filter: Option<TagFilter>,
+    ) -> BoxFuture<'q, Result<i64, Error>> {
+        Box::pin(async move {
+            let (_, events) = perform_remove_all(self, kind, category, tag_filter).await?;
+            Ok(events.len() as i64)
+        })
+    }
+
+    fn remove_all_returning<'q>(
+        &'q mut self,
+        kind: Option<EntryKind>,
+        category: Option<&'q str>,
+        tag_filter: Option<TagFilter>,
+    ) -> BoxFuture<'q, Result<Vec<EntryEvent>, Error>> {
+        Box::pin(async move {
+            let (key, events) = perform_remove_all(self, kind, category, tag_filter).await?;
+            unblock(move || {
+                events
+                    .into_iter()
+                    .map(|event| decrypt_entry_event(event, &key))
+                    .collect()
+            })
+            .await
         })
     }
 
@@ -484,10 +1458,18 @@ impl BackendSession for DbSession<Sqlite> {
                 let value = ProfileKey::prepare_input(value.unwrap_or_default());
                 let tags = tags.map(prepare_tags);
                 Box::pin(async move {
-                    let (_, key) = acquire_key(&mut *self).await?;
+                    let (profile_id, key) = acquire_key(&mut *self).await?;
+                    let compression = self.compression();
+                    let dedup_threshold = self.dedup_threshold();
                     let (enc_category, enc_name, enc_value, enc_tags) = unblock(move || {
-                        let enc_value =
-                            key.encrypt_entry_value(category.as_ref(), name.as_ref(), value)?;
+                        let enc_value = encrypt_value(
+                            &key,
+                            compression,
+                            dedup_threshold,
+                            category.as_ref(),
+                            name.as_ref(),
+                            value,
+                        )?;
                         Result::<_, Error>::Ok((
                             key.encrypt_entry_category(category)?,
                             key.encrypt_entry_name(name)?,
@@ -512,12 +1494,19 @@ impl BackendSession for DbSession<Sqlite> {
                     )
                     .await?;
                     txn.commit().await?;
+                    active.notify([EncEntryEvent {
+                        profile_id,
+                        operation: op,
+                        kind,
+                        category: enc_category,
+                        name: enc_name,
+                    }]);
                     Ok(())
                 })
             }
 
             EntryOperation::Remove => Box::pin(async move {
-                let (_, key) = acquire_key(&mut *self).await?;
+                let (profile_id, key) = acquire_key(&mut *self).await?;
                 let (enc_category, enc_name) = unblock(move || {
                     Result::<_, Error>::Ok((
                         key.encrypt_entry_category(category)?,
@@ -525,83 +1514,823 @@ impl BackendSession for DbSession<Sqlite> {
                     ))
                 })
                 .await?;
+                let soft_delete = self.soft_delete();
                 let mut active = acquire_session(&mut *self).await?;
-                perform_remove(&mut active, kind, &enc_category, &enc_name, false).await
+                perform_remove(
+                    active.connection_mut(),
+                    profile_id,
+                    kind,
+                    &enc_category,
+                    &enc_name,
+                    soft_delete,
+                    false,
+                )
+                .await?;
+                active.notify([EncEntryEvent {
+                    profile_id,
+                    operation: EntryOperation::Remove,
+                    kind,
+                    category: enc_category,
+                    name: enc_name,
+                }]);
+                Ok(())
             }),
         }
     }
 
-    fn ping(&mut self) -> BoxFuture<'_, Result<(), Error>> {
+    fn update_if<'q>(
+        &'q mut self,
+        kind: EntryKind,
+        operation: EntryOperation,
+        category: &'q str,
+        name: &'q str,
+        value: Option<&'q [u8]>,
+        tags: Option<&'q [EntryTag]>,
+        expiry_ms: Option<i64>,
+        precondition: &'q EntryPrecondition,
+    ) -> BoxFuture<'q, Result<(), Error>> {
+        let category = ProfileKey::prepare_input(category.as_bytes());
+        let name = ProfileKey::prepare_input(name.as_bytes());
+        let value = ProfileKey::prepare_input(value.unwrap_or_default());
+        let tags = tags.map(prepare_tags);
+
         Box::pin(async move {
-            let mut sess = acquire_session(&mut *self).await?;
-            let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM profiles WHERE id=$1")
-                .bind(sess.profile_id)
-                .fetch_one(sess.connection_mut())
+            if operation == EntryOperation::Insert {
+                return Err(err_msg!(
+                    Input,
+                    "Preconditions are not supported for inserts"
+                ));
+            }
+            let (profile_id, key) = acquire_key(&mut *self).await?;
+            let compression = self.compression();
+            let dedup_threshold = self.dedup_threshold();
+            let (enc_category, enc_name, enc_value, enc_tags) = unblock({
+                let key = key.clone();
+                let category = category.clone();
+                let name = name.clone();
+                move || {
+                    let enc_value = encrypt_value(
+                        &key,
+                        compression,
+                        dedup_threshold,
+                        category.as_ref(),
+                        name.as_ref(),
+                        value,
+                    )?;
+                    Result::<_, Error>::Ok((
+                        key.encrypt_entry_category(category)?,
+                        key.encrypt_entry_name(name)?,
+                        enc_value,
+                        tags.transpose()?
+                            .map(|t| key.encrypt_entry_tags(t))
+                            .transpose()?,
+                    ))
+                }
+            })
+            .await?;
+            let soft_delete = self.soft_delete();
+            let mut active = acquire_session(&mut *self).await?;
+            let mut txn = active.as_transaction().await?;
+            let row = sqlx::query(FETCH_QUERY)
+                .bind(profile_id)
+                .bind(kind as i16)
+                .bind(&enc_category)
+                .bind(&enc_name)
+                .fetch_optional(txn.connection_mut())
                 .await
-                .map_err(err_map!(Backend, "Error pinging session"))?;
-            if count == 0 {
-                Err(err_msg!(NotFound, "Session profile has been removed"))
+                .map_err(err_map!(Backend, "Error performing fetch query"))?
+                .ok_or_else(|| err_msg!(NotFound, "Entry not found"))?;
+            let (cur_value, cur_tags, cur_version, cur_compression, cur_shared) = (
+                row.try_get(1)?,
+                row.try_get(2)?,
+                row.try_get(3)?,
+                row.try_get(4)?,
+                row.try_get(5)?,
+            );
+            let precondition = precondition.clone();
+            unblock(move || {
+                check_entry_precondition(
+                    &precondition,
+                    category.as_ref(),
+                    name.as_ref(),
+                    cur_value,
+                    cur_tags,
+                    cur_version,
+                    cur_compression,
+                    cur_shared,
+                    &key,
+                )
+            })
+            .await?;
+            if operation == EntryOperation::Replace {
+                perform_insert(
+                    &mut txn,
+                    kind,
+                    &enc_category,
+                    &enc_name,
+                    &enc_value,
+                    enc_tags,
+                    expiry_ms,
+                    false,
+                )
+                .await?;
             } else {
-                Ok(())
+                perform_remove(
+                    txn.connection_mut(),
+                    profile_id,
+                    kind,
+                    &enc_category,
+                    &enc_name,
+                    soft_delete,
+                    false,
+                )
+                .await?;
             }
+            txn.commit().await?;
+            active.notify([EncEntryEvent {
+                profile_id,
+                operation,
+                kind,
+                category: enc_category,
+                name: enc_name,
+            }]);
+            Ok(())
         })
     }
 
-    fn close(&mut self, commit: bool) -> BoxFuture<'_, Result<(), Error>> {
-        Box::pin(self.close(commit))
-    }
-}
-
-impl ExtDatabase for Sqlite {
-    fn start_transaction(
-        conn: &mut Connection<Self>,
-        nested: bool,
-    ) -> BoxFuture<'_, std::result::Result<(), SqlxError>> {
-        // FIXME - this is a horrible workaround because there is currently
-        // no good way to start an immediate transaction with sqlx. Without this
-        // adjustment, updates will run into 'database is locked' errors.
+    fn insert_many<'q>(
+        &'q mut self,
+        entries: &'q [Entry],
+        expiry_ms: Option<i64>,
+    ) -> BoxFuture<'q, Result<(), Error>> {
+        let entries = entries.to_vec();
         Box::pin(async move {
-            <Sqlite as Database>::TransactionManager::begin(conn).await?;
-            if !nested {
-                // a no-op write transaction
-                sqlx::query("DELETE FROM config WHERE 0")
-                    .execute(conn)
-                    .await?;
+            if entries.is_empty() {
+                return Ok(());
             }
+            let (profile_id, key) = acquire_key(&mut *self).await?;
+            let compression = self.compression();
+            let dedup_threshold = self.dedup_threshold();
+            let enc_entries =
+                encrypt_entries_chunked(entries, compression, dedup_threshold, key).await?;
+            let mut active = acquire_session(&mut *self).await?;
+            let mut txn = active.as_transaction().await?;
+            perform_insert_many(&mut txn, &enc_entries, expiry_ms).await?;
+            txn.commit().await?;
+            active.notify(enc_entries.into_iter().map(|entry| EncEntryEvent {
+                profile_id,
+                operation: EntryOperation::Insert,
+                kind: entry.kind,
+                category: entry.category,
+                name: entry.name,
+            }));
             Ok(())
         })
     }
-}
 
-async fn acquire_key(
-    session: &mut DbSession<Sqlite>,
-) -> Result<(ProfileId, Arc<ProfileKey>), Error> {
-    acquire_session(session).await?;
-    Ok(session.profile_and_key().unwrap())
-}
+    fn move_category<'q>(
+        &'q mut self,
+        kind: EntryKind,
+        category: &'q str,
+        new_category: &'q str,
+        rename_tags: &'q [(&'q str, &'q str)],
+    ) -> BoxFuture<'q, Result<i64, Error>> {
+        let same_category = category == new_category;
+        let category = ProfileKey::prepare_input(category.as_bytes());
+        let new_category = ProfileKey::prepare_input(new_category.as_bytes());
+        let rename_tags = rename_tags
+            .iter()
+            .map(|(name, new_name)| (name.to_string(), new_name.to_string()))
+            .collect();
 
-async fn acquire_session(
-    session: &mut DbSession<Sqlite>,
-) -> Result<DbSessionActive<'_, Sqlite>, Error> {
-    session.make_active(&resolve_profile_key).await
-}
+        Box::pin(async move {
+            if same_category {
+                return Err(err_msg!(
+                    Input,
+                    "Target category must differ from the source"
+                ));
+            }
+            let (profile_id, key) = acquire_key(&mut *self).await?;
+            let (enc_category, enc_new_category, enc_renames) = unblock({
+                let key = key.clone();
+                let (category, new_category) = (category.clone(), new_category.clone());
+                move || {
+                    Result::<_, Error>::Ok((
+                        key.encrypt_entry_category(category)?,
+                        key.encrypt_entry_category(new_category)?,
+                        encrypt_tag_renames(&key, rename_tags)?,
+                    ))
+                }
+            })
+            .await?;
+            let mut active = acquire_session(&mut *self).await?;
+            let mut txn = active.as_transaction().await?;
+            let exists: i64 = sqlx::query_scalar(MOVE_EXISTS_QUERY)
+                .bind(profile_id)
+                .bind(kind as i16)
+                .bind(&enc_category)
+                .bind(&enc_new_category)
+                .fetch_one(txn.connection_mut())
+                .await
+                .map_err(err_map!(Backend, "Error checking for existing entries"))?;
+            if exists != 0 {
+                return Err(err_msg!(Duplicate, "Duplicate entry in target category"));
+            }
+            for (name, new_name) in enc_renames {
+                sqlx::query(MOVE_TAG_RENAME_QUERY)
+                    .bind(profile_id)
+                    .bind(kind as i16)
+                    .bind(&enc_category)
+                    .bind(name)
+                    .bind(new_name)
+                    .execute(txn.connection_mut())
+                    .await
+                    .map_err(err_map!(Backend, "Error renaming entry tags"))?;
+            }
+            let mut count = 0;
+            let mut events = Vec::new();
+            loop {
+                let rows: Vec<(i64, Vec<u8>, Vec<u8>)> = sqlx::query_as(MOVE_FETCH_QUERY)
+                    .bind(profile_id)
+                    .bind(kind as i16)
+                    .bind(&enc_category)
+                    .bind(INSERT_BATCH_SIZE as i64)
+                    .fetch_all(txn.connection_mut())
+                    .await
+                    .map_err(err_map!(Backend, "Error fetching entries"))?;
+                if rows.is_empty() {
+                    break;
+                }
+                // the value key is derived from the entry category
+                let rows = unblock({
+                    let key = key.clone();
+                    let (category, new_category) = (category.clone(), new_category.clone());
+                    move || {
+                        rows.into_iter()
+                            .map(|(row_id, enc_name, enc_value)| {
+                                let name = key.decrypt_entry_name(enc_name.clone())?;
+                                let value = key.decrypt_entry_value(
+                                    category.as_ref(),
+                                    name.as_bytes(),
+                                    enc_value,
+                                )?;
+                                let enc_value = key.encrypt_entry_value(
+                                    new_category.as_ref(),
+                                    name.as_bytes(),
+                                    value,
+                                )?;
+                                Result::<_, Error>::Ok((row_id, enc_name, enc_value))
+                            })
+                            .collect::<Result<Vec<_>, Error>>()
+                    }
+                })
+                .await?;
+                for (row_id, enc_name, enc_value) in rows {
+                    sqlx::query(MOVE_QUERY)
+                        .bind(row_id)
+                        .bind(&enc_new_category)
+                        .bind(enc_value)
+                        .execute(txn.connection_mut())
+                        .await
+                        .map_err(err_map!(Backend, "Error moving entry"))?;
+                    count += 1;
+                    events.push(EncEntryEvent {
+                        profile_id,
+                        operation: EntryOperation::Remove,
+                        kind,
+                        category: enc_category.clone(),
+                        name: enc_name.clone(),
+                    });
+                    events.push(EncEntryEvent {
+                        profile_id,
+                        operation: EntryOperation::Insert,
+                        kind,
+                        category: enc_new_category.clone(),
+                        name: enc_name,
+                    });
+                }
+            }
+            txn.commit().await?;
+            active.notify(events);
+            Ok(count)
+        })
+    }
 
-async fn resolve_profile_key(
-    conn: &mut PoolConnection<Sqlite>,
-    cache: Arc<KeyCache>,
-    profile: String,
-    _in_txn: bool,
-) -> Result<(ProfileId, Arc<ProfileKey>), Error> {
-    if let Some((pid, key)) = cache.get_profile(profile.as_str()).await {
-        Ok((pid, key))
-    } else if let Some(row) = sqlx::query("SELECT id, profile_key FROM profiles WHERE name=?1")
-        .bind(profile.as_str())
-        .fetch_optional(conn.as_mut())
-        .await
-        .map_err(err_map!(Backend, "Error fetching profile key"))?
-    {
-        let pid = row.try_get(0)?;
-        let key = Arc::new(cache.load_key(row.try_get(1)?).await?);
+    fn rename<'q>(
+        &'q mut self,
+        kind: EntryKind,
+        category: &'q str,
+        name: &'q str,
+        new_name: &'q str,
+    ) -> BoxFuture<'q, Result<(), Error>> {
+        let category = ProfileKey::prepare_input(category.as_bytes());
+        let name = ProfileKey::prepare_input(name.as_bytes());
+        let new_name = ProfileKey::prepare_input(new_name.as_bytes());
+
+        Box::pin(async move {
+            let (profile_id, key) = acquire_key(&mut *self).await?;
+            let (enc_category, enc_name, enc_new_name) = unblock({
+                let key = key.clone();
+                let (category, name, new_name) = (category.clone(), name.clone(), new_name.clone());
+                move || {
+                    Result::<_, Error>::Ok((
+                        key.encrypt_entry_category(category)?,
+                        key.encrypt_entry_name(name)?,
+                        key.encrypt_entry_name(new_name)?,
+                    ))
+                }
+            })
+            .await?;
+            let mut active = acquire_session(&mut *self).await?;
+            let mut txn = active.as_transaction().await?;
+            let (row_id, enc_value): (i64, Vec<u8>) = sqlx::query_as(RENAME_FETCH_QUERY)
+                .bind(profile_id)
+                .bind(kind as i16)
+                .bind(&enc_category)
+                .bind(&enc_name)
+                .fetch_optional(txn.connection_mut())
+                .await
+                .map_err(err_map!(Backend, "Error fetching entry"))?
+                .ok_or_else(|| err_msg!(NotFound, "Entry not found"))?;
+            // a soft-deleted entry is replaced by the renamed entry
+            sqlx::query(PURGE_QUERY)
+                .bind(profile_id)
+                .bind(kind as i16)
+                .bind(&enc_category)
+                .bind(&enc_new_name)
+                .execute(txn.connection_mut())
+                .await
+                .map_err(err_map!(Backend, "Error removing deleted entry"))?;
+            let exists: i64 = sqlx::query_scalar(RENAME_EXISTS_QUERY)
+                .bind(profile_id)
+                .bind(kind as i16)
+                .bind(&enc_category)
+                .bind(&enc_new_name)
+                .fetch_one(txn.connection_mut())
+                .await
+                .map_err(err_map!(Backend, "Error checking for existing entry"))?;
+            if exists != 0 {
+                return Err(err_msg!(Duplicate, "Duplicate entry"));
+            }
+            // the value key is derived from the entry name
+            let enc_value = unblock(move || {
+                let value = key.decrypt_entry_value(category.as_ref(), name.as_ref(), enc_value)?;
+                key.encrypt_entry_value(category.as_ref(), new_name.as_ref(), value)
+            })
+            .await?;
+            sqlx::query(RENAME_QUERY)
+                .bind(row_id)
+                .bind(&enc_new_name)
+                .bind(enc_value)
+                .execute(txn.connection_mut())
+                .await
+                .map_err(err_map!(Backend, "Error renaming entry"))?;
+            let events = [
+                EncEntryEvent {
+                    profile_id,
+                    operation: EntryOperation::Remove,
+                    kind,
+                    category: enc_category.clone(),
+                    name: enc_name,
+                },
+                EncEntryEvent {
+                    profile_id,
+                    operation: EntryOperation::Insert,
+                    kind,
+                    category: enc_category,
+                    name: enc_new_name,
+                },
+            ];
+            txn.commit().await?;
+            active.notify(events);
+            Ok(())
+        })
+    }
+
+    fn update_tags<'q>(
+        &'q mut self,
+        kind: EntryKind,
+        category: &'q str,
+        name: &'q str,
+        add_tags: &'q [EntryTag],
+        remove_tags: &'q [&'q str],
+    ) -> BoxFuture<'q, Result<(), Error>> {
+        let category = ProfileKey::prepare_input(category.as_bytes());
+        let name = ProfileKey::prepare_input(name.as_bytes());
+        let add_tags = prepare_tags(add_tags);
+        let remove_tags = remove_tags.iter().map(|name| name.to_string()).collect();
+
+        Box::pin(async move {
+            let add_tags = add_tags?;
+            let (profile_id, key) = acquire_key(&mut *self).await?;
+            let (enc_category, enc_name, (enc_tags, enc_remove)) = unblock(move || {
+                Result::<_, Error>::Ok((
+                    key.encrypt_entry_category(category)?,
+                    key.encrypt_entry_name(name)?,
+                    encrypt_tag_update(&key, add_tags, remove_tags)?,
+                ))
+            })
+            .await?;
+            let mut active = acquire_session(&mut *self).await?;
+            let mut txn = active.as_transaction().await?;
+            perform_update_tags(
+                &mut txn,
+                kind,
+                &enc_category,
+                &enc_name,
+                enc_tags,
+                enc_remove,
+            )
+            .await?;
+            txn.commit().await?;
+            active.notify([EncEntryEvent {
+                profile_id,
+                operation: EntryOperation::Replace,
+                kind,
+                category: enc_category,
+                name: enc_name,
+            }]);
+            Ok(())
+        })
+    }
+
+    fn write_chunk<'q>(
+        &'q mut self,
+        kind: EntryKind,
+        category: &'q str,
+        name: &'q str,
+        index: u32,
+        data: &'q [u8],
+    ) -> BoxFuture<'q, Result<(), Error>> {
+        let category = ProfileKey::prepare_input(category.as_bytes());
+        let name = ProfileKey::prepare_input(name.as_bytes());
+
+        Box::pin(async move {
+            let (profile_id, key) = acquire_key(&mut *self).await?;
+            let (enc_category, enc_name) = unblock(move || {
+                Result::<_, Error>::Ok((
+                    key.encrypt_entry_category(category)?,
+                    key.encrypt_entry_name(name)?,
+                ))
+            })
+            .await?;
+            let mut active = acquire_session(&mut *self).await?;
+            let mut txn = active.as_transaction().await?;
+            let row_id: i64 = sqlx::query_scalar(CHUNK_ITEM_QUERY)
+                .bind(profile_id)
+                .bind(kind as i16)
+                .bind(&enc_category)
+                .bind(&enc_name)
+                .fetch_optional(txn.connection_mut())
+                .await
+                .map_err(err_map!(Backend, "Error fetching entry"))?
+                .ok_or_else(|| err_msg!(NotFound, "Entry not found"))?;
+            if index == 0 {
+                sqlx::query(CHUNK_CLEAR_QUERY)
+                    .bind(row_id)
+                    .execute(txn.connection_mut())
+                    .await
+                    .map_err(err_map!(Backend, "Error removing existing entry chunks"))?;
+            }
+            sqlx::query(CHUNK_INSERT_QUERY)
+                .bind(row_id)
+                .bind(index as i64)
+                .bind(data)
+                .execute(txn.connection_mut())
+                .await
+                .map_err(err_map!(Backend, "Error inserting entry chunk"))?;
+            txn.commit().await?;
+            Ok(())
+        })
+    }
+
+    fn fetch_chunks<'q>(
+        &'q mut self,
+        kind: EntryKind,
+        category: &'q str,
+        name: &'q str,
+        start: u32,
+        limit: u32,
+    ) -> BoxFuture<'q, Result<Vec<Vec<u8>>, Error>> {
+        let category = ProfileKey::prepare_input(category.as_bytes());
+        let name = ProfileKey::prepare_input(name.as_bytes());
+
+        Box::pin(async move {
+            let (profile_id, key) = acquire_key(&mut *self).await?;
+            let (enc_category, enc_name) = unblock(move || {
+                Result::<_, Error>::Ok((
+                    key.encrypt_entry_category(category)?,
+                    key.encrypt_entry_name(name)?,
+                ))
+            })
+            .await?;
+            let mut active = acquire_session(&mut *self).await?;
+            sqlx::query_scalar(CHUNK_FETCH_QUERY)
+                .bind(profile_id)
+                .bind(kind as i16)
+                .bind(enc_category)
+                .bind(enc_name)
+                .bind(start as i64)
+                .bind(limit as i64)
+                .fetch_all(active.connection_mut())
+                .await
+                .map_err(err_map!(Backend, "Error fetching entry chunks"))
+        })
+    }
+
+    fn append_audit<'q>(
+        &'q mut self,
+        operation: &'q str,
+        kind: Option<EntryKind>,
+        category: Option<&'q str>,
+        name: Option<&'q str>,
+        error: Option<&'q str>,
+    ) -> BoxFuture<'q, Result<(), Error>> {
+        let Some(policy) = self.audit() else {
+            return Box::pin(async { Ok(()) });
+        };
+        let category = category.map(|c| ProfileKey::prepare_input(c.as_bytes()));
+        let name = name.map(|n| ProfileKey::prepare_input(n.as_bytes()));
+
+        Box::pin(async move {
+            let (profile_id, key) = acquire_key(&mut *self).await?;
+            let (enc_category, enc_name) = unblock(move || {
+                Result::<_, Error>::Ok((
+                    category
+                        .map(|c| key.encrypt_entry_category(c))
+                        .transpose()?,
+                    name.map(|n| key.encrypt_entry_name(n)).transpose()?,
+                ))
+            })
+            .await?;
+            let mut active = acquire_session(&mut *self).await?;
+            if let Some(retention) = policy.retention {
+                sqlx::query(AUDIT_PURGE_QUERY)
+                    .bind(retention.as_secs() as i64)
+                    .execute(active.connection_mut())
+                    .await
+                    .map_err(err_map!(Backend, "Error removing expired audit records"))?;
+            }
+            sqlx::query(AUDIT_INSERT_QUERY)
+                .bind(profile_id)
+                .bind(operation)
+                .bind(kind.map(|k| k as i16))
+                .bind(enc_category)
+                .bind(enc_name)
+                .bind(error)
+                .execute(active.connection_mut())
+                .await
+                .map_err(err_map!(Backend, "Error appending audit record"))?;
+            Ok(())
+        })
+    }
+
+    fn fetch_audit_log<'q>(
+        &'q mut self,
+        category: Option<&'q str>,
+        since: Option<i64>,
+        limit: Option<i64>,
+    ) -> BoxFuture<'q, Result<Vec<AuditRecord>, Error>> {
+        let category = category.map(|c| ProfileKey::prepare_input(c.as_bytes()));
+
+        Box::pin(async move {
+            let (profile_id, key) = acquire_key(&mut *self).await?;
+            let (enc_category, key) = unblock(move || {
+                Result::<_, Error>::Ok((
+                    category
+                        .map(|c| key.encrypt_entry_category(c))
+                        .transpose()?,
+                    key,
+                ))
+            })
+            .await?;
+            let mut active = acquire_session(&mut *self).await?;
+            let rows: Vec<EncAuditRow> = sqlx::query_as(AUDIT_FETCH_QUERY)
+                .bind(profile_id)
+                .bind(enc_category)
+                .bind(since)
+                .bind(limit.unwrap_or(-1))
+                .fetch_all(active.connection_mut())
+                .await
+                .map_err(err_map!(Backend, "Error fetching audit records"))?;
+            unblock(move || decrypt_audit_records(rows, &key)).await
+        })
+    }
+
+    fn restore<'q>(
+        &'q mut self,
+        kind: EntryKind,
+        category: &'q str,
+        name: &'q str,
+    ) -> BoxFuture<'q, Result<(), Error>> {
+        let category = ProfileKey::prepare_input(category.as_bytes());
+        let name = ProfileKey::prepare_input(name.as_bytes());
+
+        Box::pin(async move {
+            let (profile_id, key) = acquire_key(&mut *self).await?;
+            let (enc_category, enc_name) = unblock(move || {
+                Result::<_, Error>::Ok((
+                    key.encrypt_entry_category(category)?,
+                    key.encrypt_entry_name(name)?,
+                ))
+            })
+            .await?;
+            let mut active = acquire_session(&mut *self).await?;
+            let done = sqlx::query(RESTORE_QUERY)
+                .bind(profile_id)
+                .bind(kind as i16)
+                .bind(&enc_category)
+                .bind(&enc_name)
+                .execute(active.connection_mut())
+                .await
+                .map_err(err_map!(Backend, "Error restoring entry"))?;
+            if done.rows_affected() == 0 {
+                Err(err_msg!(NotFound, "Deleted entry not found"))
+            } else {
+                active.notify([EncEntryEvent {
+                    profile_id,
+                    operation: EntryOperation::Insert,
+                    kind,
+                    category: enc_category,
+                    name: enc_name,
+                }]);
+                Ok(())
+            }
+        })
+    }
+
+    fn purge<'q>(
+        &'q mut self,
+        kind: EntryKind,
+        category: &'q str,
+        name: &'q str,
+    ) -> BoxFuture<'q, Result<(), Error>> {
+        let category = ProfileKey::prepare_input(category.as_bytes());
+        let name = ProfileKey::prepare_input(name.as_bytes());
+
+        Box::pin(async move {
+            let (profile_id, key) = acquire_key(&mut *self).await?;
+            let (enc_category, enc_name) = unblock(move || {
+                Result::<_, Error>::Ok((
+                    key.encrypt_entry_category(category)?,
+                    key.encrypt_entry_name(name)?,
+                ))
+            })
+            .await?;
+            let mut active = acquire_session(&mut *self).await?;
+            let done = sqlx::query(PURGE_QUERY)
+                .bind(profile_id)
+                .bind(kind as i16)
+                .bind(enc_category)
+                .bind(enc_name)
+                .execute(active.connection_mut())
+                .await
+                .map_err(err_map!(Backend, "Error purging entry"))?;
+            if done.rows_affected() == 0 {
+                Err(err_msg!(NotFound, "Deleted entry not found"))
+            } else {
+                Ok(())
+            }
+        })
+    }
+
+    fn purge_all<'q>(
+        &'q mut self,
+        kind: Option<EntryKind>,
+        category: Option<&'q str>,
+    ) -> BoxFuture<'q, Result<i64, Error>> {
+        let enc_category = category.map(|c| ProfileKey::prepare_input(c.as_bytes()));
+
+        Box::pin(async move {
+            let (profile_id, key) = acquire_key(&mut *self).await?;
+            let enc_category = unblock(move || {
+                enc_category
+                    .map(|c| key.encrypt_entry_category(c))
+                    .transpose()
+            })
+            .await?;
+            let mut active = acquire_session(&mut *self).await?;
+            let removed = sqlx::query(PURGE_ALL_QUERY)
+                .bind(profile_id)
+                .bind(kind.map(|k| k as i16))
+                .bind(enc_category)
+                .execute(active.connection_mut())
+                .await
+                .map_err(err_map!(Backend, "Error purging entries"))?
+                .rows_affected();
+            Ok(removed as i64)
+        })
+    }
+
+    fn savepoint<'q>(
+        &'q mut self,
+        name: &'q str,
+        operation: SavepointOperation,
+    ) -> BoxFuture<'q, Result<(), Error>> {
+        Box::pin(async move {
+            let mut active = acquire_session(&mut *self).await?;
+            let sql = active.savepoint_statement(name, operation)?;
+            sqlx::query(&sql)
+                .execute(active.connection_mut())
+                .await
+                .map_err(err_map!(Backend, "Error performing savepoint operation"))?;
+            active.savepoint_applied(name, operation);
+            Ok(())
+        })
+    }
+
+    fn ping(&mut self) -> BoxFuture<'_, Result<(), Error>> {
+        Box::pin(async move {
+            let mut sess = acquire_session(&mut *self).await?;
+            let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM profiles WHERE id=$1")
+                .bind(sess.profile_id)
+                .fetch_one(sess.connection_mut())
+                .await
+                .map_err(err_map!(Backend, "Error pinging session"))?;
+            if count == 0 {
+                Err(err_msg!(NotFound, "Session profile has been removed"))
+            } else {
+                Ok(())
+            }
+        })
+    }
+
+    fn close(&mut self, commit: bool) -> BoxFuture<'_, Result<(), Error>> {
+        Box::pin(self.close(commit))
+    }
+}
+
+impl ExtDatabase for Sqlite {
+    fn start_transaction(
+        conn: &mut Connection<Self>,
+        nested: bool,
+        isolation: Option<IsolationLevel>,
+    ) -> BoxFuture<'_, std::result::Result<(), SqlxError>> {
+        // FIXME - this is a horrible workaround because there is currently
+        // no good way to start an immediate transaction with sqlx. Without this
+        // adjustment, updates will run into 'database is locked' errors.
+        // Weaker isolation levels start a deferred transaction instead.
+        let deferred = matches!(
+            isolation,
+            Some(IsolationLevel::ReadCommitted | IsolationLevel::RepeatableRead)
+        );
+        Box::pin(async move {
+            <Sqlite as Database>::TransactionManager::begin(conn).await?;
+            if !nested && !deferred {
+                // a no-op write transaction
+                sqlx::query("DELETE FROM config WHERE 0")
+                    .execute(conn)
+                    .await?;
+            }
+            Ok(())
+        })
+    }
+
+    fn start_operation(
+        conn: &mut Connection<Self>,
+        timeout: Duration,
+    ) -> BoxFuture<'_, std::result::Result<(), SqlxError>> {
+        Box::pin(async move {
+            // the progress handler interrupts any statement which is still
+            // executing after the deadline
+            let deadline = Instant::now() + timeout;
+            conn.lock_handle()
+                .await?
+                .set_progress_handler(PROGRESS_HANDLER_OPS, move || Instant::now() < deadline);
+            Ok(())
+        })
+    }
+}
+
+async fn acquire_key(
+    session: &mut DbSession<Sqlite>,
+) -> Result<(ProfileId, Arc<ProfileKey>), Error> {
+    acquire_session(session).await?;
+    Ok(session.profile_and_key().unwrap())
+}
+
+async fn acquire_session(
+    session: &mut DbSession<Sqlite>,
+) -> Result<DbSessionActive<'_, Sqlite>, Error> {
+    session.make_active(&resolve_profile_key).await
+}
+
+async fn resolve_profile_key(
+    conn: &mut PoolConnection<Sqlite>,
+    cache: Arc<KeyCache>,
+    profile: String,
+    _in_txn: bool,
+) -> Result<(ProfileId, Arc<ProfileKey>), Error> {
+    if let Some((pid, key)) = cache.get_profile(profile.as_str()).await {
+        Ok((pid, key))
+    } else if let Some(row) = sqlx::query("SELECT id, profile_key FROM profiles WHERE name=?1")
+        .bind(profile.as_str())
+        .fetch_optional(conn.as_mut())
+        .await
+        .map_err(err_map!(Backend, "Error fetching profile key"))?
+    {
+        let pid = row.try_get(0)?;
+        let key = Arc::new(cache.load_key(row.try_get(1)?).await?);
         cache.add_profile(profile, pid, key.clone()).await;
         Ok((pid, key))
     } else {
@@ -609,45 +2338,249 @@ async fn resolve_profile_key(
     }
 }
 
+async fn perform_insert_many(
+    active: &mut DbSessionTxn<'_, Sqlite>,
+    entries: &[EncInsertEntry],
+    expiry_ms: Option<i64>,
+) -> Result<(), Error> {
+    trace!("Insert entries");
+    let expiry = expiry_ms.map(expiry_timestamp).transpose()?;
+    for batch in entries.chunks(INSERT_BATCH_SIZE) {
+        // soft-deleted entries are replaced by the new inserts
+        let mut params = QueryParams::new();
+        params.push(active.profile_id);
+        for entry in batch {
+            params.push(entry.kind as i16);
+            params.push(entry.category.as_slice());
+            params.push(entry.name.as_slice());
+        }
+        let query = format!(
+            "{PURGE_MANY_QUERY} {})",
+            multi_row_values::<SqliteBackend>(None, batch.len(), 3, 2)
+        );
+        sqlx::query_with(query.as_str(), params)
+            .execute(active.connection_mut())
+            .await
+            .map_err(err_map!(Backend, "Error removing deleted entries"))?;
+        enforce_profile_limits(
+            active,
+            batch.len(),
+            batch.iter().map(|entry| entry.value.value.len()).sum(),
+        )
+        .await?;
+
+        let mut shared_ids = Vec::with_capacity(batch.len());
+        for entry in batch {
+            shared_ids.push(perform_insert_shared(active, entry.value.shared.as_ref()).await?);
+        }
+        let mut params = QueryParams::new();
+        params.push(active.profile_id);
+        for (entry, shared_id) in batch.iter().zip(&shared_ids) {
+            params.push(entry.kind as i16);
+            params.push(entry.category.as_slice());
+            params.push(entry.name.as_slice());
+            params.push(entry.value.value.as_slice());
+            params.push(expiry);
+            params.push(entry.value.compression);
+            params.push(shared_id);
+        }
+        let query = format!(
+            "{INSERT_MANY_QUERY} {} RETURNING id, kind, category, name",
+            multi_row_values::<SqliteBackend>(Some("?1"), batch.len(), 7, 2)
+        );
+        let rows = sqlx::query_with(query.as_str(), params)
+            .fetch_all(active.connection_mut())
+            .await
+            .map_err(err_map!(Backend, "Error inserting new entries"))?;
+        if rows.len() != batch.len() {
+            perform_release_shared(active, shared_ids).await?;
+            return Err(err_msg!(Duplicate, "Duplicate entry"));
+        }
+        let mut row_ids = HashMap::with_capacity(rows.len());
+        for row in rows {
+            let kind: i16 = row.try_get(1)?;
+            let category: Vec<u8> = row.try_get(2)?;
+            let name: Vec<u8> = row.try_get(3)?;
+            row_ids.insert((kind, category, name), row.try_get::<i64, _>(0)?);
+        }
+
+        let mut tags = Vec::new();
+        for entry in batch {
+            let row_id = row_ids
+                .get(&(
+                    entry.kind as i16,
+                    entry.category.clone(),
+                    entry.name.clone(),
+                ))
+                .copied()
+                .ok_or_else(|| err_msg!(Unexpected, "Inserted entry not found"))?;
+            tags.extend(entry.tags.iter().map(|tag| (row_id, tag)));
+        }
+        for tag_batch in tags.chunks(INSERT_TAG_BATCH_SIZE) {
+            let mut params = QueryParams::new();
+            for (row_id, tag) in tag_batch {
+                params.push(*row_id);
+                params.push(tag.name.as_slice());
+                params.push(tag.value.as_slice());
+                params.push(tag.plaintext as i16);
+            }
+            let query = format!(
+                "{TAG_INSERT_MANY_QUERY} {}",
+                multi_row_values::<SqliteBackend>(None, tag_batch.len(), 4, 1)
+            );
+            sqlx::query_with(query.as_str(), params)
+                .execute(active.connection_mut())
+                .await
+                .map_err(err_map!(Backend, "Error inserting entry tags"))?;
+        }
+    }
+    Ok(())
+}
+
+/// Remove all matching records, returning the encrypted removal events
+async fn perform_remove_all(
+    session: &mut DbSession<Sqlite>,
+    kind: Option<EntryKind>,
+    category: Option<&str>,
+    tag_filter: Option<TagFilter>,
+) -> Result<(Arc<ProfileKey>, Vec<EncEntryEvent>), Error> {
+    let enc_category = category.map(|c| ProfileKey::prepare_input(c.as_bytes()));
+    let (profile_id, key) = acquire_key(&mut *session).await?;
+    let mut params = QueryParams::new();
+    params.push(profile_id);
+    params.push(kind.map(|k| k as i16));
+    let (enc_category, tag_filter) = unblock({
+        let key = key.clone();
+        let params_len = params.len() + 1; // plus category
+        move || {
+            Result::<_, Error>::Ok((
+                enc_category
+                    .map(|c| key.encrypt_entry_category(c))
+                    .transpose()?,
+                encode_tag_filter::<SqliteBackend>(tag_filter, &key, params_len)?,
+            ))
+        }
+    })
+    .await?;
+    params.push(enc_category);
+    let query = extend_query::<SqliteBackend>(
+        if session.soft_delete() {
+            SOFT_DELETE_ALL_QUERY
+        } else {
+            DELETE_ALL_QUERY
+        },
+        &mut params,
+        tag_filter,
+        None,
+        None,
+        None,
+    )?;
+
+    let query = format!("{query} RETURNING kind, category, name");
+
+    let mut active = acquire_session(&mut *session).await?;
+    let rows = sqlx::query_with(query.as_str(), params)
+        .fetch_all(active.connection_mut())
+        .await?;
+    let events = rows
+        .into_iter()
+        .map(|row| {
+            let kind: u32 = row.try_get(0)?;
+            Result::<_, Error>::Ok(EncEntryEvent {
+                profile_id,
+                operation: EntryOperation::Remove,
+                kind: EntryKind::try_from(kind as usize)?,
+                category: row.try_get(1)?,
+                name: row.try_get(2)?,
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    active.notify(events.iter().cloned());
+    Ok((key, events))
+}
+
+async fn enforce_profile_limits(
+    active: &mut DbSessionTxn<'_, Sqlite>,
+    add_count: usize,
+    add_value_bytes: usize,
+) -> Result<(), Error> {
+    let row = sqlx::query(PROFILE_LIMITS_QUERY)
+        .bind(active.profile_id)
+        .fetch_one(active.connection_mut())
+        .await
+        .map_err(err_map!(Backend, "Error fetching profile limits"))?;
+    let limits = ProfileLimits {
+        max_entries: row.try_get(0)?,
+        max_value_bytes: row.try_get(1)?,
+    };
+    check_profile_limits(
+        limits,
+        row.try_get(2)?,
+        row.try_get(3)?,
+        add_count,
+        add_value_bytes,
+    )
+}
+
 #[allow(clippy::too_many_arguments)]
 async fn perform_insert(
     active: &mut DbSessionTxn<'_, Sqlite>,
     kind: EntryKind,
     enc_category: &[u8],
     enc_name: &[u8],
-    enc_value: &[u8],
+    enc_value: &EncValue,
     enc_tags: Option<Vec<EncEntryTag>>,
     expiry_ms: Option<i64>,
     new_row: bool,
 ) -> Result<(), Error> {
+    let shared_id = perform_insert_shared(active, enc_value.shared.as_ref()).await?;
     let row_id = if new_row {
         trace!("Insert entry");
+        // a soft-deleted entry is replaced by a new insert
+        sqlx::query(PURGE_QUERY)
+            .bind(active.profile_id)
+            .bind(kind as i16)
+            .bind(enc_category)
+            .bind(enc_name)
+            .execute(active.connection_mut())
+            .await
+            .map_err(err_map!(Backend, "Error removing deleted entry"))?;
+        enforce_profile_limits(active, 1, enc_value.value.len()).await?;
         let done = sqlx::query(INSERT_QUERY)
             .bind(active.profile_id)
             .bind(kind as i16)
             .bind(enc_category)
             .bind(enc_name)
-            .bind(enc_value)
+            .bind(&enc_value.value)
             .bind(expiry_ms.map(expiry_timestamp).transpose()?)
+            .bind(enc_value.compression)
+            .bind(shared_id)
             .execute(active.connection_mut())
             .await
             .map_err(err_map!(Backend, "Error inserting new entry"))?;
         if done.rows_affected() == 0 {
+            perform_release_shared(active, [shared_id]).await?;
             return Err(err_msg!(Duplicate, "Duplicate entry"));
         }
         done.last_insert_rowid()
     } else {
         trace!("Update entry");
-        let row_id: i64 = sqlx::query_scalar(UPDATE_QUERY)
+        let row_id: Option<i64> = sqlx::query_scalar(UPDATE_QUERY)
             .bind(active.profile_id)
             .bind(kind as i16)
             .bind(enc_category)
             .bind(enc_name)
-            .bind(enc_value)
+            .bind(&enc_value.value)
             .bind(expiry_ms.map(expiry_timestamp).transpose()?)
-            .fetch_one(active.connection_mut())
+            .bind(enc_value.compression)
+            .bind(shared_id)
+            .fetch_optional(active.connection_mut())
             .await
             .map_err(|_| err_msg!(NotFound, "Error updating existing entry"))?;
+        let Some(row_id) = row_id else {
+            perform_release_shared(active, [shared_id]).await?;
+            return Err(err_msg!(NotFound, "Error updating existing entry"));
+        };
         sqlx::query(TAG_DELETE_QUERY)
             .bind(row_id)
             .execute(active.connection_mut())
@@ -670,22 +2603,103 @@ async fn perform_insert(
     Ok(())
 }
 
-async fn perform_remove<'q>(
-    active: &mut DbSessionActive<'q, Sqlite>,
+/// Store a shared record value if it is not already present, returning the
+/// identifier of the shared value. Its reference count is maintained by
+/// triggers on the items table.
+async fn perform_insert_shared(
+    active: &mut DbSessionTxn<'_, Sqlite>,
+    shared: Option<&EncSharedValue>,
+) -> Result<Option<i64>, Error> {
+    if let Some(shared) = shared {
+        let shared_id = sqlx::query_scalar(SHARED_INSERT_QUERY)
+            .bind(&shared.hash)
+            .bind(&shared.value)
+            .fetch_one(active.connection_mut())
+            .await
+            .map_err(err_map!(Backend, "Error inserting shared entry value"))?;
+        Ok(Some(shared_id))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Remove newly stored shared values which did not become referenced by an
+/// entry, when an insert or update fails without aborting the transaction
+async fn perform_release_shared(
+    active: &mut DbSessionTxn<'_, Sqlite>,
+    shared_ids: impl IntoIterator<Item = Option<i64>>,
+) -> Result<(), Error> {
+    for shared_id in shared_ids.into_iter().flatten() {
+        sqlx::query(SHARED_RELEASE_QUERY)
+            .bind(shared_id)
+            .execute(active.connection_mut())
+            .await
+            .map_err(err_map!(Backend, "Error removing shared entry value"))?;
+    }
+    Ok(())
+}
+
+async fn perform_update_tags(
+    active: &mut DbSessionTxn<'_, Sqlite>,
     kind: EntryKind,
     enc_category: &[u8],
     enc_name: &[u8],
-    ignore_error: bool,
+    enc_tags: Vec<EncEntryTag>,
+    enc_remove: Vec<Vec<u8>>,
 ) -> Result<(), Error> {
-    trace!("Remove entry");
-    let done = sqlx::query(DELETE_QUERY)
+    trace!("Update entry tags");
+    let row_id: i64 = sqlx::query_scalar(TAG_UPDATE_ITEM_QUERY)
         .bind(active.profile_id)
         .bind(kind as i16)
         .bind(enc_category)
         .bind(enc_name)
-        .execute(active.connection_mut())
+        .fetch_optional(active.connection_mut())
         .await
-        .map_err(err_map!(Backend, "Error removing entry"))?;
+        .map_err(err_map!(Backend, "Error updating entry"))?
+        .ok_or_else(|| err_msg!(NotFound, "Entry not found"))?;
+    for name in enc_remove {
+        sqlx::query(TAG_DELETE_NAME_QUERY)
+            .bind(row_id)
+            .bind(name)
+            .execute(active.connection_mut())
+            .await
+            .map_err(err_map!(Backend, "Error removing entry tags"))?;
+    }
+    for tag in enc_tags {
+        sqlx::query(TAG_INSERT_QUERY)
+            .bind(row_id)
+            .bind(&tag.name)
+            .bind(&tag.value)
+            .bind(tag.plaintext as i16)
+            .execute(active.connection_mut())
+            .await
+            .map_err(err_map!(Backend, "Error inserting entry tags"))?;
+    }
+    Ok(())
+}
+
+async fn perform_remove(
+    conn: &mut SqliteConnection,
+    profile_id: ProfileId,
+    kind: EntryKind,
+    enc_category: &[u8],
+    enc_name: &[u8],
+    soft_delete: bool,
+    ignore_error: bool,
+) -> Result<(), Error> {
+    trace!("Remove entry");
+    let done = sqlx::query(if soft_delete {
+        SOFT_DELETE_QUERY
+    } else {
+        DELETE_QUERY
+    })
+    .bind(profile_id)
+    .bind(kind as i16)
+    .bind(enc_category)
+    .bind(enc_name)
+    .execute(conn)
+    .await
+    .map_err(err_map!(Backend, "Error removing entry"))?;
     if done.rows_affected() == 0 && !ignore_error {
         Err(err_msg!(NotFound, "Entry not found"))
     } else {
@@ -693,6 +2707,58 @@ async fn perform_remove<'q>(
     }
 }
 
+/// Build the query and arguments used to scan the items of a profile
+#[allow(clippy::too_many_arguments)]
+async fn scan_query<'q>(
+    profile_id: ProfileId,
+    key: Arc<ProfileKey>,
+    kind: Option<EntryKind>,
+    category: Option<String>,
+    tag_filter: Option<TagFilter>,
+    offset: Option<i64>,
+    limit: Option<i64>,
+    order_by: Option<OrderBy>,
+    descending: bool,
+    after: Option<ScanToken>,
+) -> Result<(String, QueryParams<'q, Sqlite>), Error> {
+    let mut params = QueryParams::new();
+    params.push(profile_id);
+    params.push(kind.map(|k| k as i16));
+    let (enc_category, tag_filter, order_by) = unblock({
+        let enc_category = category.map(|c| ProfileKey::prepare_input(c.as_bytes()));
+        let params_len = params.len() + 1; // plus category
+        move || {
+            let tag_filter = encode_tag_filter::<SqliteBackend>(tag_filter, &key, params_len)?;
+            let order_len = params_len + tag_filter.as_ref().map(|f| f.1.len()).unwrap_or(0);
+            let (after, order_by) = encode_order_by::<SqliteBackend>(
+                order_by.as_ref(),
+                descending,
+                after.as_ref(),
+                &key,
+                order_len,
+            )?;
+            Result::<_, Error>::Ok((
+                enc_category
+                    .map(|c| key.encrypt_entry_category(c))
+                    .transpose()?,
+                and_filters(tag_filter, after),
+                order_by,
+            ))
+        }
+    })
+    .await?;
+    params.push(enc_category);
+    let query = extend_query::<SqliteBackend>(
+        SCAN_QUERY,
+        &mut params,
+        tag_filter,
+        order_by,
+        offset,
+        limit,
+    )?;
+    Ok((query, params))
+}
+
 #[allow(clippy::too_many_arguments)]
 fn perform_scan(
     mut active: DbSessionRef<'_, Sqlite>,
@@ -703,24 +2769,14 @@ fn perform_scan(
     tag_filter: Option<TagFilter>,
     offset: Option<i64>,
     limit: Option<i64>,
+    order_by: Option<OrderBy>,
+    descending: bool,
+    after: Option<ScanToken>,
 ) -> impl Stream<Item = Result<Vec<EncScanEntry>, Error>> + '_ {
     try_stream! {
-        let mut params = QueryParams::new();
-        params.push(profile_id);
-        params.push(kind.map(|k| k as i16));
-        let (enc_category, tag_filter) = unblock({
-            let key = key.clone();
-            let enc_category = category.as_ref().map(|c| ProfileKey::prepare_input(c.as_bytes()));
-            let params_len = params.len() + 1; // plus category
-            move || {
-                Result::<_, Error>::Ok((
-                    enc_category.map(|c| key.encrypt_entry_category(c)).transpose()?,
-                    encode_tag_filter::<SqliteBackend>(tag_filter, &key, params_len)?
-                ))
-            }
-        }).await?;
-        params.push(enc_category);
-        let query = extend_query::<SqliteBackend>(SCAN_QUERY, &mut params, tag_filter, offset, limit)?;
+        let (query, params) = scan_query(
+            profile_id, key, kind, category, tag_filter, offset, limit, order_by, descending, after
+        ).await?;
 
         let mut batch = Vec::with_capacity(PAGE_SIZE);
 
@@ -730,13 +2786,15 @@ fn perform_scan(
             let kind: u32 = row.try_get(1)?;
             let kind = EntryKind::try_from(kind as usize)?;
             batch.push(EncScanEntry {
-                kind, category: row.try_get(2)?, name: row.try_get(3)?, value: row.try_get(4)?, tags: row.try_get(5)?
+                id: row.try_get(0)?, kind, category: row.try_get(2)?, name: row.try_get(3)?, value: row.try_get(4)?, tags: row.try_get(5)?, version: row.try_get(6)?,
+                compression: row.try_get(7)?, shared: row.try_get(8)?
             });
             if batch.len() == PAGE_SIZE {
                 yield batch.split_off(0);
             }
         }
         drop(rows);
+        drop(acquired);
         if active.is_owned() {
             active.close(false).await?;
         }
@@ -748,10 +2806,143 @@ fn perform_scan(
     }
 }
 
+/// Count the rows of a table matching a filter, removing them unless `dry_run`
+/// is set
+async fn repair_rows(
+    conn: &mut SqliteConnection,
+    table: &str,
+    filter: &str,
+    dry_run: bool,
+) -> Result<i64, Error> {
+    if dry_run {
+        sqlx::query_scalar(&format!("SELECT COUNT(*) FROM {table} WHERE {filter}"))
+            .fetch_one(conn)
+            .await
+            .map_err(err_map!(Backend, "Error checking store rows"))
+    } else {
+        let done = sqlx::query(&format!("DELETE FROM {table} WHERE {filter}"))
+            .execute(conn)
+            .await
+            .map_err(err_map!(Backend, "Error removing store rows"))?;
+        Ok(done.rows_affected() as i64)
+    }
+}
+
+pub(crate) async fn load_rekey_checkpoint(
+    conn: &mut SqliteConnection,
+) -> Result<Option<RekeyCheckpoint>, Error> {
+    let entries: Vec<(String, String)> =
+        sqlx::query_as("SELECT name, value FROM config WHERE name IN (?1, ?2, ?3, ?4)")
+            .bind(REKEY_CONFIG_NAMES[0])
+            .bind(REKEY_CONFIG_NAMES[1])
+            .bind(REKEY_CONFIG_NAMES[2])
+            .bind(REKEY_CONFIG_NAMES[3])
+            .fetch_all(conn)
+            .await
+            .map_err(err_map!(Backend, "Error fetching rekey checkpoint"))?;
+    RekeyCheckpoint::from_config(entries)
+}
+
+/// Re-encrypt the remaining profiles of a rekey in batches, recording the
+/// checkpoint after each batch, before replacing the store key reference
+pub(crate) async fn perform_rekey(
+    conn_pool: &SqlitePool,
+    prev_key: Arc<StoreKey>,
+    store_key: Arc<StoreKey>,
+    mut checkpoint: RekeyCheckpoint,
+    progress: Option<RekeyCallback>,
+) -> Result<(), Error> {
+    let mut conn = conn_pool.acquire().await?;
+    let (mut completed, mut total): (i64, i64) =
+        sqlx::query_as("SELECT COUNT(CASE WHEN id <= ?1 THEN 1 END), COUNT(*) FROM profiles")
+            .bind(checkpoint.profile_id)
+            .fetch_one(conn.as_mut())
+            .await?;
+    if let Some(progress) = progress.as_ref() {
+        progress(RekeyProgress { completed, total });
+    }
+    loop {
+        let mut txn = conn.begin().await?;
+        let rows: Vec<(ProfileId, Vec<u8>, Option<Vec<u8>>)> = sqlx::query_as(
+            "SELECT id, profile_key, metadata FROM profiles
+            WHERE id > ?1 ORDER BY id LIMIT ?2",
+        )
+        .bind(checkpoint.profile_id)
+        .bind(REKEY_BATCH_SIZE)
+        .fetch_all(txn.as_mut())
+        .await?;
+        if rows.is_empty() {
+            if sqlx::query("UPDATE config SET value=?1 WHERE name='key'")
+                .bind(&checkpoint.key_ref)
+                .execute(txn.as_mut())
+                .await?
+                .rows_affected()
+                != 1
+            {
+                return Err(err_msg!(Backend, "Error updating store key"));
+            }
+            sqlx::query("DELETE FROM config WHERE name IN (?1, ?2, ?3, ?4)")
+                .bind(REKEY_CONFIG_NAMES[0])
+                .bind(REKEY_CONFIG_NAMES[1])
+                .bind(REKEY_CONFIG_NAMES[2])
+                .bind(REKEY_CONFIG_NAMES[3])
+                .execute(txn.as_mut())
+                .await?;
+            txn.commit().await?;
+            break;
+        }
+        let count = rows.len() as i64;
+        let last_id = rows[rows.len() - 1].0;
+        let upd_keys = unblock_crypto({
+            let prev_key = prev_key.clone();
+            let store_key = store_key.clone();
+            move || {
+                rows.into_iter()
+                    .map(|(pid, enc_key, enc_metadata)| {
+                        Ok((
+                            pid,
+                            rekey_profile(enc_key, enc_metadata, &prev_key, &store_key)?,
+                        ))
+                    })
+                    .collect::<Result<Vec<_>, Error>>()
+            }
+        })
+        .await?;
+        for (pid, (key, metadata)) in upd_keys {
+            if sqlx::query("UPDATE profiles SET profile_key=?1, metadata=?2 WHERE id=?3")
+                .bind(key)
+                .bind(metadata)
+                .bind(pid)
+                .execute(txn.as_mut())
+                .await?
+                .rows_affected()
+                != 1
+            {
+                return Err(err_msg!(Backend, "Error updating profile key"));
+            }
+        }
+        checkpoint.profile_id = last_id;
+        sqlx::query(CONFIG_UPDATE_QUERY)
+            .bind(REKEY_CONFIG_NAMES[3])
+            .bind(last_id.to_string())
+            .execute(txn.as_mut())
+            .await?;
+        txn.commit().await?;
+        completed += count;
+        total = total.max(completed);
+        if let Some(progress) = progress.as_ref() {
+            progress(RekeyProgress { completed, total });
+        }
+    }
+    conn.return_to_pool().await;
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::backend::db_utils::replace_arg_placeholders;
+    use crate::error::ErrorKind;
     use crate::future::block_on;
     use crate::protect::{generate_raw_store_key, StoreKeyMethod};
 
@@ -778,6 +2969,621 @@ mod tests {
         .unwrap();
     }
 
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn sqlite_value_compression() {
+        use crate::backend::db_utils::{COMPRESSION_NONE, COMPRESSION_ZSTD};
+
+        block_on(async {
+            let key = generate_raw_store_key(None)?;
+            let db = SqliteStoreOptions::new("sqlite://:memory:?compression=zstd")?
+                .provision(StoreKeyMethod::RawKey, key, None, false)
+                .await?;
+            let large = vec![b'a'; 4096];
+            let mut session = db.session(None, false)?;
+            for (name, value) in [("small", &b"value"[..]), ("large", &large)] {
+                session
+                    .update(
+                        EntryKind::Item,
+                        EntryOperation::Insert,
+                        "category",
+                        name,
+                        Some(value),
+                        None,
+                        None,
+                    )
+                    .await?;
+            }
+            let rows: Vec<(i16, i64)> =
+                sqlx::query_as("SELECT compression, LENGTH(value) FROM items ORDER BY id")
+                    .fetch_all(&db.conn_pool)
+                    .await?;
+            assert_eq!(rows[0].0, COMPRESSION_NONE);
+            assert_eq!(rows[1].0, COMPRESSION_ZSTD);
+            assert!(rows[1].1 < 1024);
+
+            let entry = session
+                .fetch(EntryKind::Item, "category", "large", false)
+                .await?
+                .expect("Expected row");
+            assert!(entry.value.as_ref() == &large[..]);
+            let entries = session
+                .fetch_all(Some(EntryKind::Item), Some("category"), None, None, false)
+                .await?;
+            assert_eq!(entries.len(), 2);
+            assert!(entries
+                .iter()
+                .any(|entry| entry.name == "large" && entry.value.as_ref() == &large[..]));
+            Result::<_, Error>::Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn sqlite_value_dedup() {
+        block_on(async {
+            let key = generate_raw_store_key(None)?;
+            let db = SqliteStoreOptions::new("sqlite://:memory:?dedup_threshold=1024")?
+                .provision(StoreKeyMethod::RawKey, key, None, false)
+                .await?;
+            let other = db.create_profile(None).await?;
+            let large = vec![b'a'; 4096];
+            let shared_refs = || async {
+                sqlx::query_as::<_, (i64,)>("SELECT refs FROM items_shared ORDER BY id")
+                    .fetch_all(&db.conn_pool)
+                    .await
+                    .map(|rows| rows.into_iter().map(|row| row.0).collect::<Vec<_>>())
+            };
+
+            for (profile, name, value) in [
+                (None, "one", &large[..]),
+                (None, "two", &large[..]),
+                (None, "small", &b"value"[..]),
+                (Some(other.clone()), "one", &large[..]),
+            ] {
+                db.session(profile, false)?
+                    .update(
+                        EntryKind::Item,
+                        EntryOperation::Insert,
+                        "category",
+                        name,
+                        Some(value),
+                        None,
+                        None,
+                    )
+                    .await?;
+            }
+            assert_eq!(shared_refs().await?, vec![3]);
+
+            for profile in [None, Some(other.clone())] {
+                let entry = db
+                    .session(profile, false)?
+                    .fetch(EntryKind::Item, "category", "one", false)
+                    .await?
+                    .expect("Expected row");
+                assert!(entry.value.as_ref() == &large[..]);
+            }
+            let mut session = db.session(None, false)?;
+            let entries = session
+                .fetch_all(Some(EntryKind::Item), Some("category"), None, None, false)
+                .await?;
+            assert_eq!(entries.len(), 3);
+            assert!(entries
+                .iter()
+                .all(|entry| entry.name == "small" || entry.value.as_ref() == &large[..]));
+
+            // a failed insert does not leave an unreferenced shared value
+            let err = session
+                .update(
+                    EntryKind::Item,
+                    EntryOperation::Insert,
+                    "category",
+                    "one",
+                    Some(&[b'b'; 4096]),
+                    None,
+                    None,
+                )
+                .await
+                .expect_err("Expected duplicate error");
+            assert_eq!(err.kind(), ErrorKind::Duplicate);
+            assert_eq!(shared_refs().await?, vec![3]);
+
+            session
+                .update(
+                    EntryKind::Item,
+                    EntryOperation::Replace,
+                    "category",
+                    "two",
+                    Some(b"value"),
+                    None,
+                    None,
+                )
+                .await?;
+            assert_eq!(shared_refs().await?, vec![2]);
+            db.remove_profile(other).await?;
+            assert_eq!(shared_refs().await?, vec![1]);
+            session
+                .update(
+                    EntryKind::Item,
+                    EntryOperation::Remove,
+                    "category",
+                    "one",
+                    None,
+                    None,
+                    None,
+                )
+                .await?;
+            assert!(shared_refs().await?.is_empty());
+            Result::<_, Error>::Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn sqlite_audit_retention() {
+        block_on(async {
+            let key = generate_raw_store_key(None)?;
+            let db = SqliteStoreOptions::new("sqlite://:memory:?audit=true&audit_retention=3600")?
+                .provision(StoreKeyMethod::RawKey, key, None, false)
+                .await?;
+            let mut session = db.session(None, false)?;
+            session
+                .append_audit(
+                    "insert",
+                    Some(EntryKind::Item),
+                    Some("category"),
+                    Some("one"),
+                    None,
+                )
+                .await?;
+            session
+                .append_audit(
+                    "fetch",
+                    Some(EntryKind::Item),
+                    Some("other"),
+                    Some("two"),
+                    Some("Not found"),
+                )
+                .await?;
+            let records = session
+                .fetch_audit_log(Some("category"), None, None)
+                .await?;
+            assert_eq!(records.len(), 1);
+            assert_eq!(records[0].operation, "insert");
+            assert_eq!(records[0].kind, Some(EntryKind::Item));
+            assert_eq!(records[0].name.as_deref(), Some("one"));
+            assert!(records[0].is_success());
+
+            // records older than the retention period are removed on append
+            sqlx::query("UPDATE audit_log SET created = DATETIME('now', '-2 hours') WHERE id = 1")
+                .execute(&db.conn_pool)
+                .await?;
+            session
+                .append_audit("remove", Some(EntryKind::Item), None, None, None)
+                .await?;
+            let records = session.fetch_audit_log(None, None, None).await?;
+            assert_eq!(records.iter().map(|r| r.id).collect::<Vec<_>>(), vec![2, 3]);
+            assert_eq!(records[0].error.as_deref(), Some("Not found"));
+            assert_eq!(session.fetch_audit_log(None, None, Some(1)).await?.len(), 1);
+            Result::<_, Error>::Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn sqlite_verify_integrity() {
+        block_on(async {
+            let key = generate_raw_store_key(None)?;
+            let db = SqliteStoreOptions::new("sqlite://:memory:")?
+                .provision(StoreKeyMethod::RawKey, key, None, false)
+                .await?;
+            let other = db.create_profile(None).await?;
+            let tags = [EntryTag::Encrypted("tag".to_string(), "value".to_string())];
+            let mut session = db.session(None, false)?;
+            for name in ["one", "two", "three"] {
+                session
+                    .update(
+                        EntryKind::Item,
+                        EntryOperation::Insert,
+                        "category",
+                        name,
+                        Some(b"value"),
+                        Some(&tags),
+                        None,
+                    )
+                    .await?;
+            }
+            let report = db.verify_integrity(false).await?;
+            assert_eq!((report.profiles, report.entries), (2, 3));
+            assert!(report.is_valid());
+
+            sqlx::query("UPDATE items SET value = ZEROBLOB(LENGTH(value)) WHERE id = 1")
+                .execute(&db.conn_pool)
+                .await?;
+            sqlx::query("UPDATE items_tags SET value = ZEROBLOB(LENGTH(value)) WHERE item_id = 2")
+                .execute(&db.conn_pool)
+                .await?;
+            sqlx::query("UPDATE profiles SET profile_key = ZEROBLOB(40) WHERE name = ?1")
+                .bind(&other)
+                .execute(&db.conn_pool)
+                .await?;
+            let report = db.verify_integrity(false).await?;
+            assert_eq!(
+                report
+                    .issues
+                    .iter()
+                    .map(|issue| (issue.id, issue.name.as_deref(), issue.quarantined))
+                    .collect::<Vec<_>>(),
+                vec![
+                    (Some(1), Some("one"), false),
+                    (Some(2), Some("two"), false),
+                    (None, None, false)
+                ]
+            );
+            assert_eq!(report.issues[2].profile, other);
+
+            // quarantined entries are hidden until they are restored
+            let report = db.verify_integrity(true).await?;
+            assert_eq!(report.issues.len(), 3);
+            assert!(report.issues[0].quarantined && report.issues[1].quarantined);
+            assert!(session
+                .fetch(EntryKind::Item, "category", "one", false)
+                .await?
+                .is_none());
+            assert_eq!(
+                session
+                    .fetch_all(Some(EntryKind::Item), None, None, None, false)
+                    .await?
+                    .len(),
+                1
+            );
+            let report = db.verify_integrity(false).await?;
+            assert_eq!((report.entries, report.issues.len()), (1, 1));
+            session.restore(EntryKind::Item, "category", "two").await?;
+            assert!(session
+                .fetch(EntryKind::Item, "category", "two", false)
+                .await
+                .is_err());
+            Result::<_, Error>::Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn sqlite_repair() {
+        block_on(async {
+            let key = generate_raw_store_key(None)?;
+            let db = SqliteStoreOptions::new("sqlite://:memory:?dedup_threshold=16")?
+                .provision(StoreKeyMethod::RawKey, key, None, false)
+                .await?;
+            let mut session = db.session(None, false)?;
+            for name in ["one", "two"] {
+                session
+                    .update(
+                        EntryKind::Item,
+                        EntryOperation::Insert,
+                        "category",
+                        name,
+                        Some(&[b'a'; 64]),
+                        None,
+                        None,
+                    )
+                    .await?;
+            }
+            let report = db.repair(true).await?;
+            assert!(report.is_clean() && !report.reindexed);
+
+            let mut conn = db.conn_pool.acquire().await?;
+            conn.execute(
+                "PRAGMA foreign_keys = OFF;
+                INSERT INTO items (profile_id, kind, category, name, value)
+                    VALUES (99, 2, X'00', X'00', X'00');
+                INSERT INTO items_tags (item_id, name, value, plaintext)
+                    VALUES (99, X'00', X'00', 0), (99, X'01', X'00', 0);
+                INSERT INTO items_chunks (item_id, idx, value) VALUES (99, 0, X'00');
+                INSERT INTO audit_log (profile_id, created, operation)
+                    VALUES (99, DATETIME('now'), 'fetch');
+                UPDATE items_shared SET refs = 5;
+                UPDATE config SET value = 'missing' WHERE name = 'default_profile';
+                PRAGMA foreign_keys = ON;",
+            )
+            .await?;
+            drop(conn);
+
+            let expected = RepairReport {
+                orphan_entries: 1,
+                orphan_tags: 2,
+                orphan_chunks: 1,
+                orphan_audit_records: 1,
+                shared_refs: 1,
+                default_profile: Some(db.get_active_profile()),
+                reindexed: false,
+            };
+            assert_eq!(db.repair(true).await?, expected);
+            assert_eq!(
+                db.repair(false).await?,
+                RepairReport {
+                    reindexed: true,
+                    ..expected
+                }
+            );
+            let report = db.repair(true).await?;
+            assert!(report.is_clean());
+            assert_eq!(db.get_default_profile().await?, db.get_active_profile());
+
+            // the corrected reference count allows shared values to be released
+            for name in ["one", "two"] {
+                session
+                    .update(
+                        EntryKind::Item,
+                        EntryOperation::Remove,
+                        "category",
+                        name,
+                        None,
+                        None,
+                        None,
+                    )
+                    .await?;
+            }
+            let shared: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM items_shared")
+                .fetch_one(&db.conn_pool)
+                .await?;
+            assert_eq!(shared, 0);
+            Result::<_, Error>::Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn sqlite_resume_rekey() {
+        // simulate a rekey interrupted after re-encrypting the first profile
+        async fn interrupt_rekey(
+            fname: &str,
+            key1: PassKey<'_>,
+            key2: PassKey<'_>,
+        ) -> Result<SqliteBackend, Error> {
+            let db = SqliteStoreOptions::new(fname)?
+                .provision(StoreKeyMethod::RawKey, key1, None, false)
+                .await?;
+            for name in ["one", "two"] {
+                db.create_profile(Some(name.to_string())).await?;
+            }
+            let prev_key = db.key_cache.store_key()?;
+            let (store_key, store_key_ref) = StoreKeyMethod::RawKey.resolve(key2)?;
+            let mut checkpoint =
+                RekeyCheckpoint::new(store_key_ref.into_uri(), &prev_key, &store_key)?;
+            let (pid, enc_key, enc_metadata): (ProfileId, Vec<u8>, Option<Vec<u8>>) =
+                sqlx::query_as("SELECT id, profile_key, metadata FROM profiles ORDER BY id")
+                    .fetch_one(&db.conn_pool)
+                    .await?;
+            let (enc_key, _) = rekey_profile(enc_key, enc_metadata, &prev_key, &store_key)?;
+            sqlx::query("UPDATE profiles SET profile_key = ?1 WHERE id = ?2")
+                .bind(enc_key)
+                .bind(pid)
+                .execute(&db.conn_pool)
+                .await?;
+            checkpoint.profile_id = pid;
+            for (name, value) in checkpoint.config_entries() {
+                sqlx::query(CONFIG_UPDATE_QUERY)
+                    .bind(name)
+                    .bind(value)
+                    .execute(&db.conn_pool)
+                    .await?;
+            }
+            Ok(db)
+        }
+
+        async fn check_profiles(db: &SqliteBackend) -> Result<(), Error> {
+            let mut conn = db.conn_pool.acquire().await?;
+            assert!(load_rekey_checkpoint(conn.as_mut()).await?.is_none());
+            drop(conn);
+            let profiles = db.list_profiles().await?;
+            assert_eq!(profiles.len(), 3);
+            for profile in profiles {
+                let mut session = db.session(Some(profile), false)?;
+                session.count(None, None, None).await?;
+                session.close(false).await?;
+            }
+            Ok(())
+        }
+
+        block_on(async {
+            let key1 = generate_raw_store_key(None)?;
+            let key2 = generate_raw_store_key(None)?;
+            let fname = format!("sqlite-resume-rekey-{}.db", uuid::Uuid::new_v4());
+
+            // the rekey is completed when the store is opened using either key
+            for open_key in [&key1, &key2] {
+                interrupt_rekey(&fname, key1.as_ref(), key2.as_ref())
+                    .await?
+                    .close()
+                    .await?;
+                let db = SqliteStoreOptions::new(fname.as_str())?
+                    .open(None, open_key.as_ref(), None)
+                    .await?;
+                check_profiles(&db).await?;
+                db.close().await?;
+                SqliteStoreOptions::new(fname.as_str())?
+                    .open(Some(StoreKeyMethod::RawKey), key2.as_ref(), None)
+                    .await?
+                    .close()
+                    .await?;
+                SqliteStoreOptions::new(fname.as_str())?.remove().await?;
+            }
+
+            // the rekey is resumed only using the same pass key
+            let mut db = interrupt_rekey(&fname, key1.as_ref(), key2.as_ref()).await?;
+            let key3 = generate_raw_store_key(None)?;
+            let err = db
+                .rekey(StoreKeyMethod::RawKey, key3.as_ref())
+                .await
+                .expect_err("Expected rekey conflict");
+            assert_eq!(err.kind(), ErrorKind::Input);
+            let updates = Arc::new(Mutex::new(Vec::new()));
+            db.rekey_with_progress(
+                StoreKeyMethod::RawKey,
+                key2.as_ref(),
+                Some(Arc::new({
+                    let updates = updates.clone();
+                    move |progress| updates.lock().unwrap().push(progress)
+                })),
+            )
+            .await?;
+            assert_eq!(
+                *updates.lock().unwrap(),
+                [
+                    RekeyProgress {
+                        completed: 1,
+                        total: 3
+                    },
+                    RekeyProgress {
+                        completed: 3,
+                        total: 3
+                    }
+                ]
+            );
+            check_profiles(&db).await?;
+            db.close().await?;
+            SqliteStoreOptions::new(fname.as_str())?.remove().await?;
+            Result::<_, Error>::Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn sqlite_io_options() {
+        block_on(async {
+            let fname = format!("sqlite-io-options-{}.db", uuid::Uuid::new_v4());
+            let key = generate_raw_store_key(None)?;
+            let db = SqliteStoreOptions::new(
+                format!("sqlite://{fname}?mmap_size=1048576&cache_size=4096&temp_store=memory")
+                    .as_str(),
+            )?
+            .provision(StoreKeyMethod::RawKey, key, None, false)
+            .await?;
+            let mut conn = db.conn_pool.acquire().await?;
+            let mmap_size: i64 = sqlx::query_scalar("PRAGMA mmap_size")
+                .fetch_one(conn.as_mut())
+                .await?;
+            assert_eq!(mmap_size, 1048576);
+            let cache_size: i64 = sqlx::query_scalar("PRAGMA cache_size")
+                .fetch_one(conn.as_mut())
+                .await?;
+            assert_eq!(cache_size, -4096);
+            let temp_store: i64 = sqlx::query_scalar("PRAGMA temp_store")
+                .fetch_one(conn.as_mut())
+                .await?;
+            assert_eq!(temp_store, 2);
+            drop(conn);
+            db.close().await?;
+            SqliteStoreOptions::new(fname.as_str())?.remove().await?;
+
+            let err = SqliteStoreOptions::new("sqlite://:memory:?temp_store=disk")
+                .expect_err("Expected invalid temp_store option");
+            assert_eq!(err.kind(), ErrorKind::Input);
+            Result::<_, Error>::Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn sqlite_statement_timeout() {
+        const SLOW_QUERY: &str = "WITH RECURSIVE c(x) AS (SELECT 1 UNION ALL
+            SELECT x + 1 FROM c WHERE x < 100000000) SELECT COUNT(*) FROM c";
+
+        block_on(async {
+            let key = generate_raw_store_key(None)?;
+            let db = SqliteStoreOptions::new("sqlite://:memory:?statement_timeout=50")?
+                .provision(StoreKeyMethod::RawKey, key, None, false)
+                .await?;
+            let mut session = db.session(None, false)?;
+            let mut active = acquire_session(&mut session).await?;
+            let err = Error::from(
+                sqlx::query(SLOW_QUERY)
+                    .execute(active.connection_mut())
+                    .await
+                    .expect_err("Expected statement timeout"),
+            );
+            assert_eq!(err.kind(), ErrorKind::Timeout);
+            drop(active);
+
+            // each operation is allowed the full timeout
+            crate::future::sleep(Duration::from_millis(100)).await;
+            let count = session.count(None, None, None).await?;
+            assert_eq!(count, 0);
+            session.close(false).await?;
+            drop(session);
+
+            // the deadline does not apply once the connection is released
+            let mut conn = db.conn_pool.acquire().await?;
+            sqlx::query("SELECT 1").execute(conn.as_mut()).await?;
+            Result::<_, Error>::Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn sqlite_max_profile_sessions() {
+        block_on(async {
+            let key = generate_raw_store_key(None)?;
+            let db = SqliteStoreOptions::new(
+                "sqlite://:memory:?max_connections=4&max_profile_sessions=1",
+            )?
+            .provision(StoreKeyMethod::RawKey, key, Some("a".into()), false)
+            .await?;
+            db.create_profile(Some("b".into())).await?;
+
+            let mut first = db.session(None, false)?;
+            assert_eq!(first.count(None, None, None).await?, 0);
+
+            // a second session for the same profile waits for a slot
+            let mut second = db.session(None, false)?;
+            let waiting =
+                crate::future::timeout(Duration::from_millis(100), second.count(None, None, None))
+                    .await;
+            assert!(waiting.is_none());
+
+            // other profiles are not affected
+            let mut other = db.session(Some("b".into()), false)?;
+            assert_eq!(other.count(None, None, None).await?, 0);
+
+            first.close(false).await?;
+            assert_eq!(second.count(None, None, None).await?, 0);
+            Result::<_, Error>::Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn sqlite_error_backend_code() {
+        block_on(async {
+            let key = generate_raw_store_key(None)?;
+            let db = SqliteStoreOptions::in_memory()
+                .provision(StoreKeyMethod::RawKey, key, None, false)
+                .await?;
+            let profile_id: i64 = sqlx::query_scalar("SELECT id FROM profiles")
+                .fetch_one(&db.conn_pool)
+                .await?;
+            let name: String = sqlx::query_scalar("SELECT name FROM profiles")
+                .fetch_one(&db.conn_pool)
+                .await?;
+            let err = Error::from(
+                sqlx::query("INSERT INTO profiles (id, name) VALUES (?1, ?2)")
+                    .bind(profile_id + 1)
+                    .bind(name)
+                    .execute(&db.conn_pool)
+                    .await
+                    .expect_err("Expected unique constraint violation"),
+            );
+            assert_eq!(err.kind(), ErrorKind::Backend);
+            // SQLITE_CONSTRAINT_UNIQUE
+            assert_eq!(err.backend_code().as_deref(), Some("2067"));
+            assert_eq!(err.sqlstate(), None);
+            Result::<_, Error>::Ok(())
+        })
+        .unwrap();
+    }
+
     #[test]
     fn sqlite_query_placeholders() {
         assert_eq!(
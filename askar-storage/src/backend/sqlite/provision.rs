@@ -1,24 +1,25 @@
 use std::{
-    borrow::Cow, fs::remove_file, io::ErrorKind as IoErrorKind, str::FromStr,
-    thread::available_parallelism, time::Duration,
+    borrow::Cow, fs::remove_file, io::ErrorKind as IoErrorKind, num::NonZeroUsize, str::FromStr,
+    sync::Arc, thread::available_parallelism, time::Duration,
 };
 
 use sqlx::{
+    pool::PoolConnection,
     sqlite::{
-        SqliteAutoVacuum, SqliteConnectOptions, SqliteJournalMode, SqliteLockingMode, SqlitePool,
-        SqlitePoolOptions, SqliteSynchronous,
+        Sqlite, SqliteAutoVacuum, SqliteConnectOptions, SqliteJournalMode, SqliteLockingMode,
+        SqlitePool, SqlitePoolOptions, SqliteSynchronous,
     },
-    ConnectOptions, Error as SqlxError, Row,
+    Acquire, ConnectOptions, Error as SqlxError, Executor, Row,
 };
 
-use super::SqliteBackend;
+use super::{load_rekey_checkpoint, perform_rekey, SqliteBackend, PREPARED_QUERIES};
 use crate::{
     backend::{
-        db_utils::{init_keys, random_profile_name},
-        ManageBackend,
+        db_utils::{decode_profile_access_key, init_keys, random_profile_name, AuditPolicy},
+        Compression, ManageBackend,
     },
     error::Error,
-    future::{sleep, unblock, BoxFuture},
+    future::{sleep, unblock, unblock_crypto, BoxFuture},
     options::{IntoOptions, Options},
     protect::{KeyCache, PassKey, StoreKeyMethod, StoreKeyReference},
 };
@@ -30,6 +31,117 @@ const DEFAULT_BUSY_TIMEOUT: Duration = Duration::from_secs(5);
 const DEFAULT_JOURNAL_MODE: SqliteJournalMode = SqliteJournalMode::Wal;
 const DEFAULT_LOCKING_MODE: SqliteLockingMode = SqliteLockingMode::Normal;
 const DEFAULT_SYNCHRONOUS: SqliteSynchronous = SqliteSynchronous::Full;
+const DEFAULT_STATEMENT_CACHE_CAPACITY: usize = 100;
+
+/// The default maximum number of bytes of the database file accessed using
+/// memory-mapped I/O. Mobile platforms have a limited address space and may
+/// terminate applications for excessive memory use, so the mapping is only
+/// enabled by default on other platforms, where it speeds up read-heavy
+/// workloads considerably.
+#[cfg(any(target_os = "android", target_os = "ios"))]
+const DEFAULT_MMAP_SIZE: u64 = 0;
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+const DEFAULT_MMAP_SIZE: u64 = 256 * 1024 * 1024;
+
+/// The default size of the page cache of each connection, in KiB
+#[cfg(any(target_os = "android", target_os = "ios"))]
+const DEFAULT_CACHE_SIZE_KIB: u64 = 2 * 1024;
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+const DEFAULT_CACHE_SIZE_KIB: u64 = 16 * 1024;
+
+/// The current version of the store schema
+const SCHEMA_VERSION: u32 = 12;
+
+/// Statements applied in order to upgrade a store from each prior schema version
+const SCHEMA_UPGRADES: &[&str] = &[
+    // version 1 -> 2
+    "ALTER TABLE items ADD COLUMN deleted DATETIME NULL",
+    // version 2 -> 3
+    r#"
+        CREATE VIRTUAL TABLE items_tags_fts USING fts5(
+            value, content='items_tags', content_rowid='id'
+        );
+        CREATE TRIGGER tr_items_tags_fts_insert AFTER INSERT ON items_tags
+            WHEN new.plaintext=1 BEGIN
+            INSERT INTO items_tags_fts (rowid, value) VALUES (new.id, new.value);
+        END;
+        CREATE TRIGGER tr_items_tags_fts_delete AFTER DELETE ON items_tags
+            WHEN old.plaintext=1 BEGIN
+            INSERT INTO items_tags_fts (items_tags_fts, rowid, value)
+                VALUES ('delete', old.id, old.value);
+        END;
+        INSERT INTO items_tags_fts (rowid, value)
+            SELECT id, value FROM items_tags WHERE plaintext=1;
+    "#,
+    // version 3 -> 4
+    "ALTER TABLE items ADD COLUMN version INTEGER NOT NULL DEFAULT 1",
+    // version 4 -> 5
+    "ALTER TABLE profiles ADD COLUMN created DATETIME NULL;
+    ALTER TABLE profiles ADD COLUMN metadata BLOB NULL;",
+    // version 5 -> 6
+    "ALTER TABLE profiles ADD COLUMN max_entries INTEGER NULL;
+    ALTER TABLE profiles ADD COLUMN max_value_bytes INTEGER NULL;",
+    // version 6 -> 7
+    "ALTER TABLE profiles ADD COLUMN access_key BLOB NULL;
+    ALTER TABLE profiles ADD COLUMN access_key_ref TEXT NULL;",
+    // version 7 -> 8
+    "CREATE TABLE items_chunks (
+        item_id INTEGER NOT NULL,
+        idx INTEGER NOT NULL,
+        value BLOB NOT NULL,
+        PRIMARY KEY (item_id, idx),
+        FOREIGN KEY (item_id) REFERENCES items (id)
+            ON DELETE CASCADE ON UPDATE CASCADE
+    );",
+    // version 8 -> 9
+    "ALTER TABLE items ADD COLUMN compression INTEGER NOT NULL DEFAULT 0",
+    // version 9 -> 10
+    r#"
+        CREATE TABLE items_shared (
+            id INTEGER NOT NULL,
+            hash BLOB NOT NULL,
+            value BLOB NOT NULL,
+            refs INTEGER NOT NULL DEFAULT 0,
+            PRIMARY KEY (id)
+        );
+        CREATE UNIQUE INDEX ix_items_shared_hash ON items_shared (hash);
+        ALTER TABLE items ADD COLUMN shared_id INTEGER NULL;
+        CREATE TRIGGER tr_items_shared_insert AFTER INSERT ON items
+            WHEN new.shared_id IS NOT NULL BEGIN
+            UPDATE items_shared SET refs = refs + 1 WHERE id = new.shared_id;
+        END;
+        CREATE TRIGGER tr_items_shared_update AFTER UPDATE OF shared_id ON items
+            WHEN new.shared_id IS NOT old.shared_id BEGIN
+            UPDATE items_shared SET refs = refs + 1 WHERE id = new.shared_id;
+            UPDATE items_shared SET refs = refs - 1 WHERE id = old.shared_id;
+            DELETE FROM items_shared WHERE id = old.shared_id AND refs <= 0;
+        END;
+        CREATE TRIGGER tr_items_shared_delete AFTER DELETE ON items
+            WHEN old.shared_id IS NOT NULL BEGIN
+            UPDATE items_shared SET refs = refs - 1 WHERE id = old.shared_id;
+            DELETE FROM items_shared WHERE id = old.shared_id AND refs <= 0;
+        END;
+    "#,
+    // version 10 -> 11
+    r#"
+        CREATE TABLE audit_log (
+            id INTEGER NOT NULL,
+            profile_id INTEGER NOT NULL,
+            created DATETIME NOT NULL,
+            operation TEXT NOT NULL,
+            kind INTEGER NULL,
+            category BLOB NULL,
+            name BLOB NULL,
+            error TEXT NULL,
+            PRIMARY KEY (id)
+        );
+        CREATE INDEX ix_audit_log_profile ON audit_log (profile_id, category);
+        CREATE INDEX ix_audit_log_created ON audit_log (created);
+    "#,
+    // version 11 -> 12
+    "DROP INDEX IF EXISTS ix_items_tags_item_id;
+    CREATE INDEX ix_items_tags_item_name ON items_tags (item_id, name, plaintext);",
+];
 
 /// Configuration options for Sqlite stores
 #[derive(Debug)]
@@ -43,6 +155,50 @@ pub struct SqliteStoreOptions {
     pub(crate) locking_mode: SqliteLockingMode,
     pub(crate) shared_cache: bool,
     pub(crate) synchronous: SqliteSynchronous,
+    pub(crate) soft_delete: bool,
+    pub(crate) upgrade: bool,
+    pub(crate) compression: Compression,
+    pub(crate) dedup_threshold: Option<usize>,
+    pub(crate) audit: Option<AuditPolicy>,
+    pub(crate) session_idle_timeout: Option<Duration>,
+    pub(crate) statement_cache_capacity: usize,
+    pub(crate) prewarm: bool,
+    pub(crate) mmap_size: u64,
+    pub(crate) cache_size: u64,
+    pub(crate) temp_store: SqliteTempStore,
+    pub(crate) statement_timeout: Option<Duration>,
+    pub(crate) max_profile_sessions: Option<usize>,
+}
+
+/// The storage used for temporary tables and indexes
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum SqliteTempStore {
+    /// Use the compile-time default of SQLite
+    #[default]
+    Default,
+    /// Store temporary data in files
+    File,
+    /// Store temporary data in memory
+    Memory,
+}
+
+impl SqliteTempStore {
+    fn from_option(value: &str) -> Result<Self, Error> {
+        match value.to_ascii_lowercase().as_str() {
+            "default" => Ok(Self::Default),
+            "file" => Ok(Self::File),
+            "memory" => Ok(Self::Memory),
+            _ => Err(err_msg!(Input, "Error parsing 'temp_store' parameter")),
+        }
+    }
+
+    fn as_pragma(&self) -> &'static str {
+        match self {
+            Self::Default => "DEFAULT",
+            Self::File => "FILE",
+            Self::Memory => "MEMORY",
+        }
+    }
 }
 
 impl Default for SqliteStoreOptions {
@@ -112,6 +268,113 @@ impl SqliteStoreOptions {
         } else {
             DEFAULT_SYNCHRONOUS
         };
+        let soft_delete = if let Some(soft) = opts.query.remove("soft_delete") {
+            soft.parse()
+                .map_err(err_map!(Input, "Error parsing 'soft_delete' parameter"))?
+        } else {
+            false
+        };
+        let upgrade = if let Some(upgrade) = opts.query.remove("upgrade") {
+            upgrade
+                .parse()
+                .map_err(err_map!(Input, "Error parsing 'upgrade' parameter"))?
+        } else {
+            false
+        };
+        let compression = if let Some(compression) = opts.query.remove("compression") {
+            Compression::from_option(&compression)?
+        } else {
+            Compression::None
+        };
+        let dedup_threshold = if let Some(threshold) = opts.query.remove("dedup_threshold") {
+            Some(
+                threshold
+                    .parse()
+                    .map_err(err_map!(Input, "Error parsing 'dedup_threshold' parameter"))?,
+            )
+        } else {
+            None
+        };
+        let audit_enabled = if let Some(audit) = opts.query.remove("audit") {
+            audit
+                .parse()
+                .map_err(err_map!(Input, "Error parsing 'audit' parameter"))?
+        } else {
+            false
+        };
+        let audit_retention = if let Some(retention) = opts.query.remove("audit_retention") {
+            Some(Duration::from_secs(retention.parse().map_err(err_map!(
+                Input,
+                "Error parsing 'audit_retention' parameter"
+            ))?))
+        } else {
+            None
+        };
+        let audit = audit_enabled.then_some(AuditPolicy {
+            retention: audit_retention,
+        });
+        let session_idle_timeout = if let Some(timeout) = opts.query.remove("session_idle_timeout")
+        {
+            Some(Duration::from_secs(timeout.parse().map_err(err_map!(
+                Input,
+                "Error parsing 'session_idle_timeout' parameter"
+            ))?))
+        } else {
+            None
+        };
+        let statement_cache_capacity =
+            if let Some(capacity) = opts.query.remove("statement_cache_capacity") {
+                capacity.parse().map_err(err_map!(
+                    Input,
+                    "Error parsing 'statement_cache_capacity' parameter"
+                ))?
+            } else {
+                DEFAULT_STATEMENT_CACHE_CAPACITY
+            };
+        let prewarm = if let Some(prewarm) = opts.query.remove("prewarm") {
+            prewarm
+                .parse()
+                .map_err(err_map!(Input, "Error parsing 'prewarm' parameter"))?
+        } else {
+            false
+        };
+        let mmap_size = if let Some(size) = opts.query.remove("mmap_size") {
+            size.parse()
+                .map_err(err_map!(Input, "Error parsing 'mmap_size' parameter"))?
+        } else {
+            DEFAULT_MMAP_SIZE
+        };
+        let cache_size = if let Some(size) = opts.query.remove("cache_size") {
+            size.parse()
+                .map_err(err_map!(Input, "Error parsing 'cache_size' parameter"))?
+        } else {
+            DEFAULT_CACHE_SIZE_KIB
+        };
+        let temp_store = if let Some(store) = opts.query.remove("temp_store") {
+            SqliteTempStore::from_option(&store)?
+        } else {
+            SqliteTempStore::Default
+        };
+        let statement_timeout = if let Some(timeout) = opts.query.remove("statement_timeout") {
+            Some(Duration::from_millis(timeout.parse().map_err(err_map!(
+                Input,
+                "Error parsing 'statement_timeout' parameter"
+            ))?))
+        } else {
+            None
+        };
+        let max_profile_sessions = if let Some(max) = opts.query.remove("max_profile_sessions") {
+            Some(
+                max.parse::<NonZeroUsize>()
+                    .map_err(err_map!(
+                        Input,
+                        "Error parsing 'max_profile_sessions' parameter"
+                    ))?
+                    .get(),
+            )
+        } else {
+            None
+        };
 
         Ok(Self {
             in_memory,
@@ -123,11 +386,23 @@ impl SqliteStoreOptions {
             locking_mode,
             shared_cache,
             synchronous,
+            soft_delete,
+            upgrade,
+            compression,
+            dedup_threshold,
+            audit,
+            session_idle_timeout,
+            statement_cache_capacity,
+            prewarm,
+            mmap_size,
+            cache_size,
+            temp_store,
+            statement_timeout,
+            max_profile_sessions,
         })
     }
 
     async fn pool(&self, auto_create: bool) -> std::result::Result<SqlitePool, SqlxError> {
-        #[allow(unused_mut)]
         let mut conn_opts = SqliteConnectOptions::from_str(self.path.as_ref())?
             .create_if_missing(auto_create)
             .auto_vacuum(SqliteAutoVacuum::Incremental)
@@ -135,22 +410,40 @@ impl SqliteStoreOptions {
             .journal_mode(self.journal_mode)
             .locking_mode(self.locking_mode)
             .shared_cache(self.shared_cache)
+            .statement_cache_capacity(self.statement_cache_capacity)
             .synchronous(self.synchronous);
+        if !self.in_memory {
+            // a negative cache size is interpreted as a number of KiB
+            conn_opts = conn_opts
+                .pragma("mmap_size", self.mmap_size.to_string())
+                .pragma("cache_size", format!("-{}", self.cache_size));
+        }
+        if self.temp_store != SqliteTempStore::Default {
+            conn_opts = conn_opts.pragma("temp_store", self.temp_store.as_pragma());
+        }
         #[cfg(feature = "log")]
         {
             conn_opts = conn_opts
                 .log_statements(log::LevelFilter::Debug)
                 .log_slow_statements(log::LevelFilter::Debug, Default::default());
         }
-        SqlitePoolOptions::default()
+        let mut pool_opts = SqlitePoolOptions::default()
             // maintains at least 1 connection.
             // for an in-memory database this is required to avoid dropping the database,
             // for a file database this signals other instances that the database is in use
             .min_connections(self.min_connections)
             .max_connections(self.max_connections)
-            .test_before_acquire(false)
-            .connect_with(conn_opts)
-            .await
+            .test_before_acquire(false);
+        if self.statement_timeout.is_some() {
+            pool_opts = pool_opts.after_release(|conn, _meta| {
+                Box::pin(async move {
+                    // clear the deadline of the last operation
+                    conn.lock_handle().await?.remove_progress_handler();
+                    Ok(true)
+                })
+            });
+        }
+        pool_opts.connect_with(conn_opts).await
     }
 
     /// Provision a new Sqlite store from these configuration options
@@ -185,9 +478,21 @@ impl SqliteStoreOptions {
                     Some(method),
                     pass_key,
                     profile,
+                    false,
                     self.path.to_string(),
+                    self.soft_delete,
+                    self.upgrade,
                 )
-                .await;
+                .await
+                .map(|backend| {
+                    backend
+                        .with_session_idle_timeout(self.session_idle_timeout)
+                        .with_compression(self.compression)
+                        .with_dedup_threshold(self.dedup_threshold)
+                        .with_audit(self.audit)
+                        .with_statement_timeout(self.statement_timeout)
+                        .with_max_profile_sessions(self.max_profile_sessions)
+                });
             }
         }
         // else: no 'config' table, assume empty database
@@ -200,7 +505,14 @@ impl SqliteStoreOptions {
             default_profile,
             key_cache,
             self.path.to_string(),
-        ))
+            self.soft_delete,
+        )
+        .with_session_idle_timeout(self.session_idle_timeout)
+        .with_compression(self.compression)
+        .with_dedup_threshold(self.dedup_threshold)
+        .with_audit(self.audit)
+        .with_statement_timeout(self.statement_timeout)
+        .with_max_profile_sessions(self.max_profile_sessions))
     }
 
     /// Open an existing Sqlite store from this set of configuration options
@@ -210,7 +522,73 @@ impl SqliteStoreOptions {
         pass_key: PassKey<'_>,
         profile: Option<String>,
     ) -> Result<SqliteBackend, Error> {
-        let conn_pool = match self.pool(false).await {
+        let conn_pool = self.open_pool().await?;
+        let backend = open_db(
+            conn_pool,
+            method,
+            pass_key,
+            profile,
+            false,
+            self.path.to_string(),
+            self.soft_delete,
+            self.upgrade,
+        )
+        .await?
+        .with_session_idle_timeout(self.session_idle_timeout)
+        .with_compression(self.compression)
+        .with_dedup_threshold(self.dedup_threshold)
+        .with_audit(self.audit)
+        .with_statement_timeout(self.statement_timeout)
+        .with_max_profile_sessions(self.max_profile_sessions);
+        if self.prewarm {
+            prewarm_pool(
+                &backend.conn_pool,
+                self.min_connections,
+                self.statement_cache_capacity > 0,
+            )
+            .await?;
+        }
+        Ok(backend)
+    }
+
+    /// Open an existing Sqlite store scoped to a single profile, using the
+    /// profile-specific pass key
+    pub async fn open_profile(
+        self,
+        profile: String,
+        pass_key: PassKey<'_>,
+    ) -> Result<SqliteBackend, Error> {
+        let conn_pool = self.open_pool().await?;
+        let backend = open_db(
+            conn_pool,
+            None,
+            pass_key,
+            Some(profile),
+            true,
+            self.path.to_string(),
+            self.soft_delete,
+            self.upgrade,
+        )
+        .await?
+        .with_session_idle_timeout(self.session_idle_timeout)
+        .with_compression(self.compression)
+        .with_dedup_threshold(self.dedup_threshold)
+        .with_audit(self.audit)
+        .with_statement_timeout(self.statement_timeout)
+        .with_max_profile_sessions(self.max_profile_sessions);
+        if self.prewarm {
+            prewarm_pool(
+                &backend.conn_pool,
+                self.min_connections,
+                self.statement_cache_capacity > 0,
+            )
+            .await?;
+        }
+        Ok(backend)
+    }
+
+    async fn open_pool(&self) -> Result<SqlitePool, Error> {
+        match self.pool(false).await {
             Ok(pool) => Ok(pool),
             Err(SqlxError::Database(db_err)) => {
                 if db_err.code().expect("Expected SQLite error code") == "14" {
@@ -225,8 +603,7 @@ impl SqliteStoreOptions {
                 }
             }
             Err(err) => Err(err.into()),
-        }?;
-        open_db(conn_pool, method, pass_key, profile, self.path.to_string()).await
+        }
     }
 
     /// Remove the Sqlite store defined by these configuration options
@@ -265,6 +642,14 @@ impl<'a> ManageBackend<'a> for SqliteStoreOptions {
         Box::pin(self.open(method, pass_key, profile))
     }
 
+    fn open_profile_backend(
+        self,
+        profile: String,
+        pass_key: PassKey<'a>,
+    ) -> BoxFuture<'a, Result<SqliteBackend, Error>> {
+        Box::pin(self.open_profile(profile, pass_key))
+    }
+
     fn provision_backend(
         self,
         method: StoreKeyMethod,
@@ -286,7 +671,7 @@ async fn init_db(
     method: StoreKeyMethod,
     pass_key: PassKey<'_>,
 ) -> Result<KeyCache, Error> {
-    let (profile_key, enc_profile_key, store_key, store_key_ref) = unblock({
+    let (profile_key, enc_profile_key, store_key, store_key_ref) = unblock_crypto({
         let pass_key = pass_key.into_owned();
         move || init_keys(method, pass_key)
     })
@@ -306,13 +691,19 @@ async fn init_db(
         INSERT INTO config (name, value) VALUES
             ("default_profile", ?1),
             ("key", ?2),
-            ("version", "1");
+            ("version", ?4);
 
         CREATE TABLE profiles (
             id INTEGER NOT NULL,
             name TEXT NOT NULL,
             reference TEXT NULL,
             profile_key BLOB NULL,
+            created DATETIME NULL,
+            metadata BLOB NULL,
+            max_entries INTEGER NULL,
+            max_value_bytes INTEGER NULL,
+            access_key BLOB NULL,
+            access_key_ref TEXT NULL,
             PRIMARY KEY(id)
         );
         CREATE UNIQUE INDEX ix_profile_name ON profiles (name);
@@ -325,6 +716,10 @@ async fn init_db(
             name BLOB NOT NULL,
             value BLOB NOT NULL,
             expiry DATETIME NULL,
+            deleted DATETIME NULL,
+            version INTEGER NOT NULL DEFAULT 1,
+            compression INTEGER NOT NULL DEFAULT 0,
+            shared_id INTEGER NULL,
             PRIMARY KEY (id),
             FOREIGN KEY (profile_id) REFERENCES profiles (id)
                 ON DELETE CASCADE ON UPDATE CASCADE
@@ -341,11 +736,71 @@ async fn init_db(
             FOREIGN KEY (item_id) REFERENCES items (id)
                 ON DELETE CASCADE ON UPDATE CASCADE
         );
-        CREATE INDEX ix_items_tags_item_id ON items_tags (item_id);
+        CREATE INDEX ix_items_tags_item_name ON items_tags (item_id, name, plaintext);
         CREATE INDEX ix_items_tags_name_enc ON items_tags (name, SUBSTR(value, 1, 12)) WHERE plaintext=0;
         CREATE INDEX ix_items_tags_name_plain ON items_tags (name, value) WHERE plaintext=1;
 
-        INSERT INTO profiles (name, profile_key) VALUES (?1, ?3);
+        CREATE TABLE items_chunks (
+            item_id INTEGER NOT NULL,
+            idx INTEGER NOT NULL,
+            value BLOB NOT NULL,
+            PRIMARY KEY (item_id, idx),
+            FOREIGN KEY (item_id) REFERENCES items (id)
+                ON DELETE CASCADE ON UPDATE CASCADE
+        );
+
+        CREATE TABLE items_shared (
+            id INTEGER NOT NULL,
+            hash BLOB NOT NULL,
+            value BLOB NOT NULL,
+            refs INTEGER NOT NULL DEFAULT 0,
+            PRIMARY KEY (id)
+        );
+        CREATE UNIQUE INDEX ix_items_shared_hash ON items_shared (hash);
+        CREATE TRIGGER tr_items_shared_insert AFTER INSERT ON items
+            WHEN new.shared_id IS NOT NULL BEGIN
+            UPDATE items_shared SET refs = refs + 1 WHERE id = new.shared_id;
+        END;
+        CREATE TRIGGER tr_items_shared_update AFTER UPDATE OF shared_id ON items
+            WHEN new.shared_id IS NOT old.shared_id BEGIN
+            UPDATE items_shared SET refs = refs + 1 WHERE id = new.shared_id;
+            UPDATE items_shared SET refs = refs - 1 WHERE id = old.shared_id;
+            DELETE FROM items_shared WHERE id = old.shared_id AND refs <= 0;
+        END;
+        CREATE TRIGGER tr_items_shared_delete AFTER DELETE ON items
+            WHEN old.shared_id IS NOT NULL BEGIN
+            UPDATE items_shared SET refs = refs - 1 WHERE id = old.shared_id;
+            DELETE FROM items_shared WHERE id = old.shared_id AND refs <= 0;
+        END;
+
+        CREATE TABLE audit_log (
+            id INTEGER NOT NULL,
+            profile_id INTEGER NOT NULL,
+            created DATETIME NOT NULL,
+            operation TEXT NOT NULL,
+            kind INTEGER NULL,
+            category BLOB NULL,
+            name BLOB NULL,
+            error TEXT NULL,
+            PRIMARY KEY (id)
+        );
+        CREATE INDEX ix_audit_log_profile ON audit_log (profile_id, category);
+        CREATE INDEX ix_audit_log_created ON audit_log (created);
+
+        CREATE VIRTUAL TABLE items_tags_fts USING fts5(
+            value, content='items_tags', content_rowid='id'
+        );
+        CREATE TRIGGER tr_items_tags_fts_insert AFTER INSERT ON items_tags
+            WHEN new.plaintext=1 BEGIN
+            INSERT INTO items_tags_fts (rowid, value) VALUES (new.id, new.value);
+        END;
+        CREATE TRIGGER tr_items_tags_fts_delete AFTER DELETE ON items_tags
+            WHEN old.plaintext=1 BEGIN
+            INSERT INTO items_tags_fts (items_tags_fts, rowid, value)
+                VALUES ('delete', old.id, old.value);
+        END;
+
+        INSERT INTO profiles (name, profile_key, created) VALUES (?1, ?3, DATETIME('now'));
 
         COMMIT;
     "#,
@@ -354,6 +809,7 @@ async fn init_db(
     .bind(profile_name)
     .bind(store_key_ref)
     .bind(enc_profile_key)
+    .bind(SCHEMA_VERSION.to_string())
     .execute(conn.as_mut())
     .await.map_err(err_map!(Backend, "Error creating database tables"))?;
 
@@ -371,15 +827,41 @@ async fn init_db(
     Ok(key_cache)
 }
 
+/// Establish the minimum number of connections for the pool in advance of
+/// their first use, optionally preparing the frequently used statements on
+/// each connection.
+async fn prewarm_pool(pool: &SqlitePool, min_connections: u32, prepare: bool) -> Result<(), Error> {
+    // connections are held until all have been established, so that a new
+    // connection is opened for each
+    let mut conns = Vec::with_capacity(min_connections as usize);
+    for _ in 0..min_connections.max(1) {
+        let mut conn = pool.acquire().await?;
+        if prepare {
+            for query in PREPARED_QUERIES {
+                conn.as_mut()
+                    .prepare(query)
+                    .await
+                    .map_err(err_map!(Backend, "Error preparing statement"))?;
+            }
+        }
+        conns.push(conn);
+    }
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
 async fn open_db(
     conn_pool: SqlitePool,
     method: Option<StoreKeyMethod>,
     pass_key: PassKey<'_>,
     profile: Option<String>,
+    scoped: bool,
     path: String,
+    soft_delete: bool,
+    upgrade: bool,
 ) -> Result<SqliteBackend, Error> {
     let mut conn = conn_pool.acquire().await?;
-    let mut ver_ok = false;
+    let mut version: Option<u32> = None;
     let mut default_profile: Option<String> = None;
     let mut store_key_ref: Option<String> = None;
 
@@ -399,34 +881,105 @@ async fn open_db(
                 store_key_ref.replace(row.try_get(1)?);
             }
             "version" => {
-                if row.try_get::<&str, _>(1)? != "1" {
-                    return Err(err_msg!(Unsupported, "Unsupported store version"));
-                }
-                ver_ok = true;
+                version.replace(
+                    row.try_get::<&str, _>(1)?
+                        .parse()
+                        .map_err(|_| err_msg!(Unsupported, "Unsupported store version"))?,
+                );
             }
             _ => (),
         }
     }
-    if !ver_ok {
-        return Err(err_msg!(Unsupported, "Store version not found"));
+    match version {
+        Some(ver) if ver == SCHEMA_VERSION => (),
+        Some(ver) if ver > 0 && ver < SCHEMA_VERSION => {
+            // upgrading prevents the store from being opened by earlier
+            // releases, so it is only performed when requested
+            if !upgrade {
+                return Err(err_msg!(
+                    Unsupported,
+                    "Store schema version {} requires an upgrade to version {}, \
+                    which is applied when the store is opened with the 'upgrade' option",
+                    ver,
+                    SCHEMA_VERSION
+                ));
+            }
+            upgrade_db(&mut conn, ver).await?
+        }
+        Some(_) => return Err(err_msg!(Unsupported, "Unsupported store version")),
+        None => return Err(err_msg!(Unsupported, "Store version not found")),
     }
     let profile = profile
         .or(default_profile)
         .ok_or_else(|| err_msg!(Unsupported, "Default store profile not found"))?;
-    let store_key = if let Some(store_key_ref) = store_key_ref {
-        let wrap_ref = StoreKeyReference::parse_uri(&store_key_ref)?;
+    if scoped {
+        let row =
+            sqlx::query("SELECT id, access_key, access_key_ref FROM profiles WHERE name = ?1")
+                .bind(&profile)
+                .fetch_optional(conn.as_mut())
+                .await?
+                .ok_or_else(|| err_msg!(NotFound, "Profile not found"))?;
+        let profile_id = row.try_get(0)?;
+        let (enc_key, access_key_ref) = (row.try_get(1)?, row.try_get(2)?);
+        conn.return_to_pool().await;
+        let profile_key = unblock_crypto({
+            let pass_key = pass_key.into_owned();
+            move || decode_profile_access_key(enc_key, access_key_ref, pass_key)
+        })
+        .await?;
+        let mut key_cache = KeyCache::new_scoped();
+        key_cache.add_profile_mut(profile.clone(), profile_id, profile_key);
+        return Ok(SqliteBackend::new(
+            conn_pool,
+            profile,
+            key_cache,
+            path,
+            soft_delete,
+        ));
+    }
+    let store_key_ref =
+        store_key_ref.ok_or_else(|| err_msg!(Unsupported, "Store key not found"))?;
+    let store_key = if let Some(checkpoint) = load_rekey_checkpoint(conn.as_mut()).await? {
+        // complete an interrupted rekey, accepting the pass key for either store key
         if let Some(method) = method {
-            if !wrap_ref.compare_method(&method) {
+            if !StoreKeyReference::parse_uri(&store_key_ref)?.compare_method(&method)
+                && !StoreKeyReference::parse_uri(&checkpoint.key_ref)?.compare_method(&method)
+            {
                 return Err(err_msg!(Input, "Store key method mismatch"));
             }
         }
-        unblock({
+        let (prev_key, store_key) = unblock_crypto({
             let pass_key = pass_key.into_owned();
-            move || wrap_ref.resolve(pass_key)
+            let checkpoint = checkpoint.clone();
+            move || checkpoint.resolve(&store_key_ref, pass_key)
         })
-        .await?
+        .await?;
+        let store_key = Arc::new(store_key);
+        conn.return_to_pool().await;
+        perform_rekey(
+            &conn_pool,
+            Arc::new(prev_key),
+            store_key.clone(),
+            checkpoint,
+            None,
+        )
+        .await?;
+        conn = conn_pool.acquire().await?;
+        store_key
     } else {
-        return Err(err_msg!(Unsupported, "Store key not found"));
+        let wrap_ref = StoreKeyReference::parse_uri(&store_key_ref)?;
+        if let Some(method) = method {
+            if !wrap_ref.compare_method(&method) {
+                return Err(err_msg!(Input, "Store key method mismatch"));
+            }
+        }
+        Arc::new(
+            unblock_crypto({
+                let pass_key = pass_key.into_owned();
+                move || wrap_ref.resolve(pass_key)
+            })
+            .await?,
+        )
     };
 
     let mut key_cache = KeyCache::new(store_key);
@@ -439,7 +992,32 @@ async fn open_db(
     conn.return_to_pool().await;
     key_cache.add_profile_mut(profile.clone(), profile_id, profile_key);
 
-    Ok(SqliteBackend::new(conn_pool, profile, key_cache, path))
+    Ok(SqliteBackend::new(
+        conn_pool,
+        profile,
+        key_cache,
+        path,
+        soft_delete,
+    ))
+}
+
+async fn upgrade_db(conn: &mut PoolConnection<Sqlite>, version: u32) -> Result<(), Error> {
+    debug!("Upgrade store schema from version {version} to {SCHEMA_VERSION}");
+    let mut txn = conn.begin().await?;
+    for upgrade in &SCHEMA_UPGRADES[(version as usize - 1)..] {
+        sqlx::query(upgrade)
+            .persistent(false)
+            .execute(txn.as_mut())
+            .await
+            .map_err(err_map!(Backend, "Error upgrading store schema"))?;
+    }
+    sqlx::query("UPDATE config SET value = ?1 WHERE name = 'version'")
+        .bind(SCHEMA_VERSION.to_string())
+        .execute(txn.as_mut())
+        .await
+        .map_err(err_map!(Backend, "Error updating store version"))?;
+    txn.commit().await?;
+    Ok(())
 }
 
 async fn try_remove_file(path: String) -> Result<bool, Error> {
@@ -1,16 +1,22 @@
 //! Entry type definitions
 
 use std::{
+    collections::VecDeque,
     fmt::{self, Debug, Formatter},
     pin::Pin,
     str::FromStr,
+    task::{Context, Poll},
 };
 
 use futures_lite::stream::{Stream, StreamExt};
+use sha2::{Digest, Sha256};
 use zeroize::Zeroize;
 
 use super::wql;
-use crate::{crypto::buffer::SecretBytes, error::Error};
+use crate::{
+    crypto::{buffer::SecretBytes, subtle::ConstantTimeEq},
+    error::Error,
+};
 
 pub(crate) fn sorted_tags(tags: &Vec<EntryTag>) -> Vec<&EntryTag> {
     if tags.is_empty() {
@@ -39,6 +45,11 @@ pub struct Entry {
 
     /// Tags associated with the entry record
     pub tags: Vec<EntryTag>,
+
+    /// The version of the entry record, starting at one (1) when the record
+    /// is created and incremented on each update. Zero (0) for entries which
+    /// have not been loaded from the store.
+    pub version: i64,
 }
 
 impl Entry {
@@ -57,9 +68,17 @@ impl Entry {
             name: name.into(),
             value: value.into(),
             tags,
+            version: 0,
         }
     }
 
+    /// Set the version of the entry record
+    #[inline]
+    pub fn with_version(mut self, version: i64) -> Self {
+        self.version = version;
+        self
+    }
+
     pub(crate) fn sorted_tags(&self) -> Vec<&EntryTag> {
         sorted_tags(&self.tags)
     }
@@ -113,13 +132,16 @@ pub enum EntryTag {
     Encrypted(String, String),
     /// An entry tag to be stored in plaintext (for ordered comparison)
     Plaintext(String, String),
+    /// An entry tag to be stored in plaintext with a decimal numeric value,
+    /// which is compared numerically
+    Numeric(String, String),
 }
 
 impl EntryTag {
     /// Accessor for the tag name
     pub fn name(&self) -> &str {
         match self {
-            Self::Encrypted(name, _) | Self::Plaintext(name, _) => name,
+            Self::Encrypted(name, _) | Self::Plaintext(name, _) | Self::Numeric(name, _) => name,
         }
     }
 
@@ -134,27 +156,33 @@ impl EntryTag {
                 let (name, val) = f(name.as_str(), val.as_str());
                 Self::Plaintext(name, val)
             }
+            Self::Numeric(name, val) => {
+                let (name, val) = f(name.as_str(), val.as_str());
+                Self::Numeric(name, val)
+            }
         }
     }
 
     /// Setter for the tag name
     pub fn update_name(&mut self, f: impl FnOnce(&mut String)) {
         match self {
-            Self::Encrypted(name, _) | Self::Plaintext(name, _) => f(name),
+            Self::Encrypted(name, _) | Self::Plaintext(name, _) | Self::Numeric(name, _) => f(name),
         }
     }
 
     /// Accessor for the tag value
     pub fn value(&self) -> &str {
         match self {
-            Self::Encrypted(_, val) | Self::Plaintext(_, val) => val,
+            Self::Encrypted(_, val) | Self::Plaintext(_, val) | Self::Numeric(_, val) => val,
         }
     }
 
     /// Unwrap the tag value
     pub fn into_value(self) -> String {
         match self {
-            Self::Encrypted(_, value) | Self::Plaintext(_, value) => value,
+            Self::Encrypted(_, value) | Self::Plaintext(_, value) | Self::Numeric(_, value) => {
+                value
+            }
         }
     }
 }
@@ -172,10 +200,42 @@ impl Debug for EntryTag {
                 .field(&name)
                 .field(&value)
                 .finish(),
+            Self::Numeric(name, value) => {
+                f.debug_tuple("Numeric").field(&name).field(&value).finish()
+            }
         }
     }
 }
 
+/// The byte prefixed to the stored name of a numeric tag. Tag names are
+/// otherwise valid UTF-8, so numeric tags remain distinct from plaintext
+/// tags with the same name.
+const NUMERIC_TAG_NAME_PREFIX: u8 = 0xff;
+
+/// Encode the stored name of a numeric tag
+pub(crate) fn numeric_tag_name(name: &str) -> Vec<u8> {
+    let mut result = Vec::with_capacity(name.len() + 1);
+    result.push(NUMERIC_TAG_NAME_PREFIX);
+    result.extend_from_slice(name.as_bytes());
+    result
+}
+
+/// Decode a plaintext or numeric tag from its stored name and value
+pub(crate) fn decode_plaintext_tag(mut name: Vec<u8>, value: Vec<u8>) -> Result<EntryTag, Error> {
+    let numeric = name.first() == Some(&NUMERIC_TAG_NAME_PREFIX);
+    if numeric {
+        name.remove(0);
+    }
+    let name = String::from_utf8(name).map_err(err_map!(Encryption, "Error decoding entry tag"))?;
+    let value =
+        String::from_utf8(value).map_err(err_map!(Encryption, "Error decoding entry tag"))?;
+    Ok(if numeric {
+        EntryTag::Numeric(name, value)
+    } else {
+        EntryTag::Plaintext(name, value)
+    })
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub(crate) struct EncEntryTag {
     pub name: Vec<u8>,
@@ -215,6 +275,13 @@ impl TagFilter {
         }
     }
 
+    /// Get the name of a numeric tag for use in a comparison, equivalent to
+    /// comparing the plaintext tag with a number in WQL
+    #[inline]
+    pub fn numeric_tag(name: impl AsRef<str>) -> String {
+        format!("{}{}", wql::NUMERIC_KEY_PREFIX, name.as_ref())
+    }
+
     /// Create an equality comparison tag filter
     #[inline]
     pub fn is_eq(name: impl Into<String>, value: impl Into<String>) -> Self {
@@ -232,6 +299,9 @@ impl TagFilter {
     }
 
     /// Create an greater-than comparison tag filter
+    ///
+    /// Values are compared numerically for numeric tags, named using
+    /// [`TagFilter::numeric_tag`].
     #[inline]
     pub fn is_gt(name: impl Into<String>, value: impl Into<String>) -> Self {
         Self {
@@ -240,6 +310,9 @@ impl TagFilter {
     }
 
     /// Create an greater-than-or-equal comparison tag filter
+    ///
+    /// Values are compared numerically for numeric tags, named using
+    /// [`TagFilter::numeric_tag`].
     #[inline]
     pub fn is_gte(name: impl Into<String>, value: impl Into<String>) -> Self {
         Self {
@@ -248,6 +321,9 @@ impl TagFilter {
     }
 
     /// Create an less-than comparison tag filter
+    ///
+    /// Values are compared numerically for numeric tags, named using
+    /// [`TagFilter::numeric_tag`].
     #[inline]
     pub fn is_lt(name: impl Into<String>, value: impl Into<String>) -> Self {
         Self {
@@ -256,6 +332,9 @@ impl TagFilter {
     }
 
     /// Create an less-than-or-equal comparison tag filter
+    ///
+    /// Values are compared numerically for numeric tags, named using
+    /// [`TagFilter::numeric_tag`].
     #[inline]
     pub fn is_lte(name: impl Into<String>, value: impl Into<String>) -> Self {
         Self {
@@ -271,6 +350,14 @@ impl TagFilter {
         }
     }
 
+    /// Create a prefix match tag filter, supported for plaintext tags only
+    #[inline]
+    pub fn is_prefix(name: impl Into<String>, value: impl Into<String>) -> Self {
+        Self {
+            query: wql::Query::Prefix(name.into(), value.into()),
+        }
+    }
+
     /// Create an IN comparison tag filter for a set of tag values
     #[inline]
     pub fn is_in(name: impl Into<String>, values: Vec<String>) -> Self {
@@ -313,32 +400,128 @@ impl FromStr for TagFilter {
     }
 }
 
-/// An active record scan of a store backend
+/// Supported orderings for record scans
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum OrderBy {
+    /// Order by insertion into the store, approximating the creation time
+    #[default]
+    Created,
+    /// Order by the entry name
+    Name,
+    /// Order by the value of a plaintext tag. Entries without the tag are returned last
+    Tag(String),
+}
+
+impl FromStr for OrderBy {
+    type Err = Error;
+
+    fn from_str(order_by: &str) -> Result<Self, Error> {
+        match order_by {
+            "created" => Ok(Self::Created),
+            "name" => Ok(Self::Name),
+            _ => {
+                if let Some(tag) = order_by.strip_prefix("tag:") {
+                    let tag = tag.strip_prefix('~').unwrap_or(tag);
+                    if !tag.is_empty() {
+                        return Ok(Self::Tag(tag.to_string()));
+                    }
+                }
+                Err(err_msg!(Input, "Invalid scan ordering: {order_by}"))
+            }
+        }
+    }
+}
+
+/// The tags returned with each record of a scan
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum TagSelection {
+    /// Return all tags
+    #[default]
+    All,
+    /// Return no tags, skipping tag decryption
+    Omit,
+    /// Return only the tags with the given names. A leading `~` (as used for
+    /// plaintext tags in tag filters) is ignored.
+    Named(Vec<String>),
+}
+
+impl TagSelection {
+    /// Access the selected tag names, or `None` if all tags are selected
+    pub fn names(&self) -> Option<impl Iterator<Item = &str>> {
+        let names = match self {
+            Self::All => return None,
+            Self::Omit => &[][..],
+            Self::Named(names) => names.as_slice(),
+        };
+        Some(
+            names
+                .iter()
+                .map(|name| name.strip_prefix('~').unwrap_or(name)),
+        )
+    }
+
+    /// Check whether a tag name is selected
+    pub fn includes(&self, name: &str) -> bool {
+        match self.names() {
+            Some(mut names) => names.any(|n| n == name),
+            None => true,
+        }
+    }
+
+    /// Remove the tags which are not selected from a list of entry tags
+    pub fn retain_tags(&self, tags: &mut Vec<EntryTag>) {
+        match self {
+            Self::All => (),
+            Self::Omit => tags.clear(),
+            Self::Named(_) => tags.retain(|tag| self.includes(tag.name())),
+        }
+    }
+}
+
+/// An active record scan of a store backend.
+///
+/// Results may be fetched a page at a time using [`Scan::fetch_next`], or
+/// the scan may be consumed as a [`Stream`] of individual rows, in which case
+/// pages are fetched as the previous page is exhausted.
 pub struct Scan<'s, T> {
     #[allow(clippy::type_complexity)]
-    stream: Option<Pin<Box<dyn Stream<Item = Result<Vec<T>, Error>> + Send + 's>>>,
+    stream:
+        Option<Pin<Box<dyn Stream<Item = Result<(Vec<T>, Option<String>), Error>> + Send + 's>>>,
     page_size: usize,
+    continuation: Option<String>,
+    buffer: VecDeque<T>,
 }
 
 impl<'s, T> Scan<'s, T> {
+    /// Create a new scan from a stream of result pages, each paired with
+    /// a continuation token for its last row when available
     pub(crate) fn new<S>(stream: S, page_size: usize) -> Self
     where
-        S: Stream<Item = Result<Vec<T>, Error>> + Send + 's,
+        S: Stream<Item = Result<(Vec<T>, Option<String>), Error>> + Send + 's,
     {
         Self {
             stream: Some(stream.boxed()),
             page_size,
+            continuation: None,
+            buffer: VecDeque::new(),
         }
     }
 
     /// Fetch the next set of result rows
     pub async fn fetch_next(&mut self) -> Result<Option<Vec<T>>, Error> {
+        if !self.buffer.is_empty() {
+            // return the remainder of a page partially consumed as a stream
+            return Ok(Some(self.buffer.drain(..).collect()));
+        }
         if let Some(mut s) = self.stream.take() {
             match s.try_next().await? {
-                Some(val) => {
+                Some((val, token)) => {
                     if val.len() == self.page_size {
                         self.stream.replace(s);
                     }
+                    if token.is_some() {
+                        self.continuation = token;
+                    }
                     Ok(Some(val))
                 }
                 None => Ok(None),
@@ -347,12 +530,152 @@ impl<'s, T> Scan<'s, T> {
             Ok(None)
         }
     }
+
+    /// Get an opaque token identifying the position of the last fetched row.
+    ///
+    /// Passing this token to a new scan with the same ordering resumes the
+    /// results after this row. Tokens are only produced for ordered scans.
+    /// When the scan is consumed as a stream, the token identifies the last
+    /// row of the most recently fetched page.
+    pub fn continuation(&self) -> Option<&str> {
+        self.continuation.as_deref()
+    }
+}
+
+impl<T: Unpin> Stream for Scan<'_, T> {
+    type Item = Result<T, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            if let Some(row) = self.buffer.pop_front() {
+                return Poll::Ready(Some(Ok(row)));
+            }
+            let stream = match self.stream.as_mut() {
+                Some(stream) => stream,
+                None => return Poll::Ready(None),
+            };
+            match stream.as_mut().poll_next(cx) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(Some(Ok((rows, token)))) => {
+                    if rows.len() != self.page_size {
+                        self.stream = None;
+                    }
+                    if token.is_some() {
+                        self.continuation = token;
+                    }
+                    self.buffer.extend(rows);
+                }
+                Poll::Ready(Some(Err(err))) => {
+                    self.stream = None;
+                    return Poll::Ready(Some(Err(err)));
+                }
+                Poll::Ready(None) => {
+                    self.stream = None;
+                    return Poll::Ready(None);
+                }
+            }
+        }
+    }
 }
 
 impl<S> Debug for Scan<'_, S> {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         f.debug_struct("Scan")
             .field("page_size", &self.page_size)
+            .field("continuation", &self.continuation)
             .finish()
     }
 }
+
+/// The number of entries sharing a category and, optionally, a tag value
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EntryCount {
+    /// The category of the counted entries
+    pub category: String,
+
+    /// The value of the grouping tag, if requested and present
+    pub tag_value: Option<String>,
+
+    /// The number of matching entries
+    pub count: i64,
+}
+
+/// A condition on the current state of an entry which must hold for it
+/// to be replaced or removed
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EntryPrecondition {
+    /// The SHA-256 digest of the current entry value
+    ValueHash(Vec<u8>),
+    /// Tags which must all be present on the current entry
+    Tags(Vec<EntryTag>),
+    /// The current version of the entry
+    Version(i64),
+}
+
+impl EntryPrecondition {
+    /// Require the current entry value to match `value`
+    pub fn value_matches(value: &[u8]) -> Self {
+        Self::ValueHash(Sha256::digest(value).to_vec())
+    }
+
+    /// Check the precondition against the current value, tags and version of an entry
+    pub fn check(&self, value: &[u8], tags: &[EntryTag], version: i64) -> bool {
+        match self {
+            Self::ValueHash(hash) => Sha256::digest(value).as_slice().ct_eq(hash).into(),
+            Self::Tags(expected) => expected.iter().all(|tag| tags.contains(tag)),
+            Self::Version(expected) => *expected == version,
+        }
+    }
+}
+
+/// A notification of a change to an entry in the store
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EntryEvent {
+    /// The operation performed on the entry
+    pub operation: EntryOperation,
+
+    /// The entry kind discriminator
+    pub kind: EntryKind,
+
+    /// The category of the entry record
+    pub category: String,
+
+    /// The name of the entry record
+    pub name: String,
+}
+
+/// An active subscription to entry change events on a store backend
+pub struct Subscription<'s> {
+    #[allow(clippy::type_complexity)]
+    stream: Pin<Box<dyn Stream<Item = Result<EntryEvent, Error>> + Send + 's>>,
+}
+
+impl<'s> Subscription<'s> {
+    pub(crate) fn new<S>(stream: S) -> Self
+    where
+        S: Stream<Item = Result<EntryEvent, Error>> + Send + 's,
+    {
+        Self {
+            stream: stream.boxed(),
+        }
+    }
+
+    /// Wait for the next change event, returning `None` when the store is closed
+    pub async fn next_event(&mut self) -> Result<Option<EntryEvent>, Error> {
+        self.stream.try_next().await
+    }
+}
+
+impl Stream for Subscription<'_> {
+    type Item = Result<EntryEvent, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.stream.as_mut().poll_next(cx)
+    }
+}
+
+impl Debug for Subscription<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Subscription").finish()
+    }
+}
@@ -12,6 +12,9 @@ pub enum ErrorKind {
     /// The store backend was too busy to handle the request
     Busy,
 
+    /// An update failed because the entry was modified concurrently
+    Conflict,
+
     /// A custom error type for external integrations
     Custom,
 
@@ -27,6 +30,12 @@ pub enum ErrorKind {
     /// The requested record was not found
     NotFound,
 
+    /// An insert operation would exceed the storage limits of the profile
+    QuotaExceeded,
+
+    /// A database operation exceeded the configured statement timeout
+    Timeout,
+
     /// An unexpected error occurred
     Unexpected,
 
@@ -40,11 +49,14 @@ impl ErrorKind {
         match self {
             Self::Backend => "Backend error",
             Self::Busy => "Busy",
+            Self::Conflict => "Conflict",
             Self::Custom => "Custom error",
             Self::Duplicate => "Duplicate",
             Self::Encryption => "Encryption error",
             Self::Input => "Input error",
             Self::NotFound => "Not found",
+            Self::QuotaExceeded => "Quota exceeded",
+            Self::Timeout => "Timeout",
             Self::Unexpected => "Unexpected error",
             Self::Unsupported => "Unsupported",
         }
@@ -66,7 +78,9 @@ pub struct Error {
 }
 
 impl Error {
-    pub(crate) fn from_msg<T: Into<String>>(kind: ErrorKind, msg: T) -> Self {
+    /// Create a new error with a message, such as when implementing a
+    /// record store for a custom backend
+    pub fn from_msg<T: Into<String>>(kind: ErrorKind, msg: T) -> Self {
         Self {
             kind,
             cause: None,
@@ -95,13 +109,162 @@ impl Error {
         (self.kind, self.cause, self.message)
     }
 
+    /// Accessor for the error code reported by the database, if any. This is
+    /// the SQLSTATE for Postgres and the extended result code for SQLite.
+    pub fn backend_code(&self) -> Option<String> {
+        database_error_code(self.source()?).map(|(code, _)| code)
+    }
+
+    /// Accessor for the SQLSTATE reported by the database, if any
+    pub fn sqlstate(&self) -> Option<String> {
+        database_error_code(self.source()?).and_then(|(_, sqlstate)| sqlstate)
+    }
+
+    /// Attach the underlying cause of the error. Backend errors caused by a
+    /// transient condition in the database are reported as `Busy`, and those
+    /// caused by a statement timeout are reported as `Timeout`.
     pub(crate) fn with_cause<T: Into<Box<dyn StdError + Send + Sync + 'static>>>(
         mut self,
         err: T,
     ) -> Self {
-        self.cause = Some(err.into());
+        let cause = err.into();
+        #[cfg(any(feature = "postgres", feature = "sqlite"))]
+        if self.kind == ErrorKind::Backend {
+            if let Some(err) = cause.downcast_ref::<sqlx::Error>() {
+                if is_timeout_sqlx_error(err) {
+                    self.kind = ErrorKind::Timeout;
+                } else if is_transient_sqlx_error(err) {
+                    self.kind = ErrorKind::Busy;
+                }
+            }
+        }
+        self.cause = Some(cause);
         self
     }
+
+    /// Determine whether the error was caused by a transient condition, such
+    /// as a busy database or a failure to connect to the database server,
+    /// which may be resolved by retrying the operation
+    pub(crate) fn is_transient(&self) -> bool {
+        if self.kind == ErrorKind::Busy {
+            return true;
+        }
+        #[cfg(any(feature = "postgres", feature = "sqlite"))]
+        {
+            let mut next = self.source();
+            while let Some(err) = next {
+                if let Some(err) = err.downcast_ref::<sqlx::Error>() {
+                    return is_connection_sqlx_error(err);
+                }
+                next = err.source();
+            }
+        }
+        false
+    }
+}
+
+/// Determine whether a database error was caused by a failure to establish or
+/// maintain a connection to the database server
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
+fn is_connection_sqlx_error(err: &sqlx::Error) -> bool {
+    match err {
+        sqlx::Error::Io(_) | sqlx::Error::Tls(_) | sqlx::Error::PoolTimedOut => true,
+        #[cfg(feature = "postgres")]
+        sqlx::Error::Database(db_err) => db_err
+            .try_downcast_ref::<sqlx::postgres::PgDatabaseError>()
+            .map(|pg_err| {
+                // connection_exception, too_many_connections, cannot_connect_now
+                let code = pg_err.code();
+                code.starts_with("08") || matches!(code, "53300" | "57P03")
+            })
+            .unwrap_or(false),
+        _ => false,
+    }
+}
+
+/// Determine whether a database error may succeed if the operation is retried,
+/// such as a serialization failure or a locked database
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
+fn is_transient_sqlx_error(err: &sqlx::Error) -> bool {
+    match err {
+        sqlx::Error::PoolTimedOut => true,
+        sqlx::Error::Database(db_err) => {
+            #[cfg(feature = "postgres")]
+            if let Some(pg_err) = db_err.try_downcast_ref::<sqlx::postgres::PgDatabaseError>() {
+                // serialization_failure, deadlock_detected
+                return matches!(pg_err.code(), "40001" | "40P01");
+            }
+            #[cfg(feature = "sqlite")]
+            if db_err
+                .try_downcast_ref::<sqlx::sqlite::SqliteError>()
+                .is_some()
+            {
+                // SQLITE_BUSY, SQLITE_LOCKED and their extended result codes
+                return db_err
+                    .code()
+                    .and_then(|code| code.parse::<i32>().ok())
+                    .map(|code| matches!(code & 0xff, 5 | 6))
+                    .unwrap_or(false);
+            }
+            false
+        }
+        _ => false,
+    }
+}
+
+/// Determine whether a database operation was cancelled because it exceeded
+/// the statement timeout
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
+fn is_timeout_sqlx_error(err: &sqlx::Error) -> bool {
+    match err {
+        sqlx::Error::Database(db_err) => {
+            #[cfg(feature = "postgres")]
+            if let Some(pg_err) = db_err.try_downcast_ref::<sqlx::postgres::PgDatabaseError>() {
+                // query_canceled
+                return pg_err.code() == "57014";
+            }
+            #[cfg(feature = "sqlite")]
+            if db_err
+                .try_downcast_ref::<sqlx::sqlite::SqliteError>()
+                .is_some()
+            {
+                // SQLITE_INTERRUPT, raised by the progress handler
+                return db_err
+                    .code()
+                    .and_then(|code| code.parse::<i32>().ok())
+                    .map(|code| code & 0xff == 9)
+                    .unwrap_or(false);
+            }
+            false
+        }
+        _ => false,
+    }
+}
+
+/// Find the error code reported by the database in a chain of errors, along
+/// with the SQLSTATE when the backend provides one
+pub fn database_error_code(err: &(dyn StdError + 'static)) -> Option<(String, Option<String>)> {
+    #[cfg(any(feature = "postgres", feature = "sqlite"))]
+    {
+        let mut next: Option<&(dyn StdError + 'static)> = Some(err);
+        while let Some(err) = next {
+            if let Some(sqlx::Error::Database(db_err)) = err.downcast_ref::<sqlx::Error>() {
+                let code = db_err.code()?.into_owned();
+                #[cfg(feature = "postgres")]
+                if db_err
+                    .try_downcast_ref::<sqlx::postgres::PgDatabaseError>()
+                    .is_some()
+                {
+                    return Some((code.clone(), Some(code)));
+                }
+                return Some((code, None));
+            }
+            next = err.source();
+        }
+    }
+    #[cfg(not(any(feature = "postgres", feature = "sqlite")))]
+    let _ = err;
+    None
 }
 
 impl Display for Error {
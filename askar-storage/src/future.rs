@@ -1,14 +1,17 @@
 use std::{
     future::Future,
+    panic::{catch_unwind, AssertUnwindSafe},
     pin::Pin,
-    sync::Arc,
+    sync::{mpsc, Arc, Mutex},
     thread,
     time::{Duration, Instant},
 };
 
-use arc_swap::ArcSwapOption;
+use arc_swap::{ArcSwap, ArcSwapOption};
 use once_cell::sync::Lazy;
-use tokio::runtime::Runtime;
+use tokio::{runtime::Runtime, sync::oneshot};
+
+use crate::error::Error;
 
 pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
 
@@ -43,6 +46,202 @@ where
     }
 }
 
+/// The method used to run CPU-intensive cryptographic operations, such as
+/// the derivation of store keys from passwords and the re-encryption of
+/// profile keys when rekeying a store
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CryptoOffload {
+    /// Run operations on the blocking thread pool of the async runtime
+    #[default]
+    Blocking,
+    /// Run operations on a dedicated pool of threads, limiting the number of
+    /// operations which may be performed concurrently
+    Dedicated(usize),
+    /// Run operations inline on the calling task
+    Inline,
+}
+
+impl CryptoOffload {
+    /// Parse a method name (`blocking`, `dedicated` or `inline`), along with
+    /// the number of threads for a dedicated pool
+    pub fn from_option(mode: &str, threads: usize) -> Result<Self, Error> {
+        match mode {
+            "blocking" => Ok(Self::Blocking),
+            "dedicated" => Ok(Self::Dedicated(threads)),
+            "inline" => Ok(Self::Inline),
+            _ => Err(err_msg!(Input, "Unknown crypto offload method: {}", mode)),
+        }
+    }
+}
+
+type CryptoJob = Box<dyn FnOnce() + Send>;
+
+/// A fixed pool of threads for cryptographic operations. The threads exit
+/// once the pool is dropped and any queued operations have completed.
+struct CryptoPool {
+    sender: Mutex<mpsc::Sender<CryptoJob>>,
+}
+
+impl CryptoPool {
+    fn new(threads: usize) -> Result<Self, Error> {
+        if threads == 0 {
+            return Err(err_msg!(Input, "Crypto thread pool size must be non-zero"));
+        }
+        let (sender, receiver) = mpsc::channel::<CryptoJob>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        for idx in 0..threads {
+            let receiver = receiver.clone();
+            thread::Builder::new()
+                .name(format!("askar-crypto-{}", idx))
+                .spawn(move || loop {
+                    let job = receiver.lock().unwrap().recv();
+                    match job {
+                        // a panic is reported to the caller by the dropped result sender
+                        Ok(job) => {
+                            let _ = catch_unwind(AssertUnwindSafe(job));
+                        }
+                        Err(_) => break,
+                    }
+                })
+                .map_err(err_map!(Unexpected, "Error starting crypto thread"))?;
+        }
+        Ok(Self {
+            sender: Mutex::new(sender),
+        })
+    }
+
+    fn queue<F, T>(&self, f: F) -> oneshot::Receiver<T>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> T + Send + 'static,
+    {
+        let (result_send, result_recv) = oneshot::channel();
+        self.sender
+            .lock()
+            .unwrap()
+            .send(Box::new(move || {
+                let _ = result_send.send(f());
+            }))
+            .expect("Error queueing crypto task");
+        result_recv
+    }
+
+    async fn run<F, T>(&self, f: F) -> T
+    where
+        T: Send + 'static,
+        F: FnOnce() -> T + Send + 'static,
+    {
+        self.queue(f).await.expect("Error running crypto task")
+    }
+}
+
+enum CryptoExecutor {
+    Blocking,
+    Dedicated(CryptoPool),
+    Inline,
+}
+
+static CRYPTO_EXECUTOR: Lazy<ArcSwap<CryptoExecutor>> =
+    Lazy::new(|| ArcSwap::from_pointee(CryptoExecutor::Blocking));
+
+/// Select the method used to run CPU-intensive cryptographic operations.
+///
+/// Operations already in progress complete using the previous method.
+pub fn set_crypto_offload(offload: CryptoOffload) -> Result<(), Error> {
+    let executor = match offload {
+        CryptoOffload::Blocking => CryptoExecutor::Blocking,
+        CryptoOffload::Dedicated(threads) => CryptoExecutor::Dedicated(CryptoPool::new(threads)?),
+        CryptoOffload::Inline => CryptoExecutor::Inline,
+    };
+    CRYPTO_EXECUTOR.store(Arc::new(executor));
+    Ok(())
+}
+
+/// Run a CPU-intensive cryptographic operation using the method selected by
+/// `set_crypto_offload`.
+pub async fn unblock_crypto<F, T>(f: F) -> T
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    let executor = CRYPTO_EXECUTOR.load_full();
+    match &*executor {
+        CryptoExecutor::Blocking => unblock(f).await,
+        CryptoExecutor::Dedicated(pool) => pool.run(f).await,
+        CryptoExecutor::Inline => f(),
+    }
+}
+
+/// Run a CPU-intensive cryptographic operation over a batch of items using the
+/// method selected by `set_crypto_offload`.
+///
+/// The items are divided into chunks of at most `chunk_size` items, which may
+/// be processed concurrently. The results are returned in the original order.
+pub async fn unblock_crypto_chunks<I, T, E, F>(
+    items: Vec<I>,
+    chunk_size: usize,
+    f: F,
+) -> Result<Vec<T>, E>
+where
+    I: Send + 'static,
+    T: Send + 'static,
+    E: Send + 'static,
+    F: Fn(Vec<I>) -> Result<Vec<T>, E> + Send + Sync + 'static,
+{
+    let chunk_size = chunk_size.max(1);
+    if items.len() <= chunk_size {
+        return unblock_crypto(move || f(items)).await;
+    }
+    let count = items.len();
+    let f = Arc::new(f);
+    let mut items = items.into_iter();
+    let mut jobs = Vec::new();
+    loop {
+        let chunk: Vec<I> = items.by_ref().take(chunk_size).collect();
+        if chunk.is_empty() {
+            break;
+        }
+        let f = f.clone();
+        jobs.push(move || f(chunk));
+    }
+    let mut results = Vec::with_capacity(count);
+    for result in unblock_crypto_all(jobs).await {
+        results.extend(result?);
+    }
+    Ok(results)
+}
+
+async fn unblock_crypto_all<F, T>(jobs: Vec<F>) -> Vec<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    let executor = CRYPTO_EXECUTOR.load_full();
+    let mut results = Vec::with_capacity(jobs.len());
+    match &*executor {
+        CryptoExecutor::Blocking => {
+            let Some(rt) = RUNTIME.load().clone() else {
+                panic!("Runtime has been shut down");
+            };
+            let tasks = jobs
+                .into_iter()
+                .map(|f| rt.spawn_blocking(f))
+                .collect::<Vec<_>>();
+            for task in tasks {
+                results.push(task.await.expect("Error running blocking task"));
+            }
+        }
+        CryptoExecutor::Dedicated(pool) => {
+            let tasks = jobs.into_iter().map(|f| pool.queue(f)).collect::<Vec<_>>();
+            for task in tasks {
+                results.push(task.await.expect("Error running crypto task"));
+            }
+        }
+        CryptoExecutor::Inline => results.extend(jobs.into_iter().map(|f| f())),
+    }
+    results
+}
+
 /// Spawn an async task into the runtime.
 #[inline]
 pub fn spawn_ok(fut: impl Future<Output = ()> + Send + 'static) {
@@ -71,6 +270,9 @@ pub async fn timeout<R>(dur: Duration, f: impl Future<Output = R>) -> Option<R>
 #[doc(hidden)]
 pub fn shutdown(max_dur: Duration) {
     let start = Instant::now();
+    if let Some(executor) = Lazy::get(&CRYPTO_EXECUTOR) {
+        executor.store(Arc::new(CryptoExecutor::Blocking));
+    }
     if let Some(rt_swap) = Lazy::get(&RUNTIME) {
         if let Some(mut rt) = rt_swap.swap(None) {
             loop {
@@ -91,3 +293,73 @@ pub fn shutdown(max_dur: Duration) {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crypto_offload() {
+        for offload in [
+            CryptoOffload::Dedicated(2),
+            CryptoOffload::Inline,
+            CryptoOffload::Blocking,
+        ] {
+            set_crypto_offload(offload).unwrap();
+            let caller = thread::current().id();
+            let (result, worker) = block_on(async {
+                let tasks = (0..4u32).map(|idx| {
+                    unblock_crypto(move || {
+                        (
+                            idx * 2,
+                            thread::current().id(),
+                            thread::current().name().map(str::to_string),
+                        )
+                    })
+                });
+                let mut results = Vec::new();
+                for task in tasks {
+                    results.push(task.await);
+                }
+                let sum: u32 = results.iter().map(|(val, _, _)| val).sum();
+                (sum, results.pop().unwrap())
+            });
+            assert_eq!(result, 12);
+            match offload {
+                CryptoOffload::Dedicated(_) => {
+                    assert!(worker.2.unwrap().starts_with("askar-crypto-"))
+                }
+                CryptoOffload::Inline => assert_eq!(worker.1, caller),
+                CryptoOffload::Blocking => assert_ne!(worker.1, caller),
+            }
+        }
+        for offload in [
+            CryptoOffload::Dedicated(3),
+            CryptoOffload::Inline,
+            CryptoOffload::Blocking,
+        ] {
+            set_crypto_offload(offload).unwrap();
+            let result = block_on(unblock_crypto_chunks(
+                (0..100u32).collect(),
+                7,
+                |chunk: Vec<u32>| Result::<_, ()>::Ok(chunk.into_iter().map(|v| v * 2).collect()),
+            ))
+            .unwrap();
+            assert_eq!(result, (0..100u32).map(|v| v * 2).collect::<Vec<_>>());
+            let result = block_on(unblock_crypto_chunks(
+                (0..100u32).collect(),
+                7,
+                |chunk: Vec<u32>| {
+                    if chunk.contains(&50) {
+                        Err(50)
+                    } else {
+                        Ok(chunk)
+                    }
+                },
+            ));
+            assert_eq!(result, Err(50));
+        }
+        assert!(CryptoOffload::from_option("other", 0).is_err());
+        assert!(set_crypto_offload(CryptoOffload::Dedicated(0)).is_err());
+    }
+}
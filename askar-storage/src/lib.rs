@@ -7,7 +7,7 @@ pub use askar_crypto as crypto;
 
 #[macro_use]
 mod error;
-pub use self::error::{Error, ErrorKind};
+pub use self::error::{database_error_code, Error, ErrorKind};
 
 #[cfg(test)]
 #[macro_use]
@@ -25,7 +25,11 @@ extern crate log;
 extern crate serde;
 
 pub mod backend;
-pub use self::backend::{Backend, BackendSession, ManageBackend};
+pub use self::backend::{
+    AuditRecord, Backend, BackendSession, IntegrityIssue, IntegrityReport, IsolationLevel,
+    ManageBackend, ProfileDetails, ProfileLimits, QueryPlan, RekeyCallback, RekeyProgress,
+    RepairReport, SavepointOperation,
+};
 
 #[cfg(feature = "any")]
 pub mod any;
@@ -49,9 +53,9 @@ pub use options::{IntoOptions, Options};
 
 mod protect;
 pub use protect::{
-    generate_raw_store_key,
-    kdf::{Argon2Level, KdfMethod},
-    PassKey, StoreKeyMethod,
+    combine_pass_key, entry_test_vector, generate_raw_store_key,
+    kdf::{Argon2Level, KdfMethod, Pbkdf2Hash, Pbkdf2Params, ScryptParams},
+    quorum_pass_key, split_pass_key, PassKey, QuorumMethod, StoreKeyMethod,
 };
 
 mod wql;
@@ -16,6 +16,11 @@ use crate::error::Error;
 use crate::protect::kdf::Argon2Level;
 use crate::protect::{ProfileKey, StoreKey, StoreKeyReference};
 
+mod reader;
+#[cfg(feature = "postgres")]
+pub use self::reader::IndyPostgresWalletScheme;
+pub use self::reader::IndySdkWalletReader;
+
 mod strategy;
 
 const CHACHAPOLY_NONCE_LEN: u8 = 12;
@@ -46,7 +51,7 @@ pub(crate) struct IndyKey {
 
 #[derive(Default)]
 pub(crate) struct UpdatedIndyItem {
-    pub id: u32,
+    pub id: i64,
     pub category: Vec<u8>,
     pub name: Vec<u8>,
     pub value: Vec<u8>,
@@ -152,7 +157,9 @@ impl IndySdkToAriesAskarMigration {
         debug!("Completed wallet upgrade");
 
         debug!("Re-opening wallet");
-        let db_opts = SqliteStoreOptions::new(self.spec_uri.as_str())?;
+        let mut db_opts = SqliteStoreOptions::new(self.spec_uri.as_str())?;
+        // the migrated store is written at the initial schema version
+        db_opts.upgrade = true;
         let key_method = upd_key.key_ref.into();
         let db = db_opts
             .open(Some(key_method), self.wallet_key.as_str().into(), None)
@@ -232,24 +239,7 @@ impl IndySdkToAriesAskarMigration {
             .fetch_one(&mut self.conn)
             .await?
             .try_get(0)?;
-        let metadata_json = String::from_utf8_lossy(&metadata_row);
-        let metadata: IndyKeyMetadata = serde_json::from_str(&metadata_json).map_err(err_map!(
-            Input,
-            "Could not convert value from metadata to IndyKey",
-        ))?;
-        let keys_enc = metadata.keys;
-        let salt = metadata.master_key_salt.map(|s| s[..16].to_vec());
-
-        let key_ref = self.kdf_method.to_store_key_reference(salt.as_deref())?;
-        let master = key_ref.resolve(self.wallet_key.as_str().into())?;
-
-        let keys_mpk = master
-            .unwrap_data(keys_enc)
-            .map_err(err_map!(Input, "Error decrypting wallet key"))?;
-        let indy_key = rmp_serde::from_slice(&keys_mpk)
-            .map_err(err_map!(Input, "indy key not valid msgpack"))?;
-
-        Ok((indy_key, UpdatedKey { master, key_ref }))
+        decode_indy_key(&metadata_row, &self.kdf_method, self.wallet_key.as_str())
     }
 
     async fn init_profile(&mut self, key: &UpdatedKey) -> Result<ProfileKey, Error> {
@@ -390,6 +380,32 @@ impl IndySdkToAriesAskarMigration {
     }
 }
 
+/// Decrypt the Indy-SDK wallet keys contained in the wallet metadata
+fn decode_indy_key(
+    metadata_row: &[u8],
+    kdf_method: &KdfMethod,
+    wallet_key: &str,
+) -> Result<(IndyKey, UpdatedKey), Error> {
+    let metadata_json = String::from_utf8_lossy(metadata_row);
+    let metadata: IndyKeyMetadata = serde_json::from_str(&metadata_json).map_err(err_map!(
+        Input,
+        "Could not convert value from metadata to IndyKey",
+    ))?;
+    let keys_enc = metadata.keys;
+    let salt = metadata.master_key_salt.map(|s| s[..16].to_vec());
+
+    let key_ref = kdf_method.to_store_key_reference(salt.as_deref())?;
+    let master = key_ref.resolve(wallet_key.into())?;
+
+    let keys_mpk = master
+        .unwrap_data(keys_enc)
+        .map_err(err_map!(Input, "Error decrypting wallet key"))?;
+    let indy_key =
+        rmp_serde::from_slice(&keys_mpk).map_err(err_map!(Input, "indy key not valid msgpack"))?;
+
+    Ok((indy_key, UpdatedKey { master, key_ref }))
+}
+
 struct Separated<'a, T>(&'a [T], &'static str);
 
 impl<T: Display> Display for Separated<'_, T> {
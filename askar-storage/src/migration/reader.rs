@@ -0,0 +1,516 @@
+use std::fmt::{self, Debug, Formatter};
+use std::str::FromStr;
+
+#[cfg(feature = "postgres")]
+use sqlx::postgres::{PgConnectOptions, PgConnection};
+use sqlx::sqlite::{SqliteConnectOptions, SqliteConnection, SqliteRow};
+use sqlx::{ConnectOptions, Connection, Row};
+
+use super::strategy::{IndyRow, Strategy};
+use super::{decode_indy_key, IndyKey, KdfMethod};
+use crate::entry::Entry;
+use crate::error::Error;
+
+const SQLITE_FETCH_ITEMS: &str = "SELECT i.id, i.type, i.name, i.value, i.key,
+    (SELECT GROUP_CONCAT(HEX(te.name) || ':' || HEX(te.value))
+        FROM tags_encrypted te WHERE te.item_id = i.id) AS tags_enc,
+    (SELECT GROUP_CONCAT(HEX(tp.name) || ':' || HEX(tp.value))
+        FROM tags_plaintext tp WHERE tp.item_id = i.id) AS tags_plain
+    FROM items i WHERE i.id > ?1 ORDER BY i.id LIMIT ?2";
+
+#[cfg(feature = "postgres")]
+const POSTGRES_FETCH_ITEMS: &str = "SELECT i.id, i.type, i.name, i.value, i.key,
+    (SELECT STRING_AGG(ENCODE(te.name, 'hex') || ':' || ENCODE(te.value, 'hex'), ',')
+        FROM tags_encrypted te WHERE te.item_id = i.id) AS tags_enc,
+    (SELECT STRING_AGG(ENCODE(tp.name, 'hex') || ':' || ENCODE(tp.value, 'hex'), ',')
+        FROM tags_plaintext tp WHERE tp.item_id = i.id) AS tags_plain
+    FROM items i WHERE i.id > $1 ORDER BY i.id LIMIT $2";
+
+#[cfg(feature = "postgres")]
+const POSTGRES_FETCH_WALLET_ITEMS: &str = "SELECT i.id, i.type, i.name, i.value, i.key,
+    (SELECT STRING_AGG(ENCODE(te.name, 'hex') || ':' || ENCODE(te.value, 'hex'), ',')
+        FROM tags_encrypted te WHERE te.item_id = i.id) AS tags_enc,
+    (SELECT STRING_AGG(ENCODE(tp.name, 'hex') || ':' || ENCODE(tp.value, 'hex'), ',')
+        FROM tags_plaintext tp WHERE tp.item_id = i.id) AS tags_plain
+    FROM items i WHERE i.id > $1 AND i.wallet_id = $3 ORDER BY i.id LIMIT $2";
+
+/// The storage layouts of the Indy-SDK Postgres storage plugin
+#[cfg(feature = "postgres")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IndyPostgresWalletScheme {
+    /// Each wallet is kept in a separate database (`DatabasePerWallet`)
+    DatabasePerWallet,
+    /// The records of all wallets are kept in shared tables, identified by
+    /// the wallet name (`MultiWalletSingleTable` and
+    /// `MultiWalletSingleTableSharedPool`)
+    MultiWalletSingleTable,
+}
+
+#[cfg(feature = "postgres")]
+impl FromStr for IndyPostgresWalletScheme {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "DatabasePerWallet" => Ok(Self::DatabasePerWallet),
+            "MultiWalletSingleTable" | "MultiWalletSingleTableSharedPool" => {
+                Ok(Self::MultiWalletSingleTable)
+            }
+            _ => Err(err_msg!("Invalid wallet storage scheme")),
+        }
+    }
+}
+
+enum WalletConnection {
+    Sqlite(SqliteConnection),
+    #[cfg(feature = "postgres")]
+    Postgres(Box<PgConnection>, Option<String>),
+}
+
+impl WalletConnection {
+    async fn fetch_metadata(&mut self) -> Result<Option<Vec<u8>>, Error> {
+        match self {
+            Self::Sqlite(conn) => {
+                let found: Option<SqliteRow> = sqlx::query(
+                    "SELECT name FROM sqlite_master WHERE type='table' AND name='metadata'",
+                )
+                .fetch_optional(&mut *conn)
+                .await?;
+                if found.is_none() {
+                    return Ok(None);
+                }
+                Ok(sqlx::query("SELECT value FROM metadata")
+                    .fetch_optional(conn)
+                    .await?
+                    .map(|row| row.try_get(0))
+                    .transpose()?)
+            }
+            #[cfg(feature = "postgres")]
+            Self::Postgres(conn, wallet_id) => {
+                let conn: &mut PgConnection = conn;
+                let found: Option<String> =
+                    sqlx::query_scalar("SELECT CAST(TO_REGCLASS('metadata') AS TEXT)")
+                        .fetch_one(&mut *conn)
+                        .await?;
+                if found.is_none() {
+                    return Ok(None);
+                }
+                let row = if let Some(wallet_id) = wallet_id {
+                    sqlx::query("SELECT value FROM metadata WHERE wallet_id = $1")
+                        .bind(wallet_id.as_str())
+                        .fetch_optional(conn)
+                        .await?
+                } else {
+                    sqlx::query("SELECT value FROM metadata")
+                        .fetch_optional(conn)
+                        .await?
+                };
+                Ok(row.map(|row| row.try_get(0)).transpose()?)
+            }
+        }
+    }
+
+    async fn fetch_items(&mut self, after_id: i64, limit: u32) -> Result<Vec<IndyRow>, Error> {
+        match self {
+            Self::Sqlite(conn) => Ok(sqlx::query_as(SQLITE_FETCH_ITEMS)
+                .bind(after_id)
+                .bind(limit)
+                .fetch_all(conn)
+                .await?),
+            #[cfg(feature = "postgres")]
+            Self::Postgres(conn, wallet_id) => {
+                let conn: &mut PgConnection = conn;
+                if let Some(wallet_id) = wallet_id {
+                    Ok(sqlx::query_as(POSTGRES_FETCH_WALLET_ITEMS)
+                        .bind(after_id)
+                        .bind(i64::from(limit))
+                        .bind(wallet_id.as_str())
+                        .fetch_all(conn)
+                        .await?)
+                } else {
+                    Ok(sqlx::query_as(POSTGRES_FETCH_ITEMS)
+                        .bind(after_id)
+                        .bind(i64::from(limit))
+                        .fetch_all(conn)
+                        .await?)
+                }
+            }
+        }
+    }
+
+    async fn close(self) -> Result<(), Error> {
+        match self {
+            Self::Sqlite(conn) => Ok(conn.close().await?),
+            #[cfg(feature = "postgres")]
+            Self::Postgres(conn, _) => Ok((*conn).close().await?),
+        }
+    }
+}
+
+/// Reader for the decrypted records of an Indy-SDK wallet
+///
+/// The wallet is only read and is not modified, so that its records may be
+/// copied into a separate Askar store.
+pub struct IndySdkWalletReader {
+    conn: WalletConnection,
+    indy_key: IndyKey,
+    last_id: i64,
+}
+
+impl Debug for IndySdkWalletReader {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IndySdkWalletReader")
+            .field("last_id", &self.last_id)
+            .finish()
+    }
+}
+
+impl IndySdkWalletReader {
+    /// Open an Indy-SDK SQLite wallet database using the wallet key and the
+    /// name of its key derivation method: `ARGON2I_MOD`, `ARGON2I_INT` or
+    /// `RAW`
+    pub async fn open(path: &str, wallet_key: &str, kdf_method: &str) -> Result<Self, Error> {
+        let kdf_method = KdfMethod::from_str(kdf_method)?;
+        let conn = SqliteConnectOptions::from_str(path)?
+            .read_only(true)
+            .connect()
+            .await?;
+        Self::init(WalletConnection::Sqlite(conn), &kdf_method, wallet_key).await
+    }
+
+    /// Open a wallet created by the Indy-SDK Postgres storage plugin
+    ///
+    /// The connection URI must select the database containing the wallet,
+    /// and may select a schema using the `options` query parameter, such as
+    /// `options=-c%20search_path%3Dwallet`. When using the `DatabasePerWallet`
+    /// scheme this is the database named by the wallet identifier, otherwise
+    /// the wallet identifier selects the wallet records within the shared
+    /// tables.
+    #[cfg(feature = "postgres")]
+    pub async fn open_postgres(
+        uri: &str,
+        scheme: IndyPostgresWalletScheme,
+        wallet_id: &str,
+        wallet_key: &str,
+        kdf_method: &str,
+    ) -> Result<Self, Error> {
+        let kdf_method = KdfMethod::from_str(kdf_method)?;
+        let conn = PgConnectOptions::from_str(uri)?.connect().await?;
+        let wallet_id = match scheme {
+            IndyPostgresWalletScheme::DatabasePerWallet => None,
+            IndyPostgresWalletScheme::MultiWalletSingleTable => Some(wallet_id.to_string()),
+        };
+        Self::init(
+            WalletConnection::Postgres(Box::new(conn), wallet_id),
+            &kdf_method,
+            wallet_key,
+        )
+        .await
+    }
+
+    async fn init(
+        mut conn: WalletConnection,
+        kdf_method: &KdfMethod,
+        wallet_key: &str,
+    ) -> Result<Self, Error> {
+        let indy_key = match conn.fetch_metadata().await {
+            Ok(Some(metadata)) => decode_indy_key(&metadata, kdf_method, wallet_key),
+            Ok(None) => Err(err_msg!(NotFound, "Indy-SDK wallet not found")),
+            Err(err) => Err(err),
+        };
+        match indy_key {
+            Ok((indy_key, _)) => Ok(Self {
+                conn,
+                indy_key,
+                last_id: 0,
+            }),
+            Err(err) => {
+                conn.close().await?;
+                Err(err)
+            }
+        }
+    }
+
+    /// Fetch and decrypt the next batch of wallet records, returning `None`
+    /// when all records have been read
+    pub async fn fetch_next(&mut self, limit: u32) -> Result<Option<Vec<Entry>>, Error> {
+        let rows = self.conn.fetch_items(self.last_id, limit).await?;
+        if rows.is_empty() {
+            return Ok(None);
+        }
+        let mut entries = Vec::with_capacity(rows.len());
+        for row in rows {
+            self.last_id = row.id();
+            entries.push(Strategy::decrypt_entry(row, &self.indy_key)?);
+        }
+        Ok(Some(entries))
+    }
+
+    /// Close the wallet database
+    pub async fn close(self) -> Result<(), Error> {
+        self.conn.close().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{EncryptionKey, CHACHAPOLY_NONCE_LEN};
+    use super::*;
+    use crate::crypto::buffer::SecretBytes;
+    use crate::crypto::encrypt::KeyAeadInPlace;
+    use crate::crypto::repr::{KeySecretBytes, ToSecretBytes};
+    use crate::entry::EntryTag;
+    use crate::future::block_on;
+
+    const WALLET_TEMPLATE_PATH: &str = "./tests/indy_wallet_sqlite.db";
+    const WALLET_COPY_PATH: &str = "./tests/indy_wallet_sqlite_reader.db";
+    const WALLET_KEY: &str = "GfwU1DC7gEZNs3w41tjBiZYj7BNToDoFEqKY6wZXqs1A";
+
+    /// The encrypted columns of a wallet record and its tags
+    struct TestItem {
+        typ: Vec<u8>,
+        name: Vec<u8>,
+        value: Vec<u8>,
+        key: Vec<u8>,
+        tag_enc: (Vec<u8>, Vec<u8>),
+        tag_plain: (Vec<u8>, Vec<u8>),
+    }
+
+    fn encrypt_merged(value: &[u8], key: &EncryptionKey, nonce: u8) -> Vec<u8> {
+        let nonce = [nonce; CHACHAPOLY_NONCE_LEN as usize];
+        let mut buffer = SecretBytes::from_slice(value);
+        key.encrypt_in_place(&mut buffer, &nonce, &[]).unwrap();
+        let mut result = nonce.to_vec();
+        result.extend_from_slice(&buffer);
+        result
+    }
+
+    async fn template_metadata() -> Vec<u8> {
+        let mut conn = SqliteConnectOptions::from_str(WALLET_TEMPLATE_PATH)
+            .unwrap()
+            .immutable(true)
+            .connect()
+            .await
+            .unwrap();
+        let metadata = sqlx::query_scalar("SELECT value FROM metadata")
+            .fetch_one(&mut conn)
+            .await
+            .unwrap();
+        conn.close().await.unwrap();
+        metadata
+    }
+
+    fn test_item(metadata: &[u8], name: &str) -> TestItem {
+        let (keys, _) = decode_indy_key(metadata, &KdfMethod::Raw, WALLET_KEY).unwrap();
+        let item_key = EncryptionKey::from_secret_bytes(&[7u8; 32]).unwrap();
+        TestItem {
+            typ: encrypt_merged(b"category", &keys.type_key, 1),
+            name: encrypt_merged(name.as_bytes(), &keys.name_key, 2),
+            value: encrypt_merged(b"value", &item_key, 3),
+            key: encrypt_merged(&item_key.to_secret_bytes().unwrap(), &keys.value_key, 4),
+            tag_enc: (
+                encrypt_merged(b"enc", &keys.tag_name_key, 5),
+                encrypt_merged(b"secret", &keys.tag_value_key, 6),
+            ),
+            tag_plain: (
+                encrypt_merged(b"plain", &keys.tag_name_key, 7),
+                b"public".to_vec(),
+            ),
+        }
+    }
+
+    async fn check_reader(mut reader: IndySdkWalletReader, name: &str) {
+        let entries = reader.fetch_next(10).await.unwrap().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].category, "category");
+        assert_eq!(entries[0].name, name);
+        assert_eq!(&entries[0].value[..], b"value");
+        assert_eq!(
+            entries[0].tags,
+            vec![
+                EntryTag::Encrypted("enc".to_string(), "secret".to_string()),
+                EntryTag::Plaintext("plain".to_string(), "public".to_string()),
+            ]
+        );
+        assert!(reader.fetch_next(10).await.unwrap().is_none());
+        reader.close().await.unwrap();
+    }
+
+    #[test]
+    fn read_sqlite_wallet() {
+        for suffix in ["", "-shm", "-wal"] {
+            std::fs::remove_file(format!("{}{}", WALLET_COPY_PATH, suffix)).ok();
+        }
+        std::fs::copy(WALLET_TEMPLATE_PATH, WALLET_COPY_PATH).unwrap();
+
+        block_on(async {
+            let item = test_item(&template_metadata().await, "name");
+            let mut conn = SqliteConnection::connect(WALLET_COPY_PATH).await.unwrap();
+            let item_id =
+                sqlx::query("INSERT INTO items (type, name, value, key) VALUES (?1, ?2, ?3, ?4)")
+                    .bind(item.typ)
+                    .bind(item.name)
+                    .bind(item.value)
+                    .bind(item.key)
+                    .execute(&mut conn)
+                    .await
+                    .unwrap()
+                    .last_insert_rowid();
+            sqlx::query("INSERT INTO tags_encrypted (name, value, item_id) VALUES (?1, ?2, ?3)")
+                .bind(item.tag_enc.0)
+                .bind(item.tag_enc.1)
+                .bind(item_id)
+                .execute(&mut conn)
+                .await
+                .unwrap();
+            sqlx::query("INSERT INTO tags_plaintext (name, value, item_id) VALUES (?1, ?2, ?3)")
+                .bind(item.tag_plain.0)
+                .bind(item.tag_plain.1)
+                .bind(item_id)
+                .execute(&mut conn)
+                .await
+                .unwrap();
+            conn.close().await.unwrap();
+
+            let reader = IndySdkWalletReader::open(WALLET_COPY_PATH, WALLET_KEY, "RAW")
+                .await
+                .unwrap();
+            check_reader(reader, "name").await;
+
+            assert!(
+                IndySdkWalletReader::open(WALLET_COPY_PATH, "invalid key", "RAW")
+                    .await
+                    .is_err()
+            );
+        });
+    }
+
+    #[cfg(feature = "pg_test")]
+    #[test]
+    fn read_postgres_wallets() {
+        use sqlx::Executor;
+
+        let db_url = match std::env::var("POSTGRES_URL") {
+            Ok(p) if !p.is_empty() => p,
+            _ => panic!("'POSTGRES_URL' must be defined"),
+        };
+
+        async fn insert_item(conn: &mut PgConnection, wallet_id: Option<&str>, item: TestItem) {
+            let wallet_col = if wallet_id.is_some() {
+                ", wallet_id"
+            } else {
+                ""
+            };
+            let wallet_val = if wallet_id.is_some() { ", $5" } else { "" };
+            let item_id: i64 = sqlx::query_scalar(&format!(
+                "INSERT INTO items (type, name, value, key{wallet_col})
+                VALUES ($1, $2, $3, $4{wallet_val}) RETURNING id"
+            ))
+            .bind(item.typ)
+            .bind(item.name)
+            .bind(item.value)
+            .bind(item.key)
+            .bind(wallet_id)
+            .fetch_one(&mut *conn)
+            .await
+            .unwrap();
+            for (table, (name, value)) in [
+                ("tags_encrypted", item.tag_enc),
+                ("tags_plaintext", item.tag_plain),
+            ] {
+                sqlx::query(&format!(
+                    "INSERT INTO {table} (name, value, item_id) VALUES ($1, $2, $3)"
+                ))
+                .bind(name)
+                .bind(value)
+                .bind(item_id)
+                .execute(&mut *conn)
+                .await
+                .unwrap();
+            }
+        }
+
+        async fn create_tables(conn: &mut PgConnection, schema: &str, multi: bool) {
+            let wallet_col = if multi {
+                "wallet_id VARCHAR(64) NOT NULL,"
+            } else {
+                ""
+            };
+            conn.execute(
+                format!(
+                    "DROP SCHEMA IF EXISTS {schema} CASCADE;
+                    CREATE SCHEMA {schema};
+                    SET search_path TO {schema};
+                    CREATE TABLE metadata ({wallet_col} value BYTEA NOT NULL);
+                    CREATE TABLE items (id BIGSERIAL PRIMARY KEY, {wallet_col}
+                        type BYTEA NOT NULL, name BYTEA NOT NULL,
+                        value BYTEA NOT NULL, key BYTEA NOT NULL);
+                    CREATE TABLE tags_encrypted (name BYTEA NOT NULL,
+                        value BYTEA NOT NULL, item_id BIGINT NOT NULL);
+                    CREATE TABLE tags_plaintext (name BYTEA NOT NULL,
+                        value BYTEA NOT NULL, item_id BIGINT NOT NULL);"
+                )
+                .as_str(),
+            )
+            .await
+            .unwrap();
+        }
+
+        block_on(async move {
+            let metadata = template_metadata().await;
+            let opts = PgConnectOptions::from_str(&db_url).unwrap();
+            let mut conn = opts.connect().await.unwrap();
+
+            create_tables(&mut conn, "indy_wallet_single", false).await;
+            sqlx::query("INSERT INTO metadata (value) VALUES ($1)")
+                .bind(&metadata)
+                .execute(&mut conn)
+                .await
+                .unwrap();
+            insert_item(&mut conn, None, test_item(&metadata, "single")).await;
+
+            create_tables(&mut conn, "indy_wallet_multi", true).await;
+            for wallet_id in ["wallet1", "wallet2"] {
+                sqlx::query("INSERT INTO metadata (wallet_id, value) VALUES ($1, $2)")
+                    .bind(wallet_id)
+                    .bind(&metadata)
+                    .execute(&mut conn)
+                    .await
+                    .unwrap();
+                insert_item(&mut conn, Some(wallet_id), test_item(&metadata, wallet_id)).await;
+            }
+
+            for (schema, scheme, wallet_id) in [
+                (
+                    "indy_wallet_single",
+                    IndyPostgresWalletScheme::DatabasePerWallet,
+                    "single",
+                ),
+                (
+                    "indy_wallet_multi",
+                    IndyPostgresWalletScheme::MultiWalletSingleTable,
+                    "wallet2",
+                ),
+            ] {
+                let uri = format!(
+                    "{}{}options=-c%20search_path%3D{}",
+                    db_url,
+                    if db_url.contains('?') { '&' } else { '?' },
+                    schema
+                );
+                let reader =
+                    IndySdkWalletReader::open_postgres(&uri, scheme, wallet_id, WALLET_KEY, "RAW")
+                        .await
+                        .unwrap();
+                check_reader(reader, wallet_id).await;
+            }
+
+            conn.execute(
+                "DROP SCHEMA indy_wallet_single CASCADE; DROP SCHEMA indy_wallet_multi CASCADE;",
+            )
+            .await
+            .unwrap();
+            conn.close().await.unwrap();
+        });
+    }
+}
@@ -5,13 +5,13 @@ use super::{
 use crate::crypto::buffer::SecretBytes;
 use crate::crypto::encrypt::KeyAeadInPlace;
 use crate::crypto::repr::KeySecretBytes;
-use crate::entry::EntryTag;
+use crate::entry::{Entry, EntryKind, EntryTag};
 use crate::protect::EntryEncryptor;
 use crate::Error;
 
 #[derive(Default)]
 pub(crate) struct IndyItem {
-    id: u32,
+    id: i64,
     typ: Vec<u8>,
     name: Vec<u8>,
     value: Option<Vec<u8>>,
@@ -21,7 +21,7 @@ pub(crate) struct IndyItem {
 // TODO: should tags_enc and tags_plain be empty in the example?
 #[derive(sqlx::FromRow, Debug)]
 pub(crate) struct IndyRow {
-    id: u32,
+    id: i64,
     #[sqlx(rename = "type")]
     typ: Vec<u8>,
     name: Vec<u8>,
@@ -31,6 +31,12 @@ pub(crate) struct IndyRow {
     tags_plain: Option<String>,
 }
 
+impl IndyRow {
+    pub fn id(&self) -> i64 {
+        self.id
+    }
+}
+
 pub(crate) struct Strategy {}
 
 impl Strategy {
@@ -110,6 +116,21 @@ impl Strategy {
         Ok(indy_item)
     }
 
+    pub fn decrypt_entry(row: IndyRow, keys: &IndyKey) -> Result<Entry, Error> {
+        let item = Self::decrypt_item(row, keys)?;
+        let category =
+            String::from_utf8(item.typ).map_err(err_map!(Input, "item type is not valid utf-8"))?;
+        let name = String::from_utf8(item.name)
+            .map_err(err_map!(Input, "item name is not valid utf-8"))?;
+        Ok(Entry::new(
+            EntryKind::Item,
+            category,
+            name,
+            item.value.unwrap_or_default(),
+            item.tags,
+        ))
+    }
+
     pub fn update_item(item: IndyItem, key: &ProfileKey) -> Result<UpdatedIndyItem, Error> {
         let value = match item.value {
             Some(v) => key.encrypt_entry_value(&item.typ, &item.name, v.into())?,
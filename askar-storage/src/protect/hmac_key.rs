@@ -15,6 +15,7 @@ use crate::{
         kdf::KeyDerivation,
         random::KeyMaterial,
         repr::KeyGen,
+        subtle::{Choice, ConstantTimeEq},
     },
     error::Error,
 };
@@ -61,9 +62,16 @@ impl<H, L: ArrayLength<u8>> Debug for HmacKey<H, L> {
     }
 }
 
+impl<H, L: ArrayLength<u8>> ConstantTimeEq for HmacKey<H, L> {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.0.ct_eq(&other.0)
+    }
+}
+
 impl<H, L: ArrayLength<u8>> PartialEq for HmacKey<H, L> {
+    #[inline]
     fn eq(&self, other: &Self) -> bool {
-        self.0.as_ref() == other.0.as_ref()
+        self.ct_eq(other).into()
     }
 }
 impl<H, L: ArrayLength<u8>> Eq for HmacKey<H, L> {}
@@ -75,16 +83,19 @@ impl<H, L: ArrayLength<u8>> KeyGen for HmacKey<H, L> {
 }
 
 pub trait HmacDerive {
-    type Hash: Digest + BlockSizeUser;
+    type Hash: Digest + BlockSizeUser + Clone;
     type Key: AsRef<[u8]>;
 
     fn hmac_deriver<'d>(&'d self, inputs: &'d [&'d [u8]])
         -> HmacDeriver<'d, Self::Hash, Self::Key>;
+
+    /// Compute the keyed HMAC state, for reuse in multiple derivations
+    fn keyed_hmac(&self) -> Result<KeyedHmac<Self::Hash>, Error>;
 }
 
 impl<H, L: ArrayLength<u8>> HmacDerive for HmacKey<H, L>
 where
-    H: Digest + BlockSizeUser,
+    H: Digest + BlockSizeUser + Clone,
 {
     type Hash = H;
     type Key = Self;
@@ -100,6 +111,12 @@ where
             _marker: PhantomData,
         }
     }
+
+    fn keyed_hmac(&self) -> Result<KeyedHmac<Self::Hash>, Error> {
+        SimpleHmac::<H>::new_from_slice(self.as_ref())
+            .map(KeyedHmac)
+            .map_err(|_| err_msg!(Encryption, "invalid length for hmac key"))
+    }
 }
 
 pub struct HmacDeriver<'d, H, K: ?Sized> {
@@ -132,6 +149,48 @@ where
     }
 }
 
+/// An HMAC state initialized with a key, avoiding the cost of processing the
+/// key for each derivation
+pub struct KeyedHmac<H: Digest + BlockSizeUser>(SimpleHmac<H>);
+
+impl<H: Digest + BlockSizeUser + Clone> KeyedHmac<H> {
+    pub fn hmac_deriver<'d>(&'d self, inputs: &'d [&'d [u8]]) -> KeyedHmacDeriver<'d, H> {
+        KeyedHmacDeriver { hmac: self, inputs }
+    }
+}
+
+impl<H: Digest + BlockSizeUser> Debug for KeyedHmac<H> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("KeyedHmac").field(&"<secret>").finish()
+    }
+}
+
+pub struct KeyedHmacDeriver<'d, H: Digest + BlockSizeUser> {
+    hmac: &'d KeyedHmac<H>,
+    inputs: &'d [&'d [u8]],
+}
+
+impl<H> KeyDerivation for KeyedHmacDeriver<'_, H>
+where
+    H: Digest + BlockSizeUser + Clone,
+{
+    fn derive_key_bytes(&mut self, key_output: &mut [u8]) -> Result<(), crypto::Error> {
+        if key_output.len() > H::OutputSize::USIZE {
+            return Err(crypto::Error::from_msg(
+                crypto::ErrorKind::Encryption,
+                "invalid length for hmac output",
+            ));
+        }
+        let mut hmac = self.hmac.0.clone();
+        for msg in self.inputs {
+            hmac.update(msg);
+        }
+        let hash = hmac.finalize().into_bytes();
+        key_output.copy_from_slice(&hash[..key_output.len()]);
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -149,5 +208,15 @@ mod tests {
             .derive_key_bytes(&mut output)
             .unwrap();
         assert_eq!(output, &hex!("4cecfbf6be721395529be686")[..]);
+
+        let keyed = key.keyed_hmac().unwrap();
+        for _ in 0..2 {
+            let mut output = [0u8; 12];
+            keyed
+                .hmac_deriver(&[b"test ", b"message"])
+                .derive_key_bytes(&mut output)
+                .unwrap();
+            assert_eq!(output, &hex!("4cecfbf6be721395529be686")[..]);
+        }
     }
 }
@@ -15,6 +15,15 @@ pub use crate::crypto::kdf::argon2::SaltSize;
 pub const LEVEL_INTERACTIVE: &str = "13:int";
 pub const LEVEL_MODERATE: &str = "13:mod";
 
+/// The minimum memory cost of custom parameters in kibibytes
+pub const MIN_MEM_COST: u32 = 4096;
+/// The maximum memory cost of custom parameters in kibibytes
+pub const MAX_MEM_COST: u32 = 4194304;
+/// The maximum number of iterations of custom parameters
+pub const MAX_TIME_COST: u32 = 100;
+/// The maximum degree of parallelism of custom parameters
+pub const MAX_PARALLELISM: u32 = 64;
+
 /// Argon2i derivation methods
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum Level {
@@ -22,6 +31,15 @@ pub enum Level {
     Interactive,
     /// Stronger Moderate method
     Moderate,
+    /// Custom parameters
+    Custom {
+        /// The memory cost in kibibytes
+        mem_cost: u32,
+        /// The number of iterations
+        time_cost: u32,
+        /// The degree of parallelism
+        parallelism: u32,
+    },
 }
 
 impl Default for Level {
@@ -31,19 +49,87 @@ impl Default for Level {
 }
 
 impl Level {
-    pub(crate) fn from_str(level: &str) -> Option<Self> {
+    /// Create a custom derivation level, validating the parameters
+    pub fn custom(mem_cost: u32, time_cost: u32, parallelism: u32) -> Result<Self, Error> {
+        if !(MIN_MEM_COST..=MAX_MEM_COST).contains(&mem_cost) {
+            return Err(err_msg!(
+                Input,
+                "Argon2i memory cost must be between {} and {} KiB",
+                MIN_MEM_COST,
+                MAX_MEM_COST
+            ));
+        }
+        if !(1..=MAX_TIME_COST).contains(&time_cost) {
+            return Err(err_msg!(
+                Input,
+                "Argon2i iterations must be between 1 and {}",
+                MAX_TIME_COST
+            ));
+        }
+        if !(1..=MAX_PARALLELISM).contains(&parallelism) {
+            return Err(err_msg!(
+                Input,
+                "Argon2i parallelism must be between 1 and {}",
+                MAX_PARALLELISM
+            ));
+        }
+        if mem_cost < 8 * parallelism {
+            return Err(err_msg!(
+                Input,
+                "Argon2i memory cost must be at least 8 KiB per lane"
+            ));
+        }
+        Ok(Self::Custom {
+            mem_cost,
+            time_cost,
+            parallelism,
+        })
+    }
+
+    /// Parse a level in the form `int`, `mod` or `m=<mem>,t=<iter>,p=<par>`,
+    /// optionally prefixed by the Argon2 version `13:`
+    pub(crate) fn from_str(level: &str) -> Result<Self, Error> {
         match level {
-            "int" | LEVEL_INTERACTIVE => Some(Self::Interactive),
-            "mod" | LEVEL_MODERATE => Some(Self::Moderate),
-            "" => Some(Self::default()),
-            _ => None,
+            "int" | LEVEL_INTERACTIVE => return Ok(Self::Interactive),
+            "mod" | LEVEL_MODERATE => return Ok(Self::Moderate),
+            "" => return Ok(Self::default()),
+            _ => (),
+        }
+        let params = level.strip_prefix("13:").unwrap_or(level);
+        let (mut mem_cost, mut time_cost, mut parallelism) = (None, None, None);
+        for param in params.split(',') {
+            let (name, value) = param
+                .split_once('=')
+                .ok_or_else(|| err_msg!(Unsupported, "Invalid key derivation method"))?;
+            let value: u32 = value
+                .parse()
+                .map_err(|_| err_msg!(Input, "Invalid Argon2i parameter value: {}", param))?;
+            let target = match name {
+                "m" => &mut mem_cost,
+                "t" => &mut time_cost,
+                "p" => &mut parallelism,
+                _ => return Err(err_msg!(Unsupported, "Invalid key derivation method")),
+            };
+            if target.replace(value).is_some() {
+                return Err(err_msg!(Input, "Duplicate Argon2i parameter: {}", name));
+            }
         }
+        Self::custom(
+            mem_cost.ok_or_else(|| err_msg!(Input, "Missing Argon2i memory cost"))?,
+            time_cost.ok_or_else(|| err_msg!(Input, "Missing Argon2i iterations"))?,
+            parallelism.unwrap_or(1),
+        )
     }
 
-    pub(crate) fn as_str(&self) -> &'static str {
+    pub(crate) fn encode(&self) -> String {
         match self {
-            Self::Interactive => LEVEL_INTERACTIVE,
-            Self::Moderate => LEVEL_MODERATE,
+            Self::Interactive => LEVEL_INTERACTIVE.to_string(),
+            Self::Moderate => LEVEL_MODERATE.to_string(),
+            Self::Custom {
+                mem_cost,
+                time_cost,
+                parallelism,
+            } => format!("13:m={},t={},p={}", mem_cost, time_cost, parallelism),
         }
     }
 
@@ -51,16 +137,32 @@ impl Level {
         ArrayKey::random()
     }
 
-    fn params(&self) -> &Params {
+    fn params(&self) -> Result<Params, Error> {
         match self {
-            Self::Interactive => &PARAMS_INTERACTIVE,
-            Self::Moderate => &PARAMS_MODERATE,
+            Self::Interactive => Ok(PARAMS_INTERACTIVE),
+            Self::Moderate => Ok(PARAMS_MODERATE),
+            Self::Custom {
+                mem_cost,
+                time_cost,
+                parallelism,
+            } => Ok(Params::new(*mem_cost, *time_cost, *parallelism)?),
+        }
+    }
+
+    /// Determine if the memory cost or iterations of this level are lower
+    /// than those of another level
+    pub(crate) fn is_below(&self, other: &Self) -> bool {
+        match (self.params(), other.params()) {
+            (Ok(params), Ok(other)) => {
+                params.mem_cost() < other.mem_cost() || params.time_cost() < other.time_cost()
+            }
+            _ => true,
         }
     }
 
     pub(crate) fn derive_key(&self, password: &[u8], salt: &[u8]) -> Result<StoreKey, Error> {
         ArrayKey::<<StoreKeyType as KeyMeta>::KeySize>::temp(|key| {
-            Argon2::new(password, salt, *self.params())?.derive_key_bytes(key)?;
+            Argon2::new(password, salt, self.params()?)?.derive_key_bytes(key)?;
             Ok(StoreKey::from(StoreKeyType::from_secret_bytes(&*key)?))
         })
     }
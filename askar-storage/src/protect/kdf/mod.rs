@@ -11,13 +11,27 @@ mod argon2;
 pub use self::argon2::Level as Argon2Level;
 use self::argon2::SaltSize as Argon2Salt;
 
+mod pbkdf2;
+use self::pbkdf2::SaltSize as Pbkdf2Salt;
+pub use self::pbkdf2::{Hash as Pbkdf2Hash, Params as Pbkdf2Params};
+
+mod scrypt;
+pub use self::scrypt::Params as ScryptParams;
+use self::scrypt::SaltSize as ScryptSalt;
+
 pub const METHOD_ARGON2I: &str = "argon2i";
+pub const METHOD_PBKDF2: &str = "pbkdf2";
+pub const METHOD_SCRYPT: &str = "scrypt";
 
 /// Supported KDF methods for generating or referencing a store key
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum KdfMethod {
     /// Argon2i derivation method
     Argon2i(Argon2Level),
+    /// PBKDF2 derivation method
+    Pbkdf2(Pbkdf2Params),
+    /// Scrypt derivation method
+    Scrypt(ScryptParams),
 }
 
 impl KdfMethod {
@@ -26,34 +40,51 @@ impl KdfMethod {
         let prefix = method_and_detail.next();
         if prefix == Some(PREFIX_KDF) {
             let method = method_and_detail.next().unwrap_or_default();
-            let mut level_and_detail = method_and_detail.next().unwrap_or_default().splitn(2, '?');
-            let level = level_and_detail.next().unwrap_or_default();
-            let detail = level_and_detail.next().unwrap_or_default();
-            if method == METHOD_ARGON2I {
-                if let Some(level) = Argon2Level::from_str(level) {
-                    return Ok((
-                        Self::Argon2i(level),
-                        if detail.is_empty() {
-                            "".to_owned()
-                        } else {
-                            format!("?{}", detail)
-                        },
-                    ));
-                }
-            }
+            let mut params_and_detail = method_and_detail.next().unwrap_or_default().splitn(2, '?');
+            let params = params_and_detail.next().unwrap_or_default();
+            let detail = params_and_detail.next().unwrap_or_default();
+            let detail = if detail.is_empty() {
+                "".to_owned()
+            } else {
+                format!("?{}", detail)
+            };
+            let method = match method {
+                METHOD_ARGON2I => Self::Argon2i(Argon2Level::from_str(params)?),
+                METHOD_PBKDF2 => Self::Pbkdf2(Pbkdf2Params::from_str(params)?),
+                METHOD_SCRYPT => Self::Scrypt(ScryptParams::from_str(params)?),
+                _ => return Err(err_msg!(Unsupported, "Invalid key derivation method")),
+            };
+            return Ok((method, detail));
         }
         Err(err_msg!(Unsupported, "Invalid key derivation method"))
     }
 
     pub(crate) fn encode(&self, detail: Option<&str>) -> String {
-        match self {
-            Self::Argon2i(level) => format!(
-                "{}:{}:{}{}",
-                PREFIX_KDF,
-                METHOD_ARGON2I,
-                level.as_str(),
-                detail.unwrap_or_default()
-            ),
+        let (method, params) = match self {
+            Self::Argon2i(level) => (METHOD_ARGON2I, level.encode()),
+            Self::Pbkdf2(params) => (METHOD_PBKDF2, params.encode()),
+            Self::Scrypt(params) => (METHOD_SCRYPT, params.encode()),
+        };
+        format!(
+            "{}:{}:{}{}",
+            PREFIX_KDF,
+            method,
+            params,
+            detail.unwrap_or_default()
+        )
+    }
+
+    /// Determine if this method is weaker than a minimum key derivation
+    /// method, such that the store key should be re-derived
+    ///
+    /// A store key derived using a different method than the minimum is
+    /// always considered to be below it.
+    pub fn is_below(&self, minimum: &KdfMethod) -> bool {
+        match (self, minimum) {
+            (Self::Argon2i(level), Self::Argon2i(min_level)) => level.is_below(min_level),
+            (Self::Pbkdf2(params), Self::Pbkdf2(min_params)) => params.is_below(min_params),
+            (Self::Scrypt(params), Self::Scrypt(min_params)) => params.is_below(min_params),
+            _ => true,
         }
     }
 
@@ -65,6 +96,18 @@ impl KdfMethod {
                 let detail = format!("?salt={}", salt.as_hex());
                 Ok((key, detail))
             }
+            Self::Pbkdf2(params) => {
+                let salt = params.generate_salt();
+                let key = params.derive_key(password.as_bytes(), salt.as_ref())?;
+                let detail = format!("?salt={}", salt.as_hex());
+                Ok((key, detail))
+            }
+            Self::Scrypt(params) => {
+                let salt = params.generate_salt();
+                let key = params.derive_key(password.as_bytes(), salt.as_ref())?;
+                let detail = format!("?salt={}", salt.as_hex());
+                Ok((key, detail))
+            }
         }
     }
 
@@ -75,6 +118,14 @@ impl KdfMethod {
                 let key = level.derive_key(password.as_bytes(), salt.as_ref())?;
                 Ok(key)
             }
+            Self::Pbkdf2(params) => {
+                let salt = parse_salt::<Pbkdf2Salt>(detail)?;
+                params.derive_key(password.as_bytes(), salt.as_ref())
+            }
+            Self::Scrypt(params) => {
+                let salt = parse_salt::<ScryptSalt>(detail)?;
+                params.derive_key(password.as_bytes(), salt.as_ref())
+            }
         }
     }
 }
@@ -0,0 +1,208 @@
+use digest::{crypto_common::BlockSizeUser, Digest};
+use hmac::{Mac, SimpleHmac};
+use sha2::{Sha256, Sha512};
+
+use crate::{
+    crypto::{
+        buffer::ArrayKey,
+        generic_array::typenum::U16,
+        repr::{KeyMeta, KeySecretBytes},
+    },
+    error::Error,
+    protect::store_key::{StoreKey, StoreKeyType},
+};
+
+/// The size of the password salt
+pub type SaltSize = U16;
+
+/// The default number of PBKDF2 iterations
+pub const DEFAULT_ITERATIONS: u32 = 600_000;
+/// The minimum number of PBKDF2 iterations
+pub const MIN_ITERATIONS: u32 = 10_000;
+/// The maximum number of PBKDF2 iterations
+pub const MAX_ITERATIONS: u32 = 100_000_000;
+
+const HASH_SHA256: &str = "sha256";
+const HASH_SHA512: &str = "sha512";
+
+/// Supported PBKDF2 pseudo-random functions
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Hash {
+    /// HMAC-SHA-256
+    Sha256,
+    /// HMAC-SHA-512
+    Sha512,
+}
+
+impl Hash {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Sha256 => HASH_SHA256,
+            Self::Sha512 => HASH_SHA512,
+        }
+    }
+}
+
+/// PBKDF2 derivation parameters
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Params {
+    hash: Hash,
+    iterations: u32,
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Self {
+            hash: Hash::Sha256,
+            iterations: DEFAULT_ITERATIONS,
+        }
+    }
+}
+
+impl Params {
+    /// Create a new set of PBKDF2 parameters, validating the iteration count
+    pub fn new(hash: Hash, iterations: u32) -> Result<Self, Error> {
+        if !(MIN_ITERATIONS..=MAX_ITERATIONS).contains(&iterations) {
+            return Err(err_msg!(
+                Input,
+                "PBKDF2 iterations must be between {} and {}",
+                MIN_ITERATIONS,
+                MAX_ITERATIONS
+            ));
+        }
+        Ok(Self { hash, iterations })
+    }
+
+    /// Accessor for the pseudo-random function
+    pub fn hash(&self) -> Hash {
+        self.hash
+    }
+
+    /// Accessor for the number of iterations
+    pub fn iterations(&self) -> u32 {
+        self.iterations
+    }
+
+    /// Parse parameters in the form `h=<sha256|sha512>,i=<iterations>`
+    pub(crate) fn from_str(params: &str) -> Result<Self, Error> {
+        if params.is_empty() {
+            return Ok(Self::default());
+        }
+        let (mut hash, mut iterations) = (None, None);
+        for param in params.split(',') {
+            match param.split_once('=') {
+                Some(("h", value)) => {
+                    let value = match value {
+                        HASH_SHA256 => Hash::Sha256,
+                        HASH_SHA512 => Hash::Sha512,
+                        _ => return Err(err_msg!(Unsupported, "Unsupported PBKDF2 hash")),
+                    };
+                    if hash.replace(value).is_some() {
+                        return Err(err_msg!(Input, "Duplicate PBKDF2 parameter: h"));
+                    }
+                }
+                Some(("i", value)) => {
+                    let value = value
+                        .parse()
+                        .map_err(|_| err_msg!(Input, "Invalid PBKDF2 iterations: {}", value))?;
+                    if iterations.replace(value).is_some() {
+                        return Err(err_msg!(Input, "Duplicate PBKDF2 parameter: i"));
+                    }
+                }
+                _ => return Err(err_msg!(Unsupported, "Invalid key derivation method")),
+            }
+        }
+        Self::new(
+            hash.unwrap_or(Hash::Sha256),
+            iterations.unwrap_or(DEFAULT_ITERATIONS),
+        )
+    }
+
+    pub(crate) fn encode(&self) -> String {
+        format!("h={},i={}", self.hash.as_str(), self.iterations)
+    }
+
+    pub(crate) fn generate_salt(&self) -> ArrayKey<SaltSize> {
+        ArrayKey::random()
+    }
+
+    /// Determine if the iterations of these parameters are lower than those
+    /// of another set of parameters
+    pub(crate) fn is_below(&self, other: &Self) -> bool {
+        self.iterations < other.iterations
+    }
+
+    pub(crate) fn derive_key(&self, password: &[u8], salt: &[u8]) -> Result<StoreKey, Error> {
+        ArrayKey::<<StoreKeyType as KeyMeta>::KeySize>::temp(|key| {
+            match self.hash {
+                Hash::Sha256 => pbkdf2_hmac::<Sha256>(password, salt, self.iterations, key)?,
+                Hash::Sha512 => pbkdf2_hmac::<Sha512>(password, salt, self.iterations, key)?,
+            }
+            Ok(StoreKey::from(StoreKeyType::from_secret_bytes(&*key)?))
+        })
+    }
+}
+
+/// Derive key material using PBKDF2 (RFC 8018) with HMAC as the
+/// pseudo-random function
+pub(crate) fn pbkdf2_hmac<D>(
+    password: &[u8],
+    salt: &[u8],
+    iterations: u32,
+    output: &mut [u8],
+) -> Result<(), Error>
+where
+    D: Digest + BlockSizeUser + Clone,
+{
+    let prf = SimpleHmac::<D>::new_from_slice(password)
+        .map_err(|_| err_msg!(Unexpected, "Error creating HMAC"))?;
+    for (idx, block) in output.chunks_mut(<D as Digest>::output_size()).enumerate() {
+        let mut mac = prf.clone();
+        mac.update(salt);
+        mac.update(&(idx as u32 + 1).to_be_bytes());
+        let mut u = mac.finalize().into_bytes();
+        block.copy_from_slice(&u[..block.len()]);
+        for _ in 1..iterations {
+            let mut mac = prf.clone();
+            mac.update(&u);
+            u = mac.finalize().into_bytes();
+            for (out, b) in block.iter_mut().zip(u.iter()) {
+                *out ^= b;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex_literal::hex;
+
+    #[test]
+    fn pbkdf2_sha256_expected() {
+        let mut output = [0u8; 32];
+        pbkdf2_hmac::<Sha256>(b"password", b"salt", 1, &mut output).unwrap();
+        assert_eq!(
+            output,
+            hex!("120fb6cffcf8b32c43e7225256c4f837a86548c92ccc35480805987cb70be17b")
+        );
+        pbkdf2_hmac::<Sha256>(b"password", b"salt", 4096, &mut output).unwrap();
+        assert_eq!(
+            output,
+            hex!("c5e478d59288c841aa530db6845c4c8d962893a001ce4e11a4963873aa98134a")
+        );
+    }
+
+    #[test]
+    fn params_parse() {
+        assert_eq!(Params::from_str(""), Ok(Params::default()));
+        assert_eq!(
+            Params::from_str("h=sha512,i=20000"),
+            Params::new(Hash::Sha512, 20000)
+        );
+        assert_eq!(Params::default().encode(), "h=sha256,i=600000");
+        assert!(Params::from_str("i=100").is_err());
+        assert!(Params::from_str("h=md5").is_err());
+    }
+}
@@ -0,0 +1,274 @@
+use sha2::Sha256;
+use zeroize::Zeroize;
+
+use super::pbkdf2::pbkdf2_hmac;
+use crate::{
+    crypto::{
+        buffer::{ArrayKey, SecretBytes},
+        generic_array::typenum::U16,
+        repr::{KeyMeta, KeySecretBytes},
+    },
+    error::Error,
+    protect::store_key::{StoreKey, StoreKeyType},
+};
+
+/// The size of the password salt
+pub type SaltSize = U16;
+
+/// The minimum base-2 logarithm of the CPU/memory cost
+pub const MIN_LOG_N: u8 = 10;
+/// The maximum base-2 logarithm of the CPU/memory cost
+pub const MAX_LOG_N: u8 = 24;
+/// The maximum block size
+pub const MAX_BLOCK_SIZE: u32 = 32;
+/// The maximum degree of parallelism
+pub const MAX_PARALLELISM: u32 = 16;
+/// The maximum memory used by the derivation in bytes
+pub const MAX_MEMORY: u64 = 1 << 30;
+
+/// Scrypt derivation parameters
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Params {
+    log_n: u8,
+    r: u32,
+    p: u32,
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        // 32 MiB of memory
+        Self {
+            log_n: 15,
+            r: 8,
+            p: 1,
+        }
+    }
+}
+
+impl Params {
+    /// Create a new set of scrypt parameters, validating the parameter ranges
+    pub fn new(log_n: u8, r: u32, p: u32) -> Result<Self, Error> {
+        if !(MIN_LOG_N..=MAX_LOG_N).contains(&log_n) {
+            return Err(err_msg!(
+                Input,
+                "Scrypt cost must be between 2^{} and 2^{}",
+                MIN_LOG_N,
+                MAX_LOG_N
+            ));
+        }
+        if !(1..=MAX_BLOCK_SIZE).contains(&r) {
+            return Err(err_msg!(
+                Input,
+                "Scrypt block size must be between 1 and {}",
+                MAX_BLOCK_SIZE
+            ));
+        }
+        if !(1..=MAX_PARALLELISM).contains(&p) {
+            return Err(err_msg!(
+                Input,
+                "Scrypt parallelism must be between 1 and {}",
+                MAX_PARALLELISM
+            ));
+        }
+        if 128 * r as u64 * (1u64 << log_n) > MAX_MEMORY {
+            return Err(err_msg!(Input, "Scrypt memory cost must not exceed 1 GiB"));
+        }
+        Ok(Self { log_n, r, p })
+    }
+
+    /// Accessor for the base-2 logarithm of the CPU/memory cost
+    pub fn log_n(&self) -> u8 {
+        self.log_n
+    }
+
+    /// Accessor for the block size
+    pub fn block_size(&self) -> u32 {
+        self.r
+    }
+
+    /// Accessor for the degree of parallelism
+    pub fn parallelism(&self) -> u32 {
+        self.p
+    }
+
+    /// Parse parameters in the form `ln=<log2 cost>,r=<block size>,p=<par>`
+    pub(crate) fn from_str(params: &str) -> Result<Self, Error> {
+        if params.is_empty() {
+            return Ok(Self::default());
+        }
+        let default = Self::default();
+        let (mut log_n, mut r, mut p) = (None, None, None);
+        for param in params.split(',') {
+            let (name, value) = param
+                .split_once('=')
+                .ok_or_else(|| err_msg!(Unsupported, "Invalid key derivation method"))?;
+            let value: u32 = value
+                .parse()
+                .map_err(|_| err_msg!(Input, "Invalid scrypt parameter value: {}", param))?;
+            let target = match name {
+                "ln" => &mut log_n,
+                "r" => &mut r,
+                "p" => &mut p,
+                _ => return Err(err_msg!(Unsupported, "Invalid key derivation method")),
+            };
+            if target.replace(value).is_some() {
+                return Err(err_msg!(Input, "Duplicate scrypt parameter: {}", name));
+            }
+        }
+        let log_n = log_n.unwrap_or(default.log_n as u32);
+        Self::new(
+            u8::try_from(log_n).map_err(|_| err_msg!(Input, "Invalid scrypt cost"))?,
+            r.unwrap_or(default.r),
+            p.unwrap_or(default.p),
+        )
+    }
+
+    pub(crate) fn encode(&self) -> String {
+        format!("ln={},r={},p={}", self.log_n, self.r, self.p)
+    }
+
+    pub(crate) fn generate_salt(&self) -> ArrayKey<SaltSize> {
+        ArrayKey::random()
+    }
+
+    /// Determine if the work factor of these parameters is lower than that
+    /// of another set of parameters
+    pub(crate) fn is_below(&self, other: &Self) -> bool {
+        let work = |params: &Self| (params.r as u64 * params.p as u64) << params.log_n;
+        self.log_n < other.log_n || self.r < other.r || work(self) < work(other)
+    }
+
+    pub(crate) fn derive_key(&self, password: &[u8], salt: &[u8]) -> Result<StoreKey, Error> {
+        ArrayKey::<<StoreKeyType as KeyMeta>::KeySize>::temp(|key| {
+            scrypt(password, salt, self.log_n, self.r, self.p, key)?;
+            Ok(StoreKey::from(StoreKeyType::from_secret_bytes(&*key)?))
+        })
+    }
+}
+
+/// Derive key material using scrypt (RFC 7914)
+pub(crate) fn scrypt(
+    password: &[u8],
+    salt: &[u8],
+    log_n: u8,
+    r: u32,
+    p: u32,
+    output: &mut [u8],
+) -> Result<(), Error> {
+    let block_len = 128 * r as usize;
+    let mut blocks = SecretBytes::new_with(block_len * p as usize, |_| ());
+    pbkdf2_hmac::<Sha256>(password, salt, 1, blocks.as_mut())?;
+    let mut words = vec![0u32; block_len / 4];
+    let mut scratch = vec![0u32; (block_len / 4) << log_n];
+    for block in blocks.as_mut().chunks_mut(block_len) {
+        for (word, bytes) in words.iter_mut().zip(block.chunks_exact(4)) {
+            *word = u32::from_le_bytes(bytes.try_into().unwrap());
+        }
+        ro_mix(&mut words, &mut scratch, log_n);
+        for (bytes, word) in block.chunks_exact_mut(4).zip(words.iter()) {
+            bytes.copy_from_slice(&word.to_le_bytes());
+        }
+    }
+    words.zeroize();
+    scratch.zeroize();
+    pbkdf2_hmac::<Sha256>(password, blocks.as_ref(), 1, output)
+}
+
+fn ro_mix(x: &mut [u32], v: &mut [u32], log_n: u8) {
+    let len = x.len();
+    let n = 1usize << log_n;
+    let mut y = vec![0u32; len];
+    for i in 0..n {
+        v[i * len..(i + 1) * len].copy_from_slice(x);
+        block_mix(x, &mut y);
+        x.copy_from_slice(&y);
+    }
+    for _ in 0..n {
+        let j = x[len - 16] as usize & (n - 1);
+        for (a, b) in x.iter_mut().zip(&v[j * len..(j + 1) * len]) {
+            *a ^= b;
+        }
+        block_mix(x, &mut y);
+        x.copy_from_slice(&y);
+    }
+    y.zeroize();
+}
+
+fn block_mix(input: &[u32], output: &mut [u32]) {
+    let count = input.len() / 16;
+    let mut x = [0u32; 16];
+    x.copy_from_slice(&input[input.len() - 16..]);
+    for (i, block) in input.chunks_exact(16).enumerate() {
+        for (a, b) in x.iter_mut().zip(block) {
+            *a ^= b;
+        }
+        salsa20_8(&mut x);
+        // even blocks are placed in the first half of the output
+        let pos = (i / 2 + (i % 2) * (count / 2)) * 16;
+        output[pos..pos + 16].copy_from_slice(&x);
+    }
+    x.zeroize();
+}
+
+fn salsa20_8(b: &mut [u32; 16]) {
+    let mut x = *b;
+    for _ in 0..4 {
+        quarter_round(&mut x, 0, 4, 8, 12);
+        quarter_round(&mut x, 5, 9, 13, 1);
+        quarter_round(&mut x, 10, 14, 2, 6);
+        quarter_round(&mut x, 15, 3, 7, 11);
+        quarter_round(&mut x, 0, 1, 2, 3);
+        quarter_round(&mut x, 5, 6, 7, 4);
+        quarter_round(&mut x, 10, 11, 8, 9);
+        quarter_round(&mut x, 15, 12, 13, 14);
+    }
+    for (a, b) in b.iter_mut().zip(x.iter()) {
+        *a = a.wrapping_add(*b);
+    }
+    x.zeroize();
+}
+
+#[inline]
+fn quarter_round(x: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize) {
+    x[b] ^= x[a].wrapping_add(x[d]).rotate_left(7);
+    x[c] ^= x[b].wrapping_add(x[a]).rotate_left(9);
+    x[d] ^= x[c].wrapping_add(x[b]).rotate_left(13);
+    x[a] ^= x[d].wrapping_add(x[c]).rotate_left(18);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex_literal::hex;
+
+    #[test]
+    fn scrypt_expected() {
+        let mut output = [0u8; 64];
+        scrypt(b"", b"", 4, 1, 1, &mut output).unwrap();
+        assert_eq!(
+            output,
+            hex!(
+                "77d6576238657b203b19ca42c18a0497f16b4844e3074ae8dfdffa3fede21442
+                fcd0069ded0948f8326a753a0fc81f17e8d3e0fb2e0d3628cf35e20c38d18906"
+            )
+        );
+        scrypt(b"password", b"NaCl", 10, 8, 16, &mut output).unwrap();
+        assert_eq!(
+            output,
+            hex!(
+                "fdbabe1c9d3472007856e7190d01e9fe7c6ad7cbc8237830e77376634b373162
+                2eaf30d92e22a3886ff109279d9830dac727afb94a83ee6d8360cbdfa2cc0640"
+            )
+        );
+    }
+
+    #[test]
+    fn params_parse() {
+        assert_eq!(Params::from_str(""), Ok(Params::default()));
+        assert_eq!(Params::from_str("ln=12,r=4"), Params::new(12, 4, 1));
+        assert_eq!(Params::default().encode(), "ln=15,r=8,p=1");
+        assert!(Params::from_str("ln=4").is_err());
+        assert!(Params::from_str("ln=24,r=32").is_err());
+        assert!(Params::from_str("ln=300").is_err());
+    }
+}
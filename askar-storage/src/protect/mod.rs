@@ -12,14 +12,22 @@ mod pass_key;
 pub use self::pass_key::PassKey;
 
 mod profile_key;
-pub use self::profile_key::ProfileKey;
+pub use self::profile_key::{entry_test_vector, ProfileKey};
+
+mod quorum;
+pub use self::quorum::{quorum_pass_key, QuorumMethod};
+
+mod shamir;
+pub use self::shamir::{combine_pass_key, split_pass_key};
 
 mod store_key;
-pub use self::store_key::{generate_raw_store_key, StoreKey, StoreKeyMethod, StoreKeyReference};
+pub use self::store_key::{
+    generate_raw_store_key, parse_raw_store_key, StoreKey, StoreKeyMethod, StoreKeyReference,
+};
 
 use crate::{
     crypto::buffer::SecretBytes,
-    entry::{EncEntryTag, EntryTag},
+    entry::{decode_plaintext_tag, numeric_tag_name, EncEntryTag, EntryTag},
     error::Error,
     future::unblock,
 };
@@ -29,19 +37,39 @@ pub type ProfileId = i64;
 #[derive(Debug)]
 pub struct KeyCache {
     profile_info: RwLock<HashMap<String, (ProfileId, Arc<ProfileKey>)>>,
-    pub(crate) store_key: Arc<StoreKey>,
+    store_key: Option<Arc<StoreKey>>,
 }
 
 impl KeyCache {
     pub fn new(store_key: impl Into<Arc<StoreKey>>) -> Self {
         Self {
             profile_info: RwLock::new(HashMap::new()),
-            store_key: store_key.into(),
+            store_key: Some(store_key.into()),
         }
     }
 
+    /// Create a key cache for a store opened using a profile access key,
+    /// without access to the store key
+    pub fn new_scoped() -> Self {
+        Self {
+            profile_info: RwLock::new(HashMap::new()),
+            store_key: None,
+        }
+    }
+
+    /// Access the store key, which is required for all operations outside
+    /// of the profiles loaded into the cache when the store is scoped
+    pub fn store_key(&self) -> Result<Arc<StoreKey>, Error> {
+        self.store_key.clone().ok_or_else(|| {
+            err_msg!(
+                Unsupported,
+                "Operation is not permitted for a profile-scoped store"
+            )
+        })
+    }
+
     pub async fn load_key(&self, ciphertext: Vec<u8>) -> Result<ProfileKey, Error> {
-        let store_key = self.store_key.clone();
+        let store_key = self.store_key()?;
         unblock(move || {
             let data = store_key
                 .unwrap_data(ciphertext)
@@ -64,6 +92,13 @@ impl KeyCache {
     pub async fn get_profile(&self, name: &str) -> Option<(ProfileId, Arc<ProfileKey>)> {
         self.profile_info.read().await.get(name).cloned()
     }
+
+    pub async fn rename_profile(&self, name: &str, new_name: String) {
+        let mut info = self.profile_info.write().await;
+        if let Some(entry) = info.remove(name) {
+            info.insert(new_name, entry);
+        }
+    }
 }
 
 pub(crate) trait EntryEncryptor {
@@ -123,6 +158,11 @@ impl EntryEncryptor for NullEncryptor {
                     value: value.into_bytes(),
                     plaintext: true,
                 },
+                EntryTag::Numeric(name, value) => EncEntryTag {
+                    name: numeric_tag_name(&name),
+                    value: value.into_bytes(),
+                    plaintext: true,
+                },
             })
             .collect())
     }
@@ -143,11 +183,11 @@ impl EntryEncryptor for NullEncryptor {
     }
     fn decrypt_entry_tags(&self, enc_tags: Vec<EncEntryTag>) -> Result<Vec<EntryTag>, Error> {
         enc_tags.into_iter().try_fold(vec![], |mut acc, tag| {
-            let name = String::from_utf8(tag.name).map_err(err_map!(Encryption))?;
-            let value = String::from_utf8(tag.value).map_err(err_map!(Encryption))?;
             acc.push(if tag.plaintext {
-                EntryTag::Plaintext(name, value)
+                decode_plaintext_tag(tag.name, tag.value)?
             } else {
+                let name = String::from_utf8(tag.name).map_err(err_map!(Encryption))?;
+                let value = String::from_utf8(tag.value).map_err(err_map!(Encryption))?;
                 EntryTag::Encrypted(name, value)
             });
             Result::<_, Error>::Ok(acc)
@@ -1,5 +1,7 @@
 use zeroize::Zeroize;
 
+use crate::crypto::subtle::{Choice, ConstantTimeEq};
+
 use std::{
     borrow::Cow,
     fmt::{self, Debug, Formatter},
@@ -83,9 +85,16 @@ impl<'a> From<Option<&'a str>> for PassKey<'a> {
     }
 }
 
+impl ConstantTimeEq for PassKey<'_> {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.as_bytes().ct_eq(other.as_bytes())
+    }
+}
+
 impl<'a, 'b> PartialEq<PassKey<'b>> for PassKey<'a> {
+    #[inline]
     fn eq(&self, other: &PassKey<'b>) -> bool {
-        **self == **other
+        self.as_bytes().ct_eq(other.as_bytes()).into()
     }
 }
 impl Eq for PassKey<'_> {}
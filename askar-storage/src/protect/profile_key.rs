@@ -1,23 +1,29 @@
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 use sha2::Sha256;
 
-use super::hmac_key::{HmacDerive, HmacKey};
+use super::hmac_key::{HmacDerive, HmacKey, KeyedHmac};
 use super::EntryEncryptor;
 use crate::{
     crypto::{
         alg::chacha20::{Chacha20Key, C20P},
-        buffer::{ArrayKey, ResizeBuffer, SecretBytes, WriteBuffer},
+        buffer::{ArrayKey, HexRepr, ResizeBuffer, SecretBytes, WriteBuffer},
         encrypt::{KeyAeadInPlace, KeyAeadMeta},
         generic_array::typenum::{Unsigned, U32},
         kdf::FromKeyDerivation,
+        random::fill_random_buffered,
         repr::KeyGen,
+        subtle::{Choice, ConstantTimeEq},
     },
-    entry::{EncEntryTag, EntryTag},
+    entry::{decode_plaintext_tag, numeric_tag_name, EncEntryTag, Entry, EntryTag},
     error::Error,
 };
 
 pub type ProfileKey = ProfileKeyImpl<Chacha20Key<C20P>, HmacKey<Sha256, U32>>;
 
+const SCAN_TOKEN_AAD: &[u8] = b"scan_token";
+
 /// A record combining the keys required to encrypt and decrypt storage entries
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(bound(
@@ -84,22 +90,28 @@ where
 
     /// Encrypt a value with a predictable nonce, making it searchable
     fn encrypt_searchable(
-        mut buffer: SecretBytes,
+        buffer: SecretBytes,
         enc_key: &Key,
         hmac_key: &HmacKey,
     ) -> Result<Vec<u8>, Error> {
         let nonce = ArrayKey::<Key::NonceSize>::from_key_derivation(
             hmac_key.hmac_deriver(&[buffer.as_ref()]),
         )?;
-        enc_key.encrypt_in_place(&mut buffer, nonce.as_ref(), &[])?;
-        buffer.buffer_insert(0, nonce.as_ref())?;
-        Ok(buffer.into_vec())
+        Self::encrypt_with_nonce(buffer, enc_key, nonce.as_ref())
     }
 
-    fn encrypt(mut buffer: SecretBytes, enc_key: &Key) -> Result<Vec<u8>, Error> {
-        let nonce = ArrayKey::<Key::NonceSize>::random();
-        enc_key.encrypt_in_place(&mut buffer, nonce.as_ref(), &[])?;
-        buffer.buffer_insert(0, nonce.as_ref())?;
+    fn encrypt(buffer: SecretBytes, enc_key: &Key) -> Result<Vec<u8>, Error> {
+        let nonce = ArrayKey::<Key::NonceSize>::new_with(fill_random_buffered);
+        Self::encrypt_with_nonce(buffer, enc_key, nonce.as_ref())
+    }
+
+    fn encrypt_with_nonce(
+        mut buffer: SecretBytes,
+        enc_key: &Key,
+        nonce: &[u8],
+    ) -> Result<Vec<u8>, Error> {
+        enc_key.encrypt_in_place(&mut buffer, nonce, &[])?;
+        buffer.buffer_insert(0, nonce)?;
         Ok(buffer.into_vec())
     }
 
@@ -115,6 +127,19 @@ where
         Ok(buffer)
     }
 
+    /// Decrypt a borrowed ciphertext, copying it directly into the buffer
+    /// which receives the plaintext
+    fn decrypt_slice(ciphertext: &[u8], enc_key: &Key) -> Result<SecretBytes, Error> {
+        let nonce_len = Key::NonceSize::USIZE;
+        if ciphertext.len() < nonce_len {
+            return Err(err_msg!(Encryption, "invalid encrypted value"));
+        }
+        let (nonce, ciphertext) = ciphertext.split_at(nonce_len);
+        let mut buffer = SecretBytes::from_slice(ciphertext);
+        enc_key.decrypt_in_place(&mut buffer, nonce, &[])?;
+        Ok(buffer)
+    }
+
     #[inline]
     fn derive_value_key(&self, category: &[u8], name: &[u8]) -> Result<Key, Error> {
         Ok(Key::from_key_derivation(self.item_hmac_key.hmac_deriver(
@@ -142,19 +167,188 @@ where
     pub fn decrypt_tag_value(&self, enc_tag_value: Vec<u8>) -> Result<SecretBytes, Error> {
         Self::decrypt(enc_tag_value, &self.tag_value_key)
     }
+
+    /// Decrypt a borrowed tag name, such as one decoded into a shared buffer
+    pub fn decrypt_tag_name_slice(&self, enc_tag_name: &[u8]) -> Result<SecretBytes, Error> {
+        Self::decrypt_slice(enc_tag_name, &self.tag_name_key)
+    }
+
+    /// Decrypt a borrowed tag value, such as one decoded into a shared buffer
+    pub fn decrypt_tag_value_slice(&self, enc_tag_value: &[u8]) -> Result<SecretBytes, Error> {
+        Self::decrypt_slice(enc_tag_value, &self.tag_value_key)
+    }
+
+    /// Encrypt a scan continuation token. The associated data prevents
+    /// the token from being exchanged with an encrypted entry name.
+    pub fn encrypt_scan_token(&self, token: &[u8]) -> Result<Vec<u8>, Error> {
+        let nonce = ArrayKey::<Key::NonceSize>::new_with(fill_random_buffered);
+        let mut buffer = SecretBytes::from_slice(token);
+        self.name_key
+            .encrypt_in_place(&mut buffer, nonce.as_ref(), SCAN_TOKEN_AAD)?;
+        buffer.buffer_insert(0, nonce.as_ref())?;
+        Ok(buffer.into_vec())
+    }
+
+    /// Decrypt a scan continuation token, rejecting any modified token
+    pub fn decrypt_scan_token(&self, enc_token: &[u8]) -> Result<SecretBytes, Error> {
+        let nonce_len = Key::NonceSize::USIZE;
+        if enc_token.len() < nonce_len {
+            return Err(err_msg!(Encryption, "invalid encrypted value"));
+        }
+        let (nonce, ciphertext) = enc_token.split_at(nonce_len);
+        let mut buffer = SecretBytes::from_slice(ciphertext);
+        self.name_key
+            .decrypt_in_place(&mut buffer, nonce, SCAN_TOKEN_AAD)?;
+        Ok(buffer)
+    }
+
+    /// Decrypt an entry value borrowed from a result row, avoiding an
+    /// intermediate copy of the ciphertext
+    pub fn decrypt_entry_value_slice(
+        &self,
+        category: &[u8],
+        name: &[u8],
+        enc_value: &[u8],
+    ) -> Result<SecretBytes, Error> {
+        let value_key = self.derive_value_key(category, name)?;
+        Self::decrypt_slice(enc_value, &value_key)
+    }
+
+    /// Create an encryptor for a batch of entries
+    pub fn batch_encryptor(&self) -> Result<EntryBatchEncryptor<'_, Key, HmacKey>, Error> {
+        Ok(EntryBatchEncryptor {
+            key: self,
+            item_hmac: self.item_hmac_key.keyed_hmac()?,
+            tags_hmac: self.tags_hmac_key.keyed_hmac()?,
+            categories: HashMap::new(),
+            tag_names: HashMap::new(),
+            tag_values: HashMap::new(),
+        })
+    }
+}
+
+/// Encrypts the entries of a batch with a shared profile key
+///
+/// Categories, names and tags are encrypted with nonces derived from their
+/// values, so equal values always produce equal ciphertexts. The encryptor
+/// derives these nonces from HMAC states which are keyed once for the batch,
+/// and encrypts each distinct category, tag name and tag value only once,
+/// reusing the ciphertext for repeated values.
+pub struct EntryBatchEncryptor<'k, Key, HmacKey: HmacDerive> {
+    key: &'k ProfileKeyImpl<Key, HmacKey>,
+    item_hmac: KeyedHmac<HmacKey::Hash>,
+    tags_hmac: KeyedHmac<HmacKey::Hash>,
+    categories: HashMap<SecretBytes, Vec<u8>>,
+    tag_names: HashMap<SecretBytes, Vec<u8>>,
+    tag_values: HashMap<SecretBytes, Vec<u8>>,
+}
+
+impl<Key, HmacKey> EntryBatchEncryptor<'_, Key, HmacKey>
+where
+    Key: KeyAeadInPlace + KeyAeadMeta + FromKeyDerivation,
+    HmacKey: HmacDerive,
+{
+    fn encrypt_searchable(
+        cache: &mut HashMap<SecretBytes, Vec<u8>>,
+        buffer: SecretBytes,
+        enc_key: &Key,
+        hmac: &KeyedHmac<HmacKey::Hash>,
+    ) -> Result<Vec<u8>, Error> {
+        if let Some(enc) = cache.get(&buffer) {
+            return Ok(enc.clone());
+        }
+        let input = SecretBytes::from(buffer.as_ref());
+        let nonce =
+            ArrayKey::<Key::NonceSize>::from_key_derivation(hmac.hmac_deriver(&[buffer.as_ref()]))?;
+        let enc =
+            ProfileKeyImpl::<Key, HmacKey>::encrypt_with_nonce(buffer, enc_key, nonce.as_ref())?;
+        cache.insert(input, enc.clone());
+        Ok(enc)
+    }
+
+    pub fn encrypt_entry_category(&mut self, category: SecretBytes) -> Result<Vec<u8>, Error> {
+        Self::encrypt_searchable(
+            &mut self.categories,
+            category,
+            &self.key.category_key,
+            &self.item_hmac,
+        )
+    }
+
+    pub fn encrypt_entry_name(&mut self, name: SecretBytes) -> Result<Vec<u8>, Error> {
+        let nonce = ArrayKey::<Key::NonceSize>::from_key_derivation(
+            self.item_hmac.hmac_deriver(&[name.as_ref()]),
+        )?;
+        ProfileKeyImpl::<Key, HmacKey>::encrypt_with_nonce(name, &self.key.name_key, nonce.as_ref())
+    }
+
+    pub fn encrypt_entry_value(
+        &mut self,
+        category: &[u8],
+        name: &[u8],
+        value: SecretBytes,
+    ) -> Result<Vec<u8>, Error> {
+        let value_key = Key::from_key_derivation(self.item_hmac.hmac_deriver(&[
+            &(category.len() as u32).to_be_bytes(),
+            category,
+            &(name.len() as u32).to_be_bytes(),
+            name,
+        ]))?;
+        ProfileKeyImpl::<Key, HmacKey>::encrypt(value, &value_key)
+    }
+
+    pub fn encrypt_entry_tags(&mut self, tags: Vec<EntryTag>) -> Result<Vec<EncEntryTag>, Error> {
+        tags.into_iter()
+            .map(|tag| {
+                let (name, value, plaintext) = match tag {
+                    EntryTag::Plaintext(name, value) => (name.into(), value.into_bytes(), true),
+                    EntryTag::Numeric(name, value) => {
+                        (numeric_tag_name(&name).into(), value.into_bytes(), true)
+                    }
+                    EntryTag::Encrypted(name, value) => {
+                        let value = Self::encrypt_searchable(
+                            &mut self.tag_values,
+                            value.into(),
+                            &self.key.tag_value_key,
+                            &self.tags_hmac,
+                        )?;
+                        (name.into(), value, false)
+                    }
+                };
+                let name = Self::encrypt_searchable(
+                    &mut self.tag_names,
+                    name,
+                    &self.key.tag_name_key,
+                    &self.tags_hmac,
+                )?;
+                Ok(EncEntryTag {
+                    name,
+                    value,
+                    plaintext,
+                })
+            })
+            .collect()
+    }
+}
+
+impl<Key: ConstantTimeEq, HmacKey: ConstantTimeEq> ConstantTimeEq for ProfileKeyImpl<Key, HmacKey> {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.category_key.ct_eq(&other.category_key)
+            & self.name_key.ct_eq(&other.name_key)
+            & self.item_hmac_key.ct_eq(&other.item_hmac_key)
+            & self.tag_name_key.ct_eq(&other.tag_name_key)
+            & self.tag_value_key.ct_eq(&other.tag_value_key)
+            & self.tags_hmac_key.ct_eq(&other.tags_hmac_key)
+    }
 }
 
-impl<Key: PartialEq, HmacKey: PartialEq> PartialEq for ProfileKeyImpl<Key, HmacKey> {
+impl<Key: ConstantTimeEq, HmacKey: ConstantTimeEq> PartialEq for ProfileKeyImpl<Key, HmacKey> {
+    #[inline]
     fn eq(&self, other: &Self) -> bool {
-        self.category_key == other.category_key
-            && self.name_key == other.name_key
-            && self.item_hmac_key == other.item_hmac_key
-            && self.tag_name_key == other.tag_name_key
-            && self.tag_value_key == other.tag_value_key
-            && self.tags_hmac_key == other.tags_hmac_key
+        self.ct_eq(other).into()
     }
 }
-impl<Key: PartialEq, HmacKey: PartialEq> Eq for ProfileKeyImpl<Key, HmacKey> {}
+impl<Key: ConstantTimeEq, HmacKey: ConstantTimeEq> Eq for ProfileKeyImpl<Key, HmacKey> {}
 
 impl<Key, HmacKey> EntryEncryptor for ProfileKeyImpl<Key, HmacKey>
 where
@@ -204,44 +398,63 @@ where
     }
 
     fn encrypt_entry_tags(&self, tags: Vec<EntryTag>) -> Result<Vec<EncEntryTag>, Error> {
-        tags.into_iter()
-            .map(|tag| match tag {
-                EntryTag::Plaintext(name, value) => {
-                    let name = self.encrypt_tag_name(name.into())?;
-                    Ok(EncEntryTag {
-                        name,
-                        value: value.into_bytes(),
-                        plaintext: true,
-                    })
-                }
-                EntryTag::Encrypted(name, value) => {
-                    let name = self.encrypt_tag_name(name.into())?;
-                    let value = self.encrypt_tag_value(value.into())?;
-                    Ok(EncEntryTag {
-                        name,
-                        value,
-                        plaintext: false,
-                    })
-                }
-            })
-            .collect()
+        self.batch_encryptor()?.encrypt_entry_tags(tags)
     }
 
     fn decrypt_entry_tags(&self, enc_tags: Vec<EncEntryTag>) -> Result<Vec<EntryTag>, Error> {
         enc_tags.into_iter().try_fold(vec![], |mut acc, tag| {
-            let name = decode_utf8(self.decrypt_tag_name(tag.name)?.into_vec())?;
+            let name = self.decrypt_tag_name(tag.name)?.into_vec();
             acc.push(if tag.plaintext {
-                let value = decode_utf8(tag.value)?;
-                EntryTag::Plaintext(name, value)
+                decode_plaintext_tag(name, tag.value)?
             } else {
                 let value = decode_utf8(self.decrypt_tag_value(tag.value)?.into_vec())?;
-                EntryTag::Encrypted(name, value)
+                EntryTag::Encrypted(decode_utf8(name)?, value)
             });
             Result::Ok(acc)
         })
     }
 }
 
+/// Generate a new profile key and encrypt an entry with it, producing a
+/// known-answer test vector for the encryption of store records
+///
+/// The profile key and value nonce are taken from the default random number
+/// generator. Binary values are hex encoded.
+pub fn entry_test_vector(entry: &Entry) -> Result<serde_json::Value, Error> {
+    let key = ProfileKey::new()?;
+    let category = key.encrypt_entry_category(entry.category.as_bytes().into())?;
+    let name = key.encrypt_entry_name(entry.name.as_bytes().into())?;
+    let value = key.encrypt_entry_value(
+        entry.category.as_bytes(),
+        entry.name.as_bytes(),
+        ProfileKey::prepare_input(&entry.value),
+    )?;
+    let tags = key
+        .encrypt_entry_tags(entry.tags.clone())?
+        .into_iter()
+        .zip(&entry.tags)
+        .map(|(enc, tag)| {
+            serde_json::json!({
+                "name": tag.name(),
+                "value": tag.value(),
+                "plaintext": enc.plaintext,
+                "enc_name": HexRepr(enc.name).to_string(),
+                "enc_value": HexRepr(enc.value).to_string(),
+            })
+        })
+        .collect::<Vec<_>>();
+    Ok(serde_json::json!({
+        "profile_key": HexRepr(key.to_bytes()?).to_string(),
+        "category": entry.category,
+        "name": entry.name,
+        "value": HexRepr(&entry.value).to_string(),
+        "enc_category": HexRepr(category).to_string(),
+        "enc_name": HexRepr(name).to_string(),
+        "enc_value": HexRepr(value).to_string(),
+        "tags": tags,
+    }))
+}
+
 #[inline(always)]
 fn decode_utf8(value: Vec<u8>) -> Result<String, Error> {
     String::from_utf8(value).map_err(err_map!(Encryption))
@@ -282,6 +495,30 @@ mod tests {
         assert_ne!(test_record.category.as_bytes(), &enc_category[..]);
         assert_ne!(test_record.name.as_bytes(), &enc_name[..]);
         assert_ne!(test_record.value, enc_value);
+        assert_eq!(
+            key.decrypt_entry_value_slice(
+                test_record.category.as_bytes(),
+                test_record.name.as_bytes(),
+                &enc_value,
+            )
+            .unwrap(),
+            test_record.value
+        );
+        assert!(key
+            .decrypt_entry_value_slice(
+                test_record.category.as_bytes(),
+                test_record.name.as_bytes(),
+                &enc_value[..4],
+            )
+            .is_err());
+        assert_eq!(
+            key.decrypt_tag_name_slice(&enc_tags[1].name).unwrap(),
+            "enctag".as_bytes()
+        );
+        assert_eq!(
+            key.decrypt_tag_value_slice(&enc_tags[1].value).unwrap(),
+            "envtagval".as_bytes()
+        );
 
         let cmp_record = Entry::new(
             EntryKind::Item,
@@ -312,6 +549,60 @@ mod tests {
         assert_eq!(dec, input);
     }
 
+    #[test]
+    fn batch_encrypt_matches_single() {
+        let key = ProfileKey::new().unwrap();
+        let tags = vec![
+            EntryTag::Plaintext("plain".to_string(), "tag".to_string()),
+            EntryTag::Encrypted("enctag".to_string(), "envtagval".to_string()),
+        ];
+        let mut batch = key.batch_encryptor().unwrap();
+        let mut values = Vec::new();
+        for name in ["first", "second", "third"] {
+            assert_eq!(
+                batch.encrypt_entry_category("category".into()).unwrap(),
+                key.encrypt_entry_category("category".into()).unwrap()
+            );
+            assert_eq!(
+                batch.encrypt_entry_name(name.into()).unwrap(),
+                key.encrypt_entry_name(name.into()).unwrap()
+            );
+            let enc_tags = batch.encrypt_entry_tags(tags.clone()).unwrap();
+            assert_eq!(enc_tags, key.encrypt_entry_tags(tags.clone()).unwrap());
+            assert_eq!(key.decrypt_entry_tags(enc_tags).unwrap(), tags);
+            let enc_value = batch
+                .encrypt_entry_value(b"category", name.as_bytes(), name.into())
+                .unwrap();
+            assert_eq!(
+                key.decrypt_entry_value(b"category", name.as_bytes(), enc_value.clone())
+                    .unwrap(),
+                name.as_bytes()
+            );
+            values.push(enc_value[..Chacha20Key::<C20P>::NONCE_LENGTH].to_vec());
+        }
+        // each value is encrypted with a distinct random nonce
+        values.sort();
+        values.dedup();
+        assert_eq!(values.len(), 3);
+    }
+
+    #[test]
+    fn scan_token_round_trip() {
+        let key = ProfileKey::new().unwrap();
+        let enc_token = key.encrypt_scan_token(b"c:1").unwrap();
+        assert_eq!(key.decrypt_scan_token(&enc_token).unwrap(), &b"c:1"[..]);
+
+        let mut modified = enc_token.clone();
+        *modified.last_mut().unwrap() ^= 1;
+        assert!(key.decrypt_scan_token(&modified).is_err());
+        let other = ProfileKey::new().unwrap();
+        assert!(other.decrypt_scan_token(&enc_token).is_err());
+
+        // an encrypted entry name is not accepted as a token
+        let enc_name = key.encrypt_entry_name("c:1".into()).unwrap();
+        assert!(key.decrypt_scan_token(&enc_name).is_err());
+    }
+
     #[test]
     fn serialize_round_trip() {
         let key = ProfileKey::new().unwrap();
@@ -319,4 +610,13 @@ mod tests {
         let key_cmp = serde_cbor::from_slice(&key_cbor).unwrap();
         assert_eq!(key, key_cmp);
     }
+
+    #[test]
+    fn key_ct_eq() {
+        let key = ProfileKey::new().unwrap();
+        let other = ProfileKey::new().unwrap();
+        assert!(bool::from(key.ct_eq(&key.clone())));
+        assert!(!bool::from(key.ct_eq(&other)));
+        assert_ne!(key, other);
+    }
 }
@@ -0,0 +1,368 @@
+//! Store keys requiring a quorum of independent secrets
+//!
+//! A quorum store key is a random key which is split into one Shamir key
+//! share for each of a set of slots, any `threshold` of which are required to
+//! recover the store key. The share of each slot is encrypted using a key
+//! resolved from the secret of the slot holder: either a passphrase, using a
+//! key derivation method, or a raw key such as one held by a KMS. The pass
+//! key for a quorum store key is a JSON array containing the secret for each
+//! slot, or `null` for each slot whose holder is not present.
+
+use percent_encoding::{percent_decode_str, utf8_percent_encode, NON_ALPHANUMERIC};
+
+use super::{
+    pass_key::PassKey,
+    shamir::{combine_pass_key, split_pass_key},
+    store_key::{parse_raw_store_key, StoreKey, StoreKeyMethod, StoreKeyReference},
+};
+use crate::{crypto::buffer::SecretBytes, error::Error};
+
+pub const PREFIX_QUORUM: &str = "quorum";
+
+/// The method of a store key requiring a quorum of secrets
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct QuorumMethod {
+    threshold: u8,
+    slots: Vec<StoreKeyMethod>,
+}
+
+impl QuorumMethod {
+    /// Create a new quorum method, requiring the secrets of `threshold` of
+    /// the slots, each of which uses a key derivation method or a raw key
+    pub fn new(threshold: u8, slots: Vec<StoreKeyMethod>) -> Result<Self, Error> {
+        if threshold < 2 {
+            return Err(err_msg!(Input, "Quorum threshold must be at least 2"));
+        }
+        if slots.len() < threshold as usize {
+            return Err(err_msg!(
+                Input,
+                "Quorum slot count must be at least the quorum threshold"
+            ));
+        }
+        if slots.len() >= u8::MAX as usize {
+            return Err(err_msg!(Input, "Quorum slot count must be less than 255"));
+        }
+        if slots
+            .iter()
+            .any(|slot| !matches!(slot, StoreKeyMethod::DeriveKey(_) | StoreKeyMethod::RawKey))
+        {
+            return Err(err_msg!(Unsupported, "Unsupported quorum slot method"));
+        }
+        Ok(Self { threshold, slots })
+    }
+
+    /// Accessor for the number of secrets required to recover the store key
+    pub fn threshold(&self) -> u8 {
+        self.threshold
+    }
+
+    /// Accessor for the key methods of the slots
+    pub fn slots(&self) -> &[StoreKeyMethod] {
+        &self.slots
+    }
+
+    /// Parse the method details in the form
+    /// `<threshold>?slot=<method>&slot=<method>...`
+    pub(crate) fn parse_detail(detail: &str) -> Result<Self, Error> {
+        let (threshold, slots) = parse_detail(detail)?;
+        let slots = slots
+            .iter()
+            .map(|slot| StoreKeyMethod::parse_uri(slot))
+            .collect::<Result<_, _>>()?;
+        Self::new(threshold, slots)
+    }
+
+    pub(crate) fn encode(&self) -> String {
+        encode_detail(
+            self.threshold,
+            self.slots.iter().map(|slot| encode_uri(&slot.encode())),
+        )
+    }
+
+    pub(crate) fn resolve(
+        &self,
+        pass_key: PassKey<'_>,
+    ) -> Result<(StoreKey, QuorumReference), Error> {
+        let secrets = parse_secrets(&pass_key, self.slots.len())?;
+        let store_key = StoreKey::random()?;
+        let shares = split_pass_key(
+            &store_key.to_passkey(),
+            self.threshold,
+            self.slots.len() as u8,
+        )?;
+        let mut slots = Vec::with_capacity(self.slots.len());
+        for ((method, secret), share) in self.slots.iter().zip(secrets).zip(shares) {
+            let secret = secret
+                .filter(|secret| !secret.is_empty())
+                .ok_or_else(|| err_msg!(Input, "A secret must be provided for each quorum slot"))?;
+            let (slot_key, slot_ref) = method.resolve(secret)?;
+            let enc_share = slot_key.wrap_data(SecretBytes::from_slice(share.as_bytes()))?;
+            slots.push((slot_ref, bs58::encode(enc_share).into_string()));
+        }
+        Ok((
+            store_key,
+            QuorumReference {
+                threshold: self.threshold,
+                slots,
+            },
+        ))
+    }
+}
+
+/// A reference to a store key requiring a quorum of secrets, containing the
+/// encrypted key share of each slot
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct QuorumReference {
+    threshold: u8,
+    slots: Vec<(StoreKeyReference, String)>,
+}
+
+impl QuorumReference {
+    /// Parse the reference details in the form
+    /// `<threshold>?slot=<key reference>,<encrypted share>...`
+    pub(crate) fn parse_detail(detail: &str) -> Result<Self, Error> {
+        let (threshold, slots) = parse_detail(detail)?;
+        let slots = slots
+            .iter()
+            .map(|slot| {
+                let (key_ref, share) = slot
+                    .rsplit_once(',')
+                    .ok_or_else(|| err_msg!(Input, "Invalid quorum key reference"))?;
+                let key_ref = StoreKeyReference::parse_uri(key_ref)?;
+                if matches!(
+                    key_ref,
+                    StoreKeyReference::Unprotected | StoreKeyReference::Quorum(_)
+                ) {
+                    return Err(err_msg!(Unsupported, "Unsupported quorum slot method"));
+                }
+                Ok((key_ref, share.to_string()))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        if threshold < 2 || slots.len() < threshold as usize {
+            return Err(err_msg!(Input, "Invalid quorum key reference"));
+        }
+        Ok(Self { threshold, slots })
+    }
+
+    pub(crate) fn encode(&self) -> String {
+        encode_detail(
+            self.threshold,
+            self.slots.iter().map(|(key_ref, share)| {
+                format!("{},{}", encode_uri(&key_ref.clone().into_uri()), share)
+            }),
+        )
+    }
+
+    /// Get the method used to create the store key
+    pub fn method(&self) -> QuorumMethod {
+        QuorumMethod {
+            threshold: self.threshold,
+            slots: self
+                .slots
+                .iter()
+                .map(|(key_ref, _)| key_ref.clone().into())
+                .collect(),
+        }
+    }
+
+    /// Recover the store key from the secrets provided for the slots
+    pub(crate) fn resolve(&self, pass_key: PassKey<'_>) -> Result<StoreKey, Error> {
+        let secrets = parse_secrets(&pass_key, self.slots.len())?;
+        let mut shares = Vec::with_capacity(self.threshold as usize);
+        for ((key_ref, enc_share), secret) in self.slots.iter().zip(secrets) {
+            let Some(secret) = secret else {
+                continue;
+            };
+            let enc_share = bs58::decode(enc_share)
+                .into_vec()
+                .map_err(|_| err_msg!(Input, "Invalid quorum key reference"))?;
+            let share = key_ref
+                .resolve(secret)?
+                .unwrap_data(enc_share)
+                .map_err(err_map!(Encryption, "Error decrypting quorum key share"))?;
+            let share = String::from_utf8(share.into_vec())
+                .map_err(|_| err_msg!(Encryption, "Error decrypting quorum key share"))?;
+            shares.push(PassKey::from(share));
+            if shares.len() == self.threshold as usize {
+                break;
+            }
+        }
+        if shares.len() < self.threshold as usize {
+            return Err(err_msg!(
+                Input,
+                "Insufficient quorum secrets: {} of {} required",
+                shares.len(),
+                self.threshold
+            ));
+        }
+        parse_raw_store_key(&combine_pass_key(&shares)?)
+    }
+}
+
+/// Combine the secrets of the slots of a quorum store key into a pass key,
+/// using `None` for each slot whose secret is not provided
+pub fn quorum_pass_key(secrets: &[Option<PassKey<'_>>]) -> Result<PassKey<'static>, Error> {
+    let secrets = secrets
+        .iter()
+        .map(|secret| secret.as_ref().map(|s| &**s))
+        .collect::<Vec<_>>();
+    serde_json::to_string(&secrets)
+        .map(PassKey::from)
+        .map_err(err_map!(Unexpected, "Error encoding quorum pass key"))
+}
+
+fn parse_secrets(pass_key: &str, count: usize) -> Result<Vec<Option<PassKey<'static>>>, Error> {
+    let secrets: Vec<Option<String>> = serde_json::from_str(pass_key)
+        .map_err(err_map!(Input, "Invalid pass key for quorum store key"))?;
+    if secrets.len() != count {
+        return Err(err_msg!(
+            Input,
+            "Quorum pass key must contain a secret or null value for each of {} slots",
+            count
+        ));
+    }
+    Ok(secrets
+        .into_iter()
+        .map(|secret| secret.map(PassKey::from))
+        .collect())
+}
+
+fn parse_detail(detail: &str) -> Result<(u8, Vec<String>), Error> {
+    let (threshold, slots) = detail
+        .split_once('?')
+        .ok_or_else(|| err_msg!(Input, "Quorum slots not provided"))?;
+    let threshold = threshold
+        .parse()
+        .map_err(|_| err_msg!(Input, "Invalid quorum threshold"))?;
+    let slots = slots
+        .split('&')
+        .map(|param| {
+            let slot = param
+                .strip_prefix("slot=")
+                .ok_or_else(|| err_msg!(Input, "Invalid quorum parameter"))?;
+            percent_decode_str(slot)
+                .decode_utf8()
+                .map(|slot| slot.into_owned())
+                .map_err(|_| err_msg!(Input, "Invalid quorum parameter"))
+        })
+        .collect::<Result<_, Error>>()?;
+    Ok((threshold, slots))
+}
+
+fn encode_detail(threshold: u8, slots: impl Iterator<Item = String>) -> String {
+    let slots = slots
+        .map(|slot| format!("slot={}", slot))
+        .collect::<Vec<_>>();
+    format!("{}:{}?{}", PREFIX_QUORUM, threshold, slots.join("&"))
+}
+
+fn encode_uri(uri: &str) -> String {
+    utf8_percent_encode(uri, NON_ALPHANUMERIC).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use crate::protect::{
+        generate_raw_store_key,
+        kdf::{KdfMethod, Pbkdf2Hash, Pbkdf2Params},
+    };
+
+    #[test]
+    fn quorum_method_parse() {
+        let pbkdf2 = StoreKeyMethod::DeriveKey(KdfMethod::Pbkdf2(
+            Pbkdf2Params::new(Pbkdf2Hash::Sha256, 10_000).unwrap(),
+        ));
+        let method =
+            QuorumMethod::new(2, vec![pbkdf2.clone(), StoreKeyMethod::RawKey, pbkdf2]).unwrap();
+        let uri = StoreKeyMethod::Quorum(method.clone()).encode();
+        assert_eq!(
+            uri,
+            "quorum:2?slot=kdf%3Apbkdf2%3Ah%3Dsha256%2Ci%3D10000&slot=raw\
+            &slot=kdf%3Apbkdf2%3Ah%3Dsha256%2Ci%3D10000"
+        );
+        assert_eq!(
+            StoreKeyMethod::parse_uri(&uri),
+            Ok(StoreKeyMethod::Quorum(method))
+        );
+        for invalid in [
+            "quorum:1?slot=raw&slot=raw",
+            "quorum:3?slot=raw&slot=raw",
+            "quorum:2",
+            "quorum:2?slot=raw&other=raw",
+        ] {
+            assert_eq!(
+                StoreKeyMethod::parse_uri(invalid).unwrap_err().kind(),
+                ErrorKind::Input
+            );
+        }
+        assert_eq!(
+            StoreKeyMethod::parse_uri("quorum:2?slot=raw&slot=none")
+                .unwrap_err()
+                .kind(),
+            ErrorKind::Unsupported
+        );
+    }
+
+    #[test]
+    fn quorum_key_resolve() {
+        let method = StoreKeyMethod::Quorum(
+            QuorumMethod::new(
+                2,
+                vec![
+                    StoreKeyMethod::DeriveKey(KdfMethod::Pbkdf2(
+                        Pbkdf2Params::new(Pbkdf2Hash::Sha256, 10_000).unwrap(),
+                    )),
+                    StoreKeyMethod::RawKey,
+                    StoreKeyMethod::RawKey,
+                ],
+            )
+            .unwrap(),
+        );
+        let passphrase = PassKey::from("passphrase");
+        let raw1 = generate_raw_store_key(None).unwrap();
+        let raw2 = generate_raw_store_key(None).unwrap();
+
+        // all secrets are required to create the store key
+        let partial = quorum_pass_key(&[Some(passphrase.as_ref()), Some(raw1.as_ref()), None]);
+        assert_eq!(
+            method.resolve(partial.unwrap()).unwrap_err().kind(),
+            ErrorKind::Input
+        );
+        let all = quorum_pass_key(&[
+            Some(passphrase.as_ref()),
+            Some(raw1.as_ref()),
+            Some(raw2.as_ref()),
+        ])
+        .unwrap();
+        let (key, key_ref) = method.resolve(all).unwrap();
+        let key_ref = StoreKeyReference::parse_uri(&key_ref.into_uri()).unwrap();
+        assert!(key_ref.compare_method(&method));
+
+        // any two of the secrets recover the store key
+        for secrets in [
+            [Some(passphrase.as_ref()), Some(raw1.as_ref()), None],
+            [Some(passphrase.as_ref()), None, Some(raw2.as_ref())],
+            [None, Some(raw1.as_ref()), Some(raw2.as_ref())],
+        ] {
+            let pass_key = quorum_pass_key(&secrets).unwrap();
+            assert_eq!(key_ref.resolve(pass_key).unwrap().0, key.0);
+        }
+
+        let single = quorum_pass_key(&[None, Some(raw1.as_ref()), None]).unwrap();
+        assert_eq!(
+            key_ref.resolve(single).unwrap_err().kind(),
+            ErrorKind::Input
+        );
+        let wrong = quorum_pass_key(&[Some("wrong".into()), Some(raw1.as_ref()), None]).unwrap();
+        assert_eq!(
+            key_ref.resolve(wrong).unwrap_err().kind(),
+            ErrorKind::Encryption
+        );
+        assert_eq!(
+            key_ref.resolve("passphrase".into()).unwrap_err().kind(),
+            ErrorKind::Input
+        );
+    }
+}
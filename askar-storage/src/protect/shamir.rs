@@ -0,0 +1,174 @@
+//! Shamir secret sharing of pass keys over GF(2^8)
+//!
+//! Each byte of the pass key is shared independently using a random
+//! polynomial of degree `threshold - 1`. A share is encoded in base58 as the
+//! threshold, the share index and the evaluation of each polynomial at that
+//! index.
+
+use super::pass_key::PassKey;
+use crate::{
+    crypto::{buffer::SecretBytes, random::fill_random},
+    error::Error,
+};
+
+/// Multiply two elements of GF(2^8) using the AES reduction polynomial
+fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut result = 0u8;
+    for _ in 0..8 {
+        // branch-free conditional additions
+        result ^= a & 0u8.wrapping_sub(b & 1);
+        let carry = 0u8.wrapping_sub(a >> 7);
+        a = (a << 1) ^ (carry & 0x1b);
+        b >>= 1;
+    }
+    result
+}
+
+/// Find the multiplicative inverse of a non-zero element of GF(2^8)
+fn gf_inv(a: u8) -> u8 {
+    // a^254 = a^-1
+    let mut result = 1u8;
+    let mut base = a;
+    let mut exp = 254u8;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = gf_mul(result, base);
+        }
+        base = gf_mul(base, base);
+        exp >>= 1;
+    }
+    result
+}
+
+/// Split a pass key into `shares` shares, any `threshold` of which may be
+/// combined to recover the pass key
+pub fn split_pass_key(
+    pass_key: &PassKey<'_>,
+    threshold: u8,
+    shares: u8,
+) -> Result<Vec<PassKey<'static>>, Error> {
+    if threshold < 2 {
+        return Err(err_msg!(Input, "Share threshold must be at least 2"));
+    }
+    if shares < threshold {
+        return Err(err_msg!(
+            Input,
+            "Share count must be at least the share threshold"
+        ));
+    }
+    if shares == u8::MAX {
+        return Err(err_msg!(Input, "Share count must be less than 255"));
+    }
+    let secret = pass_key.as_bytes();
+    if secret.is_empty() {
+        return Err(err_msg!(Input, "Cannot split an empty pass key"));
+    }
+
+    let coeffs = SecretBytes::new_with(secret.len() * (threshold as usize - 1), fill_random);
+    let mut result = Vec::with_capacity(shares as usize);
+    let mut share = SecretBytes::with_capacity(secret.len() + 2);
+    for x in 1..=shares {
+        share.clear();
+        share.extend_from_slice(&[threshold, x]);
+        for (idx, byte) in secret.iter().enumerate() {
+            // evaluate the polynomial for this byte at x using Horner's method
+            let poly = &coeffs.as_ref()[idx * (threshold as usize - 1)..];
+            let mut y = 0u8;
+            for coeff in poly[..(threshold as usize - 1)].iter().rev() {
+                y = gf_mul(y, x) ^ coeff;
+            }
+            y = gf_mul(y, x) ^ byte;
+            share.extend_from_slice(&[y]);
+        }
+        result.push(PassKey::from(bs58::encode(share.as_ref()).into_string()));
+    }
+    Ok(result)
+}
+
+/// Combine shares produced by `split_pass_key` to recover the pass key
+pub fn combine_pass_key(shares: &[PassKey<'_>]) -> Result<PassKey<'static>, Error> {
+    let mut decoded = Vec::with_capacity(shares.len());
+    for share in shares {
+        let share = bs58::decode(&**share)
+            .into_vec()
+            .map(SecretBytes::from)
+            .map_err(|_| err_msg!(Input, "Error parsing key share as base58 value"))?;
+        if share.len() < 3 || share[1] == 0 {
+            return Err(err_msg!(Input, "Invalid key share"));
+        }
+        decoded.push(share);
+    }
+    let Some(first) = decoded.first() else {
+        return Err(err_msg!(Input, "No key shares provided"));
+    };
+    let (threshold, len) = (first[0], first.len());
+    for (idx, share) in decoded.iter().enumerate() {
+        if share[0] != threshold || share.len() != len {
+            return Err(err_msg!(Input, "Inconsistent key shares"));
+        }
+        if decoded[..idx].iter().any(|prev| prev[1] == share[1]) {
+            return Err(err_msg!(Input, "Duplicate key share"));
+        }
+    }
+    if decoded.len() < threshold as usize {
+        return Err(err_msg!(
+            Input,
+            "Insufficient key shares: {} of {} required",
+            decoded.len(),
+            threshold
+        ));
+    }
+    let decoded = &decoded[..threshold as usize];
+
+    // interpolate each polynomial at zero
+    let mut secret = SecretBytes::with_capacity(len - 2);
+    for pos in 2..len {
+        let mut value = 0u8;
+        for (i, share_i) in decoded.iter().enumerate() {
+            let xi = share_i[1];
+            let mut basis = 1u8;
+            for (j, share_j) in decoded.iter().enumerate() {
+                if i != j {
+                    let xj = share_j[1];
+                    basis = gf_mul(basis, gf_mul(xj, gf_inv(xi ^ xj)));
+                }
+            }
+            value ^= gf_mul(share_i[pos], basis);
+        }
+        secret.extend_from_slice(&[value]);
+    }
+    let secret =
+        String::from_utf8(secret.into_vec()).map_err(|_| err_msg!(Input, "Invalid key shares"))?;
+    Ok(PassKey::from(secret))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gf_inverse() {
+        for a in 1..=255u8 {
+            assert_eq!(gf_mul(a, gf_inv(a)), 1);
+        }
+    }
+
+    #[test]
+    fn split_combine() {
+        let pass_key = PassKey::from("pass key value");
+        let shares = split_pass_key(&pass_key, 3, 5).unwrap();
+        assert_eq!(shares.len(), 5);
+        let combined = combine_pass_key(&shares[1..4]).unwrap();
+        assert_eq!(&*combined, &*pass_key);
+        let combined =
+            combine_pass_key(&[shares[4].as_ref(), shares[0].as_ref(), shares[2].as_ref()])
+                .unwrap();
+        assert_eq!(&*combined, &*pass_key);
+
+        let err = combine_pass_key(&shares[..2]).expect_err("Expected insufficient shares");
+        assert!(err.to_string().contains("Insufficient"));
+        combine_pass_key(&[shares[0].as_ref(), shares[0].as_ref(), shares[1].as_ref()])
+            .expect_err("Expected duplicate share error");
+        split_pass_key(&pass_key, 4, 3).expect_err("Expected invalid share count");
+    }
+}
@@ -1,6 +1,7 @@
 use super::kdf::KdfMethod;
 
 use super::pass_key::PassKey;
+use super::quorum::{QuorumMethod, QuorumReference, PREFIX_QUORUM};
 use crate::{
     crypto::{
         alg::chacha20::{Chacha20Key, C20P},
@@ -75,6 +76,9 @@ impl StoreKey {
     pub fn unwrap_data(&self, ciphertext: Vec<u8>) -> Result<SecretBytes, Error> {
         match &self.0 {
             Some(key) => {
+                if ciphertext.len() < StoreKeyNonce::SIZE {
+                    return Err(err_msg!(Encryption, "Invalid length for encrypted data"));
+                }
                 let nonce = StoreKeyNonce::from_slice(&ciphertext[..StoreKeyNonce::SIZE]);
                 let mut buffer = SecretBytes::from(ciphertext);
                 buffer.buffer_remove(0..StoreKeyNonce::SIZE)?;
@@ -109,6 +113,8 @@ pub enum StoreKeyMethod {
     DeriveKey(KdfMethod),
     /// Wrap using an externally-managed raw key
     RawKey,
+    /// Wrap using a random key recovered from a quorum of secrets
+    Quorum(QuorumMethod),
     /// No wrapping key in effect
     Unprotected,
 }
@@ -118,18 +124,29 @@ impl StoreKeyMethod {
     pub fn parse_uri(uri: &str) -> Result<Self, Error> {
         let mut prefix_and_detail = uri.splitn(2, ':');
         let prefix = prefix_and_detail.next().unwrap_or_default();
-        // let detail = prefix_and_detail.next().unwrap_or_default();
+        let detail = prefix_and_detail.next().unwrap_or_default();
         match prefix {
             PREFIX_RAW => Ok(Self::RawKey),
             PREFIX_KDF => {
                 let (method, _) = KdfMethod::decode(uri)?;
                 Ok(Self::DeriveKey(method))
             }
+            PREFIX_QUORUM => Ok(Self::Quorum(QuorumMethod::parse_detail(detail)?)),
             PREFIX_NONE => Ok(Self::Unprotected),
             _ => Err(err_msg!(Unsupported, "Invalid store key method")),
         }
     }
 
+    /// Encode the store key method as a URI string
+    pub fn encode(&self) -> String {
+        match self {
+            Self::DeriveKey(method) => method.encode(None),
+            Self::RawKey => PREFIX_RAW.to_string(),
+            Self::Quorum(method) => method.encode(),
+            Self::Unprotected => PREFIX_NONE.to_string(),
+        }
+    }
+
     pub(crate) fn resolve(
         &self,
         pass_key: PassKey<'_>,
@@ -154,6 +171,10 @@ impl StoreKeyMethod {
                 };
                 Ok((key, StoreKeyReference::RawKey))
             }
+            Self::Quorum(method) => {
+                let (key, key_ref) = method.resolve(pass_key)?;
+                Ok((key, StoreKeyReference::Quorum(key_ref)))
+            }
             Self::Unprotected => Ok((StoreKey::empty(), StoreKeyReference::Unprotected)),
         }
     }
@@ -170,6 +191,7 @@ impl From<StoreKeyReference> for StoreKeyMethod {
         match key_ref {
             StoreKeyReference::DeriveKey(method, _) => Self::DeriveKey(method),
             StoreKeyReference::RawKey => Self::RawKey,
+            StoreKeyReference::Quorum(key_ref) => Self::Quorum(key_ref.method()),
             StoreKeyReference::Unprotected => Self::Unprotected,
         }
     }
@@ -180,6 +202,7 @@ pub enum StoreKeyReference {
     // ManagedKey(String),
     DeriveKey(KdfMethod, String),
     RawKey,
+    Quorum(QuorumReference),
     Unprotected,
 }
 
@@ -193,6 +216,9 @@ impl StoreKeyReference {
                 let (method, detail) = KdfMethod::decode(uri)?;
                 Ok(Self::DeriveKey(method, detail))
             }
+            PREFIX_QUORUM => Ok(Self::Quorum(QuorumReference::parse_detail(
+                prefix_and_detail.next().unwrap_or_default(),
+            )?)),
             PREFIX_NONE => Ok(Self::Unprotected),
             _ => Err(err_msg!(
                 Unsupported,
@@ -208,6 +234,9 @@ impl StoreKeyReference {
                 matches!(method, StoreKeyMethod::DeriveKey(m) if m == kdf_method)
             }
             Self::RawKey => *method == StoreKeyMethod::RawKey,
+            Self::Quorum(key_ref) => {
+                matches!(method, StoreKeyMethod::Quorum(m) if *m == key_ref.method())
+            }
             Self::Unprotected => *method == StoreKeyMethod::Unprotected,
         }
     }
@@ -217,6 +246,7 @@ impl StoreKeyReference {
             // Self::ManagedKey(keyref) => keyref,
             Self::DeriveKey(method, detail) => method.encode(Some(detail.as_str())),
             Self::RawKey => PREFIX_RAW.to_string(),
+            Self::Quorum(key_ref) => key_ref.encode(),
             Self::Unprotected => PREFIX_NONE.to_string(),
         }
     }
@@ -238,6 +268,7 @@ impl StoreKeyReference {
                     Err(err_msg!(Input, "Encoded raw key not provided"))
                 }
             }
+            Self::Quorum(key_ref) => key_ref.resolve(pass_key),
             Self::Unprotected => Ok(StoreKey::empty()),
         }
     }
@@ -247,6 +278,7 @@ impl StoreKeyReference {
 mod tests {
     use super::*;
     use crate::error::ErrorKind;
+    use crate::protect::kdf::{Argon2Level, Pbkdf2Hash, Pbkdf2Params, ScryptParams};
 
     #[test]
     fn protection_method_parse() {
@@ -265,6 +297,121 @@ mod tests {
         );
     }
 
+    #[test]
+    fn protection_method_parse_argon2_params() {
+        let parse = StoreKeyMethod::parse_uri;
+        let custom = StoreKeyMethod::DeriveKey(KdfMethod::Argon2i(
+            Argon2Level::custom(16384, 2, 1).unwrap(),
+        ));
+        assert_eq!(parse("kdf:argon2i:m=16384,t=2"), Ok(custom.clone()));
+        assert_eq!(parse("kdf:argon2i:13:t=2,m=16384,p=1"), Ok(custom));
+        assert_eq!(
+            parse("kdf:argon2i:13:int"),
+            Ok(StoreKeyMethod::DeriveKey(KdfMethod::Argon2i(
+                Argon2Level::Interactive
+            )))
+        );
+        for invalid in [
+            "kdf:argon2i:m=1024,t=2",
+            "kdf:argon2i:m=16384,t=0",
+            "kdf:argon2i:m=16384,t=2,p=100",
+            "kdf:argon2i:m=16384",
+            "kdf:argon2i:m=16384,t=2,t=3",
+            "kdf:argon2i:m=abc,t=2",
+        ] {
+            assert_eq!(parse(invalid).unwrap_err().kind(), ErrorKind::Input);
+        }
+        assert_eq!(
+            parse("kdf:argon2i:m=16384,t=2,x=1").unwrap_err().kind(),
+            ErrorKind::Unsupported
+        );
+    }
+
+    #[test]
+    fn protection_method_parse_alt_kdf() {
+        let parse = StoreKeyMethod::parse_uri;
+        assert_eq!(
+            parse("kdf:scrypt"),
+            Ok(StoreKeyMethod::DeriveKey(KdfMethod::Scrypt(
+                Default::default()
+            )))
+        );
+        assert_eq!(
+            parse("kdf:scrypt:ln=12,r=8,p=2"),
+            Ok(StoreKeyMethod::DeriveKey(KdfMethod::Scrypt(
+                ScryptParams::new(12, 8, 2).unwrap()
+            )))
+        );
+        assert_eq!(
+            parse("kdf:pbkdf2"),
+            Ok(StoreKeyMethod::DeriveKey(KdfMethod::Pbkdf2(
+                Default::default()
+            )))
+        );
+        assert_eq!(
+            parse("kdf:pbkdf2:h=sha512,i=210000"),
+            Ok(StoreKeyMethod::DeriveKey(KdfMethod::Pbkdf2(
+                Pbkdf2Params::new(Pbkdf2Hash::Sha512, 210000).unwrap()
+            )))
+        );
+        assert_eq!(
+            parse("kdf:pbkdf2:i=1000").unwrap_err().kind(),
+            ErrorKind::Input
+        );
+        assert_eq!(
+            parse("kdf:bcrypt").unwrap_err().kind(),
+            ErrorKind::Unsupported
+        );
+    }
+
+    #[test]
+    fn derived_key_alt_kdf() {
+        let pass = PassKey::from("pass");
+        for (method, prefix) in [
+            (
+                KdfMethod::Scrypt(ScryptParams::new(10, 8, 1).unwrap()),
+                "kdf:scrypt:ln=10,r=8,p=1?salt=",
+            ),
+            (
+                KdfMethod::Pbkdf2(Pbkdf2Params::new(Pbkdf2Hash::Sha256, 10000).unwrap()),
+                "kdf:pbkdf2:h=sha256,i=10000?salt=",
+            ),
+        ] {
+            let (key, key_ref) = StoreKeyMethod::DeriveKey(method)
+                .resolve(pass.as_ref())
+                .expect("Error deriving new key");
+            let key_uri = key_ref.into_uri();
+            assert!(key_uri.starts_with(prefix));
+            let key_ref =
+                StoreKeyReference::parse_uri(&key_uri).expect("Error parsing key reference");
+            let key2 = key_ref.resolve(pass.as_ref()).expect("Error resolving key");
+            let wrapped = key
+                .wrap_data((&b"test data"[..]).into())
+                .expect("Error wrapping input");
+            let unwrapped = key2.unwrap_data(wrapped).expect("Error unwrapping data");
+            assert_eq!(unwrapped, &b"test data"[..]);
+        }
+    }
+
+    #[test]
+    fn derived_key_argon2_params() {
+        let pass = PassKey::from("pass");
+        let method =
+            StoreKeyMethod::DeriveKey(KdfMethod::Argon2i(Argon2Level::custom(8192, 1, 2).unwrap()));
+        let (key, key_ref) = method
+            .resolve(pass.as_ref())
+            .expect("Error deriving new key");
+        let key_uri = key_ref.into_uri();
+        assert!(key_uri.starts_with("kdf:argon2i:13:m=8192,t=1,p=2?salt="));
+        let key_ref = StoreKeyReference::parse_uri(&key_uri).expect("Error parsing key reference");
+        let key2 = key_ref.resolve(pass).expect("Error resolving derived key");
+        let wrapped = key
+            .wrap_data((&b"test data"[..]).into())
+            .expect("Error wrapping input");
+        let unwrapped = key2.unwrap_data(wrapped).expect("Error unwrapping data");
+        assert_eq!(unwrapped, &b"test data"[..]);
+    }
+
     #[test]
     fn derived_key_wrap() {
         let input = b"test data";
@@ -1,5 +1,5 @@
 mod query;
-pub use query::{AbstractQuery, Query};
+pub use query::{AbstractQuery, Query, NUMERIC_KEY_PREFIX};
 
 pub mod sql;
 pub mod tags;
@@ -25,6 +25,8 @@ pub enum AbstractQuery<K, V> {
     Lte(K, V),
     /// SQL 'LIKE'-compatible string comparison for a field value
     Like(K, V),
+    /// Prefix match for a field value
+    Prefix(K, V),
     /// Match one of multiple field values in a set
     In(K, Vec<V>),
     /// Match any non-null field value of the given field names
@@ -34,6 +36,13 @@ pub enum AbstractQuery<K, V> {
 /// A concrete query implementation with String keys and values
 pub type Query = AbstractQuery<String, String>;
 
+/// The prefix of a query key which refers to a numeric tag.
+///
+/// Numeric tags are selected in WQL by comparing a plaintext (`~`) tag with
+/// a number rather than a string. Keys starting with this prefix are not
+/// accepted when parsing WQL.
+pub const NUMERIC_KEY_PREFIX: char = '\0';
+
 impl<K, V> AbstractQuery<K, V> {
     /// Perform basic query clause optimization
     pub fn optimise(self) -> Option<Self> {
@@ -125,6 +134,10 @@ impl<K, V> AbstractQuery<K, V> {
                 let tag_value = vf(&tag_name, tag_value)?;
                 Ok(AbstractQuery::<RK, RV>::Like(kf(tag_name)?, tag_value))
             }
+            Self::Prefix(tag_name, tag_value) => {
+                let tag_value = vf(&tag_name, tag_value)?;
+                Ok(AbstractQuery::<RK, RV>::Prefix(kf(tag_name)?, tag_value))
+            }
             Self::In(tag_name, tag_values) => {
                 let tag_values = tag_values
                     .into_iter()
@@ -170,9 +183,9 @@ mod serde_support {
 
     use serde::ser::{Serialize, Serializer};
     use serde::{de, Deserialize, Deserializer};
-    use serde_json::{self, json, Value as JsonValue};
+    use serde_json::{self, json, Number, Value as JsonValue};
 
-    use super::{AbstractQuery, Query};
+    use super::{AbstractQuery, Query, NUMERIC_KEY_PREFIX};
 
     impl<K, V> Serialize for AbstractQuery<K, V>
     where
@@ -232,13 +245,34 @@ mod serde_support {
     {
         fn to_value(&self) -> JsonValue {
             match self {
-                Self::Eq(ref tag_name, ref tag_value) => json!({ tag_name: tag_value }),
-                Self::Neq(ref tag_name, ref tag_value) => json!({tag_name: {"$neq": tag_value}}),
-                Self::Gt(ref tag_name, ref tag_value) => json!({tag_name: {"$gt": tag_value}}),
-                Self::Gte(ref tag_name, ref tag_value) => json!({tag_name: {"$gte": tag_value}}),
-                Self::Lt(ref tag_name, ref tag_value) => json!({tag_name: {"$lt": tag_value}}),
-                Self::Lte(ref tag_name, ref tag_value) => json!({tag_name: {"$lte": tag_value}}),
+                Self::Eq(ref tag_name, ref tag_value) => {
+                    let (tag_name, tag_value) = key_value(tag_name, tag_value);
+                    json!({ tag_name: tag_value })
+                }
+                Self::Neq(ref tag_name, ref tag_value) => {
+                    let (tag_name, tag_value) = key_value(tag_name, tag_value);
+                    json!({tag_name: {"$neq": tag_value}})
+                }
+                Self::Gt(ref tag_name, ref tag_value) => {
+                    let (tag_name, tag_value) = key_value(tag_name, tag_value);
+                    json!({tag_name: {"$gt": tag_value}})
+                }
+                Self::Gte(ref tag_name, ref tag_value) => {
+                    let (tag_name, tag_value) = key_value(tag_name, tag_value);
+                    json!({tag_name: {"$gte": tag_value}})
+                }
+                Self::Lt(ref tag_name, ref tag_value) => {
+                    let (tag_name, tag_value) = key_value(tag_name, tag_value);
+                    json!({tag_name: {"$lt": tag_value}})
+                }
+                Self::Lte(ref tag_name, ref tag_value) => {
+                    let (tag_name, tag_value) = key_value(tag_name, tag_value);
+                    json!({tag_name: {"$lte": tag_value}})
+                }
                 Self::Like(ref tag_name, ref tag_value) => json!({tag_name: {"$like": tag_value}}),
+                Self::Prefix(ref tag_name, ref tag_value) => {
+                    json!({tag_name: {"$prefix": tag_value}})
+                }
                 Self::In(ref tag_name, ref tag_values) => json!({tag_name: {"$in":tag_values}}),
                 Self::Exist(ref tag_names) => {
                     json!({ "$exist": tag_names.iter().map(Into::into).collect::<Vec<String>>() })
@@ -266,6 +300,28 @@ mod serde_support {
         }
     }
 
+    /// Convert the key and value of a comparison, restoring the numeric
+    /// value of a comparison with a numeric tag
+    fn key_value<K, V>(key: &K, value: &V) -> (String, JsonValue)
+    where
+        for<'a> &'a K: Into<String>,
+        V: Serialize,
+    {
+        let key: String = key.into();
+        let value = json!(value);
+        match key.strip_prefix(NUMERIC_KEY_PREFIX) {
+            Some(name) => {
+                let value = value
+                    .as_str()
+                    .and_then(|v| v.parse::<Number>().ok())
+                    .map(JsonValue::Number)
+                    .unwrap_or(value);
+                (format!("~{}", name), value)
+            }
+            None => (key, value),
+        }
+    }
+
     impl string::ToString for Query {
         fn to_string(&self) -> String {
             self.to_value().to_string()
@@ -291,6 +347,9 @@ mod serde_support {
     }
 
     fn parse_operator(key: String, value: JsonValue) -> Result<Option<Query>, &'static str> {
+        if key.starts_with(NUMERIC_KEY_PREFIX) {
+            return Err("Invalid tag name");
+        }
         match (key.as_str(), value) {
             ("$and", JsonValue::Array(values)) => {
                 if values.is_empty() {
@@ -315,6 +374,9 @@ mod serde_support {
                 Ok(Some(Query::Not(Box::new(operator))))
             }
             ("$not", _) => Err("$not must be JSON object"),
+            ("$exist", JsonValue::String(key)) if key.starts_with(NUMERIC_KEY_PREFIX) => {
+                Err("Invalid tag name")
+            }
             ("$exist", JsonValue::String(key)) => Ok(Some(Query::Exist(vec![key]))),
             ("$exist", JsonValue::Array(keys)) => {
                 if keys.is_empty() {
@@ -323,6 +385,9 @@ mod serde_support {
                     let mut ks = vec![];
                     for key in keys {
                         if let JsonValue::String(key) = key {
+                            if key.starts_with(NUMERIC_KEY_PREFIX) {
+                                return Err("Invalid tag name");
+                            }
                             ks.push(key);
                         } else {
                             return Err("$exist must be used with a string or array of strings");
@@ -333,6 +398,9 @@ mod serde_support {
             }
             ("$exist", _) => Err("$exist must be used with a string or array of strings"),
             (_, JsonValue::String(value)) => Ok(Some(Query::Eq(key, value))),
+            (_, JsonValue::Number(value)) => {
+                Ok(Some(Query::Eq(numeric_key(key)?, value.to_string())))
+            }
             (_, JsonValue::Object(map)) => {
                 if map.len() == 1 {
                     let (operator_name, value) = map.into_iter().next().unwrap();
@@ -345,6 +413,14 @@ mod serde_support {
         }
     }
 
+    /// Convert the key of a comparison with a number, which must refer to a
+    /// plaintext tag, into the key of the numeric tag
+    fn numeric_key(key: String) -> Result<String, &'static str> {
+        key.strip_prefix('~')
+            .map(|name| format!("{}{}", NUMERIC_KEY_PREFIX, name))
+            .ok_or("Numbers may only be compared with plaintext tags")
+    }
+
     fn parse_list_operators(operators: Vec<JsonValue>) -> Result<Vec<Query>, &'static str> {
         let mut out_operators: Vec<Query> = Vec::with_capacity(operators.len());
 
@@ -367,17 +443,34 @@ mod serde_support {
     ) -> Result<Query, &'static str> {
         match (operator_name, value) {
             ("$neq", JsonValue::String(value_)) => Ok(Query::Neq(key, value_)),
-            ("$neq", _) => Err("$neq must be used with string"),
+            ("$neq", JsonValue::Number(value_)) => {
+                Ok(Query::Neq(numeric_key(key)?, value_.to_string()))
+            }
+            ("$neq", _) => Err("$neq must be used with string or number"),
             ("$gt", JsonValue::String(value_)) => Ok(Query::Gt(key, value_)),
-            ("$gt", _) => Err("$gt must be used with string"),
+            ("$gt", JsonValue::Number(value_)) => {
+                Ok(Query::Gt(numeric_key(key)?, value_.to_string()))
+            }
+            ("$gt", _) => Err("$gt must be used with string or number"),
             ("$gte", JsonValue::String(value_)) => Ok(Query::Gte(key, value_)),
-            ("$gte", _) => Err("$gte must be used with string"),
+            ("$gte", JsonValue::Number(value_)) => {
+                Ok(Query::Gte(numeric_key(key)?, value_.to_string()))
+            }
+            ("$gte", _) => Err("$gte must be used with string or number"),
             ("$lt", JsonValue::String(value_)) => Ok(Query::Lt(key, value_)),
-            ("$lt", _) => Err("$lt must be used with string"),
+            ("$lt", JsonValue::Number(value_)) => {
+                Ok(Query::Lt(numeric_key(key)?, value_.to_string()))
+            }
+            ("$lt", _) => Err("$lt must be used with string or number"),
             ("$lte", JsonValue::String(value_)) => Ok(Query::Lte(key, value_)),
-            ("$lte", _) => Err("$lte must be used with string"),
+            ("$lte", JsonValue::Number(value_)) => {
+                Ok(Query::Lte(numeric_key(key)?, value_.to_string()))
+            }
+            ("$lte", _) => Err("$lte must be used with string or number"),
             ("$like", JsonValue::String(value_)) => Ok(Query::Like(key, value_)),
             ("$like", _) => Err("$like must be used with string"),
+            ("$prefix", JsonValue::String(value_)) => Ok(Query::Prefix(key, value_)),
+            ("$prefix", _) => Err("$prefix must be used with string"),
             ("$in", JsonValue::Array(values)) => {
                 let mut target_values: Vec<String> = Vec::with_capacity(values.len());
 
@@ -523,6 +616,30 @@ mod tests {
         assert_eq!(query, expected);
     }
 
+    #[test]
+    fn test_simple_operator_numeric_parse() {
+        let json = r#"{"~expires":{"$lt":1700000000},"~score":1.5}"#;
+
+        let query: Query = ::serde_json::from_str(json).unwrap();
+
+        let expected = Query::And(vec![
+            Query::Lt("\0expires".to_string(), "1700000000".to_string()),
+            Query::Eq("\0score".to_string(), "1.5".to_string()),
+        ]);
+
+        assert_eq!(query, expected);
+        assert_eq!(
+            ::serde_json::from_str::<Query>(&query.to_string()).unwrap(),
+            query
+        );
+
+        // numbers are only compared with plaintext tags
+        assert!(::serde_json::from_str::<Query>(r#"{"expires":{"$gt":1}}"#).is_err());
+        // the numeric key prefix is not accepted in WQL
+        assert!(::serde_json::from_str::<Query>(r#"{"\u0000expires":"1"}"#).is_err());
+        assert!(::serde_json::from_str::<Query>(r#"{"$exist":"\u0000expires"}"#).is_err());
+    }
+
     #[test]
     fn test_simple_operator_lte_plaintext_parse() {
         let name1 = _random_string(10);
@@ -551,6 +668,20 @@ mod tests {
         assert_eq!(query, expected);
     }
 
+    #[test]
+    fn test_simple_operator_prefix_parse() {
+        let name1 = _random_string(10);
+        let value1 = _random_string(10);
+
+        let json = format!(r#"{{"{}":{{"$prefix":"{}"}}}}"#, name1, value1);
+
+        let query: Query = ::serde_json::from_str(&json).unwrap();
+
+        let expected = Query::Prefix(name1, value1);
+
+        assert_eq!(query, expected);
+    }
+
     #[test]
     fn test_simple_operator_in_plaintext_parse() {
         let name1 = _random_string(10);
@@ -1772,6 +1903,20 @@ mod tests {
         assert_eq!(json, expected);
     }
 
+    #[test]
+    fn test_simple_operator_prefix_to_string() {
+        let name1 = _random_string(10);
+        let value1 = _random_string(10);
+
+        let query = Query::Prefix(name1.clone(), value1.clone());
+
+        let json = ::serde_json::to_string(&query).unwrap();
+
+        let expected = format!(r#"{{"{}":{{"$prefix":"{}"}}}}"#, name1, value1);
+
+        assert_eq!(json, expected);
+    }
+
     #[test]
     fn test_simple_operator_like_to_string() {
         let name1 = _random_string(10);
@@ -1,35 +1,52 @@
-use std::marker::PhantomData;
-
-use itertools::Itertools;
+use std::{fmt::Write, marker::PhantomData};
 
 use super::tags::{CompareOp, ConjunctionOp, TagName, TagQueryEncoder};
-use crate::error::Error;
+use crate::{entry::numeric_tag_name, error::Error};
 
 pub struct TagSqlEncoder<'e, EN, EV> {
     pub enc_name: EN,
     pub enc_value: EV,
+    pub numeric_value: fn(&str) -> String,
     pub arguments: Vec<Vec<u8>>,
     _pd: PhantomData<&'e ()>,
 }
 
+/// Convert a plaintext tag value expression to a numeric value
+pub fn numeric_value(expr: &str) -> String {
+    format!("CAST({} AS NUMERIC)", expr)
+}
+
 impl<'e, EN, EV> TagSqlEncoder<'e, EN, EV>
 where
-    EN: Fn(&str) -> Result<Vec<u8>, Error> + 'e,
+    EN: Fn(&[u8]) -> Result<Vec<u8>, Error> + 'e,
     EV: Fn(&str) -> Result<Vec<u8>, Error> + 'e,
 {
     pub fn new(enc_name: EN, enc_value: EV) -> Self {
         Self {
             enc_name,
             enc_value,
+            numeric_value,
             arguments: vec![],
             _pd: PhantomData,
         }
     }
 }
 
+/// Find the smallest value greater than every value starting with `prefix`
+fn prefix_upper_bound(prefix: &[u8]) -> Option<Vec<u8>> {
+    let mut upper = prefix.to_vec();
+    while let Some(last) = upper.pop() {
+        if last < u8::MAX {
+            upper.push(last + 1);
+            return Some(upper);
+        }
+    }
+    None
+}
+
 impl<'e, EN, EV> TagQueryEncoder for TagSqlEncoder<'e, EN, EV>
 where
-    EN: Fn(&str) -> Result<Vec<u8>, Error> + 'e,
+    EN: Fn(&[u8]) -> Result<Vec<u8>, Error> + 'e,
     EV: Fn(&str) -> Result<Vec<u8>, Error> + 'e,
 {
     type Arg = Vec<u8>;
@@ -37,7 +54,10 @@ where
 
     fn encode_name(&mut self, name: &TagName) -> Result<Self::Arg, Error> {
         Ok(match name {
-            TagName::Encrypted(name) | TagName::Plaintext(name) => (self.enc_name)(name)?,
+            TagName::Encrypted(name) | TagName::Plaintext(name) => {
+                (self.enc_name)(name.as_bytes())?
+            }
+            TagName::Numeric(name) => (self.enc_name)(&numeric_tag_name(name))?,
         })
     }
 
@@ -55,37 +75,63 @@ where
         enc_name: Self::Arg,
         enc_value: Self::Arg,
         is_plaintext: bool,
+        is_numeric: bool,
         negate: bool,
     ) -> Result<Option<Self::Clause>, Error> {
         let idx = self.arguments.len();
-        let (op_prefix, match_prefix) = match (is_plaintext, op.as_sql_str_for_prefix()) {
+        let mut query = tag_clause(negate);
+        push_arg(&mut query, idx + 1);
+        if is_numeric {
+            query.push_str(" AND ");
+            query.push_str(&(self.numeric_value)("value"));
+            query.push(' ');
+            query.push_str(op.as_sql_str());
+            query.push(' ');
+            query.push_str(&(self.numeric_value)(&format!("${}", idx + 2)));
+            self.arguments.push(enc_name);
+            self.arguments.push(enc_value);
+            push_plaintext(&mut query, true);
+            return Ok(Some(query));
+        }
+        if op == CompareOp::Prefix {
+            // match on the range of values starting with the prefix, which
+            // may be resolved using the index on plaintext tag values
+            let upper = prefix_upper_bound(&enc_value);
+            query.push_str(" AND value >= ");
+            push_arg(&mut query, idx + 2);
+            self.arguments.push(enc_name);
+            self.arguments.push(enc_value);
+            if let Some(upper) = upper {
+                query.push_str(" AND value < ");
+                push_arg(&mut query, idx + 3);
+                self.arguments.push(upper);
+            }
+            push_plaintext(&mut query, is_plaintext);
+            return Ok(Some(query));
+        }
+        query.push_str(" AND value ");
+        query.push_str(op.as_sql_str());
+        query.push(' ');
+        push_arg(&mut query, idx + 2);
+        let match_prefix = match (is_plaintext, op.as_sql_str_for_prefix()) {
             (false, Some(pfx_op)) if enc_value.len() > 12 => {
                 // the first 12 characters of an encrypted tag is the nonce, based
                 // on an HMAC of the rest of the value. it serves as an effective index
                 // on its own
-                let match_prefix = enc_value[..12].to_vec();
-                (
-                    format!(" AND SUBSTR(value, 1, 12) {} ${}", pfx_op, idx + 3),
-                    Some(match_prefix),
-                )
+                query.push_str(" AND SUBSTR(value, 1, 12) ");
+                query.push_str(pfx_op);
+                query.push(' ');
+                push_arg(&mut query, idx + 3);
+                Some(enc_value[..12].to_vec())
             }
-            _ => (String::new(), None),
+            _ => None,
         };
         self.arguments.push(enc_name);
         self.arguments.push(enc_value);
         if let Some(v) = match_prefix {
             self.arguments.push(v);
         }
-
-        let query = format!(
-            "i.id {} (SELECT item_id FROM items_tags WHERE name = ${} AND value {} ${}{} AND plaintext = {})",
-            if negate { "NOT IN" } else { "IN" },
-            idx + 1,
-            op.as_sql_str(),
-            idx + 2,
-            op_prefix.as_str(),
-            i32::from(is_plaintext)
-        );
+        push_plaintext(&mut query, is_plaintext);
         Ok(Some(query))
     }
 
@@ -96,14 +142,16 @@ where
         is_plaintext: bool,
         negate: bool,
     ) -> Result<Option<Self::Clause>, Error> {
-        let args_in = Itertools::intersperse(std::iter::repeat("$$").take(enc_values.len()), ", ")
-            .collect::<String>();
-        let query = format!(
-            "i.id {} (SELECT item_id FROM items_tags WHERE name = $$ AND value IN ({}) AND plaintext = {})",
-            if negate { "NOT IN" } else { "IN" },
-            args_in,
-            i32::from(is_plaintext)
-        );
+        let mut query = tag_clause(negate);
+        query.push_str("$$ AND value IN (");
+        for idx in 0..enc_values.len() {
+            if idx > 0 {
+                query.push_str(", ");
+            }
+            query.push_str("$$");
+        }
+        query.push(')');
+        push_plaintext(&mut query, is_plaintext);
         self.arguments.push(enc_name);
         self.arguments.extend(enc_values);
         Ok(Some(query))
@@ -115,11 +163,9 @@ where
         is_plaintext: bool,
         negate: bool,
     ) -> Result<Option<Self::Clause>, Error> {
-        let query = format!(
-            "i.id {} (SELECT item_id FROM items_tags WHERE name = $$ AND plaintext = {})",
-            if negate { "NOT IN" } else { "IN" },
-            i32::from(is_plaintext)
-        );
+        let mut query = tag_clause(negate);
+        query.push_str("$$");
+        push_plaintext(&mut query, is_plaintext);
         self.arguments.push(enc_name);
         Ok(Some(query))
     }
@@ -132,28 +178,61 @@ where
         let qc = clauses.len();
         if qc == 0 {
             if op == ConjunctionOp::Or {
-                return Ok(Some("0".to_string()));
+                return Ok(Some("1 = 0".to_string()));
             } else {
                 return Ok(None);
             }
         }
-        let mut s = String::new();
-        if qc > 1 {
-            s.push('(');
+        if qc == 1 {
+            return Ok(clauses.into_iter().next());
         }
+        let op_str = op.as_sql_str();
+        let len = clauses.iter().map(String::len).sum::<usize>() + op_str.len() * (qc - 1) + 2;
+        let mut s = String::with_capacity(len);
+        s.push('(');
         for (index, clause) in clauses.into_iter().enumerate() {
             if index > 0 {
-                s.push_str(op.as_sql_str());
+                s.push_str(op_str);
             }
             s.push_str(&clause);
         }
-        if qc > 1 {
-            s.push(')');
-        }
+        s.push(')');
         Ok(Some(s))
     }
 }
 
+/// The initial capacity of the buffer for a single tag clause, sufficient
+/// for most clauses to be written without reallocating
+const CLAUSE_CAPACITY: usize = 128;
+
+/// Start a clause selecting the items with a matching tag, ending with the
+/// comparison of the tag name. Negated clauses are correlated with the item
+/// so that they may be resolved as an anti-join on the (item_id, name) index,
+/// rather than materializing every item having the tag.
+fn tag_clause(negate: bool) -> String {
+    let mut query = String::with_capacity(CLAUSE_CAPACITY);
+    if negate {
+        query.push_str("NOT EXISTS (SELECT 1 FROM items_tags WHERE item_id = i.id AND name = ");
+    } else {
+        query.push_str("i.id IN (SELECT item_id FROM items_tags WHERE name = ");
+    }
+    query
+}
+
+/// Append a numbered argument placeholder to a clause
+fn push_arg(query: &mut String, index: usize) {
+    write!(query, "${}", index).unwrap();
+}
+
+/// Complete a tag clause by matching the plaintext flag of the tag
+fn push_plaintext(query: &mut String, is_plaintext: bool) {
+    query.push_str(if is_plaintext {
+        " AND plaintext = 1)"
+    } else {
+        " AND plaintext = 0)"
+    });
+}
+
 #[cfg(test)]
 mod tests {
     use super::super::tags::TagQuery;
@@ -183,11 +262,11 @@ mod tests {
         ]);
         let query = TagQuery::Or(vec![condition_1, condition_2]);
         let mut enc = TagSqlEncoder::new(
-            |name: &str| Ok(format!("--{}--", name).into_bytes()),
+            |name: &[u8]| Ok([b"--", name, b"--"].concat()),
             |value: &str| Ok(value.to_uppercase().into_bytes()),
         );
         let query_str = enc.encode_query(&query).unwrap().unwrap();
-        assert_eq!(query_str, "((i.id IN (SELECT item_id FROM items_tags WHERE name = $1 AND value = $2 AND SUBSTR(value, 1, 12) = $3 AND plaintext = 0) AND i.id IN (SELECT item_id FROM items_tags WHERE name = $4 AND value = $5 AND plaintext = 1)) OR (i.id IN (SELECT item_id FROM items_tags WHERE name = $6 AND value = $7 AND SUBSTR(value, 1, 12) = $8 AND plaintext = 0) AND i.id NOT IN (SELECT item_id FROM items_tags WHERE name = $9 AND value = $10 AND plaintext = 1)))");
+        assert_eq!(query_str, "((i.id IN (SELECT item_id FROM items_tags WHERE name = $1 AND value = $2 AND SUBSTR(value, 1, 12) = $3 AND plaintext = 0) AND i.id IN (SELECT item_id FROM items_tags WHERE name = $4 AND value = $5 AND plaintext = 1)) OR (i.id IN (SELECT item_id FROM items_tags WHERE name = $6 AND value = $7 AND SUBSTR(value, 1, 12) = $8 AND plaintext = 0) AND NOT EXISTS (SELECT 1 FROM items_tags WHERE item_id = i.id AND name = $9 AND value = $10 AND plaintext = 1)))");
         let args = enc.arguments;
         assert_eq!(
             args,
@@ -205,4 +284,94 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn tag_query_encode_numeric() {
+        let query = TagQuery::And(vec![
+            TagQuery::Lt(TagName::Numeric("expires".to_string()), "100".to_string()),
+            TagQuery::Not(Box::new(TagQuery::Gte(
+                TagName::Numeric("expires".to_string()),
+                "10".to_string(),
+            ))),
+        ]);
+        let mut enc = TagSqlEncoder::new(
+            |name: &[u8]| Ok([b"--", name, b"--"].concat()),
+            |value: &str| Ok(value.to_uppercase().into_bytes()),
+        );
+        let query_str = enc.encode_query(&query).unwrap().unwrap();
+        assert_eq!(query_str, "(i.id IN (SELECT item_id FROM items_tags WHERE name = $1 AND CAST(value AS NUMERIC) < CAST($2 AS NUMERIC) AND plaintext = 1) AND NOT EXISTS (SELECT 1 FROM items_tags WHERE item_id = i.id AND name = $3 AND CAST(value AS NUMERIC) >= CAST($4 AS NUMERIC) AND plaintext = 1))");
+        assert_eq!(
+            enc.arguments,
+            vec![
+                b"--\xffexpires--".to_vec(),
+                b"100".to_vec(),
+                b"--\xffexpires--".to_vec(),
+                b"10".to_vec(),
+            ]
+        );
+    }
+
+    #[test]
+    fn tag_query_encode_prefix() {
+        let query = TagQuery::Or(vec![
+            TagQuery::Prefix(TagName::Plaintext("label".to_string()), "ab".to_string()),
+            TagQuery::Not(Box::new(TagQuery::Prefix(
+                TagName::Plaintext("label".to_string()),
+                "\u{ff}".to_string(),
+            ))),
+        ]);
+        let mut enc = TagSqlEncoder::new(
+            |name: &[u8]| Ok([b"--", name, b"--"].concat()),
+            |value: &str| Ok(value.to_uppercase().into_bytes()),
+        );
+        let query_str = enc.encode_query(&query).unwrap().unwrap();
+        assert_eq!(query_str, "(i.id IN (SELECT item_id FROM items_tags WHERE name = $1 AND value >= $2 AND value < $3 AND plaintext = 1) OR NOT EXISTS (SELECT 1 FROM items_tags WHERE item_id = i.id AND name = $4 AND value >= $5 AND value < $6 AND plaintext = 1))");
+        assert_eq!(
+            enc.arguments,
+            vec![
+                b"--label--".to_vec(),
+                b"ab".to_vec(),
+                b"ac".to_vec(),
+                b"--label--".to_vec(),
+                vec![0xc3, 0xbf],
+                vec![0xc3, 0xc0],
+            ]
+        );
+    }
+
+    #[test]
+    fn prefix_upper_bound_carry() {
+        assert_eq!(prefix_upper_bound(b""), None);
+        assert_eq!(prefix_upper_bound(&[0x61, 0xff]), Some(vec![0x62]));
+        assert_eq!(prefix_upper_bound(&[0xff, 0xff]), None);
+    }
+
+    #[test]
+    fn tag_query_encode_not_exist() {
+        let query = TagQuery::Not(Box::new(TagQuery::Exist(vec![
+            TagName::Encrypted("enctag".to_string()),
+            TagName::Plaintext("plaintag".to_string()),
+        ])));
+        let mut enc = TagSqlEncoder::new(
+            |name: &[u8]| Ok([b"--", name, b"--"].concat()),
+            |value: &str| Ok(value.to_uppercase().into_bytes()),
+        );
+        let query_str = enc.encode_query(&query).unwrap().unwrap();
+        assert_eq!(query_str, "(NOT EXISTS (SELECT 1 FROM items_tags WHERE item_id = i.id AND name = $$ AND plaintext = 0) AND NOT EXISTS (SELECT 1 FROM items_tags WHERE item_id = i.id AND name = $$ AND plaintext = 1))");
+        assert_eq!(
+            enc.arguments,
+            vec![b"--enctag--".to_vec(), b"--plaintag--".to_vec()]
+        );
+    }
+
+    #[test]
+    fn tag_query_encode_not_empty() {
+        let query = TagQuery::Not(Box::new(TagQuery::And(vec![])));
+        let mut enc = TagSqlEncoder::new(
+            |name: &[u8]| Ok(name.to_vec()),
+            |value: &str| Ok(value.as_bytes().to_vec()),
+        );
+        let query_str = enc.encode_query(&query).unwrap().unwrap();
+        assert_eq!(query_str, "1 = 0");
+    }
 }
@@ -1,13 +1,18 @@
-use super::{AbstractQuery, Query};
+use super::{query::NUMERIC_KEY_PREFIX, AbstractQuery, Query};
 use crate::error::Error;
 
 pub type TagQuery = AbstractQuery<TagName, String>;
 
 pub fn tag_query(query: Query) -> Result<TagQuery, Error> {
     let result = query
-        .map_names(|k| {
-            if let Some(plain) = k.strip_prefix('~') {
-                Result::<_, ()>::Ok(TagName::Plaintext(plain.to_string()))
+        .map_names(|mut k| {
+            if k.starts_with(NUMERIC_KEY_PREFIX) {
+                k.remove(0);
+                Result::<_, ()>::Ok(TagName::Numeric(k))
+            } else if k.starts_with('~') {
+                // reuse the allocation of the name
+                k.remove(0);
+                Ok(TagName::Plaintext(k))
             } else {
                 Ok(TagName::Encrypted(k))
             }
@@ -17,15 +22,71 @@ pub fn tag_query(query: Query) -> Result<TagQuery, Error> {
     Ok(result)
 }
 
-pub fn validate_tag_query(_query: &TagQuery) -> Result<(), Error> {
+pub fn validate_tag_query(query: &TagQuery) -> Result<(), Error> {
     // FIXME only equality comparison supported for encrypted keys
-    Ok(())
+    match query {
+        TagQuery::Eq(TagName::Numeric(name), value)
+        | TagQuery::Neq(TagName::Numeric(name), value)
+        | TagQuery::Gt(TagName::Numeric(name), value)
+        | TagQuery::Gte(TagName::Numeric(name), value)
+        | TagQuery::Lt(TagName::Numeric(name), value)
+        | TagQuery::Lte(TagName::Numeric(name), value)
+            if !is_numeric_value(value) =>
+        {
+            Err(err_msg!(
+                Input,
+                "Invalid value for comparison with numeric tag: ~{}",
+                name
+            ))
+        }
+        TagQuery::Like(TagName::Numeric(name), _)
+        | TagQuery::Prefix(TagName::Numeric(name), _)
+        | TagQuery::In(TagName::Numeric(name), _) => Err(err_msg!(
+            Input,
+            "Unsupported comparison with numeric tag: ~{}",
+            name
+        )),
+        TagQuery::Prefix(name @ TagName::Encrypted(_), _) => Err(err_msg!(
+            Input,
+            "Prefix matching is only supported for plaintext tags: {}",
+            name.to_string()
+        )),
+        TagQuery::And(subqueries) | TagQuery::Or(subqueries) => {
+            subqueries.iter().try_for_each(validate_tag_query)
+        }
+        TagQuery::Not(subquery) => validate_tag_query(subquery),
+        _ => Ok(()),
+    }
+}
+
+/// The maximum length of a numeric tag value
+pub const MAX_NUMERIC_VALUE_LEN: usize = 64;
+
+/// Check whether a tag value is acceptable for a numeric tag
+pub fn is_numeric_value(value: &str) -> bool {
+    !value.is_empty()
+        && value.len() <= MAX_NUMERIC_VALUE_LEN
+        && value.trim() == value
+        // limit the exponent to three digits, within the range of the database types
+        && value
+            .find(['e', 'E'])
+            .map(|pos| value[pos + 1..].trim_start_matches(['+', '-']).len() <= 3)
+            .unwrap_or(true)
+        && value.parse::<f64>().map(f64::is_finite).unwrap_or(false)
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub enum TagName {
     Encrypted(String),
     Plaintext(String),
+    Numeric(String),
+}
+
+impl TagName {
+    /// Check whether the tag values are stored in plaintext
+    pub fn is_plaintext(&self) -> bool {
+        !matches!(self, Self::Encrypted(_))
+    }
 }
 
 impl ToString for TagName {
@@ -33,6 +94,7 @@ impl ToString for TagName {
         match self {
             Self::Encrypted(v) => v.to_string(),
             Self::Plaintext(v) => format!("~{}", v),
+            Self::Numeric(v) => format!("{}{}", NUMERIC_KEY_PREFIX, v),
         }
     }
 }
@@ -64,6 +126,7 @@ pub trait TagQueryEncoder {
         enc_name: Self::Arg,
         enc_value: Self::Arg,
         is_plaintext: bool,
+        is_numeric: bool,
         negate: bool,
     ) -> Result<Option<Self::Clause>, Error>;
 
@@ -98,6 +161,7 @@ pub enum CompareOp {
     Lt,
     Lte,
     Like,
+    Prefix,
 }
 
 impl CompareOp {
@@ -110,6 +174,8 @@ impl CompareOp {
             Self::Lt => "<",
             Self::Lte => "<=",
             Self::Like => "LIKE",
+            // the lower bound of the matching range; the encoder adds the upper bound
+            Self::Prefix => ">=",
         }
     }
 
@@ -172,6 +238,9 @@ where
         TagQuery::Like(tag_name, target_value) => {
             encode_tag_op(CompareOp::Like, tag_name, target_value, enc, negate)
         }
+        TagQuery::Prefix(tag_name, target_value) => {
+            encode_tag_op(CompareOp::Prefix, tag_name, target_value, enc, negate)
+        }
         TagQuery::In(tag_name, target_values) => {
             encode_tag_in(tag_name, target_values, enc, negate)
         }
@@ -192,11 +261,12 @@ fn encode_tag_op<V, E>(
 where
     E: TagQueryEncoder<Clause = V>,
 {
-    let is_plaintext = matches!(name, TagName::Plaintext(_));
+    let is_plaintext = name.is_plaintext();
+    let is_numeric = matches!(name, TagName::Numeric(_));
     let enc_name = enc.encode_name(name)?;
     let enc_value = enc.encode_value(value, is_plaintext)?;
 
-    enc.encode_op_clause(op, enc_name, enc_value, is_plaintext, negate)
+    enc.encode_op_clause(op, enc_name, enc_value, is_plaintext, is_numeric, negate)
 }
 
 fn encode_tag_in<V, E>(
@@ -208,7 +278,7 @@ fn encode_tag_in<V, E>(
 where
     E: TagQueryEncoder<Clause = V>,
 {
-    let is_plaintext = matches!(name, TagName::Plaintext(_));
+    let is_plaintext = name.is_plaintext();
     let enc_name = enc.encode_name(name)?;
     let enc_values = values
         .iter()
@@ -225,7 +295,7 @@ where
     match names.len() {
         0 => Ok(None),
         1 => {
-            let is_plaintext = matches!(names[0], TagName::Plaintext(_));
+            let is_plaintext = names[0].is_plaintext();
             let enc_name = enc.encode_name(&names[0])?;
             enc.encode_exist_clause(enc_name, is_plaintext, negate)
         }
@@ -236,6 +306,7 @@ where
                     cs.push(clause);
                 }
             }
+            // a negated existence check requires that none of the tags exist
             enc.encode_conj_clause(ConjunctionOp::And, cs)
         }
     }
@@ -285,6 +356,7 @@ mod tests {
             name: Self::Arg,
             value: Self::Arg,
             _is_plaintext: bool,
+            _is_numeric: bool,
             negate: bool,
         ) -> Result<Option<Self::Clause>, Error> {
             let mut s = format!("{} {} {}", name, op.as_sql_str(), value);
@@ -355,6 +427,50 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_validate_numeric() {
+        let query = Query::And(vec![
+            Query::Lt("\0expires".to_string(), "1700000000".to_string()),
+            Query::Gte("\0score".to_string(), "-1.5e3".to_string()),
+            Query::Gt("~plain".to_string(), "abc".to_string()),
+            // a plaintext tag name may start with '#'
+            Query::Gt("~#plain".to_string(), "abc".to_string()),
+        ]);
+        assert_eq!(
+            tag_query(query).unwrap(),
+            TagQuery::And(vec![
+                TagQuery::Lt(
+                    TagName::Numeric("expires".to_string()),
+                    "1700000000".to_string()
+                ),
+                TagQuery::Gte(TagName::Numeric("score".to_string()), "-1.5e3".to_string()),
+                TagQuery::Gt(TagName::Plaintext("plain".to_string()), "abc".to_string()),
+                TagQuery::Gt(TagName::Plaintext("#plain".to_string()), "abc".to_string()),
+            ])
+        );
+
+        let long_value = "1".repeat(MAX_NUMERIC_VALUE_LEN + 1);
+        for value in ["", "abc", " 10", "inf", "NaN", "1e0001", &long_value] {
+            let query = Query::Not(Box::new(Query::Gt(
+                "\0expires".to_string(),
+                value.to_string(),
+            )));
+            assert!(tag_query(query).is_err(), "{value:?} should be rejected");
+        }
+
+        let query = Query::Like("\0expires".to_string(), "1%".to_string());
+        assert!(tag_query(query).is_err());
+    }
+
+    #[test]
+    fn test_validate_prefix() {
+        let query = Query::Prefix("~label".to_string(), "Ali".to_string());
+        assert!(tag_query(query).is_ok());
+
+        let query = Query::Prefix("label".to_string(), "Ali".to_string());
+        assert!(tag_query(query).is_err());
+    }
+
     #[test]
     fn test_serialize() {
         let query = TagQuery::And(vec![
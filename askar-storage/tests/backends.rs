@@ -23,6 +23,21 @@ macro_rules! backend_tests {
             $run(super::utils::db_list_profiles)
         }
 
+        #[test]
+        fn profile_details() {
+            $run(super::utils::db_profile_details)
+        }
+
+        #[test]
+        fn profile_limits() {
+            $run(super::utils::db_profile_limits)
+        }
+
+        #[test]
+        fn rename_profile() {
+            $run(super::utils::db_rename_profile)
+        }
+
         #[test]
         fn get_set_default_profile() {
             $run(super::utils::db_get_set_default_profile)
@@ -68,21 +83,121 @@ macro_rules! backend_tests {
             $run(super::utils::db_count)
         }
 
+        #[test]
+        fn count_grouped() {
+            $run(super::utils::db_count_grouped)
+        }
+
         #[test]
         fn count_exist() {
             $run(super::utils::db_count_exist)
         }
 
+        #[test]
+        fn numeric_range() {
+            $run(super::utils::db_numeric_range)
+        }
+
+        #[test]
+        fn prefix_match() {
+            $run(super::utils::db_prefix_match)
+        }
+
+        #[test]
+        fn count_not_exist() {
+            $run(super::utils::db_count_not_exist)
+        }
+
+        #[test]
+        fn search() {
+            $run(super::utils::db_search)
+        }
+
+        #[test]
+        fn update_if() {
+            $run(super::utils::db_update_if)
+        }
+
+        #[test]
+        fn entry_version() {
+            $run(super::utils::db_entry_version)
+        }
+
+        #[test]
+        fn move_category() {
+            $run(super::utils::db_move_category)
+        }
+
+        #[test]
+        fn rename() {
+            $run(super::utils::db_rename)
+        }
+
+        #[test]
+        fn chunks() {
+            $run(super::utils::db_chunks)
+        }
+
+        #[test]
+        fn update_tags() {
+            $run(super::utils::db_update_tags)
+        }
+
+        #[test]
+        fn fetch_many() {
+            $run(super::utils::db_fetch_many)
+        }
+
+        #[test]
+        fn insert_many() {
+            $run(super::utils::db_insert_many)
+        }
+
         #[test]
         fn scan() {
             $run(super::utils::db_scan)
         }
 
+        #[test]
+        fn scan_ordered() {
+            $run(super::utils::db_scan_ordered)
+        }
+
+        #[test]
+        fn scan_tags() {
+            $run(super::utils::db_scan_tags)
+        }
+
+        #[test]
+        fn explain_scan() {
+            $run(super::utils::db_explain_scan)
+        }
+
+        #[test]
+        fn scan_stream() {
+            $run(super::utils::db_scan_stream)
+        }
+
+        #[test]
+        fn scan_continuation() {
+            $run(super::utils::db_scan_continuation)
+        }
+
         #[test]
         fn remove_all() {
             $run(super::utils::db_remove_all)
         }
 
+        #[test]
+        fn remove_all_returning() {
+            $run(super::utils::db_remove_all_returning)
+        }
+
+        #[test]
+        fn subscribe() {
+            $run(super::utils::db_subscribe)
+        }
+
         #[test]
         fn txn_rollback() {
             $run(super::utils::db_txn_rollback)
@@ -103,6 +218,16 @@ macro_rules! backend_tests {
             $run(super::utils::db_txn_commit)
         }
 
+        #[test]
+        fn txn_savepoint() {
+            $run(super::utils::db_txn_savepoint)
+        }
+
+        #[test]
+        fn txn_isolation() {
+            $run(super::utils::db_txn_isolation)
+        }
+
         #[test]
         fn txn_fetch_for_update() {
             $run(super::utils::db_txn_fetch_for_update)
@@ -129,8 +254,12 @@ mod sqlite {
     use askar_storage::any::{into_any_backend, AnyBackend};
     use askar_storage::backend::copy_store;
     use askar_storage::backend::sqlite::SqliteStoreOptions;
+    use askar_storage::entry::{EntryKind, EntryOperation};
     use askar_storage::future::block_on;
-    use askar_storage::{generate_raw_store_key, Backend, ManageBackend, StoreKeyMethod};
+    use askar_storage::{
+        generate_raw_store_key, Argon2Level, Backend, BackendSession, ErrorKind, KdfMethod,
+        ManageBackend, StoreKeyMethod,
+    };
     use std::{future::Future, path::Path};
 
     use super::*;
@@ -185,6 +314,40 @@ mod sqlite {
         })
     }
 
+    #[test]
+    fn open_prewarm() {
+        log_init();
+        let fname = format!("sqlite-prewarm-{}.db", uuid::Uuid::new_v4());
+        let key = generate_raw_store_key(None).expect("Error creating raw key");
+
+        block_on(async move {
+            let store = SqliteStoreOptions::new(fname.as_str())
+                .expect("Error initializing sqlite store options")
+                .provision_backend(StoreKeyMethod::RawKey, key.as_ref(), None, false)
+                .await
+                .expect("Error provisioning sqlite store");
+            store.close().await.expect("Error closing sqlite store");
+
+            let db = into_any_backend(
+                SqliteStoreOptions::new(
+                    format!("sqlite://{}?prewarm=true&min_connections=3", fname).as_str(),
+                )
+                .expect("Error initializing sqlite store options")
+                .open_backend(Some(StoreKeyMethod::RawKey), key.as_ref(), None)
+                .await
+                .expect("Error opening sqlite store"),
+            );
+            super::utils::db_insert_fetch(db.clone()).await;
+            db.close().await.expect("Error closing sqlite store");
+
+            SqliteStoreOptions::new(fname.as_str())
+                .expect("Error initializing sqlite store options")
+                .remove_backend()
+                .await
+                .expect("Error removing sqlite store");
+        })
+    }
+
     #[test]
     fn rekey_db() {
         log_init();
@@ -224,6 +387,61 @@ mod sqlite {
         })
     }
 
+    #[test]
+    fn upgrade_kdf_db() {
+        log_init();
+        let fname = format!("sqlite-upgrade-kdf-{}.db", uuid::Uuid::new_v4());
+        let interactive = StoreKeyMethod::DeriveKey(KdfMethod::Argon2i(Argon2Level::Interactive));
+        let minimum = KdfMethod::Argon2i(Argon2Level::Moderate);
+
+        block_on(async move {
+            let mut store = SqliteStoreOptions::new(fname.as_str())
+                .expect("Error initializing sqlite store options")
+                .provision_backend(interactive.clone(), "passphrase".into(), None, false)
+                .await
+                .expect("Error provisioning sqlite store");
+
+            store
+                .upgrade_kdf(minimum, "wrong".into())
+                .await
+                .expect_err("Expected pass key mismatch");
+            assert_eq!(
+                store
+                    .upgrade_kdf(minimum, "passphrase".into())
+                    .await
+                    .expect("Error upgrading key derivation"),
+                true
+            );
+            assert_eq!(
+                store
+                    .upgrade_kdf(minimum, "passphrase".into())
+                    .await
+                    .expect("Error upgrading key derivation"),
+                false
+            );
+            store.close().await.expect("Error closing store");
+
+            SqliteStoreOptions::new(fname.as_str())
+                .expect("Error initializing sqlite store options")
+                .open_backend(
+                    Some(StoreKeyMethod::DeriveKey(minimum)),
+                    "passphrase".into(),
+                    None,
+                )
+                .await
+                .expect("Error opening upgraded store")
+                .close()
+                .await
+                .expect("Error closing store");
+
+            SqliteStoreOptions::new(fname.as_str())
+                .expect("Error initializing sqlite store options")
+                .remove_backend()
+                .await
+                .expect("Error removing sqlite store");
+        })
+    }
+
     #[test]
     fn copy_db() {
         log_init();
@@ -278,97 +496,346 @@ mod sqlite {
     }
 
     #[test]
-    fn txn_contention_file() {
+    fn snapshot_db() {
         log_init();
-        let fname = format!("sqlite-contention-{}.db", uuid::Uuid::new_v4());
+        let fname_source = format!("sqlite-snapshot-{}.db", uuid::Uuid::new_v4());
+        let url_target = format!("sqlite://sqlite-snapshot-{}.db", uuid::Uuid::new_v4());
         let key = generate_raw_store_key(None).expect("Error creating raw key");
 
         block_on(async move {
-            let store = SqliteStoreOptions::new(fname.as_str())
+            let source = SqliteStoreOptions::new(fname_source.as_str())
                 .expect("Error initializing sqlite store options")
-                .provision_backend(StoreKeyMethod::RawKey, key.as_ref(), None, true)
+                .provision_backend(StoreKeyMethod::RawKey, key.as_ref(), None, false)
                 .await
                 .expect("Error provisioning sqlite store");
+            let mut conn = source.session(None, false).expect("Error creating session");
+            conn.update(
+                EntryKind::Item,
+                EntryOperation::Insert,
+                "category",
+                "name",
+                Some(b"value"),
+                None,
+                None,
+            )
+            .await
+            .expect("Error inserting test row");
 
-            let db = into_any_backend(store);
-            super::utils::db_txn_contention(db.clone()).await;
-            db.close().await.expect("Error closing sqlite store");
+            // the open session does not prevent a snapshot
+            source
+                .snapshot(url_target.as_str())
+                .await
+                .expect("Error creating snapshot");
+            let err = source
+                .snapshot(url_target.as_str())
+                .await
+                .expect_err("Expected error for existing snapshot target");
+            assert_eq!(err.kind(), ErrorKind::Duplicate);
+            conn.close(false).await.expect("Error closing session");
+            drop(conn);
 
-            SqliteStoreOptions::new(fname.as_str())
+            source.close().await.expect("Error closing store");
+            SqliteStoreOptions::new(fname_source.as_str())
                 .expect("Error initializing sqlite store options")
                 .remove_backend()
                 .await
                 .expect("Error removing sqlite store");
-        });
+
+            let copied = SqliteStoreOptions::new(url_target.as_str())
+                .expect("Error initializing sqlite store options")
+                .open_backend(Some(StoreKeyMethod::RawKey), key.as_ref(), None)
+                .await
+                .expect("Error opening snapshot");
+            let mut conn = copied.session(None, false).expect("Error creating session");
+            let row = conn
+                .fetch(EntryKind::Item, "category", "name", false)
+                .await
+                .expect("Error fetching test row")
+                .expect("Expected test row");
+            assert_eq!(row.value, b"value".to_vec());
+            conn.close(false).await.expect("Error closing session");
+            drop(conn);
+            copied.close().await.expect("Error closing store");
+
+            SqliteStoreOptions::new(url_target.as_str())
+                .expect("Error initializing sqlite store options")
+                .remove_backend()
+                .await
+                .expect("Error removing sqlite store");
+        })
     }
 
-    #[cfg(feature = "stress_test")]
     #[test]
-    fn stress_test() {
+    fn open_profile_scoped() {
         log_init();
-        use sqlx::sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions};
-        use std::str::FromStr;
-        let conn_opts = SqliteConnectOptions::from_str("sqlite:test.db")
-            .unwrap()
-            .create_if_missing(true);
-        // .shared_cache(true);
+        let fname = format!("sqlite-test-{}.db", uuid::Uuid::new_v4());
+        let key = generate_raw_store_key(None).expect("Error creating raw key");
+        let access_key = generate_raw_store_key(None).expect("Error creating raw key");
+
         block_on(async move {
-            let pool = SqlitePoolOptions::default()
-                // maintains at least 1 connection.
-                // for an in-memory database this is required to avoid dropping the database,
-                // for a file database this signals other instances that the database is in use
-                .min_connections(1)
-                .max_connections(5)
-                .test_before_acquire(false)
-                .connect_with(conn_opts)
+            let store = SqliteStoreOptions::new(fname.as_str())
+                .expect("Error initializing sqlite store options")
+                .provision_backend(StoreKeyMethod::RawKey, key.as_ref(), None, false)
                 .await
-                .unwrap();
-
-            let mut conn = pool.begin().await.unwrap();
-            sqlx::query("CREATE TABLE test (name TEXT)")
-                .execute(&mut conn)
+                .expect("Error provisioning sqlite store");
+            let profile = store
+                .create_profile(Some("tenant".to_string()))
                 .await
-                .unwrap();
-            sqlx::query("INSERT INTO test (name) VALUES ('test')")
-                .execute(&mut conn)
+                .expect("Error creating profile");
+            let mut conn = store
+                .session(Some(profile.clone()), false)
+                .expect("Error creating session");
+            conn.update(
+                EntryKind::Item,
+                EntryOperation::Insert,
+                "category",
+                "name",
+                Some(b"value"),
+                None,
+                None,
+            )
+            .await
+            .expect("Error inserting test row");
+            conn.close(false).await.expect("Error closing session");
+            drop(conn);
+            store
+                .set_profile_access_key(
+                    profile.clone(),
+                    StoreKeyMethod::RawKey,
+                    access_key.as_ref(),
+                )
                 .await
-                .unwrap();
-            conn.commit().await.unwrap();
+                .expect("Error setting profile access key");
 
-            const TASKS: usize = 25;
-            const COUNT: usize = 1000;
+            let err = SqliteStoreOptions::new(fname.as_str())
+                .expect("Error initializing sqlite store options")
+                .open_profile_backend(profile.clone(), key.as_ref())
+                .await
+                .expect_err("Expected error opening profile with the store key");
+            assert_eq!(err.kind(), ErrorKind::Encryption);
 
-            async fn fetch(pool: SqlitePool) -> Result<(), &'static str> {
-                // try to avoid panics in this section, as they will be raised on a tokio worker thread
-                for _ in 0..COUNT {
-                    let mut txn = pool.acquire().await.expect("Acquire error");
-                    sqlx::query("BEGIN IMMEDIATE")
-                        .execute(&mut txn)
-                        .await
-                        .expect("Transaction error");
-                    let _ = sqlx::query("SELECT * FROM test")
-                        .fetch_one(&mut txn)
-                        .await
-                        .expect("Error fetching row");
-                    sqlx::query("COMMIT")
-                        .execute(&mut txn)
-                        .await
-                        .expect("Commit error");
-                }
-                Ok(())
-            }
+            let scoped = SqliteStoreOptions::new(fname.as_str())
+                .expect("Error initializing sqlite store options")
+                .open_profile_backend(profile.clone(), access_key.as_ref())
+                .await
+                .expect("Error opening profile");
+            assert_eq!(scoped.get_active_profile(), profile);
+            let mut conn = scoped.session(None, false).expect("Error creating session");
+            let row = conn
+                .fetch(EntryKind::Item, "category", "name", false)
+                .await
+                .expect("Error fetching test row")
+                .expect("Expected test row");
+            assert_eq!(row.value, b"value".to_vec());
+            conn.close(false).await.expect("Error closing session");
+            drop(conn);
+
+            // other profiles and store-level operations are not accessible
+            let default_profile = store.get_active_profile();
+            let mut conn = scoped
+                .session(Some(default_profile), false)
+                .expect("Error creating session");
+            let err = conn
+                .count(None, None, None)
+                .await
+                .expect_err("Expected error accessing another profile");
+            assert_eq!(err.kind(), ErrorKind::Unsupported);
+            drop(conn);
+            let err = scoped
+                .create_profile(None)
+                .await
+                .expect_err("Expected error creating profile");
+            assert_eq!(err.kind(), ErrorKind::Unsupported);
+            let err = scoped
+                .list_profiles()
+                .await
+                .expect_err("Expected error listing profiles");
+            assert_eq!(err.kind(), ErrorKind::Unsupported);
+            scoped.close().await.expect("Error closing store");
 
-            let mut tasks = vec![];
-            for _ in 0..TASKS {
-                tasks.push(tokio::spawn(fetch(pool.clone())));
-            }
+            store
+                .remove_profile_access_key(profile.clone())
+                .await
+                .expect("Error removing profile access key");
+            let err = SqliteStoreOptions::new(fname.as_str())
+                .expect("Error initializing sqlite store options")
+                .open_profile_backend(profile, access_key.as_ref())
+                .await
+                .expect_err("Expected error opening profile");
+            assert_eq!(err.kind(), ErrorKind::NotFound);
 
-            for task in tasks {
-                if let Err(s) = task.await.unwrap() {
-                    panic!("Error in concurrent update task: {}", s);
-                }
-            }
-        });
+            store.close().await.expect("Error closing store");
+            SqliteStoreOptions::new(fname.as_str())
+                .expect("Error initializing sqlite store options")
+                .remove_backend()
+                .await
+                .expect("Error removing sqlite store");
+        })
+    }
+
+    #[test]
+    fn schema_upgrade_on_request() {
+        use sqlx::{sqlite::SqliteConnectOptions, ConnectOptions, Connection};
+        use std::str::FromStr;
+
+        log_init();
+        let fname = format!("sqlite-upgrade-{}.db", uuid::Uuid::new_v4());
+        let key = generate_raw_store_key(None).expect("Error creating raw key");
+
+        block_on(async move {
+            let store = SqliteStoreOptions::new(fname.as_str())
+                .expect("Error initializing sqlite store options")
+                .provision_backend(StoreKeyMethod::RawKey, key.as_ref(), None, false)
+                .await
+                .expect("Error provisioning sqlite store");
+            store.close().await.expect("Error closing sqlite store");
+
+            // revert the last schema upgrade
+            let mut conn = SqliteConnectOptions::from_str(fname.as_str())
+                .expect("Error parsing sqlite path")
+                .connect()
+                .await
+                .expect("Error connecting to sqlite store");
+            sqlx::query(
+                "DROP INDEX ix_items_tags_item_name;
+                CREATE INDEX ix_items_tags_item_id ON items_tags (item_id);
+                UPDATE config SET value = '11' WHERE name = 'version';",
+            )
+            .execute(&mut conn)
+            .await
+            .expect("Error reverting schema version");
+            conn.close().await.expect("Error closing connection");
+
+            let err = SqliteStoreOptions::new(fname.as_str())
+                .expect("Error initializing sqlite store options")
+                .open_backend(Some(StoreKeyMethod::RawKey), key.as_ref(), None)
+                .await
+                .expect_err("Expected error opening outdated store");
+            assert_eq!(err.kind(), ErrorKind::Unsupported);
+
+            let mut conn = SqliteConnectOptions::from_str(fname.as_str())
+                .expect("Error parsing sqlite path")
+                .connect()
+                .await
+                .expect("Error connecting to sqlite store");
+            let version: String =
+                sqlx::query_scalar("SELECT value FROM config WHERE name = 'version'")
+                    .fetch_one(&mut conn)
+                    .await
+                    .expect("Error fetching schema version");
+            assert_eq!(version, "11");
+            conn.close().await.expect("Error closing connection");
+
+            let db = into_any_backend(
+                SqliteStoreOptions::new(format!("sqlite://{}?upgrade=true", fname).as_str())
+                    .expect("Error initializing sqlite store options")
+                    .open_backend(Some(StoreKeyMethod::RawKey), key.as_ref(), None)
+                    .await
+                    .expect("Error opening sqlite store"),
+            );
+            super::utils::db_insert_fetch(db.clone()).await;
+            db.close().await.expect("Error closing sqlite store");
+
+            SqliteStoreOptions::new(fname.as_str())
+                .expect("Error initializing sqlite store options")
+                .remove_backend()
+                .await
+                .expect("Error removing sqlite store");
+        });
+    }
+
+    #[test]
+    fn txn_contention_file() {
+        log_init();
+        let fname = format!("sqlite-contention-{}.db", uuid::Uuid::new_v4());
+        let key = generate_raw_store_key(None).expect("Error creating raw key");
+
+        block_on(async move {
+            let store = SqliteStoreOptions::new(fname.as_str())
+                .expect("Error initializing sqlite store options")
+                .provision_backend(StoreKeyMethod::RawKey, key.as_ref(), None, true)
+                .await
+                .expect("Error provisioning sqlite store");
+
+            let db = into_any_backend(store);
+            super::utils::db_txn_contention(db.clone()).await;
+            db.close().await.expect("Error closing sqlite store");
+
+            SqliteStoreOptions::new(fname.as_str())
+                .expect("Error initializing sqlite store options")
+                .remove_backend()
+                .await
+                .expect("Error removing sqlite store");
+        });
+    }
+
+    #[cfg(feature = "stress_test")]
+    #[test]
+    fn stress_test() {
+        log_init();
+        use sqlx::sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions};
+        use std::str::FromStr;
+        let conn_opts = SqliteConnectOptions::from_str("sqlite:test.db")
+            .unwrap()
+            .create_if_missing(true);
+        // .shared_cache(true);
+        block_on(async move {
+            let pool = SqlitePoolOptions::default()
+                // maintains at least 1 connection.
+                // for an in-memory database this is required to avoid dropping the database,
+                // for a file database this signals other instances that the database is in use
+                .min_connections(1)
+                .max_connections(5)
+                .test_before_acquire(false)
+                .connect_with(conn_opts)
+                .await
+                .unwrap();
+
+            let mut conn = pool.begin().await.unwrap();
+            sqlx::query("CREATE TABLE test (name TEXT)")
+                .execute(&mut conn)
+                .await
+                .unwrap();
+            sqlx::query("INSERT INTO test (name) VALUES ('test')")
+                .execute(&mut conn)
+                .await
+                .unwrap();
+            conn.commit().await.unwrap();
+
+            const TASKS: usize = 25;
+            const COUNT: usize = 1000;
+
+            async fn fetch(pool: SqlitePool) -> Result<(), &'static str> {
+                // try to avoid panics in this section, as they will be raised on a tokio worker thread
+                for _ in 0..COUNT {
+                    let mut txn = pool.acquire().await.expect("Acquire error");
+                    sqlx::query("BEGIN IMMEDIATE")
+                        .execute(&mut txn)
+                        .await
+                        .expect("Transaction error");
+                    let _ = sqlx::query("SELECT * FROM test")
+                        .fetch_one(&mut txn)
+                        .await
+                        .expect("Error fetching row");
+                    sqlx::query("COMMIT")
+                        .execute(&mut txn)
+                        .await
+                        .expect("Commit error");
+                }
+                Ok(())
+            }
+
+            let mut tasks = vec![];
+            for _ in 0..TASKS {
+                tasks.push(tokio::spawn(fetch(pool.clone())));
+            }
+
+            for task in tasks {
+                if let Err(s) = task.await.unwrap() {
+                    panic!("Error in concurrent update task: {}", s);
+                }
+            }
+        });
     }
 
     fn with_sqlite_in_memory<F, G>(f: F)
@@ -392,6 +859,53 @@ mod sqlite {
 
     backend_tests!(with_sqlite_in_memory);
 
+    fn with_sqlite_soft_delete<F, G>(f: F)
+    where
+        F: FnOnce(AnyBackend) -> G,
+        G: Future<Output = ()>,
+    {
+        log_init();
+        let key = generate_raw_store_key(None).expect("Error generating store key");
+        block_on(async move {
+            let db = into_any_backend(
+                SqliteStoreOptions::new("sqlite://:memory:?soft_delete=true")
+                    .expect("Error initializing sqlite store options")
+                    .provision(StoreKeyMethod::RawKey, key, None, false)
+                    .await
+                    .expect("Error provisioning sqlite store"),
+            );
+            f(db.clone()).await;
+            db.close().await.expect(ERR_CLOSE);
+        })
+    }
+
+    #[test]
+    fn soft_delete_restore() {
+        with_sqlite_soft_delete(super::utils::db_soft_delete_restore)
+    }
+
+    #[test]
+    fn soft_delete_purge() {
+        with_sqlite_soft_delete(super::utils::db_soft_delete_purge)
+    }
+
+    #[test]
+    fn session_idle_timeout() {
+        log_init();
+        let key = generate_raw_store_key(None).expect("Error generating store key");
+        block_on(async move {
+            let db = into_any_backend(
+                SqliteStoreOptions::new("sqlite://:memory:?session_idle_timeout=1")
+                    .expect("Error initializing sqlite store options")
+                    .provision(StoreKeyMethod::RawKey, key, None, false)
+                    .await
+                    .expect("Error provisioning sqlite store"),
+            );
+            super::utils::db_session_idle_timeout(db.clone()).await;
+            db.close().await.expect(ERR_CLOSE);
+        })
+    }
+
     #[test]
     fn provision_from_str() {
         let key = generate_raw_store_key(None).expect("Error creating raw key");
@@ -414,6 +928,434 @@ mod sqlite {
     }
 }
 
+#[cfg(feature = "any")]
+mod custom {
+    use askar_storage::any::{into_any_backend, AnyBackend};
+    use askar_storage::backend::custom::{
+        register_record_store, CustomStoreOptions, Record, RecordStore,
+    };
+    use askar_storage::future::block_on;
+    use askar_storage::{
+        generate_raw_store_key, Backend, Error, ErrorKind, ManageBackend, StoreKeyMethod,
+    };
+    use std::{
+        collections::BTreeMap,
+        future::Future,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc, Mutex,
+        },
+    };
+
+    use super::*;
+
+    #[derive(Debug, Default)]
+    struct MemoryRecords(Mutex<BTreeMap<Vec<u8>, Vec<u8>>>);
+
+    impl RecordStore for MemoryRecords {
+        fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Error> {
+            Ok(self.0.lock().unwrap().get(key).cloned())
+        }
+
+        fn put(&self, key: &[u8], value: &[u8]) -> Result<(), Error> {
+            self.0.lock().unwrap().insert(key.to_vec(), value.to_vec());
+            Ok(())
+        }
+
+        fn remove(&self, key: &[u8]) -> Result<bool, Error> {
+            Ok(self.0.lock().unwrap().remove(key).is_some())
+        }
+
+        fn scan(&self, prefix: &[u8]) -> Result<Vec<Record>, Error> {
+            Ok(self
+                .0
+                .lock()
+                .unwrap()
+                .range(prefix.to_vec()..)
+                .take_while(|(key, _)| key.starts_with(prefix))
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect())
+        }
+    }
+
+    /// A record store which is unavailable for a number of requests
+    #[derive(Debug, Default)]
+    struct FlakyRecords {
+        records: MemoryRecords,
+        failures: AtomicUsize,
+    }
+
+    impl FlakyRecords {
+        fn check(&self) -> Result<(), Error> {
+            if self
+                .failures
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                .is_ok()
+            {
+                Err(Error::from_msg(ErrorKind::Busy, "Record store unavailable"))
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    impl RecordStore for FlakyRecords {
+        fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Error> {
+            self.check()?;
+            self.records.get(key)
+        }
+
+        fn put(&self, key: &[u8], value: &[u8]) -> Result<(), Error> {
+            self.check()?;
+            self.records.put(key, value)
+        }
+
+        fn remove(&self, key: &[u8]) -> Result<bool, Error> {
+            self.check()?;
+            self.records.remove(key)
+        }
+
+        fn scan(&self, prefix: &[u8]) -> Result<Vec<Record>, Error> {
+            self.check()?;
+            self.records.scan(prefix)
+        }
+    }
+
+    fn register() {
+        static RECORDS: std::sync::Once = std::sync::Once::new();
+        RECORDS.call_once(|| {
+            register_record_store("memrecords", Arc::new(MemoryRecords::default()));
+        });
+    }
+
+    fn with_custom<F, G>(f: F)
+    where
+        F: FnOnce(AnyBackend) -> G,
+        G: Future<Output = ()>,
+    {
+        log_init();
+        register();
+        let uri = format!("memrecords://{}", uuid::Uuid::new_v4());
+        let key = generate_raw_store_key(None).expect("Error generating store key");
+        block_on(async move {
+            let db = into_any_backend(
+                CustomStoreOptions::new(uri.as_str())
+                    .expect("Error initializing custom store options")
+                    .provision(StoreKeyMethod::RawKey, key, None, false)
+                    .await
+                    .expect("Error provisioning custom store"),
+            );
+            f(db.clone()).await;
+            db.close().await.expect(ERR_CLOSE);
+        })
+    }
+
+    #[test]
+    fn create_remove_db() {
+        log_init();
+        register();
+        let uri = format!("memrecords://{}", uuid::Uuid::new_v4());
+        let key = generate_raw_store_key(None).expect("Error creating raw key");
+        block_on(async move {
+            assert_eq!(
+                uri.as_str()
+                    .remove_backend()
+                    .await
+                    .expect("Error removing custom store"),
+                false
+            );
+
+            let store = uri
+                .as_str()
+                .provision_backend(StoreKeyMethod::RawKey, key.as_ref(), None, false)
+                .await
+                .expect("Error provisioning custom store");
+            let profile = store.get_active_profile();
+
+            let store2 = uri
+                .as_str()
+                .open_backend(Some(StoreKeyMethod::RawKey), key.as_ref(), None)
+                .await
+                .expect("Error opening custom store");
+            assert_eq!(store2.get_active_profile(), profile);
+            store2.close().await.expect(ERR_CLOSE);
+
+            let err = uri
+                .as_str()
+                .open_backend(Some(StoreKeyMethod::Unprotected), None.into(), None)
+                .await
+                .expect_err("Expected store key method mismatch");
+            assert_eq!(err.kind(), ErrorKind::Input);
+
+            store.close().await.expect(ERR_CLOSE);
+            assert_eq!(
+                uri.as_str()
+                    .remove_backend()
+                    .await
+                    .expect("Error removing custom store"),
+                true
+            );
+            let err = uri
+                .as_str()
+                .open_backend(None, key.as_ref(), None)
+                .await
+                .expect_err("Expected open failure");
+            assert_eq!(err.kind(), ErrorKind::NotFound);
+        })
+    }
+
+    #[test]
+    fn open_retry() {
+        log_init();
+        let records = Arc::new(FlakyRecords::default());
+        register_record_store("flakyrecords", records.clone());
+        let uri = format!("flakyrecords://{}", uuid::Uuid::new_v4());
+        let key = generate_raw_store_key(None).expect("Error creating raw key");
+        block_on(async move {
+            records.failures.store(2, Ordering::SeqCst);
+            let err = uri
+                .as_str()
+                .provision_backend(StoreKeyMethod::RawKey, key.as_ref(), None, false)
+                .await
+                .expect_err("Expected provision failure");
+            assert_eq!(err.kind(), ErrorKind::Busy);
+
+            records.failures.store(2, Ordering::SeqCst);
+            let retry_uri = format!("{uri}?open_retries=2&open_retry_delay=1");
+            let store = retry_uri
+                .as_str()
+                .provision_backend(StoreKeyMethod::RawKey, key.as_ref(), None, false)
+                .await
+                .expect("Error provisioning custom store");
+            store.close().await.expect(ERR_CLOSE);
+
+            records.failures.store(3, Ordering::SeqCst);
+            let err = retry_uri
+                .as_str()
+                .open_backend(Some(StoreKeyMethod::RawKey), key.as_ref(), None)
+                .await
+                .expect_err("Expected open failure");
+            assert_eq!(err.kind(), ErrorKind::Busy);
+
+            records.failures.store(1, Ordering::SeqCst);
+            let store = retry_uri
+                .as_str()
+                .open_backend(Some(StoreKeyMethod::RawKey), key.as_ref(), None)
+                .await
+                .expect("Error opening custom store");
+            store.close().await.expect(ERR_CLOSE);
+
+            // other errors are not retried
+            let err = "flakyrecords://missing?open_retries=2&open_retry_delay=1"
+                .open_backend(None, key.as_ref(), None)
+                .await
+                .expect_err("Expected open failure");
+            assert_eq!(err.kind(), ErrorKind::NotFound);
+        })
+    }
+
+    #[test]
+    fn unregistered_scheme() {
+        let err = CustomStoreOptions::new("notregistered://store").expect_err("Expected failure");
+        assert_eq!(err.kind(), ErrorKind::Unsupported);
+    }
+
+    #[test]
+    fn create_remove_profile() {
+        with_custom(super::utils::db_create_remove_profile)
+    }
+
+    #[test]
+    fn list_profiles() {
+        with_custom(super::utils::db_list_profiles)
+    }
+
+    #[test]
+    fn profile_details() {
+        with_custom(super::utils::db_profile_details)
+    }
+
+    #[test]
+    fn rename_profile() {
+        with_custom(super::utils::db_rename_profile)
+    }
+
+    #[test]
+    fn get_set_default_profile() {
+        with_custom(super::utils::db_get_set_default_profile)
+    }
+
+    #[test]
+    fn fetch_fail() {
+        with_custom(super::utils::db_fetch_fail)
+    }
+
+    #[test]
+    fn insert_fetch() {
+        with_custom(super::utils::db_insert_fetch)
+    }
+
+    #[test]
+    fn insert_duplicate() {
+        with_custom(super::utils::db_insert_duplicate)
+    }
+
+    #[test]
+    fn insert_remove() {
+        with_custom(super::utils::db_insert_remove)
+    }
+
+    #[test]
+    fn remove_missing() {
+        with_custom(super::utils::db_remove_missing)
+    }
+
+    #[test]
+    fn replace_fetch() {
+        with_custom(super::utils::db_replace_fetch)
+    }
+
+    #[test]
+    fn replace_missing() {
+        with_custom(super::utils::db_replace_missing)
+    }
+
+    #[test]
+    fn count() {
+        with_custom(super::utils::db_count)
+    }
+
+    #[test]
+    fn count_grouped() {
+        with_custom(super::utils::db_count_grouped)
+    }
+
+    #[test]
+    fn count_exist() {
+        with_custom(super::utils::db_count_exist)
+    }
+
+    #[test]
+    fn numeric_range() {
+        with_custom(super::utils::db_numeric_range)
+    }
+
+    #[test]
+    fn prefix_match() {
+        with_custom(super::utils::db_prefix_match)
+    }
+
+    #[test]
+    fn count_not_exist() {
+        with_custom(super::utils::db_count_not_exist)
+    }
+
+    #[test]
+    fn update_if() {
+        with_custom(super::utils::db_update_if)
+    }
+
+    #[test]
+    fn entry_version() {
+        with_custom(super::utils::db_entry_version)
+    }
+
+    #[test]
+    fn move_category() {
+        with_custom(super::utils::db_move_category)
+    }
+
+    #[test]
+    fn rename() {
+        with_custom(super::utils::db_rename)
+    }
+
+    #[test]
+    fn update_tags() {
+        with_custom(super::utils::db_update_tags)
+    }
+
+    #[test]
+    fn fetch_many() {
+        with_custom(super::utils::db_fetch_many)
+    }
+
+    #[test]
+    fn insert_many() {
+        with_custom(super::utils::db_insert_many)
+    }
+
+    #[test]
+    fn scan() {
+        with_custom(super::utils::db_scan)
+    }
+
+    #[test]
+    fn scan_ordered() {
+        with_custom(super::utils::db_scan_ordered)
+    }
+
+    #[test]
+    fn scan_tags() {
+        with_custom(super::utils::db_scan_tags)
+    }
+
+    #[test]
+    fn scan_stream() {
+        with_custom(super::utils::db_scan_stream)
+    }
+
+    #[test]
+    fn remove_all() {
+        with_custom(super::utils::db_remove_all)
+    }
+
+    #[test]
+    fn remove_all_returning() {
+        with_custom(super::utils::db_remove_all_returning)
+    }
+
+    #[test]
+    fn txn_rollback() {
+        with_custom(super::utils::db_txn_rollback)
+    }
+
+    #[test]
+    fn txn_drop() {
+        with_custom(super::utils::db_txn_drop)
+    }
+
+    #[test]
+    fn session_drop() {
+        with_custom(super::utils::db_session_drop)
+    }
+
+    #[test]
+    fn txn_commit() {
+        with_custom(super::utils::db_txn_commit)
+    }
+
+    #[test]
+    fn txn_isolation() {
+        with_custom(super::utils::db_txn_isolation)
+    }
+
+    #[test]
+    fn txn_fetch_for_update() {
+        with_custom(super::utils::db_txn_fetch_for_update)
+    }
+
+    #[test]
+    fn txn_contention() {
+        with_custom(super::utils::db_txn_contention)
+    }
+
+    #[test]
+    fn db_import() {
+        with_custom(super::utils::db_import_scan)
+    }
+}
+
 #[cfg(feature = "pg_test")]
 mod postgres {
     use askar_storage::any::AnyBackend;
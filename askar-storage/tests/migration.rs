@@ -2,9 +2,10 @@
 
 use std::path::PathBuf;
 
+use askar_storage::backend::sqlite::SqliteStoreOptions;
 use askar_storage::future::block_on;
 use askar_storage::migration::IndySdkToAriesAskarMigration;
-use askar_storage::Error;
+use askar_storage::{Backend, Error};
 
 const DB_TEMPLATE_PATH: &str = "./tests/indy_wallet_sqlite.db";
 const DB_UPGRADE_PATH: &str = "./tests/indy_wallet_sqlite_upgraded.db";
@@ -41,6 +42,12 @@ fn test_sqlite_migration() {
             IndySdkToAriesAskarMigration::connect(DB_UPGRADE_PATH, wallet_name, wallet_key, "RAW")
                 .await?;
         migrator.migrate().await?;
+
+        // the migration applies the pending schema upgrades
+        let store = SqliteStoreOptions::new(DB_UPGRADE_PATH)?
+            .open(None, wallet_key.into(), Some(wallet_name.to_string()))
+            .await?;
+        store.close().await?;
         Result::<_, Error>::Ok(())
     });
 
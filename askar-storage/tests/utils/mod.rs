@@ -1,9 +1,17 @@
 use askar_storage::{
     any::AnyBackend,
-    entry::{Entry, EntryKind, EntryOperation, EntryTag, TagFilter},
-    Backend, BackendSession, ErrorKind,
+    entry::{
+        Entry, EntryCount, EntryEvent, EntryKind, EntryOperation, EntryPrecondition, EntryTag,
+        OrderBy, TagFilter, TagSelection,
+    },
+    future::sleep,
+    Backend, BackendSession, ErrorKind, IsolationLevel, ProfileLimits, SavepointOperation,
 };
 
+use std::time::Duration;
+
+use futures_lite::StreamExt;
+
 use tokio::task::spawn;
 
 const ERR_PROFILE: &str = "Error creating profile";
@@ -20,6 +28,9 @@ const ERR_REPLACE: &str = "Error replacing test row";
 const ERR_REMOVE_ALL: &str = "Error removing test rows";
 const ERR_SCAN: &str = "Error starting scan";
 const ERR_SCAN_NEXT: &str = "Error fetching scan rows";
+const ERR_SUBSCRIBE: &str = "Error starting subscription";
+const ERR_SUBSCRIBE_NEXT: &str = "Error fetching subscription event";
+const ERR_REQ_EVENT: &str = "Expected event";
 
 pub async fn db_create_remove_profile(db: AnyBackend) {
     let profile = db.create_profile(None).await.expect(ERR_PROFILE);
@@ -177,6 +188,143 @@ pub async fn db_remove_missing(db: AnyBackend) {
     assert_eq!(err.kind(), ErrorKind::NotFound);
 }
 
+pub async fn db_soft_delete_restore(db: AnyBackend) {
+    let test_row = Entry::new(EntryKind::Item, "category", "name", "value", Vec::new());
+
+    let mut conn = db.session(None, false).expect(ERR_SESSION);
+
+    conn.update(
+        EntryKind::Item,
+        EntryOperation::Insert,
+        &test_row.category,
+        &test_row.name,
+        Some(&test_row.value),
+        Some(test_row.tags.as_slice()),
+        None,
+    )
+    .await
+    .expect(ERR_INSERT);
+
+    conn.update(
+        EntryKind::Item,
+        EntryOperation::Remove,
+        &test_row.category,
+        &test_row.name,
+        None,
+        None,
+        None,
+    )
+    .await
+    .expect(ERR_REQ_ROW);
+
+    let row = conn
+        .fetch(EntryKind::Item, &test_row.category, &test_row.name, false)
+        .await
+        .expect(ERR_FETCH);
+    assert!(row.is_none());
+    let count = conn
+        .count(Some(EntryKind::Item), Some(&test_row.category), None)
+        .await
+        .expect(ERR_COUNT);
+    assert_eq!(count, 0);
+
+    conn.restore(EntryKind::Item, &test_row.category, &test_row.name)
+        .await
+        .expect("Error restoring test row");
+    let row = conn
+        .fetch(EntryKind::Item, &test_row.category, &test_row.name, false)
+        .await
+        .expect(ERR_FETCH)
+        .expect(ERR_REQ_ROW);
+    assert_eq!(row, test_row);
+
+    let err = conn
+        .restore(EntryKind::Item, &test_row.category, &test_row.name)
+        .await
+        .expect_err(ERR_REQ_ERR);
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+}
+
+pub async fn db_soft_delete_purge(db: AnyBackend) {
+    let test_rows = [
+        Entry::new(EntryKind::Item, "category", "a", "value", Vec::new()),
+        Entry::new(EntryKind::Item, "category", "b", "value", Vec::new()),
+        Entry::new(EntryKind::Item, "category", "c", "value", Vec::new()),
+    ];
+
+    let mut conn = db.session(None, false).expect(ERR_SESSION);
+
+    for row in test_rows.iter() {
+        conn.update(
+            EntryKind::Item,
+            EntryOperation::Insert,
+            &row.category,
+            &row.name,
+            Some(&row.value),
+            Some(row.tags.as_slice()),
+            None,
+        )
+        .await
+        .expect(ERR_INSERT);
+    }
+
+    conn.update(
+        EntryKind::Item,
+        EntryOperation::Remove,
+        "category",
+        "a",
+        None,
+        None,
+        None,
+    )
+    .await
+    .expect(ERR_REQ_ROW);
+
+    let err = conn
+        .purge(EntryKind::Item, "category", "b")
+        .await
+        .expect_err(ERR_REQ_ERR);
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+    conn.purge(EntryKind::Item, "category", "a")
+        .await
+        .expect("Error purging test row");
+    let err = conn
+        .restore(EntryKind::Item, "category", "a")
+        .await
+        .expect_err(ERR_REQ_ERR);
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+
+    let removed = conn
+        .remove_all(Some(EntryKind::Item), Some("category"), None)
+        .await
+        .expect(ERR_REMOVE_ALL);
+    assert_eq!(removed, 2);
+
+    // a soft-deleted entry does not prevent a new insert
+    conn.update(
+        EntryKind::Item,
+        EntryOperation::Insert,
+        "category",
+        "b",
+        Some(b"new value"),
+        None,
+        None,
+    )
+    .await
+    .expect(ERR_INSERT);
+
+    let purged = conn
+        .purge_all(Some(EntryKind::Item), None)
+        .await
+        .expect("Error purging test rows");
+    assert_eq!(purged, 1);
+    let count = conn
+        .count(Some(EntryKind::Item), Some("category"), None)
+        .await
+        .expect(ERR_COUNT);
+    assert_eq!(count, 1);
+}
+
 pub async fn db_replace_fetch(db: AnyBackend) {
     let test_row = Entry::new(EntryKind::Item, "category", "name", "value", Vec::new());
 
@@ -282,6 +430,99 @@ pub async fn db_count(db: AnyBackend) {
     assert_eq!(count, 0);
 }
 
+pub async fn db_count_grouped(db: AnyBackend) {
+    let test_rows = [
+        Entry::new(
+            EntryKind::Item,
+            "cat-a",
+            "one",
+            "value",
+            vec![EntryTag::Encrypted("color".to_string(), "red".to_string())],
+        ),
+        Entry::new(
+            EntryKind::Item,
+            "cat-a",
+            "two",
+            "value",
+            vec![EntryTag::Encrypted("color".to_string(), "blue".to_string())],
+        ),
+        Entry::new(
+            EntryKind::Item,
+            "cat-a",
+            "three",
+            "value",
+            vec![EntryTag::Encrypted("color".to_string(), "red".to_string())],
+        ),
+        Entry::new(
+            EntryKind::Item,
+            "cat-b",
+            "four",
+            "value",
+            vec![EntryTag::Plaintext("color".to_string(), "red".to_string())],
+        ),
+    ];
+
+    let mut conn = db.session(None, false).expect(ERR_SESSION);
+
+    for upd in test_rows.iter() {
+        conn.update(
+            EntryKind::Item,
+            EntryOperation::Insert,
+            &upd.category,
+            &upd.name,
+            Some(&upd.value),
+            Some(upd.tags.as_slice()),
+            None,
+        )
+        .await
+        .expect(ERR_INSERT);
+    }
+
+    let count = |category: &str, tag_value: Option<&str>, count: i64| EntryCount {
+        category: category.to_string(),
+        tag_value: tag_value.map(str::to_string),
+        count,
+    };
+
+    let counts = conn
+        .count_grouped(Some(EntryKind::Item), None, None, None)
+        .await
+        .expect(ERR_COUNT);
+    assert_eq!(
+        counts,
+        vec![count("cat-a", None, 3), count("cat-b", None, 1)]
+    );
+
+    let counts = conn
+        .count_grouped(Some(EntryKind::Item), None, None, Some("color"))
+        .await
+        .expect(ERR_COUNT);
+    assert_eq!(
+        counts,
+        vec![
+            count("cat-a", Some("blue"), 1),
+            count("cat-a", Some("red"), 2),
+            count("cat-b", None, 1),
+        ]
+    );
+
+    let counts = conn
+        .count_grouped(Some(EntryKind::Item), None, None, Some("~color"))
+        .await
+        .expect(ERR_COUNT);
+    assert_eq!(
+        counts,
+        vec![count("cat-a", None, 3), count("cat-b", Some("red"), 1)]
+    );
+
+    let tag_filter = Some(TagFilter::is_eq("color", "red"));
+    let counts = conn
+        .count_grouped(Some(EntryKind::Item), Some("cat-a"), tag_filter, None)
+        .await
+        .expect(ERR_COUNT);
+    assert_eq!(counts, vec![count("cat-a", None, 2)]);
+}
+
 pub async fn db_count_exist(db: AnyBackend) {
     let test_row = Entry::new(
         EntryKind::Item,
@@ -448,84 +689,1426 @@ pub async fn db_count_exist(db: AnyBackend) {
     );
 }
 
-pub async fn db_scan(db: AnyBackend) {
+pub async fn db_numeric_range(db: AnyBackend) {
     let category = "category".to_string();
-    let test_rows = vec![Entry::new(
-        EntryKind::Item,
-        &category,
-        "name",
-        "value",
-        vec![
-            EntryTag::Encrypted("t1".to_string(), "v1".to_string()),
-            EntryTag::Plaintext("t2".to_string(), "v2".to_string()),
-        ],
-    )];
-
     let mut conn = db.session(None, false).expect(ERR_SESSION);
 
-    for upd in test_rows.iter() {
+    for (name, expires) in [("a", "9"), ("b", "10"), ("c", "100"), ("d", "-5.5")] {
         conn.update(
             EntryKind::Item,
             EntryOperation::Insert,
-            &upd.category,
-            &upd.name,
-            Some(&upd.value),
-            Some(upd.tags.as_slice()),
+            &category,
+            name,
+            Some(b"value"),
+            Some(&[
+                EntryTag::Numeric("expires".to_string(), expires.to_string()),
+                EntryTag::Plaintext("#expires".to_string(), "label".to_string()),
+            ]),
             None,
         )
         .await
         .expect(ERR_INSERT);
     }
-    drop(conn);
 
-    let tag_filter = None;
-    let offset = None;
-    let limit = None;
-    let mut scan = db
-        .scan(
+    let err = conn
+        .update(
+            EntryKind::Item,
+            EntryOperation::Insert,
+            &category,
+            "invalid",
+            Some(b"value"),
+            Some(&[EntryTag::Numeric(
+                "expires".to_string(),
+                "never".to_string(),
+            )]),
             None,
+        )
+        .await
+        .expect_err(ERR_REQ_ERR);
+    assert_eq!(err.kind(), ErrorKind::Input);
+
+    let expires = TagFilter::numeric_tag("expires");
+    let cases: [(TagFilter, &[&str]); 7] = [
+        (TagFilter::is_lt(&expires, "10"), &["a", "d"]),
+        (TagFilter::is_lte(&expires, "10"), &["a", "b", "d"]),
+        (TagFilter::is_gt(&expires, "9.5"), &["b", "c"]),
+        (TagFilter::is_gte(&expires, "1e2"), &["c"]),
+        (TagFilter::negate(TagFilter::is_gt(&expires, "0")), &["d"]),
+        (TagFilter::is_eq(&expires, "10.0"), &["b"]),
+        (
+            TagFilter::is_eq("~#expires", "label"),
+            &["a", "b", "c", "d"],
+        ),
+    ];
+    for (tag_filter, expected) in cases {
+        let mut names = conn
+            .fetch_all(
+                Some(EntryKind::Item),
+                Some(&category),
+                Some(tag_filter),
+                None,
+                false,
+            )
+            .await
+            .expect(ERR_FETCH_ALL)
+            .into_iter()
+            .map(|row| row.name)
+            .collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(names, expected);
+    }
+
+    let err = conn
+        .count(
             Some(EntryKind::Item),
-            Some(category.clone()),
-            tag_filter,
-            offset,
-            limit,
+            Some(&category),
+            Some(TagFilter::is_lt(&expires, "soon")),
         )
         .await
-        .expect(ERR_SCAN);
-    let rows = scan.fetch_next().await.expect(ERR_SCAN_NEXT);
-    assert_eq!(rows, Some(test_rows));
-    let rows = scan.fetch_next().await.expect(ERR_SCAN_NEXT);
-    assert_eq!(rows, None);
+        .expect_err(ERR_REQ_ERR);
+    assert_eq!(err.kind(), ErrorKind::Input);
+}
 
-    let tag_filter = Some(TagFilter::is_eq("sometag", "someval"));
-    let mut scan = db
-        .scan(
+pub async fn db_prefix_match(db: AnyBackend) {
+    let category = "category".to_string();
+    let mut conn = db.session(None, false).expect(ERR_SESSION);
+
+    for (name, label) in [
+        ("a", "Alice"),
+        ("b", "Alicia"),
+        ("c", "Bob"),
+        ("d", "alice"),
+    ] {
+        conn.update(
+            EntryKind::Item,
+            EntryOperation::Insert,
+            &category,
+            name,
+            Some(b"value"),
+            Some(&[EntryTag::Plaintext("label".to_string(), label.to_string())]),
             None,
+        )
+        .await
+        .expect(ERR_INSERT);
+    }
+
+    let cases: [(TagFilter, &[&str]); 4] = [
+        (TagFilter::is_prefix("~label", "Ali"), &["a", "b"]),
+        (TagFilter::is_prefix("~label", "Alice"), &["a"]),
+        (TagFilter::is_prefix("~label", ""), &["a", "b", "c", "d"]),
+        (
+            TagFilter::negate(TagFilter::is_prefix("~label", "Ali")),
+            &["c", "d"],
+        ),
+    ];
+    for (tag_filter, expected) in cases {
+        let mut names = conn
+            .fetch_all(
+                Some(EntryKind::Item),
+                Some(&category),
+                Some(tag_filter),
+                None,
+                false,
+            )
+            .await
+            .expect(ERR_FETCH_ALL)
+            .into_iter()
+            .map(|row| row.name)
+            .collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(names, expected);
+    }
+
+    let err = conn
+        .count(
             Some(EntryKind::Item),
-            Some(category.clone()),
-            tag_filter,
-            offset,
-            limit,
+            Some(&category),
+            Some(TagFilter::is_prefix("label", "Ali")),
         )
         .await
-        .expect(ERR_SCAN);
-    let rows = scan.fetch_next().await.expect(ERR_SCAN_NEXT);
-    assert_eq!(rows, None);
+        .expect_err(ERR_REQ_ERR);
+    assert_eq!(err.kind(), ErrorKind::Input);
 }
 
-pub async fn db_remove_all(db: AnyBackend) {
-    let test_rows = vec![
-        Entry::new(
-            EntryKind::Item,
-            "category",
-            "item1",
-            "value",
+pub async fn db_count_not_exist(db: AnyBackend) {
+    let category = "category".to_string();
+    let test_rows = [
+        (
+            "a",
+            vec![EntryTag::Encrypted("rev_reg".to_string(), "r1".to_string())],
+        ),
+        (
+            "b",
+            vec![EntryTag::Plaintext("plain".to_string(), "v1".to_string())],
+        ),
+        (
+            "c",
             vec![
-                EntryTag::Encrypted("t1".to_string(), "del".to_string()),
-                EntryTag::Plaintext("t2".to_string(), "del".to_string()),
+                EntryTag::Encrypted("rev_reg".to_string(), "r2".to_string()),
+                EntryTag::Plaintext("plain".to_string(), "v2".to_string()),
             ],
         ),
-        Entry::new(
+        ("d", vec![]),
+    ];
+
+    let mut conn = db.session(None, false).expect(ERR_SESSION);
+
+    for (name, tags) in test_rows.iter() {
+        conn.update(
+            EntryKind::Item,
+            EntryOperation::Insert,
+            &category,
+            name,
+            Some(b"value"),
+            Some(tags.as_slice()),
+            None,
+        )
+        .await
+        .expect(ERR_INSERT);
+    }
+
+    let cases: [(&str, i64); 5] = [
+        (r#"{"$not": {"$exist": ["rev_reg"]}}"#, 2),
+        (r#"{"$not": {"$exist": ["rev_reg", "~plain"]}}"#, 1),
+        (r#"{"$not": {"$not": {"$exist": ["rev_reg"]}}}"#, 2),
+        (
+            r#"{"$not": {"$or": [{"$exist": "rev_reg"}, {"~plain": "v1"}]}}"#,
+            1,
+        ),
+        (r#"{"$not": {}}"#, 0),
+    ];
+    for (filter, expected) in cases {
+        let tag_filter = Some(filter.parse().expect("Error parsing tag filter"));
+        assert_eq!(
+            conn.count(Some(EntryKind::Item), Some(&category), tag_filter)
+                .await
+                .expect(ERR_COUNT),
+            expected,
+            "{filter}"
+        );
+    }
+}
+
+pub async fn db_search(db: AnyBackend) {
+    let category = "connection".to_string();
+    let test_rows = [
+        ("a", "Alice Smith", "active"),
+        ("b", "Bob Alison", "active"),
+        ("c", "Carol", "inactive"),
+    ];
+
+    let mut conn = db.session(None, false).expect(ERR_SESSION);
+
+    for (name, label, state) in test_rows {
+        conn.update(
+            EntryKind::Item,
+            EntryOperation::Insert,
+            &category,
+            name,
+            Some(b"value"),
+            Some(&[
+                EntryTag::Plaintext("label".to_string(), label.to_string()),
+                EntryTag::Plaintext("state".to_string(), state.to_string()),
+                EntryTag::Encrypted("secret".to_string(), "Alice".to_string()),
+            ]),
+            None,
+        )
+        .await
+        .expect(ERR_INSERT);
+    }
+
+    assert_eq!(
+        search_names(&mut conn, &category, "ali", None).await,
+        ["a", "b"]
+    );
+    assert_eq!(
+        search_names(&mut conn, &category, "alice smi", None).await,
+        ["a"]
+    );
+    assert_eq!(
+        search_names(&mut conn, &category, "  ", None).await,
+        Vec::<String>::new()
+    );
+    assert_eq!(
+        search_names(&mut conn, &category, "carol", None).await,
+        ["c"]
+    );
+    assert_eq!(
+        search_names(
+            &mut conn,
+            &category,
+            "active",
+            Some(TagFilter::is_eq("~state", "active"))
+        )
+        .await,
+        ["a", "b"]
+    );
+
+    // removed entries and replaced tags are no longer matched
+    conn.update(
+        EntryKind::Item,
+        EntryOperation::Remove,
+        &category,
+        "a",
+        None,
+        None,
+        None,
+    )
+    .await
+    .expect("Error removing test row");
+    conn.update(
+        EntryKind::Item,
+        EntryOperation::Replace,
+        &category,
+        "b",
+        Some(b"value"),
+        Some(&[EntryTag::Plaintext(
+            "label".to_string(),
+            "Robert".to_string(),
+        )]),
+        None,
+    )
+    .await
+    .expect(ERR_REPLACE);
+    assert_eq!(
+        search_names(&mut conn, &category, "ali", None).await,
+        Vec::<String>::new()
+    );
+    assert_eq!(search_names(&mut conn, &category, "rob", None).await, ["b"]);
+}
+
+async fn search_names(
+    conn: &mut impl BackendSession,
+    category: &str,
+    text: &str,
+    tag_filter: Option<TagFilter>,
+) -> Vec<String> {
+    let mut names = conn
+        .search(
+            Some(EntryKind::Item),
+            Some(category),
+            text,
+            tag_filter,
+            None,
+        )
+        .await
+        .expect("Error performing search")
+        .into_iter()
+        .map(|row| row.name)
+        .collect::<Vec<_>>();
+    names.sort();
+    names
+}
+
+pub async fn db_update_if(db: AnyBackend) {
+    let test_row = Entry::new(
+        EntryKind::Item,
+        "category",
+        "name",
+        "value",
+        vec![EntryTag::Plaintext("state".to_string(), "open".to_string())],
+    );
+
+    let mut conn = db.session(None, false).expect(ERR_SESSION);
+
+    conn.update(
+        EntryKind::Item,
+        EntryOperation::Insert,
+        &test_row.category,
+        &test_row.name,
+        Some(&test_row.value),
+        Some(test_row.tags.as_slice()),
+        None,
+    )
+    .await
+    .expect(ERR_INSERT);
+
+    let closed_tags = vec![EntryTag::Plaintext(
+        "state".to_string(),
+        "closed".to_string(),
+    )];
+
+    // a stale value hash is rejected
+    let err = conn
+        .update_if(
+            EntryKind::Item,
+            EntryOperation::Replace,
+            &test_row.category,
+            &test_row.name,
+            Some(b"closed"),
+            Some(closed_tags.as_slice()),
+            None,
+            &EntryPrecondition::value_matches(b"other"),
+        )
+        .await
+        .expect_err(ERR_REQ_ERR);
+    assert_eq!(err.kind(), ErrorKind::Conflict);
+
+    conn.update_if(
+        EntryKind::Item,
+        EntryOperation::Replace,
+        &test_row.category,
+        &test_row.name,
+        Some(b"closed"),
+        Some(closed_tags.as_slice()),
+        None,
+        &EntryPrecondition::value_matches(b"value"),
+    )
+    .await
+    .expect(ERR_REPLACE);
+
+    let row = conn
+        .fetch(EntryKind::Item, &test_row.category, &test_row.name, false)
+        .await
+        .expect(ERR_FETCH)
+        .expect(ERR_REQ_ROW);
+    assert_eq!(row.value, "closed".as_bytes());
+
+    // remove only if the tag still has the expected value
+    let err = conn
+        .update_if(
+            EntryKind::Item,
+            EntryOperation::Remove,
+            &test_row.category,
+            &test_row.name,
+            None,
+            None,
+            None,
+            &EntryPrecondition::Tags(test_row.tags.clone()),
+        )
+        .await
+        .expect_err(ERR_REQ_ERR);
+    assert_eq!(err.kind(), ErrorKind::Conflict);
+
+    conn.update_if(
+        EntryKind::Item,
+        EntryOperation::Remove,
+        &test_row.category,
+        &test_row.name,
+        None,
+        None,
+        None,
+        &EntryPrecondition::Tags(closed_tags.clone()),
+    )
+    .await
+    .expect("Error removing test row");
+
+    let err = conn
+        .update_if(
+            EntryKind::Item,
+            EntryOperation::Remove,
+            &test_row.category,
+            &test_row.name,
+            None,
+            None,
+            None,
+            &EntryPrecondition::Tags(closed_tags),
+        )
+        .await
+        .expect_err(ERR_REQ_ERR);
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+}
+
+pub async fn db_entry_version(db: AnyBackend) {
+    let test_row = Entry::new(EntryKind::Item, "category", "name", "value", Vec::new());
+
+    let mut conn = db.session(None, false).expect(ERR_SESSION);
+
+    conn.update(
+        EntryKind::Item,
+        EntryOperation::Insert,
+        &test_row.category,
+        &test_row.name,
+        Some(&test_row.value),
+        None,
+        None,
+    )
+    .await
+    .expect(ERR_INSERT);
+
+    let row = conn
+        .fetch(EntryKind::Item, &test_row.category, &test_row.name, false)
+        .await
+        .expect(ERR_FETCH)
+        .expect(ERR_REQ_ROW);
+    assert_eq!(row.version, 1);
+
+    conn.update(
+        EntryKind::Item,
+        EntryOperation::Replace,
+        &test_row.category,
+        &test_row.name,
+        Some(b"updated"),
+        None,
+        None,
+    )
+    .await
+    .expect(ERR_REPLACE);
+
+    let rows = conn
+        .fetch_all(
+            Some(EntryKind::Item),
+            Some(&test_row.category),
+            None,
+            None,
+            false,
+        )
+        .await
+        .expect(ERR_FETCH_ALL);
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].version, 2);
+
+    // a stale version is rejected
+    let err = conn
+        .update_if(
+            EntryKind::Item,
+            EntryOperation::Replace,
+            &test_row.category,
+            &test_row.name,
+            Some(b"stale"),
+            None,
+            None,
+            &EntryPrecondition::Version(1),
+        )
+        .await
+        .expect_err(ERR_REQ_ERR);
+    assert_eq!(err.kind(), ErrorKind::Conflict);
+
+    conn.update_if(
+        EntryKind::Item,
+        EntryOperation::Replace,
+        &test_row.category,
+        &test_row.name,
+        Some(b"current"),
+        None,
+        None,
+        &EntryPrecondition::Version(2),
+    )
+    .await
+    .expect(ERR_REPLACE);
+
+    let row = conn
+        .fetch(EntryKind::Item, &test_row.category, &test_row.name, false)
+        .await
+        .expect(ERR_FETCH)
+        .expect(ERR_REQ_ROW);
+    assert_eq!(row.value, "current".as_bytes());
+    assert_eq!(row.version, 3);
+}
+
+pub async fn db_move_category(db: AnyBackend) {
+    let mut conn = db.session(None, false).expect(ERR_SESSION);
+
+    for (category, name) in [("old", "a"), ("old", "b"), ("new", "c"), ("other", "a")] {
+        conn.update(
+            EntryKind::Item,
+            EntryOperation::Insert,
+            category,
+            name,
+            Some(name.as_bytes()),
+            Some(&[
+                EntryTag::Encrypted("t1".to_string(), "x".to_string()),
+                EntryTag::Plaintext("t2".to_string(), "y".to_string()),
+            ]),
+            None,
+        )
+        .await
+        .expect(ERR_INSERT);
+    }
+
+    let err = conn
+        .move_category(EntryKind::Item, "old", "other", &[])
+        .await
+        .expect_err(ERR_REQ_ERR);
+    assert_eq!(err.kind(), ErrorKind::Duplicate);
+
+    let count = conn
+        .move_category(EntryKind::Item, "old", "new", &[("t1", "t3")])
+        .await
+        .expect("Error moving category");
+    assert_eq!(count, 2);
+
+    let count = conn
+        .count(Some(EntryKind::Item), Some("old"), None)
+        .await
+        .expect(ERR_COUNT);
+    assert_eq!(count, 0);
+
+    let mut row = conn
+        .fetch(EntryKind::Item, "new", "b", false)
+        .await
+        .expect(ERR_FETCH)
+        .expect(ERR_REQ_ROW);
+    row.tags.sort();
+    assert_eq!(row.value, "b".as_bytes());
+    assert_eq!(
+        row.tags,
+        vec![
+            EntryTag::Encrypted("t3".to_string(), "x".to_string()),
+            EntryTag::Plaintext("t2".to_string(), "y".to_string()),
+        ]
+    );
+
+    // tags on entries already present in the target category are unchanged
+    let count = conn
+        .count(
+            Some(EntryKind::Item),
+            Some("new"),
+            Some(TagFilter::is_eq("t1", "x")),
+        )
+        .await
+        .expect(ERR_COUNT);
+    assert_eq!(count, 1);
+
+    let count = conn
+        .move_category(EntryKind::Item, "missing", "new", &[])
+        .await
+        .expect("Error moving category");
+    assert_eq!(count, 0);
+}
+
+pub async fn db_rename(db: AnyBackend) {
+    let test_row = Entry::new(
+        EntryKind::Item,
+        "category",
+        "name",
+        "value",
+        vec![
+            EntryTag::Encrypted("t1".to_string(), "a".to_string()),
+            EntryTag::Plaintext("t2".to_string(), "b".to_string()),
+        ],
+    );
+
+    let mut conn = db.session(None, false).expect(ERR_SESSION);
+
+    for name in [test_row.name.as_str(), "other"] {
+        conn.update(
+            EntryKind::Item,
+            EntryOperation::Insert,
+            &test_row.category,
+            name,
+            Some(&test_row.value),
+            Some(test_row.tags.as_slice()),
+            None,
+        )
+        .await
+        .expect(ERR_INSERT);
+    }
+
+    let err = conn
+        .rename(EntryKind::Item, &test_row.category, &test_row.name, "other")
+        .await
+        .expect_err(ERR_REQ_ERR);
+    assert_eq!(err.kind(), ErrorKind::Duplicate);
+
+    conn.rename(
+        EntryKind::Item,
+        &test_row.category,
+        &test_row.name,
+        "renamed",
+    )
+    .await
+    .expect("Error renaming entry");
+
+    let row = conn
+        .fetch(EntryKind::Item, &test_row.category, &test_row.name, false)
+        .await
+        .expect(ERR_FETCH);
+    assert!(row.is_none());
+
+    let mut row = conn
+        .fetch(EntryKind::Item, &test_row.category, "renamed", false)
+        .await
+        .expect(ERR_FETCH)
+        .expect(ERR_REQ_ROW);
+    row.tags.sort();
+    assert_eq!(row.value, test_row.value);
+    assert_eq!(row.tags, test_row.tags);
+    assert_eq!(row.version, 2);
+
+    let count = conn
+        .count(
+            Some(EntryKind::Item),
+            Some(&test_row.category),
+            Some(TagFilter::is_eq("t1", "a")),
+        )
+        .await
+        .expect(ERR_COUNT);
+    assert_eq!(count, 2);
+
+    let err = conn
+        .rename(EntryKind::Item, &test_row.category, "missing", "new")
+        .await
+        .expect_err(ERR_REQ_ERR);
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+}
+
+pub async fn db_chunks(db: AnyBackend) {
+    let mut conn = db.session(None, false).expect(ERR_SESSION);
+
+    let err = conn
+        .write_chunk(EntryKind::Item, "category", "name", 0, b"chunk")
+        .await
+        .expect_err(ERR_REQ_ERR);
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+
+    conn.update(
+        EntryKind::Item,
+        EntryOperation::Insert,
+        "category",
+        "name",
+        Some(b"value"),
+        None,
+        None,
+    )
+    .await
+    .expect(ERR_INSERT);
+
+    for (index, chunk) in [&b"a"[..], b"b", b"c"].into_iter().enumerate() {
+        conn.write_chunk(EntryKind::Item, "category", "name", index as u32, chunk)
+            .await
+            .expect("Error writing chunk");
+    }
+    let chunks = conn
+        .fetch_chunks(EntryKind::Item, "category", "name", 1, 10)
+        .await
+        .expect("Error fetching chunks");
+    assert_eq!(chunks, vec![b"b".to_vec(), b"c".to_vec()]);
+
+    // writing the first chunk discards the existing chunks
+    conn.write_chunk(EntryKind::Item, "category", "name", 0, b"d")
+        .await
+        .expect("Error writing chunk");
+    let chunks = conn
+        .fetch_chunks(EntryKind::Item, "category", "name", 0, 10)
+        .await
+        .expect("Error fetching chunks");
+    assert_eq!(chunks, vec![b"d".to_vec()]);
+
+    conn.update(
+        EntryKind::Item,
+        EntryOperation::Remove,
+        "category",
+        "name",
+        None,
+        None,
+        None,
+    )
+    .await
+    .expect("Error removing test row");
+    let chunks = conn
+        .fetch_chunks(EntryKind::Item, "category", "name", 0, 10)
+        .await
+        .expect("Error fetching chunks");
+    assert!(chunks.is_empty());
+}
+
+pub async fn db_update_tags(db: AnyBackend) {
+    let test_row = Entry::new(
+        EntryKind::Item,
+        "category",
+        "name",
+        "value",
+        vec![
+            EntryTag::Encrypted("t1".to_string(), "a".to_string()),
+            EntryTag::Encrypted("t2".to_string(), "b".to_string()),
+            EntryTag::Plaintext("revoked".to_string(), "false".to_string()),
+        ],
+    );
+
+    let mut conn = db.session(None, false).expect(ERR_SESSION);
+
+    conn.update(
+        EntryKind::Item,
+        EntryOperation::Insert,
+        &test_row.category,
+        &test_row.name,
+        Some(&test_row.value),
+        Some(test_row.tags.as_slice()),
+        None,
+    )
+    .await
+    .expect(ERR_INSERT);
+
+    conn.update_tags(
+        EntryKind::Item,
+        &test_row.category,
+        &test_row.name,
+        &[
+            EntryTag::Plaintext("revoked".to_string(), "true".to_string()),
+            EntryTag::Encrypted("t3".to_string(), "c".to_string()),
+        ],
+        &["t1", "missing"],
+    )
+    .await
+    .expect("Error updating tags");
+
+    let row = conn
+        .fetch(EntryKind::Item, &test_row.category, &test_row.name, false)
+        .await
+        .expect(ERR_FETCH)
+        .expect(ERR_REQ_ROW);
+    assert_eq!(row.value, test_row.value);
+    assert_eq!(row.version, 2);
+    let mut tags = row.tags;
+    tags.sort();
+    assert_eq!(
+        tags,
+        vec![
+            EntryTag::Encrypted("t2".to_string(), "b".to_string()),
+            EntryTag::Encrypted("t3".to_string(), "c".to_string()),
+            EntryTag::Plaintext("revoked".to_string(), "true".to_string()),
+        ]
+    );
+
+    let count = conn
+        .count(
+            Some(EntryKind::Item),
+            Some(&test_row.category),
+            Some(TagFilter::is_eq("~revoked", "true")),
+        )
+        .await
+        .expect(ERR_COUNT);
+    assert_eq!(count, 1);
+
+    let err = conn
+        .update_tags(EntryKind::Item, &test_row.category, "missing", &[], &["t2"])
+        .await
+        .expect_err(ERR_REQ_ERR);
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+}
+
+pub async fn db_insert_many(db: AnyBackend) {
+    let category = "category".to_string();
+    let test_rows = (0..300)
+        .map(|idx| {
+            Entry::new(
+                EntryKind::Item,
+                &category,
+                format!("name-{idx}"),
+                format!("value-{idx}"),
+                vec![
+                    EntryTag::Encrypted("enc".to_string(), format!("{}", idx % 3)),
+                    EntryTag::Plaintext("plain".to_string(), format!("{idx}")),
+                ],
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let mut conn = db.session(None, false).expect(ERR_SESSION);
+
+    conn.insert_many(&test_rows, None)
+        .await
+        .expect("Error inserting entries");
+
+    assert_eq!(
+        conn.count(Some(EntryKind::Item), Some(&category), None)
+            .await
+            .expect(ERR_COUNT),
+        300
+    );
+    assert_eq!(
+        conn.count(
+            Some(EntryKind::Item),
+            Some(&category),
+            Some(TagFilter::is_eq("enc", "1"))
+        )
+        .await
+        .expect(ERR_COUNT),
+        100
+    );
+
+    let row = conn
+        .fetch(EntryKind::Item, &category, "name-299", false)
+        .await
+        .expect(ERR_FETCH)
+        .expect(ERR_REQ_ROW);
+    assert_eq!(row, test_rows[299]);
+
+    // a duplicate entry rolls back the whole batch
+    let dup_rows = [
+        Entry::new(EntryKind::Item, &category, "new", "value", Vec::new()),
+        test_rows[0].clone(),
+    ];
+    let err = conn
+        .insert_many(&dup_rows, None)
+        .await
+        .expect_err("Expected duplicate insert error");
+    assert_eq!(err.kind(), ErrorKind::Duplicate);
+    assert!(conn
+        .fetch(EntryKind::Item, &category, "new", false)
+        .await
+        .expect(ERR_FETCH)
+        .is_none());
+}
+
+pub async fn db_fetch_many(db: AnyBackend) {
+    let category = "category".to_string();
+    let test_rows = (0..5)
+        .map(|idx| {
+            Entry::new(
+                EntryKind::Item,
+                &category,
+                format!("name-{idx}"),
+                format!("value-{idx}"),
+                vec![EntryTag::Encrypted("enc".to_string(), format!("{idx}"))],
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let mut conn = db.session(None, false).expect(ERR_SESSION);
+
+    conn.insert_many(&test_rows, None).await.expect(ERR_INSERT);
+
+    let rows = conn
+        .fetch_many(
+            EntryKind::Item,
+            &category,
+            &["name-3", "missing", "name-0", "name-3"],
+            false,
+        )
+        .await
+        .expect(ERR_FETCH_ALL);
+    assert_eq!(rows, [test_rows[3].clone(), test_rows[0].clone()]);
+
+    let rows = conn
+        .fetch_many(EntryKind::Item, "other", &["name-0"], false)
+        .await
+        .expect(ERR_FETCH_ALL);
+    assert!(rows.is_empty());
+
+    let rows = conn
+        .fetch_many(EntryKind::Item, &category, &[], false)
+        .await
+        .expect(ERR_FETCH_ALL);
+    assert!(rows.is_empty());
+}
+
+pub async fn db_scan(db: AnyBackend) {
+    let category = "category".to_string();
+    let test_rows = vec![Entry::new(
+        EntryKind::Item,
+        &category,
+        "name",
+        "value",
+        vec![
+            EntryTag::Encrypted("t1".to_string(), "v1".to_string()),
+            EntryTag::Plaintext("t2".to_string(), "v2".to_string()),
+        ],
+    )];
+
+    let mut conn = db.session(None, false).expect(ERR_SESSION);
+
+    for upd in test_rows.iter() {
+        conn.update(
+            EntryKind::Item,
+            EntryOperation::Insert,
+            &upd.category,
+            &upd.name,
+            Some(&upd.value),
+            Some(upd.tags.as_slice()),
+            None,
+        )
+        .await
+        .expect(ERR_INSERT);
+    }
+    drop(conn);
+
+    let tag_filter = None;
+    let offset = None;
+    let limit = None;
+    let mut scan = db
+        .scan(
+            None,
+            Some(EntryKind::Item),
+            Some(category.clone()),
+            tag_filter,
+            offset,
+            limit,
+            None,
+            false,
+            None,
+            TagSelection::All,
+        )
+        .await
+        .expect(ERR_SCAN);
+    let rows = scan.fetch_next().await.expect(ERR_SCAN_NEXT);
+    assert_eq!(rows, Some(test_rows));
+    let rows = scan.fetch_next().await.expect(ERR_SCAN_NEXT);
+    assert_eq!(rows, None);
+
+    let tag_filter = Some(TagFilter::is_eq("sometag", "someval"));
+    let mut scan = db
+        .scan(
+            None,
+            Some(EntryKind::Item),
+            Some(category.clone()),
+            tag_filter,
+            offset,
+            limit,
+            None,
+            false,
+            None,
+            TagSelection::All,
+        )
+        .await
+        .expect(ERR_SCAN);
+    let rows = scan.fetch_next().await.expect(ERR_SCAN_NEXT);
+    assert_eq!(rows, None);
+}
+
+pub async fn db_scan_ordered(db: AnyBackend) {
+    let category = "category".to_string();
+    let test_rows = ["b", "c", "a"]
+        .into_iter()
+        .zip(["2", "1", "3"])
+        .map(|(name, tag)| {
+            Entry::new(
+                EntryKind::Item,
+                &category,
+                name,
+                "value",
+                vec![EntryTag::Plaintext("seq".to_string(), tag.to_string())],
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let mut conn = db.session(None, false).expect(ERR_SESSION);
+
+    for upd in test_rows.iter() {
+        conn.update(
+            EntryKind::Item,
+            EntryOperation::Insert,
+            &upd.category,
+            &upd.name,
+            Some(&upd.value),
+            Some(upd.tags.as_slice()),
+            None,
+        )
+        .await
+        .expect(ERR_INSERT);
+    }
+    drop(conn);
+
+    for (order_by, descending, offset, limit, expected) in [
+        (
+            OrderBy::Created,
+            false,
+            None,
+            None,
+            ["b", "c", "a"].as_slice(),
+        ),
+        (OrderBy::Created, true, None, Some(2), &["a", "c"]),
+        (OrderBy::Name, false, None, None, &["a", "b", "c"]),
+        (OrderBy::Name, true, Some(1), Some(1), &["b"]),
+        (
+            OrderBy::Tag("seq".to_string()),
+            false,
+            None,
+            None,
+            &["c", "b", "a"],
+        ),
+        (
+            OrderBy::Tag("seq".to_string()),
+            true,
+            Some(1),
+            None,
+            &["b", "c"],
+        ),
+    ] {
+        let mut scan = db
+            .scan(
+                None,
+                Some(EntryKind::Item),
+                Some(category.clone()),
+                None,
+                offset,
+                limit,
+                Some(order_by),
+                descending,
+                None,
+                TagSelection::All,
+            )
+            .await
+            .expect(ERR_SCAN);
+        let rows = scan
+            .fetch_next()
+            .await
+            .expect(ERR_SCAN_NEXT)
+            .unwrap_or_default();
+        let names = rows.iter().map(|row| row.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(names, expected);
+        let rows = scan.fetch_next().await.expect(ERR_SCAN_NEXT);
+        assert_eq!(rows, None);
+    }
+
+    // fetch_all returns records in the order they were inserted
+    let mut conn = db.session(None, false).expect(ERR_SESSION);
+    for (limit, expected) in [(None, ["b", "c", "a"].as_slice()), (Some(2), &["b", "c"])] {
+        let rows = conn
+            .fetch_all(Some(EntryKind::Item), Some(&category), None, limit, false)
+            .await
+            .expect(ERR_FETCH_ALL);
+        let names = rows.iter().map(|row| row.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(names, expected);
+    }
+}
+
+pub async fn db_scan_tags(db: AnyBackend) {
+    let category = "category".to_string();
+    let test_rows = ["b", "c", "a"]
+        .into_iter()
+        .zip(["2", "1", "3"])
+        .map(|(name, seq)| {
+            Entry::new(
+                EntryKind::Item,
+                &category,
+                name,
+                "value",
+                vec![
+                    EntryTag::Encrypted("enc".to_string(), "a".to_string()),
+                    EntryTag::Plaintext("plain".to_string(), "b".to_string()),
+                    EntryTag::Encrypted("other".to_string(), "c".to_string()),
+                    EntryTag::Plaintext("seq".to_string(), seq.to_string()),
+                ],
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let mut conn = db.session(None, false).expect(ERR_SESSION);
+    conn.insert_many(&test_rows, None).await.expect(ERR_INSERT);
+    drop(conn);
+
+    let mut tokens = Vec::new();
+    for (tags, expected) in [
+        (
+            TagSelection::All,
+            ["enc", "plain", "other", "seq"].as_slice(),
+        ),
+        (TagSelection::Omit, &[]),
+        (
+            TagSelection::Named(vec!["~plain".to_string(), "enc".to_string()]),
+            &["enc", "plain"],
+        ),
+        (TagSelection::Named(vec!["missing".to_string()]), &[]),
+    ] {
+        // the ordering tag is only returned when selected
+        let mut scan = db
+            .scan(
+                None,
+                Some(EntryKind::Item),
+                Some(category.clone()),
+                None,
+                None,
+                Some(2),
+                Some(OrderBy::Tag("seq".to_string())),
+                false,
+                None,
+                tags,
+            )
+            .await
+            .expect(ERR_SCAN);
+        let rows = scan.fetch_next().await.expect(ERR_SCAN_NEXT).unwrap();
+        let names = rows.iter().map(|row| row.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(names, ["c", "b"]);
+        for row in rows.iter() {
+            let mut tag_names = row.tags.iter().map(EntryTag::name).collect::<Vec<_>>();
+            tag_names.sort();
+            let mut expected = expected.to_vec();
+            expected.sort();
+            assert_eq!(tag_names, expected);
+        }
+        tokens.push(scan.continuation().map(str::to_string));
+    }
+    // the continuation token does not depend on the selected tags
+    assert!(tokens
+        .iter()
+        .all(|token| token.is_some() == tokens[0].is_some()));
+    for token in tokens.into_iter().flatten() {
+        let mut scan = db
+            .scan(
+                None,
+                Some(EntryKind::Item),
+                Some(category.clone()),
+                None,
+                None,
+                None,
+                Some(OrderBy::Tag("seq".to_string())),
+                false,
+                Some(token),
+                TagSelection::Omit,
+            )
+            .await
+            .expect(ERR_SCAN);
+        let rows = scan.fetch_next().await.expect(ERR_SCAN_NEXT).unwrap();
+        let names = rows.iter().map(|row| row.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(names, ["a"]);
+    }
+}
+
+pub async fn db_explain_scan(db: AnyBackend) {
+    let category = "category".to_string();
+    let test_rows = ["a", "b", "c"]
+        .into_iter()
+        .zip(["x", "y", "x"])
+        .map(|(name, value)| {
+            Entry::new(
+                EntryKind::Item,
+                &category,
+                name,
+                "value",
+                vec![
+                    EntryTag::Encrypted("enc".to_string(), value.to_string()),
+                    EntryTag::Plaintext("plain".to_string(), name.to_string()),
+                ],
+            )
+        })
+        .collect::<Vec<_>>();
+    let mut conn = db.session(None, false).expect(ERR_SESSION);
+    conn.insert_many(&test_rows, None).await.expect(ERR_INSERT);
+
+    let tag_filter = TagFilter::all_of(vec![
+        TagFilter::is_eq("enc", "x"),
+        TagFilter::negate(TagFilter::is_eq("~plain", "a")),
+    ]);
+    let plan = conn
+        .explain_scan(
+            Some(EntryKind::Item),
+            Some(&category),
+            Some(tag_filter.clone()),
+        )
+        .await
+        .expect("Error explaining scan");
+    // negated tag clauses are correlated with the item
+    assert!(plan.query.contains("NOT EXISTS (SELECT 1 FROM items_tags"));
+    // profile, kind, category, and the arguments for each tag clause
+    assert_eq!(plan.arguments, 3 + 3 + 2);
+    assert!(!plan.plan.is_empty());
+
+    let rows = conn
+        .fetch_all(
+            Some(EntryKind::Item),
+            Some(&category),
+            Some(tag_filter),
+            None,
+            false,
+        )
+        .await
+        .expect(ERR_FETCH_ALL);
+    let names = rows.iter().map(|row| row.name.as_str()).collect::<Vec<_>>();
+    assert_eq!(names, ["c"]);
+    drop(conn);
+}
+
+pub async fn db_scan_stream(db: AnyBackend) {
+    let category = "category".to_string();
+    let test_rows = (0..50)
+        .map(|idx| {
+            Entry::new(
+                EntryKind::Item,
+                &category,
+                format!("name-{:02}", idx),
+                "value",
+                Vec::new(),
+            )
+        })
+        .collect::<Vec<_>>();
+    let mut conn = db.session(None, false).expect(ERR_SESSION);
+    conn.insert_many(&test_rows, None).await.expect(ERR_INSERT);
+    drop(conn);
+
+    let scan = db
+        .scan(
+            None,
+            Some(EntryKind::Item),
+            Some(category.clone()),
+            None,
+            None,
+            None,
+            Some(OrderBy::Name),
+            false,
+            None,
+            TagSelection::All,
+        )
+        .await
+        .expect(ERR_SCAN);
+    let rows: Vec<Entry> = scan.try_collect().await.expect(ERR_SCAN_NEXT);
+    assert_eq!(rows, test_rows);
+
+    // a partially consumed page is returned by the next page fetch
+    let mut scan = db
+        .scan(
+            None,
+            Some(EntryKind::Item),
+            Some(category.clone()),
+            None,
+            None,
+            None,
+            Some(OrderBy::Name),
+            false,
+            None,
+            TagSelection::All,
+        )
+        .await
+        .expect(ERR_SCAN);
+    let first = scan.next().await.expect(ERR_REQ_ROW).expect(ERR_SCAN_NEXT);
+    assert_eq!(first, test_rows[0]);
+    let mut count = 1;
+    while let Some(rows) = scan.fetch_next().await.expect(ERR_SCAN_NEXT) {
+        count += rows.len();
+    }
+    assert_eq!(count, test_rows.len());
+    assert!(scan.next().await.is_none());
+}
+
+pub async fn db_scan_continuation(db: AnyBackend) {
+    let category = "category".to_string();
+    let make_row = |name: &str, tag: &str| {
+        Entry::new(
+            EntryKind::Item,
+            &category,
+            name,
+            "value",
+            vec![EntryTag::Plaintext("seq".to_string(), tag.to_string())],
+        )
+    };
+
+    let mut conn = db.session(None, false).expect(ERR_SESSION);
+    for upd in [make_row("b", "2"), make_row("c", "1"), make_row("a", "3")] {
+        conn.update(
+            EntryKind::Item,
+            EntryOperation::Insert,
+            &upd.category,
+            &upd.name,
+            Some(&upd.value),
+            Some(upd.tags.as_slice()),
+            None,
+        )
+        .await
+        .expect(ERR_INSERT);
+    }
+
+    let orderings = [
+        (OrderBy::Created, ["b", "c"], ["a", "d"]),
+        (OrderBy::Name, ["a", "b"], ["c", "d"]),
+        (OrderBy::Tag("seq".to_string()), ["c", "b"], ["d", "a"]),
+    ];
+    let mut tokens = Vec::new();
+    for (order_by, first_page, _) in orderings.iter() {
+        let mut scan = db
+            .scan(
+                None,
+                Some(EntryKind::Item),
+                Some(category.clone()),
+                None,
+                None,
+                Some(2),
+                Some(order_by.clone()),
+                false,
+                None,
+                TagSelection::All,
+            )
+            .await
+            .expect(ERR_SCAN);
+        assert_eq!(scan.continuation(), None);
+        let rows = scan.fetch_next().await.expect(ERR_SCAN_NEXT).unwrap();
+        let names = rows.iter().map(|row| row.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(names, first_page);
+        tokens.push(
+            scan.continuation()
+                .expect("Expected continuation token")
+                .to_string(),
+        );
+    }
+
+    // a row inserted between pages is returned in sequence
+    let upd = make_row("d", "2.5");
+    conn.update(
+        EntryKind::Item,
+        EntryOperation::Insert,
+        &upd.category,
+        &upd.name,
+        Some(&upd.value),
+        Some(upd.tags.as_slice()),
+        None,
+    )
+    .await
+    .expect(ERR_INSERT);
+    drop(conn);
+
+    for ((order_by, _, next_page), token) in orderings.iter().zip(tokens.iter()) {
+        let mut scan = db
+            .scan(
+                None,
+                Some(EntryKind::Item),
+                Some(category.clone()),
+                None,
+                None,
+                None,
+                Some(order_by.clone()),
+                false,
+                Some(token.clone()),
+                TagSelection::All,
+            )
+            .await
+            .expect(ERR_SCAN);
+        let rows = scan.fetch_next().await.expect(ERR_SCAN_NEXT).unwrap();
+        let names = rows.iter().map(|row| row.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(names, next_page);
+    }
+
+    let err = db
+        .scan(
+            None,
+            Some(EntryKind::Item),
+            Some(category.clone()),
+            None,
+            None,
+            None,
+            Some(OrderBy::Name),
+            false,
+            Some(tokens[0].clone()),
+            TagSelection::All,
+        )
+        .await
+        .expect_err(ERR_REQ_ERR);
+    assert_eq!(err.kind(), ErrorKind::Input);
+
+    // a modified token is rejected
+    let mut token = tokens[0].clone().into_bytes();
+    let last = token.last_mut().unwrap();
+    *last = if *last == b'0' { b'1' } else { b'0' };
+    let err = db
+        .scan(
+            None,
+            Some(EntryKind::Item),
+            Some(category.clone()),
+            None,
+            None,
+            None,
+            Some(OrderBy::Created),
+            false,
+            Some(String::from_utf8(token).unwrap()),
+            TagSelection::All,
+        )
+        .await
+        .expect_err(ERR_REQ_ERR);
+    assert_eq!(err.kind(), ErrorKind::Input);
+}
+
+pub async fn db_remove_all(db: AnyBackend) {
+    let test_rows = vec![
+        Entry::new(
+            EntryKind::Item,
+            "category",
+            "item1",
+            "value",
+            vec![
+                EntryTag::Encrypted("t1".to_string(), "del".to_string()),
+                EntryTag::Plaintext("t2".to_string(), "del".to_string()),
+            ],
+        ),
+        Entry::new(
             EntryKind::Item,
             "category",
             "item2",
@@ -579,6 +2162,171 @@ pub async fn db_remove_all(db: AnyBackend) {
     assert_eq!(removed, 2);
 }
 
+pub async fn db_remove_all_returning(db: AnyBackend) {
+    let test_rows = ["item1", "item2", "item3"].map(|name| {
+        Entry::new(
+            EntryKind::Item,
+            "category",
+            name,
+            "value",
+            vec![EntryTag::Encrypted(
+                "t1".to_string(),
+                if name == "item2" { "keep" } else { "del" }.to_string(),
+            )],
+        )
+    });
+
+    let mut conn = db.session(None, false).expect(ERR_SESSION);
+
+    conn.insert_many(&test_rows, None).await.expect(ERR_INSERT);
+
+    let mut removed = conn
+        .remove_all_returning(
+            Some(EntryKind::Item),
+            Some("category"),
+            Some(TagFilter::is_eq("t1", "del")),
+        )
+        .await
+        .expect(ERR_REMOVE_ALL);
+    removed.sort_by(|a, b| a.name.cmp(&b.name));
+    assert_eq!(
+        removed,
+        ["item1", "item3"].map(|name| EntryEvent {
+            operation: EntryOperation::Remove,
+            kind: EntryKind::Item,
+            category: "category".to_string(),
+            name: name.to_string(),
+        })
+    );
+
+    let removed = conn
+        .remove_all_returning(Some(EntryKind::Item), Some("other"), None)
+        .await
+        .expect(ERR_REMOVE_ALL);
+    assert!(removed.is_empty());
+    assert_eq!(
+        conn.count(Some(EntryKind::Item), Some("category"), None)
+            .await
+            .expect(ERR_COUNT),
+        1
+    );
+}
+
+pub async fn db_subscribe(db: AnyBackend) {
+    let test_row = Entry::new(EntryKind::Item, "category", "name", "value", Vec::new());
+    let other_row = Entry::new(EntryKind::Item, "other", "name", "value", Vec::new());
+
+    let mut sub = db
+        .subscribe(None, Some(EntryKind::Item), None)
+        .await
+        .expect(ERR_SUBSCRIBE);
+    let mut sub_other = db
+        .subscribe(
+            None,
+            Some(EntryKind::Item),
+            Some(other_row.category.clone()),
+        )
+        .await
+        .expect(ERR_SUBSCRIBE);
+
+    let mut conn = db.session(None, false).expect(ERR_SESSION);
+
+    conn.update(
+        EntryKind::Item,
+        EntryOperation::Insert,
+        &test_row.category,
+        &test_row.name,
+        Some(&test_row.value),
+        None,
+        None,
+    )
+    .await
+    .expect(ERR_INSERT);
+
+    conn.update(
+        EntryKind::Item,
+        EntryOperation::Replace,
+        &test_row.category,
+        &test_row.name,
+        Some(&test_row.value),
+        None,
+        None,
+    )
+    .await
+    .expect(ERR_REPLACE);
+
+    conn.update(
+        EntryKind::Item,
+        EntryOperation::Remove,
+        &test_row.category,
+        &test_row.name,
+        None,
+        None,
+        None,
+    )
+    .await
+    .expect(ERR_REQ_ROW);
+
+    conn.close(false).await.expect(ERR_COMMIT);
+
+    // rolled-back changes are not published
+    let mut txn = db.session(None, true).expect(ERR_TRANSACTION);
+    txn.update(
+        EntryKind::Item,
+        EntryOperation::Insert,
+        &test_row.category,
+        &test_row.name,
+        Some(&test_row.value),
+        None,
+        None,
+    )
+    .await
+    .expect(ERR_INSERT);
+    txn.close(false)
+        .await
+        .expect("Error rolling back transaction");
+
+    let mut txn = db.session(None, true).expect(ERR_TRANSACTION);
+    txn.update(
+        EntryKind::Item,
+        EntryOperation::Insert,
+        &other_row.category,
+        &other_row.name,
+        Some(&other_row.value),
+        None,
+        None,
+    )
+    .await
+    .expect(ERR_INSERT);
+    txn.close(true).await.expect(ERR_COMMIT);
+
+    for (operation, row) in [
+        (EntryOperation::Insert, &test_row),
+        (EntryOperation::Replace, &test_row),
+        (EntryOperation::Remove, &test_row),
+        (EntryOperation::Insert, &other_row),
+    ] {
+        let event = sub
+            .next_event()
+            .await
+            .expect(ERR_SUBSCRIBE_NEXT)
+            .expect(ERR_REQ_EVENT);
+        assert_eq!(event.operation, operation);
+        assert_eq!(event.kind, EntryKind::Item);
+        assert_eq!(event.category, row.category);
+        assert_eq!(event.name, row.name);
+    }
+
+    let event = sub_other
+        .next_event()
+        .await
+        .expect(ERR_SUBSCRIBE_NEXT)
+        .expect(ERR_REQ_EVENT);
+    assert_eq!(event.operation, EntryOperation::Insert);
+    assert_eq!(event.category, other_row.category);
+    assert_eq!(event.name, other_row.name);
+}
+
 pub async fn db_txn_rollback(db: AnyBackend) {
     let test_row = Entry::new(EntryKind::Item, "category", "name", "value", Vec::new());
 
@@ -609,91 +2357,289 @@ pub async fn db_txn_rollback(db: AnyBackend) {
     assert_eq!(row, None);
 }
 
-pub async fn db_txn_drop(db: AnyBackend) {
-    let test_row = Entry::new(EntryKind::Item, "category", "name", "value", Vec::new());
+pub async fn db_txn_drop(db: AnyBackend) {
+    let test_row = Entry::new(EntryKind::Item, "category", "name", "value", Vec::new());
+
+    let mut conn = db
+        .session(None, true)
+        .expect("Error starting new transaction");
+
+    conn.update(
+        EntryKind::Item,
+        EntryOperation::Insert,
+        &test_row.category,
+        &test_row.name,
+        Some(&test_row.value),
+        Some(test_row.tags.as_slice()),
+        None,
+    )
+    .await
+    .expect(ERR_INSERT);
+
+    drop(conn);
+
+    let mut conn = db.session(None, false).expect("Error starting new session");
+
+    let row = conn
+        .fetch(EntryKind::Item, &test_row.category, &test_row.name, false)
+        .await
+        .expect("Error fetching test row");
+    assert_eq!(row, None);
+}
+
+// test that session does NOT have transaction rollback behaviour
+pub async fn db_session_drop(db: AnyBackend) {
+    let test_row = Entry::new(EntryKind::Item, "category", "name", "value", Vec::new());
+
+    let mut conn = db.session(None, false).expect(ERR_SESSION);
+
+    conn.update(
+        EntryKind::Item,
+        EntryOperation::Insert,
+        &test_row.category,
+        &test_row.name,
+        Some(&test_row.value),
+        Some(test_row.tags.as_slice()),
+        None,
+    )
+    .await
+    .expect(ERR_INSERT);
+
+    drop(conn);
+
+    let mut conn = db.session(None, false).expect(ERR_SESSION);
+
+    let row = conn
+        .fetch(EntryKind::Item, &test_row.category, &test_row.name, false)
+        .await
+        .expect(ERR_FETCH);
+    assert_eq!(row, Some(test_row));
+}
+
+pub async fn db_txn_commit(db: AnyBackend) {
+    let test_row = Entry::new(EntryKind::Item, "category", "name", "value", Vec::new());
+
+    let mut conn = db.session(None, true).expect(ERR_TRANSACTION);
+
+    conn.update(
+        EntryKind::Item,
+        EntryOperation::Insert,
+        &test_row.category,
+        &test_row.name,
+        Some(&test_row.value),
+        Some(test_row.tags.as_slice()),
+        None,
+    )
+    .await
+    .expect(ERR_INSERT);
+
+    conn.close(true).await.expect(ERR_COMMIT);
+
+    let mut conn = db.session(None, false).expect(ERR_SESSION);
+
+    let row = conn
+        .fetch(EntryKind::Item, &test_row.category, &test_row.name, false)
+        .await
+        .expect(ERR_FETCH);
+    assert_eq!(row, Some(test_row));
+}
+
+pub async fn db_txn_savepoint(db: AnyBackend) {
+    let test_rows = ["first", "second", "third"]
+        .iter()
+        .map(|name| Entry::new(EntryKind::Item, "category", *name, "value", Vec::new()))
+        .collect::<Vec<_>>();
+
+    let mut conn = db.session(None, false).expect(ERR_SESSION);
+    let err = conn
+        .savepoint("sp", SavepointOperation::Create)
+        .await
+        .expect_err(ERR_REQ_ERR);
+    assert_eq!(err.kind(), ErrorKind::Input);
+    drop(conn);
+
+    let mut conn = db.session(None, true).expect(ERR_TRANSACTION);
+
+    conn.update(
+        EntryKind::Item,
+        EntryOperation::Insert,
+        &test_rows[0].category,
+        &test_rows[0].name,
+        Some(&test_rows[0].value),
+        None,
+        None,
+    )
+    .await
+    .expect(ERR_INSERT);
+
+    let err = conn
+        .savepoint("bad name", SavepointOperation::Create)
+        .await
+        .expect_err(ERR_REQ_ERR);
+    assert_eq!(err.kind(), ErrorKind::Input);
+    let err = conn
+        .savepoint("missing", SavepointOperation::Rollback)
+        .await
+        .expect_err(ERR_REQ_ERR);
+    assert_eq!(err.kind(), ErrorKind::NotFound);
 
-    let mut conn = db
-        .session(None, true)
-        .expect("Error starting new transaction");
+    conn.savepoint("sp", SavepointOperation::Create)
+        .await
+        .expect("Error creating savepoint");
 
     conn.update(
         EntryKind::Item,
         EntryOperation::Insert,
-        &test_row.category,
-        &test_row.name,
-        Some(&test_row.value),
-        Some(test_row.tags.as_slice()),
+        &test_rows[1].category,
+        &test_rows[1].name,
+        Some(&test_rows[1].value),
+        None,
         None,
     )
     .await
     .expect(ERR_INSERT);
 
-    drop(conn);
-
-    let mut conn = db.session(None, false).expect("Error starting new session");
-
-    let row = conn
-        .fetch(EntryKind::Item, &test_row.category, &test_row.name, false)
+    // a failed operation may be discarded by rolling back to the savepoint
+    let err = conn
+        .update(
+            EntryKind::Item,
+            EntryOperation::Insert,
+            &test_rows[0].category,
+            &test_rows[0].name,
+            Some(&test_rows[0].value),
+            None,
+            None,
+        )
         .await
-        .expect("Error fetching test row");
-    assert_eq!(row, None);
-}
-
-// test that session does NOT have transaction rollback behaviour
-pub async fn db_session_drop(db: AnyBackend) {
-    let test_row = Entry::new(EntryKind::Item, "category", "name", "value", Vec::new());
+        .expect_err(ERR_REQ_ERR);
+    assert_eq!(err.kind(), ErrorKind::Duplicate);
 
-    let mut conn = db.session(None, false).expect(ERR_SESSION);
+    conn.savepoint("sp", SavepointOperation::Rollback)
+        .await
+        .expect("Error rolling back to savepoint");
 
     conn.update(
         EntryKind::Item,
         EntryOperation::Insert,
-        &test_row.category,
-        &test_row.name,
-        Some(&test_row.value),
-        Some(test_row.tags.as_slice()),
+        &test_rows[2].category,
+        &test_rows[2].name,
+        Some(&test_rows[2].value),
+        None,
         None,
     )
     .await
     .expect(ERR_INSERT);
 
-    drop(conn);
+    conn.savepoint("sp", SavepointOperation::Release)
+        .await
+        .expect("Error releasing savepoint");
+    let err = conn
+        .savepoint("sp", SavepointOperation::Release)
+        .await
+        .expect_err(ERR_REQ_ERR);
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+
+    conn.close(true).await.expect(ERR_COMMIT);
 
     let mut conn = db.session(None, false).expect(ERR_SESSION);
+    let mut names = conn
+        .fetch_all(Some(EntryKind::Item), Some("category"), None, None, false)
+        .await
+        .expect(ERR_FETCH_ALL)
+        .into_iter()
+        .map(|entry| entry.name)
+        .collect::<Vec<_>>();
+    names.sort();
+    assert_eq!(names, vec!["first", "third"]);
+}
 
-    let row = conn
-        .fetch(EntryKind::Item, &test_row.category, &test_row.name, false)
+pub async fn db_txn_isolation(db: AnyBackend) {
+    let levels = [
+        IsolationLevel::ReadCommitted,
+        IsolationLevel::RepeatableRead,
+        IsolationLevel::Serializable,
+    ];
+
+    for (idx, level) in levels.iter().enumerate() {
+        let name = format!("name-{}", idx);
+        let mut conn = db.transaction(None, *level).expect(ERR_TRANSACTION);
+        assert!(conn
+            .fetch(EntryKind::Item, "category", &name, true)
+            .await
+            .expect(ERR_FETCH)
+            .is_none());
+        conn.update(
+            EntryKind::Item,
+            EntryOperation::Insert,
+            "category",
+            &name,
+            Some(b"value"),
+            None,
+            None,
+        )
         .await
-        .expect(ERR_FETCH);
-    assert_eq!(row, Some(test_row));
+        .expect(ERR_INSERT);
+        conn.close(true).await.expect(ERR_COMMIT);
+    }
+
+    let mut conn = db.session(None, false).expect(ERR_SESSION);
+    let count = conn
+        .count(Some(EntryKind::Item), Some("category"), None)
+        .await
+        .expect(ERR_COUNT);
+    assert_eq!(count, levels.len() as i64);
 }
 
-pub async fn db_txn_commit(db: AnyBackend) {
+pub async fn db_session_idle_timeout(db: AnyBackend) {
     let test_row = Entry::new(EntryKind::Item, "category", "name", "value", Vec::new());
 
     let mut conn = db.session(None, true).expect(ERR_TRANSACTION);
-
     conn.update(
         EntryKind::Item,
         EntryOperation::Insert,
         &test_row.category,
         &test_row.name,
         Some(&test_row.value),
-        Some(test_row.tags.as_slice()),
+        None,
         None,
     )
     .await
     .expect(ERR_INSERT);
 
-    conn.close(true).await.expect(ERR_COMMIT);
+    // the transaction is rolled back once the session has been idle
+    sleep(Duration::from_millis(1500)).await;
+    let err = conn
+        .fetch(EntryKind::Item, &test_row.category, &test_row.name, false)
+        .await
+        .expect_err(ERR_REQ_ERR);
+    assert_eq!(err.kind(), ErrorKind::Timeout);
+    let err = conn.close(true).await.expect_err(ERR_REQ_ERR);
+    assert_eq!(err.kind(), ErrorKind::Timeout);
 
+    // a session without a transaction acquires a new connection
     let mut conn = db.session(None, false).expect(ERR_SESSION);
-
+    conn.count(Some(EntryKind::Item), Some(&test_row.category), None)
+        .await
+        .expect(ERR_COUNT);
+    sleep(Duration::from_millis(1500)).await;
     let row = conn
         .fetch(EntryKind::Item, &test_row.category, &test_row.name, false)
         .await
         .expect(ERR_FETCH);
-    assert_eq!(row, Some(test_row));
+    assert!(row.is_none());
+    conn.close(false).await.expect(ERR_COMMIT);
+
+    // a session which remains in use is not affected
+    let mut conn = db.session(None, false).expect(ERR_SESSION);
+    for _ in 0..3 {
+        sleep(Duration::from_millis(500)).await;
+        let row = conn
+            .fetch(EntryKind::Item, &test_row.category, &test_row.name, false)
+            .await
+            .expect(ERR_FETCH);
+        assert!(row.is_none());
+    }
+    conn.close(false).await.expect(ERR_COMMIT);
 }
 
 pub async fn db_txn_fetch_for_update(db: AnyBackend) {
@@ -840,6 +2786,235 @@ pub async fn db_list_profiles(db: AnyBackend) {
     assert_eq!(profs, found);
 }
 
+pub async fn db_profile_details(db: AnyBackend) {
+    let p_new = db.create_profile(None).await.expect(ERR_PROFILE);
+    let mut conn = db.session(Some(p_new.clone()), false).expect(ERR_SESSION);
+    conn.update(
+        EntryKind::Item,
+        EntryOperation::Insert,
+        "category",
+        "name",
+        Some(b"value"),
+        None,
+        None,
+    )
+    .await
+    .expect(ERR_INSERT);
+    drop(conn);
+
+    db.set_profile_metadata(p_new.clone(), Some(b"meta".to_vec()))
+        .await
+        .expect("Error setting profile metadata");
+    let details = db
+        .list_profile_details()
+        .await
+        .expect("Error listing profile details");
+    let found = details
+        .iter()
+        .find(|p| p.name == p_new)
+        .expect("Expected profile");
+    assert_eq!(found.item_count, 1);
+    assert_eq!(found.key_count, 0);
+    assert_eq!(found.metadata.as_deref(), Some(&b"meta"[..]));
+    assert!(found.created.is_some());
+
+    db.set_profile_metadata(p_new.clone(), None)
+        .await
+        .expect("Error removing profile metadata");
+    let details = db.list_profile_details().await.unwrap();
+    let found = details.iter().find(|p| p.name == p_new).unwrap();
+    assert_eq!(found.metadata, None);
+
+    let err = db
+        .set_profile_metadata("missing".to_string(), None)
+        .await
+        .expect_err(ERR_REQ_ERR);
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+}
+
+pub async fn db_profile_limits(db: AnyBackend) {
+    let profile = db.get_active_profile();
+    db.set_profile_limits(
+        profile.clone(),
+        ProfileLimits {
+            max_entries: Some(2),
+            max_value_bytes: None,
+        },
+    )
+    .await
+    .expect("Error setting profile limits");
+    let details = db.list_profile_details().await.unwrap();
+    let found = details.iter().find(|p| p.name == profile).unwrap();
+    assert_eq!(found.limits.max_entries, Some(2));
+
+    let mut conn = db.session(None, false).expect(ERR_SESSION);
+    for name in ["one", "two"] {
+        conn.update(
+            EntryKind::Item,
+            EntryOperation::Insert,
+            "category",
+            name,
+            Some(b"value"),
+            None,
+            None,
+        )
+        .await
+        .expect(ERR_INSERT);
+    }
+    let err = conn
+        .update(
+            EntryKind::Item,
+            EntryOperation::Insert,
+            "category",
+            "three",
+            Some(b"value"),
+            None,
+            None,
+        )
+        .await
+        .expect_err(ERR_REQ_ERR);
+    assert_eq!(err.kind(), ErrorKind::QuotaExceeded);
+
+    // replacing an existing entry is permitted
+    conn.update(
+        EntryKind::Item,
+        EntryOperation::Replace,
+        "category",
+        "two",
+        Some(b"updated"),
+        None,
+        None,
+    )
+    .await
+    .expect(ERR_REPLACE);
+    conn.update(
+        EntryKind::Item,
+        EntryOperation::Remove,
+        "category",
+        "two",
+        None,
+        None,
+        None,
+    )
+    .await
+    .expect("Error removing test row");
+    conn.update(
+        EntryKind::Item,
+        EntryOperation::Insert,
+        "category",
+        "three",
+        Some(b"value"),
+        None,
+        None,
+    )
+    .await
+    .expect(ERR_INSERT);
+    drop(conn);
+
+    db.set_profile_limits(
+        profile.clone(),
+        ProfileLimits {
+            max_entries: None,
+            max_value_bytes: Some(1),
+        },
+    )
+    .await
+    .expect("Error setting profile limits");
+    let mut conn = db.session(None, false).expect(ERR_SESSION);
+    let err = conn
+        .insert_many(
+            &[Entry::new(
+                EntryKind::Item,
+                "category",
+                "four",
+                "value",
+                Vec::new(),
+            )],
+            None,
+        )
+        .await
+        .expect_err(ERR_REQ_ERR);
+    assert_eq!(err.kind(), ErrorKind::QuotaExceeded);
+    drop(conn);
+
+    db.set_profile_limits(profile, ProfileLimits::default())
+        .await
+        .expect("Error removing profile limits");
+    let mut conn = db.session(None, false).expect(ERR_SESSION);
+    conn.update(
+        EntryKind::Item,
+        EntryOperation::Insert,
+        "category",
+        "four",
+        Some(b"value"),
+        None,
+        None,
+    )
+    .await
+    .expect(ERR_INSERT);
+    drop(conn);
+
+    let err = db
+        .set_profile_limits("missing".to_string(), ProfileLimits::default())
+        .await
+        .expect_err(ERR_REQ_ERR);
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+}
+
+pub async fn db_rename_profile(db: AnyBackend) {
+    let p_active = db.get_active_profile();
+    let mut conn = db.session(None, false).expect(ERR_SESSION);
+    conn.update(
+        EntryKind::Item,
+        EntryOperation::Insert,
+        "category",
+        "name",
+        Some(b"value"),
+        None,
+        None,
+    )
+    .await
+    .expect(ERR_INSERT);
+    drop(conn);
+
+    let p_new = db.create_profile(None).await.expect(ERR_PROFILE);
+    let err = db
+        .rename_profile(p_active.clone(), p_new)
+        .await
+        .expect_err(ERR_REQ_ERR);
+    assert_eq!(err.kind(), ErrorKind::Duplicate);
+    let err = db
+        .rename_profile("missing".to_string(), "other".to_string())
+        .await
+        .expect_err(ERR_REQ_ERR);
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+
+    db.rename_profile(p_active.clone(), "renamed".to_string())
+        .await
+        .expect("Error renaming profile");
+    assert_eq!(db.get_active_profile(), "renamed");
+    assert_eq!(db.get_default_profile().await.unwrap(), "renamed");
+    assert!(!db.list_profiles().await.unwrap().contains(&p_active));
+
+    let mut conn = db
+        .session(Some("renamed".to_string()), false)
+        .expect(ERR_SESSION);
+    let row = conn
+        .fetch(EntryKind::Item, "category", "name", false)
+        .await
+        .expect(ERR_FETCH)
+        .expect(ERR_REQ_ROW);
+    assert_eq!(row.value, b"value".to_vec());
+    drop(conn);
+
+    let mut conn = db.session(Some(p_active), false).expect(ERR_SESSION);
+    let err = conn
+        .fetch(EntryKind::Item, "category", "name", false)
+        .await
+        .expect_err(ERR_REQ_ERR);
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+}
+
 pub async fn db_get_set_default_profile(db: AnyBackend) {
     let p_default = db.get_default_profile().await.unwrap();
     let p_new = db.create_profile(None).await.unwrap();
@@ -879,7 +3054,18 @@ pub async fn db_import_scan(db: AnyBackend) {
     let copy = db.create_profile(None).await.expect(ERR_PROFILE);
     let mut copy_conn = db.session(Some(copy.clone()), true).expect(ERR_SESSION);
     let records = db
-        .scan(None, Some(EntryKind::Item), None, None, None, None)
+        .scan(
+            None,
+            Some(EntryKind::Item),
+            None,
+            None,
+            None,
+            None,
+            None,
+            false,
+            None,
+            TagSelection::All,
+        )
         .await
         .expect(ERR_SCAN);
     copy_conn
@@ -889,7 +3075,18 @@ pub async fn db_import_scan(db: AnyBackend) {
     copy_conn.close(true).await.expect(ERR_COMMIT);
 
     let mut scan = db
-        .scan(Some(copy), Some(EntryKind::Item), None, None, None, None)
+        .scan(
+            Some(copy),
+            Some(EntryKind::Item),
+            None,
+            None,
+            None,
+            None,
+            None,
+            false,
+            None,
+            TagSelection::All,
+        )
         .await
         .expect(ERR_SCAN);
 
@@ -2,6 +2,7 @@ use criterion::{criterion_group, criterion_main, Criterion};
 use rand::{distributions::Alphanumeric, Rng};
 
 use aries_askar::{
+    entry::TagSelection,
     future::block_on,
     kms::{KeyAlg, LocalKey},
     Store, StoreKeyMethod,
@@ -93,9 +94,86 @@ fn criterion_benchmarks(c: &mut Criterion) {
     block_on(async { db.close().await.expect(ERR_CLOSE) });
 }
 
+/// Compare scans of a SQLite database file with and without memory-mapped I/O
+fn sqlite_mmap_benchmarks(c: &mut Criterion) {
+    let path = std::env::temp_dir().join(format!(
+        "askar-bench-{}.db",
+        rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(10)
+            .map(char::from)
+            .collect::<String>()
+    ));
+    let path = path.to_str().expect("Invalid temporary path");
+    let pass_key = Store::new_raw_key(Some(&ROOT_SEED)).expect(ERR_RAW_KEY);
+    block_on(async {
+        let db = Store::provision(
+            &format!("sqlite://{path}"),
+            StoreKeyMethod::RawKey,
+            pass_key.as_ref(),
+            None,
+            true,
+        )
+        .await
+        .expect(ERR_OPEN);
+        let mut conn = db.session(None).await.expect(ERR_SESSION);
+        let value = vec![b'x'; 2048];
+        for idx in 0..5_000 {
+            conn.insert("category", &format!("name-{idx}"), &value, None, None)
+                .await
+                .expect("Error inserting row");
+        }
+        drop(conn);
+        db.close().await.expect(ERR_CLOSE);
+    });
+
+    let mut group = c.benchmark_group("sqlite_scan");
+    group.sample_size(20);
+    for mmap_size in [0, 256 * 1024 * 1024] {
+        let db = block_on(Store::open(
+            &format!("sqlite://{path}?mmap_size={mmap_size}"),
+            Some(StoreKeyMethod::RawKey),
+            pass_key.as_ref(),
+            None,
+        ))
+        .expect(ERR_OPEN);
+        group.bench_function(format!("mmap_size={mmap_size}"), |b| {
+            b.iter(|| {
+                block_on(async {
+                    let mut scan = db
+                        .scan(
+                            None,
+                            Some("category".to_string()),
+                            None,
+                            None,
+                            None,
+                            None,
+                            false,
+                            None,
+                            TagSelection::Omit,
+                        )
+                        .await
+                        .expect("Error starting scan");
+                    while scan
+                        .fetch_next()
+                        .await
+                        .expect("Error scanning rows")
+                        .is_some()
+                    {}
+                })
+            });
+        });
+        block_on(async { db.close().await.expect(ERR_CLOSE) });
+    }
+    group.finish();
+
+    block_on(Store::remove(&format!("sqlite://{path}"))).expect("Error removing store");
+}
+
 criterion_group!(
     name = benchmarks;
     config = Criterion::default().sample_size(1_000);
     targets = criterion_benchmarks
 );
-criterion_main!(benchmarks);
+criterion_group!(sqlite_benchmarks, sqlite_mmap_benchmarks);
+criterion_main!(benchmarks, sqlite_benchmarks);
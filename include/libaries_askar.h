@@ -8,11 +8,20 @@
 #include <stdint.h>
 #include <stdlib.h>
 
+typedef size_t KeyScanHandle;
 typedef size_t ScanHandle;
 typedef size_t StoreHandle;
 typedef size_t SessionHandle;
+typedef size_t SubscriptionHandle;
 
 
+/**
+ * The version of the C ABI exposed by the library. This must be incremented
+ * whenever an exported function signature or the layout of a shared struct
+ * changes incompatibly (see `include/README.md`).
+ */
+#define ASKAR_ABI_VERSION 1
+
 enum ErrorCode
 #ifdef __cplusplus
   : int64_t
@@ -27,6 +36,9 @@ enum ErrorCode
   NotFound = 6,
   Unexpected = 7,
   Unsupported = 8,
+  Conflict = 9,
+  QuotaExceeded = 10,
+  Timeout = 11,
   Custom = 100,
 };
 #ifndef __cplusplus
@@ -48,6 +60,11 @@ typedef struct Option_EnabledCallback Option_EnabledCallback;
 
 typedef struct Option_FlushCallback Option_FlushCallback;
 
+typedef struct BufferView {
+  int64_t len;
+  const uint8_t *data;
+} BufferView;
+
 typedef struct SecretBuffer {
   int64_t len;
   uint8_t *data;
@@ -223,6 +240,24 @@ typedef int64_t CallbackId;
 
 typedef void (*LogCallback)(const void *context, int32_t level, const char *target, const char *message, const char *module_path, const char *file, int32_t line);
 
+typedef int64_t (*GenerateCallback)(int64_t context, const char *alg, const char *label, uint8_t *out, int64_t out_len);
+
+typedef int64_t (*DescribeCallback)(int64_t context, const char *reference, uint8_t *out, int64_t out_len);
+
+typedef int64_t (*SignCallback)(int64_t context, const char *reference, const uint8_t *message, int64_t message_len, const char *sig_type, uint8_t *out, int64_t out_len);
+
+typedef int64_t (*KeyExchangeCallback)(int64_t context, const char *reference, const uint8_t *public_, int64_t public_len, uint8_t *out, int64_t out_len);
+
+typedef void (*RecordEmitCallback)(void *sink, const uint8_t *key, int64_t key_len, const uint8_t *value, int64_t value_len);
+
+typedef int64_t (*RecordGetCallback)(int64_t context, const uint8_t *key, int64_t key_len, void *sink, RecordEmitCallback emit);
+
+typedef int64_t (*RecordPutCallback)(int64_t context, const uint8_t *key, int64_t key_len, const uint8_t *value, int64_t value_len);
+
+typedef int64_t (*RecordRemoveCallback)(int64_t context, const uint8_t *key, int64_t key_len);
+
+typedef int64_t (*RecordScanCallback)(int64_t context, const uint8_t *prefix, int64_t prefix_len, void *sink, RecordEmitCallback emit);
+
 typedef struct FfiResultList_String FfiStringList;
 
 typedef struct ArcHandle_FfiStringList {
@@ -255,6 +290,21 @@ ErrorCode askar_entry_list_get_value(EntryListHandle handle,
                                      int32_t index,
                                      struct SecretBuffer *value);
 
+/**
+ * Access the value of an entry without copying it. The view remains valid
+ * until the entry list is freed.
+ */
+ErrorCode askar_entry_list_get_value_view(EntryListHandle handle,
+                                          int32_t index,
+                                          struct BufferView *value);
+
+ErrorCode askar_entry_list_get_version(EntryListHandle handle, int32_t index, int64_t *version);
+
+/**
+ * Get the details of the last error as a JSON object, containing the error
+ * `code` and `message`, along with the `kind` of error, the `backend_code`
+ * and `sqlstate` reported by the database if any, and the list of `causes`
+ */
 ErrorCode askar_get_current_error(const char **error_json_p);
 
 ErrorCode askar_key_aead_decrypt(LocalKeyHandle handle,
@@ -311,6 +361,17 @@ ErrorCode askar_key_derive_ecdh_1pu(FfiStr alg,
                                     int8_t receive,
                                     LocalKeyHandle *out);
 
+ErrorCode askar_key_derive_ecdh_1pu_batch(FfiStr alg,
+                                          LocalKeyHandle ephem_key,
+                                          LocalKeyHandle sender_key,
+                                          const LocalKeyHandle *recip_keys,
+                                          int32_t recip_count,
+                                          struct ByteBuffer alg_id,
+                                          struct ByteBuffer apu,
+                                          struct ByteBuffer apv,
+                                          struct ByteBuffer cc_tag,
+                                          LocalKeyHandle *out);
+
 ErrorCode askar_key_derive_ecdh_es(FfiStr alg,
                                    LocalKeyHandle ephem_key,
                                    LocalKeyHandle recip_key,
@@ -320,14 +381,31 @@ ErrorCode askar_key_derive_ecdh_es(FfiStr alg,
                                    int8_t receive,
                                    LocalKeyHandle *out);
 
+ErrorCode askar_key_derive_ecdh_es_batch(FfiStr alg,
+                                         LocalKeyHandle ephem_key,
+                                         const LocalKeyHandle *recip_keys,
+                                         int32_t recip_count,
+                                         struct ByteBuffer alg_id,
+                                         struct ByteBuffer apu,
+                                         struct ByteBuffer apv,
+                                         LocalKeyHandle *out);
+
 ErrorCode askar_key_entry_list_count(KeyEntryListHandle handle, int32_t *count);
 
 void askar_key_entry_list_free(KeyEntryListHandle handle);
 
+ErrorCode askar_key_entry_list_get_active(KeyEntryListHandle handle,
+                                          int32_t index,
+                                          int8_t *active);
+
 ErrorCode askar_key_entry_list_get_algorithm(KeyEntryListHandle handle,
                                              int32_t index,
                                              const char **alg);
 
+ErrorCode askar_key_entry_list_get_json_metadata(KeyEntryListHandle handle,
+                                                 int32_t index,
+                                                 const char **metadata);
+
 ErrorCode askar_key_entry_list_get_metadata(KeyEntryListHandle handle,
                                             int32_t index,
                                             const char **metadata);
@@ -340,10 +418,23 @@ ErrorCode askar_key_entry_list_get_tags(KeyEntryListHandle handle,
                                         int32_t index,
                                         const char **tags);
 
+ErrorCode askar_key_entry_list_get_version(KeyEntryListHandle handle,
+                                           int32_t index,
+                                           int32_t *version);
+
 ErrorCode askar_key_entry_list_load_local(KeyEntryListHandle handle,
                                           int32_t index,
                                           LocalKeyHandle *out);
 
+ErrorCode askar_key_entry_list_load_public(KeyEntryListHandle handle,
+                                           int32_t index,
+                                           LocalKeyHandle *out);
+
+ErrorCode askar_key_export_wrapped(LocalKeyHandle handle,
+                                  FfiStr recipient_jwk,
+                                  FfiStr alg,
+                                  const char **out);
+
 void askar_key_free(LocalKeyHandle handle);
 
 ErrorCode askar_key_from_jwk(struct ByteBuffer jwk, LocalKeyHandle *out);
@@ -355,6 +446,8 @@ ErrorCode askar_key_from_key_exchange(FfiStr alg,
 
 ErrorCode askar_key_from_public_bytes(FfiStr alg, struct ByteBuffer public_, LocalKeyHandle *out);
 
+ErrorCode askar_key_from_reference(FfiStr reference, LocalKeyHandle *out);
+
 ErrorCode askar_key_from_secret_bytes(FfiStr alg, struct ByteBuffer secret, LocalKeyHandle *out);
 
 ErrorCode askar_key_from_seed(FfiStr alg,
@@ -364,6 +457,11 @@ ErrorCode askar_key_from_seed(FfiStr alg,
 
 ErrorCode askar_key_generate(FfiStr alg, int8_t ephemeral, LocalKeyHandle *out);
 
+ErrorCode askar_key_generate_external(FfiStr scheme,
+                                      FfiStr alg,
+                                      FfiStr label,
+                                      LocalKeyHandle *out);
+
 ErrorCode askar_key_get_algorithm(LocalKeyHandle handle, const char **out);
 
 ErrorCode askar_key_get_ephemeral(LocalKeyHandle handle, int8_t *out);
@@ -374,10 +472,44 @@ ErrorCode askar_key_get_jwk_secret(LocalKeyHandle handle, struct SecretBuffer *o
 
 ErrorCode askar_key_get_jwk_thumbprint(LocalKeyHandle handle, FfiStr alg, const char **out);
 
+ErrorCode askar_key_get_policy(LocalKeyHandle handle, const char **out);
+
 ErrorCode askar_key_get_public_bytes(LocalKeyHandle handle, struct SecretBuffer *out);
 
+ErrorCode askar_key_get_reference(LocalKeyHandle handle, const char **out);
+
 ErrorCode askar_key_get_secret_bytes(LocalKeyHandle handle, struct SecretBuffer *out);
 
+ErrorCode askar_key_import_wrapped(FfiStr jwe, LocalKeyHandle recipient, LocalKeyHandle *out);
+
+ErrorCode askar_key_provider_register(FfiStr scheme,
+                                      int64_t context,
+                                      GenerateCallback generate,
+                                      DescribeCallback algorithm,
+                                      DescribeCallback public_bytes,
+                                      SignCallback sign,
+                                      KeyExchangeCallback key_exchange);
+
+ErrorCode askar_key_provider_unregister(FfiStr scheme);
+
+ErrorCode askar_key_scan_free(KeyScanHandle handle);
+
+ErrorCode askar_key_scan_next(KeyScanHandle handle,
+                              void (*cb)(CallbackId cb_id, ErrorCode err, KeyEntryListHandle results),
+                              CallbackId cb_id);
+
+ErrorCode askar_key_scan_start(StoreHandle handle,
+                               FfiStr profile,
+                               FfiStr alg,
+                               FfiStr thumbprint,
+                               FfiStr tag_filter,
+                               int64_t created_after,
+                               int64_t created_before,
+                               int64_t offset,
+                               int64_t limit,
+                               void (*cb)(CallbackId cb_id, ErrorCode err, KeyScanHandle handle),
+                               CallbackId cb_id);
+
 ErrorCode askar_key_sign_message(LocalKeyHandle handle,
                                  struct ByteBuffer message,
                                  FfiStr sig_type,
@@ -420,6 +552,19 @@ ErrorCode askar_migrate_indy_sdk(FfiStr spec_uri,
                                  void (*cb)(CallbackId cb_id, ErrorCode err),
                                  CallbackId cb_id);
 
+ErrorCode askar_record_store_register(FfiStr scheme,
+                                     int64_t context,
+                                     RecordGetCallback get,
+                                     RecordPutCallback put,
+                                     RecordRemoveCallback remove,
+                                     RecordScanCallback scan);
+
+ErrorCode askar_record_store_unregister(FfiStr scheme);
+
+ErrorCode askar_scan_continuation(ScanHandle handle,
+                                  void (*cb)(CallbackId cb_id, ErrorCode err, const char *token),
+                                  CallbackId cb_id);
+
 ErrorCode askar_scan_free(ScanHandle handle);
 
 ErrorCode askar_scan_next(ScanHandle handle,
@@ -432,9 +577,24 @@ ErrorCode askar_scan_start(StoreHandle handle,
                            FfiStr tag_filter,
                            int64_t offset,
                            int64_t limit,
+                           FfiStr order_by,
+                           int8_t descending,
+                           FfiStr after,
+                           FfiStr tag_names,
                            void (*cb)(CallbackId cb_id, ErrorCode err, ScanHandle handle),
                            CallbackId cb_id);
 
+/**
+ * Deliver each page of scan results to the `page` callback as it is
+ * fetched, until the scan is exhausted or the callback returns a nonzero
+ * value. Each page is provided as a new entry list, which must be freed by
+ * the caller. The `cb` callback is invoked once delivery has finished.
+ */
+ErrorCode askar_scan_stream(ScanHandle handle,
+                            int8_t (*page)(CallbackId cb_id, EntryListHandle results),
+                            void (*cb)(CallbackId cb_id, ErrorCode err),
+                            CallbackId cb_id);
+
 ErrorCode askar_session_close(SessionHandle handle,
                               int8_t commit,
                               void (*cb)(CallbackId cb_id, ErrorCode err),
@@ -446,6 +606,13 @@ ErrorCode askar_session_count(SessionHandle handle,
                               void (*cb)(CallbackId cb_id, ErrorCode err, int64_t count),
                               CallbackId cb_id);
 
+ErrorCode askar_session_count_grouped(SessionHandle handle,
+                                     FfiStr category,
+                                     FfiStr tag_filter,
+                                     FfiStr group_tag,
+                                     void (*cb)(CallbackId cb_id, ErrorCode err, const char *counts),
+                                     CallbackId cb_id);
+
 ErrorCode askar_session_fetch(SessionHandle handle,
                               FfiStr category,
                               FfiStr name,
@@ -465,17 +632,51 @@ ErrorCode askar_session_fetch_all_keys(SessionHandle handle,
                                        FfiStr alg,
                                        FfiStr thumbprint,
                                        FfiStr tag_filter,
+                                       int64_t created_after,
+                                       int64_t created_before,
+                                       FfiStr metadata,
                                        int64_t limit,
                                        int8_t for_update,
                                        void (*cb)(CallbackId cb_id, ErrorCode err, KeyEntryListHandle results),
                                        CallbackId cb_id);
 
+ErrorCode askar_session_fetch_audit_log(SessionHandle handle,
+                                        FfiStr category,
+                                        int64_t since,
+                                        int64_t limit,
+                                        void (*cb)(CallbackId cb_id, ErrorCode err, const char *records),
+                                        CallbackId cb_id);
+
+ErrorCode askar_session_fetch_expiring_keys(SessionHandle handle,
+                                            int64_t expires_before,
+                                            int64_t limit,
+                                            void (*cb)(CallbackId cb_id, ErrorCode err, KeyEntryListHandle results),
+                                            CallbackId cb_id);
+
 ErrorCode askar_session_fetch_key(SessionHandle handle,
                                   FfiStr name,
                                   int8_t for_update,
                                   void (*cb)(CallbackId cb_id, ErrorCode err, KeyEntryListHandle results),
                                   CallbackId cb_id);
 
+ErrorCode askar_session_fetch_key_by_thumbprint(SessionHandle handle,
+                                                FfiStr thumbprint,
+                                                int8_t for_update,
+                                                void (*cb)(CallbackId cb_id, ErrorCode err, KeyEntryListHandle results),
+                                                CallbackId cb_id);
+
+ErrorCode askar_session_fetch_key_versions(SessionHandle handle,
+                                           FfiStr name,
+                                           void (*cb)(CallbackId cb_id, ErrorCode err, KeyEntryListHandle results),
+                                           CallbackId cb_id);
+
+ErrorCode askar_session_fetch_many(SessionHandle handle,
+                                   FfiStr category,
+                                   FfiStr names,
+                                   int8_t for_update,
+                                   void (*cb)(CallbackId cb_id, ErrorCode err, EntryListHandle results),
+                                   CallbackId cb_id);
+
 ErrorCode askar_session_insert_key(SessionHandle handle,
                                    LocalKeyHandle key_handle,
                                    FfiStr name,
@@ -485,23 +686,117 @@ ErrorCode askar_session_insert_key(SessionHandle handle,
                                    void (*cb)(CallbackId cb_id, ErrorCode err),
                                    CallbackId cb_id);
 
+ErrorCode askar_session_insert_key_by_thumbprint(SessionHandle handle,
+                                                LocalKeyHandle key_handle,
+                                                FfiStr metadata,
+                                                FfiStr tags,
+                                                int64_t expiry_ms,
+                                                void (*cb)(CallbackId cb_id, ErrorCode err, const char *name),
+                                                CallbackId cb_id);
+
+ErrorCode askar_session_insert_many(SessionHandle handle,
+                                    FfiStr entries,
+                                    int64_t expiry_ms,
+                                    void (*cb)(CallbackId cb_id, ErrorCode err),
+                                    CallbackId cb_id);
+
+ErrorCode askar_session_load_key(SessionHandle handle,
+                                 FfiStr name,
+                                 void (*cb)(CallbackId cb_id, ErrorCode err, LocalKeyHandle key),
+                                 CallbackId cb_id);
+
+ErrorCode askar_session_move_category(SessionHandle handle,
+                                      FfiStr category,
+                                      FfiStr new_category,
+                                      FfiStr rename_tags,
+                                      void (*cb)(CallbackId cb_id, ErrorCode err, int64_t count),
+                                      CallbackId cb_id);
+
+ErrorCode askar_session_purge(SessionHandle handle,
+                              FfiStr category,
+                              FfiStr name,
+                              void (*cb)(CallbackId cb_id, ErrorCode err),
+                              CallbackId cb_id);
+
+ErrorCode askar_session_purge_all(SessionHandle handle,
+                                  FfiStr category,
+                                  void (*cb)(CallbackId cb_id, ErrorCode err, int64_t removed),
+                                  CallbackId cb_id);
+
 ErrorCode askar_session_remove_all(SessionHandle handle,
                                    FfiStr category,
                                    FfiStr tag_filter,
                                    void (*cb)(CallbackId cb_id, ErrorCode err, int64_t removed),
                                    CallbackId cb_id);
 
+ErrorCode askar_session_remove_all_returning(SessionHandle handle,
+                                             FfiStr category,
+                                             FfiStr tag_filter,
+                                             void (*cb)(CallbackId cb_id, ErrorCode err, const char *removed),
+                                             CallbackId cb_id);
+
 ErrorCode askar_session_remove_key(SessionHandle handle,
                                    FfiStr name,
                                    void (*cb)(CallbackId cb_id, ErrorCode err),
                                    CallbackId cb_id);
 
+ErrorCode askar_session_rename(SessionHandle handle,
+                               FfiStr category,
+                               FfiStr old_name,
+                               FfiStr new_name,
+                               void (*cb)(CallbackId cb_id, ErrorCode err),
+                               CallbackId cb_id);
+
+ErrorCode askar_session_restore(SessionHandle handle,
+                                FfiStr category,
+                                FfiStr name,
+                                void (*cb)(CallbackId cb_id, ErrorCode err),
+                                CallbackId cb_id);
+
+ErrorCode askar_session_rotate_key(SessionHandle handle,
+                                   FfiStr name,
+                                   void (*cb)(CallbackId cb_id, ErrorCode err, KeyEntryListHandle results),
+                                   CallbackId cb_id);
+
+ErrorCode askar_session_savepoint(SessionHandle handle,
+                                  FfiStr name,
+                                  int8_t operation,
+                                  void (*cb)(CallbackId cb_id, ErrorCode err),
+                                  CallbackId cb_id);
+
+ErrorCode askar_session_search(SessionHandle handle,
+                               FfiStr category,
+                               FfiStr text,
+                               FfiStr tag_filter,
+                               int64_t limit,
+                               void (*cb)(CallbackId cb_id, ErrorCode err, EntryListHandle results),
+                               CallbackId cb_id);
+
+ErrorCode askar_session_set_key_json_metadata(SessionHandle handle,
+                                              FfiStr name,
+                                              FfiStr metadata,
+                                              FfiStr indexed,
+                                              void (*cb)(CallbackId cb_id, ErrorCode err),
+                                              CallbackId cb_id);
+
+ErrorCode askar_session_set_key_policy(SessionHandle handle,
+                                       FfiStr name,
+                                       FfiStr policy,
+                                       void (*cb)(CallbackId cb_id, ErrorCode err),
+                                       CallbackId cb_id);
+
 ErrorCode askar_session_start(StoreHandle handle,
                               FfiStr profile,
                               int8_t as_transaction,
                               void (*cb)(CallbackId cb_id, ErrorCode err, SessionHandle handle),
                               CallbackId cb_id);
 
+ErrorCode askar_session_start_transaction(StoreHandle handle,
+                                          FfiStr profile,
+                                          int8_t isolation,
+                                          void (*cb)(CallbackId cb_id, ErrorCode err, SessionHandle handle),
+                                          CallbackId cb_id);
+
 ErrorCode askar_session_update(SessionHandle handle,
                                int8_t operation,
                                FfiStr category,
@@ -512,6 +807,19 @@ ErrorCode askar_session_update(SessionHandle handle,
                                void (*cb)(CallbackId cb_id, ErrorCode err),
                                CallbackId cb_id);
 
+ErrorCode askar_session_update_if(SessionHandle handle,
+                                  int8_t operation,
+                                  FfiStr category,
+                                  FfiStr name,
+                                  struct ByteBuffer value,
+                                  FfiStr tags,
+                                  int64_t expiry_ms,
+                                  struct ByteBuffer value_hash,
+                                  FfiStr expected_tags,
+                                  int64_t expected_version,
+                                  void (*cb)(CallbackId cb_id, ErrorCode err),
+                                  CallbackId cb_id);
+
 ErrorCode askar_session_update_key(SessionHandle handle,
                                    FfiStr name,
                                    FfiStr metadata,
@@ -520,6 +828,22 @@ ErrorCode askar_session_update_key(SessionHandle handle,
                                    void (*cb)(CallbackId cb_id, ErrorCode err),
                                    CallbackId cb_id);
 
+ErrorCode askar_session_update_tags(SessionHandle handle,
+                                    FfiStr category,
+                                    FfiStr name,
+                                    FfiStr add_tags,
+                                    FfiStr remove_tag_names,
+                                    void (*cb)(CallbackId cb_id, ErrorCode err),
+                                    CallbackId cb_id);
+
+/**
+ * Select the method used to run CPU-intensive cryptographic operations such
+ * as store key derivation: `blocking` (the default) uses the blocking thread
+ * pool of the async runtime, `dedicated` uses a separate pool of `threads`
+ * threads, and `inline` runs operations on the calling task.
+ */
+ErrorCode askar_set_crypto_offload(FfiStr mode, int32_t threads);
+
 ErrorCode askar_set_custom_logger(const void *context,
                                   LogCallback log,
                                   struct Option_EnabledCallback enabled,
@@ -528,12 +852,22 @@ ErrorCode askar_set_custom_logger(const void *context,
 
 ErrorCode askar_set_default_logger(void);
 
+/**
+ * Set the log level for individual modules of the custom logger, using the
+ * same syntax as the `RUST_LOG` environment variable (for example
+ * `askar_storage=debug,sqlx=warn`). The filter may be replaced at any time,
+ * and an empty filter removes it, leaving the current maximum log level.
+ */
+ErrorCode askar_set_log_filter(FfiStr filter);
+
 ErrorCode askar_set_max_log_level(int32_t max_level);
 
 ErrorCode askar_store_close(StoreHandle handle,
                             void (*cb)(CallbackId cb_id, ErrorCode err),
                             CallbackId cb_id);
 
+ErrorCode askar_store_combine_key_shares(FfiStr shares, const char **out);
+
 ErrorCode askar_store_copy(StoreHandle handle,
                            FfiStr target_uri,
                            FfiStr key_method,
@@ -542,11 +876,26 @@ ErrorCode askar_store_copy(StoreHandle handle,
                            void (*cb)(CallbackId cb_id, ErrorCode err, StoreHandle handle),
                            CallbackId cb_id);
 
+ErrorCode askar_store_copy_to_store(StoreHandle handle,
+                                    StoreHandle target_handle,
+                                    FfiStr profiles,
+                                    FfiStr categories,
+                                    void (*cb)(CallbackId cb_id, ErrorCode err),
+                                    CallbackId cb_id);
+
 ErrorCode askar_store_create_profile(StoreHandle handle,
                                      FfiStr profile,
                                      void (*cb)(CallbackId cb_id, ErrorCode err, const char *result_p),
                                      CallbackId cb_id);
 
+ErrorCode askar_store_export(StoreHandle handle,
+                             FfiStr path,
+                             FfiStr pass_key,
+                             LocalKeyHandle recipient_key,
+                             FfiStr profiles,
+                             void (*cb)(CallbackId cb_id, ErrorCode err),
+                             CallbackId cb_id);
+
 ErrorCode askar_store_generate_raw_key(struct ByteBuffer seed, const char **out);
 
 ErrorCode askar_store_get_default_profile(StoreHandle handle,
@@ -557,6 +906,19 @@ ErrorCode askar_store_get_profile_name(StoreHandle handle,
                                        void (*cb)(CallbackId cb_id, ErrorCode err, const char *name),
                                        CallbackId cb_id);
 
+ErrorCode askar_store_import(StoreHandle handle,
+                             FfiStr path,
+                             FfiStr pass_key,
+                             LocalKeyHandle recipient_key,
+                             int8_t conflict,
+                             FfiStr categories,
+                             void (*cb)(CallbackId cb_id, ErrorCode err),
+                             CallbackId cb_id);
+
+ErrorCode askar_store_list_profile_details(StoreHandle handle,
+                                           void (*cb)(CallbackId cb_id, ErrorCode err, const char *details),
+                                           CallbackId cb_id);
+
 ErrorCode askar_store_list_profiles(StoreHandle handle,
                                     void (*cb)(CallbackId cb_id, ErrorCode err, StringListHandle results),
                                     CallbackId cb_id);
@@ -568,6 +930,12 @@ ErrorCode askar_store_open(FfiStr spec_uri,
                            void (*cb)(CallbackId cb_id, ErrorCode err, StoreHandle handle),
                            CallbackId cb_id);
 
+ErrorCode askar_store_open_profile(FfiStr spec_uri,
+                                   FfiStr profile,
+                                   FfiStr pass_key,
+                                   void (*cb)(CallbackId cb_id, ErrorCode err, StoreHandle handle),
+                                   CallbackId cb_id);
+
 ErrorCode askar_store_provision(FfiStr spec_uri,
                                 FfiStr key_method,
                                 FfiStr pass_key,
@@ -576,12 +944,29 @@ ErrorCode askar_store_provision(FfiStr spec_uri,
                                 void (*cb)(CallbackId cb_id, ErrorCode err, StoreHandle handle),
                                 CallbackId cb_id);
 
+ErrorCode askar_store_purge_entry_cache(StoreHandle handle,
+                                        void (*cb)(CallbackId cb_id, ErrorCode err),
+                                        CallbackId cb_id);
+
+ErrorCode askar_store_purge_key_cache(StoreHandle handle,
+                                      void (*cb)(CallbackId cb_id, ErrorCode err),
+                                      CallbackId cb_id);
+
 ErrorCode askar_store_rekey(StoreHandle handle,
                             FfiStr key_method,
                             FfiStr pass_key,
                             void (*cb)(CallbackId cb_id, ErrorCode err),
                             CallbackId cb_id);
 
+ErrorCode askar_store_rekey_with_progress(StoreHandle handle,
+                                          FfiStr key_method,
+                                          FfiStr pass_key,
+                                          void (*progress)(CallbackId cb_id,
+                                                           int64_t completed,
+                                                           int64_t total),
+                                          void (*cb)(CallbackId cb_id, ErrorCode err),
+                                          CallbackId cb_id);
+
 ErrorCode askar_store_remove(FfiStr spec_uri,
                              void (*cb)(CallbackId cb_id, ErrorCode err, int8_t),
                              CallbackId cb_id);
@@ -591,21 +976,112 @@ ErrorCode askar_store_remove_profile(StoreHandle handle,
                                      void (*cb)(CallbackId cb_id, ErrorCode err, int8_t removed),
                                      CallbackId cb_id);
 
+ErrorCode askar_store_remove_profile_access_key(StoreHandle handle,
+                                                FfiStr profile,
+                                                void (*cb)(CallbackId cb_id, ErrorCode err),
+                                                CallbackId cb_id);
+
+ErrorCode askar_store_rename_profile(StoreHandle handle,
+                                     FfiStr profile,
+                                     FfiStr new_name,
+                                     void (*cb)(CallbackId cb_id, ErrorCode err),
+                                     CallbackId cb_id);
+
+ErrorCode askar_store_repair(StoreHandle handle,
+                             int8_t dry_run,
+                             void (*cb)(CallbackId cb_id, ErrorCode err, const char *report),
+                             CallbackId cb_id);
+
 ErrorCode askar_store_set_default_profile(StoreHandle handle,
                                           FfiStr profile,
                                           void (*cb)(CallbackId cb_id, ErrorCode err),
                                           CallbackId cb_id);
 
+ErrorCode askar_store_set_entry_cache(StoreHandle handle,
+                                      int64_t capacity,
+                                      int64_t ttl_ms,
+                                      void (*cb)(CallbackId cb_id, ErrorCode err),
+                                      CallbackId cb_id);
+
+ErrorCode askar_store_set_key_cache(StoreHandle handle,
+                                    int64_t ttl_ms,
+                                    void (*cb)(CallbackId cb_id, ErrorCode err),
+                                    CallbackId cb_id);
+
+ErrorCode askar_store_set_profile_access_key(StoreHandle handle,
+                                             FfiStr profile,
+                                             FfiStr key_method,
+                                             FfiStr pass_key,
+                                             void (*cb)(CallbackId cb_id, ErrorCode err),
+                                             CallbackId cb_id);
+
+ErrorCode askar_store_set_profile_limits(StoreHandle handle,
+                                         FfiStr profile,
+                                         int64_t max_entries,
+                                         int64_t max_value_bytes,
+                                         void (*cb)(CallbackId cb_id, ErrorCode err),
+                                         CallbackId cb_id);
+
+ErrorCode askar_store_set_profile_metadata(StoreHandle handle,
+                                           FfiStr profile,
+                                           struct ByteBuffer metadata,
+                                           void (*cb)(CallbackId cb_id, ErrorCode err),
+                                           CallbackId cb_id);
+
+ErrorCode askar_store_snapshot(StoreHandle handle,
+                               FfiStr target_uri,
+                               void (*cb)(CallbackId cb_id, ErrorCode err),
+                               CallbackId cb_id);
+
+ErrorCode askar_store_split_key(FfiStr pass_key, int32_t threshold, int32_t shares, const char **out);
+
+ErrorCode askar_store_upgrade_kdf(StoreHandle handle,
+                                  FfiStr key_method,
+                                  FfiStr pass_key,
+                                  void (*cb)(CallbackId cb_id, ErrorCode err, int8_t upgraded),
+                                  CallbackId cb_id);
+
+ErrorCode askar_store_verify_integrity(StoreHandle handle,
+                                       int8_t quarantine,
+                                       void (*cb)(CallbackId cb_id, ErrorCode err, const char *report),
+                                       CallbackId cb_id);
+
 ErrorCode askar_string_list_count(StringListHandle handle, int32_t *count);
 
 void askar_string_list_free(StringListHandle handle);
 
 ErrorCode askar_string_list_get_item(StringListHandle handle, int32_t index, const char **item);
 
+void askar_string_free_zeroize(char *value);
+
+ErrorCode askar_subscription_free(SubscriptionHandle handle);
+
+ErrorCode askar_subscription_next(SubscriptionHandle handle,
+                                  void (*cb)(CallbackId cb_id,
+                                             ErrorCode err,
+                                             int8_t operation,
+                                             const char *category,
+                                             const char *name),
+                                  CallbackId cb_id);
+
+ErrorCode askar_subscription_start(StoreHandle handle,
+                                   FfiStr profile,
+                                   FfiStr category,
+                                   void (*cb)(CallbackId cb_id,
+                                              ErrorCode err,
+                                              SubscriptionHandle handle),
+                                   CallbackId cb_id);
+
 void askar_terminate(void);
 
 char *askar_version(void);
 
+/**
+ * Get the version of the C ABI, which bindings should compare against the
+ * `ASKAR_ABI_VERSION` they were generated for before using any other function
+ */
+int32_t askar_version_abi(void);
+
 #ifdef __cplusplus
 } // extern "C"
 #endif // __cplusplus
@@ -0,0 +1,587 @@
+//! Command-line tool for the management of Askar stores
+
+use std::error::Error as StdError;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Write};
+use std::process::ExitCode;
+
+use aries_askar::{
+    entry::{Entry, TagFilter, TagSelection},
+    export::{ExportKey, ImportConflict},
+    future::block_on,
+    vectors::generate_test_vectors,
+    PassKey, Store, StoreKeyMethod,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use serde_json::json;
+
+#[cfg(all(feature = "migration", feature = "sqlite"))]
+use aries_askar::migration::{import_credo_export, migrate_indy_sdk_wallet, MigrationReport};
+#[cfg(all(feature = "migration", feature = "sqlite", feature = "postgres"))]
+use aries_askar::migration::{migrate_indy_sdk_postgres_wallet, IndyPostgresWalletScheme};
+
+type Error = Box<dyn StdError + Send + Sync>;
+
+fn cli() -> Command {
+    let cmd = Command::new("askar")
+        .about("Manage Aries Askar stores")
+        .version(env!("CARGO_PKG_VERSION"))
+        .subcommand_required(true)
+        .arg(
+            Arg::new("uri")
+                .long("uri")
+                .env("ASKAR_URI")
+                .global(true)
+                .help("The store URI, such as sqlite://wallet.db or postgres://host/wallet"),
+        )
+        .arg(
+            Arg::new("key-method")
+                .long("key-method")
+                .global(true)
+                .help("The store key method, such as kdf:argon2i or raw"),
+        )
+        .arg(
+            Arg::new("pass-key")
+                .long("pass-key")
+                .env("ASKAR_PASS_KEY")
+                .hide_env_values(true)
+                .global(true)
+                .help("The store pass key"),
+        )
+        .arg(
+            Arg::new("profile")
+                .long("profile")
+                .global(true)
+                .help("The store profile to use in place of the default profile"),
+        )
+        .subcommand(
+            Command::new("provision").about("Create a new store").arg(
+                Arg::new("recreate")
+                    .long("recreate")
+                    .action(ArgAction::SetTrue)
+                    .help("Replace an existing store"),
+            ),
+        )
+        .subcommand(Command::new("check").about("Check that the store can be opened"))
+        .subcommand(Command::new("list-profiles").about("List the profiles of the store"))
+        .subcommand(
+            Command::new("scan")
+                .about("Output the items of a profile as JSON lines")
+                .arg(
+                    Arg::new("category")
+                        .long("category")
+                        .help("The item category"),
+                )
+                .arg(
+                    Arg::new("tag-filter")
+                        .long("tag-filter")
+                        .help("A JSON tag filter for the items"),
+                )
+                .arg(
+                    Arg::new("limit")
+                        .long("limit")
+                        .value_parser(clap::value_parser!(i64))
+                        .help("The maximum number of items to output"),
+                ),
+        )
+        .subcommand(
+            Command::new("explain")
+                .about("Output the query and execution plan used to scan the items of a profile")
+                .arg(
+                    Arg::new("category")
+                        .long("category")
+                        .help("The item category"),
+                )
+                .arg(
+                    Arg::new("tag-filter")
+                        .long("tag-filter")
+                        .help("A JSON tag filter for the items"),
+                ),
+        )
+        .subcommand(
+            Command::new("export")
+                .about("Write an encrypted export of the store")
+                .arg(
+                    Arg::new("output")
+                        .long("output")
+                        .required(true)
+                        .help("The path of the export file"),
+                )
+                .arg(export_passphrase_arg()),
+        )
+        .subcommand(
+            Command::new("import")
+                .about("Import the contents of an encrypted export")
+                .arg(
+                    Arg::new("input")
+                        .long("input")
+                        .required(true)
+                        .help("The path of the export file"),
+                )
+                .arg(export_passphrase_arg())
+                .arg(
+                    Arg::new("on-conflict")
+                        .long("on-conflict")
+                        .value_parser(["fail", "skip", "overwrite"])
+                        .default_value("fail")
+                        .help("The handling of entries which already exist"),
+                ),
+        )
+        .subcommand(
+            Command::new("rekey")
+                .about("Replace the store key")
+                .arg(
+                    Arg::new("new-key-method")
+                        .long("new-key-method")
+                        .help("The new store key method, such as kdf:argon2i or raw"),
+                )
+                .arg(
+                    Arg::new("new-pass-key")
+                        .long("new-pass-key")
+                        .env("ASKAR_NEW_PASS_KEY")
+                        .hide_env_values(true)
+                        .help("The new store pass key"),
+                ),
+        )
+        .subcommand(
+            Command::new("list-keys")
+                .about("List the keys of a profile")
+                .arg(
+                    Arg::new("algorithm")
+                        .long("algorithm")
+                        .help("The key algorithm, such as ed25519"),
+                ),
+        )
+        .subcommand(
+            Command::new("test-vectors")
+                .about("Output known-answer test vectors for the supported algorithms")
+                .arg(
+                    Arg::new("seed")
+                        .long("seed")
+                        .default_value("askar-test-vectors")
+                        .help("The seed for the deterministic random number generator"),
+                ),
+        );
+    #[cfg(all(feature = "migration", feature = "sqlite"))]
+    let cmd = cmd.subcommand(
+        Command::new("migrate-indy-sdk")
+            .about("Provision a new store containing the records of an Indy-SDK SQLite wallet")
+            .arg(
+                Arg::new("wallet")
+                    .long("wallet")
+                    .required(true)
+                    .help("The path of the Indy-SDK wallet database, or a postgres:// URI"),
+            )
+            .arg(
+                Arg::new("wallet-id")
+                    .long("wallet-id")
+                    .help("The wallet identifier within a shared Postgres database"),
+            )
+            .arg(
+                Arg::new("wallet-scheme")
+                    .long("wallet-scheme")
+                    .value_parser([
+                        "DatabasePerWallet",
+                        "MultiWalletSingleTable",
+                        "MultiWalletSingleTableSharedPool",
+                    ])
+                    .default_value("DatabasePerWallet")
+                    .help("The storage scheme of the Postgres storage plugin"),
+            )
+            .arg(
+                Arg::new("wallet-key")
+                    .long("wallet-key")
+                    .env("INDY_WALLET_KEY")
+                    .hide_env_values(true)
+                    .required(true)
+                    .help("The Indy-SDK wallet key"),
+            )
+            .arg(
+                Arg::new("kdf-method")
+                    .long("kdf-method")
+                    .value_parser(["ARGON2I_MOD", "ARGON2I_INT", "RAW"])
+                    .default_value("ARGON2I_MOD")
+                    .help("The key derivation method of the wallet key"),
+            ),
+    );
+    #[cfg(all(feature = "migration", feature = "sqlite"))]
+    let cmd = cmd.subcommand(
+        Command::new("import-credo-export")
+            .about("Provision a new store containing the records of a Credo wallet export")
+            .arg(
+                Arg::new("input")
+                    .long("input")
+                    .required(true)
+                    .help("The path of the wallet export"),
+            )
+            .arg(
+                Arg::new("export-key")
+                    .long("export-key")
+                    .env("CREDO_EXPORT_KEY")
+                    .hide_env_values(true)
+                    .required(true)
+                    .help("The key protecting the export"),
+            )
+            .arg(
+                Arg::new("key-derivation-method")
+                    .long("key-derivation-method")
+                    .value_parser(["ARGON2I_MOD", "ARGON2I_INT", "RAW"])
+                    .default_value("ARGON2I_MOD")
+                    .help("The key derivation method of the export key"),
+            ),
+    );
+    cmd
+}
+
+fn export_passphrase_arg() -> Arg {
+    Arg::new("passphrase")
+        .long("passphrase")
+        .env("ASKAR_EXPORT_PASSPHRASE")
+        .hide_env_values(true)
+        .required(true)
+        .help("The passphrase protecting the export")
+}
+
+fn key_method(args: &ArgMatches, name: &str) -> Result<Option<StoreKeyMethod>, Error> {
+    Ok(args
+        .get_one::<String>(name)
+        .map(|method| StoreKeyMethod::parse_uri(method))
+        .transpose()?)
+}
+
+fn uri(args: &ArgMatches) -> Result<&str, Error> {
+    Ok(args
+        .get_one::<String>("uri")
+        .ok_or("The store URI must be provided using --uri or ASKAR_URI")?)
+}
+
+fn pass_key<'a>(args: &'a ArgMatches, name: &str) -> PassKey<'a> {
+    PassKey::from(args.get_one::<String>(name).map(String::as_str))
+}
+
+fn profile(args: &ArgMatches) -> Option<String> {
+    args.get_one::<String>("profile").cloned()
+}
+
+async fn open(args: &ArgMatches) -> Result<Store, Error> {
+    Ok(Store::open(
+        uri(args)?,
+        key_method(args, "key-method")?,
+        pass_key(args, "pass-key"),
+        profile(args),
+    )
+    .await?)
+}
+
+fn write_json(out: &mut impl Write, value: &serde_json::Value) -> Result<(), Error> {
+    serde_json::to_writer(&mut *out, value)?;
+    out.write_all(b"\n")?;
+    Ok(())
+}
+
+fn entry_json(entry: &Entry) -> serde_json::Value {
+    let tags = entry
+        .tags
+        .iter()
+        .map(|tag| (tag.name().to_string(), json!(tag.value())))
+        .collect::<serde_json::Map<_, _>>();
+    match std::str::from_utf8(&entry.value) {
+        Ok(value) => json!({
+            "category": entry.category,
+            "name": entry.name,
+            "value": value,
+            "tags": tags,
+        }),
+        Err(_) => json!({
+            "category": entry.category,
+            "name": entry.name,
+            "value_base64": STANDARD.encode(&entry.value),
+            "tags": tags,
+        }),
+    }
+}
+
+#[cfg(all(feature = "migration", feature = "sqlite"))]
+async fn migrate_postgres_wallet(
+    args: &ArgMatches,
+    uri: &str,
+    wallet_key: &str,
+    kdf_method: &str,
+    store: &Store,
+) -> Result<MigrationReport, Error> {
+    #[cfg(feature = "postgres")]
+    {
+        let scheme: IndyPostgresWalletScheme =
+            args.get_one::<String>("wallet-scheme").unwrap().parse()?;
+        let wallet_id = match args.get_one::<String>("wallet-id") {
+            Some(wallet_id) => wallet_id.as_str(),
+            None if scheme == IndyPostgresWalletScheme::DatabasePerWallet => "",
+            None => return Err("The wallet identifier must be provided using --wallet-id".into()),
+        };
+        Ok(migrate_indy_sdk_postgres_wallet(
+            uri, scheme, wallet_id, wallet_key, kdf_method, store, None,
+        )
+        .await?)
+    }
+    #[cfg(not(feature = "postgres"))]
+    {
+        let _ = (args, uri, wallet_key, kdf_method, store);
+        Err("Postgres support is not enabled".into())
+    }
+}
+
+async fn run(args: &ArgMatches) -> Result<(), Error> {
+    let mut out = io::stdout().lock();
+    match args.subcommand() {
+        Some(("provision", sub)) => {
+            let store = Store::provision(
+                uri(args)?,
+                key_method(args, "key-method")?.unwrap_or_default(),
+                pass_key(args, "pass-key"),
+                profile(args),
+                sub.get_flag("recreate"),
+            )
+            .await?;
+            store.close().await?;
+        }
+        Some(("check", _)) => {
+            let store = open(args).await?;
+            let default_profile = store.get_default_profile().await?;
+            let profiles = store.list_profiles().await?;
+            write_json(
+                &mut out,
+                &json!({
+                    "default_profile": default_profile,
+                    "profiles": profiles.len(),
+                }),
+            )?;
+            store.close().await?;
+        }
+        Some(("list-profiles", _)) => {
+            let store = open(args).await?;
+            let default_profile = store.get_default_profile().await?;
+            for details in store.list_profile_details().await? {
+                write_json(
+                    &mut out,
+                    &json!({
+                        "name": details.name,
+                        "default": details.name == default_profile,
+                        "created": details.created,
+                        "items": details.item_count,
+                        "keys": details.key_count,
+                    }),
+                )?;
+            }
+            store.close().await?;
+        }
+        Some(("scan", sub)) => {
+            let store = open(args).await?;
+            let tag_filter = sub
+                .get_one::<String>("tag-filter")
+                .map(|filter| filter.parse::<TagFilter>())
+                .transpose()?;
+            let mut scan = store
+                .scan(
+                    profile(args),
+                    sub.get_one::<String>("category").cloned(),
+                    tag_filter,
+                    None,
+                    sub.get_one::<i64>("limit").copied(),
+                    None,
+                    false,
+                    None,
+                    TagSelection::All,
+                )
+                .await?;
+            while let Some(rows) = scan.fetch_next().await? {
+                for entry in rows {
+                    write_json(&mut out, &entry_json(&entry))?;
+                }
+            }
+            drop(scan);
+            store.close().await?;
+        }
+        Some(("explain", sub)) => {
+            let store = open(args).await?;
+            let tag_filter = sub
+                .get_one::<String>("tag-filter")
+                .map(|filter| filter.parse::<TagFilter>())
+                .transpose()?;
+            let mut session = store.session(profile(args)).await?;
+            let plan = session
+                .explain_scan(
+                    sub.get_one::<String>("category").map(String::as_str),
+                    tag_filter,
+                )
+                .await?;
+            drop(session);
+            write_json(
+                &mut out,
+                &json!({
+                    "query": plan.query,
+                    "arguments": plan.arguments,
+                    "plan": plan.plan,
+                }),
+            )?;
+            store.close().await?;
+        }
+        Some(("export", sub)) => {
+            let store = open(args).await?;
+            let file = File::create(sub.get_one::<String>("output").unwrap())?;
+            let profiles = profile(args).map(|profile| vec![profile]);
+            let writer = store
+                .export(
+                    BufWriter::new(file),
+                    ExportKey::Passphrase(sub.get_one::<String>("passphrase").unwrap()),
+                    profiles.as_deref(),
+                )
+                .await?;
+            writer.into_inner().map_err(|err| err.into_error())?;
+            store.close().await?;
+        }
+        Some(("import", sub)) => {
+            let store = open(args).await?;
+            let file = File::open(sub.get_one::<String>("input").unwrap())?;
+            let conflict = match sub.get_one::<String>("on-conflict").map(String::as_str) {
+                Some("skip") => ImportConflict::Skip,
+                Some("overwrite") => ImportConflict::Overwrite,
+                _ => ImportConflict::Fail,
+            };
+            store
+                .import(
+                    BufReader::new(file),
+                    ExportKey::Passphrase(sub.get_one::<String>("passphrase").unwrap()),
+                    conflict,
+                    None,
+                )
+                .await?;
+            store.close().await?;
+        }
+        Some(("rekey", sub)) => {
+            let mut store = open(args).await?;
+            store
+                .rekey(
+                    key_method(sub, "new-key-method")?.unwrap_or_default(),
+                    pass_key(sub, "new-pass-key"),
+                )
+                .await?;
+            store.close().await?;
+        }
+        Some(("list-keys", sub)) => {
+            let store = open(args).await?;
+            let mut scan = store
+                .scan_keys(
+                    profile(args),
+                    sub.get_one::<String>("algorithm").map(String::as_str),
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                )
+                .await?;
+            while let Some(keys) = scan.fetch_next().await? {
+                for key in keys {
+                    write_json(
+                        &mut out,
+                        &json!({
+                            "name": key.name(),
+                            "algorithm": key.algorithm(),
+                            "created": key.created(),
+                            "version": key.version(),
+                            "metadata": key.metadata(),
+                        }),
+                    )?;
+                }
+            }
+            drop(scan);
+            store.close().await?;
+        }
+        Some(("test-vectors", sub)) => {
+            let seed = sub.get_one::<String>("seed").unwrap();
+            let vectors = generate_test_vectors(seed.as_bytes())?;
+            serde_json::to_writer_pretty(&mut out, &vectors)?;
+            out.write_all(b"\n")?;
+        }
+        #[cfg(all(feature = "migration", feature = "sqlite"))]
+        Some(("migrate-indy-sdk", sub)) => {
+            let store = Store::provision(
+                uri(args)?,
+                key_method(args, "key-method")?.unwrap_or_default(),
+                pass_key(args, "pass-key"),
+                profile(args),
+                false,
+            )
+            .await?;
+            let wallet = sub.get_one::<String>("wallet").unwrap();
+            let wallet_key = sub.get_one::<String>("wallet-key").unwrap();
+            let kdf_method = sub.get_one::<String>("kdf-method").unwrap();
+            let report = if wallet.starts_with("postgres:") || wallet.starts_with("postgresql:") {
+                migrate_postgres_wallet(sub, wallet, wallet_key, kdf_method, &store).await
+            } else {
+                migrate_indy_sdk_wallet(wallet, wallet_key, kdf_method, &store, None)
+                    .await
+                    .map_err(Error::from)
+            };
+            store.close().await?;
+            let report = report?;
+            write_json(
+                &mut out,
+                &json!({
+                    "items": report.items,
+                    "keys": report.keys,
+                    "skipped": report.skipped,
+                }),
+            )?;
+        }
+        #[cfg(all(feature = "migration", feature = "sqlite"))]
+        Some(("import-credo-export", sub)) => {
+            let store = Store::provision(
+                uri(args)?,
+                key_method(args, "key-method")?.unwrap_or_default(),
+                pass_key(args, "pass-key"),
+                profile(args),
+                false,
+            )
+            .await?;
+            let input = sub.get_one::<String>("input").unwrap();
+            let export_key = sub.get_one::<String>("export-key").unwrap();
+            let method = sub.get_one::<String>("key-derivation-method").unwrap();
+            let report = import_credo_export(input, export_key, method, &store, None).await;
+            store.close().await?;
+            let report = report?;
+            write_json(
+                &mut out,
+                &json!({
+                    "items": report.items,
+                    "keys": report.keys,
+                    "skipped": report.skipped,
+                }),
+            )?;
+        }
+        _ => unreachable!("subcommand is required"),
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    env_logger::init();
+    let args = cli().get_matches();
+    match block_on(run(&args)) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn cli_definition() {
+        super::cli().debug_assert();
+    }
+}
@@ -0,0 +1,231 @@
+//! A cache of fetched entries shared between the sessions of a store
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::{self, Debug, Formatter},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use crate::entry::Entry;
+
+type CacheKey = (String, String, String);
+
+struct CachedEntry {
+    entry: Entry,
+    expires: Option<Instant>,
+    used: u64,
+}
+
+#[derive(Default)]
+struct CacheState {
+    capacity: usize,
+    ttl: Option<Duration>,
+    generation: u64,
+    counter: u64,
+    entries: HashMap<CacheKey, CachedEntry>,
+    recent: BTreeMap<u64, CacheKey>,
+}
+
+impl CacheState {
+    fn touch(&mut self, cache_key: &CacheKey) {
+        self.counter += 1;
+        let used = self.counter;
+        if let Some(cached) = self.entries.get_mut(cache_key) {
+            self.recent.remove(&cached.used);
+            self.recent.insert(used, cache_key.clone());
+            cached.used = used;
+        }
+    }
+
+    fn remove(&mut self, cache_key: &CacheKey) {
+        if let Some(cached) = self.entries.remove(cache_key) {
+            self.recent.remove(&cached.used);
+        }
+    }
+
+    fn evict(&mut self) {
+        while self.entries.len() > self.capacity {
+            let Some(&used) = self.recent.keys().next() else {
+                break;
+            };
+            if let Some(cache_key) = self.recent.remove(&used) {
+                self.entries.remove(&cache_key);
+            }
+        }
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.recent.clear();
+    }
+}
+
+/// A least-recently-used cache of item entries, indexed by profile, category
+/// and name
+///
+/// The cache is disabled until a capacity is assigned. Each entry is retained
+/// until it is displaced by more recently used entries, its time-to-live
+/// elapses, the entry is modified through a session of the same store, or the
+/// cache is purged.
+///
+/// Every modification advances a generation counter. An entry fetched from
+/// the backend is only added to the cache if no modification has occurred
+/// since the fetch began, so that a slow fetch cannot restore a stale entry.
+#[derive(Default)]
+pub(crate) struct EntryCache(Mutex<CacheState>);
+
+impl EntryCache {
+    /// Enable the cache, retaining up to `capacity` entries for the optional
+    /// time-to-live `ttl`, or disable and purge the cache if `capacity` is zero
+    pub fn configure(&self, capacity: usize, ttl: Option<Duration>) {
+        let mut state = self.0.lock().unwrap();
+        state.capacity = capacity;
+        state.ttl = ttl;
+        state.evict();
+    }
+
+    /// Get the current generation of the cache, to be passed to `insert`
+    pub fn generation(&self) -> u64 {
+        self.0.lock().unwrap().generation
+    }
+
+    /// Look up an unexpired entry in the cache
+    pub fn get(&self, profile: &str, category: &str, name: &str) -> Option<Entry> {
+        let mut state = self.0.lock().unwrap();
+        if state.capacity == 0 {
+            return None;
+        }
+        let cache_key = (profile.to_string(), category.to_string(), name.to_string());
+        match state.entries.get(&cache_key) {
+            Some(cached) if cached.expires.map_or(true, |exp| exp > Instant::now()) => {
+                let entry = cached.entry.clone();
+                state.touch(&cache_key);
+                Some(entry)
+            }
+            Some(_) => {
+                state.remove(&cache_key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// Add a fetched entry to the cache, if it is enabled and no entries have
+    /// been modified since `generation` was read
+    pub fn insert(&self, generation: u64, profile: &str, entry: &Entry) {
+        let mut state = self.0.lock().unwrap();
+        if state.capacity == 0 || state.generation != generation {
+            return;
+        }
+        let cache_key = (
+            profile.to_string(),
+            entry.category.clone(),
+            entry.name.clone(),
+        );
+        state.remove(&cache_key);
+        state.counter += 1;
+        let cached = CachedEntry {
+            entry: entry.clone(),
+            expires: state.ttl.map(|ttl| Instant::now() + ttl),
+            used: state.counter,
+        };
+        state.recent.insert(cached.used, cache_key.clone());
+        state.entries.insert(cache_key, cached);
+        state.evict();
+    }
+
+    /// Remove modified entries from the cache: a single entry if `category`
+    /// and `name` are provided, otherwise all entries in the category or
+    /// profile
+    pub fn invalidate(&self, profile: &str, category: Option<&str>, name: Option<&str>) {
+        let mut state = self.0.lock().unwrap();
+        state.generation += 1;
+        match (category, name) {
+            (Some(category), Some(name)) => {
+                state.remove(&(profile.to_string(), category.to_string(), name.to_string()));
+            }
+            _ => {
+                let removed: Vec<CacheKey> = state
+                    .entries
+                    .keys()
+                    .filter(|(p, c, _)| p == profile && category.map_or(true, |cat| c == cat))
+                    .cloned()
+                    .collect();
+                for cache_key in removed {
+                    state.remove(&cache_key);
+                }
+            }
+        }
+    }
+
+    /// Remove all entries from the cache
+    pub fn purge(&self) {
+        let mut state = self.0.lock().unwrap();
+        state.generation += 1;
+        state.clear();
+    }
+}
+
+impl Debug for EntryCache {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let state = self.0.lock().unwrap();
+        f.debug_struct("EntryCache")
+            .field("capacity", &state.capacity)
+            .field("ttl", &state.ttl)
+            .field("entries", &state.entries.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entry::EntryKind;
+
+    fn entry(category: &str, name: &str) -> Entry {
+        Entry::new(EntryKind::Item, category, name, name.as_bytes(), vec![])
+    }
+
+    #[test]
+    fn evict_least_recently_used() {
+        let cache = EntryCache::default();
+        cache.configure(2, None);
+        let gen = cache.generation();
+        cache.insert(gen, "p", &entry("cat", "a"));
+        cache.insert(gen, "p", &entry("cat", "b"));
+        assert!(cache.get("p", "cat", "a").is_some());
+        cache.insert(gen, "p", &entry("cat", "c"));
+        assert!(cache.get("p", "cat", "a").is_some());
+        assert!(cache.get("p", "cat", "b").is_none());
+        assert!(cache.get("p", "cat", "c").is_some());
+        assert!(cache.get("q", "cat", "a").is_none());
+    }
+
+    #[test]
+    fn invalidate_entries() {
+        let cache = EntryCache::default();
+        cache.configure(10, Some(Duration::from_secs(60)));
+        let gen = cache.generation();
+        cache.insert(gen, "p", &entry("cat", "a"));
+        cache.insert(gen, "p", &entry("cat", "b"));
+        cache.insert(gen, "p", &entry("other", "a"));
+        cache.invalidate("p", Some("cat"), Some("a"));
+        assert!(cache.get("p", "cat", "a").is_none());
+        assert!(cache.get("p", "cat", "b").is_some());
+        cache.invalidate("p", Some("cat"), None);
+        assert!(cache.get("p", "cat", "b").is_none());
+        assert!(cache.get("p", "other", "a").is_some());
+
+        // a fetch which began before the modification is not cached
+        cache.insert(gen, "p", &entry("cat", "a"));
+        assert!(cache.get("p", "cat", "a").is_none());
+    }
+
+    #[test]
+    fn disabled_cache() {
+        let cache = EntryCache::default();
+        cache.insert(cache.generation(), "p", &entry("cat", "a"));
+        assert!(cache.get("p", "cat", "a").is_none());
+    }
+}
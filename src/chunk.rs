@@ -0,0 +1,118 @@
+//! Support for record values stored as a sequence of encrypted chunks
+//!
+//! The value of a chunked record holds a manifest consisting of the magic
+//! bytes `ASKARCHK`, a format version byte and a CBOR body carrying a random
+//! content key, the number of chunks and the total length of the value. Each
+//! chunk is encrypted with the content key using the chunk index as
+//! associated data, so that reordered or missing chunks are detected.
+
+use std::io::{ErrorKind as IoErrorKind, Read};
+
+use crate::{
+    crypto::alg::Chacha20Types,
+    error::Error,
+    kms::{KeyAlg, LocalKey, SecretBytes},
+};
+
+/// The magic bytes identifying a chunk manifest
+const CHUNK_MAGIC: &[u8; 8] = b"ASKARCHK";
+
+/// The current version of the chunk manifest format
+const CHUNK_VERSION: u8 = 1;
+
+/// The algorithm used to encrypt chunks
+const CHUNK_KEY_ALG: KeyAlg = KeyAlg::Chacha20(Chacha20Types::XC20P);
+
+/// The maximum length of the plaintext of a single chunk
+pub(crate) const CHUNK_SIZE: usize = 64 * 1024;
+
+/// The number of chunks fetched from the backend at once
+pub(crate) const CHUNK_FETCH_LIMIT: u32 = 16;
+
+#[derive(Deserialize, Serialize)]
+struct ManifestBody {
+    key: SecretBytes,
+    chunks: u32,
+    length: u64,
+}
+
+/// The description of a chunked record value
+pub(crate) struct ChunkManifest {
+    key: LocalKey,
+    pub chunks: u32,
+    pub length: u64,
+}
+
+impl ChunkManifest {
+    pub fn generate() -> Result<Self, Error> {
+        Ok(Self {
+            key: LocalKey::generate(CHUNK_KEY_ALG, true)?,
+            chunks: 0,
+            length: 0,
+        })
+    }
+
+    pub fn decode(value: &[u8]) -> Result<Self, Error> {
+        if value.len() <= CHUNK_MAGIC.len() || &value[..CHUNK_MAGIC.len()] != CHUNK_MAGIC {
+            return Err(err_msg!(Input, "Entry value is not chunked"));
+        }
+        if value[CHUNK_MAGIC.len()] != CHUNK_VERSION {
+            return Err(err_msg!(
+                Unsupported,
+                "Unsupported chunk manifest version: {}",
+                value[CHUNK_MAGIC.len()]
+            ));
+        }
+        let body: ManifestBody = serde_cbor::from_slice(&value[CHUNK_MAGIC.len() + 1..])
+            .map_err(err_map!(Input, "Error decoding chunk manifest"))?;
+        Ok(Self {
+            key: LocalKey::from_secret_bytes(CHUNK_KEY_ALG, body.key.as_ref())?,
+            chunks: body.chunks,
+            length: body.length,
+        })
+    }
+
+    pub fn encode(&self) -> Result<SecretBytes, Error> {
+        let body = serde_cbor::to_vec(&ManifestBody {
+            key: self.key.to_secret_bytes()?,
+            chunks: self.chunks,
+            length: self.length,
+        })
+        .map_err(err_map!(Unexpected, "Error encoding chunk manifest"))?;
+        let mut value = SecretBytes::with_capacity(CHUNK_MAGIC.len() + 1 + body.len());
+        value.extend_from_slice(CHUNK_MAGIC);
+        value.extend_from_slice(&[CHUNK_VERSION]);
+        value.extend_from_slice(&body);
+        Ok(value)
+    }
+
+    pub fn encrypt_chunk(&self, index: u32, data: &[u8]) -> Result<Vec<u8>, Error> {
+        let enc = self.key.aead_encrypt(data, &[], &index.to_be_bytes())?;
+        Ok(enc.as_ref().to_vec())
+    }
+
+    pub fn decrypt_chunk(&self, index: u32, data: &[u8]) -> Result<SecretBytes, Error> {
+        let nonce_len = self.key.aead_params()?.nonce_length;
+        if data.len() < nonce_len {
+            return Err(err_msg!(Encryption, "Invalid entry chunk"));
+        }
+        let (ciphertext, nonce) = data.split_at(data.len() - nonce_len);
+        self.key
+            .aead_decrypt(ciphertext, nonce, &index.to_be_bytes())
+            .map_err(|_| err_msg!(Encryption, "Error decrypting entry chunk"))
+    }
+}
+
+/// Read up to `buf.len()` bytes, stopping early only at the end of the stream
+pub(crate) fn read_chunk(reader: &mut impl Read, buf: &mut [u8]) -> Result<usize, Error> {
+    let mut pos = 0;
+    while pos < buf.len() {
+        match reader.read(&mut buf[pos..]) {
+            Ok(0) => break,
+            Ok(len) => pos += len,
+            Err(err) if err.kind() == IoErrorKind::Interrupted => (),
+            Err(err) => return Err(err_msg!(Input, "Error reading entry value").with_cause(err)),
+        }
+    }
+    Ok(pos)
+}
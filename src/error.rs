@@ -13,6 +13,9 @@ pub enum ErrorKind {
     /// The store backend was too busy to handle the request
     Busy,
 
+    /// An update failed because the entry was modified concurrently
+    Conflict,
+
     /// A custom error type for external integrations
     Custom,
 
@@ -28,6 +31,12 @@ pub enum ErrorKind {
     /// The requested record was not found
     NotFound,
 
+    /// An insert operation would exceed the storage limits of the profile
+    QuotaExceeded,
+
+    /// A database operation exceeded the configured statement timeout
+    Timeout,
+
     /// An unexpected error occurred
     Unexpected,
 
@@ -41,11 +50,14 @@ impl ErrorKind {
         match self {
             Self::Backend => "Backend error",
             Self::Busy => "Busy",
+            Self::Conflict => "Conflict",
             Self::Custom => "Custom error",
             Self::Duplicate => "Duplicate",
             Self::Encryption => "Encryption error",
             Self::Input => "Input error",
             Self::NotFound => "Not found",
+            Self::QuotaExceeded => "Quota exceeded",
+            Self::Timeout => "Timeout",
             Self::Unexpected => "Unexpected error",
             Self::Unsupported => "Unsupported",
         }
@@ -85,6 +97,17 @@ impl Error {
         self.message.as_deref()
     }
 
+    /// Accessor for the error code reported by the database, if any. This is
+    /// the SQLSTATE for Postgres and the extended result code for SQLite.
+    pub fn backend_code(&self) -> Option<String> {
+        crate::storage::database_error_code(self.source()?).map(|(code, _)| code)
+    }
+
+    /// Accessor for the SQLSTATE reported by the database, if any
+    pub fn sqlstate(&self) -> Option<String> {
+        crate::storage::database_error_code(self.source()?).and_then(|(_, sqlstate)| sqlstate)
+    }
+
     pub(crate) fn with_cause<T: Into<Box<dyn StdError + Send + Sync + 'static>>>(
         mut self,
         err: T,
@@ -155,11 +178,14 @@ impl From<StorageError> for Error {
         let kind = match kind {
             StorageErrorKind::Backend => ErrorKind::Backend,
             StorageErrorKind::Busy => ErrorKind::Busy,
+            StorageErrorKind::Conflict => ErrorKind::Conflict,
             StorageErrorKind::Custom => ErrorKind::Custom,
             StorageErrorKind::Duplicate => ErrorKind::Duplicate,
             StorageErrorKind::Encryption => ErrorKind::Encryption,
             StorageErrorKind::Input => ErrorKind::Input,
             StorageErrorKind::NotFound => ErrorKind::NotFound,
+            StorageErrorKind::QuotaExceeded => ErrorKind::QuotaExceeded,
+            StorageErrorKind::Timeout => ErrorKind::Timeout,
             StorageErrorKind::Unexpected => ErrorKind::Unexpected,
             StorageErrorKind::Unsupported => ErrorKind::Unsupported,
         };
@@ -0,0 +1,444 @@
+//! Portable encrypted export format for store contents
+//!
+//! An export begins with the magic bytes `ASKAREXP` and a format version
+//! byte, followed by a length-prefixed CBOR header describing how the content
+//! key is protected. The remainder of the stream is a sequence of
+//! length-prefixed records, each encrypted with the content key using the
+//! record index as associated data. Each profile record is followed by the
+//! entries of that profile, and a final record marks the end of the export so
+//! that truncation can be detected.
+
+use std::{
+    io::{ErrorKind as IoErrorKind, Read, Write},
+    sync::Arc,
+};
+
+use crate::{
+    crypto::{
+        alg::Chacha20Types,
+        kdf::{
+            argon2::{Argon2, PARAMS_MODERATE, SALT_LENGTH},
+            KeyDerivation,
+        },
+        random::fill_random,
+    },
+    entry::{Entry, EntryKind, EntryTag},
+    error::Error,
+    future::unblock_crypto_chunks,
+    kms::{crypto_box_seal, crypto_box_seal_open, KeyAlg, LocalKey, SecretBytes},
+};
+
+/// The magic bytes identifying an export stream
+const EXPORT_MAGIC: &[u8; 8] = b"ASKAREXP";
+
+/// The current version of the export format
+pub const EXPORT_VERSION: u8 = 1;
+
+/// The maximum accepted length of an encoded header or record
+const MAX_FRAME_LENGTH: usize = 64 * 1024 * 1024;
+
+/// The maximum number of records read from an export stream at once
+const RECORD_BATCH_SIZE: usize = 256;
+
+/// The number of records encrypted or decrypted by each concurrent task
+const RECORD_CHUNK_SIZE: usize = 16;
+
+/// The algorithm used to encrypt export records
+const CONTENT_KEY_ALG: KeyAlg = KeyAlg::Chacha20(Chacha20Types::XC20P);
+
+/// The identifier of the passphrase key derivation parameters
+const PASSPHRASE_LEVEL: &str = "argon2i:mod";
+
+/// The method used to protect the contents of an export
+#[derive(Debug)]
+pub enum ExportKey<'a> {
+    /// Derive the content key from a passphrase
+    Passphrase(&'a str),
+    /// Seal a random content key for an X25519 recipient key. The secret
+    /// key is required to read the export.
+    Recipient(&'a LocalKey),
+}
+
+/// The handling of imported entries which already exist in the store
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImportConflict {
+    /// Abort the import of the profile containing the entry
+    Fail,
+    /// Keep the existing entry
+    Skip,
+    /// Replace the existing entry
+    Overwrite,
+}
+
+#[derive(Deserialize, Serialize)]
+struct ExportHeader {
+    protection: ExportProtection,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(tag = "method", rename_all = "snake_case")]
+enum ExportProtection {
+    Passphrase {
+        level: String,
+        salt: Vec<u8>,
+    },
+    Recipient {
+        recipient: String,
+        sealed_key: Vec<u8>,
+    },
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ExportTag {
+    name: String,
+    value: String,
+    plaintext: bool,
+    #[serde(default)]
+    numeric: bool,
+}
+
+/// A single record of an export stream
+#[derive(Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(crate) enum ExportRecord {
+    Profile {
+        name: String,
+        default: bool,
+    },
+    Entry {
+        kind: u8,
+        category: String,
+        name: String,
+        value: SecretBytes,
+        tags: Vec<ExportTag>,
+    },
+    End {
+        records: u64,
+    },
+}
+
+impl ExportRecord {
+    pub fn from_entry(entry: Entry) -> Self {
+        Self::Entry {
+            kind: entry.kind as u8,
+            category: entry.category,
+            name: entry.name,
+            value: entry.value,
+            tags: entry
+                .tags
+                .into_iter()
+                .map(|tag| match tag {
+                    EntryTag::Encrypted(name, value) => ExportTag {
+                        name,
+                        value,
+                        plaintext: false,
+                        numeric: false,
+                    },
+                    EntryTag::Plaintext(name, value) => ExportTag {
+                        name,
+                        value,
+                        plaintext: true,
+                        numeric: false,
+                    },
+                    EntryTag::Numeric(name, value) => ExportTag {
+                        name,
+                        value,
+                        plaintext: true,
+                        numeric: true,
+                    },
+                })
+                .collect(),
+        }
+    }
+
+    pub fn into_entry(self) -> Result<Option<Entry>, Error> {
+        match self {
+            Self::Entry {
+                kind,
+                category,
+                name,
+                value,
+                tags,
+            } => {
+                let kind = EntryKind::try_from(kind as usize)?;
+                let tags = tags
+                    .into_iter()
+                    .map(|tag| {
+                        if tag.numeric {
+                            EntryTag::Numeric(tag.name, tag.value)
+                        } else if tag.plaintext {
+                            EntryTag::Plaintext(tag.name, tag.value)
+                        } else {
+                            EntryTag::Encrypted(tag.name, tag.value)
+                        }
+                    })
+                    .collect();
+                Ok(Some(Entry::new(kind, category, name, value, tags)))
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+fn derive_passphrase_key(passphrase: &str, salt: &[u8]) -> Result<LocalKey, Error> {
+    let mut key = SecretBytes::new_with(32, |_| ());
+    Argon2::new(passphrase.as_bytes(), salt, PARAMS_MODERATE)?.derive_key_bytes(key.as_mut())?;
+    LocalKey::from_secret_bytes(CONTENT_KEY_ALG, key.as_ref())
+}
+
+fn write_frame(writer: &mut impl Write, data: &[u8]) -> Result<(), Error> {
+    if data.len() > MAX_FRAME_LENGTH {
+        return Err(err_msg!(Input, "Export record exceeds the maximum length"));
+    }
+    writer
+        .write_all(&(data.len() as u32).to_be_bytes())
+        .and_then(|_| writer.write_all(data))
+        .map_err(err_map!(Input, "Error writing export"))
+}
+
+fn read_exact(reader: &mut impl Read, buf: &mut [u8]) -> Result<(), Error> {
+    reader.read_exact(buf).map_err(|err| {
+        if err.kind() == IoErrorKind::UnexpectedEof {
+            err_msg!(Input, "Export stream is truncated")
+        } else {
+            err_msg!(Input, "Error reading export").with_cause(err)
+        }
+    })
+}
+
+/// Read the next frame, returning `None` if the stream ends before the frame
+fn read_frame(reader: &mut impl Read) -> Result<Option<Vec<u8>>, Error> {
+    let mut len = [0u8; 4];
+    loop {
+        match reader.read(&mut len[..1]) {
+            Ok(0) => return Ok(None),
+            Ok(_) => break,
+            Err(err) if err.kind() == IoErrorKind::Interrupted => continue,
+            Err(err) => return Err(err_msg!(Input, "Error reading export").with_cause(err)),
+        }
+    }
+    read_exact(reader, &mut len[1..])?;
+    let len = u32::from_be_bytes(len) as usize;
+    if len > MAX_FRAME_LENGTH {
+        return Err(err_msg!(Input, "Export record exceeds the maximum length"));
+    }
+    let mut data = vec![0u8; len];
+    read_exact(reader, &mut data)?;
+    Ok(Some(data))
+}
+
+fn encrypt_record(key: &LocalKey, index: u64, record: &ExportRecord) -> Result<Vec<u8>, Error> {
+    let data = SecretBytes::from(
+        serde_cbor::to_vec(record).map_err(err_map!(Unexpected, "Error encoding export"))?,
+    );
+    let enc = key.aead_encrypt(data.as_ref(), &[], &index.to_be_bytes())?;
+    Ok(enc.into_vec())
+}
+
+fn decrypt_record(key: &LocalKey, index: u64, frame: &[u8]) -> Result<ExportRecord, Error> {
+    let nonce_len = key.aead_params()?.nonce_length;
+    if frame.len() < nonce_len {
+        return Err(err_msg!(Input, "Invalid export record"));
+    }
+    let (ciphertext, nonce) = frame.split_at(frame.len() - nonce_len);
+    let data = key
+        .aead_decrypt(ciphertext, nonce, &index.to_be_bytes())
+        .map_err(|_| err_msg!(Encryption, "Error decrypting export record"))?;
+    serde_cbor::from_slice(data.as_ref()).map_err(err_map!(Input, "Error decoding export record"))
+}
+
+/// Write an encrypted export to an output stream
+pub(crate) struct ExportWriter<W: Write> {
+    writer: W,
+    key: Arc<LocalKey>,
+    index: u64,
+}
+
+impl<W: Write> ExportWriter<W> {
+    pub fn new(mut writer: W, key: ExportKey<'_>) -> Result<Self, Error> {
+        let (protection, content_key) = match key {
+            ExportKey::Passphrase(passphrase) => {
+                let mut salt = vec![0u8; SALT_LENGTH];
+                fill_random(&mut salt);
+                let content_key = derive_passphrase_key(passphrase, &salt)?;
+                let protection = ExportProtection::Passphrase {
+                    level: PASSPHRASE_LEVEL.to_string(),
+                    salt,
+                };
+                (protection, content_key)
+            }
+            ExportKey::Recipient(recipient) => {
+                let content_key = LocalKey::generate(CONTENT_KEY_ALG, true)?;
+                let sealed_key =
+                    crypto_box_seal(recipient, content_key.to_secret_bytes()?.as_ref())?;
+                let protection = ExportProtection::Recipient {
+                    recipient: recipient.to_jwk_thumbprint(None)?,
+                    sealed_key,
+                };
+                (protection, content_key)
+            }
+        };
+        let header = serde_cbor::to_vec(&ExportHeader { protection })
+            .map_err(err_map!(Unexpected, "Error encoding export header"))?;
+        writer
+            .write_all(EXPORT_MAGIC)
+            .and_then(|_| writer.write_all(&[EXPORT_VERSION]))
+            .map_err(err_map!(Input, "Error writing export"))?;
+        write_frame(&mut writer, &header)?;
+        Ok(Self {
+            writer,
+            key: Arc::new(content_key),
+            index: 0,
+        })
+    }
+
+    pub fn write_record(&mut self, record: &ExportRecord) -> Result<(), Error> {
+        let frame = encrypt_record(&self.key, self.index, record)?;
+        self.index += 1;
+        write_frame(&mut self.writer, &frame)
+    }
+
+    /// Encrypt a batch of records using concurrent tasks, writing the records
+    /// to the output stream in order
+    pub async fn write_records(&mut self, records: Vec<ExportRecord>) -> Result<(), Error> {
+        let start = self.index;
+        let indexed = (start..).zip(records).collect::<Vec<_>>();
+        let key = self.key.clone();
+        let frames = unblock_crypto_chunks(indexed, RECORD_CHUNK_SIZE, move |records| {
+            records
+                .iter()
+                .map(|(index, record)| encrypt_record(&key, *index, record))
+                .collect::<Result<Vec<_>, Error>>()
+        })
+        .await?;
+        self.index = start + frames.len() as u64;
+        for frame in frames {
+            write_frame(&mut self.writer, &frame)?;
+        }
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<W, Error> {
+        self.write_record(&ExportRecord::End {
+            records: self.index,
+        })?;
+        self.writer
+            .flush()
+            .map_err(err_map!(Input, "Error writing export"))?;
+        Ok(self.writer)
+    }
+}
+
+/// Read the records of an encrypted export from an input stream
+pub(crate) struct ExportReader<R: Read> {
+    reader: R,
+    key: Arc<LocalKey>,
+    index: u64,
+    done: bool,
+}
+
+impl<R: Read> ExportReader<R> {
+    pub fn new(mut reader: R, key: ExportKey<'_>) -> Result<Self, Error> {
+        let mut magic = [0u8; 9];
+        read_exact(&mut reader, &mut magic)?;
+        if &magic[..8] != EXPORT_MAGIC {
+            return Err(err_msg!(Input, "Invalid export stream"));
+        }
+        if magic[8] != EXPORT_VERSION {
+            return Err(err_msg!(
+                Unsupported,
+                "Unsupported export format version: {}",
+                magic[8]
+            ));
+        }
+        let header = read_frame(&mut reader)?
+            .ok_or_else(|| err_msg!(Input, "Export stream is truncated"))?;
+        let header: ExportHeader = serde_cbor::from_slice(&header)
+            .map_err(err_map!(Input, "Error decoding export header"))?;
+        let content_key = match (header.protection, key) {
+            (ExportProtection::Passphrase { level, salt }, ExportKey::Passphrase(passphrase)) => {
+                if level != PASSPHRASE_LEVEL {
+                    return Err(err_msg!(
+                        Unsupported,
+                        "Unsupported export key derivation: {}",
+                        level
+                    ));
+                }
+                derive_passphrase_key(passphrase, &salt)?
+            }
+            (
+                ExportProtection::Recipient {
+                    recipient,
+                    sealed_key,
+                },
+                ExportKey::Recipient(secret),
+            ) => {
+                if secret.to_jwk_thumbprint(None)? != recipient {
+                    return Err(err_msg!(
+                        Input,
+                        "Export is not addressed to the provided key"
+                    ));
+                }
+                let key = crypto_box_seal_open(secret, &sealed_key)?;
+                LocalKey::from_secret_bytes(CONTENT_KEY_ALG, key.as_ref())?
+            }
+            (ExportProtection::Passphrase { .. }, _) => {
+                return Err(err_msg!(Input, "Export is protected by a passphrase"));
+            }
+            (ExportProtection::Recipient { .. }, _) => {
+                return Err(err_msg!(Input, "Export is protected by a recipient key"));
+            }
+        };
+        Ok(Self {
+            reader,
+            key: Arc::new(content_key),
+            index: 0,
+            done: false,
+        })
+    }
+
+    /// Read and decrypt the next batch of records using concurrent tasks,
+    /// returning an empty batch after the end of the export
+    pub async fn read_records(&mut self) -> Result<Vec<ExportRecord>, Error> {
+        if self.done {
+            return Ok(Vec::new());
+        }
+        let mut frames = Vec::with_capacity(RECORD_BATCH_SIZE);
+        while frames.len() < RECORD_BATCH_SIZE {
+            match read_frame(&mut self.reader)? {
+                Some(frame) => frames.push((self.index + frames.len() as u64, frame)),
+                None => break,
+            }
+        }
+        let at_end = frames.len() < RECORD_BATCH_SIZE;
+        let key = self.key.clone();
+        let mut records = unblock_crypto_chunks(frames, RECORD_CHUNK_SIZE, move |frames| {
+            frames
+                .iter()
+                .map(|(index, frame)| decrypt_record(&key, *index, frame))
+                .collect::<Result<Vec<_>, Error>>()
+        })
+        .await?;
+        if let Some(pos) = records
+            .iter()
+            .position(|record| matches!(record, ExportRecord::End { .. }))
+        {
+            if pos + 1 != records.len() {
+                return Err(err_msg!(
+                    Input,
+                    "Unexpected export record following the end"
+                ));
+            }
+            if let Some(ExportRecord::End { records: count }) = records.pop() {
+                if count != self.index + pos as u64 {
+                    return Err(err_msg!(Input, "Export record count mismatch"));
+                }
+            }
+            self.done = true;
+        } else if at_end {
+            return Err(err_msg!(Input, "Export stream is truncated"));
+        }
+        self.index += records.len() as u64;
+        Ok(records)
+    }
+}
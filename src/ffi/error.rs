@@ -1,5 +1,6 @@
 use crate::error::{Error, ErrorKind};
 
+use std::error::Error as StdError;
 use std::os::raw::c_char;
 use std::sync::RwLock;
 
@@ -21,6 +22,9 @@ pub enum ErrorCode {
     NotFound = 6,
     Unexpected = 7,
     Unsupported = 8,
+    Conflict = 9,
+    QuotaExceeded = 10,
+    Timeout = 11,
     Custom = 100,
 }
 
@@ -29,11 +33,14 @@ impl From<ErrorKind> for ErrorCode {
         match kind {
             ErrorKind::Backend => ErrorCode::Backend,
             ErrorKind::Busy => ErrorCode::Busy,
+            ErrorKind::Conflict => ErrorCode::Conflict,
             ErrorKind::Custom => ErrorCode::Custom,
             ErrorKind::Duplicate => ErrorCode::Duplicate,
             ErrorKind::Encryption => ErrorCode::Encryption,
             ErrorKind::Input => ErrorCode::Input,
             ErrorKind::NotFound => ErrorCode::NotFound,
+            ErrorKind::QuotaExceeded => ErrorCode::QuotaExceeded,
+            ErrorKind::Timeout => ErrorCode::Timeout,
             ErrorKind::Unexpected => ErrorCode::Unexpected,
             ErrorKind::Unsupported => ErrorCode::Unsupported,
         }
@@ -49,6 +56,9 @@ impl<T> From<Result<T, Error>> for ErrorCode {
     }
 }
 
+/// Get the details of the last error as a JSON object, containing the error
+/// `code` and `message`, along with the `kind` of error, the `backend_code`
+/// and `sqlstate` reported by the database if any, and the list of `causes`
 #[no_mangle]
 pub extern "C" fn askar_get_current_error(error_json_p: *mut *const c_char) -> ErrorCode {
     trace!("askar_get_current_error");
@@ -64,12 +74,30 @@ pub fn get_current_error_json() -> String {
     struct ErrorJson {
         code: usize,
         message: String,
+        kind: &'static str,
+        backend_code: Option<String>,
+        sqlstate: Option<String>,
+        causes: Vec<String>,
     }
 
     if let Some(err) = Option::take(&mut *LAST_ERROR.write().unwrap()) {
         let message = err.to_string();
         let code = ErrorCode::from(err.kind()) as usize;
-        serde_json::json!(&ErrorJson { code, message }).to_string()
+        let mut causes = Vec::new();
+        let mut source = err.source();
+        while let Some(cause) = source {
+            causes.push(cause.to_string());
+            source = cause.source();
+        }
+        serde_json::json!(&ErrorJson {
+            code,
+            message,
+            kind: err.kind().as_str(),
+            backend_code: err.backend_code(),
+            sqlstate: err.sqlstate(),
+            causes,
+        })
+        .to_string()
     } else {
         r#"{"code":0,"message":null}"#.to_owned()
     }
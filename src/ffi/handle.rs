@@ -20,6 +20,10 @@ impl<T: Send> ArcHandle<T> {
         Self(results)
     }
 
+    pub fn from_arc(value: Arc<T>) -> Self {
+        Self(Arc::into_raw(value))
+    }
+
     pub fn load(&self) -> Result<Arc<T>, Error> {
         self.validate()?;
         let result = unsafe { mem::ManuallyDrop::new(Arc::from_raw(self.0)) };
@@ -1,4 +1,4 @@
-use std::{os::raw::c_char, str::FromStr};
+use std::{os::raw::c_char, str::FromStr, sync::Arc};
 
 use ffi_support::{rust_string_to_c, ByteBuffer, FfiStr};
 
@@ -7,9 +7,13 @@ use super::{
     secret::{EncryptedBuffer, SecretBuffer},
     ErrorCode,
 };
-use crate::kms::{
-    crypto_box, crypto_box_open, crypto_box_random_nonce, crypto_box_seal, crypto_box_seal_open,
-    derive_key_ecdh_1pu, derive_key_ecdh_es, KeyAlg, LocalKey,
+use crate::{
+    error::Error,
+    kms::{
+        crypto_box, crypto_box_open, crypto_box_random_nonce, crypto_box_seal,
+        crypto_box_seal_open, derive_key_ecdh_1pu, derive_key_ecdh_es, derive_keys_ecdh_1pu,
+        derive_keys_ecdh_es, KeyAlg, LocalKey,
+    },
 };
 
 pub type LocalKeyHandle = ArcHandle<LocalKey>;
@@ -197,6 +201,26 @@ pub extern "C" fn askar_key_get_ephemeral(handle: LocalKeyHandle, out: *mut i8)
     }
 }
 
+#[no_mangle]
+pub extern "C" fn askar_key_get_policy(
+    handle: LocalKeyHandle,
+    out: *mut *const c_char,
+) -> ErrorCode {
+    catch_err! {
+        trace!("Get key policy: {}", handle);
+        check_useful_c_ptr!(out);
+        let key = handle.load()?;
+        if let Some(policy) = key.policy() {
+            let policy = serde_json::to_string(policy)
+                .map_err(err_map!(Unexpected, "Error encoding key policy"))?;
+            unsafe { *out = rust_string_to_c(policy) };
+        } else {
+            unsafe { *out = std::ptr::null() };
+        }
+        Ok(ErrorCode::Success)
+    }
+}
+
 #[no_mangle]
 pub extern "C" fn askar_key_get_jwk_public(
     handle: LocalKeyHandle,
@@ -229,6 +253,42 @@ pub extern "C" fn askar_key_get_jwk_secret(
     }
 }
 
+#[no_mangle]
+pub extern "C" fn askar_key_export_wrapped(
+    handle: LocalKeyHandle,
+    recipient_jwk: FfiStr<'_>,
+    alg: FfiStr<'_>,
+    out: *mut *const c_char,
+) -> ErrorCode {
+    catch_err! {
+        trace!("Export wrapped key: {}", handle);
+        check_useful_c_ptr!(out);
+        let recipient_jwk = recipient_jwk.as_opt_str().ok_or_else(|| err_msg!("No recipient JWK provided"))?;
+        let alg = KeyAlg::from_str(alg.as_opt_str().unwrap_or_default())?;
+        let key = handle.load()?;
+        let jwe = key.export_wrapped(recipient_jwk, alg)?;
+        unsafe { *out = rust_string_to_c(jwe) };
+        Ok(ErrorCode::Success)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn askar_key_import_wrapped(
+    jwe: FfiStr<'_>,
+    recipient: LocalKeyHandle,
+    out: *mut LocalKeyHandle,
+) -> ErrorCode {
+    catch_err! {
+        trace!("Import wrapped key: {}", recipient);
+        check_useful_c_ptr!(out);
+        let jwe = jwe.as_opt_str().ok_or_else(|| err_msg!("No JWE provided"))?;
+        let recipient = recipient.load()?;
+        let key = LocalKey::import_wrapped(jwe, &recipient)?;
+        unsafe { *out = LocalKeyHandle::create(key) };
+        Ok(ErrorCode::Success)
+    }
+}
+
 #[no_mangle]
 pub extern "C" fn askar_key_get_jwk_thumbprint(
     handle: LocalKeyHandle,
@@ -569,3 +629,94 @@ pub extern "C" fn askar_key_derive_ecdh_1pu(
         Ok(ErrorCode::Success)
     }
 }
+
+#[no_mangle]
+pub extern "C" fn askar_key_derive_ecdh_es_batch(
+    alg: FfiStr<'_>,
+    ephem_key: LocalKeyHandle,
+    recip_keys: *const LocalKeyHandle,
+    recip_count: i32,
+    alg_id: ByteBuffer,
+    apu: ByteBuffer,
+    apv: ByteBuffer,
+    out: *mut LocalKeyHandle,
+) -> ErrorCode {
+    catch_err! {
+        let alg = alg.as_opt_str().unwrap_or_default();
+        trace!("ECDH-ES batch: {}, {} recipients", alg, recip_count);
+        check_useful_c_ptr!(out);
+        let alg = KeyAlg::from_str(alg)?;
+        let ephem_key = ephem_key.load()?;
+        let recip_keys = load_key_handles(recip_keys, recip_count)?;
+        let keys = derive_keys_ecdh_es(
+            alg,
+            &ephem_key,
+            &recip_keys.iter().map(AsRef::as_ref).collect::<Vec<_>>(),
+            alg_id.as_slice(),
+            apu.as_slice(),
+            apv.as_slice(),
+        )?;
+        for (idx, key) in keys.into_iter().enumerate() {
+            unsafe { *out.add(idx) = LocalKeyHandle::create(key) };
+        }
+        Ok(ErrorCode::Success)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn askar_key_derive_ecdh_1pu_batch(
+    alg: FfiStr<'_>,
+    ephem_key: LocalKeyHandle,
+    sender_key: LocalKeyHandle,
+    recip_keys: *const LocalKeyHandle,
+    recip_count: i32,
+    alg_id: ByteBuffer,
+    apu: ByteBuffer,
+    apv: ByteBuffer,
+    cc_tag: ByteBuffer,
+    out: *mut LocalKeyHandle,
+) -> ErrorCode {
+    catch_err! {
+        let alg = alg.as_opt_str().unwrap_or_default();
+        trace!("ECDH-1PU batch: {}, {} recipients", alg, recip_count);
+        check_useful_c_ptr!(out);
+        let alg = KeyAlg::from_str(alg)?;
+        let ephem_key = ephem_key.load()?;
+        let sender_key = sender_key.load()?;
+        let recip_keys = load_key_handles(recip_keys, recip_count)?;
+        let keys = derive_keys_ecdh_1pu(
+            alg,
+            &ephem_key,
+            &sender_key,
+            &recip_keys.iter().map(AsRef::as_ref).collect::<Vec<_>>(),
+            alg_id.as_slice(),
+            apu.as_slice(),
+            apv.as_slice(),
+            cc_tag.as_slice(),
+        )?;
+        for (idx, key) in keys.into_iter().enumerate() {
+            unsafe { *out.add(idx) = LocalKeyHandle::create(key) };
+        }
+        Ok(ErrorCode::Success)
+    }
+}
+
+/// Load the keys referenced by an array of `count` key handles
+fn load_key_handles(
+    handles: *const LocalKeyHandle,
+    count: i32,
+) -> Result<Vec<Arc<LocalKey>>, Error> {
+    if count < 0 {
+        return Err(err_msg!("Invalid key handle count"));
+    }
+    if count == 0 {
+        return Ok(Vec::new());
+    }
+    if handles.is_null() {
+        return Err(err_msg!("Invalid pointer for key handles"));
+    }
+    unsafe { std::slice::from_raw_parts(handles, count as usize) }
+        .iter()
+        .map(LocalKeyHandle::load)
+        .collect()
+}
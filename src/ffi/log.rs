@@ -2,7 +2,10 @@ use std::ffi::CString;
 use std::os::raw::{c_char, c_void};
 use std::ptr;
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::RwLock;
 
+use env_logger::filter::{Builder as FilterBuilder, Filter};
+use ffi_support::FfiStr;
 use log::{LevelFilter, Metadata, Record};
 use once_cell::sync::OnceCell;
 
@@ -31,6 +34,7 @@ pub struct CustomLogger {
     log: LogCallback,
     flush: Option<FlushCallback>,
     disabled: AtomicBool,
+    filter: RwLock<Option<Filter>>,
 }
 
 impl CustomLogger {
@@ -46,17 +50,30 @@ impl CustomLogger {
             log,
             flush,
             disabled: AtomicBool::new(false),
+            filter: RwLock::new(None),
         }
     }
 
     fn disable(&self) {
         self.disabled.store(true, Ordering::Release);
     }
+
+    fn set_filter(&self, filter: Option<Filter>) {
+        *self.filter.write().unwrap() = filter;
+    }
 }
 
 impl log::Log for CustomLogger {
     fn enabled(&self, metadata: &Metadata<'_>) -> bool {
-        if self.disabled.load(Ordering::Acquire) {
+        if self.disabled.load(Ordering::Acquire)
+            || !self
+                .filter
+                .read()
+                .unwrap()
+                .as_ref()
+                .map(|filter| filter.enabled(metadata))
+                .unwrap_or(true)
+        {
             false
         } else if let Some(enabled_cb) = self.enabled {
             enabled_cb(self.context, metadata.level() as i32) != 0
@@ -151,6 +168,26 @@ pub extern "C" fn askar_set_max_log_level(max_level: i32) -> ErrorCode {
     }
 }
 
+/// Set the log level for individual modules of the custom logger, using the
+/// same syntax as the `RUST_LOG` environment variable (for example
+/// `askar_storage=debug,sqlx=warn`). The filter may be replaced at any time,
+/// and an empty filter removes it, leaving the current maximum log level.
+#[no_mangle]
+pub extern "C" fn askar_set_log_filter(filter: FfiStr<'_>) -> ErrorCode {
+    catch_err! {
+        let logger = LOGGER.get().ok_or_else(|| err_msg!(Input, "Custom logger not initialized"))?;
+        match filter.as_opt_str().filter(|spec| !spec.is_empty()) {
+            Some(spec) => {
+                let filter = FilterBuilder::new().parse(spec).build();
+                log::set_max_level(filter.filter());
+                logger.set_filter(Some(filter));
+            }
+            None => logger.set_filter(None),
+        }
+        Ok(ErrorCode::Success)
+    }
+}
+
 fn get_level_filter(max_level: i32) -> Result<LevelFilter, Error> {
     Ok(match max_level {
         -1 => {
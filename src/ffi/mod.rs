@@ -2,7 +2,7 @@ use std::marker::PhantomData;
 use std::os::raw::c_char;
 use std::time::Duration;
 
-use ffi_support::rust_string_to_c;
+use ffi_support::{rust_string_to_c, FfiStr};
 
 #[cfg(feature = "jemalloc")]
 #[global_allocator]
@@ -10,6 +10,11 @@ static ALLOC: jemallocator::Jemalloc = jemallocator::Jemalloc;
 
 pub static LIB_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+/// The version of the C ABI exposed by the library. This must be incremented
+/// whenever an exported function signature or the layout of a shared struct
+/// changes incompatibly (see `include/README.md`).
+pub const ASKAR_ABI_VERSION: i32 = 1;
+
 #[macro_use]
 mod handle;
 use self::handle::ResourceHandle;
@@ -20,6 +25,8 @@ mod macros;
 mod error;
 mod key;
 mod log;
+mod provider;
+mod record_store;
 mod result_list;
 mod secret;
 mod store;
@@ -65,6 +72,21 @@ impl<T, F: Fn(Result<T, Error>)> Drop for EnsureCallback<T, F> {
     }
 }
 
+/// Select the method used to run CPU-intensive cryptographic operations such
+/// as store key derivation: `blocking` (the default) uses the blocking thread
+/// pool of the async runtime, `dedicated` uses a separate pool of `threads`
+/// threads, and `inline` runs operations on the calling task.
+#[no_mangle]
+pub extern "C" fn askar_set_crypto_offload(mode: FfiStr<'_>, threads: i32) -> ErrorCode {
+    catch_err! {
+        let mode = mode.as_opt_str().unwrap_or("blocking");
+        let threads = usize::try_from(threads)
+            .map_err(|_| err_msg!(Input, "Invalid number of crypto threads"))?;
+        crate::future::set_crypto_offload(crate::future::CryptoOffload::from_option(mode, threads)?)?;
+        Ok(ErrorCode::Success)
+    }
+}
+
 #[no_mangle]
 pub extern "C" fn askar_terminate() {
     crate::future::shutdown(Duration::from_secs(5));
@@ -74,3 +96,10 @@ pub extern "C" fn askar_terminate() {
 pub extern "C" fn askar_version() -> *mut c_char {
     rust_string_to_c(LIB_VERSION.to_owned())
 }
+
+/// Get the version of the C ABI, which bindings should compare against the
+/// `ASKAR_ABI_VERSION` they were generated for before using any other function
+#[no_mangle]
+pub extern "C" fn askar_version_abi() -> i32 {
+    ASKAR_ABI_VERSION
+}
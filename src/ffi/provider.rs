@@ -0,0 +1,271 @@
+use std::{ffi::CString, os::raw::c_char, str::FromStr, sync::Arc};
+
+use ffi_support::{rust_string_to_c, FfiStr};
+
+use super::{key::LocalKeyHandle, ErrorCode};
+use crate::{
+    crypto::buffer::SecretBytes,
+    error::Error,
+    kms::{
+        register_key_provider, unregister_key_provider, ExternalKey, KeyAlg, KeyProvider, LocalKey,
+    },
+};
+
+/// The size of the buffer provided to key provider callbacks for their output
+const OUTPUT_BUFFER_LENGTH: usize = 4096;
+
+/// Write a key reference into `out`, returning its length or -1 on failure
+pub type GenerateCallback = extern "C" fn(
+    context: i64,
+    alg: *const c_char,
+    label: *const c_char,
+    out: *mut u8,
+    out_len: i64,
+) -> i64;
+
+/// Write the algorithm name or public key bytes for a key reference into
+/// `out`, returning the length or -1 on failure
+pub type DescribeCallback =
+    extern "C" fn(context: i64, reference: *const c_char, out: *mut u8, out_len: i64) -> i64;
+
+/// Write a signature into `out`, returning its length or -1 on failure
+pub type SignCallback = extern "C" fn(
+    context: i64,
+    reference: *const c_char,
+    message: *const u8,
+    message_len: i64,
+    sig_type: *const c_char,
+    out: *mut u8,
+    out_len: i64,
+) -> i64;
+
+/// Write a shared secret into `out`, returning its length or -1 on failure
+pub type KeyExchangeCallback = extern "C" fn(
+    context: i64,
+    reference: *const c_char,
+    public: *const u8,
+    public_len: i64,
+    out: *mut u8,
+    out_len: i64,
+) -> i64;
+
+#[derive(Debug)]
+struct FfiKeyCallbacks {
+    context: i64,
+    generate: Option<GenerateCallback>,
+    algorithm: DescribeCallback,
+    public_bytes: DescribeCallback,
+    sign: Option<SignCallback>,
+    key_exchange: Option<KeyExchangeCallback>,
+}
+
+/// A key provider implemented by the host application through callbacks
+#[derive(Debug)]
+struct FfiKeyProvider(Arc<FfiKeyCallbacks>);
+
+fn c_string(value: &str) -> Result<CString, Error> {
+    CString::new(value).map_err(err_map!("Invalid string value"))
+}
+
+fn read_output(action: &str, f: impl FnOnce(*mut u8, i64) -> i64) -> Result<SecretBytes, Error> {
+    let mut buf = SecretBytes::new_with(OUTPUT_BUFFER_LENGTH, |_| ());
+    let len = f(buf.as_mut().as_mut_ptr(), OUTPUT_BUFFER_LENGTH as i64);
+    if len < 0 || len as usize > OUTPUT_BUFFER_LENGTH {
+        return Err(err_msg!(Backend, "Key provider failed to {}", action));
+    }
+    Ok(SecretBytes::from_slice(&buf.as_ref()[..len as usize]))
+}
+
+impl KeyProvider for FfiKeyProvider {
+    fn generate(&self, alg: KeyAlg, label: Option<&str>) -> Result<Arc<dyn ExternalKey>, Error> {
+        let generate = self
+            .0
+            .generate
+            .ok_or_else(|| err_msg!(Unsupported, "Key provider does not support generation"))?;
+        let alg_str = c_string(alg.as_str())?;
+        let label = label.map(c_string).transpose()?;
+        let reference = read_output("generate a key", |out, out_len| {
+            generate(
+                self.0.context,
+                alg_str.as_ptr(),
+                label.as_ref().map_or(std::ptr::null(), |l| l.as_ptr()),
+                out,
+                out_len,
+            )
+        })?;
+        let reference = String::from_utf8(reference.into_vec())
+            .map_err(err_map!(Backend, "Invalid key reference"))?;
+        self.load(&reference)
+    }
+
+    fn load(&self, reference: &str) -> Result<Arc<dyn ExternalKey>, Error> {
+        let c_reference = c_string(reference)?;
+        let alg = read_output("describe a key", |out, out_len| {
+            (self.0.algorithm)(self.0.context, c_reference.as_ptr(), out, out_len)
+        })?;
+        let alg = std::str::from_utf8(alg.as_ref())
+            .map_err(err_map!(Backend, "Invalid key algorithm"))
+            .and_then(|alg| Ok(KeyAlg::from_str(alg)?))?;
+        let public = read_output("describe a key", |out, out_len| {
+            (self.0.public_bytes)(self.0.context, c_reference.as_ptr(), out, out_len)
+        })?;
+        Ok(Arc::new(FfiExternalKey {
+            callbacks: self.0.clone(),
+            reference: reference.to_string(),
+            c_reference,
+            alg,
+            public: public.into_vec(),
+        }))
+    }
+}
+
+#[derive(Debug)]
+struct FfiExternalKey {
+    callbacks: Arc<FfiKeyCallbacks>,
+    reference: String,
+    c_reference: CString,
+    alg: KeyAlg,
+    public: Vec<u8>,
+}
+
+impl ExternalKey for FfiExternalKey {
+    fn reference(&self) -> &str {
+        &self.reference
+    }
+
+    fn algorithm(&self) -> KeyAlg {
+        self.alg
+    }
+
+    fn public_bytes(&self) -> Result<Vec<u8>, Error> {
+        Ok(self.public.clone())
+    }
+
+    fn sign(&self, message: &[u8], sig_type: Option<&str>) -> Result<Vec<u8>, Error> {
+        let sign = self
+            .callbacks
+            .sign
+            .ok_or_else(|| err_msg!(Unsupported, "Key provider does not support signing"))?;
+        let sig_type = sig_type.map(c_string).transpose()?;
+        let sig = read_output("sign a message", |out, out_len| {
+            sign(
+                self.callbacks.context,
+                self.c_reference.as_ptr(),
+                message.as_ptr(),
+                message.len() as i64,
+                sig_type.as_ref().map_or(std::ptr::null(), |s| s.as_ptr()),
+                out,
+                out_len,
+            )
+        })?;
+        Ok(sig.into_vec())
+    }
+
+    fn key_exchange(&self, public: &LocalKey) -> Result<SecretBytes, Error> {
+        let key_exchange = self
+            .callbacks
+            .key_exchange
+            .ok_or_else(|| err_msg!(Unsupported, "Key provider does not support key exchange"))?;
+        let public = public.to_public_bytes()?;
+        read_output("perform a key exchange", |out, out_len| {
+            key_exchange(
+                self.callbacks.context,
+                self.c_reference.as_ptr(),
+                public.as_ref().as_ptr(),
+                public.len() as i64,
+                out,
+                out_len,
+            )
+        })
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn askar_key_provider_register(
+    scheme: FfiStr<'_>,
+    context: i64,
+    generate: Option<GenerateCallback>,
+    algorithm: Option<DescribeCallback>,
+    public_bytes: Option<DescribeCallback>,
+    sign: Option<SignCallback>,
+    key_exchange: Option<KeyExchangeCallback>,
+) -> ErrorCode {
+    catch_err! {
+        let scheme = scheme.as_opt_str().ok_or_else(|| err_msg!("No key provider scheme provided"))?;
+        trace!("Register key provider: {}", scheme);
+        let algorithm = algorithm.ok_or_else(|| err_msg!("No algorithm callback provided"))?;
+        let public_bytes = public_bytes.ok_or_else(|| err_msg!("No public bytes callback provided"))?;
+        let callbacks = Arc::new(FfiKeyCallbacks {
+            context,
+            generate,
+            algorithm,
+            public_bytes,
+            sign,
+            key_exchange,
+        });
+        register_key_provider(scheme, Arc::new(FfiKeyProvider(callbacks)));
+        Ok(ErrorCode::Success)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn askar_key_provider_unregister(scheme: FfiStr<'_>) -> ErrorCode {
+    catch_err! {
+        let scheme = scheme.as_opt_str().ok_or_else(|| err_msg!("No key provider scheme provided"))?;
+        trace!("Unregister key provider: {}", scheme);
+        unregister_key_provider(scheme);
+        Ok(ErrorCode::Success)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn askar_key_generate_external(
+    scheme: FfiStr<'_>,
+    alg: FfiStr<'_>,
+    label: FfiStr<'_>,
+    out: *mut LocalKeyHandle,
+) -> ErrorCode {
+    catch_err! {
+        let scheme = scheme.as_opt_str().ok_or_else(|| err_msg!("No key provider scheme provided"))?;
+        let alg = alg.as_opt_str().unwrap_or_default();
+        trace!("Generate external key: {} {}", scheme, alg);
+        check_useful_c_ptr!(out);
+        let alg = KeyAlg::from_str(alg)?;
+        let key = LocalKey::generate_external(scheme, alg, label.as_opt_str())?;
+        unsafe { *out = LocalKeyHandle::create(key) };
+        Ok(ErrorCode::Success)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn askar_key_from_reference(
+    reference: FfiStr<'_>,
+    out: *mut LocalKeyHandle,
+) -> ErrorCode {
+    catch_err! {
+        let reference = reference.as_opt_str().ok_or_else(|| err_msg!("No key reference provided"))?;
+        trace!("Load external key: {}", reference);
+        check_useful_c_ptr!(out);
+        let key = LocalKey::from_reference(reference)?;
+        unsafe { *out = LocalKeyHandle::create(key) };
+        Ok(ErrorCode::Success)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn askar_key_get_reference(
+    handle: LocalKeyHandle,
+    out: *mut *const c_char,
+) -> ErrorCode {
+    catch_err! {
+        trace!("Get key reference: {}", handle);
+        check_useful_c_ptr!(out);
+        let key = handle.load()?;
+        if let Some(reference) = key.reference() {
+            unsafe { *out = rust_string_to_c(reference) };
+        } else {
+            unsafe { *out = std::ptr::null() };
+        }
+        Ok(ErrorCode::Success)
+    }
+}
@@ -0,0 +1,195 @@
+use std::{os::raw::c_void, sync::Arc};
+
+use ffi_support::FfiStr;
+
+use super::ErrorCode;
+use crate::storage::{
+    backend::custom::{register_record_store, unregister_record_store, Record, RecordStore},
+    Error as StorageError, ErrorKind as StorageErrorKind,
+};
+
+/// Provide a record found by a record store callback
+pub type RecordEmitCallback = extern "C" fn(
+    sink: *mut c_void,
+    key: *const u8,
+    key_len: i64,
+    value: *const u8,
+    value_len: i64,
+);
+
+/// Emit the record for `key` to `sink`, returning 1 if the record was found,
+/// 0 if it was not found or -1 on failure
+pub type RecordGetCallback = extern "C" fn(
+    context: i64,
+    key: *const u8,
+    key_len: i64,
+    sink: *mut c_void,
+    emit: RecordEmitCallback,
+) -> i64;
+
+/// Insert or replace a record, returning 0 on success or -1 on failure
+pub type RecordPutCallback = extern "C" fn(
+    context: i64,
+    key: *const u8,
+    key_len: i64,
+    value: *const u8,
+    value_len: i64,
+) -> i64;
+
+/// Remove a record, returning 1 if the record was found, 0 if it was not
+/// found or -1 on failure
+pub type RecordRemoveCallback = extern "C" fn(context: i64, key: *const u8, key_len: i64) -> i64;
+
+/// Emit every record with a key starting with `prefix` to `sink`, returning
+/// 0 on success or -1 on failure
+pub type RecordScanCallback = extern "C" fn(
+    context: i64,
+    prefix: *const u8,
+    prefix_len: i64,
+    sink: *mut c_void,
+    emit: RecordEmitCallback,
+) -> i64;
+
+/// A record store implemented by the host application through callbacks
+#[derive(Debug)]
+struct FfiRecordStore {
+    context: i64,
+    get: RecordGetCallback,
+    put: RecordPutCallback,
+    remove: RecordRemoveCallback,
+    scan: RecordScanCallback,
+}
+
+type RecordSink = Vec<Record>;
+
+unsafe fn slice_from_raw<'a>(data: *const u8, len: i64) -> &'a [u8] {
+    if data.is_null() || len <= 0 {
+        &[]
+    } else {
+        std::slice::from_raw_parts(data, len as usize)
+    }
+}
+
+extern "C" fn emit_record(
+    sink: *mut c_void,
+    key: *const u8,
+    key_len: i64,
+    value: *const u8,
+    value_len: i64,
+) {
+    if sink.is_null() {
+        return;
+    }
+    let sink = unsafe { &mut *(sink as *mut RecordSink) };
+    let (key, value) = unsafe {
+        (
+            slice_from_raw(key, key_len),
+            slice_from_raw(value, value_len),
+        )
+    };
+    sink.push((key.to_vec(), value.to_vec()));
+}
+
+fn callback_failed(action: &str) -> StorageError {
+    StorageError::from_msg(
+        StorageErrorKind::Backend,
+        format!("Record store failed to {}", action),
+    )
+}
+
+impl RecordStore for FfiRecordStore {
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, StorageError> {
+        let mut sink = RecordSink::new();
+        let result = (self.get)(
+            self.context,
+            key.as_ptr(),
+            key.len() as i64,
+            &mut sink as *mut RecordSink as *mut c_void,
+            emit_record,
+        );
+        match result {
+            0 => Ok(None),
+            1 => sink
+                .pop()
+                .map(|(_, value)| Some(value))
+                .ok_or_else(|| callback_failed("provide a record")),
+            _ => Err(callback_failed("fetch a record")),
+        }
+    }
+
+    fn put(&self, key: &[u8], value: &[u8]) -> Result<(), StorageError> {
+        let result = (self.put)(
+            self.context,
+            key.as_ptr(),
+            key.len() as i64,
+            value.as_ptr(),
+            value.len() as i64,
+        );
+        if result == 0 {
+            Ok(())
+        } else {
+            Err(callback_failed("store a record"))
+        }
+    }
+
+    fn remove(&self, key: &[u8]) -> Result<bool, StorageError> {
+        match (self.remove)(self.context, key.as_ptr(), key.len() as i64) {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(callback_failed("remove a record")),
+        }
+    }
+
+    fn scan(&self, prefix: &[u8]) -> Result<Vec<Record>, StorageError> {
+        let mut sink = RecordSink::new();
+        let result = (self.scan)(
+            self.context,
+            prefix.as_ptr(),
+            prefix.len() as i64,
+            &mut sink as *mut RecordSink as *mut c_void,
+            emit_record,
+        );
+        if result == 0 {
+            Ok(sink)
+        } else {
+            Err(callback_failed("scan records"))
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn askar_record_store_register(
+    scheme: FfiStr<'_>,
+    context: i64,
+    get: Option<RecordGetCallback>,
+    put: Option<RecordPutCallback>,
+    remove: Option<RecordRemoveCallback>,
+    scan: Option<RecordScanCallback>,
+) -> ErrorCode {
+    catch_err! {
+        let scheme = scheme.as_opt_str().ok_or_else(|| err_msg!("No record store scheme provided"))?;
+        trace!("Register record store: {}", scheme);
+        let get = get.ok_or_else(|| err_msg!("No get callback provided"))?;
+        let put = put.ok_or_else(|| err_msg!("No put callback provided"))?;
+        let remove = remove.ok_or_else(|| err_msg!("No remove callback provided"))?;
+        let scan = scan.ok_or_else(|| err_msg!("No scan callback provided"))?;
+        register_record_store(scheme, Arc::new(FfiRecordStore {
+            context,
+            get,
+            put,
+            remove,
+            scan,
+        }));
+        Ok(ErrorCode::Success)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn askar_record_store_unregister(scheme: FfiStr<'_>) -> ErrorCode {
+    catch_err! {
+        let scheme = scheme.as_opt_str().ok_or_else(|| err_msg!("No record store scheme provided"))?;
+        trace!("Unregister record store: {}", scheme);
+        unregister_record_store(scheme);
+        Ok(ErrorCode::Success)
+    }
+}
@@ -1,7 +1,11 @@
 use std::{ffi::CString, os::raw::c_char, ptr};
 
 use super::{
-    handle::ArcHandle, key::LocalKeyHandle, secret::SecretBuffer, tags::EntryTagSet, ErrorCode,
+    handle::ArcHandle,
+    key::LocalKeyHandle,
+    secret::{BufferView, SecretBuffer},
+    tags::EntryTagSet,
+    ErrorCode,
 };
 use crate::{entry::Entry, error::Error, kms::KeyEntry};
 
@@ -108,6 +112,38 @@ pub extern "C" fn askar_entry_list_get_value(
     }
 }
 
+/// Access the value of an entry without copying it. The view remains valid
+/// until the entry list is freed.
+#[no_mangle]
+pub extern "C" fn askar_entry_list_get_value_view(
+    handle: EntryListHandle,
+    index: i32,
+    value: *mut BufferView,
+) -> ErrorCode {
+    catch_err! {
+        check_useful_c_ptr!(value);
+        let results = handle.load()?;
+        let entry = results.get_row(index)?;
+        unsafe { *value = BufferView::from_slice(entry.value.as_ref()); }
+        Ok(ErrorCode::Success)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn askar_entry_list_get_version(
+    handle: EntryListHandle,
+    index: i32,
+    version: *mut i64,
+) -> ErrorCode {
+    catch_err! {
+        check_useful_c_ptr!(version);
+        let results = handle.load()?;
+        let entry = results.get_row(index)?;
+        unsafe { *version = entry.version };
+        Ok(ErrorCode::Success)
+    }
+}
+
 #[no_mangle]
 pub extern "C" fn askar_entry_list_get_tags(
     handle: EntryListHandle,
@@ -155,6 +191,21 @@ pub extern "C" fn askar_key_entry_list_free(handle: KeyEntryListHandle) {
     handle.remove();
 }
 
+#[no_mangle]
+pub extern "C" fn askar_key_entry_list_get_active(
+    handle: KeyEntryListHandle,
+    index: i32,
+    active: *mut i8,
+) -> ErrorCode {
+    catch_err! {
+        check_useful_c_ptr!(active);
+        let results = handle.load()?;
+        let entry = results.get_row(index)?;
+        unsafe { *active = entry.is_active() as i8 };
+        Ok(ErrorCode::Success)
+    }
+}
+
 #[no_mangle]
 pub extern "C" fn askar_key_entry_list_get_algorithm(
     handle: KeyEntryListHandle,
@@ -189,6 +240,25 @@ pub extern "C" fn askar_key_entry_list_get_name(
     }
 }
 
+#[no_mangle]
+pub extern "C" fn askar_key_entry_list_get_json_metadata(
+    handle: KeyEntryListHandle,
+    index: i32,
+    metadata: *mut *const c_char,
+) -> ErrorCode {
+    catch_err! {
+        check_useful_c_ptr!(metadata);
+        let results = handle.load()?;
+        let entry = results.get_row(index)?;
+        if let Some(m) = entry.json_metadata() {
+            unsafe { *metadata = CString::new(m.to_string()).unwrap().into_raw(); }
+        } else {
+            unsafe { *metadata = ptr::null(); }
+        }
+        Ok(ErrorCode::Success)
+    }
+}
+
 #[no_mangle]
 pub extern "C" fn askar_key_entry_list_get_metadata(
     handle: KeyEntryListHandle,
@@ -228,6 +298,21 @@ pub extern "C" fn askar_key_entry_list_get_tags(
     }
 }
 
+#[no_mangle]
+pub extern "C" fn askar_key_entry_list_get_version(
+    handle: KeyEntryListHandle,
+    index: i32,
+    version: *mut i32,
+) -> ErrorCode {
+    catch_err! {
+        check_useful_c_ptr!(version);
+        let results = handle.load()?;
+        let entry = results.get_row(index)?;
+        unsafe { *version = entry.version() as i32 };
+        Ok(ErrorCode::Success)
+    }
+}
+
 #[no_mangle]
 pub extern "C" fn askar_key_entry_list_load_local(
     handle: KeyEntryListHandle,
@@ -245,6 +330,23 @@ pub extern "C" fn askar_key_entry_list_load_local(
     }
 }
 
+#[no_mangle]
+pub extern "C" fn askar_key_entry_list_load_public(
+    handle: KeyEntryListHandle,
+    index: i32,
+    out: *mut LocalKeyHandle,
+) -> ErrorCode {
+    catch_err! {
+        trace!("Load public key");
+        check_useful_c_ptr!(out);
+        let results = handle.load()?;
+        let entry = results.get_row(index)?;
+        let key = entry.load_public_key()?;
+        unsafe { *out = LocalKeyHandle::create(key) };
+        Ok(ErrorCode::Success)
+    }
+}
+
 pub type StringListHandle = ArcHandle<FfiStringList>;
 
 pub type FfiStringList = FfiResultList<String>;
@@ -1,14 +1,57 @@
-use std::{mem, ptr};
+use std::{ffi::CString, mem, os::raw::c_char, ptr};
+
+use zeroize::Zeroize;
 
 use crate::{crypto::buffer::SecretBytes, kms::Encrypted};
 
+/// Free a secret buffer allocated by the library. The contents of the
+/// buffer are zeroized before the memory is released. Each buffer must be
+/// freed exactly once, and must not be accessed afterward. Debug builds
+/// abort when a buffer is freed twice or was not allocated by the library.
 #[no_mangle]
 pub extern "C" fn askar_buffer_free(buffer: SecretBuffer) {
     ffi_support::abort_on_panic::with_abort_on_panic(|| {
-        drop(buffer.destroy_into_secret());
+        let mut secret = buffer.destroy_into_secret();
+        secret.zeroize();
     })
 }
 
+/// Zeroize and free a string allocated by the library which holds secret
+/// data, such as a raw store key
+#[no_mangle]
+pub extern "C" fn askar_string_free_zeroize(value: *mut c_char) {
+    ffi_support::abort_on_panic::with_abort_on_panic(|| {
+        if !value.is_null() {
+            let mut bytes = unsafe { CString::from_raw(value) }.into_bytes();
+            bytes.zeroize();
+        }
+    })
+}
+
+/// Tracking of the secret buffers handed to the caller, used to detect
+/// buffers which are freed more than once in debug builds
+#[cfg(debug_assertions)]
+mod live {
+    use std::{collections::HashSet, sync::Mutex};
+
+    use once_cell::sync::Lazy;
+
+    static LIVE_BUFFERS: Lazy<Mutex<HashSet<usize>>> = Lazy::new(Mutex::default);
+
+    pub fn register(data: *mut u8) {
+        let added = LIVE_BUFFERS.lock().unwrap().insert(data as usize);
+        debug_assert!(added, "secret buffer allocated twice");
+    }
+
+    pub fn release(data: *mut u8) {
+        let found = LIVE_BUFFERS.lock().unwrap().remove(&(data as usize));
+        debug_assert!(
+            found,
+            "secret buffer freed twice or not allocated by the library"
+        );
+    }
+}
+
 // Structure consistent with ffi_support ByteBuffer, but zeroized on drop
 #[derive(Debug)]
 #[repr(C)]
@@ -36,6 +79,11 @@ impl SecretBuffer {
         let mut buf = mem::ManuallyDrop::new(buf.into_vec());
         let len = i64::try_from(buf.len()).expect("secret length exceeds i64::MAX");
         let data = buf.as_mut_ptr();
+        // empty buffers do not own an allocation
+        #[cfg(debug_assertions)]
+        if len > 0 {
+            live::register(data);
+        }
         Self { len, data }
     }
 
@@ -47,11 +95,34 @@ impl SecretBuffer {
                 panic!("found negative length for secret buffer");
             }
             let len = self.len as usize;
+            #[cfg(debug_assertions)]
+            if len > 0 {
+                live::release(self.data);
+            }
             SecretBytes::from(unsafe { Vec::from_raw_parts(self.data, len, len) })
         }
     }
 }
 
+// A borrowed view of a byte string held by the library, which is not freed
+// by the caller and remains valid only as long as the owning resource
+#[derive(Debug)]
+#[repr(C)]
+pub struct BufferView {
+    len: i64,
+    data: *const u8,
+}
+
+impl BufferView {
+    pub fn from_slice(data: &[u8]) -> Self {
+        let len = i64::try_from(data.len()).expect("buffer length exceeds i64::MAX");
+        Self {
+            len,
+            data: data.as_ptr(),
+        }
+    }
+}
+
 // A combined ciphertext and tag value
 #[derive(Debug)]
 #[repr(C)]
@@ -72,3 +143,35 @@ impl EncryptedBuffer {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn secret_buffer_round_trip() {
+        let buf = SecretBuffer::from_secret(&b"secret"[..]);
+        assert_eq!(buf.destroy_into_secret(), &b"secret"[..]);
+        let empty = SecretBuffer::from_secret(SecretBytes::default());
+        assert!(empty.destroy_into_secret().is_empty());
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "secret buffer freed twice")]
+    fn secret_buffer_double_free() {
+        let buf = SecretBuffer::from_secret(&b"secret"[..]);
+        let copy = SecretBuffer {
+            len: buf.len,
+            data: buf.data,
+        };
+        drop(buf.destroy_into_secret());
+        drop(copy.destroy_into_secret());
+    }
+
+    #[test]
+    fn string_free_zeroize() {
+        askar_string_free_zeroize(ffi_support::rust_string_to_c("secret"));
+        askar_string_free_zeroize(ptr::null_mut());
+    }
+}
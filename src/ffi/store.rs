@@ -1,8 +1,19 @@
-use std::{collections::BTreeMap, ffi::CString, os::raw::c_char, ptr, str::FromStr, sync::Arc};
+use std::{
+    collections::BTreeMap,
+    ffi::CString,
+    fs::File,
+    io::{BufReader, BufWriter},
+    os::raw::c_char,
+    ptr,
+    str::FromStr,
+    sync::Arc,
+    time::Duration,
+};
 
 use async_lock::{Mutex as TryMutex, MutexGuardArc as TryMutexGuard, RwLock};
 use ffi_support::{rust_string_to_c, ByteBuffer, FfiStr};
 use once_cell::sync::Lazy;
+use serde::Deserialize;
 
 use super::{
     error::set_last_error,
@@ -14,16 +25,27 @@ use super::{
     CallbackId, EnsureCallback, ErrorCode, ResourceHandle,
 };
 use crate::{
-    entry::{Entry, EntryOperation, Scan, TagFilter},
+    crypto::buffer::HexRepr,
+    entry::{
+        Entry, EntryCount, EntryEvent, EntryKind, EntryOperation, EntryPrecondition, OrderBy, Scan,
+        Subscription, TagFilter, TagSelection,
+    },
     error::Error,
+    export::{ExportKey, ImportConflict},
     ffi::result_list::FfiStringList,
     future::spawn_ok,
-    store::{PassKey, Session, Store, StoreKeyMethod},
+    kms::{KeyEntry, KeyPolicy, KeyScan},
+    store::{
+        AuditRecord, IntegrityReport, IsolationLevel, PassKey, ProfileDetails, ProfileLimits,
+        RekeyProgress, RepairReport, Session, Store, StoreKeyMethod,
+    },
 };
 
 new_sequence_handle!(StoreHandle, FFI_STORE_COUNTER);
 new_sequence_handle!(SessionHandle, FFI_SESSION_COUNTER);
 new_sequence_handle!(ScanHandle, FFI_SCAN_COUNTER);
+new_sequence_handle!(KeyScanHandle, FFI_KEY_SCAN_COUNTER);
+new_sequence_handle!(SubscriptionHandle, FFI_SUBSCRIPTION_COUNTER);
 
 static FFI_STORES: Lazy<RwLock<BTreeMap<StoreHandle, Store>>> =
     Lazy::new(|| RwLock::new(BTreeMap::new()));
@@ -31,6 +53,10 @@ static FFI_SESSIONS: Lazy<StoreResourceMap<SessionHandle, Session>> =
     Lazy::new(StoreResourceMap::new);
 static FFI_SCANS: Lazy<StoreResourceMap<ScanHandle, Scan<'static, Entry>>> =
     Lazy::new(StoreResourceMap::new);
+static FFI_KEY_SCANS: Lazy<StoreResourceMap<KeyScanHandle, KeyScan>> =
+    Lazy::new(StoreResourceMap::new);
+static FFI_SUBSCRIPTIONS: Lazy<StoreResourceMap<SubscriptionHandle, Subscription<'static>>> =
+    Lazy::new(StoreResourceMap::new);
 
 impl StoreHandle {
     pub async fn create(value: Store) -> Self {
@@ -144,6 +170,44 @@ pub extern "C" fn askar_store_generate_raw_key(
     }
 }
 
+#[no_mangle]
+pub extern "C" fn askar_store_split_key(
+    pass_key: FfiStr<'_>,
+    threshold: i32,
+    shares: i32,
+    out: *mut *const c_char,
+) -> ErrorCode {
+    catch_err! {
+        trace!("Split store key");
+        check_useful_c_ptr!(out);
+        let threshold = u8::try_from(threshold).map_err(|_| err_msg!("Invalid share threshold"))?;
+        let shares = u8::try_from(shares).map_err(|_| err_msg!("Invalid share count"))?;
+        let pass_key = PassKey::from(pass_key.as_opt_str());
+        let shares = Store::split_key(pass_key, threshold, shares)?;
+        let shares = shares.iter().map(|share| &**share).collect::<Vec<_>>();
+        let json = serde_json::to_string(&shares).map_err(err_map!(Unexpected, "Error encoding key shares"))?;
+        unsafe { *out = rust_string_to_c(json); }
+        Ok(ErrorCode::Success)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn askar_store_combine_key_shares(
+    shares: FfiStr<'_>,
+    out: *mut *const c_char,
+) -> ErrorCode {
+    catch_err! {
+        trace!("Combine store key shares");
+        check_useful_c_ptr!(out);
+        let shares = shares.as_opt_str().ok_or_else(|| err_msg!("No key shares provided"))?;
+        let shares: Vec<String> = serde_json::from_str(shares).map_err(err_map!("Error parsing key shares"))?;
+        let shares = shares.into_iter().map(PassKey::from).collect::<Vec<_>>();
+        let key = Store::combine_key_shares(&shares)?;
+        unsafe { *out = rust_string_to_c(key.to_string()); }
+        Ok(ErrorCode::Success)
+    }
+}
+
 #[no_mangle]
 pub extern "C" fn askar_store_provision(
     spec_uri: FfiStr<'_>,
@@ -234,6 +298,44 @@ pub extern "C" fn askar_store_open(
     }
 }
 
+#[no_mangle]
+pub extern "C" fn askar_store_open_profile(
+    spec_uri: FfiStr<'_>,
+    profile: FfiStr<'_>,
+    pass_key: FfiStr<'_>,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode, handle: StoreHandle)>,
+    cb_id: CallbackId,
+) -> ErrorCode {
+    catch_err! {
+        trace!("Open store profile");
+        let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
+        let spec_uri = spec_uri.into_opt_string().ok_or_else(|| err_msg!("No store URI provided"))?;
+        let profile = profile.into_opt_string().ok_or_else(|| err_msg!("Profile name not provided"))?;
+        let pass_key = PassKey::from(pass_key.as_opt_str()).into_owned();
+        let cb = EnsureCallback::new(move |result|
+            match result {
+                Ok(sid) => {
+                    debug!("Opened store {}", sid);
+                    cb(cb_id, ErrorCode::Success, sid)
+                }
+                Err(err) => cb(cb_id, set_last_error(Some(err)), StoreHandle::invalid()),
+            }
+        );
+        spawn_ok(async move {
+            let result = async {
+                let store = Store::open_profile(
+                    spec_uri.as_str(),
+                    profile,
+                    pass_key
+                ).await?;
+                Ok(StoreHandle::create(store).await)
+            }.await;
+            cb.resolve(result);
+        });
+        Ok(ErrorCode::Success)
+    }
+}
+
 #[no_mangle]
 pub extern "C" fn askar_store_remove(
     spec_uri: FfiStr<'_>,
@@ -344,26 +446,36 @@ pub extern "C" fn askar_store_list_profiles(
 }
 
 #[no_mangle]
-pub extern "C" fn askar_store_remove_profile(
+pub extern "C" fn askar_store_list_profile_details(
     handle: StoreHandle,
-    profile: FfiStr<'_>,
-    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode, removed: i8)>,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode, details: *const c_char)>,
     cb_id: CallbackId,
 ) -> ErrorCode {
     catch_err! {
-        trace!("Remove profile");
+        trace!("List profile details");
         let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
-        let profile = profile.into_opt_string().ok_or_else(|| err_msg!("Profile name not provided"))?;
-        let cb = EnsureCallback::new(move |result|
+        let cb = EnsureCallback::new(move |result: Result<Vec<ProfileDetails>,Error>|
             match result {
-                Ok(removed) => cb(cb_id, ErrorCode::Success, removed as i8),
-                Err(err) => cb(cb_id, set_last_error(Some(err)), 0),
+                Ok(details) => {
+                    let details = details.into_iter().map(|p| serde_json::json!({
+                        "id": p.id,
+                        "name": p.name,
+                        "created": p.created,
+                        "item_count": p.item_count,
+                        "key_count": p.key_count,
+                        "metadata": p.metadata.map(|m| HexRepr(m).to_string()),
+                        "max_entries": p.limits.max_entries,
+                        "max_value_bytes": p.limits.max_value_bytes,
+                    })).collect::<Vec<_>>();
+                    cb(cb_id, ErrorCode::Success, rust_string_to_c(serde_json::Value::from(details).to_string()))
+                }
+                Err(err) => cb(cb_id, set_last_error(Some(err)), ptr::null()),
             }
         );
         spawn_ok(async move {
             let result = async {
                 let store = handle.load().await?;
-                store.remove_profile(profile).await
+                store.list_profile_details().await
             }.await;
             cb.resolve(result);
         });
@@ -372,25 +484,29 @@ pub extern "C" fn askar_store_remove_profile(
 }
 
 #[no_mangle]
-pub extern "C" fn askar_store_get_default_profile(
+pub extern "C" fn askar_store_set_profile_metadata(
     handle: StoreHandle,
-    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode, profile: *const c_char)>,
+    profile: FfiStr<'_>,
+    metadata: ByteBuffer,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode)>,
     cb_id: CallbackId,
 ) -> ErrorCode {
     catch_err! {
-        trace!("Get default profile");
+        trace!("Set profile metadata");
         let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
-        let cb = EnsureCallback::new(move |result: Result<String, Error>|
+        let profile = profile.into_opt_string().ok_or_else(|| err_msg!("Profile name not provided"))?;
+        // an empty buffer removes the metadata
+        let metadata = Some(metadata.as_slice().to_vec()).filter(|m| !m.is_empty());
+        let cb = EnsureCallback::new(move |result|
             match result {
-                Ok(name) => cb(cb_id, ErrorCode::Success,
-                    CString::new(name.as_str()).unwrap().into_raw() as *const c_char),
-                Err(err) => cb(cb_id, set_last_error(Some(err)), ptr::null()),
+                Ok(_) => cb(cb_id, ErrorCode::Success),
+                Err(err) => cb(cb_id, set_last_error(Some(err))),
             }
         );
         spawn_ok(async move {
             let result = async {
                 let store = handle.load().await?;
-                store.get_default_profile().await
+                store.set_profile_metadata(profile, metadata.as_deref()).await
             }.await;
             cb.resolve(result);
         });
@@ -399,16 +515,23 @@ pub extern "C" fn askar_store_get_default_profile(
 }
 
 #[no_mangle]
-pub extern "C" fn askar_store_set_default_profile(
+pub extern "C" fn askar_store_set_profile_limits(
     handle: StoreHandle,
     profile: FfiStr<'_>,
+    max_entries: i64,
+    max_value_bytes: i64,
     cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode)>,
     cb_id: CallbackId,
 ) -> ErrorCode {
     catch_err! {
-        trace!("Set default profile");
+        trace!("Set profile limits");
         let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
         let profile = profile.into_opt_string().ok_or_else(|| err_msg!("Profile name not provided"))?;
+        // a negative value removes the limit
+        let limits = ProfileLimits {
+            max_entries: Some(max_entries).filter(|m| *m >= 0),
+            max_value_bytes: Some(max_value_bytes).filter(|m| *m >= 0),
+        };
         let cb = EnsureCallback::new(move |result|
             match result {
                 Ok(_) => cb(cb_id, ErrorCode::Success),
@@ -418,7 +541,7 @@ pub extern "C" fn askar_store_set_default_profile(
         spawn_ok(async move {
             let result = async {
                 let store = handle.load().await?;
-                store.set_default_profile(profile).await
+                store.set_profile_limits(profile, limits).await
             }.await;
             cb.resolve(result);
         });
@@ -427,16 +550,18 @@ pub extern "C" fn askar_store_set_default_profile(
 }
 
 #[no_mangle]
-pub extern "C" fn askar_store_rekey(
+pub extern "C" fn askar_store_set_profile_access_key(
     handle: StoreHandle,
+    profile: FfiStr<'_>,
     key_method: FfiStr<'_>,
     pass_key: FfiStr<'_>,
     cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode)>,
     cb_id: CallbackId,
 ) -> ErrorCode {
     catch_err! {
-        trace!("Re-key store");
+        trace!("Set profile access key");
         let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
+        let profile = profile.into_opt_string().ok_or_else(|| err_msg!("Profile name not provided"))?;
         let key_method = match key_method.as_opt_str() {
             Some(method) => StoreKeyMethod::parse_uri(method)?,
             None => StoreKeyMethod::default()
@@ -450,10 +575,8 @@ pub extern "C" fn askar_store_rekey(
         );
         spawn_ok(async move {
             let result = async {
-                let mut store = handle.remove().await?;
-                let result = store.rekey(key_method, pass_key.as_ref()).await;
-                handle.replace(store).await;
-                result
+                let store = handle.load().await?;
+                store.set_profile_access_key(profile, key_method, pass_key).await
             }.await;
             cb.resolve(result);
         });
@@ -462,36 +585,26 @@ pub extern "C" fn askar_store_rekey(
 }
 
 #[no_mangle]
-pub extern "C" fn askar_store_copy(
+pub extern "C" fn askar_store_remove_profile_access_key(
     handle: StoreHandle,
-    target_uri: FfiStr<'_>,
-    key_method: FfiStr<'_>,
-    pass_key: FfiStr<'_>,
-    recreate: i8,
-    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode, handle: StoreHandle)>,
+    profile: FfiStr<'_>,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode)>,
     cb_id: CallbackId,
 ) -> ErrorCode {
     catch_err! {
-        trace!("Copy store");
+        trace!("Remove profile access key");
         let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
-        let target_uri = target_uri.into_opt_string().ok_or_else(|| err_msg!("No target URI provided"))?;
-        let key_method = match key_method.as_opt_str() {
-            Some(method) => StoreKeyMethod::parse_uri(method)?,
-            None => StoreKeyMethod::default()
-        };
-        let pass_key = PassKey::from(pass_key.as_opt_str()).into_owned();
+        let profile = profile.into_opt_string().ok_or_else(|| err_msg!("Profile name not provided"))?;
         let cb = EnsureCallback::new(move |result|
             match result {
-                Ok(handle) => cb(cb_id, ErrorCode::Success, handle),
-                Err(err) => cb(cb_id, set_last_error(Some(err)), StoreHandle::invalid()),
+                Ok(_) => cb(cb_id, ErrorCode::Success),
+                Err(err) => cb(cb_id, set_last_error(Some(err))),
             }
         );
         spawn_ok(async move {
-            let result = async move {
+            let result = async {
                 let store = handle.load().await?;
-                let copied = store.copy_to(target_uri.as_str(), key_method, pass_key.as_ref(), recreate != 0).await?;
-                debug!("Copied store {}", handle);
-                Ok(StoreHandle::create(copied).await)
+                store.remove_profile_access_key(profile).await
             }.await;
             cb.resolve(result);
         });
@@ -500,75 +613,56 @@ pub extern "C" fn askar_store_copy(
 }
 
 #[no_mangle]
-pub extern "C" fn askar_store_close(
+pub extern "C" fn askar_store_rename_profile(
     handle: StoreHandle,
+    profile: FfiStr<'_>,
+    new_name: FfiStr<'_>,
     cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode)>,
     cb_id: CallbackId,
 ) -> ErrorCode {
     catch_err! {
-        trace!("Close store");
-        let cb = cb.map(|cb| {
-            EnsureCallback::new(move |result|
-                match result {
-                    Ok(_) => cb(cb_id, ErrorCode::Success),
-                    Err(err) => cb(cb_id, set_last_error(Some(err))),
-                }
-            )
-        });
+        trace!("Rename profile");
+        let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
+        let profile = profile.into_opt_string().ok_or_else(|| err_msg!("Profile name not provided"))?;
+        let new_name = new_name.into_opt_string().ok_or_else(|| err_msg!("New profile name not provided"))?;
+        let cb = EnsureCallback::new(move |result|
+            match result {
+                Ok(_) => cb(cb_id, ErrorCode::Success),
+                Err(err) => cb(cb_id, set_last_error(Some(err))),
+            }
+        );
         spawn_ok(async move {
             let result = async {
-                let store = handle.remove().await?;
-                // remove any leftover sessions and scans associated with this store,
-                // to avoid blocking unnecessarily due to handles that simply haven't
-                // been dropped yet (this will invalidate associated handles)
-                FFI_SESSIONS.remove_all(handle).await?;
-                FFI_SCANS.remove_all(handle).await?;
-                store.close().await?;
-                debug!("Closed store {}", handle);
-                Ok(())
+                let store = handle.load().await?;
+                store.rename_profile(profile, new_name).await
             }.await;
-            if let Some(cb) = cb {
-                cb.resolve(result);
-            }
-            else if let Err(err) = result {
-                error!("{}", err);
-            }
+            cb.resolve(result);
         });
         Ok(ErrorCode::Success)
     }
 }
 
 #[no_mangle]
-pub extern "C" fn askar_scan_start(
+pub extern "C" fn askar_store_remove_profile(
     handle: StoreHandle,
     profile: FfiStr<'_>,
-    category: FfiStr<'_>,
-    tag_filter: FfiStr<'_>,
-    offset: i64,
-    limit: i64,
-    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode, handle: ScanHandle)>,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode, removed: i8)>,
     cb_id: CallbackId,
 ) -> ErrorCode {
     catch_err! {
-        trace!("Scan store start");
+        trace!("Remove profile");
         let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
-        let profile = profile.into_opt_string();
-        let category = category.into_opt_string();
-        let tag_filter = tag_filter.as_opt_str().map(TagFilter::from_str).transpose()?;
-        let cb = EnsureCallback::new(move |result: Result<ScanHandle,Error>|
+        let profile = profile.into_opt_string().ok_or_else(|| err_msg!("Profile name not provided"))?;
+        let cb = EnsureCallback::new(move |result|
             match result {
-                Ok(scan_handle) => {
-                    debug!("Started scan {} on store {}", scan_handle, handle);
-                    cb(cb_id, ErrorCode::Success, scan_handle)
-                }
-                Err(err) => cb(cb_id, set_last_error(Some(err)), ScanHandle::invalid()),
+                Ok(removed) => cb(cb_id, ErrorCode::Success, removed as i8),
+                Err(err) => cb(cb_id, set_last_error(Some(err)), 0),
             }
         );
         spawn_ok(async move {
             let result = async {
                 let store = handle.load().await?;
-                let scan = store.scan(profile, category, tag_filter, Some(offset), if limit < 0 { None }else {Some(limit)}).await?;
-                Ok(FFI_SCANS.insert(handle, scan).await)
+                store.remove_profile(profile).await
             }.await;
             cb.resolve(result);
         });
@@ -577,29 +671,25 @@ pub extern "C" fn askar_scan_start(
 }
 
 #[no_mangle]
-pub extern "C" fn askar_scan_next(
-    handle: ScanHandle,
-    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode, results: EntryListHandle)>,
+pub extern "C" fn askar_store_get_default_profile(
+    handle: StoreHandle,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode, profile: *const c_char)>,
     cb_id: CallbackId,
 ) -> ErrorCode {
     catch_err! {
-        trace!("Scan store next");
+        trace!("Get default profile");
         let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
-        let cb = EnsureCallback::new(move |result: Result<Option<Vec<Entry>>,Error>|
+        let cb = EnsureCallback::new(move |result: Result<String, Error>|
             match result {
-                Ok(Some(entries)) => {
-                    let results = EntryListHandle::create(FfiEntryList::from(entries));
-                    cb(cb_id, ErrorCode::Success, results)
-                },
-                Ok(None) => cb(cb_id, ErrorCode::Success, EntryListHandle::invalid()),
-                Err(err) => cb(cb_id, set_last_error(Some(err)), EntryListHandle::invalid()),
+                Ok(name) => cb(cb_id, ErrorCode::Success,
+                    CString::new(name.as_str()).unwrap().into_raw() as *const c_char),
+                Err(err) => cb(cb_id, set_last_error(Some(err)), ptr::null()),
             }
         );
         spawn_ok(async move {
             let result = async {
-                let mut scan = FFI_SCANS.borrow(handle).await?;
-                let entries = scan.fetch_next().await?;
-                Ok(entries)
+                let store = handle.load().await?;
+                store.get_default_profile().await
             }.await;
             cb.resolve(result);
         });
@@ -608,52 +698,64 @@ pub extern "C" fn askar_scan_next(
 }
 
 #[no_mangle]
-pub extern "C" fn askar_scan_free(handle: ScanHandle) -> ErrorCode {
+pub extern "C" fn askar_store_repair(
+    handle: StoreHandle,
+    dry_run: i8,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode, report: *const c_char)>,
+    cb_id: CallbackId,
+) -> ErrorCode {
     catch_err! {
-        trace!("Close scan");
-        spawn_ok(async move {
-            // the Scan may have been removed due to the Store being closed
-            if let Some(scan) = FFI_SCANS.remove(handle).await {
-                scan.ok();
-                debug!("Closed scan {}", handle);
-            } else {
-                debug!("Scan not found for closing: {}", handle);
+        trace!("Repair store");
+        let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
+        let cb = EnsureCallback::new(move |result: Result<RepairReport,Error>|
+            match result {
+                Ok(report) => {
+                    let report = serde_json::json!({
+                        "orphan_entries": report.orphan_entries,
+                        "orphan_tags": report.orphan_tags,
+                        "orphan_chunks": report.orphan_chunks,
+                        "orphan_audit_records": report.orphan_audit_records,
+                        "shared_refs": report.shared_refs,
+                        "default_profile": report.default_profile,
+                        "reindexed": report.reindexed,
+                    });
+                    cb(cb_id, ErrorCode::Success, rust_string_to_c(report.to_string()))
+                }
+                Err(err) => cb(cb_id, set_last_error(Some(err)), ptr::null()),
             }
+        );
+        spawn_ok(async move {
+            let result = async {
+                let store = handle.load().await?;
+                store.repair(dry_run != 0).await
+            }.await;
+            cb.resolve(result);
         });
         Ok(ErrorCode::Success)
     }
 }
 
 #[no_mangle]
-pub extern "C" fn askar_session_start(
+pub extern "C" fn askar_store_set_default_profile(
     handle: StoreHandle,
     profile: FfiStr<'_>,
-    as_transaction: i8,
-    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode, handle: SessionHandle)>,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode)>,
     cb_id: CallbackId,
 ) -> ErrorCode {
     catch_err! {
-        trace!("Session start");
-        let profile = profile.into_opt_string();
+        trace!("Set default profile");
         let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
-        let cb = EnsureCallback::new(move |result: Result<SessionHandle,Error>|
+        let profile = profile.into_opt_string().ok_or_else(|| err_msg!("Profile name not provided"))?;
+        let cb = EnsureCallback::new(move |result|
             match result {
-                Ok(sess_handle) => {
-                    debug!("Started session {} on store {} (txn: {})", sess_handle, handle, as_transaction != 0);
-                    cb(cb_id, ErrorCode::Success, sess_handle)
-                }
-                Err(err) => cb(cb_id, set_last_error(Some(err)), SessionHandle::invalid()),
+                Ok(_) => cb(cb_id, ErrorCode::Success),
+                Err(err) => cb(cb_id, set_last_error(Some(err))),
             }
         );
         spawn_ok(async move {
             let result = async {
                 let store = handle.load().await?;
-                let session = if as_transaction == 0 {
-                    store.session(profile).await?
-                } else {
-                    store.transaction(profile).await?
-                };
-                Ok(FFI_SESSIONS.insert(handle, session).await)
+                store.set_default_profile(profile).await
             }.await;
             cb.resolve(result);
         });
@@ -662,28 +764,1849 @@ pub extern "C" fn askar_session_start(
 }
 
 #[no_mangle]
-pub extern "C" fn askar_session_count(
-    handle: SessionHandle,
-    category: FfiStr<'_>,
-    tag_filter: FfiStr<'_>,
-    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode, count: i64)>,
+pub extern "C" fn askar_store_set_key_cache(
+    handle: StoreHandle,
+    ttl_ms: i64,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode)>,
+    cb_id: CallbackId,
+) -> ErrorCode {
+    catch_err! {
+        trace!("Set key cache");
+        let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
+        let ttl = (ttl_ms >= 0).then(|| Duration::from_millis(ttl_ms as u64));
+        let cb = EnsureCallback::new(move |result|
+            match result {
+                Ok(_) => cb(cb_id, ErrorCode::Success),
+                Err(err) => cb(cb_id, set_last_error(Some(err))),
+            }
+        );
+        spawn_ok(async move {
+            let result = async {
+                let store = handle.load().await?;
+                store.set_key_cache(ttl);
+                Ok(())
+            }.await;
+            cb.resolve(result);
+        });
+        Ok(ErrorCode::Success)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn askar_store_purge_key_cache(
+    handle: StoreHandle,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode)>,
+    cb_id: CallbackId,
+) -> ErrorCode {
+    catch_err! {
+        trace!("Purge key cache");
+        let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
+        let cb = EnsureCallback::new(move |result|
+            match result {
+                Ok(_) => cb(cb_id, ErrorCode::Success),
+                Err(err) => cb(cb_id, set_last_error(Some(err))),
+            }
+        );
+        spawn_ok(async move {
+            let result = async {
+                let store = handle.load().await?;
+                store.purge_key_cache();
+                Ok(())
+            }.await;
+            cb.resolve(result);
+        });
+        Ok(ErrorCode::Success)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn askar_store_set_entry_cache(
+    handle: StoreHandle,
+    capacity: i64,
+    ttl_ms: i64,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode)>,
+    cb_id: CallbackId,
+) -> ErrorCode {
+    catch_err! {
+        trace!("Set entry cache");
+        let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
+        let capacity = capacity.max(0) as usize;
+        let ttl = (ttl_ms >= 0).then(|| Duration::from_millis(ttl_ms as u64));
+        let cb = EnsureCallback::new(move |result|
+            match result {
+                Ok(_) => cb(cb_id, ErrorCode::Success),
+                Err(err) => cb(cb_id, set_last_error(Some(err))),
+            }
+        );
+        spawn_ok(async move {
+            let result = async {
+                let store = handle.load().await?;
+                store.set_entry_cache(capacity, ttl);
+                Ok(())
+            }.await;
+            cb.resolve(result);
+        });
+        Ok(ErrorCode::Success)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn askar_store_purge_entry_cache(
+    handle: StoreHandle,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode)>,
+    cb_id: CallbackId,
+) -> ErrorCode {
+    catch_err! {
+        trace!("Purge entry cache");
+        let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
+        let cb = EnsureCallback::new(move |result|
+            match result {
+                Ok(_) => cb(cb_id, ErrorCode::Success),
+                Err(err) => cb(cb_id, set_last_error(Some(err))),
+            }
+        );
+        spawn_ok(async move {
+            let result = async {
+                let store = handle.load().await?;
+                store.purge_entry_cache();
+                Ok(())
+            }.await;
+            cb.resolve(result);
+        });
+        Ok(ErrorCode::Success)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn askar_store_rekey(
+    handle: StoreHandle,
+    key_method: FfiStr<'_>,
+    pass_key: FfiStr<'_>,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode)>,
+    cb_id: CallbackId,
+) -> ErrorCode {
+    catch_err! {
+        trace!("Re-key store");
+        let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
+        let key_method = match key_method.as_opt_str() {
+            Some(method) => StoreKeyMethod::parse_uri(method)?,
+            None => StoreKeyMethod::default()
+        };
+        let pass_key = PassKey::from(pass_key.as_opt_str()).into_owned();
+        let cb = EnsureCallback::new(move |result|
+            match result {
+                Ok(_) => cb(cb_id, ErrorCode::Success),
+                Err(err) => cb(cb_id, set_last_error(Some(err))),
+            }
+        );
+        spawn_ok(async move {
+            let result = async {
+                let mut store = handle.remove().await?;
+                let result = store.rekey(key_method, pass_key.as_ref()).await;
+                handle.replace(store).await;
+                result
+            }.await;
+            cb.resolve(result);
+        });
+        Ok(ErrorCode::Success)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn askar_store_rekey_with_progress(
+    handle: StoreHandle,
+    key_method: FfiStr<'_>,
+    pass_key: FfiStr<'_>,
+    progress: Option<extern "C" fn(cb_id: CallbackId, completed: i64, total: i64)>,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode)>,
+    cb_id: CallbackId,
+) -> ErrorCode {
+    catch_err! {
+        trace!("Re-key store with progress");
+        let progress = progress.ok_or_else(|| err_msg!("No progress callback provided"))?;
+        let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
+        let key_method = match key_method.as_opt_str() {
+            Some(method) => StoreKeyMethod::parse_uri(method)?,
+            None => StoreKeyMethod::default()
+        };
+        let pass_key = PassKey::from(pass_key.as_opt_str()).into_owned();
+        let cb = EnsureCallback::new(move |result|
+            match result {
+                Ok(_) => cb(cb_id, ErrorCode::Success),
+                Err(err) => cb(cb_id, set_last_error(Some(err))),
+            }
+        );
+        spawn_ok(async move {
+            let result = async {
+                let mut store = handle.remove().await?;
+                let result = store.rekey_with_progress(
+                    key_method,
+                    pass_key.as_ref(),
+                    move |status: RekeyProgress| progress(cb_id, status.completed, status.total),
+                ).await;
+                handle.replace(store).await;
+                result
+            }.await;
+            cb.resolve(result);
+        });
+        Ok(ErrorCode::Success)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn askar_store_upgrade_kdf(
+    handle: StoreHandle,
+    key_method: FfiStr<'_>,
+    pass_key: FfiStr<'_>,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode, upgraded: i8)>,
+    cb_id: CallbackId,
+) -> ErrorCode {
+    catch_err! {
+        trace!("Upgrade store key derivation");
+        let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
+        let key_method = match key_method.as_opt_str() {
+            Some(method) => StoreKeyMethod::parse_uri(method)?,
+            None => StoreKeyMethod::default()
+        };
+        let pass_key = PassKey::from(pass_key.as_opt_str()).into_owned();
+        let cb = EnsureCallback::new(move |result|
+            match result {
+                Ok(upgraded) => cb(cb_id, ErrorCode::Success, upgraded as i8),
+                Err(err) => cb(cb_id, set_last_error(Some(err)), 0),
+            }
+        );
+        spawn_ok(async move {
+            let result = async {
+                let mut store = handle.remove().await?;
+                let result = store.upgrade_kdf(key_method, pass_key.as_ref()).await;
+                handle.replace(store).await;
+                result
+            }.await;
+            cb.resolve(result);
+        });
+        Ok(ErrorCode::Success)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn askar_store_copy(
+    handle: StoreHandle,
+    target_uri: FfiStr<'_>,
+    key_method: FfiStr<'_>,
+    pass_key: FfiStr<'_>,
+    recreate: i8,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode, handle: StoreHandle)>,
+    cb_id: CallbackId,
+) -> ErrorCode {
+    catch_err! {
+        trace!("Copy store");
+        let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
+        let target_uri = target_uri.into_opt_string().ok_or_else(|| err_msg!("No target URI provided"))?;
+        let key_method = match key_method.as_opt_str() {
+            Some(method) => StoreKeyMethod::parse_uri(method)?,
+            None => StoreKeyMethod::default()
+        };
+        let pass_key = PassKey::from(pass_key.as_opt_str()).into_owned();
+        let cb = EnsureCallback::new(move |result|
+            match result {
+                Ok(handle) => cb(cb_id, ErrorCode::Success, handle),
+                Err(err) => cb(cb_id, set_last_error(Some(err)), StoreHandle::invalid()),
+            }
+        );
+        spawn_ok(async move {
+            let result = async move {
+                let store = handle.load().await?;
+                let copied = store.copy_to(target_uri.as_str(), key_method, pass_key.as_ref(), recreate != 0).await?;
+                debug!("Copied store {}", handle);
+                Ok(StoreHandle::create(copied).await)
+            }.await;
+            cb.resolve(result);
+        });
+        Ok(ErrorCode::Success)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn askar_store_copy_to_store(
+    handle: StoreHandle,
+    target_handle: StoreHandle,
+    profiles: FfiStr<'_>,
+    categories: FfiStr<'_>,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode)>,
+    cb_id: CallbackId,
+) -> ErrorCode {
+    catch_err! {
+        trace!("Copy store entries");
+        let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
+        let profiles = profiles.as_opt_str().map(serde_json::from_str::<Vec<String>>)
+            .transpose()
+            .map_err(err_map!("Error decoding profiles"))?;
+        let categories = categories.as_opt_str().map(serde_json::from_str::<Vec<String>>)
+            .transpose()
+            .map_err(err_map!("Error decoding categories"))?;
+        let cb = EnsureCallback::new(move |result|
+            match result {
+                Ok(_) => cb(cb_id, ErrorCode::Success),
+                Err(err) => cb(cb_id, set_last_error(Some(err))),
+            }
+        );
+        spawn_ok(async move {
+            let result = async move {
+                let store = handle.load().await?;
+                let target = target_handle.load().await?;
+                store.copy_to_store(&target, profiles.as_deref(), categories.as_deref()).await?;
+                debug!("Copied store {} entries to {}", handle, target_handle);
+                Ok(())
+            }.await;
+            cb.resolve(result);
+        });
+        Ok(ErrorCode::Success)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn askar_store_export(
+    handle: StoreHandle,
+    path: FfiStr<'_>,
+    pass_key: FfiStr<'_>,
+    recipient_key: LocalKeyHandle,
+    profiles: FfiStr<'_>,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode)>,
+    cb_id: CallbackId,
+) -> ErrorCode {
+    catch_err! {
+        trace!("Export store");
+        let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
+        let path = path.into_opt_string().ok_or_else(|| err_msg!("No export path provided"))?;
+        let pass_key = pass_key.into_opt_string();
+        let recipient_key = if pass_key.is_none() {
+            Some(recipient_key.load()?)
+        } else {
+            None
+        };
+        let profiles = profiles.as_opt_str().map(serde_json::from_str::<Vec<String>>)
+            .transpose()
+            .map_err(err_map!("Error decoding profiles"))?;
+        let cb = EnsureCallback::new(move |result|
+            match result {
+                Ok(_) => cb(cb_id, ErrorCode::Success),
+                Err(err) => cb(cb_id, set_last_error(Some(err))),
+            }
+        );
+        spawn_ok(async move {
+            let result = async move {
+                let store = handle.load().await?;
+                // the recipient key is only loaded when no passphrase is provided
+                let key = match recipient_key.as_ref() {
+                    Some(recipient_key) => ExportKey::Recipient(recipient_key),
+                    None => ExportKey::Passphrase(pass_key.as_deref().unwrap_or_default()),
+                };
+                let file = File::create(path.as_str())
+                    .map_err(err_map!("Error creating export file"))?;
+                store.export(BufWriter::new(file), key, profiles.as_deref()).await?;
+                debug!("Exported store {}", handle);
+                Ok(())
+            }.await;
+            cb.resolve(result);
+        });
+        Ok(ErrorCode::Success)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn askar_store_import(
+    handle: StoreHandle,
+    path: FfiStr<'_>,
+    pass_key: FfiStr<'_>,
+    recipient_key: LocalKeyHandle,
+    conflict: i8,
+    categories: FfiStr<'_>,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode)>,
+    cb_id: CallbackId,
+) -> ErrorCode {
+    catch_err! {
+        trace!("Import store");
+        let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
+        let path = path.into_opt_string().ok_or_else(|| err_msg!("No import path provided"))?;
+        let pass_key = pass_key.into_opt_string();
+        let recipient_key = if pass_key.is_none() {
+            Some(recipient_key.load()?)
+        } else {
+            None
+        };
+        let conflict = match conflict {
+            0 => ImportConflict::Fail,
+            1 => ImportConflict::Skip,
+            2 => ImportConflict::Overwrite,
+            _ => return Err(err_msg!("Invalid import conflict handling"))
+        };
+        let categories = categories.as_opt_str().map(serde_json::from_str::<Vec<String>>)
+            .transpose()
+            .map_err(err_map!("Error decoding categories"))?;
+        let cb = EnsureCallback::new(move |result|
+            match result {
+                Ok(_) => cb(cb_id, ErrorCode::Success),
+                Err(err) => cb(cb_id, set_last_error(Some(err))),
+            }
+        );
+        spawn_ok(async move {
+            let result = async move {
+                let store = handle.load().await?;
+                // the recipient key is only loaded when no passphrase is provided
+                let key = match recipient_key.as_ref() {
+                    Some(recipient_key) => ExportKey::Recipient(recipient_key),
+                    None => ExportKey::Passphrase(pass_key.as_deref().unwrap_or_default()),
+                };
+                let file = File::open(path.as_str())
+                    .map_err(err_map!("Error opening import file"))?;
+                store.import(BufReader::new(file), key, conflict, categories.as_deref()).await?;
+                debug!("Imported into store {}", handle);
+                Ok(())
+            }.await;
+            cb.resolve(result);
+        });
+        Ok(ErrorCode::Success)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn askar_store_snapshot(
+    handle: StoreHandle,
+    target_uri: FfiStr<'_>,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode)>,
+    cb_id: CallbackId,
+) -> ErrorCode {
+    catch_err! {
+        trace!("Snapshot store");
+        let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
+        let target_uri = target_uri.into_opt_string().ok_or_else(|| err_msg!("No target URI provided"))?;
+        let cb = EnsureCallback::new(move |result|
+            match result {
+                Ok(_) => cb(cb_id, ErrorCode::Success),
+                Err(err) => cb(cb_id, set_last_error(Some(err))),
+            }
+        );
+        spawn_ok(async move {
+            let result = async move {
+                let store = handle.load().await?;
+                store.snapshot(target_uri.as_str()).await?;
+                debug!("Created snapshot of store {}", handle);
+                Ok(())
+            }.await;
+            cb.resolve(result);
+        });
+        Ok(ErrorCode::Success)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn askar_store_verify_integrity(
+    handle: StoreHandle,
+    quarantine: i8,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode, report: *const c_char)>,
+    cb_id: CallbackId,
+) -> ErrorCode {
+    catch_err! {
+        trace!("Verify store integrity");
+        let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
+        let cb = EnsureCallback::new(move |result: Result<IntegrityReport,Error>|
+            match result {
+                Ok(report) => {
+                    let issues = report.issues.into_iter().map(|i| serde_json::json!({
+                        "profile": i.profile,
+                        "id": i.id,
+                        "kind": i.kind.map(|k| k as u8),
+                        "category": i.category,
+                        "name": i.name,
+                        "reason": i.reason,
+                        "quarantined": i.quarantined,
+                    })).collect::<Vec<_>>();
+                    let report = serde_json::json!({
+                        "profiles": report.profiles,
+                        "entries": report.entries,
+                        "issues": issues,
+                    });
+                    cb(cb_id, ErrorCode::Success, rust_string_to_c(report.to_string()))
+                }
+                Err(err) => cb(cb_id, set_last_error(Some(err)), ptr::null()),
+            }
+        );
+        spawn_ok(async move {
+            let result = async {
+                let store = handle.load().await?;
+                store.verify_integrity(quarantine != 0).await
+            }.await;
+            cb.resolve(result);
+        });
+        Ok(ErrorCode::Success)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn askar_store_close(
+    handle: StoreHandle,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode)>,
+    cb_id: CallbackId,
+) -> ErrorCode {
+    catch_err! {
+        trace!("Close store");
+        let cb = cb.map(|cb| {
+            EnsureCallback::new(move |result|
+                match result {
+                    Ok(_) => cb(cb_id, ErrorCode::Success),
+                    Err(err) => cb(cb_id, set_last_error(Some(err))),
+                }
+            )
+        });
+        spawn_ok(async move {
+            let result = async {
+                let store = handle.remove().await?;
+                // remove any leftover sessions, scans and subscriptions associated with
+                // this store, to avoid blocking unnecessarily due to handles that simply
+                // haven't been dropped yet (this will invalidate associated handles)
+                FFI_SESSIONS.remove_all(handle).await?;
+                FFI_SCANS.remove_all(handle).await?;
+                FFI_KEY_SCANS.remove_all(handle).await?;
+                FFI_SUBSCRIPTIONS.remove_all(handle).await?;
+                store.close().await?;
+                debug!("Closed store {}", handle);
+                Ok(())
+            }.await;
+            if let Some(cb) = cb {
+                cb.resolve(result);
+            }
+            else if let Err(err) = result {
+                error!("{}", err);
+            }
+        });
+        Ok(ErrorCode::Success)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn askar_scan_start(
+    handle: StoreHandle,
+    profile: FfiStr<'_>,
+    category: FfiStr<'_>,
+    tag_filter: FfiStr<'_>,
+    offset: i64,
+    limit: i64,
+    order_by: FfiStr<'_>,
+    descending: i8,
+    after: FfiStr<'_>,
+    tag_names: FfiStr<'_>,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode, handle: ScanHandle)>,
+    cb_id: CallbackId,
+) -> ErrorCode {
+    catch_err! {
+        trace!("Scan store start");
+        let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
+        let profile = profile.into_opt_string();
+        let category = category.into_opt_string();
+        let tag_filter = tag_filter.as_opt_str().map(TagFilter::from_str).transpose()?;
+        let order_by = order_by.as_opt_str().map(OrderBy::from_str).transpose()?;
+        let after = after.into_opt_string();
+        let tags = tag_names.as_opt_str().map(serde_json::from_str::<Vec<String>>)
+            .transpose()
+            .map_err(err_map!("Error decoding tag names"))?
+            .map_or(TagSelection::All, TagSelection::Named);
+        let cb = EnsureCallback::new(move |result: Result<ScanHandle,Error>|
+            match result {
+                Ok(scan_handle) => {
+                    debug!("Started scan {} on store {}", scan_handle, handle);
+                    cb(cb_id, ErrorCode::Success, scan_handle)
+                }
+                Err(err) => cb(cb_id, set_last_error(Some(err)), ScanHandle::invalid()),
+            }
+        );
+        spawn_ok(async move {
+            let result = async {
+                let store = handle.load().await?;
+                let scan = store.scan(profile, category, tag_filter, Some(offset), if limit < 0 { None }else {Some(limit)}, order_by, descending != 0, after, tags).await?;
+                Ok(FFI_SCANS.insert(handle, scan).await)
+            }.await;
+            cb.resolve(result);
+        });
+        Ok(ErrorCode::Success)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn askar_scan_next(
+    handle: ScanHandle,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode, results: EntryListHandle)>,
+    cb_id: CallbackId,
+) -> ErrorCode {
+    catch_err! {
+        trace!("Scan store next");
+        let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
+        let cb = EnsureCallback::new(move |result: Result<Option<Vec<Entry>>,Error>|
+            match result {
+                Ok(Some(entries)) => {
+                    let results = EntryListHandle::create(FfiEntryList::from(entries));
+                    cb(cb_id, ErrorCode::Success, results)
+                },
+                Ok(None) => cb(cb_id, ErrorCode::Success, EntryListHandle::invalid()),
+                Err(err) => cb(cb_id, set_last_error(Some(err)), EntryListHandle::invalid()),
+            }
+        );
+        spawn_ok(async move {
+            let result = async {
+                let mut scan = FFI_SCANS.borrow(handle).await?;
+                let entries = scan.fetch_next().await?;
+                Ok(entries)
+            }.await;
+            cb.resolve(result);
+        });
+        Ok(ErrorCode::Success)
+    }
+}
+
+/// Deliver each page of scan results to the `page` callback as it is
+/// fetched, until the scan is exhausted or the callback returns a nonzero
+/// value. Each page is provided as a new entry list, which must be freed by
+/// the caller. The `cb` callback is invoked once delivery has finished.
+#[no_mangle]
+pub extern "C" fn askar_scan_stream(
+    handle: ScanHandle,
+    page: Option<extern "C" fn(cb_id: CallbackId, results: EntryListHandle) -> i8>,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode)>,
+    cb_id: CallbackId,
+) -> ErrorCode {
+    catch_err! {
+        trace!("Scan store stream");
+        let page = page.ok_or_else(|| err_msg!("No page callback provided"))?;
+        let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
+        let cb = EnsureCallback::new(move |result|
+            match result {
+                Ok(_) => cb(cb_id, ErrorCode::Success),
+                Err(err) => cb(cb_id, set_last_error(Some(err))),
+            }
+        );
+        spawn_ok(async move {
+            let result = async {
+                let mut scan = FFI_SCANS.borrow(handle).await?;
+                while let Some(entries) = scan.fetch_next().await? {
+                    let results = EntryListHandle::create(FfiEntryList::from(entries));
+                    if page(cb_id, results) != 0 {
+                        break;
+                    }
+                }
+                Ok(())
+            }.await;
+            cb.resolve(result);
+        });
+        Ok(ErrorCode::Success)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn askar_scan_continuation(
+    handle: ScanHandle,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode, token: *const c_char)>,
+    cb_id: CallbackId,
+) -> ErrorCode {
+    catch_err! {
+        trace!("Scan continuation");
+        let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
+        let cb = EnsureCallback::new(move |result: Result<Option<String>,Error>|
+            match result {
+                Ok(Some(token)) => cb(cb_id, ErrorCode::Success, rust_string_to_c(token)),
+                Ok(None) => cb(cb_id, ErrorCode::Success, ptr::null()),
+                Err(err) => cb(cb_id, set_last_error(Some(err)), ptr::null()),
+            }
+        );
+        spawn_ok(async move {
+            let result = async {
+                let scan = FFI_SCANS.borrow(handle).await?;
+                Ok(scan.continuation().map(str::to_string))
+            }.await;
+            cb.resolve(result);
+        });
+        Ok(ErrorCode::Success)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn askar_scan_free(handle: ScanHandle) -> ErrorCode {
+    catch_err! {
+        trace!("Close scan");
+        spawn_ok(async move {
+            // the Scan may have been removed due to the Store being closed
+            if let Some(scan) = FFI_SCANS.remove(handle).await {
+                scan.ok();
+                debug!("Closed scan {}", handle);
+            } else {
+                debug!("Scan not found for closing: {}", handle);
+            }
+        });
+        Ok(ErrorCode::Success)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn askar_key_scan_start(
+    handle: StoreHandle,
+    profile: FfiStr<'_>,
+    alg: FfiStr<'_>,
+    thumbprint: FfiStr<'_>,
+    tag_filter: FfiStr<'_>,
+    created_after: i64,
+    created_before: i64,
+    offset: i64,
+    limit: i64,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode, handle: KeyScanHandle)>,
+    cb_id: CallbackId,
+) -> ErrorCode {
+    catch_err! {
+        trace!("Scan keys start");
+        let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
+        let profile = profile.into_opt_string();
+        let alg = alg.into_opt_string();
+        let thumbprint = thumbprint.into_opt_string();
+        let tag_filter = tag_filter.as_opt_str().map(TagFilter::from_str).transpose()?;
+        let created_after = if created_after < 0 { None } else { Some(created_after) };
+        let created_before = if created_before < 0 { None } else { Some(created_before) };
+        let limit = if limit < 0 { None } else { Some(limit) };
+        let cb = EnsureCallback::new(move |result: Result<KeyScanHandle,Error>|
+            match result {
+                Ok(scan_handle) => {
+                    debug!("Started key scan {} on store {}", scan_handle, handle);
+                    cb(cb_id, ErrorCode::Success, scan_handle)
+                }
+                Err(err) => cb(cb_id, set_last_error(Some(err)), KeyScanHandle::invalid()),
+            }
+        );
+        spawn_ok(async move {
+            let result = async {
+                let store = handle.load().await?;
+                let scan = store.scan_keys(
+                    profile,
+                    alg.as_deref(),
+                    thumbprint.as_deref(),
+                    tag_filter,
+                    created_after,
+                    created_before,
+                    Some(offset),
+                    limit,
+                ).await?;
+                Ok(FFI_KEY_SCANS.insert(handle, scan).await)
+            }.await;
+            cb.resolve(result);
+        });
+        Ok(ErrorCode::Success)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn askar_key_scan_next(
+    handle: KeyScanHandle,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode, results: KeyEntryListHandle)>,
+    cb_id: CallbackId,
+) -> ErrorCode {
+    catch_err! {
+        trace!("Scan keys next");
+        let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
+        let cb = EnsureCallback::new(move |result: Result<Option<Vec<KeyEntry>>,Error>|
+            match result {
+                Ok(Some(entries)) => {
+                    let results = KeyEntryListHandle::create(FfiKeyEntryList::from(entries));
+                    cb(cb_id, ErrorCode::Success, results)
+                },
+                Ok(None) => cb(cb_id, ErrorCode::Success, KeyEntryListHandle::invalid()),
+                Err(err) => cb(cb_id, set_last_error(Some(err)), KeyEntryListHandle::invalid()),
+            }
+        );
+        spawn_ok(async move {
+            let result = async {
+                let mut scan = FFI_KEY_SCANS.borrow(handle).await?;
+                let entries = scan.fetch_next().await?;
+                Ok(entries)
+            }.await;
+            cb.resolve(result);
+        });
+        Ok(ErrorCode::Success)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn askar_key_scan_free(handle: KeyScanHandle) -> ErrorCode {
+    catch_err! {
+        trace!("Close key scan");
+        spawn_ok(async move {
+            // the KeyScan may have been removed due to the Store being closed
+            if let Some(scan) = FFI_KEY_SCANS.remove(handle).await {
+                scan.ok();
+                debug!("Closed key scan {}", handle);
+            } else {
+                debug!("Key scan not found for closing: {}", handle);
+            }
+        });
+        Ok(ErrorCode::Success)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn askar_subscription_start(
+    handle: StoreHandle,
+    profile: FfiStr<'_>,
+    category: FfiStr<'_>,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode, handle: SubscriptionHandle)>,
+    cb_id: CallbackId,
+) -> ErrorCode {
+    catch_err! {
+        trace!("Subscription start");
+        let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
+        let profile = profile.into_opt_string();
+        let category = category.into_opt_string();
+        let cb = EnsureCallback::new(move |result: Result<SubscriptionHandle,Error>|
+            match result {
+                Ok(sub_handle) => {
+                    debug!("Started subscription {} on store {}", sub_handle, handle);
+                    cb(cb_id, ErrorCode::Success, sub_handle)
+                }
+                Err(err) => cb(cb_id, set_last_error(Some(err)), SubscriptionHandle::invalid()),
+            }
+        );
+        spawn_ok(async move {
+            let result = async {
+                let store = handle.load().await?;
+                let subscription = store.subscribe(profile, category).await?;
+                Ok(FFI_SUBSCRIPTIONS.insert(handle, subscription).await)
+            }.await;
+            cb.resolve(result);
+        });
+        Ok(ErrorCode::Success)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn askar_subscription_next(
+    handle: SubscriptionHandle,
+    cb: Option<
+        extern "C" fn(
+            cb_id: CallbackId,
+            err: ErrorCode,
+            operation: i8,
+            category: *const c_char,
+            name: *const c_char,
+        ),
+    >,
+    cb_id: CallbackId,
+) -> ErrorCode {
+    catch_err! {
+        trace!("Subscription next");
+        let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
+        let cb = EnsureCallback::new(move |result: Result<Option<EntryEvent>,Error>|
+            match result {
+                Ok(Some(event)) => {
+                    let operation = match event.operation {
+                        EntryOperation::Insert => 0,
+                        EntryOperation::Replace => 1,
+                        EntryOperation::Remove => 2,
+                    };
+                    cb(
+                        cb_id,
+                        ErrorCode::Success,
+                        operation,
+                        rust_string_to_c(event.category),
+                        rust_string_to_c(event.name),
+                    )
+                },
+                Ok(None) => cb(cb_id, ErrorCode::Success, -1, ptr::null(), ptr::null()),
+                Err(err) => cb(cb_id, set_last_error(Some(err)), -1, ptr::null(), ptr::null()),
+            }
+        );
+        spawn_ok(async move {
+            let result = async {
+                let mut subscription = FFI_SUBSCRIPTIONS.borrow(handle).await?;
+                let event = subscription.next_event().await?;
+                Ok(event)
+            }.await;
+            cb.resolve(result);
+        });
+        Ok(ErrorCode::Success)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn askar_subscription_free(handle: SubscriptionHandle) -> ErrorCode {
+    catch_err! {
+        trace!("Close subscription");
+        spawn_ok(async move {
+            // the Subscription may have been removed due to the Store being closed
+            if let Some(subscription) = FFI_SUBSCRIPTIONS.remove(handle).await {
+                subscription.ok();
+                debug!("Closed subscription {}", handle);
+            } else {
+                debug!("Subscription not found for closing: {}", handle);
+            }
+        });
+        Ok(ErrorCode::Success)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn askar_session_start(
+    handle: StoreHandle,
+    profile: FfiStr<'_>,
+    as_transaction: i8,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode, handle: SessionHandle)>,
+    cb_id: CallbackId,
+) -> ErrorCode {
+    catch_err! {
+        trace!("Session start");
+        let profile = profile.into_opt_string();
+        let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
+        let cb = EnsureCallback::new(move |result: Result<SessionHandle,Error>|
+            match result {
+                Ok(sess_handle) => {
+                    debug!("Started session {} on store {} (txn: {})", sess_handle, handle, as_transaction != 0);
+                    cb(cb_id, ErrorCode::Success, sess_handle)
+                }
+                Err(err) => cb(cb_id, set_last_error(Some(err)), SessionHandle::invalid()),
+            }
+        );
+        spawn_ok(async move {
+            let result = async {
+                let store = handle.load().await?;
+                let session = if as_transaction == 0 {
+                    store.session(profile).await?
+                } else {
+                    store.transaction(profile).await?
+                };
+                Ok(FFI_SESSIONS.insert(handle, session).await)
+            }.await;
+            cb.resolve(result);
+        });
+        Ok(ErrorCode::Success)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn askar_session_start_transaction(
+    handle: StoreHandle,
+    profile: FfiStr<'_>,
+    isolation: i8,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode, handle: SessionHandle)>,
+    cb_id: CallbackId,
+) -> ErrorCode {
+    catch_err! {
+        trace!("Transaction start");
+        let profile = profile.into_opt_string();
+        let isolation = match isolation {
+            0 => None,
+            1 => Some(IsolationLevel::ReadCommitted),
+            2 => Some(IsolationLevel::RepeatableRead),
+            3 => Some(IsolationLevel::Serializable),
+            _ => return Err(err_msg!("Invalid isolation level"))
+        };
+        let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
+        let cb = EnsureCallback::new(move |result: Result<SessionHandle,Error>|
+            match result {
+                Ok(sess_handle) => {
+                    debug!("Started transaction {} on store {} (isolation: {:?})", sess_handle, handle, isolation);
+                    cb(cb_id, ErrorCode::Success, sess_handle)
+                }
+                Err(err) => cb(cb_id, set_last_error(Some(err)), SessionHandle::invalid()),
+            }
+        );
+        spawn_ok(async move {
+            let result = async {
+                let store = handle.load().await?;
+                let session = if let Some(isolation) = isolation {
+                    store.transaction_with_isolation(profile, isolation).await?
+                } else {
+                    store.transaction(profile).await?
+                };
+                Ok(FFI_SESSIONS.insert(handle, session).await)
+            }.await;
+            cb.resolve(result);
+        });
+        Ok(ErrorCode::Success)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn askar_session_count(
+    handle: SessionHandle,
+    category: FfiStr<'_>,
+    tag_filter: FfiStr<'_>,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode, count: i64)>,
+    cb_id: CallbackId,
+) -> ErrorCode {
+    catch_err! {
+        trace!("Count from store");
+        let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
+        let category = category.into_opt_string();
+        let tag_filter = tag_filter.as_opt_str().map(TagFilter::from_str).transpose()?;
+        let cb = EnsureCallback::new(move |result: Result<i64,Error>|
+            match result {
+                Ok(count) => cb(cb_id, ErrorCode::Success, count),
+                Err(err) => cb(cb_id, set_last_error(Some(err)), 0),
+            }
+        );
+        spawn_ok(async move {
+            let result = async {
+                let mut session = FFI_SESSIONS.borrow(handle).await?;
+                session.count(category.as_deref(), tag_filter).await
+            }.await;
+            cb.resolve(result);
+        });
+        Ok(ErrorCode::Success)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn askar_session_count_grouped(
+    handle: SessionHandle,
+    category: FfiStr<'_>,
+    tag_filter: FfiStr<'_>,
+    group_tag: FfiStr<'_>,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode, counts: *const c_char)>,
+    cb_id: CallbackId,
+) -> ErrorCode {
+    catch_err! {
+        trace!("Count grouped from store");
+        let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
+        let category = category.into_opt_string();
+        let tag_filter = tag_filter.as_opt_str().map(TagFilter::from_str).transpose()?;
+        let group_tag = group_tag.into_opt_string();
+        let cb = EnsureCallback::new(move |result: Result<Vec<EntryCount>,Error>|
+            match result {
+                Ok(counts) => {
+                    let counts = counts.into_iter().map(|c| serde_json::json!({
+                        "category": c.category,
+                        "tag_value": c.tag_value,
+                        "count": c.count,
+                    })).collect::<Vec<_>>();
+                    cb(cb_id, ErrorCode::Success, rust_string_to_c(serde_json::Value::from(counts).to_string()))
+                }
+                Err(err) => cb(cb_id, set_last_error(Some(err)), ptr::null()),
+            }
+        );
+        spawn_ok(async move {
+            let result = async {
+                let mut session = FFI_SESSIONS.borrow(handle).await?;
+                session.count_grouped(category.as_deref(), tag_filter, group_tag.as_deref()).await
+            }.await;
+            cb.resolve(result);
+        });
+        Ok(ErrorCode::Success)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn askar_session_fetch(
+    handle: SessionHandle,
+    category: FfiStr<'_>,
+    name: FfiStr<'_>,
+    for_update: i8,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode, results: EntryListHandle)>,
+    cb_id: CallbackId,
+) -> ErrorCode {
+    catch_err! {
+        trace!("Fetch from store");
+        let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
+        let category = category.into_opt_string().ok_or_else(|| err_msg!("Category not provided"))?;
+        let name = name.into_opt_string().ok_or_else(|| err_msg!("Name not provided"))?;
+        let cb = EnsureCallback::new(move |result: Result<Option<Entry>,Error>|
+            match result {
+                Ok(Some(entry)) => {
+                    let results = EntryListHandle::create(FfiEntryList::from(entry));
+                    cb(cb_id, ErrorCode::Success, results)
+                },
+                Ok(None) => cb(cb_id, ErrorCode::Success, EntryListHandle::invalid()),
+                Err(err) => cb(cb_id, set_last_error(Some(err)), EntryListHandle::invalid()),
+            }
+        );
+        spawn_ok(async move {
+            let result = async {
+                let mut session = FFI_SESSIONS.borrow(handle).await?;
+                session.fetch(&category, &name, for_update != 0).await
+            }.await;
+            cb.resolve(result);
+        });
+        Ok(ErrorCode::Success)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn askar_session_fetch_all(
+    handle: SessionHandle,
+    category: FfiStr<'_>,
+    tag_filter: FfiStr<'_>,
+    limit: i64,
+    for_update: i8,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode, results: EntryListHandle)>,
+    cb_id: CallbackId,
+) -> ErrorCode {
+    catch_err! {
+        trace!("Count from store");
+        let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
+        let category = category.into_opt_string();
+        let tag_filter = tag_filter.as_opt_str().map(TagFilter::from_str).transpose()?;
+        let limit = if limit < 0 { None } else {Some(limit)};
+        let cb = EnsureCallback::new(move |result|
+            match result {
+                Ok(rows) => {
+                    let results = EntryListHandle::create(FfiEntryList::from(rows));
+                    cb(cb_id, ErrorCode::Success, results)
+                }
+                Err(err) => cb(cb_id, set_last_error(Some(err)), EntryListHandle::invalid()),
+            }
+        );
+        spawn_ok(async move {
+            let result = async {
+                let mut session = FFI_SESSIONS.borrow(handle).await?;
+                session.fetch_all(category.as_deref(), tag_filter, limit, for_update != 0).await
+            }.await;
+            cb.resolve(result);
+        });
+        Ok(ErrorCode::Success)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn askar_session_fetch_many(
+    handle: SessionHandle,
+    category: FfiStr<'_>,
+    names: FfiStr<'_>,
+    for_update: i8,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode, results: EntryListHandle)>,
+    cb_id: CallbackId,
+) -> ErrorCode {
+    catch_err! {
+        trace!("Fetch many from store");
+        let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
+        let category = category.into_opt_string().ok_or_else(|| err_msg!("Category not provided"))?;
+        let names = names.as_opt_str().ok_or_else(|| err_msg!("Names not provided"))?;
+        let names = serde_json::from_str::<Vec<String>>(names)
+            .map_err(err_map!("Error decoding names"))?;
+        let cb = EnsureCallback::new(move |result|
+            match result {
+                Ok(rows) => {
+                    let results = EntryListHandle::create(FfiEntryList::from(rows));
+                    cb(cb_id, ErrorCode::Success, results)
+                }
+                Err(err) => cb(cb_id, set_last_error(Some(err)), EntryListHandle::invalid()),
+            }
+        );
+        spawn_ok(async move {
+            let result = async {
+                let names = names.iter().map(String::as_str).collect::<Vec<_>>();
+                let mut session = FFI_SESSIONS.borrow(handle).await?;
+                session.fetch_many(&category, &names, for_update != 0).await
+            }.await;
+            cb.resolve(result);
+        });
+        Ok(ErrorCode::Success)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn askar_session_fetch_audit_log(
+    handle: SessionHandle,
+    category: FfiStr<'_>,
+    since: i64,
+    limit: i64,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode, records: *const c_char)>,
+    cb_id: CallbackId,
+) -> ErrorCode {
+    catch_err! {
+        trace!("Fetch audit log from store");
+        let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
+        let category = category.into_opt_string();
+        let since = if since < 0 { None } else { Some(since) };
+        let limit = if limit < 0 { None } else { Some(limit) };
+        let cb = EnsureCallback::new(move |result: Result<Vec<AuditRecord>,Error>|
+            match result {
+                Ok(records) => {
+                    let records = records.into_iter().map(|r| serde_json::json!({
+                        "id": r.id,
+                        "timestamp": r.timestamp,
+                        "operation": r.operation,
+                        "kind": r.kind.map(|k| k as u8),
+                        "category": r.category,
+                        "name": r.name,
+                        "error": r.error,
+                    })).collect::<Vec<_>>();
+                    cb(cb_id, ErrorCode::Success, rust_string_to_c(serde_json::Value::from(records).to_string()))
+                }
+                Err(err) => cb(cb_id, set_last_error(Some(err)), ptr::null()),
+            }
+        );
+        spawn_ok(async move {
+            let result = async {
+                let mut session = FFI_SESSIONS.borrow(handle).await?;
+                session.fetch_audit_log(category.as_deref(), since, limit).await
+            }.await;
+            cb.resolve(result);
+        });
+        Ok(ErrorCode::Success)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn askar_session_search(
+    handle: SessionHandle,
+    category: FfiStr<'_>,
+    text: FfiStr<'_>,
+    tag_filter: FfiStr<'_>,
+    limit: i64,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode, results: EntryListHandle)>,
+    cb_id: CallbackId,
+) -> ErrorCode {
+    catch_err! {
+        trace!("Search store");
+        let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
+        let category = category.into_opt_string();
+        let text = text.into_opt_string().ok_or_else(|| err_msg!("Search text not provided"))?;
+        let tag_filter = tag_filter.as_opt_str().map(TagFilter::from_str).transpose()?;
+        let limit = if limit < 0 { None } else {Some(limit)};
+        let cb = EnsureCallback::new(move |result|
+            match result {
+                Ok(rows) => {
+                    let results = EntryListHandle::create(FfiEntryList::from(rows));
+                    cb(cb_id, ErrorCode::Success, results)
+                }
+                Err(err) => cb(cb_id, set_last_error(Some(err)), EntryListHandle::invalid()),
+            }
+        );
+        spawn_ok(async move {
+            let result = async {
+                let mut session = FFI_SESSIONS.borrow(handle).await?;
+                session.search(category.as_deref(), &text, tag_filter, limit).await
+            }.await;
+            cb.resolve(result);
+        });
+        Ok(ErrorCode::Success)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn askar_session_remove_all(
+    handle: SessionHandle,
+    category: FfiStr<'_>,
+    tag_filter: FfiStr<'_>,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode, removed: i64)>,
     cb_id: CallbackId,
 ) -> ErrorCode {
     catch_err! {
         trace!("Count from store");
         let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
-        let category = category.into_opt_string();
-        let tag_filter = tag_filter.as_opt_str().map(TagFilter::from_str).transpose()?;
-        let cb = EnsureCallback::new(move |result: Result<i64,Error>|
+        let category = category.into_opt_string();
+        let tag_filter = tag_filter.as_opt_str().map(TagFilter::from_str).transpose()?;
+        let cb = EnsureCallback::new(move |result|
+            match result {
+                Ok(removed) => {
+                    cb(cb_id, ErrorCode::Success, removed)
+                }
+                Err(err) => cb(cb_id, set_last_error(Some(err)), 0),
+            }
+        );
+        spawn_ok(async move {
+            let result = async {
+                let mut session = FFI_SESSIONS.borrow(handle).await?;
+                session.remove_all(category.as_deref(), tag_filter).await
+            }.await;
+            cb.resolve(result);
+        });
+        Ok(ErrorCode::Success)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn askar_session_remove_all_returning(
+    handle: SessionHandle,
+    category: FfiStr<'_>,
+    tag_filter: FfiStr<'_>,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode, removed: *const c_char)>,
+    cb_id: CallbackId,
+) -> ErrorCode {
+    catch_err! {
+        trace!("Remove all from store");
+        let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
+        let category = category.into_opt_string();
+        let tag_filter = tag_filter.as_opt_str().map(TagFilter::from_str).transpose()?;
+        let cb = EnsureCallback::new(move |result: Result<Vec<EntryEvent>,Error>|
+            match result {
+                Ok(removed) => {
+                    let removed = removed.into_iter().map(|r| serde_json::json!({
+                        "category": r.category,
+                        "name": r.name,
+                    })).collect::<Vec<_>>();
+                    cb(cb_id, ErrorCode::Success, rust_string_to_c(serde_json::Value::from(removed).to_string()))
+                }
+                Err(err) => cb(cb_id, set_last_error(Some(err)), ptr::null()),
+            }
+        );
+        spawn_ok(async move {
+            let result = async {
+                let mut session = FFI_SESSIONS.borrow(handle).await?;
+                session.remove_all_returning(category.as_deref(), tag_filter).await
+            }.await;
+            cb.resolve(result);
+        });
+        Ok(ErrorCode::Success)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn askar_session_restore(
+    handle: SessionHandle,
+    category: FfiStr<'_>,
+    name: FfiStr<'_>,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode)>,
+    cb_id: CallbackId,
+) -> ErrorCode {
+    catch_err! {
+        trace!("Restore entry");
+        let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
+        let category = category.into_opt_string().ok_or_else(|| err_msg!("Entry category not provided"))?;
+        let name = name.into_opt_string().ok_or_else(|| err_msg!("Entry name not provided"))?;
+        let cb = EnsureCallback::new(move |result|
+            match result {
+                Ok(_) => cb(cb_id, ErrorCode::Success),
+                Err(err) => cb(cb_id, set_last_error(Some(err))),
+            }
+        );
+        spawn_ok(async move {
+            let result = async {
+                let mut session = FFI_SESSIONS.borrow(handle).await?;
+                session.restore(&category, &name).await
+            }.await;
+            cb.resolve(result);
+        });
+        Ok(ErrorCode::Success)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn askar_session_purge(
+    handle: SessionHandle,
+    category: FfiStr<'_>,
+    name: FfiStr<'_>,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode)>,
+    cb_id: CallbackId,
+) -> ErrorCode {
+    catch_err! {
+        trace!("Purge entry");
+        let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
+        let category = category.into_opt_string().ok_or_else(|| err_msg!("Entry category not provided"))?;
+        let name = name.into_opt_string().ok_or_else(|| err_msg!("Entry name not provided"))?;
+        let cb = EnsureCallback::new(move |result|
+            match result {
+                Ok(_) => cb(cb_id, ErrorCode::Success),
+                Err(err) => cb(cb_id, set_last_error(Some(err))),
+            }
+        );
+        spawn_ok(async move {
+            let result = async {
+                let mut session = FFI_SESSIONS.borrow(handle).await?;
+                session.purge(&category, &name).await
+            }.await;
+            cb.resolve(result);
+        });
+        Ok(ErrorCode::Success)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn askar_session_purge_all(
+    handle: SessionHandle,
+    category: FfiStr<'_>,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode, removed: i64)>,
+    cb_id: CallbackId,
+) -> ErrorCode {
+    catch_err! {
+        trace!("Purge all entries");
+        let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
+        let category = category.into_opt_string();
+        let cb = EnsureCallback::new(move |result|
+            match result {
+                Ok(removed) => cb(cb_id, ErrorCode::Success, removed),
+                Err(err) => cb(cb_id, set_last_error(Some(err)), 0),
+            }
+        );
+        spawn_ok(async move {
+            let result = async {
+                let mut session = FFI_SESSIONS.borrow(handle).await?;
+                session.purge_all(category.as_deref()).await
+            }.await;
+            cb.resolve(result);
+        });
+        Ok(ErrorCode::Success)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn askar_session_update(
+    handle: SessionHandle,
+    operation: i8,
+    category: FfiStr<'_>,
+    name: FfiStr<'_>,
+    value: ByteBuffer,
+    tags: FfiStr<'_>,
+    expiry_ms: i64,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode)>,
+    cb_id: CallbackId,
+) -> ErrorCode {
+    catch_err! {
+        trace!("Update store");
+        let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
+        let operation = match operation {
+            0 => EntryOperation::Insert,
+            1 => EntryOperation::Replace,
+            2 => EntryOperation::Remove,
+            _ => return Err(err_msg!("Invalid update operation"))
+        };
+        let category = category.into_opt_string().ok_or_else(|| err_msg!("Entry category not provided"))?;
+        let name = name.into_opt_string().ok_or_else(|| err_msg!("Entry name not provided"))?;
+        let value = value.as_slice().to_vec();
+        let tags = if let Some(tags) = tags.as_opt_str() {
+            Some(
+                serde_json::from_str::<EntryTagSet<'static>>(tags)
+                    .map_err(err_map!("Error decoding tags"))?
+                    .into_vec(),
+            )
+        } else {
+            None
+        };
+        let expiry_ms = if expiry_ms < 0 {
+            None
+        } else {
+            Some(expiry_ms)
+        };
+        let cb = EnsureCallback::new(move |result|
+            match result {
+                Ok(_) => cb(cb_id, ErrorCode::Success),
+                Err(err) => cb(cb_id, set_last_error(Some(err))),
+            }
+        );
+        spawn_ok(async move {
+            let result = async {
+                let mut session = FFI_SESSIONS.borrow(handle).await?;
+                session.update(operation, &category, &name, Some(value.as_slice()), tags.as_deref(), expiry_ms).await
+            }.await;
+            cb.resolve(result);
+        });
+        Ok(ErrorCode::Success)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn askar_session_move_category(
+    handle: SessionHandle,
+    category: FfiStr<'_>,
+    new_category: FfiStr<'_>,
+    rename_tags: FfiStr<'_>,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode, count: i64)>,
+    cb_id: CallbackId,
+) -> ErrorCode {
+    catch_err! {
+        trace!("Move entry category");
+        let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
+        let category = category.into_opt_string().ok_or_else(|| err_msg!("Entry category not provided"))?;
+        let new_category = new_category.into_opt_string().ok_or_else(|| err_msg!("New entry category not provided"))?;
+        let rename_tags = rename_tags.as_opt_str().map(serde_json::from_str::<BTreeMap<String, String>>)
+            .transpose()
+            .map_err(err_map!("Error decoding tag renames"))?
+            .unwrap_or_default();
+        let cb = EnsureCallback::new(move |result: Result<i64,Error>|
+            match result {
+                Ok(count) => cb(cb_id, ErrorCode::Success, count),
+                Err(err) => cb(cb_id, set_last_error(Some(err)), 0),
+            }
+        );
+        spawn_ok(async move {
+            let result = async {
+                let mut session = FFI_SESSIONS.borrow(handle).await?;
+                let rename_tags = rename_tags.iter().map(|(name, new_name)| (name.as_str(), new_name.as_str())).collect::<Vec<_>>();
+                session.move_category(&category, &new_category, &rename_tags).await
+            }.await;
+            cb.resolve(result);
+        });
+        Ok(ErrorCode::Success)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn askar_session_rename(
+    handle: SessionHandle,
+    category: FfiStr<'_>,
+    old_name: FfiStr<'_>,
+    new_name: FfiStr<'_>,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode)>,
+    cb_id: CallbackId,
+) -> ErrorCode {
+    catch_err! {
+        trace!("Rename entry");
+        let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
+        let category = category.into_opt_string().ok_or_else(|| err_msg!("Entry category not provided"))?;
+        let old_name = old_name.into_opt_string().ok_or_else(|| err_msg!("Entry name not provided"))?;
+        let new_name = new_name.into_opt_string().ok_or_else(|| err_msg!("New entry name not provided"))?;
+        let cb = EnsureCallback::new(move |result|
+            match result {
+                Ok(_) => cb(cb_id, ErrorCode::Success),
+                Err(err) => cb(cb_id, set_last_error(Some(err))),
+            }
+        );
+        spawn_ok(async move {
+            let result = async {
+                let mut session = FFI_SESSIONS.borrow(handle).await?;
+                session.rename(&category, &old_name, &new_name).await
+            }.await;
+            cb.resolve(result);
+        });
+        Ok(ErrorCode::Success)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn askar_session_update_tags(
+    handle: SessionHandle,
+    category: FfiStr<'_>,
+    name: FfiStr<'_>,
+    add_tags: FfiStr<'_>,
+    remove_tag_names: FfiStr<'_>,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode)>,
+    cb_id: CallbackId,
+) -> ErrorCode {
+    catch_err! {
+        trace!("Update entry tags");
+        let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
+        let category = category.into_opt_string().ok_or_else(|| err_msg!("Entry category not provided"))?;
+        let name = name.into_opt_string().ok_or_else(|| err_msg!("Entry name not provided"))?;
+        let add_tags = if let Some(tags) = add_tags.as_opt_str() {
+            serde_json::from_str::<EntryTagSet<'static>>(tags)
+                .map_err(err_map!("Error decoding tags"))?
+                .into_vec()
+        } else {
+            Vec::new()
+        };
+        let remove_tag_names = remove_tag_names.as_opt_str().map(serde_json::from_str::<Vec<String>>)
+            .transpose()
+            .map_err(err_map!("Error decoding tag names"))?
+            .unwrap_or_default();
+        let cb = EnsureCallback::new(move |result|
+            match result {
+                Ok(_) => cb(cb_id, ErrorCode::Success),
+                Err(err) => cb(cb_id, set_last_error(Some(err))),
+            }
+        );
+        spawn_ok(async move {
+            let result = async {
+                let mut session = FFI_SESSIONS.borrow(handle).await?;
+                let remove_tag_names = remove_tag_names.iter().map(String::as_str).collect::<Vec<_>>();
+                session.update_tags(&category, &name, &add_tags, &remove_tag_names).await
+            }.await;
+            cb.resolve(result);
+        });
+        Ok(ErrorCode::Success)
+    }
+}
+
+#[derive(Deserialize)]
+struct FfiInsertEntry {
+    category: String,
+    name: String,
+    value: String,
+    #[serde(default)]
+    tags: Option<EntryTagSet<'static>>,
+}
+
+#[no_mangle]
+pub extern "C" fn askar_session_update_if(
+    handle: SessionHandle,
+    operation: i8,
+    category: FfiStr<'_>,
+    name: FfiStr<'_>,
+    value: ByteBuffer,
+    tags: FfiStr<'_>,
+    expiry_ms: i64,
+    value_hash: ByteBuffer,
+    expected_tags: FfiStr<'_>,
+    expected_version: i64,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode)>,
+    cb_id: CallbackId,
+) -> ErrorCode {
+    catch_err! {
+        trace!("Update store with precondition");
+        let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
+        let operation = match operation {
+            1 => EntryOperation::Replace,
+            2 => EntryOperation::Remove,
+            _ => return Err(err_msg!("Invalid update operation"))
+        };
+        let category = category.into_opt_string().ok_or_else(|| err_msg!("Entry category not provided"))?;
+        let name = name.into_opt_string().ok_or_else(|| err_msg!("Entry name not provided"))?;
+        let value = value.as_slice().to_vec();
+        let tags = if let Some(tags) = tags.as_opt_str() {
+            Some(
+                serde_json::from_str::<EntryTagSet<'static>>(tags)
+                    .map_err(err_map!("Error decoding tags"))?
+                    .into_vec(),
+            )
+        } else {
+            None
+        };
+        let expiry_ms = if expiry_ms < 0 {
+            None
+        } else {
+            Some(expiry_ms)
+        };
+        let expected_version = if expected_version < 0 {
+            None
+        } else {
+            Some(expected_version)
+        };
+        let precondition = match (value_hash.as_slice(), expected_tags.as_opt_str(), expected_version) {
+            (hash, None, None) if !hash.is_empty() => EntryPrecondition::ValueHash(hash.to_vec()),
+            ([], Some(expected), None) => EntryPrecondition::Tags(
+                serde_json::from_str::<EntryTagSet<'static>>(expected)
+                    .map_err(err_map!("Error decoding expected tags"))?
+                    .into_vec(),
+            ),
+            ([], None, Some(version)) => EntryPrecondition::Version(version),
+            ([], None, None) => return Err(err_msg!("No precondition provided")),
+            _ => return Err(err_msg!("Only one precondition may be provided")),
+        };
+        let cb = EnsureCallback::new(move |result|
+            match result {
+                Ok(_) => cb(cb_id, ErrorCode::Success),
+                Err(err) => cb(cb_id, set_last_error(Some(err))),
+            }
+        );
+        spawn_ok(async move {
+            let result = async {
+                let mut session = FFI_SESSIONS.borrow(handle).await?;
+                session.update_if(operation, &category, &name, Some(value.as_slice()), tags.as_deref(), expiry_ms, &precondition).await
+            }.await;
+            cb.resolve(result);
+        });
+        Ok(ErrorCode::Success)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn askar_session_insert_many(
+    handle: SessionHandle,
+    entries: FfiStr<'_>,
+    expiry_ms: i64,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode)>,
+    cb_id: CallbackId,
+) -> ErrorCode {
+    catch_err! {
+        trace!("Insert entries");
+        let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
+        let entries = entries.as_opt_str().ok_or_else(|| err_msg!("No entries provided"))?;
+        let entries = serde_json::from_str::<Vec<FfiInsertEntry>>(entries)
+            .map_err(err_map!("Error decoding entries"))?
+            .into_iter()
+            .map(|entry| Entry::new(
+                EntryKind::Item,
+                entry.category,
+                entry.name,
+                entry.value.into_bytes(),
+                entry.tags.map(EntryTagSet::into_vec).unwrap_or_default(),
+            ))
+            .collect::<Vec<_>>();
+        let expiry_ms = if expiry_ms < 0 {
+            None
+        } else {
+            Some(expiry_ms)
+        };
+        let cb = EnsureCallback::new(move |result|
+            match result {
+                Ok(_) => cb(cb_id, ErrorCode::Success),
+                Err(err) => cb(cb_id, set_last_error(Some(err))),
+            }
+        );
+        spawn_ok(async move {
+            let result = async {
+                let mut session = FFI_SESSIONS.borrow(handle).await?;
+                session.insert_many(&entries, expiry_ms).await
+            }.await;
+            cb.resolve(result);
+        });
+        Ok(ErrorCode::Success)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn askar_session_insert_key(
+    handle: SessionHandle,
+    key_handle: LocalKeyHandle,
+    name: FfiStr<'_>,
+    metadata: FfiStr<'_>,
+    tags: FfiStr<'_>,
+    expiry_ms: i64,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode)>,
+    cb_id: CallbackId,
+) -> ErrorCode {
+    catch_err! {
+        trace!("Insert key");
+        let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
+        let key = key_handle.load()?;
+        let name = name.into_opt_string().ok_or_else(|| err_msg!("No key name provided"))?;
+        let metadata = metadata.into_opt_string();
+        let tags = if let Some(tags) = tags.as_opt_str() {
+            Some(
+                serde_json::from_str::<EntryTagSet<'static>>(tags)
+                    .map_err(err_map!("Error decoding tags"))?
+                    .into_vec(),
+            )
+        } else {
+            None
+        };
+        let expiry_ms = if expiry_ms < 0 {
+            None
+        } else {
+            Some(expiry_ms)
+        };
+        let cb = EnsureCallback::new(move |result|
+            match result {
+                Ok(_) => {
+                    cb(cb_id, ErrorCode::Success)
+                }
+                Err(err) => cb(cb_id, set_last_error(Some(err))),
+            }
+        );
+
+        spawn_ok(async move {
+            let result = async {
+                let mut session = FFI_SESSIONS.borrow(handle).await?;
+                session.insert_key(
+                    name.as_str(),
+                    &key,
+                    metadata.as_deref(),
+                    tags.as_deref(),
+                    expiry_ms,
+                ).await
+            }.await;
+            cb.resolve(result);
+        });
+        Ok(ErrorCode::Success)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn askar_session_insert_key_by_thumbprint(
+    handle: SessionHandle,
+    key_handle: LocalKeyHandle,
+    metadata: FfiStr<'_>,
+    tags: FfiStr<'_>,
+    expiry_ms: i64,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode, name: *const c_char)>,
+    cb_id: CallbackId,
+) -> ErrorCode {
+    catch_err! {
+        trace!("Insert key by thumbprint");
+        let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
+        let key = key_handle.load()?;
+        let metadata = metadata.into_opt_string();
+        let tags = if let Some(tags) = tags.as_opt_str() {
+            Some(
+                serde_json::from_str::<EntryTagSet<'static>>(tags)
+                    .map_err(err_map!("Error decoding tags"))?
+                    .into_vec(),
+            )
+        } else {
+            None
+        };
+        let expiry_ms = if expiry_ms < 0 {
+            None
+        } else {
+            Some(expiry_ms)
+        };
+        let cb = EnsureCallback::new(move |result|
+            match result {
+                Ok(name) => cb(cb_id, ErrorCode::Success, rust_string_to_c(name)),
+                Err(err) => cb(cb_id, set_last_error(Some(err)), ptr::null_mut()),
+            }
+        );
+
+        spawn_ok(async move {
+            let result = async {
+                let mut session = FFI_SESSIONS.borrow(handle).await?;
+                session.insert_key_by_thumbprint(
+                    &key,
+                    metadata.as_deref(),
+                    tags.as_deref(),
+                    expiry_ms,
+                ).await
+            }.await;
+            cb.resolve(result);
+        });
+        Ok(ErrorCode::Success)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn askar_session_fetch_key(
+    handle: SessionHandle,
+    name: FfiStr<'_>,
+    for_update: i8,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode, results: KeyEntryListHandle)>,
+    cb_id: CallbackId,
+) -> ErrorCode {
+    catch_err! {
+        trace!("Fetch key");
+        let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
+        let name = name.into_opt_string().ok_or_else(|| err_msg!("No key name provided"))?;
+
+        let cb = EnsureCallback::new(move |result|
+            match result {
+                Ok(Some(entry)) => {
+                    let results = KeyEntryListHandle::create(FfiKeyEntryList::from(entry));
+                    cb(cb_id, ErrorCode::Success, results)
+                }
+                Ok(None) => {
+                    cb(cb_id, ErrorCode::Success, KeyEntryListHandle::invalid())
+                }
+                Err(err) => cb(cb_id, set_last_error(Some(err)), KeyEntryListHandle::invalid()),
+            }
+        );
+
+        spawn_ok(async move {
+            let result = async {
+                let mut session = FFI_SESSIONS.borrow(handle).await?;
+                session.fetch_key(
+                    name.as_str(),
+                    for_update != 0
+                ).await
+            }.await;
+            cb.resolve(result);
+        });
+        Ok(ErrorCode::Success)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn askar_session_load_key(
+    handle: SessionHandle,
+    name: FfiStr<'_>,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode, key: LocalKeyHandle)>,
+    cb_id: CallbackId,
+) -> ErrorCode {
+    catch_err! {
+        trace!("Load key");
+        let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
+        let name = name.into_opt_string().ok_or_else(|| err_msg!("No key name provided"))?;
+
+        let cb = EnsureCallback::new(move |result|
             match result {
-                Ok(count) => cb(cb_id, ErrorCode::Success, count),
-                Err(err) => cb(cb_id, set_last_error(Some(err)), 0),
+                Ok(Some(key)) => cb(cb_id, ErrorCode::Success, LocalKeyHandle::from_arc(key)),
+                Ok(None) => cb(cb_id, ErrorCode::Success, LocalKeyHandle::invalid()),
+                Err(err) => cb(cb_id, set_last_error(Some(err)), LocalKeyHandle::invalid()),
             }
         );
+
         spawn_ok(async move {
             let result = async {
                 let mut session = FFI_SESSIONS.borrow(handle).await?;
-                session.count(category.as_deref(), tag_filter).await
+                session.load_key(name.as_str()).await
             }.await;
             cb.resolve(result);
         });
@@ -692,33 +2615,38 @@ pub extern "C" fn askar_session_count(
 }
 
 #[no_mangle]
-pub extern "C" fn askar_session_fetch(
+pub extern "C" fn askar_session_fetch_key_by_thumbprint(
     handle: SessionHandle,
-    category: FfiStr<'_>,
-    name: FfiStr<'_>,
+    thumbprint: FfiStr<'_>,
     for_update: i8,
-    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode, results: EntryListHandle)>,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode, results: KeyEntryListHandle)>,
     cb_id: CallbackId,
 ) -> ErrorCode {
     catch_err! {
-        trace!("Fetch from store");
+        trace!("Fetch key by thumbprint");
         let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
-        let category = category.into_opt_string().ok_or_else(|| err_msg!("Category not provided"))?;
-        let name = name.into_opt_string().ok_or_else(|| err_msg!("Name not provided"))?;
-        let cb = EnsureCallback::new(move |result: Result<Option<Entry>,Error>|
+        let thumbprint = thumbprint.into_opt_string().ok_or_else(|| err_msg!("No key thumbprint provided"))?;
+
+        let cb = EnsureCallback::new(move |result|
             match result {
                 Ok(Some(entry)) => {
-                    let results = EntryListHandle::create(FfiEntryList::from(entry));
+                    let results = KeyEntryListHandle::create(FfiKeyEntryList::from(entry));
                     cb(cb_id, ErrorCode::Success, results)
-                },
-                Ok(None) => cb(cb_id, ErrorCode::Success, EntryListHandle::invalid()),
-                Err(err) => cb(cb_id, set_last_error(Some(err)), EntryListHandle::invalid()),
+                }
+                Ok(None) => {
+                    cb(cb_id, ErrorCode::Success, KeyEntryListHandle::invalid())
+                }
+                Err(err) => cb(cb_id, set_last_error(Some(err)), KeyEntryListHandle::invalid()),
             }
         );
+
         spawn_ok(async move {
             let result = async {
                 let mut session = FFI_SESSIONS.borrow(handle).await?;
-                session.fetch(&category, &name, for_update != 0).await
+                session.fetch_key_by_thumbprint(
+                    thumbprint.as_str(),
+                    for_update != 0
+                ).await
             }.await;
             cb.resolve(result);
         });
@@ -727,34 +2655,53 @@ pub extern "C" fn askar_session_fetch(
 }
 
 #[no_mangle]
-pub extern "C" fn askar_session_fetch_all(
+pub extern "C" fn askar_session_fetch_all_keys(
     handle: SessionHandle,
-    category: FfiStr<'_>,
+    alg: FfiStr<'_>,
+    thumbprint: FfiStr<'_>,
     tag_filter: FfiStr<'_>,
+    created_after: i64,
+    created_before: i64,
+    metadata: FfiStr<'_>,
     limit: i64,
     for_update: i8,
-    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode, results: EntryListHandle)>,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode, results: KeyEntryListHandle)>,
     cb_id: CallbackId,
 ) -> ErrorCode {
     catch_err! {
-        trace!("Count from store");
+        trace!("Fetch all keys");
         let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
-        let category = category.into_opt_string();
+        let alg = alg.into_opt_string();
+        let thumbprint = thumbprint.into_opt_string();
         let tag_filter = tag_filter.as_opt_str().map(TagFilter::from_str).transpose()?;
+        let created_after = if created_after < 0 { None } else { Some(created_after) };
+        let created_before = if created_before < 0 { None } else { Some(created_before) };
+        let metadata = metadata.into_opt_string();
         let limit = if limit < 0 { None } else {Some(limit)};
+
         let cb = EnsureCallback::new(move |result|
             match result {
-                Ok(rows) => {
-                    let results = EntryListHandle::create(FfiEntryList::from(rows));
+                Ok(entries) => {
+                    let results = KeyEntryListHandle::create(FfiKeyEntryList::from(entries));
                     cb(cb_id, ErrorCode::Success, results)
                 }
-                Err(err) => cb(cb_id, set_last_error(Some(err)), EntryListHandle::invalid()),
+                Err(err) => cb(cb_id, set_last_error(Some(err)), KeyEntryListHandle::invalid()),
             }
         );
+
         spawn_ok(async move {
             let result = async {
                 let mut session = FFI_SESSIONS.borrow(handle).await?;
-                session.fetch_all(category.as_deref(), tag_filter, limit, for_update != 0).await
+                session.fetch_all_keys(
+                    alg.as_deref(),
+                    thumbprint.as_deref(),
+                    tag_filter,
+                    created_after,
+                    created_before,
+                    metadata.as_deref(),
+                    limit,
+                    for_update != 0
+                ).await
             }.await;
             cb.resolve(result);
         });
@@ -763,30 +2710,35 @@ pub extern "C" fn askar_session_fetch_all(
 }
 
 #[no_mangle]
-pub extern "C" fn askar_session_remove_all(
+pub extern "C" fn askar_session_fetch_expiring_keys(
     handle: SessionHandle,
-    category: FfiStr<'_>,
-    tag_filter: FfiStr<'_>,
-    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode, removed: i64)>,
+    expires_before: i64,
+    limit: i64,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode, results: KeyEntryListHandle)>,
     cb_id: CallbackId,
 ) -> ErrorCode {
     catch_err! {
-        trace!("Count from store");
+        trace!("Fetch expiring keys");
         let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
-        let category = category.into_opt_string();
-        let tag_filter = tag_filter.as_opt_str().map(TagFilter::from_str).transpose()?;
+        let limit = if limit < 0 { None } else {Some(limit)};
+
         let cb = EnsureCallback::new(move |result|
             match result {
-                Ok(removed) => {
-                    cb(cb_id, ErrorCode::Success, removed)
+                Ok(entries) => {
+                    let results = KeyEntryListHandle::create(FfiKeyEntryList::from(entries));
+                    cb(cb_id, ErrorCode::Success, results)
                 }
-                Err(err) => cb(cb_id, set_last_error(Some(err)), 0),
+                Err(err) => cb(cb_id, set_last_error(Some(err)), KeyEntryListHandle::invalid()),
             }
         );
+
         spawn_ok(async move {
             let result = async {
                 let mut session = FFI_SESSIONS.borrow(handle).await?;
-                session.remove_all(category.as_deref(), tag_filter).await
+                session.fetch_expiring_keys(
+                    expires_before,
+                    limit,
+                ).await
             }.await;
             cb.resolve(result);
         });
@@ -795,29 +2747,20 @@ pub extern "C" fn askar_session_remove_all(
 }
 
 #[no_mangle]
-pub extern "C" fn askar_session_update(
+pub extern "C" fn askar_session_update_key(
     handle: SessionHandle,
-    operation: i8,
-    category: FfiStr<'_>,
     name: FfiStr<'_>,
-    value: ByteBuffer,
+    metadata: FfiStr<'_>,
     tags: FfiStr<'_>,
     expiry_ms: i64,
     cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode)>,
     cb_id: CallbackId,
 ) -> ErrorCode {
     catch_err! {
-        trace!("Update store");
+        trace!("Update key");
         let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
-        let operation = match operation {
-            0 => EntryOperation::Insert,
-            1 => EntryOperation::Replace,
-            2 => EntryOperation::Remove,
-            _ => return Err(err_msg!("Invalid update operation"))
-        };
-        let category = category.into_opt_string().ok_or_else(|| err_msg!("Entry category not provided"))?;
-        let name = name.into_opt_string().ok_or_else(|| err_msg!("Entry name not provided"))?;
-        let value = value.as_slice().to_vec();
+        let name = name.into_opt_string().ok_or_else(|| err_msg!("No key name provided"))?;
+        let metadata = metadata.into_opt_string();
         let tags = if let Some(tags) = tags.as_opt_str() {
             Some(
                 serde_json::from_str::<EntryTagSet<'static>>(tags)
@@ -834,14 +2777,23 @@ pub extern "C" fn askar_session_update(
         };
         let cb = EnsureCallback::new(move |result|
             match result {
-                Ok(_) => cb(cb_id, ErrorCode::Success),
+                Ok(_) => {
+                    cb(cb_id, ErrorCode::Success)
+                }
                 Err(err) => cb(cb_id, set_last_error(Some(err))),
             }
         );
+
         spawn_ok(async move {
             let result = async {
                 let mut session = FFI_SESSIONS.borrow(handle).await?;
-                session.update(operation, &category, &name, Some(value.as_slice()), tags.as_deref(), expiry_ms).await
+                session.update_key(
+                    &name,
+                    metadata.as_deref(),
+                    tags.as_deref(),
+                    expiry_ms,
+
+                ).await
             }.await;
             cb.resolve(result);
         });
@@ -850,36 +2802,68 @@ pub extern "C" fn askar_session_update(
 }
 
 #[no_mangle]
-pub extern "C" fn askar_session_insert_key(
+pub extern "C" fn askar_session_set_key_json_metadata(
     handle: SessionHandle,
-    key_handle: LocalKeyHandle,
     name: FfiStr<'_>,
     metadata: FfiStr<'_>,
-    tags: FfiStr<'_>,
-    expiry_ms: i64,
+    indexed: FfiStr<'_>,
     cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode)>,
     cb_id: CallbackId,
 ) -> ErrorCode {
     catch_err! {
-        trace!("Insert key");
+        trace!("Set key JSON metadata");
         let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
-        let key = key_handle.load()?;
         let name = name.into_opt_string().ok_or_else(|| err_msg!("No key name provided"))?;
-        let metadata = metadata.into_opt_string();
-        let tags = if let Some(tags) = tags.as_opt_str() {
+        let metadata = metadata.as_opt_str().map(serde_json::from_str::<serde_json::Value>)
+            .transpose()
+            .map_err(err_map!("Error decoding key metadata"))?;
+        let indexed = indexed.as_opt_str().map(serde_json::from_str::<Vec<String>>)
+            .transpose()
+            .map_err(err_map!("Error decoding indexed fields"))?
+            .unwrap_or_default();
+        let cb = EnsureCallback::new(move |result|
+            match result {
+                Ok(_) => cb(cb_id, ErrorCode::Success),
+                Err(err) => cb(cb_id, set_last_error(Some(err))),
+            }
+        );
+
+        spawn_ok(async move {
+            let result = async {
+                let indexed: Vec<&str> = indexed.iter().map(String::as_str).collect();
+                let mut session = FFI_SESSIONS.borrow(handle).await?;
+                session.set_key_json_metadata(
+                    &name,
+                    metadata.as_ref(),
+                    &indexed,
+                ).await
+            }.await;
+            cb.resolve(result);
+        });
+        Ok(ErrorCode::Success)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn askar_session_set_key_policy(
+    handle: SessionHandle,
+    name: FfiStr<'_>,
+    policy: FfiStr<'_>,
+    cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode)>,
+    cb_id: CallbackId,
+) -> ErrorCode {
+    catch_err! {
+        trace!("Set key policy");
+        let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
+        let name = name.into_opt_string().ok_or_else(|| err_msg!("No key name provided"))?;
+        let policy = if let Some(policy) = policy.as_opt_str() {
             Some(
-                serde_json::from_str::<EntryTagSet<'static>>(tags)
-                    .map_err(err_map!("Error decoding tags"))?
-                    .into_vec(),
+                serde_json::from_str::<KeyPolicy>(policy)
+                    .map_err(err_map!("Error decoding key policy"))?,
             )
         } else {
             None
         };
-        let expiry_ms = if expiry_ms < 0 {
-            None
-        } else {
-            Some(expiry_ms)
-        };
         let cb = EnsureCallback::new(move |result|
             match result {
                 Ok(_) => {
@@ -892,12 +2876,9 @@ pub extern "C" fn askar_session_insert_key(
         spawn_ok(async move {
             let result = async {
                 let mut session = FFI_SESSIONS.borrow(handle).await?;
-                session.insert_key(
-                    name.as_str(),
-                    &key,
-                    metadata.as_deref(),
-                    tags.as_deref(),
-                    expiry_ms,
+                session.set_key_policy(
+                    &name,
+                    policy.as_ref(),
                 ).await
             }.await;
             cb.resolve(result);
@@ -907,27 +2888,22 @@ pub extern "C" fn askar_session_insert_key(
 }
 
 #[no_mangle]
-pub extern "C" fn askar_session_fetch_key(
+pub extern "C" fn askar_session_rotate_key(
     handle: SessionHandle,
     name: FfiStr<'_>,
-    for_update: i8,
     cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode, results: KeyEntryListHandle)>,
     cb_id: CallbackId,
 ) -> ErrorCode {
     catch_err! {
-        trace!("Fetch key");
+        trace!("Rotate key");
         let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
         let name = name.into_opt_string().ok_or_else(|| err_msg!("No key name provided"))?;
-
         let cb = EnsureCallback::new(move |result|
             match result {
-                Ok(Some(entry)) => {
+                Ok(entry) => {
                     let results = KeyEntryListHandle::create(FfiKeyEntryList::from(entry));
                     cb(cb_id, ErrorCode::Success, results)
                 }
-                Ok(None) => {
-                    cb(cb_id, ErrorCode::Success, KeyEntryListHandle::invalid())
-                }
                 Err(err) => cb(cb_id, set_last_error(Some(err)), KeyEntryListHandle::invalid()),
             }
         );
@@ -935,9 +2911,8 @@ pub extern "C" fn askar_session_fetch_key(
         spawn_ok(async move {
             let result = async {
                 let mut session = FFI_SESSIONS.borrow(handle).await?;
-                session.fetch_key(
-                    name.as_str(),
-                    for_update != 0
+                session.rotate_key(
+                    &name,
                 ).await
             }.await;
             cb.resolve(result);
@@ -947,24 +2922,16 @@ pub extern "C" fn askar_session_fetch_key(
 }
 
 #[no_mangle]
-pub extern "C" fn askar_session_fetch_all_keys(
+pub extern "C" fn askar_session_fetch_key_versions(
     handle: SessionHandle,
-    alg: FfiStr<'_>,
-    thumbprint: FfiStr<'_>,
-    tag_filter: FfiStr<'_>,
-    limit: i64,
-    for_update: i8,
+    name: FfiStr<'_>,
     cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode, results: KeyEntryListHandle)>,
     cb_id: CallbackId,
 ) -> ErrorCode {
     catch_err! {
-        trace!("Fetch all keys");
+        trace!("Fetch key versions");
         let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
-        let alg = alg.into_opt_string();
-        let thumbprint = thumbprint.into_opt_string();
-        let tag_filter = tag_filter.as_opt_str().map(TagFilter::from_str).transpose()?;
-        let limit = if limit < 0 { None } else {Some(limit)};
-
+        let name = name.into_opt_string().ok_or_else(|| err_msg!("No key name provided"))?;
         let cb = EnsureCallback::new(move |result|
             match result {
                 Ok(entries) => {
@@ -978,12 +2945,8 @@ pub extern "C" fn askar_session_fetch_all_keys(
         spawn_ok(async move {
             let result = async {
                 let mut session = FFI_SESSIONS.borrow(handle).await?;
-                session.fetch_all_keys(
-                    alg.as_deref(),
-                    thumbprint.as_deref(),
-                    tag_filter,
-                    limit,
-                    for_update != 0
+                session.fetch_key_versions(
+                    &name,
                 ).await
             }.await;
             cb.resolve(result);
@@ -993,34 +2956,16 @@ pub extern "C" fn askar_session_fetch_all_keys(
 }
 
 #[no_mangle]
-pub extern "C" fn askar_session_update_key(
+pub extern "C" fn askar_session_remove_key(
     handle: SessionHandle,
     name: FfiStr<'_>,
-    metadata: FfiStr<'_>,
-    tags: FfiStr<'_>,
-    expiry_ms: i64,
     cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode)>,
     cb_id: CallbackId,
 ) -> ErrorCode {
     catch_err! {
-        trace!("Update key");
+        trace!("Remove key");
         let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
         let name = name.into_opt_string().ok_or_else(|| err_msg!("No key name provided"))?;
-        let metadata = metadata.into_opt_string();
-        let tags = if let Some(tags) = tags.as_opt_str() {
-            Some(
-                serde_json::from_str::<EntryTagSet<'static>>(tags)
-                    .map_err(err_map!("Error decoding tags"))?
-                    .into_vec(),
-            )
-        } else {
-            None
-        };
-        let expiry_ms = if expiry_ms < 0 {
-            None
-        } else {
-            Some(expiry_ms)
-        };
         let cb = EnsureCallback::new(move |result|
             match result {
                 Ok(_) => {
@@ -1033,12 +2978,8 @@ pub extern "C" fn askar_session_update_key(
         spawn_ok(async move {
             let result = async {
                 let mut session = FFI_SESSIONS.borrow(handle).await?;
-                session.update_key(
+                session.remove_key(
                     &name,
-                    metadata.as_deref(),
-                    tags.as_deref(),
-                    expiry_ms,
-
                 ).await
             }.await;
             cb.resolve(result);
@@ -1048,31 +2989,34 @@ pub extern "C" fn askar_session_update_key(
 }
 
 #[no_mangle]
-pub extern "C" fn askar_session_remove_key(
+pub extern "C" fn askar_session_savepoint(
     handle: SessionHandle,
     name: FfiStr<'_>,
+    operation: i8,
     cb: Option<extern "C" fn(cb_id: CallbackId, err: ErrorCode)>,
     cb_id: CallbackId,
 ) -> ErrorCode {
     catch_err! {
-        trace!("Remove key");
+        trace!("Savepoint");
         let cb = cb.ok_or_else(|| err_msg!("No callback provided"))?;
-        let name = name.into_opt_string().ok_or_else(|| err_msg!("No key name provided"))?;
+        let name = name.into_opt_string().ok_or_else(|| err_msg!("Savepoint name not provided"))?;
+        if !(0..=2).contains(&operation) {
+            return Err(err_msg!("Invalid savepoint operation"));
+        }
         let cb = EnsureCallback::new(move |result|
             match result {
-                Ok(_) => {
-                    cb(cb_id, ErrorCode::Success)
-                }
+                Ok(_) => cb(cb_id, ErrorCode::Success),
                 Err(err) => cb(cb_id, set_last_error(Some(err))),
             }
         );
-
         spawn_ok(async move {
             let result = async {
                 let mut session = FFI_SESSIONS.borrow(handle).await?;
-                session.remove_key(
-                    &name,
-                ).await
+                match operation {
+                    0 => session.savepoint(&name).await,
+                    1 => session.release_savepoint(&name).await,
+                    _ => session.rollback_to_savepoint(&name).await,
+                }
             }.await;
             cb.resolve(result);
         });
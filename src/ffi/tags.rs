@@ -2,9 +2,10 @@ use std::{borrow::Cow, fmt};
 
 use serde::{
     de::{Error as SerdeError, MapAccess, SeqAccess, Visitor},
-    ser::SerializeMap,
+    ser::{Error as _, SerializeMap},
     Deserialize, Deserializer, Serialize, Serializer,
 };
+use serde_json::Number;
 
 use crate::entry::EntryTag;
 
@@ -62,23 +63,28 @@ impl<'de> Deserialize<'de> for EntryTagSet<'static> {
                         None => return Err(M::Error::custom("invalid tag name: empty string")),
                         _ => (key.to_owned(), true),
                     };
-                    match (values, enc) {
-                        (EntryTagValues::Single(value), true) => {
-                            v.push(EntryTag::Encrypted(tag, value))
-                        }
-                        (EntryTagValues::Single(value), false) => {
-                            v.push(EntryTag::Plaintext(tag, value))
-                        }
-                        (EntryTagValues::Multiple(values), true) => {
-                            for value in values {
-                                v.push(EntryTag::Encrypted(tag.clone(), value))
+                    let values = match values {
+                        EntryTagValues::Single(value) => vec![value],
+                        EntryTagValues::Multiple(values) => values,
+                    };
+                    for value in values {
+                        v.push(match (value, enc) {
+                            (EntryTagValue::Text(value), true) => {
+                                EntryTag::Encrypted(tag.clone(), value)
+                            }
+                            (EntryTagValue::Text(value), false) => {
+                                EntryTag::Plaintext(tag.clone(), value)
+                            }
+                            (EntryTagValue::Number(value), false) => {
+                                EntryTag::Numeric(tag.clone(), value)
                             }
-                        }
-                        (EntryTagValues::Multiple(values), false) => {
-                            for value in values {
-                                v.push(EntryTag::Plaintext(tag.clone(), value))
+                            (EntryTagValue::Number(_), true) => {
+                                return Err(M::Error::custom(format!(
+                                    "invalid tag value: numeric tag must be plaintext: {}",
+                                    tag
+                                )))
                             }
-                        }
+                        });
                     }
                 }
 
@@ -91,8 +97,8 @@ impl<'de> Deserialize<'de> for EntryTagSet<'static> {
 }
 
 enum EntryTagValues {
-    Single(String),
-    Multiple(Vec<String>),
+    Single(EntryTagValue),
+    Multiple(Vec<EntryTagValue>),
 }
 
 impl<'de> Deserialize<'de> for EntryTagValues {
@@ -106,21 +112,44 @@ impl<'de> Deserialize<'de> for EntryTagValues {
             type Value = EntryTagValues;
 
             fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
-                formatter.write_str("a string or list of strings")
+                formatter.write_str("a string, number, or list of strings and numbers")
             }
 
             fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
             where
                 E: SerdeError,
             {
-                Ok(EntryTagValues::Single(value.to_owned()))
+                TagValueVisitor.visit_str(value).map(EntryTagValues::Single)
             }
 
             fn visit_string<E>(self, value: String) -> Result<Self::Value, E>
             where
                 E: SerdeError,
             {
-                Ok(EntryTagValues::Single(value))
+                TagValueVisitor
+                    .visit_string(value)
+                    .map(EntryTagValues::Single)
+            }
+
+            fn visit_i64<E>(self, value: i64) -> Result<Self::Value, E>
+            where
+                E: SerdeError,
+            {
+                TagValueVisitor.visit_i64(value).map(EntryTagValues::Single)
+            }
+
+            fn visit_u64<E>(self, value: u64) -> Result<Self::Value, E>
+            where
+                E: SerdeError,
+            {
+                TagValueVisitor.visit_u64(value).map(EntryTagValues::Single)
+            }
+
+            fn visit_f64<E>(self, value: f64) -> Result<Self::Value, E>
+            where
+                E: SerdeError,
+            {
+                TagValueVisitor.visit_f64(value).map(EntryTagValues::Single)
             }
 
             fn visit_seq<S>(self, mut access: S) -> Result<Self::Value, S::Error>
@@ -139,6 +168,85 @@ impl<'de> Deserialize<'de> for EntryTagValues {
     }
 }
 
+/// A single tag value, where a JSON number indicates a numeric tag
+enum EntryTagValue {
+    Text(String),
+    Number(String),
+}
+
+struct TagValueVisitor;
+
+impl<'d> Visitor<'d> for TagValueVisitor {
+    type Value = EntryTagValue;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("a string or number")
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+    where
+        E: SerdeError,
+    {
+        Ok(EntryTagValue::Text(value.to_owned()))
+    }
+
+    fn visit_string<E>(self, value: String) -> Result<Self::Value, E>
+    where
+        E: SerdeError,
+    {
+        Ok(EntryTagValue::Text(value))
+    }
+
+    fn visit_i64<E>(self, value: i64) -> Result<Self::Value, E>
+    where
+        E: SerdeError,
+    {
+        Ok(EntryTagValue::Number(value.to_string()))
+    }
+
+    fn visit_u64<E>(self, value: u64) -> Result<Self::Value, E>
+    where
+        E: SerdeError,
+    {
+        Ok(EntryTagValue::Number(value.to_string()))
+    }
+
+    fn visit_f64<E>(self, value: f64) -> Result<Self::Value, E>
+    where
+        E: SerdeError,
+    {
+        Number::from_f64(value)
+            .map(|value| EntryTagValue::Number(value.to_string()))
+            .ok_or_else(|| E::custom("invalid tag value: non-finite number"))
+    }
+}
+
+impl<'de> Deserialize<'de> for EntryTagValue {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(TagValueVisitor)
+    }
+}
+
+impl Serialize for EntryTagValue {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            Self::Text(value) => serializer.serialize_str(value),
+            Self::Number(value) => value
+                .parse::<Number>()
+                .ok()
+                .or_else(|| value.parse::<f64>().ok().and_then(Number::from_f64))
+                .ok_or_else(|| S::Error::custom("invalid numeric tag value"))?
+                .serialize(serializer),
+        }
+    }
+}
+
 impl Serialize for EntryTagSet<'_> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -173,8 +281,18 @@ impl Serialize for EntryTagSet<'_> {
         let mut tags = BTreeMap::new();
         for tag in self.0.iter() {
             let (name, value) = match tag {
-                EntryTag::Encrypted(name, val) => (TagName(name.as_str(), true), val.as_str()),
-                EntryTag::Plaintext(name, val) => (TagName(name.as_str(), false), val.as_str()),
+                EntryTag::Encrypted(name, val) => (
+                    TagName(name.as_str(), true),
+                    EntryTagValue::Text(val.clone()),
+                ),
+                EntryTag::Plaintext(name, val) => (
+                    TagName(name.as_str(), false),
+                    EntryTagValue::Text(val.clone()),
+                ),
+                EntryTag::Numeric(name, val) => (
+                    TagName(name.as_str(), false),
+                    EntryTagValue::Number(val.clone()),
+                ),
             };
             tags.entry(name).or_insert_with(Vec::new).push(value);
         }
@@ -207,4 +325,26 @@ mod tests {
         let tags2 = serde_json::from_str(&ser).unwrap();
         assert_eq!(tags, tags2);
     }
+
+    #[test]
+    fn serialize_numeric_tags() {
+        let tags = EntryTagSet::from(vec![
+            EntryTag::Numeric("a".to_owned(), "5".to_owned()),
+            EntryTag::Numeric("b".to_owned(), "1.5e10".to_owned()),
+            EntryTag::Plaintext("b".to_owned(), "bval".to_owned()),
+        ]);
+        let ser = serde_json::to_string(&tags).unwrap();
+        assert_eq!(ser, r#"{"~a":5,"~b":[15000000000.0,"bval"]}"#);
+        let tags2: EntryTagSet<'static> =
+            serde_json::from_str(r#"{"~a":5,"~b":["bval",1.5e10]}"#).unwrap();
+        assert_eq!(
+            tags2.into_vec(),
+            vec![
+                EntryTag::Numeric("a".to_owned(), "5".to_owned()),
+                EntryTag::Plaintext("b".to_owned(), "bval".to_owned()),
+                EntryTag::Numeric("b".to_owned(), "15000000000.0".to_owned()),
+            ]
+        );
+        assert!(serde_json::from_str::<EntryTagSet<'static>>(r#"{"a":5}"#).is_err());
+    }
 }
@@ -0,0 +1,221 @@
+//! Signed attestation statements describing stored keys
+//!
+//! An attestation statement records the properties of a stored key, such as
+//! its algorithm, public key, creation time, storage and usage policy, and is
+//! signed by a designated attestation key held in the same store. A relying
+//! party which trusts the attestation key may then rely on the properties of
+//! the attested key. Statements are produced using `Session::attest_key`,
+//! either as a JWS in compact serialization or as a COSE_Sign1 structure.
+
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use serde_cbor::Value as CborValue;
+
+use super::{local_key::KeyAlg, policy::KeyPolicy};
+use crate::{crypto::alg::EcCurves, error::Error};
+
+/// The JWS `typ` header value of an attestation statement
+pub const ATTESTATION_JWS_TYPE: &str = "key-attestation+jwt";
+
+/// The CBOR tag of a COSE_Sign1 structure
+const COSE_SIGN1_TAG: u8 = 0xd2;
+
+/// The storage of a stored key
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyStorage {
+    /// A key held by the store itself
+    Software,
+    /// A key held by a hardware token or security module
+    Hardware,
+    /// A key held by a secure enclave or trusted execution environment
+    Enclave,
+    /// A key held by a remote key service
+    Remote,
+}
+
+impl KeyStorage {
+    /// Get a reference to a string representing the `KeyStorage`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Software => "software",
+            Self::Hardware => "hardware",
+            Self::Enclave => "enclave",
+            Self::Remote => "remote",
+        }
+    }
+}
+
+impl Display for KeyStorage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Supported formats of attestation statements
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AttestationFormat {
+    /// A JWS in compact serialization with a JSON payload
+    Jws,
+    /// A tagged COSE_Sign1 structure with a CBOR payload
+    Cose,
+}
+
+impl AttestationFormat {
+    /// Get a reference to a string representing the `AttestationFormat`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Jws => "jws",
+            Self::Cose => "cose",
+        }
+    }
+}
+
+impl FromStr for AttestationFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "jws" => Ok(Self::Jws),
+            "cose" => Ok(Self::Cose),
+            _ => Err(err_msg!("Unknown attestation format: {}", s)),
+        }
+    }
+}
+
+/// The claims of an attestation statement
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct KeyAttestation {
+    /// The name of the attested key entry
+    pub name: String,
+    /// The algorithm of the attested key
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alg: Option<String>,
+    /// The public JWK of the attested key, if it is not a symmetric key
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jwk: Option<serde_json::Value>,
+    /// The creation time of the key, in seconds since the Unix epoch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created: Option<i64>,
+    /// The version number of the key
+    pub version: u32,
+    /// The storage of the key
+    pub storage: KeyStorage,
+    /// The usage policy of the key
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub policy: Option<KeyPolicy>,
+    /// The time the statement was issued, in seconds since the Unix epoch
+    pub iat: i64,
+    /// A challenge provided by the relying party
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<String>,
+}
+
+/// An attestation statement pending its signature
+pub(crate) struct AttestationBuilder {
+    format: AttestationFormat,
+    protected: Vec<u8>,
+    payload: Vec<u8>,
+}
+
+impl AttestationBuilder {
+    /// Encode the claims and protected header for a signature by a key of
+    /// the algorithm `alg`, identified by `kid`
+    pub fn new(
+        format: AttestationFormat,
+        claims: &KeyAttestation,
+        alg: KeyAlg,
+        kid: &str,
+    ) -> Result<Self, Error> {
+        let (jws_alg, cose_alg) = signature_algorithm(alg)?;
+        let (protected, payload) = match format {
+            AttestationFormat::Jws => {
+                let header = serde_json::json!({
+                    "alg": jws_alg,
+                    "typ": ATTESTATION_JWS_TYPE,
+                    "kid": kid,
+                });
+                let payload = serde_json::to_vec(claims)
+                    .map_err(err_map!(Unexpected, "Error serializing attestation"))?;
+                (
+                    URL_SAFE_NO_PAD.encode(header.to_string()).into_bytes(),
+                    URL_SAFE_NO_PAD.encode(payload).into_bytes(),
+                )
+            }
+            AttestationFormat::Cose => {
+                let header = BTreeMap::from([
+                    (CborValue::Integer(1), CborValue::Integer(cose_alg.into())),
+                    (
+                        CborValue::Integer(4),
+                        CborValue::Bytes(kid.as_bytes().to_vec()),
+                    ),
+                ]);
+                (cbor_encode(&header)?, cbor_encode(claims)?)
+            }
+        };
+        Ok(Self {
+            format,
+            protected,
+            payload,
+        })
+    }
+
+    /// Get the input to be signed by the attestation key
+    pub fn signing_input(&self) -> Result<Vec<u8>, Error> {
+        match self.format {
+            AttestationFormat::Jws => Ok([&self.protected[..], b".", &self.payload[..]].concat()),
+            AttestationFormat::Cose => cbor_encode(&CborValue::Array(vec![
+                CborValue::Text("Signature1".to_string()),
+                CborValue::Bytes(self.protected.clone()),
+                CborValue::Bytes(Vec::new()),
+                CborValue::Bytes(self.payload.clone()),
+            ])),
+        }
+    }
+
+    /// Produce the signed attestation statement
+    pub fn finish(self, signature: &[u8]) -> Result<Vec<u8>, Error> {
+        match self.format {
+            AttestationFormat::Jws => {
+                let mut result = self.signing_input()?;
+                result.push(b'.');
+                result.extend_from_slice(URL_SAFE_NO_PAD.encode(signature).as_bytes());
+                Ok(result)
+            }
+            AttestationFormat::Cose => {
+                let mut result = vec![COSE_SIGN1_TAG];
+                result.extend(cbor_encode(&CborValue::Array(vec![
+                    CborValue::Bytes(self.protected),
+                    CborValue::Map(BTreeMap::new()),
+                    CborValue::Bytes(self.payload),
+                    CborValue::Bytes(signature.to_vec()),
+                ]))?);
+                Ok(result)
+            }
+        }
+    }
+}
+
+/// Look up the JWS and COSE signature algorithms for a key algorithm
+fn signature_algorithm(alg: KeyAlg) -> Result<(&'static str, i8), Error> {
+    match alg {
+        KeyAlg::Ed25519 => Ok(("EdDSA", -8)),
+        KeyAlg::EcCurve(EcCurves::Secp256r1) => Ok(("ES256", -7)),
+        KeyAlg::EcCurve(EcCurves::Secp384r1) => Ok(("ES384", -35)),
+        KeyAlg::EcCurve(EcCurves::Secp256k1) => Ok(("ES256K", -47)),
+        _ => Err(err_msg!(
+            Unsupported,
+            "Unsupported attestation key algorithm: {}",
+            alg
+        )),
+    }
+}
+
+fn cbor_encode(value: &impl serde::Serialize) -> Result<Vec<u8>, Error> {
+    serde_cbor::to_vec(value).map_err(err_map!(Unexpected, "Error encoding CBOR"))
+}
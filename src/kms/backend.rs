@@ -0,0 +1,123 @@
+//! Support for key operations performed by a remote key service
+//!
+//! A key backend performs private key operations asynchronously on behalf of
+//! the store, such as for keys held by a cloud KMS or a custom signing
+//! service. Key entries are associated with a backend through the scheme of
+//! their reference, and operations on the entry through the `Session` key
+//! operation methods are delegated to the backend registered for the scheme.
+//! Entries without a registered backend are loaded and used locally.
+
+use std::{
+    collections::BTreeMap,
+    fmt::Debug,
+    sync::{Arc, RwLock},
+};
+
+use once_cell::sync::Lazy;
+
+use super::{
+    attestation::KeyStorage,
+    enc::Encrypted,
+    local_key::{KeyAlg, LocalKey},
+};
+use crate::{crypto::buffer::SecretBytes, error::Error, future::BoxFuture};
+
+static KEY_BACKENDS: Lazy<RwLock<BTreeMap<String, Arc<dyn KeyBackend>>>> =
+    Lazy::new(|| RwLock::new(BTreeMap::new()));
+
+/// A service performing private key operations for referenced keys
+///
+/// Each method is passed the reference of the stored key entry. Operations
+/// which are not supported by the backend fail by default.
+pub trait KeyBackend: Debug + Send + Sync {
+    /// Sign a message with the private key
+    fn sign<'a>(
+        &'a self,
+        reference: &'a str,
+        message: &'a [u8],
+        sig_type: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<Vec<u8>, Error>>;
+
+    /// Perform a Diffie-Hellman exchange between the private key and a public
+    /// key, returning the raw shared secret
+    fn key_exchange<'a>(
+        &'a self,
+        reference: &'a str,
+        public: &'a LocalKey,
+    ) -> BoxFuture<'a, Result<SecretBytes, Error>> {
+        let _ = (reference, public);
+        Box::pin(async {
+            Err(err_msg!(
+                Unsupported,
+                "Key backend does not support key exchange"
+            ))
+        })
+    }
+
+    /// Wrap (encrypt) another key
+    fn wrap_key<'a>(
+        &'a self,
+        reference: &'a str,
+        key: &'a LocalKey,
+        nonce: &'a [u8],
+    ) -> BoxFuture<'a, Result<Encrypted, Error>> {
+        let _ = (reference, key, nonce);
+        Box::pin(async {
+            Err(err_msg!(
+                Unsupported,
+                "Key backend does not support wrapping"
+            ))
+        })
+    }
+
+    /// Unwrap (decrypt) a key which was previously wrapped
+    fn unwrap_key<'a>(
+        &'a self,
+        reference: &'a str,
+        alg: KeyAlg,
+        ciphertext: &'a [u8],
+        nonce: &'a [u8],
+    ) -> BoxFuture<'a, Result<LocalKey, Error>> {
+        let _ = (reference, alg, ciphertext, nonce);
+        Box::pin(async {
+            Err(err_msg!(
+                Unsupported,
+                "Key backend does not support wrapping"
+            ))
+        })
+    }
+
+    /// The storage of the keys held by the backend, as reported in key
+    /// attestation statements
+    fn storage(&self) -> KeyStorage {
+        KeyStorage::Remote
+    }
+}
+
+/// Register a key backend for references using the URI scheme `scheme`,
+/// replacing any backend previously registered for the scheme
+pub fn register_key_backend(scheme: &str, backend: Arc<dyn KeyBackend>) {
+    KEY_BACKENDS
+        .write()
+        .unwrap()
+        .insert(scheme.to_ascii_lowercase(), backend);
+}
+
+/// Remove the key backend registered for the URI scheme `scheme`
+pub fn unregister_key_backend(scheme: &str) -> bool {
+    KEY_BACKENDS
+        .write()
+        .unwrap()
+        .remove(&scheme.to_ascii_lowercase())
+        .is_some()
+}
+
+/// Find the backend responsible for a key reference, if any
+pub(crate) fn key_backend(reference: &str) -> Option<Arc<dyn KeyBackend>> {
+    let (scheme, _) = reference.split_once(':')?;
+    KEY_BACKENDS
+        .read()
+        .unwrap()
+        .get(&scheme.to_ascii_lowercase())
+        .cloned()
+}
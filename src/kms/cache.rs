@@ -0,0 +1,87 @@
+//! A cache of loaded keys shared between the sessions of a store
+
+use std::{
+    collections::HashMap,
+    fmt::{self, Debug, Formatter},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use super::local_key::LocalKey;
+
+#[derive(Default)]
+struct CacheState {
+    ttl: Option<Duration>,
+    keys: HashMap<(String, String), (Arc<LocalKey>, Instant)>,
+}
+
+/// A cache of decrypted keys, indexed by profile and key name
+///
+/// The cache is disabled until a time-to-live is assigned, and each key is
+/// retained until its time-to-live elapses, the key entry is modified through
+/// a session of the same store, or the cache is purged.
+#[derive(Default)]
+pub(crate) struct LocalKeyCache(Mutex<CacheState>);
+
+impl LocalKeyCache {
+    /// Enable the cache with the time-to-live `ttl`, or disable and purge the
+    /// cache if `ttl` is `None`
+    pub fn set_ttl(&self, ttl: Option<Duration>) {
+        let mut state = self.0.lock().unwrap();
+        state.ttl = ttl;
+        if ttl.is_none() {
+            state.keys.clear();
+        }
+    }
+
+    /// Look up an unexpired key in the cache
+    pub fn get(&self, profile: &str, name: &str) -> Option<Arc<LocalKey>> {
+        let mut state = self.0.lock().unwrap();
+        let cache_key = (profile.to_string(), name.to_string());
+        match state.keys.get(&cache_key) {
+            Some((key, expires)) if *expires > Instant::now() => Some(key.clone()),
+            Some(_) => {
+                state.keys.remove(&cache_key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// Add a key to the cache, if it is enabled
+    pub fn insert(&self, profile: &str, name: &str, key: Arc<LocalKey>) {
+        let mut state = self.0.lock().unwrap();
+        let Some(ttl) = state.ttl else {
+            return;
+        };
+        let now = Instant::now();
+        state.keys.retain(|_, (_, expires)| *expires > now);
+        state
+            .keys
+            .insert((profile.to_string(), name.to_string()), (key, now + ttl));
+    }
+
+    /// Remove a key from the cache
+    pub fn remove(&self, profile: &str, name: &str) {
+        self.0
+            .lock()
+            .unwrap()
+            .keys
+            .remove(&(profile.to_string(), name.to_string()));
+    }
+
+    /// Remove all keys from the cache
+    pub fn purge(&self) {
+        self.0.lock().unwrap().keys.clear();
+    }
+}
+
+impl Debug for LocalKeyCache {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let state = self.0.lock().unwrap();
+        f.debug_struct("LocalKeyCache")
+            .field("ttl", &state.ttl)
+            .field("keys", &state.keys.len())
+            .finish()
+    }
+}
@@ -1,10 +1,44 @@
-use super::local_key::LocalKey;
+use std::{
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use futures_lite::stream::Stream;
+
+use super::{
+    attestation::KeyStorage,
+    backend::{key_backend, KeyBackend},
+    local_key::{KeyAlg, LocalKey},
+    policy::{now_secs, KeyOperation, KeyPolicy},
+    provider::key_provider,
+};
 use crate::{
     crypto::{alg::AnyKey, buffer::SecretBytes, jwk::FromJwk},
-    entry::{Entry, EntryTag},
+    entry::{Entry, EntryTag, Scan},
     error::Error,
 };
 
+/// The numeric tag recording the creation time of a key entry
+pub(crate) const KEY_CREATED_TAG: &str = "created";
+
+/// The numeric tag recording the expiry time of the key usage policy
+pub(crate) const KEY_EXPIRES_TAG: &str = "expires";
+
+/// The numeric tag recording the version number of a key entry
+pub(crate) const KEY_VERSION_TAG: &str = "version";
+
+/// The numeric tag recording the time a key version was superseded
+pub(crate) const KEY_ROTATED_TAG: &str = "rotated";
+
+/// The prefix of the tags recording indexed fields of the JSON key metadata
+pub(crate) const KEY_METADATA_TAG_PREFIX: &str = "meta:";
+
+/// The entry name used to store a superseded version of a key
+pub(crate) fn key_version_name(name: &str, version: u32) -> String {
+    format!("{}#{}", name, version)
+}
+
 /// Parameters defining a stored key
 #[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
 pub struct KeyParams {
@@ -12,13 +46,22 @@ pub struct KeyParams {
     #[serde(default, rename = "meta", skip_serializing_if = "Option::is_none")]
     pub metadata: Option<String>,
 
+    /// Structured key metadata, a JSON object
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub json: Option<serde_json::Value>,
+
     /// An optional external reference for the key
     #[serde(default, rename = "ref", skip_serializing_if = "Option::is_none")]
     pub reference: Option<String>,
 
-    /// The associated key data (JWK)
+    /// The associated key data (JWK), containing only the public key for
+    /// an external key
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub data: Option<SecretBytes>,
+
+    /// Restrictions on the usage of the key
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub policy: Option<KeyPolicy>,
 }
 
 impl KeyParams {
@@ -34,6 +77,46 @@ impl KeyParams {
     }
 }
 
+/// Build the tags exposing the fields `indexed` of the JSON key metadata
+///
+/// Indexed fields must contain a string, number or boolean value, or an array
+/// of such values. Fields which are not present are not indexed.
+pub(crate) fn key_metadata_tags(
+    metadata: &serde_json::Value,
+    indexed: &[&str],
+) -> Result<Vec<EntryTag>, Error> {
+    let fields = metadata
+        .as_object()
+        .ok_or_else(|| err_msg!(Input, "Key metadata must be a JSON object"))?;
+    let mut tags = Vec::with_capacity(indexed.len());
+    for field in indexed {
+        let values = match fields.get(*field) {
+            None => continue,
+            Some(serde_json::Value::Array(values)) => values.as_slice(),
+            Some(value) => std::slice::from_ref(value),
+        };
+        for value in values {
+            let value = match value {
+                serde_json::Value::String(s) => s.clone(),
+                serde_json::Value::Number(n) => n.to_string(),
+                serde_json::Value::Bool(b) => b.to_string(),
+                _ => {
+                    return Err(err_msg!(
+                        Input,
+                        "Unsupported value for indexed key metadata field: {}",
+                        field
+                    ))
+                }
+            };
+            tags.push(EntryTag::Encrypted(
+                format!("{}{}", KEY_METADATA_TAG_PREFIX, field),
+                value,
+            ));
+        }
+    }
+    Ok(tags)
+}
+
 /// A stored key entry
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct KeyEntry {
@@ -47,6 +130,12 @@ pub struct KeyEntry {
     pub(crate) thumbprints: Vec<String>,
     /// Thumbprints for the key
     pub(crate) tags: Vec<EntryTag>,
+    /// The creation time of the key, in seconds since the Unix epoch
+    pub(crate) created: Option<i64>,
+    /// The version number of the key
+    pub(crate) version: u32,
+    /// The time the key version was superseded, in seconds since the Unix epoch
+    pub(crate) rotated: Option<i64>,
 }
 
 impl KeyEntry {
@@ -55,11 +144,64 @@ impl KeyEntry {
         self.alg.as_ref().map(String::as_ref)
     }
 
+    /// Accessor for the key creation time, in seconds since the Unix epoch
+    ///
+    /// This is not recorded for keys inserted by earlier versions of the store.
+    pub fn created(&self) -> Option<i64> {
+        self.created
+    }
+
+    /// Accessor for the version number of the key, starting at 1 and
+    /// incremented each time the key is rotated
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Accessor for the time this key version was replaced by a newer version,
+    /// in seconds since the Unix epoch
+    ///
+    /// This is only recorded for superseded versions of a rotated key.
+    pub fn rotated(&self) -> Option<i64> {
+        self.rotated
+    }
+
+    /// Determine if this entry is a superseded version of a rotated key
+    pub fn is_superseded(&self) -> bool {
+        self.rotated.is_some()
+    }
+
+    /// Accessor for the usage policy attached to the key
+    pub fn policy(&self) -> Option<&KeyPolicy> {
+        self.params.policy.as_ref()
+    }
+
+    /// Accessor for the time before which the key may not be used, in seconds
+    /// since the Unix epoch
+    pub fn not_before(&self) -> Option<i64> {
+        self.policy().and_then(|p| p.not_before)
+    }
+
+    /// Accessor for the time from which the key may no longer be used, in
+    /// seconds since the Unix epoch
+    pub fn expires_at(&self) -> Option<i64> {
+        self.policy().and_then(|p| p.expires_at)
+    }
+
+    /// Determine if the current time is within the validity window of the key
+    pub fn is_active(&self) -> bool {
+        self.policy().map_or(true, |p| p.is_valid_at(now_secs()))
+    }
+
     /// Accessor for the stored key metadata
     pub fn metadata(&self) -> Option<&str> {
         self.params.metadata.as_ref().map(String::as_ref)
     }
 
+    /// Accessor for the structured key metadata
+    pub fn json_metadata(&self) -> Option<&serde_json::Value> {
+        self.params.json.as_ref()
+    }
+
     /// Accessor for the key identity
     pub fn name(&self) -> &str {
         self.name.as_str()
@@ -78,6 +220,9 @@ impl KeyEntry {
     pub(crate) fn from_entry(entry: Entry) -> Result<Self, Error> {
         let params = KeyParams::from_slice(&entry.value)?;
         let mut alg = None;
+        let mut created = None;
+        let mut version = None;
+        let mut rotated = None;
         let mut thumbprints = Vec::new();
         let mut tags = entry.tags;
         let mut idx = 0;
@@ -91,6 +236,15 @@ impl KeyEntry {
                 alg.replace(tags.remove(idx).into_value());
             } else if name == "thumb" {
                 thumbprints.push(tags.remove(idx).into_value());
+            } else if name == KEY_CREATED_TAG {
+                created = tags.remove(idx).into_value().parse().ok();
+            } else if name == KEY_EXPIRES_TAG {
+                // derived from the key policy
+                tags.remove(idx);
+            } else if name == KEY_VERSION_TAG {
+                version = tags.remove(idx).into_value().parse().ok();
+            } else if name == KEY_ROTATED_TAG {
+                rotated = tags.remove(idx).into_value().parse().ok();
             } else {
                 // unrecognized tag
                 tags.remove(idx).into_value();
@@ -99,27 +253,160 @@ impl KeyEntry {
         // keep sorted for checking equality
         thumbprints.sort();
         tags.sort();
+        let version = version.unwrap_or(1);
+        let mut name = entry.name;
+        if rotated.is_some() {
+            // superseded versions are stored under a derived name
+            let suffix_len = key_version_name("", version).len();
+            name.truncate(name.len().saturating_sub(suffix_len));
+        }
         Ok(Self {
-            name: entry.name,
+            name,
             params,
             alg,
             thumbprints,
             tags,
+            created,
+            version,
+            rotated,
         })
     }
 
     /// Create a local key instance from this key storage entry
+    ///
+    /// For an external key, the key is loaded from the key provider
+    /// registered for the scheme of its reference, and checked against the
+    /// stored public key.
     pub fn load_local_key(&self) -> Result<LocalKey, Error> {
-        if let Some(key_data) = self.params.data.as_ref() {
+        if let Some(reference) = self.params.reference.as_deref() {
+            let mut key = LocalKey::from_reference(reference)?;
+            if let Some(key_data) = self.params.data.as_ref() {
+                let public = LocalKey {
+                    inner: Box::<AnyKey>::from_jwk_slice(key_data.as_ref())?,
+                    ephemeral: false,
+                    policy: None,
+                    external: None,
+                };
+                if public.to_public_bytes()? != key.to_public_bytes()? {
+                    return Err(err_msg!(
+                        Input,
+                        "External key does not match the stored public key"
+                    ));
+                }
+            }
+            key.policy = self.params.policy.clone();
+            Ok(key)
+        } else if let Some(key_data) = self.params.data.as_ref() {
             let inner = Box::<AnyKey>::from_jwk_slice(key_data.as_ref())?;
             Ok(LocalKey {
                 inner,
                 ephemeral: false,
+                policy: self.params.policy.clone(),
+                external: None,
             })
         } else {
             Err(err_msg!("Missing key data"))
         }
     }
+
+    /// Find the key backend responsible for the reference of this entry
+    pub(crate) fn key_backend(&self) -> Option<(Arc<dyn KeyBackend>, &str)> {
+        let reference = self.params.reference.as_deref()?;
+        Some((key_backend(reference)?, reference))
+    }
+
+    /// Determine the storage of the key
+    ///
+    /// The storage of an external key is reported by its key backend or key
+    /// provider, and external keys without a registered provider are assumed
+    /// to be held in hardware.
+    pub fn storage(&self) -> KeyStorage {
+        let Some(reference) = self.params.reference.as_deref() else {
+            return KeyStorage::Software;
+        };
+        if let Some((backend, _)) = self.key_backend() {
+            return backend.storage();
+        }
+        reference
+            .split_once(':')
+            .and_then(|(scheme, _)| key_provider(scheme).ok())
+            .map_or(KeyStorage::Hardware, |provider| provider.storage())
+    }
+
+    pub(crate) fn check_policy(&self, operation: KeyOperation) -> Result<(), Error> {
+        self.params
+            .policy
+            .as_ref()
+            .map_or(Ok(()), |policy| policy.check(operation))
+    }
+
+    pub(crate) fn check_algorithm(&self, alg: KeyAlg) -> Result<(), Error> {
+        self.params
+            .policy
+            .as_ref()
+            .map_or(Ok(()), |policy| policy.check_algorithm(alg))
+    }
+
+    /// Create a public key instance from this key storage entry
+    ///
+    /// The key provider of an external key is not required.
+    pub fn load_public_key(&self) -> Result<LocalKey, Error> {
+        let key_data = self
+            .params
+            .data
+            .as_ref()
+            .ok_or_else(|| err_msg!("Missing key data"))?;
+        let key = LocalKey {
+            inner: Box::<AnyKey>::from_jwk_slice(key_data.as_ref())?,
+            ephemeral: false,
+            policy: None,
+            external: None,
+        };
+        let mut public = LocalKey::from_public_bytes(key.algorithm(), &key.to_public_bytes()?)?;
+        public.policy = self.params.policy.clone();
+        Ok(public)
+    }
+}
+
+/// An active scan of the key entries in a store
+///
+/// Key entries may be fetched a page at a time using `fetch_next`, or the scan
+/// may be consumed as a `Stream` of individual key entries.
+#[derive(Debug)]
+pub struct KeyScan(Scan<'static, Entry>);
+
+impl KeyScan {
+    pub(crate) fn new(scan: Scan<'static, Entry>) -> Self {
+        Self(scan)
+    }
+
+    /// Fetch the next set of key entries, or `None` if the scan is complete
+    pub async fn fetch_next(&mut self) -> Result<Option<Vec<KeyEntry>>, Error> {
+        if let Some(rows) = self.0.fetch_next().await? {
+            let mut entries = Vec::with_capacity(rows.len());
+            for row in rows {
+                entries.push(KeyEntry::from_entry(row)?);
+            }
+            Ok(Some(entries))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// A token which may be used to resume the scan after the last fetched key
+    pub fn continuation(&self) -> Option<&str> {
+        self.0.continuation()
+    }
+}
+
+impl Stream for KeyScan {
+    type Item = Result<KeyEntry, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.0)
+            .poll_next(cx)
+            .map(|row| row.map(|row| row.map_err(Error::from).and_then(KeyEntry::from_entry)))
+    }
 }
 
 #[cfg(test)]
@@ -130,11 +417,36 @@ mod tests {
     fn key_params_roundtrip() {
         let params = KeyParams {
             metadata: Some("meta".to_string()),
+            json: Some(serde_json::json!({"purpose": "auth", "rotation": {"days": 90}})),
             reference: None,
             data: Some(SecretBytes::from(vec![0, 0, 0, 0])),
+            policy: None,
         };
         let enc_params = params.to_bytes().unwrap();
         let p2 = KeyParams::from_slice(&enc_params).unwrap();
         assert_eq!(p2, params);
     }
+
+    #[test]
+    fn key_metadata_indexed_tags() {
+        let metadata = serde_json::json!({
+            "purpose": "auth",
+            "controller": ["did:example:1", "did:example:2"],
+            "days": 90,
+            "rotation": {"days": 90},
+        });
+        let tags =
+            key_metadata_tags(&metadata, &["purpose", "controller", "days", "missing"]).unwrap();
+        assert_eq!(
+            tags,
+            vec![
+                EntryTag::Encrypted("meta:purpose".to_string(), "auth".to_string()),
+                EntryTag::Encrypted("meta:controller".to_string(), "did:example:1".to_string()),
+                EntryTag::Encrypted("meta:controller".to_string(), "did:example:2".to_string()),
+                EntryTag::Encrypted("meta:days".to_string(), "90".to_string()),
+            ]
+        );
+        assert!(key_metadata_tags(&metadata, &["rotation"]).is_err());
+        assert!(key_metadata_tags(&serde_json::json!("meta"), &[]).is_err());
+    }
 }
@@ -1,4 +1,4 @@
-use super::local_key::LocalKey;
+use super::{local_key::LocalKey, policy::KeyOperation};
 use crate::{
     crypto::{
         alg::{x25519::X25519KeyPair, KeyAlg},
@@ -37,6 +37,7 @@ pub fn crypto_box(
     message: &[u8],
     nonce: &[u8],
 ) -> Result<Vec<u8>, Error> {
+    sender_x25519.check_operation(KeyOperation::Derive)?;
     let recip_pk = cast_x25519(recip_x25519)?;
     let sender_sk = cast_x25519(sender_x25519)?;
     let mut buffer = SecretBytes::from_slice_reserve(message, CBOX_TAG_LENGTH);
@@ -51,6 +52,7 @@ pub fn crypto_box_open(
     message: &[u8],
     nonce: &[u8],
 ) -> Result<SecretBytes, Error> {
+    recip_x25519.check_operation(KeyOperation::Derive)?;
     let recip_pk = cast_x25519(recip_x25519)?;
     let sender_sk = cast_x25519(sender_x25519)?;
     let mut buffer = SecretBytes::from_slice(message);
@@ -70,6 +72,7 @@ pub fn crypto_box_seal_open(
     recip_x25519: &LocalKey,
     ciphertext: &[u8],
 ) -> Result<SecretBytes, Error> {
+    recip_x25519.check_operation(KeyOperation::Derive)?;
     let kp = cast_x25519(recip_x25519)?;
     Ok(nacl_box_seal_open(kp, ciphertext)?)
 }
@@ -87,6 +90,9 @@ pub fn derive_key_ecdh_1pu(
     cc_tag: &[u8],
     receive: bool,
 ) -> Result<LocalKey, Error> {
+    for key in [ephem_key, sender_key, recip_key] {
+        key.check_algorithm(key_alg)?;
+    }
     let derive = Ecdh1PU::new(
         ephem_key, sender_key, recip_key, alg_id, apu, apv, cc_tag, receive,
     );
@@ -103,6 +109,63 @@ pub fn derive_key_ecdh_es(
     apv: &[u8],
     receive: bool,
 ) -> Result<LocalKey, Error> {
+    for key in [ephem_key, recip_key] {
+        key.check_algorithm(key_alg)?;
+    }
     let derive = EcdhEs::new(ephem_key, recip_key, alg_id, apu, apv, receive);
     LocalKey::from_key_derivation(key_alg, derive)
 }
+
+/// Derive ECDH-1PU shared keys for authenticated encryption to multiple
+/// recipients, as when wrapping a content encryption key for each recipient
+/// of a message
+///
+/// The derived keys are returned in the order of the recipient keys.
+#[allow(clippy::too_many_arguments)]
+pub fn derive_keys_ecdh_1pu(
+    key_alg: KeyAlg,
+    ephem_key: &LocalKey,
+    sender_key: &LocalKey,
+    recip_keys: &[&LocalKey],
+    alg_id: &[u8],
+    apu: &[u8],
+    apv: &[u8],
+    cc_tag: &[u8],
+) -> Result<Vec<LocalKey>, Error> {
+    for key in [ephem_key, sender_key].iter().chain(recip_keys) {
+        key.check_algorithm(key_alg)?;
+    }
+    recip_keys
+        .iter()
+        .map(|recip_key| {
+            let derive = Ecdh1PU::new(
+                ephem_key, sender_key, recip_key, alg_id, apu, apv, cc_tag, false,
+            );
+            LocalKey::from_key_derivation(key_alg, derive)
+        })
+        .collect()
+}
+
+/// Derive ECDH-ES shared keys for anonymous encryption to multiple recipients,
+/// sharing a single ephemeral key
+///
+/// The derived keys are returned in the order of the recipient keys.
+pub fn derive_keys_ecdh_es(
+    key_alg: KeyAlg,
+    ephem_key: &LocalKey,
+    recip_keys: &[&LocalKey],
+    alg_id: &[u8],
+    apu: &[u8],
+    apv: &[u8],
+) -> Result<Vec<LocalKey>, Error> {
+    for key in [ephem_key].iter().chain(recip_keys) {
+        key.check_algorithm(key_alg)?;
+    }
+    recip_keys
+        .iter()
+        .map(|recip_key| {
+            let derive = EcdhEs::new(ephem_key, recip_key, alg_id, apu, apv, false);
+            LocalKey::from_key_derivation(key_alg, derive)
+        })
+        .collect()
+}
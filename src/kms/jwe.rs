@@ -0,0 +1,235 @@
+//! Compact JWE encryption of keys to a recipient public key
+//!
+//! Keys are wrapped using direct key agreement (`ECDH-ES`) with an ephemeral
+//! key of the same algorithm as the recipient key, and the secret JWK of the
+//! key is encrypted as the JWE payload using the content encryption
+//! algorithm selected by the caller. Arbitrary payloads may also be
+//! encrypted using the flattened JSON serialization.
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+
+use super::{
+    envelope::derive_key_ecdh_es,
+    local_key::{KeyAlg, LocalKey},
+};
+use crate::{
+    crypto::{
+        alg::{AesTypes, Chacha20Types, EcCurves},
+        buffer::SecretBytes,
+    },
+    error::Error,
+};
+
+/// The JWE key management algorithm used for wrapped keys
+const JWE_ALG_ECDH_ES: &str = "ECDH-ES";
+
+/// The JWE content type of a wrapped key
+const JWE_CONTENT_TYPE: &str = "jwk+json";
+
+/// Look up the JWE content encryption algorithm for a key algorithm
+fn content_encryption(alg: KeyAlg) -> Result<&'static str, Error> {
+    match alg {
+        KeyAlg::Aes(AesTypes::A128Gcm) => Ok("A128GCM"),
+        KeyAlg::Aes(AesTypes::A256Gcm) => Ok("A256GCM"),
+        KeyAlg::Aes(AesTypes::A128CbcHs256) => Ok("A128CBC-HS256"),
+        KeyAlg::Aes(AesTypes::A256CbcHs512) => Ok("A256CBC-HS512"),
+        KeyAlg::Chacha20(Chacha20Types::C20P) => Ok("C20P"),
+        KeyAlg::Chacha20(Chacha20Types::XC20P) => Ok("XC20P"),
+        _ => Err(err_msg!(
+            Unsupported,
+            "Unsupported content encryption algorithm: {}",
+            alg
+        )),
+    }
+}
+
+fn content_encryption_alg(enc: &str) -> Result<KeyAlg, Error> {
+    Ok(match enc {
+        "A128GCM" => KeyAlg::Aes(AesTypes::A128Gcm),
+        "A256GCM" => KeyAlg::Aes(AesTypes::A256Gcm),
+        "A128CBC-HS256" => KeyAlg::Aes(AesTypes::A128CbcHs256),
+        "A256CBC-HS512" => KeyAlg::Aes(AesTypes::A256CbcHs512),
+        "C20P" => KeyAlg::Chacha20(Chacha20Types::C20P),
+        "XC20P" => KeyAlg::Chacha20(Chacha20Types::XC20P),
+        _ => {
+            return Err(err_msg!(
+                Unsupported,
+                "Unsupported content encryption algorithm: {}",
+                enc
+            ))
+        }
+    })
+}
+
+fn check_recipient(recip_key: &LocalKey) -> Result<(), Error> {
+    match recip_key.algorithm() {
+        KeyAlg::X25519
+        | KeyAlg::EcCurve(EcCurves::Secp256r1)
+        | KeyAlg::EcCurve(EcCurves::Secp384r1)
+        | KeyAlg::EcCurve(EcCurves::Secp256k1) => Ok(()),
+        alg => Err(err_msg!(
+            Unsupported,
+            "Unsupported recipient key algorithm: {}",
+            alg
+        )),
+    }
+}
+
+fn decode_part(part: &str) -> Result<Vec<u8>, Error> {
+    URL_SAFE_NO_PAD
+        .decode(part)
+        .map_err(err_map!(Input, "Invalid JWE encoding"))
+}
+
+/// The encoded protected header and the encrypted payload of a JWE
+struct JweParts {
+    protected: String,
+    nonce: Vec<u8>,
+    ciphertext: Vec<u8>,
+    tag: Vec<u8>,
+}
+
+fn encrypt_parts(
+    payload: &[u8],
+    recip_key: &LocalKey,
+    enc_alg: KeyAlg,
+    content_type: &str,
+) -> Result<JweParts, Error> {
+    let enc = content_encryption(enc_alg)?;
+    check_recipient(recip_key)?;
+    let ephem_key = LocalKey::generate(recip_key.algorithm(), true)?;
+    let epk: serde_json::Value = serde_json::from_str(&ephem_key.to_jwk_public(None)?)
+        .map_err(err_map!(Unexpected, "Error encoding ephemeral key"))?;
+    let header = serde_json::json!({
+        "alg": JWE_ALG_ECDH_ES,
+        "enc": enc,
+        "cty": content_type,
+        "epk": epk,
+    });
+    let protected = URL_SAFE_NO_PAD.encode(header.to_string());
+    let cek = derive_key_ecdh_es(
+        enc_alg,
+        &ephem_key,
+        recip_key,
+        enc.as_bytes(),
+        &[],
+        &[],
+        false,
+    )?;
+    let enc = cek.aead_encrypt(payload, &[], protected.as_bytes())?;
+    Ok(JweParts {
+        nonce: enc.nonce().to_vec(),
+        ciphertext: enc.ciphertext().to_vec(),
+        tag: enc.tag().to_vec(),
+        protected,
+    })
+}
+
+fn decrypt_parts(parts: JweParts, recip_key: &LocalKey) -> Result<SecretBytes, Error> {
+    let header: serde_json::Value = serde_json::from_slice(&decode_part(&parts.protected)?)
+        .map_err(err_map!(Input, "Invalid JWE header"))?;
+    if header["alg"] != JWE_ALG_ECDH_ES {
+        return Err(err_msg!(Unsupported, "Unsupported JWE key management"));
+    }
+    let enc = header["enc"]
+        .as_str()
+        .ok_or_else(|| err_msg!(Input, "Missing JWE content encryption algorithm"))?;
+    let enc_alg = content_encryption_alg(enc)?;
+    if !header["epk"].is_object() {
+        return Err(err_msg!(Input, "Missing JWE ephemeral key"));
+    }
+    let ephem_key = LocalKey::from_jwk(&header["epk"].to_string())?;
+    if ephem_key.algorithm() != recip_key.algorithm() {
+        return Err(err_msg!(Input, "JWE ephemeral key algorithm mismatch"));
+    }
+    let cek = derive_key_ecdh_es(
+        enc_alg,
+        &ephem_key,
+        recip_key,
+        enc.as_bytes(),
+        &[],
+        &[],
+        true,
+    )?;
+    let mut buf = parts.ciphertext;
+    buf.extend(parts.tag);
+    cek.aead_decrypt(buf.as_slice(), &parts.nonce, parts.protected.as_bytes())
+}
+
+/// Encrypt a payload to a recipient public key, producing a compact JWE
+pub(crate) fn encrypt_compact(
+    payload: &[u8],
+    recip_key: &LocalKey,
+    enc_alg: KeyAlg,
+) -> Result<String, Error> {
+    let parts = encrypt_parts(payload, recip_key, enc_alg, JWE_CONTENT_TYPE)?;
+    Ok(format!(
+        "{}..{}.{}.{}",
+        parts.protected,
+        URL_SAFE_NO_PAD.encode(parts.nonce),
+        URL_SAFE_NO_PAD.encode(parts.ciphertext),
+        URL_SAFE_NO_PAD.encode(parts.tag),
+    ))
+}
+
+/// Decrypt a compact JWE produced by `encrypt_compact` using the recipient
+/// private key
+pub(crate) fn decrypt_compact(jwe: &str, recip_key: &LocalKey) -> Result<SecretBytes, Error> {
+    let parts: Vec<&str> = jwe.split('.').collect();
+    let [protected, encrypted_key, nonce, ciphertext, tag] = parts[..] else {
+        return Err(err_msg!(Input, "Invalid compact JWE"));
+    };
+    if !encrypted_key.is_empty() {
+        return Err(err_msg!(Unsupported, "Unsupported JWE key management"));
+    }
+    decrypt_parts(
+        JweParts {
+            protected: protected.to_string(),
+            nonce: decode_part(nonce)?,
+            ciphertext: decode_part(ciphertext)?,
+            tag: decode_part(tag)?,
+        },
+        recip_key,
+    )
+}
+
+/// Encrypt a payload to a recipient public key, producing a JWE using the
+/// flattened JSON serialization
+pub(crate) fn encrypt_json(
+    payload: &[u8],
+    recip_key: &LocalKey,
+    enc_alg: KeyAlg,
+    content_type: &str,
+) -> Result<serde_json::Value, Error> {
+    let parts = encrypt_parts(payload, recip_key, enc_alg, content_type)?;
+    Ok(serde_json::json!({
+        "protected": parts.protected,
+        "iv": URL_SAFE_NO_PAD.encode(parts.nonce),
+        "ciphertext": URL_SAFE_NO_PAD.encode(parts.ciphertext),
+        "tag": URL_SAFE_NO_PAD.encode(parts.tag),
+    }))
+}
+
+/// Decrypt a JWE produced by `encrypt_json` using the recipient private key
+pub(crate) fn decrypt_json(
+    jwe: &serde_json::Value,
+    recip_key: &LocalKey,
+) -> Result<SecretBytes, Error> {
+    if jwe.get("encrypted_key").is_some() || jwe.get("recipients").is_some() {
+        return Err(err_msg!(Unsupported, "Unsupported JWE key management"));
+    }
+    let part = |name: &str| {
+        jwe[name]
+            .as_str()
+            .ok_or_else(|| err_msg!(Input, "Missing JWE member: {}", name))
+    };
+    decrypt_parts(
+        JweParts {
+            protected: part("protected")?.to_string(),
+            nonce: decode_part(part("iv")?)?,
+            ciphertext: decode_part(part("ciphertext")?)?,
+            tag: decode_part(part("tag")?)?,
+        },
+        recip_key,
+    )
+}
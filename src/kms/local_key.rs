@@ -1,7 +1,13 @@
 use std::borrow::Cow;
 use std::str::FromStr;
+use std::sync::Arc;
 
-use super::enc::{Encrypted, ToDecrypt};
+use super::{
+    enc::{Encrypted, ToDecrypt},
+    jwe::{decrypt_compact, encrypt_compact},
+    policy::{KeyOperation, KeyPolicy},
+    provider::{key_provider, load_external_key, ExternalKey},
+};
 pub use crate::crypto::{
     alg::KeyAlg,
     buffer::{SecretBytes, WriteBuffer},
@@ -16,7 +22,7 @@ use crate::{
         random::{fill_random, RandomDet},
         repr::{ToPublicBytes, ToSecretBytes},
         sign::{KeySigVerify, KeySign, SignatureType},
-        Error as CryptoError,
+        Error as CryptoError, ErrorKind as CryptoErrorKind,
     },
     error::Error,
 };
@@ -26,13 +32,20 @@ use crate::{
 pub struct LocalKey {
     pub(crate) inner: Box<AnyKey>,
     pub(crate) ephemeral: bool,
+    pub(crate) policy: Option<KeyPolicy>,
+    pub(crate) external: Option<Arc<dyn ExternalKey>>,
 }
 
 impl LocalKey {
     /// Create a new random key or keypair
     pub fn generate(alg: KeyAlg, ephemeral: bool) -> Result<Self, Error> {
         let inner = Box::<AnyKey>::random(alg)?;
-        Ok(Self { inner, ephemeral })
+        Ok(Self {
+            inner,
+            ephemeral,
+            policy: None,
+            external: None,
+        })
     }
 
     /// Create a new deterministic key or keypair
@@ -50,6 +63,8 @@ impl LocalKey {
         Ok(Self {
             inner,
             ephemeral: false,
+            policy: None,
+            external: None,
         })
     }
 
@@ -59,6 +74,8 @@ impl LocalKey {
         Ok(Self {
             inner,
             ephemeral: false,
+            policy: None,
+            external: None,
         })
     }
 
@@ -68,6 +85,8 @@ impl LocalKey {
         Ok(Self {
             inner,
             ephemeral: false,
+            policy: None,
+            external: None,
         })
     }
 
@@ -77,9 +96,55 @@ impl LocalKey {
         Ok(Self {
             inner,
             ephemeral: false,
+            policy: None,
+            external: None,
         })
     }
 
+    /// Generate a new key or keypair using the key provider registered for
+    /// the URI scheme `scheme`
+    ///
+    /// The private key remains within the provider. Storing the key using
+    /// `Session::insert_key` records only the reference to the key.
+    pub fn generate_external(
+        scheme: &str,
+        alg: KeyAlg,
+        label: Option<&str>,
+    ) -> Result<Self, Error> {
+        Self::from_external(key_provider(scheme)?.generate(alg, label)?)
+    }
+
+    /// Load a key held by an external key provider from its reference
+    pub fn from_reference(reference: &str) -> Result<Self, Error> {
+        Self::from_external(load_external_key(reference)?)
+    }
+
+    pub(crate) fn from_external(external: Arc<dyn ExternalKey>) -> Result<Self, Error> {
+        let inner = Box::<AnyKey>::from_public_bytes(
+            external.algorithm(),
+            external.public_bytes()?.as_ref(),
+        )?;
+        Ok(Self {
+            inner,
+            ephemeral: false,
+            policy: None,
+            external: Some(external),
+        })
+    }
+
+    /// Accessor for the reference of a key held by an external key provider
+    pub fn reference(&self) -> Option<&str> {
+        self.external.as_ref().map(|ext| ext.reference())
+    }
+
+    fn check_extractable(&self) -> Result<(), Error> {
+        if self.external.is_some() {
+            Err(err_msg!(Unsupported, "External keys cannot be exported"))
+        } else {
+            self.check_export()
+        }
+    }
+
     /// Export the raw bytes of the public key
     pub fn to_public_bytes(&self) -> Result<SecretBytes, Error> {
         Ok(self.inner.to_public_bytes()?)
@@ -91,20 +156,31 @@ impl LocalKey {
         Ok(Self {
             inner,
             ephemeral: false,
+            policy: None,
+            external: None,
         })
     }
 
     /// Export the raw bytes of the private key
     pub fn to_secret_bytes(&self) -> Result<SecretBytes, Error> {
+        self.check_extractable()?;
         Ok(self.inner.to_secret_bytes()?)
     }
 
     /// Derive a new key from a Diffie-Hellman exchange between this keypair and a public key
     pub fn to_key_exchange(&self, alg: KeyAlg, pk: &LocalKey) -> Result<Self, Error> {
-        let inner = Box::<AnyKey>::from_key_exchange(alg, &*self.inner, &*pk.inner)?;
+        self.check_operation(KeyOperation::Derive)?;
+        self.check_algorithm(alg)?;
+        let inner = if self.external.is_some() {
+            Box::<AnyKey>::from_key_exchange(alg, self, pk)?
+        } else {
+            Box::<AnyKey>::from_key_exchange(alg, &*self.inner, &*pk.inner)?
+        };
         Ok(Self {
             inner,
             ephemeral: self.ephemeral || pk.ephemeral,
+            policy: None,
+            external: None,
         })
     }
 
@@ -116,6 +192,8 @@ impl LocalKey {
         Ok(Self {
             inner,
             ephemeral: false,
+            policy: None,
+            external: None,
         })
     }
 
@@ -128,6 +206,28 @@ impl LocalKey {
         self.inner.algorithm()
     }
 
+    /// Accessor for the usage policy of the key, if any
+    ///
+    /// A policy is only present for keys loaded from a key entry which has
+    /// a policy attached.
+    pub fn policy(&self) -> Option<&KeyPolicy> {
+        self.policy.as_ref()
+    }
+
+    pub(crate) fn check_operation(&self, operation: KeyOperation) -> Result<(), Error> {
+        self.policy.as_ref().map_or(Ok(()), |p| p.check(operation))
+    }
+
+    pub(crate) fn check_algorithm(&self, alg: KeyAlg) -> Result<(), Error> {
+        self.policy
+            .as_ref()
+            .map_or(Ok(()), |p| p.check_algorithm(alg))
+    }
+
+    pub(crate) fn check_export(&self) -> Result<(), Error> {
+        self.policy.as_ref().map_or(Ok(()), KeyPolicy::check_export)
+    }
+
     /// Get the public JWK representation for this key or keypair
     pub fn to_jwk_public(&self, alg: Option<KeyAlg>) -> Result<String, Error> {
         Ok(self.inner.to_jwk_public(alg)?)
@@ -135,9 +235,31 @@ impl LocalKey {
 
     /// Get the JWK representation for this private key or keypair
     pub fn to_jwk_secret(&self) -> Result<SecretBytes, Error> {
+        self.check_extractable()?;
         Ok(self.inner.to_jwk_secret(None)?)
     }
 
+    /// Export this private key or keypair as a compact JWE encrypted to a
+    /// recipient public key, for escrow or transfer to another store
+    ///
+    /// The secret JWK of the key is encrypted using ECDH-ES key agreement
+    /// with the recipient key (an X25519 or elliptic curve key in JWK format),
+    /// and the content encryption algorithm `alg`. The key must be exportable
+    /// according to its usage policy.
+    pub fn export_wrapped(&self, recipient_jwk: &str, alg: KeyAlg) -> Result<String, Error> {
+        self.check_extractable()?;
+        let recipient = LocalKey::from_jwk(recipient_jwk)?;
+        let jwk = self.inner.to_jwk_secret(None)?;
+        encrypt_compact(jwk.as_ref(), &recipient, alg)
+    }
+
+    /// Import a key exported using `export_wrapped`, decrypting it with the
+    /// recipient private key
+    pub fn import_wrapped(jwe: &str, recipient: &LocalKey) -> Result<Self, Error> {
+        let jwk = decrypt_compact(jwe, recipient)?;
+        Self::from_jwk_slice(jwk.as_ref())
+    }
+
     /// Get the JWK thumbprint for this key or keypair
     pub fn to_jwk_thumbprint(&self, alg: Option<KeyAlg>) -> Result<String, Error> {
         Ok(self.inner.to_jwk_thumbprint(alg)?)
@@ -158,11 +280,19 @@ impl LocalKey {
     }
 
     /// Map this key or keypair to its equivalent for another key algorithm
+    ///
+    /// The converted key is subject to the same usage policy as this key.
     pub fn convert_key(&self, alg: KeyAlg) -> Result<Self, Error> {
+        if self.external.is_some() {
+            return Err(err_msg!(Unsupported, "External keys cannot be converted"));
+        }
+        self.check_algorithm(alg)?;
         let inner = self.inner.convert_key(alg)?;
         Ok(Self {
             inner,
             ephemeral: self.ephemeral,
+            policy: self.policy.clone(),
+            external: None,
         })
     }
 
@@ -201,6 +331,7 @@ impl LocalKey {
         nonce: &[u8],
         aad: &[u8],
     ) -> Result<Encrypted, Error> {
+        self.check_operation(KeyOperation::Encrypt)?;
         let params = self.inner.aead_params();
         let mut nonce = Cow::Borrowed(nonce);
         if nonce.is_empty() && params.nonce_length > 0 {
@@ -224,6 +355,7 @@ impl LocalKey {
         nonce: &[u8],
         aad: &[u8],
     ) -> Result<SecretBytes, Error> {
+        self.check_operation(KeyOperation::Encrypt)?;
         let mut buf = ciphertext.into().into_secret();
         self.inner.decrypt_in_place(&mut buf, nonce, aad)?;
         Ok(buf)
@@ -231,6 +363,10 @@ impl LocalKey {
 
     /// Sign a message with this private signing key
     pub fn sign_message(&self, message: &[u8], sig_type: Option<&str>) -> Result<Vec<u8>, Error> {
+        self.check_operation(KeyOperation::Sign)?;
+        if let Some(external) = self.external.as_ref() {
+            return external.sign(message, sig_type);
+        }
         let mut sig = Vec::new();
         self.inner.write_signature(
             message,
@@ -247,6 +383,7 @@ impl LocalKey {
         signature: &[u8],
         sig_type: Option<&str>,
     ) -> Result<bool, Error> {
+        self.check_operation(KeyOperation::Verify)?;
         Ok(self.inner.verify_signature(
             message,
             signature,
@@ -256,6 +393,9 @@ impl LocalKey {
 
     /// Wrap another key using this key
     pub fn wrap_key(&self, key: &LocalKey, nonce: &[u8]) -> Result<Encrypted, Error> {
+        self.check_operation(KeyOperation::Wrap)?;
+        self.check_algorithm(key.algorithm())?;
+        key.check_extractable()?;
         let params = self.inner.aead_params();
         let mut buf = SecretBytes::with_capacity(
             key.inner.secret_bytes_length()? + params.tag_length + params.nonce_length,
@@ -274,6 +414,8 @@ impl LocalKey {
         ciphertext: impl Into<ToDecrypt<'d>>,
         nonce: &[u8],
     ) -> Result<LocalKey, Error> {
+        self.check_operation(KeyOperation::Wrap)?;
+        self.check_algorithm(alg)?;
         let mut buf = ciphertext.into().into_secret();
         self.inner.decrypt_in_place(&mut buf, nonce, &[])?;
         Self::from_secret_bytes(alg, buf.as_ref())
@@ -286,6 +428,21 @@ impl KeyExchange for LocalKey {
         other: &LocalKey,
         out: &mut dyn WriteBuffer,
     ) -> Result<(), CryptoError> {
+        if self.check_operation(KeyOperation::Derive).is_err() {
+            return Err(CryptoError::from_msg(
+                CryptoErrorKind::Unsupported,
+                "Key policy does not permit key derivation",
+            ));
+        }
+        if let Some(external) = self.external.as_ref() {
+            let secret = external.key_exchange(other).map_err(|_| {
+                CryptoError::from_msg(
+                    CryptoErrorKind::Unexpected,
+                    "Error performing external key exchange",
+                )
+            })?;
+            return out.buffer_write(secret.as_ref());
+        }
         self.inner.write_key_exchange(&other.inner, out)
     }
 }
@@ -9,26 +9,56 @@ use zeroize::Zeroize;
 
 use crate::error::Error;
 
+mod attestation;
+pub(crate) use self::attestation::AttestationBuilder;
+pub use self::attestation::{AttestationFormat, KeyAttestation, KeyStorage, ATTESTATION_JWS_TYPE};
+
+mod backend;
+pub use self::backend::{register_key_backend, unregister_key_backend, KeyBackend};
+
+mod cache;
+pub(crate) use self::cache::LocalKeyCache;
+
 mod enc;
 pub use self::enc::{Encrypted, SecretBytes, ToDecrypt};
 
 mod envelope;
 pub use self::envelope::{
     crypto_box, crypto_box_open, crypto_box_random_nonce, crypto_box_seal, crypto_box_seal_open,
-    derive_key_ecdh_1pu, derive_key_ecdh_es,
+    derive_key_ecdh_1pu, derive_key_ecdh_es, derive_keys_ecdh_1pu, derive_keys_ecdh_es,
 };
 
 mod entry;
-pub use self::entry::{KeyEntry, KeyParams};
+pub(crate) use self::entry::{
+    key_metadata_tags, key_version_name, KEY_CREATED_TAG, KEY_EXPIRES_TAG, KEY_METADATA_TAG_PREFIX,
+    KEY_ROTATED_TAG, KEY_VERSION_TAG,
+};
+pub use self::entry::{KeyEntry, KeyParams, KeyScan};
+
+mod jwe;
+pub(crate) use self::jwe::{decrypt_json, encrypt_json};
 
 mod local_key;
 pub use self::local_key::{KeyAlg, LocalKey};
 
+mod piv;
+pub use self::piv::{PivReference, PivSlot, PIV_SCHEME};
+
+mod policy;
+pub use self::policy::{KeyOperation, KeyPolicy};
+
+mod provider;
+pub use self::provider::{
+    register_key_provider, unregister_key_provider, ExternalKey, KeyProvider,
+};
+
 /// Supported categories of KMS entries
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Zeroize)]
 pub(crate) enum KmsCategory {
     /// A stored key or keypair
     CryptoKey,
+    /// A superseded version of a rotated key
+    CryptoKeyVersion,
     // future options: Mnemonic, Entropy
 }
 
@@ -37,6 +67,7 @@ impl KmsCategory {
     pub fn as_str(&self) -> &str {
         match self {
             Self::CryptoKey => "cryptokey",
+            Self::CryptoKeyVersion => "cryptokey-version",
         }
     }
 }
@@ -53,6 +84,7 @@ impl FromStr for KmsCategory {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         Ok(match s {
             "cryptokey" => Self::CryptoKey,
+            "cryptokey-version" => Self::CryptoKeyVersion,
             _ => return Err(err_msg!("Unknown KMS category: {}", s)),
         })
     }
@@ -0,0 +1,158 @@
+//! References to keys resident on a PIV (FIPS 201) token such as a YubiKey
+//!
+//! A PIV key is addressed by the slot holding it and optionally the serial
+//! number of the token, using references of the form
+//! `piv:slot=9c;serial=12345678`. Signing with the key is performed by the
+//! key provider registered for the `piv` scheme, which is responsible for
+//! communicating with the token.
+
+use std::{
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
+
+use crate::error::Error;
+
+/// The reference scheme used for PIV keys
+pub const PIV_SCHEME: &str = "piv";
+
+/// A PIV key slot
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PivSlot {
+    /// PIV authentication (9A)
+    Authentication,
+    /// Digital signature (9C)
+    Signature,
+    /// Key management (9D)
+    KeyManagement,
+    /// Card authentication (9E)
+    CardAuthentication,
+    /// A retired key management slot (82 to 95)
+    Retired(u8),
+    /// The attestation key (F9)
+    Attestation,
+}
+
+impl PivSlot {
+    /// Get the identifier of the slot
+    pub fn id(&self) -> u8 {
+        match self {
+            Self::Authentication => 0x9a,
+            Self::Signature => 0x9c,
+            Self::KeyManagement => 0x9d,
+            Self::CardAuthentication => 0x9e,
+            Self::Retired(id) => *id,
+            Self::Attestation => 0xf9,
+        }
+    }
+
+    /// Look up a slot from its identifier
+    pub fn from_id(id: u8) -> Option<Self> {
+        match id {
+            0x9a => Some(Self::Authentication),
+            0x9c => Some(Self::Signature),
+            0x9d => Some(Self::KeyManagement),
+            0x9e => Some(Self::CardAuthentication),
+            0x82..=0x95 => Some(Self::Retired(id)),
+            0xf9 => Some(Self::Attestation),
+            _ => None,
+        }
+    }
+}
+
+impl Display for PivSlot {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{:02x}", self.id())
+    }
+}
+
+impl FromStr for PivSlot {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        u8::from_str_radix(s, 16)
+            .ok()
+            .and_then(Self::from_id)
+            .ok_or_else(|| err_msg!("Invalid PIV slot: {}", s))
+    }
+}
+
+/// A reference to a key resident on a PIV token
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct PivReference {
+    /// The slot holding the key
+    pub slot: PivSlot,
+    /// The serial number of the token, or `None` for any connected token
+    pub serial: Option<u32>,
+}
+
+impl PivReference {
+    /// Create a new reference to a PIV key slot
+    pub fn new(slot: PivSlot, serial: Option<u32>) -> Self {
+        Self { slot, serial }
+    }
+}
+
+impl Display for PivReference {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:slot={}", PIV_SCHEME, self.slot)?;
+        if let Some(serial) = self.serial {
+            write!(f, ";serial={}", serial)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for PivReference {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let attrs = s
+            .split_once(':')
+            .filter(|(scheme, _)| scheme.eq_ignore_ascii_case(PIV_SCHEME))
+            .map(|(_, attrs)| attrs)
+            .ok_or_else(|| err_msg!("Invalid PIV key reference"))?;
+        let mut slot = None;
+        let mut serial = None;
+        for attr in attrs.split(';').filter(|a| !a.is_empty()) {
+            match attr.split_once('=') {
+                Some(("slot", value)) => {
+                    slot.replace(value.parse()?);
+                }
+                Some(("serial", value)) => {
+                    serial.replace(
+                        value
+                            .parse()
+                            .map_err(err_map!("Invalid PIV token serial number"))?,
+                    );
+                }
+                _ => return Err(err_msg!("Unsupported PIV key reference attribute")),
+            }
+        }
+        Ok(Self {
+            slot: slot.ok_or_else(|| err_msg!("Missing PIV slot"))?,
+            serial,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn piv_reference_roundtrip() {
+        let reference = PivReference::new(PivSlot::Signature, Some(12345678));
+        let encoded = reference.to_string();
+        assert_eq!(encoded, "piv:slot=9c;serial=12345678");
+        assert_eq!(encoded.parse::<PivReference>().unwrap(), reference);
+
+        let reference: PivReference = "piv:slot=82".parse().unwrap();
+        assert_eq!(reference.slot, PivSlot::Retired(0x82));
+        assert_eq!(reference.serial, None);
+
+        assert!("piv:slot=01".parse::<PivReference>().is_err());
+        assert!("piv:serial=1".parse::<PivReference>().is_err());
+        assert!("pkcs11:slot=9c".parse::<PivReference>().is_err());
+    }
+}
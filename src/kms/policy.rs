@@ -0,0 +1,157 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use super::local_key::KeyAlg;
+use crate::error::Error;
+
+/// A class of operation which may be performed with a key
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyOperation {
+    /// Sign messages with a private key
+    Sign,
+    /// Verify message signatures
+    Verify,
+    /// Derive shared keys by key agreement, including crypto_box operations
+    Derive,
+    /// Wrap and unwrap other keys
+    Wrap,
+    /// Encrypt and decrypt messages with a symmetric key
+    Encrypt,
+}
+
+impl KeyOperation {
+    /// Get a reference to a string representing the `KeyOperation`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Sign => "sign",
+            Self::Verify => "verify",
+            Self::Derive => "derive",
+            Self::Wrap => "wrap",
+            Self::Encrypt => "encrypt",
+        }
+    }
+}
+
+impl Display for KeyOperation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Restrictions on the usage of a stored key
+///
+/// A policy is attached to a key entry using `Session::set_key_policy` and
+/// is enforced by every operation on the `LocalKey` loaded from the entry,
+/// including keys converted from it. Outside of the validity window defined
+/// by `not_before` and `expires_at`, all operations other than export fail.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct KeyPolicy {
+    /// The permitted operations, or `None` to permit all operations
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub operations: Option<Vec<KeyOperation>>,
+
+    /// The permitted algorithms for keys which are derived, converted,
+    /// wrapped or unwrapped using the key, or `None` to permit any algorithm
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub algorithms: Option<Vec<String>>,
+
+    /// Whether the secret key may be exported or wrapped by another key
+    #[serde(default = "default_exportable")]
+    pub exportable: bool,
+
+    /// The time before which the key may not be used, in seconds since the
+    /// Unix epoch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub not_before: Option<i64>,
+
+    /// The time from which the key may no longer be used, in seconds since
+    /// the Unix epoch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<i64>,
+}
+
+fn default_exportable() -> bool {
+    true
+}
+
+impl Default for KeyPolicy {
+    fn default() -> Self {
+        Self {
+            operations: None,
+            algorithms: None,
+            exportable: true,
+            not_before: None,
+            expires_at: None,
+        }
+    }
+}
+
+/// The current time in seconds since the Unix epoch
+pub(crate) fn now_secs() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+impl KeyPolicy {
+    /// Determine if the policy permits an operation
+    pub fn permits(&self, operation: KeyOperation) -> bool {
+        self.operations
+            .as_ref()
+            .map_or(true, |ops| ops.contains(&operation))
+    }
+
+    /// Determine if the policy permits producing a key of the given algorithm
+    pub fn permits_algorithm(&self, alg: KeyAlg) -> bool {
+        self.algorithms
+            .as_ref()
+            .map_or(true, |algs| algs.iter().any(|a| a == alg.as_str()))
+    }
+
+    /// Determine if the key may be used at a given time, in seconds since the
+    /// Unix epoch
+    pub fn is_valid_at(&self, time: i64) -> bool {
+        self.not_before.map_or(true, |t| time >= t) && self.expires_at.map_or(true, |t| time < t)
+    }
+
+    pub(crate) fn check(&self, operation: KeyOperation) -> Result<(), Error> {
+        let now = now_secs();
+        if self.not_before.map_or(false, |t| now < t) {
+            Err(err_msg!(Unsupported, "Key is not yet valid"))
+        } else if self.expires_at.map_or(false, |t| now >= t) {
+            Err(err_msg!(Unsupported, "Key has expired"))
+        } else if self.permits(operation) {
+            Ok(())
+        } else {
+            Err(err_msg!(
+                Unsupported,
+                "Key policy does not permit the '{}' operation",
+                operation
+            ))
+        }
+    }
+
+    pub(crate) fn check_algorithm(&self, alg: KeyAlg) -> Result<(), Error> {
+        if self.permits_algorithm(alg) {
+            Ok(())
+        } else {
+            Err(err_msg!(
+                Unsupported,
+                "Key policy does not permit the '{}' algorithm",
+                alg
+            ))
+        }
+    }
+
+    pub(crate) fn check_export(&self) -> Result<(), Error> {
+        if self.exportable {
+            Ok(())
+        } else {
+            Err(err_msg!(Unsupported, "Key policy does not permit export"))
+        }
+    }
+}
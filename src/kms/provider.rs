@@ -0,0 +1,116 @@
+//! Support for keys held outside of the store by an external provider
+//!
+//! An external key is stored as a key entry holding only a reference to the
+//! key, in the form of a URI such as a PKCS#11 URI (RFC 7512). The scheme of
+//! the reference selects the registered `KeyProvider` which is used to load
+//! the key, and the private key operations of the loaded `LocalKey` are
+//! delegated to the provider. The private key material never leaves the
+//! provider, so external keys cannot be exported or wrapped. A provider need
+//! only implement the operations supported by its keys, such as signing for
+//! a platform keystore which does not support key agreement.
+//!
+//! The public key of an external key is stored in the key entry along with
+//! its reference, so that it remains available without the provider.
+
+use std::{
+    collections::BTreeMap,
+    fmt::Debug,
+    panic::{RefUnwindSafe, UnwindSafe},
+    sync::{Arc, RwLock},
+};
+
+use once_cell::sync::Lazy;
+
+use super::{
+    attestation::KeyStorage,
+    local_key::{KeyAlg, LocalKey},
+};
+use crate::{crypto::buffer::SecretBytes, error::Error};
+
+static KEY_PROVIDERS: Lazy<RwLock<BTreeMap<String, Arc<dyn KeyProvider>>>> =
+    Lazy::new(|| RwLock::new(BTreeMap::new()));
+
+/// A key whose private key operations are performed by an external provider
+pub trait ExternalKey: Debug + Send + Sync + RefUnwindSafe + UnwindSafe {
+    /// The reference used to load the key from its provider
+    fn reference(&self) -> &str;
+
+    /// The algorithm of the key
+    fn algorithm(&self) -> KeyAlg;
+
+    /// Export the compact representation of the public key
+    fn public_bytes(&self) -> Result<Vec<u8>, Error>;
+
+    /// Sign a message with the private key
+    fn sign(&self, _message: &[u8], _sig_type: Option<&str>) -> Result<Vec<u8>, Error> {
+        Err(err_msg!(
+            Unsupported,
+            "Key provider does not support signing"
+        ))
+    }
+
+    /// Perform a Diffie-Hellman exchange between the private key and a public
+    /// key, returning the raw shared secret
+    fn key_exchange(&self, _public: &LocalKey) -> Result<SecretBytes, Error> {
+        Err(err_msg!(
+            Unsupported,
+            "Key provider does not support key exchange"
+        ))
+    }
+}
+
+/// A source of external keys, such as a hardware token or key service
+pub trait KeyProvider: Debug + Send + Sync {
+    /// Generate a new key within the provider
+    fn generate(&self, alg: KeyAlg, label: Option<&str>) -> Result<Arc<dyn ExternalKey>, Error>;
+
+    /// Load an existing key from its reference
+    fn load(&self, reference: &str) -> Result<Arc<dyn ExternalKey>, Error>;
+
+    /// The storage of the keys held by the provider, as reported in key
+    /// attestation statements
+    fn storage(&self) -> KeyStorage {
+        KeyStorage::Hardware
+    }
+}
+
+/// Register a key provider for references using the URI scheme `scheme`,
+/// replacing any provider previously registered for the scheme
+pub fn register_key_provider(scheme: &str, provider: Arc<dyn KeyProvider>) {
+    KEY_PROVIDERS
+        .write()
+        .unwrap()
+        .insert(scheme.to_ascii_lowercase(), provider);
+}
+
+/// Remove the key provider registered for the URI scheme `scheme`
+pub fn unregister_key_provider(scheme: &str) -> bool {
+    KEY_PROVIDERS
+        .write()
+        .unwrap()
+        .remove(&scheme.to_ascii_lowercase())
+        .is_some()
+}
+
+pub(crate) fn key_provider(scheme: &str) -> Result<Arc<dyn KeyProvider>, Error> {
+    KEY_PROVIDERS
+        .read()
+        .unwrap()
+        .get(&scheme.to_ascii_lowercase())
+        .cloned()
+        .ok_or_else(|| {
+            err_msg!(
+                Unsupported,
+                "No key provider registered for scheme: {}",
+                scheme
+            )
+        })
+}
+
+/// Find the provider responsible for a key reference and load the key
+pub(crate) fn load_external_key(reference: &str) -> Result<Arc<dyn ExternalKey>, Error> {
+    let (scheme, _) = reference
+        .split_once(':')
+        .ok_or_else(|| err_msg!(Input, "Invalid key reference"))?;
+    key_provider(scheme)?.load(reference)
+}
@@ -20,11 +20,36 @@ pub use askar_crypto as crypto;
 pub use askar_storage as storage;
 #[doc(hidden)]
 pub use askar_storage::future;
+pub use askar_storage::future::{set_crypto_offload, CryptoOffload};
+
+mod cache;
+
+mod chunk;
+
+pub mod export;
 
 #[cfg(feature = "ffi")]
 mod ffi;
 
 pub mod kms;
 
+#[cfg(all(feature = "migration", feature = "sqlite"))]
+pub mod migration;
+
+pub mod oid4vc;
+
+pub mod queue;
+
+mod scan;
+pub use scan::ProfileScan;
+
 mod store;
-pub use store::{entry, PassKey, Session, Store, StoreKeyMethod};
+pub use store::{
+    entry, AuditRecord, IntegrityIssue, IntegrityReport, IsolationLevel, PassKey, ProfileDetails,
+    ProfileLimits, QueryPlan, RekeyProgress, RepairReport, Session, Store, StoreKeyMethod,
+};
+
+mod universal_wallet;
+
+#[cfg(any(test, feature = "test_vectors"))]
+pub mod vectors;
@@ -0,0 +1,565 @@
+//! Import of wallet exports created by Aries Framework JavaScript (Credo)
+//!
+//! Wallets backed by the Indy-SDK are exported in the Indy-SDK export format:
+//! a msgpack header describing the key derivation, followed by a stream of
+//! msgpack records encrypted in fixed-size chunks. Wallets backed by Askar are
+//! exported as a SQLite store protected by the export key.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{BufReader, ErrorKind as IoErrorKind, Read, Seek, SeekFrom};
+
+use serde::de::{self, Deserializer, MapAccess, SeqAccess, Visitor};
+use sha2::{Digest, Sha256};
+
+use super::{insert_entries, MigrationReport};
+use crate::{
+    crypto::{
+        alg::Chacha20Types,
+        kdf::{
+            argon2::{Argon2, Params, PARAMS_INTERACTIVE, PARAMS_MODERATE},
+            KeyDerivation,
+        },
+    },
+    entry::{Entry, EntryKind, EntryTag},
+    error::Error,
+    kms::{KeyAlg, LocalKey, SecretBytes},
+    Store, StoreKeyMethod,
+};
+
+/// The first bytes of a SQLite database file
+const SQLITE_MAGIC: &[u8; 16] = b"SQLite format 3\0";
+
+/// The algorithm used to encrypt Indy-SDK exports
+const EXPORT_KEY_ALG: KeyAlg = KeyAlg::Chacha20(Chacha20Types::C20P);
+
+/// The length of the verification tag of each encrypted chunk
+const CHUNK_TAG_LENGTH: usize = 16;
+
+/// The maximum accepted length of an encoded header or record
+const MAX_RECORD_LENGTH: usize = 64 * 1024 * 1024;
+
+/// The encryption of an Indy-SDK export, along with its key derivation
+#[derive(Debug)]
+enum EncryptionMethod {
+    /// The key is derived from a passphrase using Argon2i
+    Derived {
+        params: Params,
+        salt: Vec<u8>,
+        nonce: Vec<u8>,
+        chunk_size: usize,
+    },
+    /// The key is provided directly as a base58-encoded value
+    Raw { nonce: Vec<u8>, chunk_size: usize },
+}
+
+impl EncryptionMethod {
+    fn from_parts(variant: u32, fields: Vec<MsgValue>) -> Result<Self, String> {
+        let mut fields = fields.into_iter();
+        let mut bytes = || match fields.next() {
+            Some(MsgValue::Bytes(bytes)) => Ok(bytes),
+            _ => Err("expected byte array".to_string()),
+        };
+        match variant {
+            0 | 1 => {
+                let salt = bytes()?;
+                let nonce = bytes()?;
+                let chunk_size = match fields.next() {
+                    Some(MsgValue::Int(size)) => size as usize,
+                    _ => return Err("expected chunk size".to_string()),
+                };
+                Ok(Self::Derived {
+                    params: if variant == 0 {
+                        PARAMS_MODERATE
+                    } else {
+                        PARAMS_INTERACTIVE
+                    },
+                    salt,
+                    nonce,
+                    chunk_size,
+                })
+            }
+            2 => {
+                let nonce = bytes()?;
+                let chunk_size = match fields.next() {
+                    Some(MsgValue::Int(size)) => size as usize,
+                    _ => return Err("expected chunk size".to_string()),
+                };
+                Ok(Self::Raw { nonce, chunk_size })
+            }
+            _ => Err(format!("unknown encryption method: {}", variant)),
+        }
+    }
+
+    fn variant_index(name: &str) -> Option<u32> {
+        match name {
+            "ChaCha20Poly1305IETF" => Some(0),
+            "ChaCha20Poly1305IETFInteractive" => Some(1),
+            "ChaCha20Poly1305IETFRaw" => Some(2),
+            _ => None,
+        }
+    }
+}
+
+/// A field of the encryption method, which may be encoded either as a
+/// msgpack array of integers or as binary data
+enum MsgValue {
+    Bytes(Vec<u8>),
+    Int(u64),
+}
+
+impl<'de> de::Deserialize<'de> for MsgValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct MsgValueVisitor;
+
+        impl<'de> Visitor<'de> for MsgValueVisitor {
+            type Value = MsgValue;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+                formatter.write_str("a byte array or an integer")
+            }
+
+            fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+                Ok(MsgValue::Int(v))
+            }
+
+            fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+                Ok(MsgValue::Bytes(v.to_vec()))
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let mut bytes = Vec::new();
+                while let Some(b) = seq.next_element::<u8>()? {
+                    bytes.push(b);
+                }
+                Ok(MsgValue::Bytes(bytes))
+            }
+        }
+
+        deserializer.deserialize_any(MsgValueVisitor)
+    }
+}
+
+impl<'de> de::Deserialize<'de> for EncryptionMethod {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct MethodVisitor;
+
+        /// The variant of the encryption method, as an index or a name
+        enum VariantId {
+            Index(u32),
+            Name(String),
+        }
+
+        impl VariantId {
+            fn index<E: de::Error>(self) -> Result<u32, E> {
+                match self {
+                    Self::Index(idx) => Ok(idx),
+                    Self::Name(name) => EncryptionMethod::variant_index(&name)
+                        .ok_or_else(|| E::custom(format!("unknown encryption method: {}", name))),
+                }
+            }
+        }
+
+        impl<'de> de::Deserialize<'de> for VariantId {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                struct IdVisitor;
+
+                impl<'de> Visitor<'de> for IdVisitor {
+                    type Value = VariantId;
+
+                    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+                        formatter.write_str("an encryption method identifier")
+                    }
+
+                    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+                        Ok(VariantId::Index(v as u32))
+                    }
+
+                    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+                        Ok(VariantId::Name(v.to_string()))
+                    }
+                }
+
+                deserializer.deserialize_any(IdVisitor)
+            }
+        }
+
+        impl<'de> Visitor<'de> for MethodVisitor {
+            type Value = EncryptionMethod;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+                formatter.write_str("an export encryption method")
+            }
+
+            // encoded by older versions of rmp-serde as [variant, [fields]]
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let variant = seq
+                    .next_element::<VariantId>()?
+                    .ok_or_else(|| de::Error::invalid_length(0, &self))?
+                    .index()?;
+                let fields = seq
+                    .next_element::<Vec<MsgValue>>()?
+                    .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+                EncryptionMethod::from_parts(variant, fields).map_err(de::Error::custom)
+            }
+
+            // encoded by newer versions of rmp-serde as {variant: [fields]}
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let (variant, fields) = map
+                    .next_entry::<VariantId, Vec<MsgValue>>()?
+                    .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+                EncryptionMethod::from_parts(variant.index()?, fields).map_err(de::Error::custom)
+            }
+        }
+
+        deserializer.deserialize_any(MethodVisitor)
+    }
+}
+
+#[derive(Deserialize)]
+struct ExportHeader {
+    encryption_method: EncryptionMethod,
+    #[allow(unused)]
+    time: u64,
+    version: u32,
+}
+
+#[derive(Deserialize)]
+struct ExportRecord {
+    #[serde(rename = "type")]
+    type_: String,
+    id: String,
+    value: String,
+    tags: HashMap<String, String>,
+}
+
+impl ExportRecord {
+    /// Convert to an entry. Tag names starting with `~` are unencrypted.
+    fn into_entry(self) -> Entry {
+        let tags = self
+            .tags
+            .into_iter()
+            .map(|(name, value)| match name.strip_prefix('~') {
+                Some(name) => EntryTag::Plaintext(name.to_string(), value),
+                None => EntryTag::Encrypted(name, value),
+            })
+            .collect();
+        Entry::new(EntryKind::Item, self.type_, self.id, self.value, tags)
+    }
+}
+
+/// Reader for the plaintext of a stream encrypted in fixed-size chunks, each
+/// chunk using the next nonce in sequence
+struct ChunkReader<R> {
+    inner: R,
+    key: LocalKey,
+    nonce: Vec<u8>,
+    chunk_size: usize,
+    chunk: SecretBytes,
+    pos: usize,
+}
+
+impl<R: Read> ChunkReader<R> {
+    fn new(inner: R, key: LocalKey, nonce: Vec<u8>, chunk_size: usize) -> Self {
+        Self {
+            inner,
+            key,
+            nonce,
+            chunk_size,
+            chunk: SecretBytes::default(),
+            pos: 0,
+        }
+    }
+
+    fn read_chunk(&mut self) -> Result<bool, Error> {
+        let mut buf = vec![0u8; self.chunk_size + CHUNK_TAG_LENGTH];
+        let mut len = 0;
+        while len < buf.len() {
+            match self.inner.read(&mut buf[len..]) {
+                Ok(0) => break,
+                Ok(count) => len += count,
+                Err(err) if err.kind() == IoErrorKind::Interrupted => {}
+                Err(err) => return Err(err_msg!(Input, "Error reading export").with_cause(err)),
+            }
+        }
+        if len == 0 {
+            return Ok(false);
+        }
+        self.chunk = self
+            .key
+            .aead_decrypt(&buf[..len], &self.nonce, &[])
+            .map_err(err_map!(Encryption, "Error decrypting export"))?;
+        self.pos = 0;
+        increment_nonce(&mut self.nonce);
+        Ok(true)
+    }
+
+    fn read_exact(&mut self, mut output: &mut [u8]) -> Result<(), Error> {
+        while !output.is_empty() {
+            if self.pos == self.chunk.len() && !self.read_chunk()? {
+                return Err(err_msg!(Input, "Unexpected end of export"));
+            }
+            let count = output.len().min(self.chunk.len() - self.pos);
+            output[..count].copy_from_slice(&self.chunk[self.pos..self.pos + count]);
+            self.pos += count;
+            output = &mut output[count..];
+        }
+        Ok(())
+    }
+
+    fn read_frame(&mut self) -> Result<Vec<u8>, Error> {
+        let mut len = [0u8; 4];
+        self.read_exact(&mut len)?;
+        let len = u32::from_le_bytes(len) as usize;
+        if len > MAX_RECORD_LENGTH {
+            return Err(err_msg!(Input, "Export record exceeds the maximum length"));
+        }
+        let mut record = vec![0u8; len];
+        self.read_exact(&mut record)?;
+        Ok(record)
+    }
+}
+
+/// Increment a nonce, interpreted as a little-endian integer
+fn increment_nonce(nonce: &mut [u8]) {
+    for b in nonce.iter_mut() {
+        let (value, overflow) = b.overflowing_add(1);
+        *b = value;
+        if !overflow {
+            break;
+        }
+    }
+}
+
+fn read_plain_frame(reader: &mut impl Read) -> Result<Vec<u8>, Error> {
+    let mut len = [0u8; 4];
+    reader
+        .read_exact(&mut len)
+        .map_err(err_map!(Input, "Error reading export header"))?;
+    let len = u32::from_le_bytes(len) as usize;
+    if len > MAX_RECORD_LENGTH {
+        return Err(err_msg!(Input, "Export header exceeds the maximum length"));
+    }
+    let mut frame = vec![0u8; len];
+    reader
+        .read_exact(&mut frame)
+        .map_err(err_map!(Input, "Error reading export header"))?;
+    Ok(frame)
+}
+
+/// Read and decrypt the records of an Indy-SDK export
+fn read_indy_sdk_export(mut reader: impl Read, export_key: &str) -> Result<Vec<Entry>, Error> {
+    let header_bytes = read_plain_frame(&mut reader)?;
+    let header: ExportHeader =
+        rmp_serde::from_slice(&header_bytes).map_err(err_map!(Input, "Invalid export header"))?;
+    if header.version != 0 {
+        return Err(err_msg!(Unsupported, "Unsupported export version"));
+    }
+    let (key, nonce, chunk_size) = match header.encryption_method {
+        EncryptionMethod::Derived {
+            params,
+            salt,
+            nonce,
+            chunk_size,
+        } => {
+            let mut key = SecretBytes::new_with(32, |_| ());
+            Argon2::new(export_key.as_bytes(), &salt, params)?.derive_key_bytes(key.as_mut())?;
+            (
+                LocalKey::from_secret_bytes(EXPORT_KEY_ALG, key.as_ref())?,
+                nonce,
+                chunk_size,
+            )
+        }
+        EncryptionMethod::Raw { nonce, chunk_size } => {
+            let key = bs58::decode(export_key)
+                .into_vec()
+                .map_err(err_map!(Input, "Invalid raw export key"))?;
+            (
+                LocalKey::from_secret_bytes(EXPORT_KEY_ALG, &key)?,
+                nonce,
+                chunk_size,
+            )
+        }
+    };
+    if chunk_size == 0 || chunk_size > MAX_RECORD_LENGTH {
+        return Err(err_msg!(Input, "Invalid export chunk size"));
+    }
+
+    let mut reader = ChunkReader::new(reader, key, nonce, chunk_size);
+    let mut hash = [0u8; 32];
+    reader.read_exact(&mut hash)?;
+    if hash[..] != Sha256::digest(&header_bytes)[..] {
+        return Err(err_msg!(Input, "Export header hash does not match"));
+    }
+    let mut entries = Vec::new();
+    loop {
+        let record = reader.read_frame()?;
+        if record.is_empty() {
+            break;
+        }
+        let record: ExportRecord =
+            rmp_serde::from_slice(&record).map_err(err_map!(Input, "Invalid export record"))?;
+        entries.push(record.into_entry());
+    }
+    Ok(entries)
+}
+
+/// Import an export created by the Indy-SDK into an Askar store
+///
+/// The records are converted in the same manner as when migrating an Indy-SDK
+/// wallet, and are inserted into the given profile of `store`, or its default
+/// profile, within a single transaction.
+pub async fn import_indy_sdk_export<R: Read>(
+    reader: R,
+    export_key: &str,
+    store: &Store,
+    profile: Option<String>,
+) -> Result<MigrationReport, Error> {
+    let entries = read_indy_sdk_export(reader, export_key)?;
+    insert_entries(entries, store, profile).await
+}
+
+/// Import a wallet export created by Aries Framework JavaScript (Credo)
+///
+/// Exports of wallets backed by the Indy-SDK are read using the export key,
+/// as described by [`import_indy_sdk_export`]. Exports of wallets backed by
+/// Askar are opened using the export key and its key derivation method,
+/// `ARGON2I_MOD`, `ARGON2I_INT` or `RAW`, and the records of the default
+/// profile are copied into the given profile of `store`, or its default
+/// profile.
+pub async fn import_credo_export(
+    path: &str,
+    export_key: &str,
+    key_derivation_method: &str,
+    store: &Store,
+    profile: Option<String>,
+) -> Result<MigrationReport, Error> {
+    let mut file = File::open(path).map_err(err_map!(Input, "Error opening export"))?;
+    let mut magic = [0u8; 16];
+    let is_store = match file.read_exact(&mut magic) {
+        Ok(()) => &magic == SQLITE_MAGIC,
+        Err(err) if err.kind() == IoErrorKind::UnexpectedEof => false,
+        Err(err) => return Err(err_msg!(Input, "Error reading export").with_cause(err)),
+    };
+    if !is_store {
+        file.seek(SeekFrom::Start(0))
+            .map_err(err_map!(Input, "Error reading export"))?;
+        return import_indy_sdk_export(BufReader::new(file), export_key, store, profile).await;
+    }
+    drop(file);
+
+    let key_method = match key_derivation_method {
+        "ARGON2I_MOD" => "kdf:argon2i:mod",
+        "ARGON2I_INT" => "kdf:argon2i:int",
+        "RAW" => "raw",
+        _ => return Err(err_msg!(Input, "Invalid key derivation method")),
+    };
+    let source = Store::open(
+        &format!("sqlite://{}", path),
+        Some(StoreKeyMethod::parse_uri(key_method)?),
+        export_key.into(),
+        None,
+    )
+    .await?;
+    let result = copy_store_export(&source, store, profile).await;
+    source.close().await?;
+    result
+}
+
+async fn copy_store_export(
+    source: &Store,
+    store: &Store,
+    profile: Option<String>,
+) -> Result<MigrationReport, Error> {
+    let from_profile = source.get_active_profile();
+    let to_profile = profile.unwrap_or_else(|| store.get_active_profile());
+    let items = source.session(None).await?.count(None, None).await?;
+    let mut keys = 0;
+    let mut scan = source
+        .scan_keys(None, None, None, None, None, None, None, None)
+        .await?;
+    while let Some(batch) = scan.fetch_next().await? {
+        keys += batch.len();
+    }
+    drop(scan);
+    source
+        .copy_profile_to_store(store, &from_profile, &to_profile)
+        .await?;
+    Ok(MigrationReport {
+        items: items as usize,
+        keys,
+        skipped: 0,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Encrypt a stream in the chunked format used by Indy-SDK exports
+    fn encrypt_chunks(key: &LocalKey, nonce: &[u8], chunk_size: usize, data: &[u8]) -> Vec<u8> {
+        let mut nonce = nonce.to_vec();
+        let mut output = Vec::new();
+        for chunk in data.chunks(chunk_size) {
+            let enc = key.aead_encrypt(chunk, &nonce, &[]).unwrap();
+            output.extend_from_slice(enc.ciphertext());
+            output.extend_from_slice(enc.tag());
+            increment_nonce(&mut nonce);
+        }
+        output
+    }
+
+    fn frame(data: &[u8]) -> Vec<u8> {
+        let mut output = (data.len() as u32).to_le_bytes().to_vec();
+        output.extend_from_slice(data);
+        output
+    }
+
+    #[test]
+    fn nonce_increment() {
+        let mut nonce = [0xff, 0xff, 0x01];
+        increment_nonce(&mut nonce);
+        assert_eq!(nonce, [0x00, 0x00, 0x02]);
+    }
+
+    #[test]
+    fn read_raw_export() {
+        let raw_key = [3u8; 32];
+        let export_key = bs58::encode(raw_key).into_string();
+        let nonce = vec![1u8; 12];
+        let chunk_size = 64;
+        // an enum variant encoded as [variant, [fields]], as written by the Indy-SDK
+        let header = rmp_serde::to_vec(&((2u32, (nonce.clone(), chunk_size)), 0u64, 0u32)).unwrap();
+
+        let mut tags = HashMap::new();
+        tags.insert("enc".to_string(), "secret".to_string());
+        tags.insert("~plain".to_string(), "public".to_string());
+        let record = rmp_serde::to_vec(&("connection", "conn-id", "{}", tags)).unwrap();
+        let mut plaintext = Sha256::digest(&header).to_vec();
+        plaintext.extend(frame(&record));
+        plaintext.extend(frame(&[]));
+
+        let key = LocalKey::from_secret_bytes(EXPORT_KEY_ALG, &raw_key).unwrap();
+        let mut export = frame(&header);
+        export.extend(encrypt_chunks(&key, &nonce, chunk_size, &plaintext));
+
+        let entries = read_indy_sdk_export(&export[..], &export_key).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].category, "connection");
+        assert_eq!(entries[0].name, "conn-id");
+        assert_eq!(&entries[0].value[..], b"{}");
+        assert_eq!(entries[0].tags.len(), 2);
+        assert!(entries[0].tags.contains(&EntryTag::Plaintext(
+            "plain".to_string(),
+            "public".to_string()
+        )));
+        assert!(entries[0].tags.contains(&EntryTag::Encrypted(
+            "enc".to_string(),
+            "secret".to_string()
+        )));
+
+        let wrong_key = bs58::encode([4u8; 32]).into_string();
+        assert!(read_indy_sdk_export(&export[..], &wrong_key).is_err());
+    }
+}
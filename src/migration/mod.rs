@@ -0,0 +1,377 @@
+//! Migration of Indy-SDK wallets into Askar stores
+//!
+//! The records of an Indy-SDK SQLite wallet, or a wallet created by the
+//! Indy-SDK Postgres storage plugin, are decrypted and copied into an Askar
+//! store, leaving the original wallet unchanged. Records created by the
+//! Indy-SDK are converted to the layout used by Askar-based agents:
+//!
+//! - `Indy::Key` records, along with their `Indy::KeyMetadata`, become
+//!   Ed25519 keys named by their verkey.
+//! - `Indy::Did` records, along with their `Indy::DidMetadata`, become `did`
+//!   records tagged with the DID method, verkey and verkey type.
+//! - Anoncreds records such as `Indy::Credential` and `Indy::MasterSecret`
+//!   are moved to the corresponding Askar categories, keeping their values
+//!   and tags.
+//! - Other `Indy::` records, such as `Indy::TheirDid` and
+//!   `Indy::TemporaryDid`, are skipped.
+//!
+//! Records created by the agent itself are copied unchanged. Wallet exports
+//! created by Aries Framework JavaScript (Credo) may also be imported, in
+//! either the Indy-SDK export format or as an exported Askar store.
+
+use std::collections::HashMap;
+
+use serde_json::{json, Value};
+
+use crate::{
+    entry::{Entry, EntryKind, EntryTag},
+    error::Error,
+    kms::{KeyAlg, LocalKey},
+    storage::migration::IndySdkWalletReader,
+    Store,
+};
+
+#[cfg(feature = "postgres")]
+pub use crate::storage::migration::IndyPostgresWalletScheme;
+
+mod credo;
+pub use self::credo::{import_credo_export, import_indy_sdk_export};
+
+/// The number of wallet records fetched in each batch
+const FETCH_BATCH_SIZE: u32 = 100;
+
+/// Indy-SDK record categories and their Askar equivalents
+const CATEGORY_MAP: &[(&str, &str)] = &[
+    ("Indy::Credential", "credential"),
+    ("Indy::CredentialDefinition", "credential_def"),
+    (
+        "Indy::CredentialDefinitionCorrectnessProof",
+        "credential_def_key_proof",
+    ),
+    (
+        "Indy::CredentialDefinitionPrivateKey",
+        "credential_def_private",
+    ),
+    ("Indy::MasterSecret", "master_secret"),
+    ("Indy::RevocationRegistry", "revocation_reg"),
+    ("Indy::RevocationRegistryDefinition", "revocation_reg_def"),
+    (
+        "Indy::RevocationRegistryDefinitionPrivate",
+        "revocation_reg_def_private",
+    ),
+    ("Indy::RevocationRegistryInfo", "revocation_reg_info"),
+    ("Indy::Schema", "schema"),
+];
+
+const INDY_PREFIX: &str = "Indy::";
+
+/// A summary of the records copied into an Askar store
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MigrationReport {
+    /// The number of records copied as items
+    pub items: usize,
+    /// The number of keys converted
+    pub keys: usize,
+    /// The number of records which were not copied
+    pub skipped: usize,
+}
+
+/// A key converted from an Indy-SDK wallet record
+#[derive(Debug)]
+struct ConvertedKey {
+    name: String,
+    key: LocalKey,
+    metadata: Option<String>,
+}
+
+/// Copy the records of an Indy-SDK SQLite wallet into an Askar store
+///
+/// The wallet is opened read-only using the wallet key and the name of its
+/// key derivation method: `ARGON2I_MOD`, `ARGON2I_INT` or `RAW`. The records
+/// are converted and inserted into the given profile of `store`, or its
+/// default profile, within a single transaction. The store is expected to be
+/// freshly provisioned, and the migration fails without changes to the store
+/// if any converted record already exists.
+pub async fn migrate_indy_sdk_wallet(
+    wallet_path: &str,
+    wallet_key: &str,
+    kdf_method: &str,
+    store: &Store,
+    profile: Option<String>,
+) -> Result<MigrationReport, Error> {
+    let reader = IndySdkWalletReader::open(wallet_path, wallet_key, kdf_method).await?;
+    copy_wallet(reader, store, profile).await
+}
+
+/// Copy the records of a wallet created by the Indy-SDK Postgres storage
+/// plugin into an Askar store
+///
+/// The connection URI must select the database containing the wallet, and
+/// may select a schema using the `options` query parameter. The wallet
+/// identifier selects the wallet records when using a shared table scheme.
+/// The records are converted as for [`migrate_indy_sdk_wallet`].
+#[cfg(feature = "postgres")]
+pub async fn migrate_indy_sdk_postgres_wallet(
+    uri: &str,
+    scheme: IndyPostgresWalletScheme,
+    wallet_id: &str,
+    wallet_key: &str,
+    kdf_method: &str,
+    store: &Store,
+    profile: Option<String>,
+) -> Result<MigrationReport, Error> {
+    let reader =
+        IndySdkWalletReader::open_postgres(uri, scheme, wallet_id, wallet_key, kdf_method).await?;
+    copy_wallet(reader, store, profile).await
+}
+
+async fn copy_wallet(
+    mut reader: IndySdkWalletReader,
+    store: &Store,
+    profile: Option<String>,
+) -> Result<MigrationReport, Error> {
+    let mut entries = Vec::new();
+    let fetched = async {
+        while let Some(batch) = reader.fetch_next(FETCH_BATCH_SIZE).await? {
+            entries.extend(batch);
+        }
+        Result::<_, Error>::Ok(())
+    }
+    .await;
+    reader.close().await?;
+    fetched?;
+    insert_entries(entries, store, profile).await
+}
+
+/// Convert the records of an Indy-SDK wallet and insert them into a store
+/// within a single transaction
+async fn insert_entries(
+    entries: Vec<Entry>,
+    store: &Store,
+    profile: Option<String>,
+) -> Result<MigrationReport, Error> {
+    let (items, keys, skipped) = convert_entries(entries)?;
+    let report = MigrationReport {
+        items: items.len(),
+        keys: keys.len(),
+        skipped,
+    };
+
+    let mut txn = store.transaction(profile).await?;
+    for key in keys {
+        txn.insert_key(&key.name, &key.key, key.metadata.as_deref(), None, None)
+            .await?;
+    }
+    for item in items {
+        txn.insert(
+            &item.category,
+            &item.name,
+            &item.value,
+            Some(&item.tags),
+            None,
+        )
+        .await?;
+    }
+    txn.commit().await?;
+    Ok(report)
+}
+
+/// Convert the records of an Indy-SDK wallet, returning the converted items,
+/// the converted keys and the number of skipped records
+fn convert_entries(entries: Vec<Entry>) -> Result<(Vec<Entry>, Vec<ConvertedKey>, usize), Error> {
+    let mut key_metadata = HashMap::new();
+    let mut did_metadata = HashMap::new();
+    let mut remain = Vec::with_capacity(entries.len());
+    for entry in entries {
+        match entry.category.as_str() {
+            "Indy::KeyMetadata" => {
+                key_metadata.insert(entry.name.clone(), metadata_value(&entry)?);
+            }
+            "Indy::DidMetadata" => {
+                did_metadata.insert(entry.name.clone(), metadata_value(&entry)?);
+            }
+            _ => remain.push(entry),
+        }
+    }
+
+    let mut items = Vec::with_capacity(remain.len());
+    let mut keys = Vec::new();
+    let mut skipped = 0;
+    for entry in remain {
+        match entry.category.as_str() {
+            "Indy::Key" => {
+                let metadata = key_metadata.remove(&entry.name);
+                keys.push(convert_key(&entry, metadata)?);
+            }
+            "Indy::Did" => {
+                let metadata = did_metadata.remove(&entry.name);
+                items.push(convert_did(&entry, metadata)?);
+            }
+            category => {
+                if let Some((_, target)) = CATEGORY_MAP.iter().find(|(src, _)| *src == category) {
+                    items.push(Entry::new(
+                        EntryKind::Item,
+                        *target,
+                        entry.name,
+                        entry.value,
+                        entry.tags,
+                    ));
+                } else if category.starts_with(INDY_PREFIX) {
+                    skipped += 1;
+                } else {
+                    items.push(entry);
+                }
+            }
+        }
+    }
+    // metadata records without a corresponding key or DID
+    skipped += key_metadata.len() + did_metadata.len();
+    Ok((items, keys, skipped))
+}
+
+fn parse_value(entry: &Entry) -> Result<Value, Error> {
+    serde_json::from_slice(&entry.value).map_err(err_map!(
+        Input,
+        "Invalid value for wallet record: {}",
+        entry.category
+    ))
+}
+
+/// Extract the value of an `Indy::KeyMetadata` or `Indy::DidMetadata` record
+fn metadata_value(entry: &Entry) -> Result<String, Error> {
+    match parse_value(entry)?.get("value") {
+        Some(Value::String(value)) => Ok(value.clone()),
+        _ => Err(err_msg!(
+            Input,
+            "Missing metadata value for wallet record: {}",
+            entry.category
+        )),
+    }
+}
+
+fn string_field<'v>(value: &'v Value, field: &str) -> Result<&'v str, Error> {
+    value
+        .get(field)
+        .and_then(Value::as_str)
+        .ok_or_else(|| err_msg!(Input, "Missing '{}' in wallet record", field))
+}
+
+/// Convert an `Indy::Key` record, whose value contains the base58-encoded
+/// verkey and signkey, to an Ed25519 key named by its verkey
+fn convert_key(entry: &Entry, metadata: Option<String>) -> Result<ConvertedKey, Error> {
+    let value = parse_value(entry)?;
+    let verkey = string_field(&value, "verkey")?;
+    let signkey = bs58::decode(string_field(&value, "signkey")?)
+        .into_vec()
+        .map_err(err_map!(Input, "Invalid signkey in wallet record"))?;
+    // the signkey is the concatenated secret and public key
+    if signkey.len() != 64 {
+        return Err(err_msg!(Input, "Invalid signkey length in wallet record"));
+    }
+    let key = LocalKey::from_secret_bytes(KeyAlg::Ed25519, &signkey[..32])?;
+    if bs58::encode(key.to_public_bytes()?).into_string() != verkey {
+        return Err(err_msg!(
+            Input,
+            "Verkey does not match signkey for wallet key"
+        ));
+    }
+    Ok(ConvertedKey {
+        name: entry.name.clone(),
+        key,
+        metadata,
+    })
+}
+
+/// Convert an `Indy::Did` record, whose value contains the DID and its
+/// verkey, to a `did` record
+fn convert_did(entry: &Entry, metadata: Option<String>) -> Result<Entry, Error> {
+    let value = parse_value(entry)?;
+    let did = string_field(&value, "did")?;
+    let verkey = string_field(&value, "verkey")?;
+    let metadata = match metadata {
+        Some(metadata) => serde_json::from_str(&metadata).unwrap_or(Value::String(metadata)),
+        None => Value::Null,
+    };
+    let did_info = json!({
+        "did": did,
+        "method": "sov",
+        "verkey": verkey,
+        "verkey_type": "ed25519",
+        "metadata": metadata,
+    });
+    Ok(Entry::new(
+        EntryKind::Item,
+        "did",
+        did,
+        did_info.to_string(),
+        vec![
+            EntryTag::Encrypted("method".to_string(), "sov".to_string()),
+            EntryTag::Encrypted("verkey".to_string(), verkey.to_string()),
+            EntryTag::Encrypted("verkey_type".to_string(), "ed25519".to_string()),
+        ],
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(category: &str, name: &str, value: Value) -> Entry {
+        Entry::new(EntryKind::Item, category, name, value.to_string(), vec![])
+    }
+
+    #[test]
+    fn convert_indy_records() {
+        let key = LocalKey::from_seed(KeyAlg::Ed25519, b"testseed000000000000000000000001", None)
+            .unwrap();
+        let public = key.to_public_bytes().unwrap();
+        let verkey = bs58::encode(&public).into_string();
+        let mut signkey = key.to_secret_bytes().unwrap().to_vec();
+        signkey.extend_from_slice(&public);
+        let did = "VsKV7grR1BUE29mG2Fm2kX";
+
+        let entries = vec![
+            item(
+                "Indy::Key",
+                &verkey,
+                json!({"verkey": verkey, "signkey": bs58::encode(&signkey).into_string()}),
+            ),
+            item("Indy::KeyMetadata", &verkey, json!({"value": "key meta"})),
+            item("Indy::Did", did, json!({"did": did, "verkey": verkey})),
+            item(
+                "Indy::DidMetadata",
+                did,
+                json!({"value": "{\"public\":true}"}),
+            ),
+            item("Indy::TheirDid", "other", json!({})),
+            Entry::new(
+                EntryKind::Item,
+                "Indy::Credential",
+                "cred",
+                "{}",
+                vec![EntryTag::Encrypted("schema_id".into(), "s".into())],
+            ),
+            item("connection", "conn", json!({"state": "active"})),
+        ];
+        let (items, keys, skipped) = convert_entries(entries).unwrap();
+        assert_eq!(skipped, 1);
+
+        assert_eq!(keys.len(), 1);
+        assert_eq!(keys[0].name, verkey);
+        assert_eq!(keys[0].metadata.as_deref(), Some("key meta"));
+        assert_eq!(keys[0].key.to_public_bytes().unwrap(), public);
+
+        assert_eq!(items.len(), 3);
+        let did_item = items.iter().find(|i| i.category == "did").unwrap();
+        assert_eq!(did_item.name, did);
+        let did_info: Value = serde_json::from_slice(&did_item.value).unwrap();
+        assert_eq!(did_info["verkey"], verkey.as_str());
+        assert_eq!(did_info["metadata"], json!({"public": true}));
+        assert!(did_item
+            .tags
+            .contains(&EntryTag::Encrypted("verkey".into(), verkey.clone())));
+        let cred = items.iter().find(|i| i.category == "credential").unwrap();
+        assert_eq!(cred.tags.len(), 1);
+        assert!(items.iter().any(|i| i.category == "connection"));
+    }
+}
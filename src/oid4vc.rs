@@ -0,0 +1,377 @@
+//! Typed records for OpenID for Verifiable Credentials wallets
+//!
+//! The records defined here share a common storage layout: each record type
+//! is stored in its own category, named by its identifier, with the value
+//! encoded as JSON. Tags are derived from the record so that records may be
+//! located using tag filters. Tag names are given below in the form used by
+//! tag filters, where a `~` prefix denotes a plaintext tag. Numeric tags are
+//! marked with `#` below, and are named in tag filters by
+//! [`TagFilter::numeric_tag`].
+//!
+//! | Record                    | Category                   | Tags                                                      |
+//! |---------------------------|----------------------------|-----------------------------------------------------------|
+//! | [`SdJwtVcRecord`]         | `oid4vc_sd_jwt_vc`         | `vct`, `issuer`, `holder_key`, `expires` #                |
+//! | [`MdocRecord`]            | `oid4vc_mdoc`              | `doc_type`, `issuer`, `device_key`, `expires` #           |
+//! | [`IssuerMetadataRecord`]  | `oid4vc_issuer_metadata`   | `fetched` #                                               |
+//! | [`IssuanceSessionRecord`] | `oid4vci_issuance_session` | `state`, `issuer`, `pre_authorized_code`, `issuer_state`  |
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    entry::{Entry, EntryTag, TagFilter},
+    error::Error,
+    kms::LocalKey,
+};
+
+/// The tag holding the credential type of an SD-JWT VC
+pub const VCT_TAG: &str = "vct";
+
+/// The tag holding the document type of an mdoc
+pub const DOC_TYPE_TAG: &str = "doc_type";
+
+/// The tag holding the credential issuer identifier
+pub const ISSUER_TAG: &str = "issuer";
+
+/// The tag holding the JWK thumbprint of the key bound to an SD-JWT VC
+pub const HOLDER_KEY_TAG: &str = "holder_key";
+
+/// The tag holding the JWK thumbprint of the device key of an mdoc
+pub const DEVICE_KEY_TAG: &str = "device_key";
+
+/// The numeric tag holding the expiry time of a credential, in seconds since
+/// the Unix epoch
+pub const EXPIRES_TAG: &str = "expires";
+
+/// The numeric tag holding the time issuer metadata was fetched, in seconds
+/// since the Unix epoch
+pub const FETCHED_TAG: &str = "fetched";
+
+/// The tag holding the state of an issuance session
+pub const STATE_TAG: &str = "state";
+
+/// The tag holding the pre-authorized code of an issuance session
+pub const PRE_AUTHORIZED_CODE_TAG: &str = "pre_authorized_code";
+
+/// The tag holding the issuer state of an issuance session
+pub const ISSUER_STATE_TAG: &str = "issuer_state";
+
+/// A record stored in a dedicated category using a JSON encoding
+pub trait TypedRecord: Serialize + DeserializeOwned {
+    /// The category of records of this type
+    const CATEGORY: &'static str;
+
+    /// The name of the record
+    fn name(&self) -> &str;
+
+    /// The tags derived from the record
+    fn tags(&self) -> Vec<EntryTag>;
+}
+
+/// Create a tag from its name in the form used by tag filters
+fn tag(name: &str, value: impl ToString) -> EntryTag {
+    match name.strip_prefix('~') {
+        Some(name) => EntryTag::Plaintext(name.to_string(), value.to_string()),
+        None => EntryTag::Encrypted(name.to_string(), value.to_string()),
+    }
+}
+
+/// Create a numeric tag
+fn numeric_tag(name: &str, value: i64) -> EntryTag {
+    EntryTag::Numeric(name.to_string(), value.to_string())
+}
+
+pub(crate) fn encode_record<R: TypedRecord>(record: &R) -> Result<Vec<u8>, Error> {
+    serde_json::to_vec(record).map_err(err_map!(Unexpected, "Error encoding record"))
+}
+
+pub(crate) fn decode_record<R: TypedRecord>(entry: &Entry) -> Result<R, Error> {
+    serde_json::from_slice(&entry.value).map_err(err_map!(Input, "Error decoding record"))
+}
+
+/// A filter matching credentials which expire before the given time, in
+/// seconds since the Unix epoch
+pub fn expires_before(time: i64) -> TagFilter {
+    TagFilter::is_lt(TagFilter::numeric_tag(EXPIRES_TAG), time.to_string())
+}
+
+/// A stored SD-JWT VC, including its disclosures and any key binding
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct SdJwtVcRecord {
+    /// The record identifier
+    pub id: String,
+    /// The SD-JWT VC in compact form
+    pub compact: String,
+    /// The credential type
+    pub vct: String,
+    /// The credential issuer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub issuer: Option<String>,
+    /// The JWK thumbprint of the key bound to the credential
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub holder_key: Option<String>,
+    /// The issuance time, in seconds since the Unix epoch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub issued_at: Option<i64>,
+    /// The expiry time, in seconds since the Unix epoch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<i64>,
+}
+
+impl SdJwtVcRecord {
+    /// Create a record from an SD-JWT VC in compact form
+    ///
+    /// The credential type, issuer, validity period and holder key are read
+    /// from the payload of the issuer-signed JWT. The signature is not
+    /// verified.
+    pub fn from_compact(id: impl Into<String>, compact: impl Into<String>) -> Result<Self, Error> {
+        let compact = compact.into();
+        let jwt = compact.split('~').next().unwrap_or_default();
+        let payload = jwt
+            .split('.')
+            .nth(1)
+            .ok_or_else(|| err_msg!(Input, "Invalid SD-JWT"))?;
+        let claims: serde_json::Value = URL_SAFE_NO_PAD
+            .decode(payload)
+            .ok()
+            .and_then(|payload| serde_json::from_slice(&payload).ok())
+            .ok_or_else(|| err_msg!(Input, "Invalid SD-JWT payload"))?;
+        let vct = claims["vct"]
+            .as_str()
+            .ok_or_else(|| err_msg!(Input, "SD-JWT VC is missing the vct claim"))?
+            .to_string();
+        let holder_key = if claims["cnf"]["jwk"].is_object() {
+            Some(LocalKey::from_jwk(&claims["cnf"]["jwk"].to_string())?.to_jwk_thumbprint(None)?)
+        } else {
+            None
+        };
+        Ok(Self {
+            id: id.into(),
+            compact,
+            vct,
+            issuer: claims["iss"].as_str().map(str::to_string),
+            holder_key,
+            issued_at: claims["iat"].as_i64(),
+            expires_at: claims["exp"].as_i64(),
+        })
+    }
+}
+
+impl TypedRecord for SdJwtVcRecord {
+    const CATEGORY: &'static str = "oid4vc_sd_jwt_vc";
+
+    fn name(&self) -> &str {
+        &self.id
+    }
+
+    fn tags(&self) -> Vec<EntryTag> {
+        let mut tags = vec![tag(VCT_TAG, &self.vct)];
+        if let Some(issuer) = &self.issuer {
+            tags.push(tag(ISSUER_TAG, issuer));
+        }
+        if let Some(holder_key) = &self.holder_key {
+            tags.push(tag(HOLDER_KEY_TAG, holder_key));
+        }
+        if let Some(expires_at) = self.expires_at {
+            tags.push(numeric_tag(EXPIRES_TAG, expires_at));
+        }
+        tags
+    }
+}
+
+/// A stored ISO 18013-5 mdoc
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct MdocRecord {
+    /// The record identifier
+    pub id: String,
+    /// The document type
+    pub doc_type: String,
+    /// The CBOR-encoded `IssuerSigned` structure, base64url encoded
+    pub issuer_signed: String,
+    /// The credential issuer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub issuer: Option<String>,
+    /// The JWK thumbprint of the device key
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device_key: Option<String>,
+    /// The end of the validity period, in seconds since the Unix epoch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<i64>,
+}
+
+impl TypedRecord for MdocRecord {
+    const CATEGORY: &'static str = "oid4vc_mdoc";
+
+    fn name(&self) -> &str {
+        &self.id
+    }
+
+    fn tags(&self) -> Vec<EntryTag> {
+        let mut tags = vec![tag(DOC_TYPE_TAG, &self.doc_type)];
+        if let Some(issuer) = &self.issuer {
+            tags.push(tag(ISSUER_TAG, issuer));
+        }
+        if let Some(device_key) = &self.device_key {
+            tags.push(tag(DEVICE_KEY_TAG, device_key));
+        }
+        if let Some(expires_at) = self.expires_at {
+            tags.push(numeric_tag(EXPIRES_TAG, expires_at));
+        }
+        tags
+    }
+}
+
+/// The cached metadata of a credential issuer, named by the issuer identifier
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct IssuerMetadataRecord {
+    /// The credential issuer identifier
+    pub credential_issuer: String,
+    /// The credential issuer metadata
+    pub metadata: serde_json::Value,
+    /// The metadata of the associated authorization servers
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub authorization_servers: Vec<serde_json::Value>,
+    /// The time the metadata was fetched, in seconds since the Unix epoch
+    pub fetched_at: i64,
+}
+
+impl TypedRecord for IssuerMetadataRecord {
+    const CATEGORY: &'static str = "oid4vc_issuer_metadata";
+
+    fn name(&self) -> &str {
+        &self.credential_issuer
+    }
+
+    fn tags(&self) -> Vec<EntryTag> {
+        vec![numeric_tag(FETCHED_TAG, self.fetched_at)]
+    }
+}
+
+/// The state of an OpenID4VCI issuance session
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IssuanceState {
+    /// A credential offer has been received
+    Offered,
+    /// Authorization has been requested from the authorization server
+    AuthorizationPending,
+    /// An access token has been obtained
+    TokenReceived,
+    /// The credential has been requested, and its issuance is deferred
+    Deferred,
+    /// The credentials have been received
+    Completed,
+    /// The issuance failed
+    Failed,
+}
+
+impl IssuanceState {
+    /// Access the state as a string, as stored in the state tag
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Offered => "offered",
+            Self::AuthorizationPending => "authorization_pending",
+            Self::TokenReceived => "token_received",
+            Self::Deferred => "deferred",
+            Self::Completed => "completed",
+            Self::Failed => "failed",
+        }
+    }
+}
+
+/// A pending OpenID4VCI issuance session
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct IssuanceSessionRecord {
+    /// The session identifier
+    pub id: String,
+    /// The state of the session
+    pub state: IssuanceState,
+    /// The credential issuer identifier
+    pub credential_issuer: String,
+    /// The offered credential configurations
+    pub credential_configuration_ids: Vec<String>,
+    /// The pre-authorized code of the credential offer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pre_authorized_code: Option<String>,
+    /// The issuer state of the credential offer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub issuer_state: Option<String>,
+    /// The PKCE code verifier of a pending authorization request
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code_verifier: Option<String>,
+    /// The access token issued for the session
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub access_token: Option<String>,
+    /// The most recent nonce provided by the credential issuer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub c_nonce: Option<String>,
+    /// The transaction identifier of a deferred issuance
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transaction_id: Option<String>,
+    /// The identifiers of the credential records received
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub credential_ids: Vec<String>,
+    /// The creation time of the session, in seconds since the Unix epoch
+    pub created_at: i64,
+}
+
+impl TypedRecord for IssuanceSessionRecord {
+    const CATEGORY: &'static str = "oid4vci_issuance_session";
+
+    fn name(&self) -> &str {
+        &self.id
+    }
+
+    fn tags(&self) -> Vec<EntryTag> {
+        let mut tags = vec![
+            tag(STATE_TAG, self.state.as_str()),
+            tag(ISSUER_TAG, &self.credential_issuer),
+        ];
+        if let Some(code) = &self.pre_authorized_code {
+            tags.push(tag(PRE_AUTHORIZED_CODE_TAG, code));
+        }
+        if let Some(issuer_state) = &self.issuer_state {
+            tags.push(tag(ISSUER_STATE_TAG, issuer_state));
+        }
+        tags
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kms::KeyAlg;
+
+    #[test]
+    fn sd_jwt_vc_from_compact() {
+        let key = LocalKey::generate(KeyAlg::Ed25519, false).unwrap();
+        let jwk: serde_json::Value =
+            serde_json::from_str(&key.to_jwk_public(None).unwrap()).unwrap();
+        let claims = serde_json::json!({
+            "vct": "https://example.com/identity",
+            "iss": "https://issuer.example.com",
+            "iat": 1700000000,
+            "exp": 1800000000,
+            "cnf": {"jwk": jwk},
+        });
+        let compact = format!(
+            "eyJhbGciOiJFUzI1NiJ9.{}.c2ln~WyJzYWx0IiwibmFtZSIsIkFsaWNlIl0~",
+            URL_SAFE_NO_PAD.encode(claims.to_string())
+        );
+        let record = SdJwtVcRecord::from_compact("cred", compact.clone()).unwrap();
+        assert_eq!(record.compact, compact);
+        assert_eq!(record.vct, "https://example.com/identity");
+        assert_eq!(record.issuer.as_deref(), Some("https://issuer.example.com"));
+        assert_eq!(
+            record.holder_key,
+            Some(key.to_jwk_thumbprint(None).unwrap())
+        );
+        assert_eq!(record.expires_at, Some(1800000000));
+        assert!(record.tags().contains(&EntryTag::Numeric(
+            "expires".to_string(),
+            "1800000000".to_string()
+        )));
+
+        assert!(SdJwtVcRecord::from_compact("cred", "invalid").is_err());
+    }
+}
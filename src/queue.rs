@@ -0,0 +1,169 @@
+//! Storage of queued DIDComm messages awaiting pickup from a mediator
+//!
+//! Queued messages are stored as items in a dedicated category, named by the
+//! recipient and a message identifier, and tagged with the recipient. The
+//! recipient tag is resolved using the existing index on encrypted tag
+//! values. Messages for a recipient are returned in the order they were
+//! queued, with any limit applied by the database so that a pickup only loads
+//! and locks the messages it returns. Messages are removed as they are
+//! dequeued, and a message is only returned by the operation which removed
+//! it, so that concurrent pickups for the same recipient never deliver a
+//! message twice.
+
+use std::{
+    fmt::{self, Debug, Formatter},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    crypto::{buffer::HexRepr, random::fill_random},
+    entry::{Entry, EntryOperation, EntryTag, Subscription, TagFilter},
+    error::Error,
+};
+
+/// The category of queued message entries
+pub const MESSAGE_QUEUE_CATEGORY: &str = "didcomm-message-queue";
+
+/// The encrypted tag holding the recipient of a queued message
+const RECIPIENT_TAG: &str = "recipient";
+
+/// The plaintext tag holding the time a message was queued
+const CREATED_TAG: &str = "created";
+
+/// A message held in the queue for a recipient
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct QueuedMessage {
+    /// The message identifier
+    pub id: String,
+    /// The recipient key or DID
+    pub recipient: String,
+    /// The time the message was queued, in milliseconds since the Unix epoch
+    pub created: i64,
+    /// The message content
+    pub message: Vec<u8>,
+}
+
+impl QueuedMessage {
+    pub(crate) fn from_entry(entry: Entry) -> Result<Self, Error> {
+        let (recipient, id) = split_message_name(&entry.name)
+            .ok_or_else(|| err_msg!(Unexpected, "Invalid queued message name"))?;
+        let created = entry
+            .tags
+            .iter()
+            .find(|tag| tag.name() == CREATED_TAG)
+            .and_then(|tag| tag.value().parse().ok())
+            .unwrap_or_default();
+        Ok(Self {
+            id: id.to_string(),
+            recipient: recipient.to_string(),
+            created,
+            message: entry.value.to_vec(),
+        })
+    }
+}
+
+/// A notification that a message has been queued
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MessageNotification {
+    /// The recipient key or DID
+    pub recipient: String,
+    /// The message identifier
+    pub id: String,
+}
+
+/// An active subscription to the messages queued in a profile
+pub struct MessageSubscription {
+    inner: Subscription<'static>,
+    recipient: Option<String>,
+}
+
+impl MessageSubscription {
+    pub(crate) fn new(inner: Subscription<'static>, recipient: Option<String>) -> Self {
+        Self { inner, recipient }
+    }
+
+    /// Wait for the next message to be queued, returning `None` when the
+    /// store is closed
+    ///
+    /// Notifications are delivered once the message has been committed. The
+    /// message may already have been dequeued by another session by the time
+    /// the notification is received.
+    pub async fn next_message(&mut self) -> Result<Option<MessageNotification>, Error> {
+        while let Some(event) = self.inner.next_event().await? {
+            if event.operation != EntryOperation::Insert {
+                continue;
+            }
+            if let Some((recipient, id)) = split_message_name(&event.name) {
+                if self.recipient.as_deref().map_or(true, |r| r == recipient) {
+                    return Ok(Some(MessageNotification {
+                        recipient: recipient.to_string(),
+                        id: id.to_string(),
+                    }));
+                }
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl Debug for MessageSubscription {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MessageSubscription")
+            .field("recipient", &self.recipient)
+            .finish()
+    }
+}
+
+/// Generate a message identifier ordered by the current time, returning
+/// the identifier and the current time in milliseconds
+pub(crate) fn generate_message_id() -> (String, i64) {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let mut rand = [0u8; 8];
+    fill_random(&mut rand);
+    (
+        format!("{:016x}{}", now.as_micros() as u64, HexRepr(rand)),
+        now.as_millis() as i64,
+    )
+}
+
+/// The entry name of a queued message
+pub(crate) fn message_name(recipient: &str, id: &str) -> String {
+    format!("{}#{}", recipient, id)
+}
+
+/// Split an entry name into the recipient and message identifier. The
+/// identifier never contains `#`, while the recipient may.
+fn split_message_name(name: &str) -> Option<(&str, &str)> {
+    name.rsplit_once('#')
+}
+
+pub(crate) fn message_tags(recipient: &str, created: i64) -> Vec<EntryTag> {
+    vec![
+        EntryTag::Encrypted(RECIPIENT_TAG.to_string(), recipient.to_string()),
+        EntryTag::Plaintext(CREATED_TAG.to_string(), created.to_string()),
+    ]
+}
+
+pub(crate) fn recipient_filter(recipient: &str) -> TagFilter {
+    TagFilter::is_eq(RECIPIENT_TAG, recipient)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn message_names() {
+        let (first, _) = generate_message_id();
+        let (second, _) = generate_message_id();
+        assert!(first[..16] <= second[..16]);
+        assert!(!first.contains('#'));
+        let name = message_name("did:example:123#key-1", &first);
+        assert_eq!(
+            split_message_name(&name),
+            Some(("did:example:123#key-1", first.as_str()))
+        );
+    }
+}
@@ -0,0 +1,162 @@
+//! Scans of a category across multiple profiles of a store
+
+use std::{
+    collections::VecDeque,
+    fmt::{self, Debug, Formatter},
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_lite::stream::Stream;
+
+use crate::{
+    error::Error,
+    future::BoxFuture,
+    storage::{
+        any::AnyBackend,
+        backend::Backend,
+        entry::{Entry, EntryKind, Scan, TagFilter, TagSelection},
+        Error as StorageError,
+    },
+};
+
+type OpenScan = BoxFuture<'static, Result<Scan<'static, Entry>, StorageError>>;
+
+/// A scan of the item entries in multiple profiles, performed concurrently
+///
+/// The scan is consumed as a [`Stream`] of `(profile, entry)` pairs. At most
+/// `concurrency` profiles are scanned at a time, each holding an open
+/// connection to the backend, and a new profile is scanned as each profile
+/// scan completes. Entries of different profiles are interleaved as they are
+/// fetched, while the entries of each profile are returned in order.
+///
+/// An error opening or fetching the scan of a profile is returned in place of
+/// its remaining entries, and the remaining profiles continue to be scanned.
+pub struct ProfileScan {
+    backend: AnyBackend,
+    category: Option<String>,
+    tag_filter: Option<TagFilter>,
+    concurrency: usize,
+    pending: VecDeque<String>,
+    opening: Vec<(String, OpenScan)>,
+    active: Vec<(String, Scan<'static, Entry>)>,
+    next: usize,
+}
+
+impl ProfileScan {
+    pub(crate) fn new(
+        backend: AnyBackend,
+        profiles: Vec<String>,
+        category: Option<String>,
+        tag_filter: Option<TagFilter>,
+        concurrency: usize,
+    ) -> Self {
+        Self {
+            backend,
+            category,
+            tag_filter,
+            concurrency: concurrency.max(1),
+            pending: profiles.into(),
+            opening: Vec::new(),
+            active: Vec::new(),
+            next: 0,
+        }
+    }
+
+    /// The number of profiles which have not yet been fully scanned
+    pub fn remaining_profiles(&self) -> usize {
+        self.pending.len() + self.opening.len() + self.active.len()
+    }
+
+    fn open_scan(&self, profile: String) -> OpenScan {
+        let backend = self.backend.clone();
+        let category = self.category.clone();
+        let tag_filter = self.tag_filter.clone();
+        Box::pin(async move {
+            backend
+                .scan(
+                    Some(profile),
+                    Some(EntryKind::Item),
+                    category,
+                    tag_filter,
+                    None,
+                    None,
+                    None,
+                    false,
+                    None,
+                    TagSelection::All,
+                )
+                .await
+        })
+    }
+}
+
+impl Stream for ProfileScan {
+    type Item = Result<(String, Entry), Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        'fill: loop {
+            while this.opening.len() + this.active.len() < this.concurrency {
+                let Some(profile) = this.pending.pop_front() else {
+                    break;
+                };
+                let open = this.open_scan(profile.clone());
+                this.opening.push((profile, open));
+            }
+
+            let mut idx = 0;
+            while idx < this.opening.len() {
+                match this.opening[idx].1.as_mut().poll(cx) {
+                    Poll::Ready(Ok(scan)) => {
+                        let (profile, _) = this.opening.swap_remove(idx);
+                        this.active.push((profile, scan));
+                    }
+                    Poll::Ready(Err(err)) => {
+                        drop(this.opening.swap_remove(idx));
+                        return Poll::Ready(Some(Err(err.into())));
+                    }
+                    Poll::Pending => idx += 1,
+                }
+            }
+
+            // poll the active scans in turn, so that no profile is starved
+            for _ in 0..this.active.len() {
+                let idx = this.next % this.active.len();
+                let (profile, scan) = &mut this.active[idx];
+                match Pin::new(scan).poll_next(cx) {
+                    Poll::Ready(Some(Ok(entry))) => {
+                        let profile = profile.clone();
+                        this.next = idx + 1;
+                        return Poll::Ready(Some(Ok((profile, entry))));
+                    }
+                    Poll::Ready(Some(Err(err))) => {
+                        this.active.swap_remove(idx);
+                        return Poll::Ready(Some(Err(err.into())));
+                    }
+                    Poll::Ready(None) => {
+                        this.active.swap_remove(idx);
+                        continue 'fill;
+                    }
+                    Poll::Pending => this.next = idx + 1,
+                }
+            }
+
+            if this.remaining_profiles() == 0 {
+                return Poll::Ready(None);
+            }
+            return Poll::Pending;
+        }
+    }
+}
+
+impl Debug for ProfileScan {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProfileScan")
+            .field("category", &self.category)
+            .field("tag_filter", &self.tag_filter)
+            .field("concurrency", &self.concurrency)
+            .field("remaining_profiles", &self.remaining_profiles())
+            .finish()
+    }
+}
@@ -1,25 +1,145 @@
-use askar_storage::backend::copy_profile;
+use std::{
+    collections::HashMap,
+    io::{Read, Write},
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use askar_storage::backend::{copy_profile, copy_profile_entries};
+use futures_lite::future::zip;
 
 use crate::{
-    error::Error,
-    kms::{KeyEntry, KeyParams, KmsCategory, LocalKey},
+    cache::EntryCache,
+    chunk::{read_chunk, ChunkManifest, CHUNK_FETCH_LIMIT, CHUNK_SIZE},
+    crypto::{buffer::SecretBytes, random::fill_random},
+    error::{Error, ErrorKind},
+    export::{ExportKey, ExportReader, ExportRecord, ExportWriter, ImportConflict},
+    future::{sleep, BoxFuture},
+    kms::{
+        key_metadata_tags, key_version_name, AttestationBuilder, AttestationFormat, Encrypted,
+        KeyAlg, KeyAttestation, KeyBackend, KeyEntry, KeyOperation, KeyParams, KeyPolicy, KeyScan,
+        KmsCategory, LocalKey, LocalKeyCache, KEY_CREATED_TAG, KEY_EXPIRES_TAG,
+        KEY_METADATA_TAG_PREFIX, KEY_ROTATED_TAG, KEY_VERSION_TAG,
+    },
+    oid4vc::{decode_record, encode_record, TypedRecord},
+    queue::{
+        generate_message_id, message_name, message_tags, recipient_filter, MessageSubscription,
+        QueuedMessage, MESSAGE_QUEUE_CATEGORY,
+    },
+    scan::ProfileScan,
     storage::{
         any::{AnyBackend, AnyBackendSession},
-        backend::{Backend, BackendSession, ManageBackend},
-        entry::{Entry, EntryKind, EntryOperation, EntryTag, Scan, TagFilter},
-        generate_raw_store_key,
+        backend::{Backend, BackendSession, ManageBackend, SavepointOperation},
+        combine_pass_key,
+        entry::{
+            Entry, EntryCount, EntryEvent, EntryKind, EntryOperation, EntryPrecondition, EntryTag,
+            OrderBy, Scan, Subscription, TagFilter, TagSelection,
+        },
+        generate_raw_store_key, quorum_pass_key, split_pass_key, ErrorKind as StorageErrorKind,
     },
+    universal_wallet::{read_wallet, WalletContent, WalletWriter},
 };
 
-pub use crate::storage::{entry, PassKey, StoreKeyMethod};
+pub use crate::storage::{
+    entry, AuditRecord, IntegrityIssue, IntegrityReport, IsolationLevel, PassKey, ProfileDetails,
+    ProfileLimits, QueryPlan, RekeyProgress, RepairReport, StoreKeyMethod,
+};
+
+/// The maximum number of attempts made by `Store::run_transaction`
+const TXN_RETRY_ATTEMPTS: usize = 5;
+
+/// The delay before the first retry of a transaction
+const TXN_RETRY_BASE_DELAY: Duration = Duration::from_millis(10);
+
+/// The upper limit on the delay between retries of a transaction
+const TXN_RETRY_MAX_DELAY: Duration = Duration::from_millis(500);
+
+/// The name of an entry update operation as recorded in the audit log
+fn operation_name(operation: EntryOperation) -> &'static str {
+    match operation {
+        EntryOperation::Insert => "insert",
+        EntryOperation::Replace => "replace",
+        EntryOperation::Remove => "remove",
+    }
+}
+
+/// Build the tag filter used to select key entries
+fn key_tag_filter(
+    algorithm: Option<&str>,
+    thumbprint: Option<&str>,
+    tag_filter: Option<TagFilter>,
+    created_after: Option<i64>,
+    created_before: Option<i64>,
+) -> Option<TagFilter> {
+    let mut query_parts = Vec::with_capacity(5);
+    if let Some(query) = tag_filter.map(|f| f.into_query()) {
+        query_parts.push(TagFilter::from(
+            query
+                .map_names(|mut k| {
+                    // indexed metadata fields are not user tags
+                    if !k.starts_with(KEY_METADATA_TAG_PREFIX) {
+                        k.replace_range(0..0, "user:");
+                    }
+                    Result::<_, ()>::Ok(k)
+                })
+                .unwrap(),
+        ));
+    }
+    if let Some(algorithm) = algorithm {
+        query_parts.push(TagFilter::is_eq("alg", algorithm));
+    }
+    if let Some(thumbprint) = thumbprint {
+        query_parts.push(TagFilter::is_eq("thumb", thumbprint));
+    }
+    if let Some(created_after) = created_after {
+        query_parts.push(TagFilter::is_gte(
+            TagFilter::numeric_tag(KEY_CREATED_TAG),
+            created_after.to_string(),
+        ));
+    }
+    if let Some(created_before) = created_before {
+        query_parts.push(TagFilter::is_lt(
+            TagFilter::numeric_tag(KEY_CREATED_TAG),
+            created_before.to_string(),
+        ));
+    }
+    if query_parts.is_empty() {
+        None
+    } else {
+        Some(TagFilter::all_of(query_parts))
+    }
+}
+
+/// Build the parameters of a key entry for a local or external key
+fn local_key_params(key: &LocalKey, metadata: Option<&str>) -> Result<KeyParams, Error> {
+    Ok(KeyParams {
+        metadata: metadata.map(str::to_string),
+        json: None,
+        reference: key.reference().map(str::to_string),
+        data: if key.reference().is_some() {
+            Some(key.to_jwk_public(None)?.into())
+        } else {
+            Some(key.encode()?)
+        },
+        policy: key.policy.clone(),
+    })
+}
 
 #[derive(Debug, Clone)]
 /// An instance of an opened store
-pub struct Store(AnyBackend);
+pub struct Store {
+    backend: AnyBackend,
+    key_cache: Arc<LocalKeyCache>,
+    entry_cache: Arc<EntryCache>,
+}
 
 impl Store {
-    pub(crate) fn new(inner: AnyBackend) -> Self {
-        Self(inner)
+    pub(crate) fn new(backend: AnyBackend) -> Self {
+        Self {
+            backend,
+            key_cache: Arc::default(),
+            entry_cache: Arc::default(),
+        }
     }
 
     /// Provision a new store instance using a database URL
@@ -47,6 +167,18 @@ impl Store {
         Ok(Self::new(backend))
     }
 
+    /// Open a store instance scoped to a single profile, using the
+    /// profile-specific pass key. Operations requiring the store key,
+    /// including access to other profiles, are not permitted.
+    pub async fn open_profile(
+        db_url: &str,
+        profile: String,
+        pass_key: PassKey<'_>,
+    ) -> Result<Self, Error> {
+        let backend = db_url.open_profile_backend(profile, pass_key).await?;
+        Ok(Self::new(backend))
+    }
+
     /// Remove a store instance using a database URL
     pub async fn remove(db_url: &str) -> Result<bool, Error> {
         Ok(db_url.remove_backend().await?)
@@ -57,19 +189,48 @@ impl Store {
         Ok(generate_raw_store_key(seed)?)
     }
 
+    /// Split a pass key into `shares` key shares using Shamir secret sharing,
+    /// such that any `threshold` of the shares are required to recover it
+    ///
+    /// The shares may be combined using `combine_key_shares` in order to
+    /// obtain the pass key when opening the store.
+    pub fn split_key(
+        pass_key: PassKey<'_>,
+        threshold: u8,
+        shares: u8,
+    ) -> Result<Vec<PassKey<'static>>, Error> {
+        Ok(split_pass_key(&pass_key, threshold, shares)?)
+    }
+
+    /// Recover a pass key from key shares produced by `split_key`
+    pub fn combine_key_shares(shares: &[PassKey<'_>]) -> Result<PassKey<'static>, Error> {
+        Ok(combine_pass_key(shares)?)
+    }
+
+    /// Combine the secrets of the slot holders of a quorum store key into a
+    /// pass key for opening the store, using `None` for each slot whose
+    /// secret is not provided
+    ///
+    /// The secrets of all slots are required when creating a store using a
+    /// `StoreKeyMethod::Quorum` method, while only the threshold number of
+    /// secrets are required to open it.
+    pub fn quorum_pass_key(secrets: &[Option<PassKey<'_>>]) -> Result<PassKey<'static>, Error> {
+        Ok(quorum_pass_key(secrets)?)
+    }
+
     /// Get the default profile name used when starting a scan or a session
     pub fn get_active_profile(&self) -> String {
-        self.0.get_active_profile()
+        self.backend.get_active_profile()
     }
 
     /// Get the default profile name used when opening the Store
     pub async fn get_default_profile(&self) -> Result<String, Error> {
-        Ok(self.0.get_default_profile().await?)
+        Ok(self.backend.get_default_profile().await?)
     }
 
     /// Set the default profile name used when opening the Store
     pub async fn set_default_profile(&self, profile: String) -> Result<(), Error> {
-        Ok(self.0.set_default_profile(profile).await?)
+        Ok(self.backend.set_default_profile(profile).await?)
     }
 
     /// Replace the wrapping key on a store
@@ -78,7 +239,48 @@ impl Store {
         method: StoreKeyMethod,
         pass_key: PassKey<'_>,
     ) -> Result<(), Error> {
-        Ok(self.0.rekey(method, pass_key).await?)
+        Ok(self.backend.rekey(method, pass_key).await?)
+    }
+
+    /// Replace the wrapping key on a store, reporting the progress of the
+    /// re-encryption of the store profiles to the `progress` callback
+    ///
+    /// The profiles are re-encrypted in batches and a checkpoint is recorded
+    /// after each batch. If the rekey is interrupted, it may be resumed by
+    /// repeating the operation with the same key method and pass key, and is
+    /// otherwise completed when the store is next opened using either the
+    /// previous or the new pass key.
+    pub async fn rekey_with_progress(
+        &mut self,
+        method: StoreKeyMethod,
+        pass_key: PassKey<'_>,
+        progress: impl Fn(RekeyProgress) + Send + Sync + 'static,
+    ) -> Result<(), Error> {
+        Ok(self
+            .backend
+            .rekey_with_progress(method, pass_key, Some(Arc::new(progress)))
+            .await?)
+    }
+
+    /// Re-derive the store key under the key derivation method `minimum`,
+    /// without changing the passphrase, if the store key is currently derived
+    /// using weaker parameters
+    ///
+    /// The passphrase must match the current store key. Stores using a raw
+    /// store key are left unchanged. Returns `true` if the store key was
+    /// replaced.
+    pub async fn upgrade_kdf(
+        &mut self,
+        minimum: StoreKeyMethod,
+        pass_key: PassKey<'_>,
+    ) -> Result<bool, Error> {
+        let StoreKeyMethod::DeriveKey(minimum) = minimum else {
+            return Err(err_msg!(
+                Input,
+                "Minimum store key method must use key derivation"
+            ));
+        };
+        Ok(self.backend.upgrade_kdf(minimum, pass_key).await?)
     }
 
     /// Copy to a new store instance using a database URL
@@ -95,29 +297,457 @@ impl Store {
             .provision_backend(key_method, pass_key, Some(default_profile), recreate)
             .await?;
         for profile in profile_ids {
-            copy_profile(&self.0, &target, &profile, &profile).await?;
+            copy_profile(&self.backend, &target, &profile, &profile).await?;
         }
         Ok(Self::new(target))
     }
 
+    /// Copy records into another open store
+    ///
+    /// Entries and keys are re-encrypted using the keys of the target store.
+    /// All profiles are copied unless `profiles` is provided, and all item
+    /// categories are copied unless `categories` is provided. Profiles are
+    /// created in the target store as necessary, and each profile is copied
+    /// within a single transaction.
+    pub async fn copy_to_store(
+        &self,
+        target: &Store,
+        profiles: Option<&[String]>,
+        categories: Option<&[String]>,
+    ) -> Result<(), Error> {
+        let profile_ids = match profiles {
+            Some(profiles) => profiles.to_vec(),
+            None => self.list_profiles().await?,
+        };
+        for profile in profile_ids {
+            copy_profile_entries(
+                &self.backend,
+                &target.backend,
+                &profile,
+                &profile,
+                categories,
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Copy the records of a profile into a profile of another open store
+    ///
+    /// Entries and keys are re-encrypted using the keys of the target store.
+    /// The target profile is created as necessary, and the records are copied
+    /// within a single transaction.
+    pub async fn copy_profile_to_store(
+        &self,
+        target: &Store,
+        from_profile: &str,
+        to_profile: &str,
+    ) -> Result<(), Error> {
+        copy_profile_entries(
+            &self.backend,
+            &target.backend,
+            from_profile,
+            to_profile,
+            None,
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Write a consistent point-in-time copy of the store to a new location
+    ///
+    /// The target must be a database URL or path for the same kind of backend,
+    /// and must not already contain a store. The copy may be opened using the
+    /// same store key as the original.
+    pub async fn snapshot(&self, target: &str) -> Result<(), Error> {
+        Ok(self.backend.snapshot(target).await?)
+    }
+
+    /// Check that every profile key and entry in the store can be decrypted,
+    /// returning a report of the problems found
+    ///
+    /// When `quarantine` is set, entries which fail verification are marked as
+    /// deleted. Quarantined entries are no longer returned by the store, but
+    /// may be restored or purged in the same manner as soft-deleted entries.
+    pub async fn verify_integrity(&self, quarantine: bool) -> Result<IntegrityReport, Error> {
+        Ok(self.backend.verify_integrity(quarantine).await?)
+    }
+
+    /// Check the structure of the store, returning a report of the problems found
+    ///
+    /// Tag and chunk rows without an associated entry, as well as entries and
+    /// audit records associated with a missing profile, are removed. Shared
+    /// value reference counts are corrected, a missing default profile is
+    /// replaced by the first remaining profile, and the indexes are rebuilt.
+    /// When `dry_run` is set, the store is not modified.
+    pub async fn repair(&self, dry_run: bool) -> Result<RepairReport, Error> {
+        let report = self.backend.repair(dry_run).await?;
+        if !dry_run {
+            self.entry_cache.purge();
+        }
+        Ok(report)
+    }
+
+    /// Write an encrypted export of the store contents to an output stream
+    ///
+    /// The export includes the profiles of the store along with their entries,
+    /// tags and keys. All profiles are exported unless `profiles` is provided.
+    /// The output stream is returned once the export has been completed.
+    pub async fn export<W: Write>(
+        &self,
+        writer: W,
+        key: ExportKey<'_>,
+        profiles: Option<&[String]>,
+    ) -> Result<W, Error> {
+        let default_profile = self.get_default_profile().await?;
+        let profile_ids = match profiles {
+            Some(profiles) => profiles.to_vec(),
+            None => self.list_profiles().await?,
+        };
+        let mut export = ExportWriter::new(writer, key)?;
+        for profile in profile_ids {
+            let mut scan = self
+                .backend
+                .scan(
+                    Some(profile.clone()),
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    false,
+                    None,
+                    TagSelection::All,
+                )
+                .await?;
+            export.write_record(&ExportRecord::Profile {
+                default: profile == default_profile,
+                name: profile,
+            })?;
+            // encrypt each page of entries while the next page is fetched
+            let mut rows = scan.fetch_next().await?;
+            while let Some(entries) = rows {
+                let records = entries.into_iter().map(ExportRecord::from_entry).collect();
+                let (written, next) = zip(export.write_records(records), scan.fetch_next()).await;
+                written?;
+                rows = next?;
+            }
+        }
+        export.finish()
+    }
+
+    /// Import the contents of an encrypted export into the store
+    ///
+    /// Profiles are created as necessary, and the entries of each profile are
+    /// imported within a single transaction. Entries which already exist are
+    /// handled according to `conflict`. When `categories` is provided, only
+    /// item entries within those categories are imported. Key entries are
+    /// always included.
+    pub async fn import<R: Read>(
+        &self,
+        reader: R,
+        key: ExportKey<'_>,
+        conflict: ImportConflict,
+        categories: Option<&[String]>,
+    ) -> Result<(), Error> {
+        let mut import = ExportReader::new(reader, key)?;
+        let mut txn: Option<AnyBackendSession> = None;
+        // write each batch of records while the next batch is decrypted
+        let mut records = import.read_records().await?;
+        while !records.is_empty() {
+            let (written, next) = zip(
+                self.import_records(&mut txn, records, conflict, categories),
+                import.read_records(),
+            )
+            .await;
+            written?;
+            records = next?;
+        }
+        if let Some(mut txn) = txn {
+            txn.close(true).await?;
+            self.entry_cache.purge();
+        }
+        Ok(())
+    }
+
+    async fn import_records(
+        &self,
+        txn: &mut Option<AnyBackendSession>,
+        records: Vec<ExportRecord>,
+        conflict: ImportConflict,
+        categories: Option<&[String]>,
+    ) -> Result<(), Error> {
+        // new entries are inserted together, so that they may be encrypted
+        // concurrently
+        let mut inserts = Vec::new();
+        for record in records {
+            if let ExportRecord::Profile { name, .. } = record {
+                if let Some(mut txn) = txn.take() {
+                    if !inserts.is_empty() {
+                        txn.insert_many(&inserts, None).await?;
+                        inserts.clear();
+                    }
+                    txn.close(true).await?;
+                    self.entry_cache.purge();
+                }
+                if let Err(err) = self.backend.create_profile(Some(name.clone())).await {
+                    if err.kind() != StorageErrorKind::Duplicate {
+                        return Err(err.into());
+                    }
+                }
+                txn.replace(self.backend.session(Some(name), true)?);
+                continue;
+            }
+            let entry = match record.into_entry()? {
+                Some(entry) => entry,
+                None => continue,
+            };
+            let txn = txn
+                .as_mut()
+                .ok_or_else(|| err_msg!(Input, "Export entry precedes any profile"))?;
+            if let (EntryKind::Item, Some(categories)) = (entry.kind, categories) {
+                if !categories.contains(&entry.category) {
+                    continue;
+                }
+            }
+            let exists = txn
+                .fetch(entry.kind, &entry.category, &entry.name, true)
+                .await?
+                .is_some();
+            match (exists, conflict) {
+                (false, _) => inserts.push(entry),
+                (true, ImportConflict::Skip) => continue,
+                (true, ImportConflict::Overwrite) => {
+                    txn.update(
+                        entry.kind,
+                        EntryOperation::Replace,
+                        &entry.category,
+                        &entry.name,
+                        Some(entry.value.as_ref()),
+                        Some(entry.tags.as_slice()),
+                        None,
+                    )
+                    .await?;
+                }
+                (true, ImportConflict::Fail) => {
+                    return Err(err_msg!(
+                        Duplicate,
+                        "Imported entry already exists: {}",
+                        entry.name
+                    ));
+                }
+            }
+        }
+        if let (Some(txn), false) = (txn.as_mut(), inserts.is_empty()) {
+            txn.insert_many(&inserts, None).await?;
+        }
+        Ok(())
+    }
+
+    /// Export the items and keys of a profile as a Universal Wallet 2020
+    /// document
+    ///
+    /// When `key` is provided, each content object is encrypted and the
+    /// wallet is marked as locked. Keys held by an external key provider are
+    /// not included.
+    pub async fn export_universal_wallet(
+        &self,
+        key: Option<ExportKey<'_>>,
+        profile: Option<String>,
+    ) -> Result<String, Error> {
+        let mut wallet = WalletWriter::new(key)?;
+        let mut scan = self
+            .backend
+            .scan(
+                profile.clone(),
+                Some(EntryKind::Item),
+                None,
+                None,
+                None,
+                None,
+                None,
+                false,
+                None,
+                TagSelection::All,
+            )
+            .await?;
+        while let Some(rows) = scan.fetch_next().await? {
+            for entry in rows {
+                wallet.add_entry(&entry)?;
+            }
+        }
+        drop(scan);
+        let mut session = self.session(profile).await?;
+        let keys = session
+            .fetch_all_keys(None, None, None, None, None, None, None, false)
+            .await?;
+        for key in keys.iter().filter(|key| key.is_local()) {
+            wallet.add_key(key.name(), &key.load_local_key()?)?;
+        }
+        wallet.finish()
+    }
+
+    /// Import the contents of a Universal Wallet 2020 document into a profile
+    ///
+    /// A locked wallet requires the passphrase or recipient secret key used
+    /// to lock it. Contents are imported within a single transaction, and
+    /// entries or keys which already exist are handled according to
+    /// `conflict`. Keys without an identifier are named by their JWK
+    /// thumbprint.
+    pub async fn import_universal_wallet(
+        &self,
+        wallet: &str,
+        key: Option<ExportKey<'_>>,
+        conflict: ImportConflict,
+        profile: Option<String>,
+    ) -> Result<(), Error> {
+        let contents = read_wallet(wallet, key)?;
+        let mut txn = self.transaction(profile).await?;
+        for content in contents {
+            match content {
+                WalletContent::Item(entry) => {
+                    let exists = txn
+                        .fetch(&entry.category, &entry.name, true)
+                        .await?
+                        .is_some();
+                    match (exists, conflict) {
+                        (false, _) => {
+                            txn.insert(
+                                &entry.category,
+                                &entry.name,
+                                &entry.value,
+                                Some(&entry.tags),
+                                None,
+                            )
+                            .await?
+                        }
+                        (true, ImportConflict::Skip) => (),
+                        (true, ImportConflict::Overwrite) => {
+                            txn.replace(
+                                &entry.category,
+                                &entry.name,
+                                &entry.value,
+                                Some(&entry.tags),
+                                None,
+                            )
+                            .await?
+                        }
+                        (true, ImportConflict::Fail) => {
+                            return Err(err_msg!(
+                                Duplicate,
+                                "Imported entry already exists: {}",
+                                entry.name
+                            ));
+                        }
+                    }
+                }
+                WalletContent::Key { name, key } => {
+                    let name = match name {
+                        Some(name) => name,
+                        None => key.to_jwk_thumbprint(None)?,
+                    };
+                    if txn.fetch_key(&name, true).await?.is_some() {
+                        match conflict {
+                            ImportConflict::Skip => continue,
+                            ImportConflict::Overwrite => txn.remove_key(&name).await?,
+                            ImportConflict::Fail => {
+                                return Err(err_msg!(
+                                    Duplicate,
+                                    "Imported key already exists: {}",
+                                    name
+                                ));
+                            }
+                        }
+                    }
+                    txn.insert_key(&name, &key, None, None, None).await?;
+                }
+            }
+        }
+        txn.commit().await
+    }
+
     /// Create a new profile with the given profile name
     pub async fn create_profile(&self, name: Option<String>) -> Result<String, Error> {
-        Ok(self.0.create_profile(name).await?)
+        Ok(self.backend.create_profile(name).await?)
     }
 
     /// Get the details of all store profiles
     pub async fn list_profiles(&self) -> Result<Vec<String>, Error> {
-        Ok(self.0.list_profiles().await?)
+        Ok(self.backend.list_profiles().await?)
+    }
+
+    /// Get the details of all store profiles, including the number of
+    /// entries and any custom metadata
+    pub async fn list_profile_details(&self) -> Result<Vec<ProfileDetails>, Error> {
+        Ok(self.backend.list_profile_details().await?)
+    }
+
+    /// Replace the custom metadata associated with a profile
+    pub async fn set_profile_metadata(
+        &self,
+        name: String,
+        metadata: Option<&[u8]>,
+    ) -> Result<(), Error> {
+        Ok(self
+            .backend
+            .set_profile_metadata(name, metadata.map(<[u8]>::to_vec))
+            .await?)
+    }
+
+    /// Replace the storage limits of a profile. Inserts which would exceed
+    /// the limits fail with a `QuotaExceeded` error.
+    pub async fn set_profile_limits(
+        &self,
+        name: String,
+        limits: ProfileLimits,
+    ) -> Result<(), Error> {
+        Ok(self.backend.set_profile_limits(name, limits).await?)
+    }
+
+    /// Allow a profile to be opened using a profile-specific pass key
+    pub async fn set_profile_access_key(
+        &self,
+        name: String,
+        method: StoreKeyMethod,
+        pass_key: PassKey<'_>,
+    ) -> Result<(), Error> {
+        Ok(self
+            .backend
+            .set_profile_access_key(name, method, pass_key)
+            .await?)
+    }
+
+    /// Remove the profile-specific pass key of a profile
+    pub async fn remove_profile_access_key(&self, name: String) -> Result<(), Error> {
+        Ok(self.backend.remove_profile_access_key(name).await?)
+    }
+
+    /// Rename an existing profile. If the profile is the default profile for
+    /// the store, the default profile name is updated as well.
+    pub async fn rename_profile(&self, name: String, new_name: String) -> Result<(), Error> {
+        self.key_cache.purge();
+        self.entry_cache.purge();
+        Ok(self.backend.rename_profile(name, new_name).await?)
     }
 
     /// Remove an existing profile with the given profile name
     pub async fn remove_profile(&self, name: String) -> Result<bool, Error> {
-        Ok(self.0.remove_profile(name).await?)
+        self.key_cache.purge();
+        self.entry_cache.purge();
+        Ok(self.backend.remove_profile(name).await?)
     }
 
     /// Create a new scan instance against the store
     ///
-    /// The result will keep an open connection to the backend until it is consumed
+    /// The result will keep an open connection to the backend until it is consumed.
+    /// Results may be sorted by passing `order_by`, in descending order if
+    /// `descending` is set. An ordered scan may be resumed after the last row of a
+    /// previous scan by passing its continuation token as `after`. The token is
+    /// encrypted with the profile key and is rejected if modified. The tags
+    /// returned with each row are determined by `tags`, and tags which are
+    /// not selected are not decrypted.
+    #[allow(clippy::too_many_arguments)]
     pub async fn scan(
         &self,
         profile: Option<String>,
@@ -125,9 +755,13 @@ impl Store {
         tag_filter: Option<TagFilter>,
         offset: Option<i64>,
         limit: Option<i64>,
+        order_by: Option<OrderBy>,
+        descending: bool,
+        after: Option<String>,
+        tags: TagSelection,
     ) -> Result<Scan<'static, Entry>, Error> {
         Ok(self
-            .0
+            .backend
             .scan(
                 profile,
                 Some(EntryKind::Item),
@@ -135,15 +769,178 @@ impl Store {
                 tag_filter,
                 offset,
                 limit,
+                order_by,
+                descending,
+                after,
+                tags,
+            )
+            .await?)
+    }
+
+    /// Create a new scan of the items in a category across multiple profiles
+    ///
+    /// The listed profiles are scanned, or every profile of the store when
+    /// `profiles` is `None`, with up to `concurrency` profile scans in
+    /// progress at a time. Each profile scan keeps an open connection to the
+    /// backend until it is consumed, so `concurrency` should not exceed the
+    /// size of the connection pool.
+    pub async fn scan_profiles(
+        &self,
+        profiles: Option<Vec<String>>,
+        category: Option<String>,
+        tag_filter: Option<TagFilter>,
+        concurrency: usize,
+    ) -> Result<ProfileScan, Error> {
+        let profiles = match profiles {
+            Some(profiles) => profiles,
+            None => self.backend.list_profiles().await?,
+        };
+        Ok(ProfileScan::new(
+            self.backend.clone(),
+            profiles,
+            category,
+            tag_filter,
+            concurrency,
+        ))
+    }
+
+    /// Create a new scan of the key entries in the store
+    ///
+    /// Keys may be filtered by algorithm, thumbprint and user tags, and by
+    /// creation time: only keys created at or after `created_after` and before
+    /// `created_before` (in seconds since the Unix epoch) are returned when these
+    /// are provided. As with `scan`, the result will keep an open connection
+    /// to the backend until it is consumed.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn scan_keys(
+        &self,
+        profile: Option<String>,
+        algorithm: Option<&str>,
+        thumbprint: Option<&str>,
+        tag_filter: Option<TagFilter>,
+        created_after: Option<i64>,
+        created_before: Option<i64>,
+        offset: Option<i64>,
+        limit: Option<i64>,
+    ) -> Result<KeyScan, Error> {
+        let tag_filter = key_tag_filter(
+            algorithm,
+            thumbprint,
+            tag_filter,
+            created_after,
+            created_before,
+        );
+        let scan = self
+            .backend
+            .scan(
+                profile,
+                Some(EntryKind::Kms),
+                Some(KmsCategory::CryptoKey.as_str().to_string()),
+                tag_filter,
+                offset,
+                limit,
+                None,
+                false,
+                None,
+                TagSelection::All,
             )
+            .await?;
+        Ok(KeyScan::new(scan))
+    }
+
+    /// Subscribe to changes to the items in a profile, optionally
+    /// restricted to a single category
+    ///
+    /// Events are delivered once the corresponding changes have been committed
+    pub async fn subscribe(
+        &self,
+        profile: Option<String>,
+        category: Option<String>,
+    ) -> Result<Subscription<'static>, Error> {
+        Ok(self
+            .backend
+            .subscribe(profile, Some(EntryKind::Item), category)
             .await?)
     }
 
+    /// Subscribe to the messages queued in a profile, optionally restricted
+    /// to a single recipient
+    ///
+    /// This may be used to deliver messages to a recipient with an active
+    /// connection as soon as they are queued.
+    pub async fn subscribe_messages(
+        &self,
+        profile: Option<String>,
+        recipient: Option<String>,
+    ) -> Result<MessageSubscription, Error> {
+        let inner = self
+            .subscribe(profile, Some(MESSAGE_QUEUE_CATEGORY.to_string()))
+            .await?;
+        Ok(MessageSubscription::new(inner, recipient))
+    }
+
+    /// Enable the caching of keys loaded using `Session::load_key`, shared
+    /// between all sessions of the store, retaining each key for the
+    /// time-to-live `ttl`. Passing `None` disables and purges the cache.
+    ///
+    /// Keys are removed from the cache when the key entry is updated,
+    /// rotated or removed using a session of this store, but changes made
+    /// by other store instances are not visible until the key expires from
+    /// the cache.
+    pub fn set_key_cache(&self, ttl: Option<Duration>) {
+        self.key_cache.set_ttl(ttl);
+    }
+
+    /// Remove all keys from the key cache of the store
+    pub fn purge_key_cache(&self) {
+        self.key_cache.purge();
+    }
+
+    /// Enable the caching of records retrieved using `Session::fetch`, shared
+    /// between all sessions of the store. Up to `capacity` records are
+    /// retained, displacing the least recently used records, each for the
+    /// optional time-to-live `ttl`. Passing a capacity of zero disables and
+    /// purges the cache.
+    ///
+    /// Records are removed from the cache when they are modified using a
+    /// session of this store, but changes made by other store instances, and
+    /// the expiry of records, are not visible until the record expires from
+    /// the cache. The cache is not used within transactions or when fetching
+    /// records for update.
+    pub fn set_entry_cache(&self, capacity: usize, ttl: Option<Duration>) {
+        self.entry_cache.configure(capacity, ttl);
+    }
+
+    /// Remove all records from the entry cache of the store
+    pub fn purge_entry_cache(&self) {
+        self.entry_cache.purge();
+    }
+
+    fn new_session(
+        &self,
+        inner: AnyBackendSession,
+        profile: Option<String>,
+        transaction: bool,
+    ) -> Session {
+        let profile = profile.unwrap_or_else(|| self.backend.get_active_profile());
+        Session::new(
+            inner,
+            self.key_cache.clone(),
+            profile,
+            self.entry_cache.clone(),
+            transaction,
+        )
+    }
+
     /// Create a new session against the store
     pub async fn session(&self, profile: Option<String>) -> Result<Session, Error> {
-        let mut sess = Session::new(self.0.session(profile, false)?);
+        let mut sess = self.new_session(
+            self.backend.session(profile.clone(), false)?,
+            profile,
+            false,
+        );
         if let Err(e) = sess.ping().await {
-            sess.0.close(false).await?;
+            sess.backend.close(false).await?;
             Err(e)
         } else {
             Ok(sess)
@@ -152,18 +949,75 @@ impl Store {
 
     /// Create a new transaction session against the store
     pub async fn transaction(&self, profile: Option<String>) -> Result<Session, Error> {
-        let mut txn = Session::new(self.0.session(profile, true)?);
+        let mut txn = self.new_session(self.backend.session(profile.clone(), true)?, profile, true);
+        if let Err(e) = txn.ping().await {
+            txn.backend.close(false).await?;
+            Err(e)
+        } else {
+            Ok(txn)
+        }
+    }
+
+    /// Create a new transaction session using a specific isolation level
+    pub async fn transaction_with_isolation(
+        &self,
+        profile: Option<String>,
+        isolation: IsolationLevel,
+    ) -> Result<Session, Error> {
+        let mut txn = self.new_session(
+            self.backend.transaction(profile.clone(), isolation)?,
+            profile,
+            true,
+        );
         if let Err(e) = txn.ping().await {
-            txn.0.close(false).await?;
+            txn.backend.close(false).await?;
             Err(e)
         } else {
             Ok(txn)
         }
     }
 
+    /// Run a function within a new transaction, committing the transaction
+    /// if it succeeds and rolling it back otherwise.
+    ///
+    /// When the transaction fails because the store is busy, for example due
+    /// to a serialization failure or a locked database, the function is run
+    /// again in a fresh transaction after a randomized exponential backoff.
+    /// The function may therefore be invoked more than once.
+    pub async fn run_transaction<T, F>(&self, profile: Option<String>, mut f: F) -> Result<T, Error>
+    where
+        F: for<'s> FnMut(&'s mut Session) -> BoxFuture<'s, Result<T, Error>>,
+    {
+        let mut attempt = 1;
+        loop {
+            let result = async {
+                let mut txn = self.transaction(profile.clone()).await?;
+                match f(&mut txn).await {
+                    Ok(value) => {
+                        txn.commit().await?;
+                        Ok(value)
+                    }
+                    Err(err) => {
+                        // report the original error rather than any failure to roll back
+                        txn.rollback().await.ok();
+                        Err(err)
+                    }
+                }
+            }
+            .await;
+            match result {
+                Err(err) if err.kind() == ErrorKind::Busy && attempt < TXN_RETRY_ATTEMPTS => {
+                    sleep(retry_delay(attempt)).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
     /// Close the store instance, waiting for any shutdown procedures to complete.
     pub async fn close(self) -> Result<(), Error> {
-        Ok(self.0.close().await?)
+        Ok(self.backend.close().await?)
     }
 }
 
@@ -175,11 +1029,33 @@ impl From<AnyBackend> for Store {
 
 /// An active connection to the store backend
 #[derive(Debug)]
-pub struct Session(AnyBackendSession);
+pub struct Session {
+    backend: AnyBackendSession,
+    key_cache: Arc<LocalKeyCache>,
+    profile: String,
+    entry_cache: Arc<EntryCache>,
+    /// The records modified within a transaction, which are removed from the
+    /// entry cache again once the transaction commits
+    modified: Option<Vec<(Option<String>, Option<String>)>>,
+    keys: HashMap<String, Arc<LocalKey>>,
+}
 
 impl Session {
-    pub(crate) fn new(inner: AnyBackendSession) -> Self {
-        Self(inner)
+    pub(crate) fn new(
+        backend: AnyBackendSession,
+        key_cache: Arc<LocalKeyCache>,
+        profile: String,
+        entry_cache: Arc<EntryCache>,
+        transaction: bool,
+    ) -> Self {
+        Self {
+            backend,
+            key_cache,
+            profile,
+            entry_cache,
+            modified: transaction.then(Vec::new),
+            keys: HashMap::new(),
+        }
     }
 
     /// Count the number of entries for a given record category
@@ -189,11 +1065,41 @@ impl Session {
         tag_filter: Option<TagFilter>,
     ) -> Result<i64, Error> {
         Ok(self
-            .0
+            .backend
             .count(Some(EntryKind::Item), category, tag_filter)
             .await?)
     }
 
+    /// Describe the query and execution plan used to scan for entries matching
+    /// a category and tag filter. This is intended for diagnosing slow queries
+    /// and is not supported by custom stores.
+    pub async fn explain_scan(
+        &mut self,
+        category: Option<&str>,
+        tag_filter: Option<TagFilter>,
+    ) -> Result<QueryPlan, Error> {
+        Ok(self
+            .backend
+            .explain_scan(Some(EntryKind::Item), category, tag_filter)
+            .await?)
+    }
+
+    /// Count the number of entries for a given tag filter, grouped by category.
+    ///
+    /// When `group_tag` is provided, counts are further grouped by the value of
+    /// this tag. A `~` prefix indicates a plaintext tag.
+    pub async fn count_grouped(
+        &mut self,
+        category: Option<&str>,
+        tag_filter: Option<TagFilter>,
+        group_tag: Option<&str>,
+    ) -> Result<Vec<EntryCount>, Error> {
+        Ok(self
+            .backend
+            .count_grouped(Some(EntryKind::Item), category, tag_filter, group_tag)
+            .await?)
+    }
+
     /// Retrieve the current record at `(category, name)`.
     ///
     /// Specify `for_update` when in a transaction to create an update lock on the
@@ -204,10 +1110,27 @@ impl Session {
         name: &str,
         for_update: bool,
     ) -> Result<Option<Entry>, Error> {
-        Ok(self
-            .0
-            .fetch(EntryKind::Item, category, name, for_update)
-            .await?)
+        let cached = !for_update && self.modified.is_none();
+        let result = match cached
+            .then(|| self.entry_cache.get(&self.profile, category, name))
+            .flatten()
+        {
+            Some(entry) => Ok(Some(entry)),
+            None => {
+                let generation = self.entry_cache.generation();
+                let result = self
+                    .backend
+                    .fetch(EntryKind::Item, category, name, for_update)
+                    .await
+                    .map_err(Error::from);
+                if let (true, Ok(Some(entry))) = (cached, &result) {
+                    self.entry_cache.insert(generation, &self.profile, entry);
+                }
+                result
+            }
+        };
+        self.audit("fetch", EntryKind::Item, Some(category), Some(name), result)
+            .await
     }
 
     /// Retrieve all records matching the given `category` and `tag_filter`.
@@ -222,8 +1145,8 @@ impl Session {
         limit: Option<i64>,
         for_update: bool,
     ) -> Result<Vec<Entry>, Error> {
-        Ok(self
-            .0
+        let result = self
+            .backend
             .fetch_all(
                 Some(EntryKind::Item),
                 category,
@@ -231,7 +1154,49 @@ impl Session {
                 limit,
                 for_update,
             )
-            .await?)
+            .await
+            .map_err(Error::from);
+        self.audit("fetch_all", EntryKind::Item, category, None, result)
+            .await
+    }
+
+    /// Retrieve multiple records by category and name in a single query
+    ///
+    /// Records are returned in the order of the requested names, and names
+    /// without a matching record are skipped.
+    pub async fn fetch_many(
+        &mut self,
+        category: &str,
+        names: &[&str],
+        for_update: bool,
+    ) -> Result<Vec<Entry>, Error> {
+        let result = self
+            .backend
+            .fetch_many(EntryKind::Item, category, names, for_update)
+            .await
+            .map_err(Error::from);
+        self.audit("fetch_many", EntryKind::Item, Some(category), None, result)
+            .await
+    }
+
+    /// Search for records with plaintext tag values matching a full-text query
+    ///
+    /// Each word in the search text is matched as a prefix of a word within a
+    /// plaintext tag value. Results are returned in order of relevance.
+    pub async fn search(
+        &mut self,
+        category: Option<&str>,
+        text: &str,
+        tag_filter: Option<TagFilter>,
+        limit: Option<i64>,
+    ) -> Result<Vec<Entry>, Error> {
+        let result = self
+            .backend
+            .search(Some(EntryKind::Item), category, text, tag_filter, limit)
+            .await
+            .map_err(Error::from);
+        self.audit("search", EntryKind::Item, category, None, result)
+            .await
     }
 
     /// Insert a new record into the store
@@ -243,8 +1208,8 @@ impl Session {
         tags: Option<&[EntryTag]>,
         expiry_ms: Option<i64>,
     ) -> Result<(), Error> {
-        Ok(self
-            .0
+        let result = self
+            .backend
             .update(
                 EntryKind::Item,
                 EntryOperation::Insert,
@@ -254,13 +1219,61 @@ impl Session {
                 tags,
                 expiry_ms,
             )
-            .await?)
+            .await
+            .map_err(Error::from);
+        self.invalidate_entries(Some(category), Some(name));
+        self.audit(
+            "insert",
+            EntryKind::Item,
+            Some(category),
+            Some(name),
+            result,
+        )
+        .await
+    }
+
+    /// Insert a batch of new records into the store in a single transaction
+    ///
+    /// If any of the records already exists, no records are inserted.
+    pub async fn insert_many(
+        &mut self,
+        entries: &[Entry],
+        expiry_ms: Option<i64>,
+    ) -> Result<(), Error> {
+        if entries.iter().any(|entry| entry.kind != EntryKind::Item) {
+            return Err(err_msg!(Input, "Only item entries may be inserted"));
+        }
+        let result = self
+            .backend
+            .insert_many(entries, expiry_ms)
+            .await
+            .map_err(Error::from);
+        for entry in entries {
+            self.invalidate_entries(Some(&entry.category), Some(&entry.name));
+        }
+        let error = result.as_ref().err().map(|err| err.kind().as_str());
+        for entry in entries {
+            let appended = self
+                .backend
+                .append_audit(
+                    "insert",
+                    Some(EntryKind::Item),
+                    Some(&entry.category),
+                    Some(&entry.name),
+                    error,
+                )
+                .await;
+            if result.is_ok() {
+                appended?;
+            }
+        }
+        result
     }
 
     /// Remove a record from the store
     pub async fn remove(&mut self, category: &str, name: &str) -> Result<(), Error> {
-        Ok(self
-            .0
+        let result = self
+            .backend
             .update(
                 EntryKind::Item,
                 EntryOperation::Remove,
@@ -270,7 +1283,93 @@ impl Session {
                 None,
                 None,
             )
-            .await?)
+            .await
+            .map_err(Error::from);
+        self.invalidate_entries(Some(category), Some(name));
+        self.audit(
+            "remove",
+            EntryKind::Item,
+            Some(category),
+            Some(name),
+            result,
+        )
+        .await
+    }
+
+    /// Remove a record from the store if its current state satisfies `precondition`
+    ///
+    /// Fails with a `Conflict` error if the precondition does not hold.
+    pub async fn remove_if(
+        &mut self,
+        category: &str,
+        name: &str,
+        precondition: &EntryPrecondition,
+    ) -> Result<(), Error> {
+        let result = self
+            .backend
+            .update_if(
+                EntryKind::Item,
+                EntryOperation::Remove,
+                category,
+                name,
+                None,
+                None,
+                None,
+                precondition,
+            )
+            .await
+            .map_err(Error::from);
+        self.invalidate_entries(Some(category), Some(name));
+        self.audit(
+            "remove",
+            EntryKind::Item,
+            Some(category),
+            Some(name),
+            result,
+        )
+        .await
+    }
+
+    /// Restore a record which was removed while soft deletion is enabled
+    pub async fn restore(&mut self, category: &str, name: &str) -> Result<(), Error> {
+        let result = self
+            .backend
+            .restore(EntryKind::Item, category, name)
+            .await
+            .map_err(Error::from);
+        self.invalidate_entries(Some(category), Some(name));
+        self.audit(
+            "restore",
+            EntryKind::Item,
+            Some(category),
+            Some(name),
+            result,
+        )
+        .await
+    }
+
+    /// Permanently remove a record which was removed while soft deletion is enabled
+    pub async fn purge(&mut self, category: &str, name: &str) -> Result<(), Error> {
+        let result = self
+            .backend
+            .purge(EntryKind::Item, category, name)
+            .await
+            .map_err(Error::from);
+        self.invalidate_entries(Some(category), Some(name));
+        self.audit("purge", EntryKind::Item, Some(category), Some(name), result)
+            .await
+    }
+
+    /// Permanently remove all soft-deleted records, optionally limited to a `category`
+    pub async fn purge_all(&mut self, category: Option<&str>) -> Result<i64, Error> {
+        let result = self
+            .backend
+            .purge_all(Some(EntryKind::Item), category)
+            .await
+            .map_err(Error::from);
+        self.invalidate_entries(category, None);
+        self.audit("purge_all", EntryKind::Item, category, None, result)
+            .await
     }
 
     /// Replace the value and tags of a record in the store
@@ -282,8 +1381,8 @@ impl Session {
         tags: Option<&[EntryTag]>,
         expiry_ms: Option<i64>,
     ) -> Result<(), Error> {
-        Ok(self
-            .0
+        let result = self
+            .backend
             .update(
                 EntryKind::Item,
                 EntryOperation::Replace,
@@ -293,7 +1392,55 @@ impl Session {
                 tags,
                 expiry_ms,
             )
-            .await?)
+            .await
+            .map_err(Error::from);
+        self.invalidate_entries(Some(category), Some(name));
+        self.audit(
+            "replace",
+            EntryKind::Item,
+            Some(category),
+            Some(name),
+            result,
+        )
+        .await
+    }
+
+    /// Replace the value and tags of a record in the store if its current state
+    /// satisfies `precondition`
+    ///
+    /// Fails with a `Conflict` error if the precondition does not hold.
+    pub async fn replace_if(
+        &mut self,
+        category: &str,
+        name: &str,
+        value: &[u8],
+        tags: Option<&[EntryTag]>,
+        expiry_ms: Option<i64>,
+        precondition: &EntryPrecondition,
+    ) -> Result<(), Error> {
+        let result = self
+            .backend
+            .update_if(
+                EntryKind::Item,
+                EntryOperation::Replace,
+                category,
+                name,
+                Some(value),
+                tags,
+                expiry_ms,
+                precondition,
+            )
+            .await
+            .map_err(Error::from);
+        self.invalidate_entries(Some(category), Some(name));
+        self.audit(
+            "replace",
+            EntryKind::Item,
+            Some(category),
+            Some(name),
+            result,
+        )
+        .await
     }
 
     /// Remove all records in the store matching a given `category` and `tag_filter`
@@ -302,10 +1449,31 @@ impl Session {
         category: Option<&str>,
         tag_filter: Option<TagFilter>,
     ) -> Result<i64, Error> {
-        Ok(self
-            .0
+        let result = self
+            .backend
             .remove_all(Some(EntryKind::Item), category, tag_filter)
-            .await?)
+            .await
+            .map_err(Error::from);
+        self.invalidate_entries(category, None);
+        self.audit("remove_all", EntryKind::Item, category, None, result)
+            .await
+    }
+
+    /// Remove all records in the store matching a given `category` and `tag_filter`,
+    /// returning a removal event for each record removed
+    pub async fn remove_all_returning(
+        &mut self,
+        category: Option<&str>,
+        tag_filter: Option<TagFilter>,
+    ) -> Result<Vec<EntryEvent>, Error> {
+        let result = self
+            .backend
+            .remove_all_returning(Some(EntryKind::Item), category, tag_filter)
+            .await
+            .map_err(Error::from);
+        self.invalidate_entries(category, None);
+        self.audit("remove_all", EntryKind::Item, category, None, result)
+            .await
     }
 
     /// Perform a record update
@@ -321,8 +1489,8 @@ impl Session {
         tags: Option<&[EntryTag]>,
         expiry_ms: Option<i64>,
     ) -> Result<(), Error> {
-        Ok(self
-            .0
+        let result = self
+            .backend
             .update(
                 EntryKind::Item,
                 operation,
@@ -332,201 +1500,1624 @@ impl Session {
                 tags,
                 expiry_ms,
             )
-            .await?)
+            .await
+            .map_err(Error::from);
+        self.invalidate_entries(Some(category), Some(name));
+        self.audit(
+            operation_name(operation),
+            EntryKind::Item,
+            Some(category),
+            Some(name),
+            result,
+        )
+        .await
     }
 
-    /// Insert a local key instance into the store
-    pub async fn insert_key(
+    /// Move all records in a category to another category, returning the number
+    /// of records moved
+    ///
+    /// Tags on the moved records may be renamed by providing pairs of existing
+    /// and new tag names. The move fails if any record of the same name already
+    /// exists in the target category.
+    pub async fn move_category(
         &mut self,
-        name: &str,
-        key: &LocalKey,
-        metadata: Option<&str>,
-        tags: Option<&[EntryTag]>,
-        expiry_ms: Option<i64>,
+        category: &str,
+        new_category: &str,
+        rename_tags: &[(&str, &str)],
+    ) -> Result<i64, Error> {
+        let result = self
+            .backend
+            .move_category(EntryKind::Item, category, new_category, rename_tags)
+            .await
+            .map_err(Error::from);
+        self.invalidate_entries(Some(category), None);
+        self.audit(
+            "move_category",
+            EntryKind::Item,
+            Some(category),
+            None,
+            result,
+        )
+        .await
+    }
+
+    /// Rename a record in the store, preserving its value, tags and expiry
+    pub async fn rename(
+        &mut self,
+        category: &str,
+        old_name: &str,
+        new_name: &str,
     ) -> Result<(), Error> {
-        let data = key.encode()?;
-        let params = KeyParams {
-            metadata: metadata.map(str::to_string),
-            reference: None,
-            data: Some(data),
-        };
-        let value = params.to_bytes()?;
-        let mut ins_tags = Vec::with_capacity(10);
-        let alg = key.algorithm().as_str();
-        if !alg.is_empty() {
-            ins_tags.push(EntryTag::Encrypted("alg".to_string(), alg.to_string()));
-        }
-        let thumbs = key.to_jwk_thumbprints()?;
-        for thumb in thumbs {
-            ins_tags.push(EntryTag::Encrypted("thumb".to_string(), thumb));
-        }
-        if let Some(tags) = tags {
-            for t in tags {
-                ins_tags.push(t.map_ref(|k, v| (format!("user:{}", k), v.to_string())));
-            }
-        }
-        self.0
-            .update(
-                EntryKind::Kms,
-                EntryOperation::Insert,
-                KmsCategory::CryptoKey.as_str(),
-                name,
-                Some(value.as_ref()),
-                Some(ins_tags.as_slice()),
-                expiry_ms,
-            )
-            .await?;
-        Ok(())
+        let result = self
+            .backend
+            .rename(EntryKind::Item, category, old_name, new_name)
+            .await
+            .map_err(Error::from);
+        self.invalidate_entries(Some(category), Some(old_name));
+        self.audit(
+            "rename",
+            EntryKind::Item,
+            Some(category),
+            Some(old_name),
+            result,
+        )
+        .await
     }
 
-    /// Fetch an existing key from the store
+    /// Add and remove tags on an existing record without rewriting its value
     ///
-    /// Specify `for_update` when in a transaction to create an update lock on the
-    /// associated record, if supported by the store backend
-    pub async fn fetch_key(
+    /// Tags named in `remove_tag_names` are removed, and the tags in `add_tags`
+    /// replace any existing tags with the same name.
+    pub async fn update_tags(
         &mut self,
+        category: &str,
         name: &str,
-        for_update: bool,
-    ) -> Result<Option<KeyEntry>, Error> {
-        Ok(
-            if let Some(row) = self
-                .0
-                .fetch(
-                    EntryKind::Kms,
-                    KmsCategory::CryptoKey.as_str(),
-                    name,
-                    for_update,
-                )
-                .await?
-            {
-                Some(KeyEntry::from_entry(row)?)
-            } else {
-                None
-            },
+        add_tags: &[EntryTag],
+        remove_tag_names: &[&str],
+    ) -> Result<(), Error> {
+        let result = self
+            .backend
+            .update_tags(EntryKind::Item, category, name, add_tags, remove_tag_names)
+            .await
+            .map_err(Error::from);
+        self.invalidate_entries(Some(category), Some(name));
+        self.audit(
+            "update_tags",
+            EntryKind::Item,
+            Some(category),
+            Some(name),
+            result,
         )
+        .await
     }
 
-    /// Retrieve all keys matching the given filters.
-    pub async fn fetch_all_keys(
+    /// Insert a new record whose value is read from `reader` and stored as a
+    /// sequence of encrypted chunks, returning the length of the value
+    ///
+    /// The record value itself only describes the chunks, and must be read back
+    /// using `fetch_stream`. If an error occurs after the record is created, the
+    /// partially written record is removed; use a transaction to avoid exposing
+    /// the record to other sessions before it is complete.
+    pub async fn insert_stream<R: Read>(
         &mut self,
-        algorithm: Option<&str>,
-        thumbprint: Option<&str>,
-        tag_filter: Option<TagFilter>,
-        limit: Option<i64>,
-        for_update: bool,
-    ) -> Result<Vec<KeyEntry>, Error> {
-        let mut query_parts = Vec::with_capacity(3);
-        if let Some(query) = tag_filter.map(|f| f.into_query()) {
-            query_parts.push(TagFilter::from(
-                query
-                    .map_names(|mut k| {
-                        k.replace_range(0..0, "user:");
-                        Result::<_, ()>::Ok(k)
-                    })
-                    .unwrap(),
-            ));
-        }
-        if let Some(algorithm) = algorithm {
-            query_parts.push(TagFilter::is_eq("alg", algorithm));
-        }
-        if let Some(thumbprint) = thumbprint {
-            query_parts.push(TagFilter::is_eq("thumb", thumbprint));
-        }
-        let tag_filter = if query_parts.is_empty() {
-            None
-        } else {
-            Some(TagFilter::all_of(query_parts))
-        };
-        let rows = self
-            .0
-            .fetch_all(
-                Some(EntryKind::Kms),
-                Some(KmsCategory::CryptoKey.as_str()),
-                tag_filter,
-                limit,
-                for_update,
-            )
+        category: &str,
+        name: &str,
+        mut reader: R,
+        tags: Option<&[EntryTag]>,
+        expiry_ms: Option<i64>,
+    ) -> Result<u64, Error> {
+        let mut manifest = ChunkManifest::generate()?;
+        self.insert(category, name, &manifest.encode()?, tags, expiry_ms)
             .await?;
-        let mut entries = Vec::with_capacity(rows.len());
-        for row in rows {
-            entries.push(KeyEntry::from_entry(row)?)
+        let result = async {
+            let mut buf = vec![0u8; CHUNK_SIZE];
+            loop {
+                let len = read_chunk(&mut reader, &mut buf)?;
+                if len == 0 {
+                    break;
+                }
+                let chunk = manifest.encrypt_chunk(manifest.chunks, &buf[..len])?;
+                self.backend
+                    .write_chunk(EntryKind::Item, category, name, manifest.chunks, &chunk)
+                    .await?;
+                manifest.chunks += 1;
+                manifest.length += len as u64;
+                if len < CHUNK_SIZE {
+                    break;
+                }
+            }
+            self.replace(category, name, &manifest.encode()?, tags, expiry_ms)
+                .await
+        }
+        .await;
+        if let Err(err) = result {
+            self.remove(category, name).await.ok();
+            return Err(err);
         }
-        Ok(entries)
+        Ok(manifest.length)
     }
 
-    /// Remove an existing key from the store
-    pub async fn remove_key(&mut self, name: &str) -> Result<(), Error> {
-        Ok(self
-            .0
-            .update(
-                EntryKind::Kms,
-                EntryOperation::Remove,
-                KmsCategory::CryptoKey.as_str(),
-                name,
-                None,
-                None,
-                None,
-            )
-            .await?)
+    /// Write the value of a record created by `insert_stream` to `writer`,
+    /// returning the length of the value or `None` if the record does not exist
+    pub async fn fetch_stream<W: Write>(
+        &mut self,
+        category: &str,
+        name: &str,
+        mut writer: W,
+    ) -> Result<Option<u64>, Error> {
+        let entry = match self.fetch(category, name, false).await? {
+            Some(entry) => entry,
+            None => return Ok(None),
+        };
+        let manifest = ChunkManifest::decode(entry.value.as_ref())?;
+        let mut index = 0;
+        let mut length = 0;
+        while index < manifest.chunks {
+            let chunks = self
+                .backend
+                .fetch_chunks(
+                    EntryKind::Item,
+                    category,
+                    name,
+                    index,
+                    CHUNK_FETCH_LIMIT.min(manifest.chunks - index),
+                )
+                .await?;
+            if chunks.is_empty() {
+                return Err(err_msg!(Input, "Entry value is truncated"));
+            }
+            for chunk in chunks {
+                let data = manifest.decrypt_chunk(index, &chunk)?;
+                writer
+                    .write_all(data.as_ref())
+                    .map_err(err_map!(Input, "Error writing entry value"))?;
+                index += 1;
+                length += data.len() as u64;
+            }
+        }
+        if length != manifest.length {
+            return Err(err_msg!(Input, "Entry value length mismatch"));
+        }
+        writer
+            .flush()
+            .map_err(err_map!(Input, "Error writing entry value"))?;
+        Ok(Some(length))
     }
 
-    /// Replace the metadata and tags on an existing key in the store
-    pub async fn update_key(
+    /// Replace or remove a record in the store if its current state satisfies
+    /// `precondition`
+    #[allow(clippy::too_many_arguments)]
+    pub async fn update_if(
         &mut self,
+        operation: EntryOperation,
+        category: &str,
         name: &str,
-        metadata: Option<&str>,
+        value: Option<&[u8]>,
         tags: Option<&[EntryTag]>,
         expiry_ms: Option<i64>,
+        precondition: &EntryPrecondition,
     ) -> Result<(), Error> {
-        let row = self
-            .0
-            .fetch(EntryKind::Kms, KmsCategory::CryptoKey.as_str(), name, true)
-            .await?
-            .ok_or_else(|| err_msg!(NotFound, "Key entry not found"))?;
-
-        let mut params = KeyParams::from_slice(&row.value)?;
-        params.metadata = metadata.map(str::to_string);
-        let value = params.to_bytes()?;
-
-        let mut upd_tags = Vec::with_capacity(10);
-        if let Some(tags) = tags {
-            for t in tags {
-                upd_tags.push(t.map_ref(|k, v| (format!("user:{}", k), v.to_string())));
-            }
-        }
-        for t in row.tags {
-            if !t.name().starts_with("user:") {
-                upd_tags.push(t);
-            }
-        }
-
-        self.0
-            .update(
-                EntryKind::Kms,
-                EntryOperation::Replace,
-                KmsCategory::CryptoKey.as_str(),
+        let result = self
+            .backend
+            .update_if(
+                EntryKind::Item,
+                operation,
+                category,
                 name,
-                Some(value.as_ref()),
-                Some(upd_tags.as_slice()),
+                value,
+                tags,
                 expiry_ms,
+                precondition,
             )
-            .await?;
-
-        Ok(())
+            .await
+            .map_err(Error::from);
+        self.invalidate_entries(Some(category), Some(name));
+        self.audit(
+            operation_name(operation),
+            EntryKind::Item,
+            Some(category),
+            Some(name),
+            result,
+        )
+        .await
     }
 
-    /// Test the connection to the store
-    pub async fn ping(&mut self) -> Result<(), Error> {
-        Ok(self.0.ping().await?)
+    /// Insert a new typed record, named and tagged according to its type
+    pub async fn insert_typed<R: TypedRecord>(
+        &mut self,
+        record: &R,
+        expiry_ms: Option<i64>,
+    ) -> Result<(), Error> {
+        let value = encode_record(record)?;
+        self.insert(
+            R::CATEGORY,
+            record.name(),
+            &value,
+            Some(&record.tags()),
+            expiry_ms,
+        )
+        .await
     }
 
-    /// Commit the pending transaction
-    pub async fn commit(mut self) -> Result<(), Error> {
-        Ok(self.0.close(true).await?)
+    /// Replace an existing typed record, updating its tags
+    pub async fn replace_typed<R: TypedRecord>(
+        &mut self,
+        record: &R,
+        expiry_ms: Option<i64>,
+    ) -> Result<(), Error> {
+        let value = encode_record(record)?;
+        self.replace(
+            R::CATEGORY,
+            record.name(),
+            &value,
+            Some(&record.tags()),
+            expiry_ms,
+        )
+        .await
     }
 
-    /// Roll back the pending transaction
-    pub async fn rollback(mut self) -> Result<(), Error> {
-        Ok(self.0.close(false).await?)
+    /// Fetch a typed record by name
+    pub async fn fetch_typed<R: TypedRecord>(
+        &mut self,
+        name: &str,
+        for_update: bool,
+    ) -> Result<Option<R>, Error> {
+        self.fetch(R::CATEGORY, name, for_update)
+            .await?
+            .map(|entry| decode_record(&entry))
+            .transpose()
     }
+
+    /// Fetch all typed records matching a tag filter
+    pub async fn fetch_all_typed<R: TypedRecord>(
+        &mut self,
+        tag_filter: Option<TagFilter>,
+        limit: Option<i64>,
+        for_update: bool,
+    ) -> Result<Vec<R>, Error> {
+        self.fetch_all(Some(R::CATEGORY), tag_filter, limit, for_update)
+            .await?
+            .iter()
+            .map(decode_record)
+            .collect()
+    }
+
+    /// Remove a typed record by name
+    pub async fn remove_typed<R: TypedRecord>(&mut self, name: &str) -> Result<(), Error> {
+        self.remove(R::CATEGORY, name).await
+    }
+
+    /// Add a message to the queue of a recipient, returning the message identifier
+    pub async fn enqueue_message(
+        &mut self,
+        recipient: &str,
+        message: &[u8],
+        expiry_ms: Option<i64>,
+    ) -> Result<String, Error> {
+        let (id, created) = generate_message_id();
+        let name = message_name(recipient, &id);
+        let result = self
+            .backend
+            .update(
+                EntryKind::Item,
+                EntryOperation::Insert,
+                MESSAGE_QUEUE_CATEGORY,
+                &name,
+                Some(message),
+                Some(&message_tags(recipient, created)),
+                expiry_ms,
+            )
+            .await
+            .map(|_| id)
+            .map_err(Error::from);
+        self.audit(
+            "enqueue_message",
+            EntryKind::Item,
+            Some(MESSAGE_QUEUE_CATEGORY),
+            Some(&name),
+            result,
+        )
+        .await
+    }
+
+    /// Retrieve the messages queued for a recipient without removing them,
+    /// in the order they were queued
+    pub async fn peek_messages(
+        &mut self,
+        recipient: &str,
+        limit: Option<usize>,
+    ) -> Result<Vec<QueuedMessage>, Error> {
+        let result = self.fetch_queued(recipient, limit, false).await;
+        self.audit(
+            "peek_messages",
+            EntryKind::Item,
+            Some(MESSAGE_QUEUE_CATEGORY),
+            None,
+            result,
+        )
+        .await
+    }
+
+    /// Remove and return up to `limit` messages queued for a recipient, in
+    /// the order they were queued
+    ///
+    /// Each message is returned only by the operation which removed it, so
+    /// concurrent sessions dequeuing messages for the same recipient never
+    /// receive the same message.
+    pub async fn dequeue_messages(
+        &mut self,
+        recipient: &str,
+        limit: Option<usize>,
+    ) -> Result<Vec<QueuedMessage>, Error> {
+        let result = async {
+            let messages = self.fetch_queued(recipient, limit, true).await?;
+            let mut claimed = Vec::with_capacity(messages.len());
+            for message in messages {
+                if self.remove_queued(recipient, &message.id).await? {
+                    claimed.push(message);
+                }
+            }
+            Ok(claimed)
+        }
+        .await;
+        self.audit(
+            "dequeue_messages",
+            EntryKind::Item,
+            Some(MESSAGE_QUEUE_CATEGORY),
+            None,
+            result,
+        )
+        .await
+    }
+
+    /// Remove acknowledged messages from the queue of a recipient, returning
+    /// the number of messages removed. Unknown message identifiers are ignored.
+    pub async fn ack_messages(&mut self, recipient: &str, ids: &[&str]) -> Result<usize, Error> {
+        let result = async {
+            let mut removed = 0;
+            for id in ids {
+                if self.remove_queued(recipient, id).await? {
+                    removed += 1;
+                }
+            }
+            Ok(removed)
+        }
+        .await;
+        self.audit(
+            "ack_messages",
+            EntryKind::Item,
+            Some(MESSAGE_QUEUE_CATEGORY),
+            None,
+            result,
+        )
+        .await
+    }
+
+    /// Count the messages queued for a recipient
+    pub async fn count_messages(&mut self, recipient: &str) -> Result<i64, Error> {
+        Ok(self
+            .backend
+            .count(
+                Some(EntryKind::Item),
+                Some(MESSAGE_QUEUE_CATEGORY),
+                Some(recipient_filter(recipient)),
+            )
+            .await?)
+    }
+
+    async fn fetch_queued(
+        &mut self,
+        recipient: &str,
+        limit: Option<usize>,
+        for_update: bool,
+    ) -> Result<Vec<QueuedMessage>, Error> {
+        // the messages are returned in the order they were inserted, so that
+        // only the oldest messages are loaded and locked
+        let limit = limit.map(|limit| i64::try_from(limit).unwrap_or(i64::MAX));
+        self.backend
+            .fetch_all(
+                Some(EntryKind::Item),
+                Some(MESSAGE_QUEUE_CATEGORY),
+                Some(recipient_filter(recipient)),
+                limit,
+                for_update,
+            )
+            .await?
+            .into_iter()
+            .map(QueuedMessage::from_entry)
+            .collect()
+    }
+
+    /// Remove a queued message, returning `false` if it was already removed
+    async fn remove_queued(&mut self, recipient: &str, id: &str) -> Result<bool, Error> {
+        let name = message_name(recipient, id);
+        let result = self
+            .backend
+            .update(
+                EntryKind::Item,
+                EntryOperation::Remove,
+                MESSAGE_QUEUE_CATEGORY,
+                &name,
+                None,
+                None,
+                None,
+            )
+            .await;
+        self.invalidate_entries(Some(MESSAGE_QUEUE_CATEGORY), Some(&name));
+        match result {
+            Ok(()) => Ok(true),
+            Err(err) if err.kind() == StorageErrorKind::NotFound => Ok(false),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Insert a local key instance into the store
+    ///
+    /// The usage policy of the key, if any, is stored along with it. For a key
+    /// held by an external key provider, only the reference to the key and
+    /// its public key are stored.
+    pub async fn insert_key(
+        &mut self,
+        name: &str,
+        key: &LocalKey,
+        metadata: Option<&str>,
+        tags: Option<&[EntryTag]>,
+        expiry_ms: Option<i64>,
+    ) -> Result<(), Error> {
+        let result = async {
+            let params = local_key_params(key, metadata)?;
+            self.insert_key_params(name, key.algorithm(), Some(key), params, tags, expiry_ms)
+                .await
+        }
+        .await;
+        self.audit(
+            "insert_key",
+            EntryKind::Kms,
+            Some(KmsCategory::CryptoKey.as_str()),
+            Some(name),
+            result,
+        )
+        .await
+    }
+
+    /// Insert a new key into the store, named by its JWK thumbprint
+    ///
+    /// The key entry is named using the RFC 7638 thumbprint of the key, which
+    /// is returned. An existing key entry with the same thumbprint, under any
+    /// name, results in a duplicate error.
+    pub async fn insert_key_by_thumbprint(
+        &mut self,
+        key: &LocalKey,
+        metadata: Option<&str>,
+        tags: Option<&[EntryTag]>,
+        expiry_ms: Option<i64>,
+    ) -> Result<String, Error> {
+        let name = key.to_jwk_thumbprint(None)?;
+        let result = async {
+            if self.find_key_by_thumbprint(&name, true).await?.is_some() {
+                return Err(err_msg!(
+                    Duplicate,
+                    "A key with the same thumbprint already exists"
+                ));
+            }
+            let params = local_key_params(key, metadata)?;
+            self.insert_key_params(&name, key.algorithm(), Some(key), params, tags, expiry_ms)
+                .await
+        }
+        .await;
+        self.audit(
+            "insert_key",
+            EntryKind::Kms,
+            Some(KmsCategory::CryptoKey.as_str()),
+            Some(&name),
+            result,
+        )
+        .await?;
+        Ok(name)
+    }
+
+    /// Insert a reference to a key held by a key backend into the store
+    ///
+    /// Operations on the key are performed by the key backend registered for
+    /// the scheme of the reference, using the key operation methods of the
+    /// session. Only the reference and the public key, if any, are stored.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn insert_key_reference(
+        &mut self,
+        name: &str,
+        reference: &str,
+        alg: KeyAlg,
+        public_key: Option<&LocalKey>,
+        metadata: Option<&str>,
+        tags: Option<&[EntryTag]>,
+        expiry_ms: Option<i64>,
+    ) -> Result<(), Error> {
+        let result = async {
+            if !reference.contains(':') {
+                return Err(err_msg!("Invalid key reference"));
+            }
+            if public_key.map_or(false, |key| key.algorithm() != alg) {
+                return Err(err_msg!("Public key algorithm mismatch"));
+            }
+            let params = KeyParams {
+                metadata: metadata.map(str::to_string),
+                json: None,
+                reference: Some(reference.to_string()),
+                data: public_key
+                    .map(|key| key.to_jwk_public(None).map(SecretBytes::from))
+                    .transpose()?,
+                policy: None,
+            };
+            self.insert_key_params(name, alg, public_key, params, tags, expiry_ms)
+                .await
+        }
+        .await;
+        self.audit(
+            "insert_key",
+            EntryKind::Kms,
+            Some(KmsCategory::CryptoKey.as_str()),
+            Some(name),
+            result,
+        )
+        .await
+    }
+
+    async fn insert_key_params(
+        &mut self,
+        name: &str,
+        alg: KeyAlg,
+        key: Option<&LocalKey>,
+        params: KeyParams,
+        tags: Option<&[EntryTag]>,
+        expiry_ms: Option<i64>,
+    ) -> Result<(), Error> {
+        let value = params.to_bytes()?;
+        let mut ins_tags = Vec::with_capacity(10);
+        let alg = alg.as_str();
+        if !alg.is_empty() {
+            ins_tags.push(EntryTag::Encrypted("alg".to_string(), alg.to_string()));
+        }
+        if let Some(key) = key {
+            for thumb in key.to_jwk_thumbprints()? {
+                ins_tags.push(EntryTag::Encrypted("thumb".to_string(), thumb));
+            }
+        }
+        let created = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        ins_tags.push(EntryTag::Numeric(
+            KEY_CREATED_TAG.to_string(),
+            created.to_string(),
+        ));
+        if let Some(expires_at) = params.policy.as_ref().and_then(|p| p.expires_at) {
+            ins_tags.push(EntryTag::Numeric(
+                KEY_EXPIRES_TAG.to_string(),
+                expires_at.to_string(),
+            ));
+        }
+        if let Some(tags) = tags {
+            for t in tags {
+                ins_tags.push(t.map_ref(|k, v| (format!("user:{}", k), v.to_string())));
+            }
+        }
+        self.backend
+            .update(
+                EntryKind::Kms,
+                EntryOperation::Insert,
+                KmsCategory::CryptoKey.as_str(),
+                name,
+                Some(value.as_ref()),
+                Some(ins_tags.as_slice()),
+                expiry_ms,
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Fetch an existing key from the store
+    ///
+    /// Specify `for_update` when in a transaction to create an update lock on the
+    /// associated record, if supported by the store backend
+    pub async fn fetch_key(
+        &mut self,
+        name: &str,
+        for_update: bool,
+    ) -> Result<Option<KeyEntry>, Error> {
+        let result = async {
+            Ok(
+                if let Some(row) = self
+                    .backend
+                    .fetch(
+                        EntryKind::Kms,
+                        KmsCategory::CryptoKey.as_str(),
+                        name,
+                        for_update,
+                    )
+                    .await?
+                {
+                    Some(KeyEntry::from_entry(row)?)
+                } else {
+                    None
+                },
+            )
+        }
+        .await;
+        self.audit(
+            "fetch_key",
+            EntryKind::Kms,
+            Some(KmsCategory::CryptoKey.as_str()),
+            Some(name),
+            result,
+        )
+        .await
+    }
+
+    /// Fetch an existing key from the store by its JWK thumbprint
+    ///
+    /// Specify `for_update` when in a transaction to create an update lock on the
+    /// associated record, if supported by the store backend
+    pub async fn fetch_key_by_thumbprint(
+        &mut self,
+        thumbprint: &str,
+        for_update: bool,
+    ) -> Result<Option<KeyEntry>, Error> {
+        let result = self.find_key_by_thumbprint(thumbprint, for_update).await;
+        let name = result
+            .as_ref()
+            .ok()
+            .and_then(|entry| entry.as_ref().map(|e| e.name().to_string()));
+        self.audit(
+            "fetch_key",
+            EntryKind::Kms,
+            Some(KmsCategory::CryptoKey.as_str()),
+            name.as_deref(),
+            result,
+        )
+        .await
+    }
+
+    async fn find_key_by_thumbprint(
+        &mut self,
+        thumbprint: &str,
+        for_update: bool,
+    ) -> Result<Option<KeyEntry>, Error> {
+        let rows = self
+            .backend
+            .fetch_all(
+                Some(EntryKind::Kms),
+                Some(KmsCategory::CryptoKey.as_str()),
+                key_tag_filter(None, Some(thumbprint), None, None, None),
+                Some(1),
+                for_update,
+            )
+            .await?;
+        rows.into_iter()
+            .next()
+            .map(KeyEntry::from_entry)
+            .transpose()
+    }
+
+    /// Fetch an existing key from the store and load it for use
+    ///
+    /// When the key cache of the store is enabled using `Store::set_key_cache`,
+    /// the loaded key is retained and shared with the other sessions of the
+    /// store until it expires, avoiding the fetch and decryption of the key
+    /// entry. Keys returned from the cache are not recorded in the audit log.
+    pub async fn load_key(&mut self, name: &str) -> Result<Option<Arc<LocalKey>>, Error> {
+        if let Some(key) = self.key_cache.get(&self.profile, name) {
+            return Ok(Some(key));
+        }
+        let Some(entry) = self.fetch_key(name, false).await? else {
+            return Ok(None);
+        };
+        let key = Arc::new(entry.load_local_key()?);
+        self.key_cache.insert(&self.profile, name, key.clone());
+        Ok(Some(key))
+    }
+
+    /// Remove all keys retained by this session
+    ///
+    /// Keys loaded by `sign_message`, `key_exchange`, `wrap_key` and
+    /// `unwrap_key` are retained until the session is closed or the key entry
+    /// is modified using this session. Clearing the keys ensures that changes
+    /// made by other sessions are visible to a long-lived session.
+    pub fn clear_loaded_keys(&mut self) {
+        self.keys.clear();
+    }
+
+    /// Remove a modified key from the key caches of the session and store
+    fn forget_key(&mut self, name: &str) {
+        self.key_cache.remove(&self.profile, name);
+        self.keys.remove(name);
+    }
+
+    /// Retrieve all keys matching the given filters.
+    ///
+    /// Keys may be restricted to those created at or after `created_after` and
+    /// before `created_before`, in seconds since the Unix epoch, and to those
+    /// whose metadata contains the substring `metadata`. As the metadata is
+    /// stored within the encrypted key parameters, this last filter is applied
+    /// after the matching keys are loaded.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn fetch_all_keys(
+        &mut self,
+        algorithm: Option<&str>,
+        thumbprint: Option<&str>,
+        tag_filter: Option<TagFilter>,
+        created_after: Option<i64>,
+        created_before: Option<i64>,
+        metadata: Option<&str>,
+        limit: Option<i64>,
+        for_update: bool,
+    ) -> Result<Vec<KeyEntry>, Error> {
+        let result = async {
+            let tag_filter = key_tag_filter(
+                algorithm,
+                thumbprint,
+                tag_filter,
+                created_after,
+                created_before,
+            );
+            let rows = self
+                .backend
+                .fetch_all(
+                    Some(EntryKind::Kms),
+                    Some(KmsCategory::CryptoKey.as_str()),
+                    tag_filter,
+                    if metadata.is_some() { None } else { limit },
+                    for_update,
+                )
+                .await?;
+            let mut entries = Vec::with_capacity(rows.len());
+            for row in rows {
+                let entry = KeyEntry::from_entry(row)?;
+                if let Some(metadata) = metadata {
+                    if !entry.metadata().map_or(false, |m| m.contains(metadata)) {
+                        continue;
+                    }
+                    if limit.map_or(false, |limit| entries.len() as i64 >= limit) {
+                        break;
+                    }
+                }
+                entries.push(entry);
+            }
+            Ok(entries)
+        }
+        .await;
+        self.audit(
+            "fetch_all_keys",
+            EntryKind::Kms,
+            Some(KmsCategory::CryptoKey.as_str()),
+            None,
+            result,
+        )
+        .await
+    }
+
+    /// Retrieve the keys whose usage policy expires before `expires_before`, in
+    /// seconds since the Unix epoch, including keys which have already expired
+    pub async fn fetch_expiring_keys(
+        &mut self,
+        expires_before: i64,
+        limit: Option<i64>,
+    ) -> Result<Vec<KeyEntry>, Error> {
+        let result = async {
+            let tag_filter = TagFilter::is_lt(
+                TagFilter::numeric_tag(KEY_EXPIRES_TAG),
+                expires_before.to_string(),
+            );
+            let rows = self
+                .backend
+                .fetch_all(
+                    Some(EntryKind::Kms),
+                    Some(KmsCategory::CryptoKey.as_str()),
+                    Some(tag_filter),
+                    limit,
+                    false,
+                )
+                .await?;
+            let mut entries = Vec::with_capacity(rows.len());
+            for row in rows {
+                entries.push(KeyEntry::from_entry(row)?);
+            }
+            Ok(entries)
+        }
+        .await;
+        self.audit(
+            "fetch_expiring_keys",
+            EntryKind::Kms,
+            Some(KmsCategory::CryptoKey.as_str()),
+            None,
+            result,
+        )
+        .await
+    }
+
+    /// Remove an existing key from the store
+    pub async fn remove_key(&mut self, name: &str) -> Result<(), Error> {
+        self.forget_key(name);
+        let result = self
+            .backend
+            .update(
+                EntryKind::Kms,
+                EntryOperation::Remove,
+                KmsCategory::CryptoKey.as_str(),
+                name,
+                None,
+                None,
+                None,
+            )
+            .await
+            .map_err(Error::from);
+        self.audit(
+            "remove_key",
+            EntryKind::Kms,
+            Some(KmsCategory::CryptoKey.as_str()),
+            Some(name),
+            result,
+        )
+        .await
+    }
+
+    /// Restore a key which was removed while soft deletion is enabled
+    pub async fn restore_key(&mut self, name: &str) -> Result<(), Error> {
+        let result = self
+            .backend
+            .restore(EntryKind::Kms, KmsCategory::CryptoKey.as_str(), name)
+            .await
+            .map_err(Error::from);
+        self.audit(
+            "restore_key",
+            EntryKind::Kms,
+            Some(KmsCategory::CryptoKey.as_str()),
+            Some(name),
+            result,
+        )
+        .await
+    }
+
+    /// Replace the metadata and tags on an existing key in the store
+    pub async fn update_key(
+        &mut self,
+        name: &str,
+        metadata: Option<&str>,
+        tags: Option<&[EntryTag]>,
+        expiry_ms: Option<i64>,
+    ) -> Result<(), Error> {
+        self.forget_key(name);
+        let result = async {
+            let row = self
+                .backend
+                .fetch(EntryKind::Kms, KmsCategory::CryptoKey.as_str(), name, true)
+                .await?
+                .ok_or_else(|| err_msg!(NotFound, "Key entry not found"))?;
+
+            let mut params = KeyParams::from_slice(&row.value)?;
+            params.metadata = metadata.map(str::to_string);
+            let value = params.to_bytes()?;
+
+            let mut upd_tags = Vec::with_capacity(10);
+            if let Some(tags) = tags {
+                for t in tags {
+                    upd_tags.push(t.map_ref(|k, v| (format!("user:{}", k), v.to_string())));
+                }
+            }
+            for t in row.tags {
+                if !t.name().starts_with("user:") {
+                    upd_tags.push(t);
+                }
+            }
+
+            self.backend
+                .update(
+                    EntryKind::Kms,
+                    EntryOperation::Replace,
+                    KmsCategory::CryptoKey.as_str(),
+                    name,
+                    Some(value.as_ref()),
+                    Some(upd_tags.as_slice()),
+                    expiry_ms,
+                )
+                .await?;
+
+            Ok(())
+        }
+        .await;
+        self.audit(
+            "update_key",
+            EntryKind::Kms,
+            Some(KmsCategory::CryptoKey.as_str()),
+            Some(name),
+            result,
+        )
+        .await
+    }
+
+    /// Replace the structured JSON metadata of an existing key in the store,
+    /// or remove it
+    ///
+    /// The metadata must be a JSON object. The values of the top-level fields
+    /// listed in `indexed` are recorded as encrypted tags, such that keys may
+    /// be selected by these fields using a tag filter on `meta:<field>`, for
+    /// example `{"meta:purpose": "auth"}`. Indexed fields must contain a
+    /// string, number or boolean value, or an array of such values.
+    pub async fn set_key_json_metadata(
+        &mut self,
+        name: &str,
+        metadata: Option<&serde_json::Value>,
+        indexed: &[&str],
+    ) -> Result<(), Error> {
+        self.forget_key(name);
+        let result = async {
+            let row = self
+                .backend
+                .fetch(EntryKind::Kms, KmsCategory::CryptoKey.as_str(), name, true)
+                .await?
+                .ok_or_else(|| err_msg!(NotFound, "Key entry not found"))?;
+
+            let mut upd_tags = match metadata {
+                Some(metadata) => key_metadata_tags(metadata, indexed)?,
+                None => Vec::new(),
+            };
+            let mut params = KeyParams::from_slice(&row.value)?;
+            params.json = metadata.cloned();
+            let value = params.to_bytes()?;
+
+            for t in row.tags {
+                if !t.name().starts_with(KEY_METADATA_TAG_PREFIX) {
+                    upd_tags.push(t);
+                }
+            }
+
+            self.backend
+                .update(
+                    EntryKind::Kms,
+                    EntryOperation::Replace,
+                    KmsCategory::CryptoKey.as_str(),
+                    name,
+                    Some(value.as_ref()),
+                    Some(upd_tags.as_slice()),
+                    None,
+                )
+                .await?;
+
+            Ok(())
+        }
+        .await;
+        self.audit(
+            "set_key_json_metadata",
+            EntryKind::Kms,
+            Some(KmsCategory::CryptoKey.as_str()),
+            Some(name),
+            result,
+        )
+        .await
+    }
+
+    /// Attach a usage policy to an existing key in the store, or remove the
+    /// current policy
+    ///
+    /// The policy is enforced on all operations performed with the key once
+    /// it is loaded from the store, and is carried over when the key is
+    /// rotated. The expiry time of the policy is indexed for use by
+    /// `fetch_expiring_keys`.
+    pub async fn set_key_policy(
+        &mut self,
+        name: &str,
+        policy: Option<&KeyPolicy>,
+    ) -> Result<(), Error> {
+        self.forget_key(name);
+        let result = async {
+            let row = self
+                .backend
+                .fetch(EntryKind::Kms, KmsCategory::CryptoKey.as_str(), name, true)
+                .await?
+                .ok_or_else(|| err_msg!(NotFound, "Key entry not found"))?;
+
+            let mut params = KeyParams::from_slice(&row.value)?;
+            params.policy = policy.cloned();
+            let value = params.to_bytes()?;
+
+            let mut upd_tags = Vec::with_capacity(row.tags.len() + 1);
+            for t in row.tags {
+                if t.name() != KEY_EXPIRES_TAG {
+                    upd_tags.push(t);
+                }
+            }
+            if let Some(expires_at) = policy.and_then(|p| p.expires_at) {
+                upd_tags.push(EntryTag::Numeric(
+                    KEY_EXPIRES_TAG.to_string(),
+                    expires_at.to_string(),
+                ));
+            }
+
+            self.backend
+                .update(
+                    EntryKind::Kms,
+                    EntryOperation::Replace,
+                    KmsCategory::CryptoKey.as_str(),
+                    name,
+                    Some(value.as_ref()),
+                    Some(upd_tags.as_slice()),
+                    None,
+                )
+                .await?;
+
+            Ok(())
+        }
+        .await;
+        self.audit(
+            "set_key_policy",
+            EntryKind::Kms,
+            Some(KmsCategory::CryptoKey.as_str()),
+            Some(name),
+            result,
+        )
+        .await
+    }
+
+    /// Sign a message using a stored key
+    ///
+    /// For a key entry whose reference is handled by a registered key backend,
+    /// the signature is produced by the backend. Otherwise the key is loaded
+    /// and used locally, and retained by the session for subsequent
+    /// operations.
+    pub async fn sign_message(
+        &mut self,
+        name: &str,
+        message: &[u8],
+        sig_type: Option<&str>,
+    ) -> Result<Vec<u8>, Error> {
+        let result = async {
+            match self.operation_key(name).await? {
+                OperationKey::Local(key) => key.sign_message(message, sig_type),
+                OperationKey::Backend {
+                    entry,
+                    backend,
+                    reference,
+                } => {
+                    entry.check_policy(KeyOperation::Sign)?;
+                    backend.sign(&reference, message, sig_type).await
+                }
+            }
+        }
+        .await;
+        self.audit(
+            "sign_message",
+            EntryKind::Kms,
+            Some(KmsCategory::CryptoKey.as_str()),
+            Some(name),
+            result,
+        )
+        .await
+    }
+
+    /// Derive a key of the algorithm `alg` by key agreement between a stored
+    /// key and a public key
+    ///
+    /// For a key entry whose reference is handled by a registered key backend,
+    /// the key agreement is performed by the backend.
+    pub async fn key_exchange(
+        &mut self,
+        name: &str,
+        alg: KeyAlg,
+        public: &LocalKey,
+    ) -> Result<LocalKey, Error> {
+        let result = async {
+            match self.operation_key(name).await? {
+                OperationKey::Local(key) => key.to_key_exchange(alg, public),
+                OperationKey::Backend {
+                    entry,
+                    backend,
+                    reference,
+                } => {
+                    entry.check_policy(KeyOperation::Derive)?;
+                    entry.check_algorithm(alg)?;
+                    let secret = backend.key_exchange(&reference, public).await?;
+                    LocalKey::from_secret_bytes(alg, secret.as_ref())
+                }
+            }
+        }
+        .await;
+        self.audit(
+            "key_exchange",
+            EntryKind::Kms,
+            Some(KmsCategory::CryptoKey.as_str()),
+            Some(name),
+            result,
+        )
+        .await
+    }
+
+    /// Wrap (encrypt) a key using a stored key
+    ///
+    /// For a key entry whose reference is handled by a registered key backend,
+    /// the key is wrapped by the backend.
+    pub async fn wrap_key(
+        &mut self,
+        name: &str,
+        key: &LocalKey,
+        nonce: &[u8],
+    ) -> Result<Encrypted, Error> {
+        let result = async {
+            match self.operation_key(name).await? {
+                OperationKey::Local(wrap_key) => wrap_key.wrap_key(key, nonce),
+                OperationKey::Backend {
+                    entry,
+                    backend,
+                    reference,
+                } => {
+                    entry.check_policy(KeyOperation::Wrap)?;
+                    entry.check_algorithm(key.algorithm())?;
+                    backend.wrap_key(&reference, key, nonce).await
+                }
+            }
+        }
+        .await;
+        self.audit(
+            "wrap_key",
+            EntryKind::Kms,
+            Some(KmsCategory::CryptoKey.as_str()),
+            Some(name),
+            result,
+        )
+        .await
+    }
+
+    /// Unwrap (decrypt) a key of the algorithm `alg` using a stored key
+    ///
+    /// For a key entry whose reference is handled by a registered key backend,
+    /// the key is unwrapped by the backend.
+    pub async fn unwrap_key(
+        &mut self,
+        name: &str,
+        alg: KeyAlg,
+        ciphertext: &[u8],
+        nonce: &[u8],
+    ) -> Result<LocalKey, Error> {
+        let result = async {
+            match self.operation_key(name).await? {
+                OperationKey::Local(key) => key.unwrap_key(alg, ciphertext, nonce),
+                OperationKey::Backend {
+                    entry,
+                    backend,
+                    reference,
+                } => {
+                    entry.check_policy(KeyOperation::Wrap)?;
+                    entry.check_algorithm(alg)?;
+                    backend.unwrap_key(&reference, alg, ciphertext, nonce).await
+                }
+            }
+        }
+        .await;
+        self.audit(
+            "unwrap_key",
+            EntryKind::Kms,
+            Some(KmsCategory::CryptoKey.as_str()),
+            Some(name),
+            result,
+        )
+        .await
+    }
+
+    /// Load a stored key for use in a cryptographic operation, retaining
+    /// local keys within the session
+    async fn operation_key(&mut self, name: &str) -> Result<OperationKey, Error> {
+        if let Some(key) = self.keys.get(name) {
+            return Ok(OperationKey::Local(key.clone()));
+        }
+        let entry = self.fetch_key_entry(name).await?;
+        if let Some((backend, reference)) = entry.key_backend() {
+            let reference = reference.to_string();
+            return Ok(OperationKey::Backend {
+                entry: Box::new(entry),
+                backend,
+                reference,
+            });
+        }
+        let key = Arc::new(entry.load_local_key()?);
+        self.keys.insert(name.to_string(), key.clone());
+        Ok(OperationKey::Local(key))
+    }
+
+    async fn fetch_key_entry(&mut self, name: &str) -> Result<KeyEntry, Error> {
+        let row = self
+            .backend
+            .fetch(EntryKind::Kms, KmsCategory::CryptoKey.as_str(), name, false)
+            .await?
+            .ok_or_else(|| err_msg!(NotFound, "Key entry not found"))?;
+        KeyEntry::from_entry(row)
+    }
+
+    /// Produce a signed attestation statement describing a stored key
+    ///
+    /// The statement records the algorithm, public key, creation time,
+    /// version, storage and usage policy of the key entry `name`, along with
+    /// an optional challenge `nonce` provided by the relying party. It is
+    /// signed by the stored key `attestation_key`, which must be a signing
+    /// key, and identifies the attestation key by its JWK thumbprint.
+    pub async fn attest_key(
+        &mut self,
+        name: &str,
+        attestation_key: &str,
+        format: AttestationFormat,
+        nonce: Option<&str>,
+    ) -> Result<Vec<u8>, Error> {
+        let result = async {
+            let entry = self.fetch_key_entry(name).await?;
+            let jwk = match entry
+                .load_public_key()
+                .and_then(|key| key.to_jwk_public(None))
+            {
+                Ok(jwk) => Some(
+                    serde_json::from_str(&jwk)
+                        .map_err(err_map!(Unexpected, "Error parsing public JWK"))?,
+                ),
+                // symmetric keys have no public representation
+                Err(err) if err.kind() == ErrorKind::Unsupported => None,
+                Err(err) => return Err(err),
+            };
+            let iat = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default();
+            let claims = KeyAttestation {
+                name: entry.name().to_string(),
+                alg: entry.algorithm().map(str::to_string),
+                jwk,
+                created: entry.created(),
+                version: entry.version(),
+                storage: entry.storage(),
+                policy: entry.policy().cloned(),
+                iat: iat as i64,
+                nonce: nonce.map(str::to_string),
+            };
+            let signer = self
+                .fetch_key_entry(attestation_key)
+                .await?
+                .load_public_key()?;
+            let builder = AttestationBuilder::new(
+                format,
+                &claims,
+                signer.algorithm(),
+                &signer.to_jwk_thumbprint(None)?,
+            )?;
+            let signature = self
+                .sign_message(attestation_key, &builder.signing_input()?, None)
+                .await?;
+            builder.finish(&signature)
+        }
+        .await;
+        self.audit(
+            "attest_key",
+            EntryKind::Kms,
+            Some(KmsCategory::CryptoKey.as_str()),
+            Some(name),
+            result,
+        )
+        .await
+    }
+
+    /// Rotate an existing local key, replacing it with a newly generated key
+    /// of the same algorithm and returning the new key entry
+    ///
+    /// The replaced key is kept as a superseded version which may still be
+    /// loaded using `fetch_key_version` in order to verify or decrypt existing
+    /// data. The metadata and user tags of the key are carried over to the new
+    /// version, along with its usage policy excluding any validity window.
+    /// Perform the rotation within a transaction to ensure that the
+    /// superseded version is recorded atomically with the new key.
+    pub async fn rotate_key(&mut self, name: &str) -> Result<KeyEntry, Error> {
+        self.forget_key(name);
+        let result = async {
+            let row = self
+                .backend
+                .fetch(EntryKind::Kms, KmsCategory::CryptoKey.as_str(), name, true)
+                .await?
+                .ok_or_else(|| err_msg!(NotFound, "Key entry not found"))?;
+            let entry = KeyEntry::from_entry(row.clone())?;
+            if !entry.is_local() {
+                return Err(err_msg!(Unsupported, "Cannot rotate an external key"));
+            }
+            let key = LocalKey::generate(entry.load_local_key()?.algorithm(), false)?;
+            let rotated = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default();
+
+            let mut prev_tags = Vec::with_capacity(row.tags.len() + 2);
+            for t in row.tags.iter() {
+                if t.name() != KEY_VERSION_TAG {
+                    prev_tags.push(t.clone());
+                }
+            }
+            prev_tags.push(EntryTag::Numeric(
+                KEY_VERSION_TAG.to_string(),
+                entry.version.to_string(),
+            ));
+            prev_tags.push(EntryTag::Numeric(
+                KEY_ROTATED_TAG.to_string(),
+                rotated.to_string(),
+            ));
+            self.backend
+                .update(
+                    EntryKind::Kms,
+                    EntryOperation::Insert,
+                    KmsCategory::CryptoKeyVersion.as_str(),
+                    &key_version_name(name, entry.version),
+                    Some(row.value.as_ref()),
+                    Some(prev_tags.as_slice()),
+                    None,
+                )
+                .await?;
+
+            let params = KeyParams {
+                metadata: entry.params.metadata.clone(),
+                json: entry.params.json.clone(),
+                reference: None,
+                data: Some(key.encode()?),
+                // the validity window applies only to the superseded version
+                policy: entry.params.policy.clone().map(|policy| KeyPolicy {
+                    not_before: None,
+                    expires_at: None,
+                    ..policy
+                }),
+            };
+            let value = params.to_bytes()?;
+            let mut upd_tags = Vec::with_capacity(row.tags.len() + 2);
+            let alg = key.algorithm().as_str();
+            if !alg.is_empty() {
+                upd_tags.push(EntryTag::Encrypted("alg".to_string(), alg.to_string()));
+            }
+            for thumb in key.to_jwk_thumbprints()? {
+                upd_tags.push(EntryTag::Encrypted("thumb".to_string(), thumb));
+            }
+            upd_tags.push(EntryTag::Numeric(
+                KEY_CREATED_TAG.to_string(),
+                rotated.to_string(),
+            ));
+            upd_tags.push(EntryTag::Numeric(
+                KEY_VERSION_TAG.to_string(),
+                (entry.version + 1).to_string(),
+            ));
+            for t in row.tags {
+                if t.name().starts_with("user:") || t.name().starts_with(KEY_METADATA_TAG_PREFIX) {
+                    upd_tags.push(t);
+                }
+            }
+            self.backend
+                .update(
+                    EntryKind::Kms,
+                    EntryOperation::Replace,
+                    KmsCategory::CryptoKey.as_str(),
+                    name,
+                    Some(value.as_ref()),
+                    Some(upd_tags.as_slice()),
+                    None,
+                )
+                .await?;
+
+            KeyEntry::from_entry(Entry::new(
+                EntryKind::Kms,
+                KmsCategory::CryptoKey.as_str(),
+                name,
+                value,
+                upd_tags,
+            ))
+        }
+        .await;
+        self.audit(
+            "rotate_key",
+            EntryKind::Kms,
+            Some(KmsCategory::CryptoKey.as_str()),
+            Some(name),
+            result,
+        )
+        .await
+    }
+
+    /// Fetch a specific version of a key from the store, which may be the
+    /// current version or a version superseded by `rotate_key`
+    pub async fn fetch_key_version(
+        &mut self,
+        name: &str,
+        version: u32,
+        for_update: bool,
+    ) -> Result<Option<KeyEntry>, Error> {
+        let result = self.fetch_versioned_key(name, version, for_update).await;
+        self.audit(
+            "fetch_key_version",
+            EntryKind::Kms,
+            Some(KmsCategory::CryptoKey.as_str()),
+            Some(name),
+            result,
+        )
+        .await
+    }
+
+    /// Fetch all available versions of a key from the store, ordered from the
+    /// oldest to the current version
+    ///
+    /// Each entry records its version number and, for superseded versions,
+    /// the time it was rotated. An empty list is returned if the key is not
+    /// found.
+    pub async fn fetch_key_versions(&mut self, name: &str) -> Result<Vec<KeyEntry>, Error> {
+        let result = async {
+            let Some(current) = self.fetch_versioned_key(name, 0, false).await? else {
+                return Ok(Vec::new());
+            };
+            let mut entries = Vec::with_capacity(current.version as usize);
+            for version in 1..current.version {
+                if let Some(entry) = self.fetch_versioned_key(name, version, false).await? {
+                    entries.push(entry);
+                }
+            }
+            entries.push(current);
+            Ok(entries)
+        }
+        .await;
+        self.audit(
+            "fetch_key_versions",
+            EntryKind::Kms,
+            Some(KmsCategory::CryptoKey.as_str()),
+            Some(name),
+            result,
+        )
+        .await
+    }
+
+    /// Fetch a version of a key, where version 0 selects the current version
+    async fn fetch_versioned_key(
+        &mut self,
+        name: &str,
+        version: u32,
+        for_update: bool,
+    ) -> Result<Option<KeyEntry>, Error> {
+        let current = self
+            .backend
+            .fetch(
+                EntryKind::Kms,
+                KmsCategory::CryptoKey.as_str(),
+                name,
+                for_update,
+            )
+            .await?
+            .map(KeyEntry::from_entry)
+            .transpose()?;
+        if version == 0 || current.as_ref().map(|c| c.version) == Some(version) {
+            return Ok(current);
+        }
+        Ok(
+            if let Some(row) = self
+                .backend
+                .fetch(
+                    EntryKind::Kms,
+                    KmsCategory::CryptoKeyVersion.as_str(),
+                    &key_version_name(name, version),
+                    for_update,
+                )
+                .await?
+            {
+                Some(KeyEntry::from_entry(row)?)
+            } else {
+                None
+            },
+        )
+    }
+
+    /// Fetch records from the audit log of the profile in the order they were
+    /// appended, optionally limited to a `category` and to records appended
+    /// at or after `since`, in seconds since the Unix epoch
+    ///
+    /// Records are only appended when the store is opened with auditing
+    /// enabled. Within a transaction, records are committed or rolled back
+    /// along with the operations they describe.
+    pub async fn fetch_audit_log(
+        &mut self,
+        category: Option<&str>,
+        since: Option<i64>,
+        limit: Option<i64>,
+    ) -> Result<Vec<AuditRecord>, Error> {
+        Ok(self.backend.fetch_audit_log(category, since, limit).await?)
+    }
+
+    /// Append a record of an operation to the audit log when auditing is
+    /// enabled, returning the result of the operation
+    async fn audit<T>(
+        &mut self,
+        operation: &str,
+        kind: EntryKind,
+        category: Option<&str>,
+        name: Option<&str>,
+        result: Result<T, Error>,
+    ) -> Result<T, Error> {
+        let error = result.as_ref().err().map(|err| err.kind().as_str());
+        let appended = self
+            .backend
+            .append_audit(operation, Some(kind), category, name, error)
+            .await;
+        match (result, appended) {
+            (Ok(_), Err(err)) => Err(err.into()),
+            (result, _) => result,
+        }
+    }
+
+    /// Create a named savepoint within the current transaction
+    pub async fn savepoint(&mut self, name: &str) -> Result<(), Error> {
+        Ok(self
+            .backend
+            .savepoint(name, SavepointOperation::Create)
+            .await?)
+    }
+
+    /// Release a savepoint, keeping all changes made since it was created
+    pub async fn release_savepoint(&mut self, name: &str) -> Result<(), Error> {
+        Ok(self
+            .backend
+            .savepoint(name, SavepointOperation::Release)
+            .await?)
+    }
+
+    /// Discard all changes made since a savepoint was created, leaving the
+    /// savepoint and the transaction active
+    pub async fn rollback_to_savepoint(&mut self, name: &str) -> Result<(), Error> {
+        Ok(self
+            .backend
+            .savepoint(name, SavepointOperation::Rollback)
+            .await?)
+    }
+
+    /// Test the connection to the store
+    pub async fn ping(&mut self) -> Result<(), Error> {
+        Ok(self.backend.ping().await?)
+    }
+
+    /// Remove records modified by this session from the entry cache of the store
+    fn invalidate_entries(&mut self, category: Option<&str>, name: Option<&str>) {
+        self.entry_cache.invalidate(&self.profile, category, name);
+        if let Some(modified) = self.modified.as_mut() {
+            modified.push((category.map(str::to_string), name.map(str::to_string)));
+        }
+    }
+
+    /// Commit the pending transaction
+    pub async fn commit(mut self) -> Result<(), Error> {
+        self.backend.close(true).await?;
+        // records may have been cached by other sessions before the commit
+        for (category, name) in self.modified.take().unwrap_or_default() {
+            self.entry_cache
+                .invalidate(&self.profile, category.as_deref(), name.as_deref());
+        }
+        Ok(())
+    }
+
+    /// Roll back the pending transaction
+    pub async fn rollback(mut self) -> Result<(), Error> {
+        Ok(self.backend.close(false).await?)
+    }
+}
+
+/// A stored key loaded for use in a cryptographic operation
+enum OperationKey {
+    /// A key used locally
+    Local(Arc<LocalKey>),
+    /// A key entry whose reference is handled by a registered key backend
+    Backend {
+        entry: Box<KeyEntry>,
+        backend: Arc<dyn KeyBackend>,
+        reference: String,
+    },
+}
+
+/// Calculate the delay before retrying a transaction: exponential in the
+/// number of attempts, with a random jitter of up to half the delay
+fn retry_delay(attempt: usize) -> Duration {
+    let delay = TXN_RETRY_BASE_DELAY
+        .saturating_mul(1 << (attempt - 1).min(16))
+        .min(TXN_RETRY_MAX_DELAY);
+    let mut rand = [0u8; 4];
+    fill_random(&mut rand);
+    let jitter = u32::from_le_bytes(rand) as f64 / u32::MAX as f64;
+    delay.mul_f64(1.0 - jitter / 2.0)
 }
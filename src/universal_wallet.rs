@@ -0,0 +1,532 @@
+//! Interoperable wallet documents in the Universal Wallet 2020 format
+//!
+//! The W3C CCG Universal Wallet 2020 format is a JSON-LD document listing
+//! the contents of a wallet. Items in the categories listed below are
+//! represented by their JSON value, using the corresponding content type, and
+//! keys are represented as `JsonWebKey2020` objects including the private
+//! JWK. Items in other categories are represented as `AskarRecord` objects
+//! retaining their category, name, value and tags.
+//!
+//! | Category           | Content type             |
+//! |--------------------|--------------------------|
+//! | `w3c_credential`   | `VerifiableCredential`   |
+//! | `w3c_presentation` | `VerifiablePresentation` |
+//! | `mnemonic`         | `Mnemonic`               |
+//! | `entity`           | `Entity`                 |
+//! | `person`           | `Person`                 |
+//! | `organization`     | `Organization`           |
+//!
+//! A locked wallet replaces each content object with a JWE in the flattened
+//! JSON serialization, encrypted to an X25519 key which is either derived
+//! from a passphrase or provided by the recipient of the wallet. The wallet
+//! is identified by the JWK thumbprint URN of this key.
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use serde_json::{json, Value};
+
+use crate::{
+    crypto::{
+        alg::AesTypes,
+        buffer::HexRepr,
+        kdf::{
+            argon2::{Argon2, PARAMS_MODERATE, SALT_LENGTH},
+            KeyDerivation,
+        },
+        random::fill_random,
+    },
+    entry::{Entry, EntryKind, EntryTag},
+    error::Error,
+    export::ExportKey,
+    kms::{decrypt_json, encrypt_json, KeyAlg, LocalKey, SecretBytes},
+};
+
+/// The JSON-LD context of a wallet document
+const WALLET_CONTEXT: &str = "https://w3id.org/wallet/v1";
+
+/// The type of a wallet document
+const WALLET_TYPE: &str = "UniversalWallet2020";
+
+const STATUS_LOCKED: &str = "LOCKED";
+const STATUS_UNLOCKED: &str = "UNLOCKED";
+
+/// The content type of an exported key
+const KEY_CONTENT_TYPE: &str = "JsonWebKey2020";
+
+/// The content type of an item without a corresponding content type
+const RECORD_CONTENT_TYPE: &str = "AskarRecord";
+
+/// The content encryption algorithm of locked wallet contents
+const CONTENT_ENC_ALG: KeyAlg = KeyAlg::Aes(AesTypes::A256Gcm);
+
+/// The JWE content type of locked wallet contents
+const CONTENT_JWE_TYPE: &str = "json";
+
+/// The identifier of the passphrase key derivation parameters
+const PASSPHRASE_LEVEL: &str = "argon2i:mod";
+
+/// The URN prefix of the wallet identifier
+const THUMBPRINT_URN_PREFIX: &str = "urn:ietf:params:oauth:jwk-thumbprint:sha-256:";
+
+/// The wallet content types corresponding to item categories
+const CONTENT_TYPES: &[(&str, &str)] = &[
+    ("w3c_credential", "VerifiableCredential"),
+    ("w3c_presentation", "VerifiablePresentation"),
+    ("mnemonic", "Mnemonic"),
+    ("entity", "Entity"),
+    ("person", "Person"),
+    ("organization", "Organization"),
+];
+
+#[derive(Deserialize, Serialize)]
+struct WalletDocument {
+    #[serde(rename = "@context")]
+    context: Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    id: Option<String>,
+    #[serde(rename = "type")]
+    type_: Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    status: Option<String>,
+    #[serde(
+        default,
+        rename = "keyDerivation",
+        skip_serializing_if = "Option::is_none"
+    )]
+    key_derivation: Option<WalletKeyDerivation>,
+    #[serde(default)]
+    contents: Vec<Value>,
+}
+
+#[derive(Deserialize, Serialize)]
+struct WalletKeyDerivation {
+    level: String,
+    salt: String,
+}
+
+#[derive(Deserialize, Serialize)]
+struct RecordContent {
+    #[serde(rename = "type")]
+    type_: String,
+    category: String,
+    name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    value: Option<String>,
+    #[serde(
+        default,
+        rename = "valueBase64",
+        skip_serializing_if = "Option::is_none"
+    )]
+    value_base64: Option<String>,
+    #[serde(default)]
+    tags: Vec<RecordTag>,
+}
+
+#[derive(Deserialize, Serialize)]
+struct RecordTag {
+    name: String,
+    value: String,
+    #[serde(default)]
+    plaintext: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    numeric: bool,
+}
+
+/// A content object read from a wallet document
+pub(crate) enum WalletContent {
+    /// An item entry
+    Item(Entry),
+    /// A key, along with its identifier if provided
+    Key { name: Option<String>, key: LocalKey },
+}
+
+fn content_type(category: &str) -> Option<&'static str> {
+    CONTENT_TYPES
+        .iter()
+        .find(|(cat, _)| *cat == category)
+        .map(|(_, ty)| *ty)
+}
+
+fn content_category(type_: &str) -> Option<&'static str> {
+    CONTENT_TYPES
+        .iter()
+        .find(|(_, ty)| *ty == type_)
+        .map(|(cat, _)| *cat)
+}
+
+/// Collect the types of a JSON-LD object, given as a string or an array
+fn object_types(value: &Value) -> Vec<&str> {
+    match value {
+        Value::String(ty) => vec![ty.as_str()],
+        Value::Array(types) => types.iter().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn random_name() -> String {
+    let mut name = [0u8; 16];
+    fill_random(&mut name);
+    HexRepr(name).to_string()
+}
+
+fn derive_wallet_key(passphrase: &str, salt: &[u8]) -> Result<LocalKey, Error> {
+    let mut key = SecretBytes::new_with(32, |_| ());
+    Argon2::new(passphrase.as_bytes(), salt, PARAMS_MODERATE)?.derive_key_bytes(key.as_mut())?;
+    LocalKey::from_secret_bytes(KeyAlg::X25519, key.as_ref())
+}
+
+fn wallet_id(key: &LocalKey) -> Result<String, Error> {
+    Ok(format!(
+        "{}{}",
+        THUMBPRINT_URN_PREFIX,
+        key.to_jwk_thumbprint(None)?
+    ))
+}
+
+fn item_content(entry: &Entry) -> Result<Value, Error> {
+    if let Some(content_type) = content_type(&entry.category) {
+        if let Ok(Value::Object(mut content)) = serde_json::from_slice(&entry.value) {
+            content
+                .entry("type")
+                .or_insert_with(|| Value::from(content_type));
+            return Ok(Value::Object(content));
+        }
+    }
+    let (value, value_base64) = match std::str::from_utf8(&entry.value) {
+        Ok(value) => (Some(value.to_string()), None),
+        Err(_) => (None, Some(URL_SAFE_NO_PAD.encode(&entry.value))),
+    };
+    let record = RecordContent {
+        type_: RECORD_CONTENT_TYPE.to_string(),
+        category: entry.category.clone(),
+        name: entry.name.clone(),
+        value,
+        value_base64,
+        tags: entry
+            .tags
+            .iter()
+            .map(|tag| RecordTag {
+                name: tag.name().to_string(),
+                value: tag.value().to_string(),
+                plaintext: !matches!(tag, EntryTag::Encrypted(..)),
+                numeric: matches!(tag, EntryTag::Numeric(..)),
+            })
+            .collect(),
+    };
+    serde_json::to_value(record).map_err(err_map!(Unexpected, "Error encoding wallet content"))
+}
+
+fn key_content(name: &str, key: &LocalKey) -> Result<Value, Error> {
+    let public: Value = serde_json::from_str(&key.to_jwk_public(None)?)
+        .map_err(err_map!(Unexpected, "Error encoding public key"))?;
+    let secret: Value = serde_json::from_slice(key.to_jwk_secret()?.as_ref())
+        .map_err(err_map!(Unexpected, "Error encoding private key"))?;
+    Ok(json!({
+        "id": name,
+        "type": KEY_CONTENT_TYPE,
+        "publicKeyJwk": public,
+        "privateKeyJwk": secret,
+    }))
+}
+
+fn parse_content(content: Value) -> Result<WalletContent, Error> {
+    let types = object_types(&content["type"]);
+    let id = content["id"].as_str().map(str::to_string);
+    if types.contains(&KEY_CONTENT_TYPE) {
+        let jwk = content
+            .get("privateKeyJwk")
+            .ok_or_else(|| err_msg!(Input, "Wallet key does not include a private key"))?;
+        let key = LocalKey::from_jwk(&jwk.to_string())?;
+        return Ok(WalletContent::Key { name: id, key });
+    }
+    if types.contains(&RECORD_CONTENT_TYPE) {
+        let record: RecordContent =
+            serde_json::from_value(content).map_err(err_map!(Input, "Invalid wallet record"))?;
+        let value = match (record.value, record.value_base64) {
+            (Some(value), None) => value.into_bytes(),
+            (None, Some(value)) => URL_SAFE_NO_PAD
+                .decode(value)
+                .map_err(err_map!(Input, "Invalid wallet record value"))?,
+            _ => return Err(err_msg!(Input, "Invalid wallet record value")),
+        };
+        let tags = record
+            .tags
+            .into_iter()
+            .map(|tag| {
+                if tag.numeric {
+                    EntryTag::Numeric(tag.name, tag.value)
+                } else if tag.plaintext {
+                    EntryTag::Plaintext(tag.name, tag.value)
+                } else {
+                    EntryTag::Encrypted(tag.name, tag.value)
+                }
+            })
+            .collect();
+        return Ok(WalletContent::Item(Entry::new(
+            EntryKind::Item,
+            record.category,
+            record.name,
+            value,
+            tags,
+        )));
+    }
+    let category = types
+        .iter()
+        .find_map(|ty| content_category(ty))
+        .map(str::to_string)
+        .or_else(|| types.first().map(|ty| ty.to_string()))
+        .ok_or_else(|| err_msg!(Input, "Wallet content has no type"))?;
+    let value = serde_json::to_vec(&content)
+        .map_err(err_map!(Unexpected, "Error encoding wallet content"))?;
+    Ok(WalletContent::Item(Entry::new(
+        EntryKind::Item,
+        category,
+        id.unwrap_or_else(random_name),
+        value,
+        Vec::new(),
+    )))
+}
+
+/// Build a wallet document from the items and keys of a profile
+pub(crate) struct WalletWriter {
+    key: Option<LocalKey>,
+    document: WalletDocument,
+}
+
+impl WalletWriter {
+    pub fn new(key: Option<ExportKey<'_>>) -> Result<Self, Error> {
+        let (key, key_derivation) = match key {
+            Some(ExportKey::Passphrase(passphrase)) => {
+                let mut salt = vec![0u8; SALT_LENGTH];
+                fill_random(&mut salt);
+                let key = derive_wallet_key(passphrase, &salt)?;
+                let key_derivation = WalletKeyDerivation {
+                    level: PASSPHRASE_LEVEL.to_string(),
+                    salt: URL_SAFE_NO_PAD.encode(salt),
+                };
+                (Some(key), Some(key_derivation))
+            }
+            Some(ExportKey::Recipient(recipient)) => {
+                if recipient.algorithm() != KeyAlg::X25519 {
+                    return Err(err_msg!(
+                        Input,
+                        "Wallet recipient key must be an X25519 key"
+                    ));
+                }
+                let key = LocalKey::from_public_bytes(
+                    recipient.algorithm(),
+                    recipient.to_public_bytes()?.as_ref(),
+                )?;
+                (Some(key), None)
+            }
+            None => (None, None),
+        };
+        let document = WalletDocument {
+            context: json!([WALLET_CONTEXT]),
+            id: key.as_ref().map(wallet_id).transpose()?,
+            type_: Value::from(WALLET_TYPE),
+            status: Some(
+                if key.is_some() {
+                    STATUS_LOCKED
+                } else {
+                    STATUS_UNLOCKED
+                }
+                .to_string(),
+            ),
+            key_derivation,
+            contents: Vec::new(),
+        };
+        Ok(Self { key, document })
+    }
+
+    fn add_content(&mut self, content: Value) -> Result<(), Error> {
+        let content = match self.key.as_ref() {
+            Some(key) => encrypt_json(
+                content.to_string().as_bytes(),
+                key,
+                CONTENT_ENC_ALG,
+                CONTENT_JWE_TYPE,
+            )?,
+            None => content,
+        };
+        self.document.contents.push(content);
+        Ok(())
+    }
+
+    pub fn add_entry(&mut self, entry: &Entry) -> Result<(), Error> {
+        self.add_content(item_content(entry)?)
+    }
+
+    pub fn add_key(&mut self, name: &str, key: &LocalKey) -> Result<(), Error> {
+        self.add_content(key_content(name, key)?)
+    }
+
+    pub fn finish(self) -> Result<String, Error> {
+        serde_json::to_string(&self.document)
+            .map_err(err_map!(Unexpected, "Error encoding wallet document"))
+    }
+}
+
+/// Read the contents of a wallet document, decrypting them if necessary
+pub(crate) fn read_wallet(
+    wallet: &str,
+    key: Option<ExportKey<'_>>,
+) -> Result<Vec<WalletContent>, Error> {
+    let document: WalletDocument =
+        serde_json::from_str(wallet).map_err(err_map!(Input, "Invalid wallet document"))?;
+    if !object_types(&document.type_).contains(&WALLET_TYPE) {
+        return Err(err_msg!(Unsupported, "Unsupported wallet document type"));
+    }
+    if document.status.as_deref() != Some(STATUS_LOCKED) {
+        return document.contents.into_iter().map(parse_content).collect();
+    }
+    let mut derived = None;
+    let key = match (key, document.key_derivation) {
+        (Some(ExportKey::Passphrase(passphrase)), Some(key_derivation)) => {
+            if key_derivation.level != PASSPHRASE_LEVEL {
+                return Err(err_msg!(
+                    Unsupported,
+                    "Unsupported wallet key derivation: {}",
+                    key_derivation.level
+                ));
+            }
+            let salt = URL_SAFE_NO_PAD
+                .decode(key_derivation.salt)
+                .map_err(err_map!(Input, "Invalid wallet key derivation salt"))?;
+            &*derived.insert(derive_wallet_key(passphrase, &salt)?)
+        }
+        (Some(ExportKey::Recipient(secret)), None) => secret,
+        (Some(ExportKey::Passphrase(_)), None) => {
+            return Err(err_msg!(Input, "Wallet is locked using a recipient key"));
+        }
+        (Some(ExportKey::Recipient(_)), Some(_)) => {
+            return Err(err_msg!(Input, "Wallet is locked using a passphrase"));
+        }
+        (None, _) => return Err(err_msg!(Input, "Wallet is locked")),
+    };
+    if let Some(id) = document.id.as_deref() {
+        if id.starts_with(THUMBPRINT_URN_PREFIX) && id != wallet_id(key)? {
+            return Err(if derived.is_some() {
+                err_msg!(Encryption, "Incorrect passphrase for locked wallet")
+            } else {
+                err_msg!(Input, "Wallet is not addressed to the provided key")
+            });
+        }
+    }
+    document
+        .contents
+        .iter()
+        .map(|content| {
+            let data = decrypt_json(content, key)
+                .map_err(|_| err_msg!(Encryption, "Error decrypting wallet content"))?;
+            let content = serde_json::from_slice(data.as_ref())
+                .map_err(err_map!(Input, "Invalid wallet content"))?;
+            parse_content(content)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries() -> Vec<Entry> {
+        vec![
+            Entry::new(
+                EntryKind::Item,
+                "w3c_credential",
+                "urn:uuid:1234",
+                br#"{"id":"urn:uuid:1234","type":["VerifiableCredential"]}"#.to_vec(),
+                Vec::new(),
+            ),
+            Entry::new(
+                EntryKind::Item,
+                "connection",
+                "conn",
+                b"\xff\x00".to_vec(),
+                vec![
+                    EntryTag::Encrypted("state".to_string(), "active".to_string()),
+                    EntryTag::Plaintext("role".to_string(), "inviter".to_string()),
+                ],
+            ),
+        ]
+    }
+
+    fn round_trip(
+        lock: Option<ExportKey<'_>>,
+        unlock: Option<ExportKey<'_>>,
+    ) -> Vec<WalletContent> {
+        let key = LocalKey::generate(KeyAlg::Ed25519, false).unwrap();
+        let mut writer = WalletWriter::new(lock).unwrap();
+        for entry in entries() {
+            writer.add_entry(&entry).unwrap();
+        }
+        writer.add_key("signing", &key).unwrap();
+        let wallet = writer.finish().unwrap();
+        let contents = read_wallet(&wallet, unlock).unwrap();
+        assert_eq!(contents.len(), 3);
+        match &contents[2] {
+            WalletContent::Key { name, key: loaded } => {
+                assert_eq!(name.as_deref(), Some("signing"));
+                assert_eq!(
+                    loaded.to_jwk_thumbprint(None).unwrap(),
+                    key.to_jwk_thumbprint(None).unwrap()
+                );
+            }
+            _ => panic!("expected key content"),
+        }
+        contents
+    }
+
+    #[test]
+    fn unlocked_wallet() {
+        let contents = round_trip(None, None);
+        let expected = entries();
+        for (content, entry) in contents.iter().zip(expected.iter()) {
+            match content {
+                WalletContent::Item(item) => assert_eq!(item, entry),
+                _ => panic!("expected item content"),
+            }
+        }
+    }
+
+    #[test]
+    fn locked_wallet() {
+        round_trip(
+            Some(ExportKey::Passphrase("test")),
+            Some(ExportKey::Passphrase("test")),
+        );
+        let recip = LocalKey::generate(KeyAlg::X25519, false).unwrap();
+        round_trip(
+            Some(ExportKey::Recipient(&recip)),
+            Some(ExportKey::Recipient(&recip)),
+        );
+
+        let mut writer = WalletWriter::new(Some(ExportKey::Passphrase("test"))).unwrap();
+        writer.add_entry(&entries()[0]).unwrap();
+        let wallet = writer.finish().unwrap();
+        assert!(read_wallet(&wallet, None).is_err());
+        assert!(read_wallet(&wallet, Some(ExportKey::Passphrase("other"))).is_err());
+    }
+
+    #[test]
+    fn foreign_content() {
+        let wallet = json!({
+            "@context": [WALLET_CONTEXT],
+            "type": WALLET_TYPE,
+            "status": STATUS_UNLOCKED,
+            "contents": [
+                {"type": "Person", "name": "Alice"},
+                {"id": "urn:metal:1", "type": "MetalCard"},
+            ],
+        });
+        let contents = read_wallet(&wallet.to_string(), None).unwrap();
+        match &contents[..] {
+            [WalletContent::Item(person), WalletContent::Item(card)] => {
+                assert_eq!(person.category, "person");
+                assert_eq!(card.category, "MetalCard");
+                assert_eq!(card.name, "urn:metal:1");
+            }
+            _ => panic!("expected item contents"),
+        }
+    }
+}
@@ -1,6 +1,15 @@
 #![allow(clippy::bool_assert_comparison)]
 
-use aries_askar::kms::{KeyAlg, LocalKey};
+use aries_askar::{
+    crypto::{
+        alg::{AesTypes, Chacha20Types, EcCurves},
+        random::{with_rng, RandomDet},
+    },
+    kms::{
+        derive_key_ecdh_1pu, derive_key_ecdh_es, derive_keys_ecdh_1pu, derive_keys_ecdh_es, KeyAlg,
+        LocalKey,
+    },
+};
 
 const ERR_CREATE_KEYPAIR: &str = "Error creating keypair";
 const ERR_SIGN: &str = "Error signing message";
@@ -50,3 +59,122 @@ pub fn localkey_sign_verify() {
         true
     );
 }
+
+#[test]
+pub fn localkey_export_wrapped() {
+    let key = LocalKey::generate(KeyAlg::Ed25519, false).expect(ERR_CREATE_KEYPAIR);
+    for (recip_alg, enc_alg) in [
+        (KeyAlg::X25519, KeyAlg::Chacha20(Chacha20Types::XC20P)),
+        (
+            KeyAlg::EcCurve(EcCurves::Secp256r1),
+            KeyAlg::Aes(AesTypes::A256Gcm),
+        ),
+        (
+            KeyAlg::EcCurve(EcCurves::Secp384r1),
+            KeyAlg::Aes(AesTypes::A128CbcHs256),
+        ),
+    ] {
+        let recipient = LocalKey::generate(recip_alg, false).expect(ERR_CREATE_KEYPAIR);
+        let jwe = key
+            .export_wrapped(&recipient.to_jwk_public(None).unwrap(), enc_alg)
+            .expect("Error exporting key");
+        assert_eq!(jwe.split('.').count(), 5);
+        let unwrapped = LocalKey::import_wrapped(&jwe, &recipient).expect("Error importing key");
+        assert_eq!(
+            unwrapped.to_jwk_secret().unwrap(),
+            key.to_jwk_secret().unwrap()
+        );
+
+        let other = LocalKey::generate(recip_alg, false).expect(ERR_CREATE_KEYPAIR);
+        LocalKey::import_wrapped(&jwe, &other).expect_err("Expected decryption error");
+    }
+
+    let recipient = LocalKey::generate(KeyAlg::Ed25519, false).expect(ERR_CREATE_KEYPAIR);
+    key.export_wrapped(
+        &recipient.to_jwk_public(None).unwrap(),
+        KeyAlg::Aes(AesTypes::A256Gcm),
+    )
+    .expect_err("Expected unsupported recipient");
+}
+
+#[test]
+pub fn localkey_export_wrapped_deterministic() {
+    let key = LocalKey::generate(KeyAlg::Ed25519, false).expect(ERR_CREATE_KEYPAIR);
+    let recipient = LocalKey::generate(KeyAlg::X25519, false).expect(ERR_CREATE_KEYPAIR);
+    let recip_jwk = recipient.to_jwk_public(None).unwrap();
+    let export = || {
+        with_rng(RandomDet::new(b"testseed"), || {
+            key.export_wrapped(&recip_jwk, KeyAlg::Chacha20(Chacha20Types::XC20P))
+                .expect("Error exporting key")
+        })
+    };
+    let jwe = export();
+    assert_eq!(export(), jwe);
+    assert_ne!(
+        key.export_wrapped(&recip_jwk, KeyAlg::Chacha20(Chacha20Types::XC20P))
+            .expect("Error exporting key"),
+        jwe
+    );
+}
+
+#[test]
+pub fn localkey_derive_batch() {
+    let wrap_alg = KeyAlg::Aes(AesTypes::A256Kw);
+    let ephem = LocalKey::generate(KeyAlg::X25519, true).expect(ERR_CREATE_KEYPAIR);
+    let sender = LocalKey::generate(KeyAlg::X25519, false).expect(ERR_CREATE_KEYPAIR);
+    let recips = (0..5)
+        .map(|_| LocalKey::generate(KeyAlg::X25519, false).expect(ERR_CREATE_KEYPAIR))
+        .collect::<Vec<_>>();
+    let recip_refs = recips.iter().collect::<Vec<_>>();
+
+    let keys = derive_keys_ecdh_es(wrap_alg, &ephem, &recip_refs, b"alg", b"apu", b"apv")
+        .expect("Error deriving keys");
+    assert_eq!(keys.len(), recips.len());
+    for (key, recip) in keys.iter().zip(&recips) {
+        let expected = derive_key_ecdh_es(wrap_alg, &ephem, recip, b"alg", b"apu", b"apv", false)
+            .expect("Error deriving key");
+        assert_eq!(
+            key.to_secret_bytes().unwrap(),
+            expected.to_secret_bytes().unwrap()
+        );
+    }
+
+    let keys = derive_keys_ecdh_1pu(
+        wrap_alg,
+        &ephem,
+        &sender,
+        &recip_refs,
+        b"alg",
+        b"apu",
+        b"apv",
+        b"tag",
+    )
+    .expect("Error deriving keys");
+    assert_eq!(keys.len(), recips.len());
+    for (key, recip) in keys.iter().zip(&recips) {
+        let expected = derive_key_ecdh_1pu(
+            wrap_alg, &ephem, &sender, recip, b"alg", b"apu", b"apv", b"tag", false,
+        )
+        .expect("Error deriving key");
+        assert_eq!(
+            key.to_secret_bytes().unwrap(),
+            expected.to_secret_bytes().unwrap()
+        );
+    }
+
+    assert!(
+        derive_keys_ecdh_es(wrap_alg, &ephem, &[], b"alg", b"apu", b"apv")
+            .expect("Error deriving keys")
+            .is_empty()
+    );
+    let other = LocalKey::generate(KeyAlg::Ed25519, false).expect(ERR_CREATE_KEYPAIR);
+    derive_keys_ecdh_es(
+        wrap_alg,
+        &ephem,
+        &[&recips[0], &other],
+        b"alg",
+        b"apu",
+        b"apv",
+    )
+    .expect_err("Expected key exchange error");
+}
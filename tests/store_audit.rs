@@ -0,0 +1,143 @@
+use aries_askar::{
+    entry::EntryKind,
+    future::block_on,
+    kms::{KeyAlg, LocalKey},
+    ErrorKind, Store, StoreKeyMethod,
+};
+
+mod utils;
+
+use self::utils::{ERR_CLOSE, ERR_OPEN, ERR_RAW_KEY, ERR_SESSION};
+
+const ERR_AUDIT: &str = "Error fetching audit log";
+
+#[test]
+fn store_audit_log() {
+    block_on(async {
+        let pass_key = Store::new_raw_key(None).expect(ERR_RAW_KEY);
+        let db = Store::provision(
+            "sqlite://:memory:?audit=true",
+            StoreKeyMethod::RawKey,
+            pass_key,
+            None,
+            true,
+        )
+        .await
+        .expect(ERR_OPEN);
+
+        let mut conn = db.session(None).await.expect(ERR_SESSION);
+        conn.insert("testcat", "testrow", b"testval", None, None)
+            .await
+            .expect("Error inserting row");
+        conn.fetch("testcat", "testrow", false)
+            .await
+            .expect("Error fetching row");
+        let err = conn
+            .insert("testcat", "testrow", b"testval", None, None)
+            .await
+            .expect_err("Expected duplicate error");
+        assert_eq!(err.kind(), ErrorKind::Duplicate);
+        conn.remove("othercat", "testrow")
+            .await
+            .expect_err("Expected not found error");
+        let key = LocalKey::generate(KeyAlg::Ed25519, false).expect("Error creating key");
+        conn.insert_key("testkey", &key, None, None, None)
+            .await
+            .expect("Error inserting key");
+
+        let records = conn
+            .fetch_audit_log(None, None, None)
+            .await
+            .expect(ERR_AUDIT);
+        let summary = records
+            .iter()
+            .map(|r| {
+                (
+                    r.operation.as_str(),
+                    r.category.as_deref(),
+                    r.name.as_deref(),
+                    r.is_success(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            vec![
+                ("insert", Some("testcat"), Some("testrow"), true),
+                ("fetch", Some("testcat"), Some("testrow"), true),
+                ("insert", Some("testcat"), Some("testrow"), false),
+                ("remove", Some("othercat"), Some("testrow"), false),
+                ("insert_key", Some("cryptokey"), Some("testkey"), true),
+            ]
+        );
+        assert_eq!(records[4].kind, Some(EntryKind::Kms));
+        assert_eq!(
+            records[2].error.as_deref(),
+            Some(ErrorKind::Duplicate.as_str())
+        );
+
+        let records = conn
+            .fetch_audit_log(Some("testcat"), None, Some(2))
+            .await
+            .expect(ERR_AUDIT);
+        assert_eq!(records.len(), 2);
+        assert!(records
+            .iter()
+            .all(|r| r.category.as_deref() == Some("testcat")));
+        let since = records[0].timestamp + 3600;
+        let records = conn
+            .fetch_audit_log(None, Some(since), None)
+            .await
+            .expect(ERR_AUDIT);
+        assert!(records.is_empty());
+        drop(conn);
+
+        // audit records are rolled back along with the transaction
+        let mut txn = db.transaction(None).await.expect(ERR_SESSION);
+        txn.insert("testcat", "txnrow", b"testval", None, None)
+            .await
+            .expect("Error inserting row");
+        txn.rollback()
+            .await
+            .expect("Error rolling back transaction");
+
+        let mut conn = db.session(None).await.expect(ERR_SESSION);
+        let records = conn
+            .fetch_audit_log(None, None, None)
+            .await
+            .expect(ERR_AUDIT);
+        assert_eq!(records.len(), 5);
+        drop(conn);
+
+        db.close().await.expect(ERR_CLOSE);
+    })
+}
+
+#[test]
+fn store_audit_disabled() {
+    block_on(async {
+        let pass_key = Store::new_raw_key(None).expect(ERR_RAW_KEY);
+        let db = Store::provision(
+            "sqlite://:memory:",
+            StoreKeyMethod::RawKey,
+            pass_key,
+            None,
+            true,
+        )
+        .await
+        .expect(ERR_OPEN);
+
+        let mut conn = db.session(None).await.expect(ERR_SESSION);
+        conn.insert("testcat", "testrow", b"testval", None, None)
+            .await
+            .expect("Error inserting row");
+        let records = conn
+            .fetch_audit_log(None, None, None)
+            .await
+            .expect(ERR_AUDIT);
+        assert!(records.is_empty());
+        drop(conn);
+
+        db.close().await.expect(ERR_CLOSE);
+    })
+}
@@ -1,7 +1,7 @@
 use aries_askar::{
     future::block_on,
     kms::{KeyAlg, LocalKey},
-    Store, StoreKeyMethod,
+    ErrorKind, Store, StoreKeyMethod,
 };
 
 const ERR_RAW_KEY: &str = "Error creating raw store key";
@@ -75,3 +75,96 @@ fn store_copy() {
         db.close().await.expect(ERR_CLOSE);
     })
 }
+
+#[test]
+fn store_copy_to_store() {
+    block_on(async {
+        let pass_key = Store::new_raw_key(None).expect(ERR_RAW_KEY);
+        let db = Store::provision(
+            "sqlite://:memory:",
+            StoreKeyMethod::RawKey,
+            pass_key,
+            None,
+            true,
+        )
+        .await
+        .expect(ERR_OPEN);
+
+        let keypair = LocalKey::generate(KeyAlg::Ed25519, false).expect("Error creating keypair");
+
+        let mut conn = db.session(None).await.expect(ERR_SESSION);
+        conn.insert_key("testkey", &keypair, None, None, None)
+            .await
+            .expect("Error inserting key");
+        conn.insert("testcat", "testrow", b"testval", None, None)
+            .await
+            .expect("Error inserting row");
+        conn.insert("othercat", "testrow", b"otherval", None, None)
+            .await
+            .expect("Error inserting row");
+        drop(conn);
+
+        let pass_key_target = Store::new_raw_key(None).expect(ERR_RAW_KEY);
+        let target = Store::provision(
+            "sqlite://:memory:",
+            StoreKeyMethod::RawKey,
+            pass_key_target,
+            None,
+            true,
+        )
+        .await
+        .expect(ERR_OPEN);
+        let profile = db
+            .get_default_profile()
+            .await
+            .expect("Error fetching default profile");
+
+        db.copy_to_store(
+            &target,
+            Some(std::slice::from_ref(&profile)),
+            Some(&["testcat".to_string()]),
+        )
+        .await
+        .expect("Error copying store entries");
+
+        let mut conn = target
+            .session(Some(profile.clone()))
+            .await
+            .expect(ERR_SESSION);
+        let found = conn
+            .fetch_key("testkey", false)
+            .await
+            .expect("Error fetching key")
+            .expect(ERR_REQ_ROW);
+        found.load_local_key().expect("Error loading key");
+        let found = conn
+            .fetch("testcat", "testrow", false)
+            .await
+            .expect("Error loading row")
+            .expect(ERR_REQ_ROW);
+        assert_eq!(found.value, b"testval".to_vec());
+        let found = conn
+            .fetch("othercat", "testrow", false)
+            .await
+            .expect("Error loading row");
+        assert!(found.is_none());
+        drop(conn);
+
+        // existing records are not overwritten and the copy is rolled back
+        let err = db
+            .copy_to_store(&target, None, None)
+            .await
+            .expect_err("Expected duplicate error");
+        assert_eq!(err.kind(), ErrorKind::Duplicate);
+        let mut conn = target.session(Some(profile)).await.expect(ERR_SESSION);
+        let found = conn
+            .fetch("othercat", "testrow", false)
+            .await
+            .expect("Error loading row");
+        assert!(found.is_none());
+        drop(conn);
+
+        target.close().await.expect(ERR_CLOSE);
+        db.close().await.expect(ERR_CLOSE);
+    })
+}
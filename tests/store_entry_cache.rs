@@ -0,0 +1,101 @@
+use std::time::Duration;
+
+use aries_askar::{future::block_on, Store};
+
+mod utils;
+
+use self::utils::{provision, ERR_CLOSE, ERR_SESSION};
+
+const ERR_TRANSACTION: &str = "Error creating store transaction";
+const ERR_FETCH: &str = "Error fetching test row";
+const ERR_UPDATE: &str = "Error updating test row";
+
+async fn fetch_value(db: &Store, name: &str) -> Option<Vec<u8>> {
+    let mut conn = db.session(None).await.expect(ERR_SESSION);
+    conn.fetch("category", name, false)
+        .await
+        .expect(ERR_FETCH)
+        .map(|entry| entry.value.to_vec())
+}
+
+#[test]
+fn entry_cache_invalidation() {
+    block_on(async {
+        let db = provision().await;
+        db.set_entry_cache(16, Some(Duration::from_secs(60)));
+
+        let mut conn = db.session(None).await.expect(ERR_SESSION);
+        conn.insert("category", "name", b"first", None, None)
+            .await
+            .expect(ERR_UPDATE);
+        drop(conn);
+        assert_eq!(
+            fetch_value(&db, "name").await.as_deref(),
+            Some(&b"first"[..])
+        );
+
+        let mut conn = db.session(None).await.expect(ERR_SESSION);
+        conn.replace("category", "name", b"second", None, None)
+            .await
+            .expect(ERR_UPDATE);
+        drop(conn);
+        assert_eq!(
+            fetch_value(&db, "name").await.as_deref(),
+            Some(&b"second"[..])
+        );
+
+        let mut txn = db.transaction(None).await.expect(ERR_TRANSACTION);
+        txn.replace("category", "name", b"third", None, None)
+            .await
+            .expect(ERR_UPDATE);
+        assert_eq!(
+            txn.fetch("category", "name", false)
+                .await
+                .expect(ERR_FETCH)
+                .map(|entry| entry.value.to_vec())
+                .as_deref(),
+            Some(&b"third"[..])
+        );
+        txn.rollback().await.expect(ERR_TRANSACTION);
+        assert_eq!(
+            fetch_value(&db, "name").await.as_deref(),
+            Some(&b"second"[..])
+        );
+
+        let mut conn = db.session(None).await.expect(ERR_SESSION);
+        conn.remove_all(Some("category"), None)
+            .await
+            .expect(ERR_UPDATE);
+        drop(conn);
+        assert_eq!(fetch_value(&db, "name").await, None);
+
+        db.close().await.expect(ERR_CLOSE);
+    });
+}
+
+#[test]
+fn entry_cache_disabled() {
+    block_on(async {
+        let db = provision().await;
+        db.set_entry_cache(16, None);
+        db.set_entry_cache(0, None);
+
+        let mut conn = db.session(None).await.expect(ERR_SESSION);
+        conn.insert("category", "name", b"value", None, None)
+            .await
+            .expect(ERR_UPDATE);
+        assert!(conn
+            .fetch("category", "name", false)
+            .await
+            .expect(ERR_FETCH)
+            .is_some());
+        drop(conn);
+        db.purge_entry_cache();
+        assert_eq!(
+            fetch_value(&db, "name").await.as_deref(),
+            Some(&b"value"[..])
+        );
+
+        db.close().await.expect(ERR_CLOSE);
+    });
+}
@@ -0,0 +1,322 @@
+use aries_askar::{
+    export::{ExportKey, ImportConflict, EXPORT_VERSION},
+    future::block_on,
+    kms::{KeyAlg, LocalKey},
+    ErrorKind, Store, StoreKeyMethod,
+};
+
+mod utils;
+
+use self::utils::{ERR_CLOSE, ERR_OPEN, ERR_RAW_KEY, ERR_SESSION};
+
+#[test]
+fn store_export() {
+    block_on(async {
+        let pass_key = Store::new_raw_key(None).expect(ERR_RAW_KEY);
+        let db = Store::provision(
+            "sqlite://:memory:",
+            StoreKeyMethod::RawKey,
+            pass_key,
+            None,
+            true,
+        )
+        .await
+        .expect(ERR_OPEN);
+
+        let mut conn = db.session(None).await.expect(ERR_SESSION);
+        conn.insert("testcat", "testrow", b"testval", None, None)
+            .await
+            .expect("Error inserting row");
+        drop(conn);
+
+        let export = db
+            .export(Vec::new(), ExportKey::Passphrase("passphrase"), None)
+            .await
+            .expect("Error exporting store");
+        assert_eq!(&export[..8], b"ASKAREXP");
+        assert_eq!(export[8], EXPORT_VERSION);
+        // entry values are not written in plaintext
+        assert!(!export.windows(7).any(|w| w == b"testval"));
+        db.import(
+            export.as_slice(),
+            ExportKey::Passphrase("passphrase"),
+            ImportConflict::Skip,
+            None,
+        )
+        .await
+        .expect("Error importing store");
+
+        let recipient =
+            LocalKey::generate(KeyAlg::X25519, false).expect("Error creating recipient key");
+        db.export(Vec::new(), ExportKey::Recipient(&recipient), None)
+            .await
+            .expect("Error exporting store");
+
+        let signing_key =
+            LocalKey::generate(KeyAlg::Ed25519, false).expect("Error creating signing key");
+        let err = db
+            .export(Vec::new(), ExportKey::Recipient(&signing_key), None)
+            .await
+            .expect_err("Expected error for unsupported recipient key");
+        assert_eq!(err.kind(), ErrorKind::Input);
+
+        db.close().await.expect(ERR_CLOSE);
+    })
+}
+
+#[test]
+fn store_import() {
+    block_on(async {
+        let pass_key = Store::new_raw_key(None).expect(ERR_RAW_KEY);
+        let db = Store::provision(
+            "sqlite://:memory:",
+            StoreKeyMethod::RawKey,
+            pass_key,
+            None,
+            true,
+        )
+        .await
+        .expect(ERR_OPEN);
+        let profile = db
+            .get_default_profile()
+            .await
+            .expect("Error fetching default profile");
+
+        let keypair = LocalKey::generate(KeyAlg::Ed25519, false).expect("Error creating keypair");
+        let mut conn = db.session(None).await.expect(ERR_SESSION);
+        conn.insert_key("testkey", &keypair, None, None, None)
+            .await
+            .expect("Error inserting key");
+        conn.insert("testcat", "testrow", b"testval", None, None)
+            .await
+            .expect("Error inserting row");
+        conn.insert("othercat", "testrow", b"otherval", None, None)
+            .await
+            .expect("Error inserting row");
+        drop(conn);
+
+        let recipient =
+            LocalKey::generate(KeyAlg::X25519, false).expect("Error creating recipient key");
+        let export = db
+            .export(Vec::new(), ExportKey::Recipient(&recipient), None)
+            .await
+            .expect("Error exporting store");
+
+        let pass_key = Store::new_raw_key(None).expect(ERR_RAW_KEY);
+        let target = Store::provision(
+            "sqlite://:memory:",
+            StoreKeyMethod::RawKey,
+            pass_key,
+            None,
+            true,
+        )
+        .await
+        .expect(ERR_OPEN);
+        target
+            .import(
+                export.as_slice(),
+                ExportKey::Recipient(&recipient),
+                ImportConflict::Fail,
+                Some(&["testcat".to_string()]),
+            )
+            .await
+            .expect("Error importing store");
+
+        let mut conn = target
+            .session(Some(profile.clone()))
+            .await
+            .expect(ERR_SESSION);
+        conn.fetch_key("testkey", false)
+            .await
+            .expect("Error fetching key")
+            .expect("Expected imported key")
+            .load_local_key()
+            .expect("Error loading key");
+        assert!(conn
+            .fetch("testcat", "testrow", false)
+            .await
+            .expect("Error fetching row")
+            .is_some());
+        assert!(conn
+            .fetch("othercat", "testrow", false)
+            .await
+            .expect("Error fetching row")
+            .is_none());
+        conn.replace("testcat", "testrow", b"updated", None, None)
+            .await
+            .expect("Error replacing row");
+        drop(conn);
+
+        // existing entries cause the import to fail by default
+        let err = target
+            .import(
+                export.as_slice(),
+                ExportKey::Recipient(&recipient),
+                ImportConflict::Fail,
+                None,
+            )
+            .await
+            .expect_err("Expected duplicate error");
+        assert_eq!(err.kind(), ErrorKind::Duplicate);
+
+        for (conflict, expected) in [
+            (ImportConflict::Skip, &b"updated"[..]),
+            (ImportConflict::Overwrite, &b"testval"[..]),
+        ] {
+            target
+                .import(
+                    export.as_slice(),
+                    ExportKey::Recipient(&recipient),
+                    conflict,
+                    None,
+                )
+                .await
+                .expect("Error importing store");
+            let mut conn = target
+                .session(Some(profile.clone()))
+                .await
+                .expect(ERR_SESSION);
+            let found = conn
+                .fetch("testcat", "testrow", false)
+                .await
+                .expect("Error fetching row")
+                .expect("Expected imported row");
+            assert_eq!(found.value, expected);
+            assert!(conn
+                .fetch("othercat", "testrow", false)
+                .await
+                .expect("Error fetching row")
+                .is_some());
+        }
+
+        // the export cannot be read with a different key
+        let other =
+            LocalKey::generate(KeyAlg::X25519, false).expect("Error creating recipient key");
+        let err = target
+            .import(
+                export.as_slice(),
+                ExportKey::Recipient(&other),
+                ImportConflict::Skip,
+                None,
+            )
+            .await
+            .expect_err("Expected error for incorrect key");
+        assert_eq!(err.kind(), ErrorKind::Input);
+
+        // a truncated export is rejected
+        let err = target
+            .import(
+                &export[..export.len() - 1],
+                ExportKey::Recipient(&recipient),
+                ImportConflict::Skip,
+                None,
+            )
+            .await
+            .expect_err("Expected error for truncated export");
+        assert_eq!(err.kind(), ErrorKind::Input);
+
+        target.close().await.expect(ERR_CLOSE);
+        db.close().await.expect(ERR_CLOSE);
+    })
+}
+
+#[test]
+fn store_export_import_batches() {
+    block_on(async {
+        let pass_key = Store::new_raw_key(None).expect(ERR_RAW_KEY);
+        let db = Store::provision(
+            "sqlite://:memory:",
+            StoreKeyMethod::RawKey,
+            pass_key,
+            None,
+            true,
+        )
+        .await
+        .expect(ERR_OPEN);
+        let profile = db
+            .get_default_profile()
+            .await
+            .expect("Error fetching default profile");
+        let other_profile = db
+            .create_profile(None)
+            .await
+            .expect("Error creating profile");
+
+        // the entries span several batches of export records
+        for profile in [&profile, &other_profile] {
+            let mut conn = db.session(Some(profile.clone())).await.expect(ERR_SESSION);
+            for idx in 0..600 {
+                conn.insert(
+                    "testcat",
+                    &format!("row{idx}"),
+                    format!("value{idx}").as_bytes(),
+                    None,
+                    None,
+                )
+                .await
+                .expect("Error inserting row");
+            }
+        }
+
+        let export = db
+            .export(Vec::new(), ExportKey::Passphrase("passphrase"), None)
+            .await
+            .expect("Error exporting store");
+
+        let pass_key = Store::new_raw_key(None).expect(ERR_RAW_KEY);
+        let target = Store::provision(
+            "sqlite://:memory:",
+            StoreKeyMethod::RawKey,
+            pass_key,
+            None,
+            true,
+        )
+        .await
+        .expect(ERR_OPEN);
+        target
+            .import(
+                export.as_slice(),
+                ExportKey::Passphrase("passphrase"),
+                ImportConflict::Fail,
+                None,
+            )
+            .await
+            .expect("Error importing store");
+
+        for profile in [&profile, &other_profile] {
+            let mut conn = target
+                .session(Some(profile.clone()))
+                .await
+                .expect(ERR_SESSION);
+            assert_eq!(
+                conn.count(Some("testcat"), None)
+                    .await
+                    .expect("Error counting rows"),
+                600
+            );
+            for idx in [0, 255, 256, 599] {
+                let found = conn
+                    .fetch("testcat", &format!("row{idx}"), false)
+                    .await
+                    .expect("Error fetching row")
+                    .expect("Expected imported row");
+                assert_eq!(found.value, format!("value{idx}").as_bytes());
+            }
+        }
+
+        // a truncated export is rejected
+        let err = target
+            .import(
+                &export[..export.len() / 2],
+                ExportKey::Passphrase("passphrase"),
+                ImportConflict::Overwrite,
+                None,
+            )
+            .await
+            .expect_err("Expected error for truncated export");
+        assert_eq!(err.kind(), ErrorKind::Input);
+
+        target.close().await.expect(ERR_CLOSE);
+        db.close().await.expect(ERR_CLOSE);
+    })
+}
@@ -1,9 +1,26 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
 use aries_askar::{
-    future::block_on,
-    kms::{KeyAlg, LocalKey},
-    Store, StoreKeyMethod,
+    crypto::{
+        alg::{Chacha20Types, EcCurves},
+        kdf::KeyExchange,
+    },
+    entry::{EntryTag, TagFilter},
+    future::{block_on, BoxFuture},
+    kms::{
+        crypto_box_seal, crypto_box_seal_open, register_key_backend, register_key_provider,
+        unregister_key_provider, AttestationFormat, Encrypted, ExternalKey, KeyAlg, KeyAttestation,
+        KeyBackend, KeyOperation, KeyPolicy, KeyProvider, KeyStorage, LocalKey, SecretBytes,
+    },
+    Error, ErrorKind, Store, StoreKeyMethod,
 };
 
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use futures_lite::StreamExt;
+
 const ERR_RAW_KEY: &str = "Error creating raw store key";
 const ERR_SESSION: &str = "Error creating store session";
 const ERR_OPEN: &str = "Error opening test store instance";
@@ -49,3 +66,1277 @@ fn keypair_create_fetch() {
         db.close().await.expect(ERR_CLOSE);
     })
 }
+
+#[test]
+fn keypair_scan() {
+    block_on(async {
+        let pass_key = Store::new_raw_key(None).expect(ERR_RAW_KEY);
+        let db = Store::provision(
+            "sqlite://:memory:",
+            StoreKeyMethod::RawKey,
+            pass_key,
+            None,
+            true,
+        )
+        .await
+        .expect(ERR_OPEN);
+
+        let mut conn = db.session(None).await.expect(ERR_SESSION);
+        for (name, alg) in [
+            ("ed1", KeyAlg::Ed25519),
+            ("ed2", KeyAlg::Ed25519),
+            ("x1", KeyAlg::X25519),
+        ] {
+            let keypair = LocalKey::generate(alg, false).expect("Error creating keypair");
+            conn.insert_key(name, &keypair, None, None, None)
+                .await
+                .expect("Error inserting key");
+        }
+        drop(conn);
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+
+        let mut scan = db
+            .scan_keys(
+                None,
+                Some(KeyAlg::Ed25519.as_str()),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .await
+            .expect("Error starting key scan");
+        let rows = scan
+            .fetch_next()
+            .await
+            .expect("Error fetching scan rows")
+            .expect(ERR_REQ_ROW);
+        let mut names = rows.iter().map(|k| k.name()).collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(names, ["ed1", "ed2"]);
+        assert!(rows.iter().all(|k| k.created().is_some()));
+        drop(scan);
+
+        let keys = db
+            .scan_keys(
+                None,
+                None,
+                None,
+                None,
+                Some(now - 3600),
+                Some(now + 3600),
+                None,
+                None,
+            )
+            .await
+            .expect("Error starting key scan")
+            .try_collect::<_, _, Vec<_>>()
+            .await
+            .expect("Error streaming keys");
+        assert_eq!(keys.len(), 3);
+        for key in keys {
+            key.load_local_key().expect("Error loading key");
+        }
+
+        let keys = db
+            .scan_keys(None, None, None, None, Some(now + 3600), None, None, None)
+            .await
+            .expect("Error starting key scan")
+            .try_collect::<_, _, Vec<_>>()
+            .await
+            .expect("Error streaming keys");
+        assert!(keys.is_empty());
+
+        db.close().await.expect(ERR_CLOSE);
+    })
+}
+
+#[test]
+fn keypair_fetch_all_filtered() {
+    block_on(async {
+        let pass_key = Store::new_raw_key(None).expect(ERR_RAW_KEY);
+        let db = Store::provision(
+            "sqlite://:memory:",
+            StoreKeyMethod::RawKey,
+            pass_key,
+            None,
+            true,
+        )
+        .await
+        .expect(ERR_OPEN);
+
+        let mut conn = db.session(None).await.expect(ERR_SESSION);
+        for (name, alg, metadata) in [
+            ("ed1", KeyAlg::Ed25519, "rotation: signing"),
+            ("ed2", KeyAlg::Ed25519, "archived"),
+            ("ed3", KeyAlg::Ed25519, "rotation: backup"),
+            ("x1", KeyAlg::X25519, "rotation: exchange"),
+        ] {
+            let keypair = LocalKey::generate(alg, false).expect("Error creating keypair");
+            conn.insert_key(name, &keypair, Some(metadata), None, None)
+                .await
+                .expect("Error inserting key");
+        }
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+
+        let keys = conn
+            .fetch_all_keys(
+                Some(KeyAlg::Ed25519.as_str()),
+                None,
+                None,
+                None,
+                Some(now + 3600),
+                None,
+                None,
+                false,
+            )
+            .await
+            .expect("Error fetching keys");
+        assert_eq!(keys.len(), 3);
+
+        let keys = conn
+            .fetch_all_keys(None, None, None, None, Some(now - 3600), None, None, false)
+            .await
+            .expect("Error fetching keys");
+        assert!(keys.is_empty());
+
+        let mut keys = conn
+            .fetch_all_keys(
+                Some(KeyAlg::Ed25519.as_str()),
+                None,
+                None,
+                Some(now - 3600),
+                None,
+                Some("rotation"),
+                None,
+                false,
+            )
+            .await
+            .expect("Error fetching keys")
+            .into_iter()
+            .map(|k| k.name().to_string())
+            .collect::<Vec<_>>();
+        keys.sort();
+        assert_eq!(keys, ["ed1", "ed3"]);
+
+        let keys = conn
+            .fetch_all_keys(
+                None,
+                None,
+                None,
+                None,
+                None,
+                Some("rotation"),
+                Some(2),
+                false,
+            )
+            .await
+            .expect("Error fetching keys");
+        assert_eq!(keys.len(), 2);
+        assert!(keys
+            .iter()
+            .all(|k| k.metadata().unwrap().contains("rotation")));
+
+        drop(conn);
+        db.close().await.expect(ERR_CLOSE);
+    })
+}
+
+#[test]
+fn keypair_rotate() {
+    block_on(async {
+        let pass_key = Store::new_raw_key(None).expect(ERR_RAW_KEY);
+        let db = Store::provision(
+            "sqlite://:memory:",
+            StoreKeyMethod::RawKey,
+            pass_key,
+            None,
+            true,
+        )
+        .await
+        .expect(ERR_OPEN);
+
+        let keypair = LocalKey::generate(KeyAlg::Ed25519, false).expect("Error creating keypair");
+        let message = b"test message";
+        let sig = keypair.sign_message(message, None).expect("Error signing");
+
+        let mut conn = db.session(None).await.expect(ERR_SESSION);
+        let tags = [EntryTag::Encrypted(
+            "purpose".to_string(),
+            "issuer".to_string(),
+        )];
+        conn.insert_key("testkey", &keypair, Some("meta"), Some(&tags), None)
+            .await
+            .expect("Error inserting key");
+
+        let rotated = conn
+            .rotate_key("testkey")
+            .await
+            .expect("Error rotating key");
+        assert_eq!(rotated.name(), "testkey");
+        assert_eq!(rotated.version(), 2);
+        assert!(!rotated.is_superseded());
+        assert_eq!(rotated.metadata(), Some("meta"));
+        assert_eq!(rotated.tags_as_slice(), &tags);
+        conn.rotate_key("testkey")
+            .await
+            .expect("Error rotating key");
+
+        let current = conn
+            .fetch_key("testkey", false)
+            .await
+            .expect("Error fetching key")
+            .expect(ERR_REQ_ROW);
+        assert_eq!(current.version(), 3);
+        assert_ne!(
+            current.load_local_key().unwrap().to_public_bytes().unwrap(),
+            keypair.to_public_bytes().unwrap()
+        );
+
+        let first = conn
+            .fetch_key_version("testkey", 1, false)
+            .await
+            .expect("Error fetching key version")
+            .expect(ERR_REQ_ROW);
+        assert_eq!(first.name(), "testkey");
+        assert_eq!(first.version(), 1);
+        assert!(first.is_superseded());
+        assert!(first
+            .load_local_key()
+            .unwrap()
+            .verify_signature(message, &sig, None)
+            .unwrap());
+        assert!(conn
+            .fetch_key_version("testkey", 4, false)
+            .await
+            .expect("Error fetching key version")
+            .is_none());
+
+        let versions = conn
+            .fetch_key_versions("testkey")
+            .await
+            .expect("Error fetching key versions");
+        assert_eq!(
+            versions.iter().map(|k| k.version()).collect::<Vec<_>>(),
+            [1, 2, 3]
+        );
+        assert!(versions[..2].iter().all(|k| k.rotated().is_some()));
+
+        // superseded versions are not listed as current keys
+        let keys = conn
+            .fetch_all_keys(None, None, None, None, None, None, None, false)
+            .await
+            .expect("Error fetching keys");
+        assert_eq!(keys.len(), 1);
+
+        let err = conn
+            .rotate_key("missing")
+            .await
+            .expect_err("Expected not found error");
+        assert_eq!(err.kind(), ErrorKind::NotFound);
+
+        drop(conn);
+        db.close().await.expect(ERR_CLOSE);
+    })
+}
+
+#[test]
+fn keypair_policy() {
+    block_on(async {
+        let pass_key = Store::new_raw_key(None).expect(ERR_RAW_KEY);
+        let db = Store::provision(
+            "sqlite://:memory:",
+            StoreKeyMethod::RawKey,
+            pass_key,
+            None,
+            true,
+        )
+        .await
+        .expect(ERR_OPEN);
+
+        let keypair = LocalKey::generate(KeyAlg::Ed25519, false).expect("Error creating keypair");
+        let message = b"test message";
+        let sig = keypair.sign_message(message, None).expect("Error signing");
+
+        let mut conn = db.session(None).await.expect(ERR_SESSION);
+        conn.insert_key("testkey", &keypair, None, None, None)
+            .await
+            .expect("Error inserting key");
+        let policy = KeyPolicy {
+            operations: Some(vec![KeyOperation::Verify]),
+            algorithms: Some(vec![KeyAlg::X25519.as_str().to_string()]),
+            exportable: false,
+            ..Default::default()
+        };
+        conn.set_key_policy("testkey", Some(&policy))
+            .await
+            .expect("Error setting key policy");
+
+        let entry = conn
+            .fetch_key("testkey", false)
+            .await
+            .expect("Error fetching key")
+            .expect(ERR_REQ_ROW);
+        assert_eq!(entry.policy(), Some(&policy));
+        let key = entry.load_local_key().expect("Error loading key");
+        assert!(key.verify_signature(message, &sig, None).unwrap());
+        let err = key
+            .sign_message(message, None)
+            .expect_err("Expected policy error");
+        assert_eq!(err.kind(), ErrorKind::Unsupported);
+        key.to_secret_bytes().expect_err("Expected policy error");
+        key.to_jwk_secret().expect_err("Expected policy error");
+        let recipient = LocalKey::generate(KeyAlg::X25519, false).expect("Error creating keypair");
+        key.export_wrapped(
+            &recipient.to_jwk_public(None).unwrap(),
+            KeyAlg::Chacha20(Chacha20Types::C20P),
+        )
+        .expect_err("Expected policy error");
+
+        // the policy is inherited by converted keys
+        let x_key = key
+            .convert_key(KeyAlg::X25519)
+            .expect("Error converting key");
+        let peer = LocalKey::generate(KeyAlg::X25519, false).expect("Error creating keypair");
+        x_key
+            .to_key_exchange(KeyAlg::X25519, &peer)
+            .expect_err("Expected policy error");
+        crypto_box_seal_open(&x_key, &crypto_box_seal(&x_key, message).unwrap())
+            .expect_err("Expected policy error");
+        let wrap = LocalKey::generate(KeyAlg::Chacha20(Chacha20Types::C20P), false).unwrap();
+        wrap.wrap_key(&key, &wrap.aead_random_nonce().unwrap())
+            .expect_err("Expected policy error");
+
+        // the policy is carried over when the key is copied or rotated
+        conn.insert_key("copied", &key, None, None, None)
+            .await
+            .expect("Error inserting key");
+        let rotated = conn
+            .rotate_key("testkey")
+            .await
+            .expect("Error rotating key");
+        for entry in [
+            rotated,
+            conn.fetch_key("copied", false)
+                .await
+                .expect("Error fetching key")
+                .expect(ERR_REQ_ROW),
+        ] {
+            assert_eq!(entry.policy(), Some(&policy));
+        }
+
+        conn.set_key_policy("testkey", None)
+            .await
+            .expect("Error removing key policy");
+        let key = conn
+            .fetch_key("testkey", false)
+            .await
+            .expect("Error fetching key")
+            .expect(ERR_REQ_ROW)
+            .load_local_key()
+            .expect("Error loading key");
+        assert!(key.policy().is_none());
+        key.sign_message(message, None).expect("Error signing");
+
+        drop(conn);
+        db.close().await.expect(ERR_CLOSE);
+    })
+}
+
+#[test]
+fn keypair_expiry() {
+    block_on(async {
+        let pass_key = Store::new_raw_key(None).expect(ERR_RAW_KEY);
+        let db = Store::provision(
+            "sqlite://:memory:",
+            StoreKeyMethod::RawKey,
+            pass_key,
+            None,
+            true,
+        )
+        .await
+        .expect(ERR_OPEN);
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+
+        let mut conn = db.session(None).await.expect(ERR_SESSION);
+        for (name, not_before, expires_at) in [
+            ("expired", None, Some(now - 60)),
+            ("expiring", None, Some(now + 3600)),
+            ("pending", Some(now + 3600), None),
+            ("active", None, None),
+        ] {
+            let keypair =
+                LocalKey::generate(KeyAlg::Ed25519, false).expect("Error creating keypair");
+            conn.insert_key(name, &keypair, None, None, None)
+                .await
+                .expect("Error inserting key");
+            conn.set_key_policy(
+                name,
+                Some(&KeyPolicy {
+                    not_before,
+                    expires_at,
+                    ..Default::default()
+                }),
+            )
+            .await
+            .expect("Error setting key policy");
+        }
+
+        for (name, active) in [
+            ("expired", false),
+            ("expiring", true),
+            ("pending", false),
+            ("active", true),
+        ] {
+            let entry = conn
+                .fetch_key(name, false)
+                .await
+                .expect("Error fetching key")
+                .expect(ERR_REQ_ROW);
+            assert_eq!(entry.is_active(), active);
+            let key = entry.load_local_key().expect("Error loading key");
+            let result = key.sign_message(b"test message", None);
+            assert_eq!(result.is_ok(), active);
+            if let Err(err) = result {
+                assert_eq!(err.kind(), ErrorKind::Unsupported);
+            }
+        }
+
+        let mut names = conn
+            .fetch_expiring_keys(now + 7200, None)
+            .await
+            .expect("Error fetching keys")
+            .into_iter()
+            .map(|k| k.name().to_string())
+            .collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(names, ["expired", "expiring"]);
+        let keys = conn
+            .fetch_expiring_keys(now, None)
+            .await
+            .expect("Error fetching keys");
+        assert_eq!(keys.len(), 1);
+        assert_eq!(keys[0].expires_at(), Some(now - 60));
+        // the expiry tag is not exposed as a key tag
+        assert!(keys[0].tags_as_slice().is_empty());
+
+        // the validity window is not carried over to a rotated key
+        let rotated = conn
+            .rotate_key("expiring")
+            .await
+            .expect("Error rotating key");
+        assert_eq!(rotated.expires_at(), None);
+        let keys = conn
+            .fetch_expiring_keys(now + 7200, None)
+            .await
+            .expect("Error fetching keys");
+        assert_eq!(keys.len(), 1);
+
+        drop(conn);
+        db.close().await.expect(ERR_CLOSE);
+    })
+}
+
+#[derive(Debug)]
+struct TestExternalKey {
+    reference: String,
+    key: LocalKey,
+}
+
+impl ExternalKey for TestExternalKey {
+    fn reference(&self) -> &str {
+        &self.reference
+    }
+
+    fn algorithm(&self) -> KeyAlg {
+        self.key.algorithm()
+    }
+
+    fn public_bytes(&self) -> Result<Vec<u8>, Error> {
+        Ok(self.key.to_public_bytes()?.into_vec())
+    }
+
+    fn sign(&self, message: &[u8], sig_type: Option<&str>) -> Result<Vec<u8>, Error> {
+        self.key.sign_message(message, sig_type)
+    }
+
+    fn key_exchange(&self, public: &LocalKey) -> Result<SecretBytes, Error> {
+        Ok(self.key.key_exchange_bytes(public)?)
+    }
+}
+
+#[derive(Debug, Default)]
+struct TestKeyProvider {
+    keys: Mutex<Vec<Arc<TestExternalKey>>>,
+}
+
+impl KeyProvider for TestKeyProvider {
+    fn generate(&self, alg: KeyAlg, label: Option<&str>) -> Result<Arc<dyn ExternalKey>, Error> {
+        let mut keys = self.keys.lock().unwrap();
+        let key = Arc::new(TestExternalKey {
+            reference: format!(
+                "testhsm:object={};id={}",
+                label.unwrap_or_default(),
+                keys.len()
+            ),
+            key: LocalKey::generate(alg, false)?,
+        });
+        keys.push(key.clone());
+        Ok(key)
+    }
+
+    fn load(&self, reference: &str) -> Result<Arc<dyn ExternalKey>, Error> {
+        let keys = self.keys.lock().unwrap();
+        match keys.iter().find(|k| k.reference == reference) {
+            Some(key) => Ok(key.clone()),
+            None => Err(ErrorKind::NotFound.into()),
+        }
+    }
+}
+
+#[test]
+fn keypair_external() {
+    block_on(async {
+        let pass_key = Store::new_raw_key(None).expect(ERR_RAW_KEY);
+        let db = Store::provision(
+            "sqlite://:memory:",
+            StoreKeyMethod::RawKey,
+            pass_key,
+            None,
+            true,
+        )
+        .await
+        .expect(ERR_OPEN);
+
+        let err = LocalKey::generate_external("testhsm", KeyAlg::Ed25519, None)
+            .expect_err("Expected missing provider");
+        assert_eq!(err.kind(), ErrorKind::Unsupported);
+        register_key_provider("testhsm", Arc::new(TestKeyProvider::default()));
+
+        let mut conn = db.session(None).await.expect(ERR_SESSION);
+        for (name, alg) in [("signing", KeyAlg::Ed25519), ("exchange", KeyAlg::X25519)] {
+            let key = LocalKey::generate_external("testhsm", alg, Some(name))
+                .expect("Error generating external key");
+            assert!(key.reference().is_some());
+            conn.insert_key(name, &key, None, None, None)
+                .await
+                .expect("Error inserting key");
+        }
+
+        let entry = conn
+            .fetch_key("signing", false)
+            .await
+            .expect("Error fetching key")
+            .expect(ERR_REQ_ROW);
+        assert!(!entry.is_local());
+        assert_eq!(entry.algorithm(), Some(KeyAlg::Ed25519.as_str()));
+        let key = entry.load_local_key().expect("Error loading key");
+        assert_eq!(key.reference(), Some("testhsm:object=signing;id=0"));
+        let message = b"test message";
+        let sig = key.sign_message(message, None).expect("Error signing");
+        let public =
+            LocalKey::from_public_bytes(KeyAlg::Ed25519, &key.to_public_bytes().unwrap()).unwrap();
+        assert!(public.verify_signature(message, &sig, None).unwrap());
+        key.to_secret_bytes().expect_err("Expected export error");
+        key.to_jwk_secret().expect_err("Expected export error");
+
+        let key = conn
+            .fetch_key("exchange", false)
+            .await
+            .expect("Error fetching key")
+            .expect(ERR_REQ_ROW)
+            .load_local_key()
+            .expect("Error loading key");
+        let peer = LocalKey::generate(KeyAlg::X25519, false).expect("Error creating keypair");
+        let alg = KeyAlg::Chacha20(Chacha20Types::C20P);
+        let shared = key
+            .to_key_exchange(alg, &peer)
+            .expect("Error performing key exchange");
+        let peer_shared = peer
+            .to_key_exchange(alg, &key)
+            .expect("Error performing key exchange");
+        assert_eq!(
+            shared.to_secret_bytes().unwrap(),
+            peer_shared.to_secret_bytes().unwrap()
+        );
+
+        unregister_key_provider("testhsm");
+        let entry = conn
+            .fetch_key("signing", false)
+            .await
+            .expect("Error fetching key")
+            .expect(ERR_REQ_ROW);
+        let err = entry
+            .load_local_key()
+            .expect_err("Expected missing provider");
+        assert_eq!(err.kind(), ErrorKind::Unsupported);
+        let public = entry.load_public_key().expect("Error loading public key");
+        assert_eq!(public.reference(), None);
+        assert!(public.verify_signature(message, &sig, None).unwrap());
+
+        drop(conn);
+        db.close().await.expect(ERR_CLOSE);
+    })
+}
+
+#[derive(Debug, Default)]
+struct TestKeyBackend {
+    keys: Mutex<Vec<(String, Arc<LocalKey>)>>,
+}
+
+impl TestKeyBackend {
+    fn create(&self, alg: KeyAlg) -> (String, Arc<LocalKey>) {
+        let mut keys = self.keys.lock().unwrap();
+        let reference = format!("testkms:key/{}", keys.len());
+        let key = Arc::new(LocalKey::generate(alg, false).unwrap());
+        keys.push((reference.clone(), key.clone()));
+        (reference, key)
+    }
+
+    fn key(&self, reference: &str) -> Result<Arc<LocalKey>, Error> {
+        let keys = self.keys.lock().unwrap();
+        match keys.iter().find(|(r, _)| r == reference) {
+            Some((_, key)) => Ok(key.clone()),
+            None => Err(ErrorKind::NotFound.into()),
+        }
+    }
+}
+
+impl KeyBackend for TestKeyBackend {
+    fn sign<'a>(
+        &'a self,
+        reference: &'a str,
+        message: &'a [u8],
+        sig_type: Option<&'a str>,
+    ) -> BoxFuture<'a, Result<Vec<u8>, Error>> {
+        Box::pin(async move { self.key(reference)?.sign_message(message, sig_type) })
+    }
+
+    fn wrap_key<'a>(
+        &'a self,
+        reference: &'a str,
+        key: &'a LocalKey,
+        nonce: &'a [u8],
+    ) -> BoxFuture<'a, Result<Encrypted, Error>> {
+        Box::pin(async move { self.key(reference)?.wrap_key(key, nonce) })
+    }
+
+    fn unwrap_key<'a>(
+        &'a self,
+        reference: &'a str,
+        alg: KeyAlg,
+        ciphertext: &'a [u8],
+        nonce: &'a [u8],
+    ) -> BoxFuture<'a, Result<LocalKey, Error>> {
+        Box::pin(async move { self.key(reference)?.unwrap_key(alg, ciphertext, nonce) })
+    }
+}
+
+#[test]
+fn keypair_backend() {
+    block_on(async {
+        let pass_key = Store::new_raw_key(None).expect(ERR_RAW_KEY);
+        let db = Store::provision(
+            "sqlite://:memory:",
+            StoreKeyMethod::RawKey,
+            pass_key,
+            None,
+            true,
+        )
+        .await
+        .expect(ERR_OPEN);
+
+        let backend = Arc::new(TestKeyBackend::default());
+        register_key_backend("testkms", backend.clone());
+
+        let mut conn = db.session(None).await.expect(ERR_SESSION);
+        let (sign_ref, sign_key) = backend.create(KeyAlg::Ed25519);
+        let sign_public =
+            LocalKey::from_public_bytes(KeyAlg::Ed25519, &sign_key.to_public_bytes().unwrap())
+                .unwrap();
+        conn.insert_key_reference(
+            "signing",
+            &sign_ref,
+            KeyAlg::Ed25519,
+            Some(&sign_public),
+            None,
+            None,
+            None,
+        )
+        .await
+        .expect("Error inserting key reference");
+        let entry = conn
+            .fetch_key("signing", false)
+            .await
+            .expect("Error fetching key")
+            .expect(ERR_REQ_ROW);
+        assert!(!entry.is_local());
+        assert_eq!(entry.algorithm(), Some(KeyAlg::Ed25519.as_str()));
+
+        let message = b"test message";
+        let sig = conn
+            .sign_message("signing", message, None)
+            .await
+            .expect("Error signing");
+        assert!(sign_public.verify_signature(message, &sig, None).unwrap());
+        let peer = LocalKey::generate(KeyAlg::X25519, false).expect("Error creating keypair");
+        let err = conn
+            .key_exchange("signing", KeyAlg::Chacha20(Chacha20Types::C20P), &peer)
+            .await
+            .expect_err("Expected unsupported key exchange");
+        assert_eq!(err.kind(), ErrorKind::Unsupported);
+
+        let alg = KeyAlg::Chacha20(Chacha20Types::C20P);
+        let (wrap_ref, _) = backend.create(alg);
+        conn.insert_key_reference("wrapping", &wrap_ref, alg, None, None, None, None)
+            .await
+            .expect("Error inserting key reference");
+        let key = LocalKey::generate(KeyAlg::Ed25519, false).expect("Error creating keypair");
+        let nonce = [0u8; 12];
+        let wrapped = conn
+            .wrap_key("wrapping", &key, &nonce)
+            .await
+            .expect("Error wrapping key");
+        let ciphertext = [wrapped.ciphertext(), wrapped.tag()].concat();
+        let unwrapped = conn
+            .unwrap_key("wrapping", KeyAlg::Ed25519, &ciphertext, wrapped.nonce())
+            .await
+            .expect("Error unwrapping key");
+        assert_eq!(
+            unwrapped.to_public_bytes().unwrap(),
+            key.to_public_bytes().unwrap()
+        );
+
+        conn.set_key_policy(
+            "wrapping",
+            Some(&KeyPolicy {
+                operations: Some(vec![KeyOperation::Sign]),
+                ..Default::default()
+            }),
+        )
+        .await
+        .expect("Error setting key policy");
+        let err = conn
+            .wrap_key("wrapping", &key, &nonce)
+            .await
+            .expect_err("Expected policy error");
+        assert_eq!(err.kind(), ErrorKind::Unsupported);
+
+        // keys without a registered backend are used locally
+        let local = LocalKey::generate(KeyAlg::Ed25519, false).expect("Error creating keypair");
+        conn.insert_key("local", &local, None, None, None)
+            .await
+            .expect("Error inserting key");
+        let sig = conn
+            .sign_message("local", message, None)
+            .await
+            .expect("Error signing");
+        assert!(local.verify_signature(message, &sig, None).unwrap());
+
+        drop(conn);
+        db.close().await.expect(ERR_CLOSE);
+    })
+}
+
+#[test]
+fn keypair_attestation() {
+    block_on(async {
+        let pass_key = Store::new_raw_key(None).expect(ERR_RAW_KEY);
+        let db = Store::provision(
+            "sqlite://:memory:",
+            StoreKeyMethod::RawKey,
+            pass_key,
+            None,
+            true,
+        )
+        .await
+        .expect(ERR_OPEN);
+
+        let mut conn = db.session(None).await.expect(ERR_SESSION);
+        let attest_key = LocalKey::generate(KeyAlg::EcCurve(EcCurves::Secp256r1), false)
+            .expect("Error creating keypair");
+        conn.insert_key("attestation", &attest_key, None, None, None)
+            .await
+            .expect("Error inserting key");
+        let key = LocalKey::generate(KeyAlg::Ed25519, false).expect("Error creating keypair");
+        conn.insert_key("subject", &key, None, None, None)
+            .await
+            .expect("Error inserting key");
+        let policy = KeyPolicy {
+            operations: Some(vec![KeyOperation::Sign]),
+            exportable: false,
+            ..Default::default()
+        };
+        conn.set_key_policy("subject", Some(&policy))
+            .await
+            .expect("Error setting key policy");
+
+        let statement = conn
+            .attest_key(
+                "subject",
+                "attestation",
+                AttestationFormat::Jws,
+                Some("challenge"),
+            )
+            .await
+            .expect("Error producing attestation");
+        let statement = String::from_utf8(statement).expect("Invalid JWS");
+        let (input, sig) = statement.rsplit_once('.').expect("Invalid JWS");
+        let sig = URL_SAFE_NO_PAD.decode(sig).expect("Invalid signature");
+        assert!(attest_key
+            .verify_signature(input.as_bytes(), &sig, None)
+            .unwrap());
+        let (header, payload) = input.split_once('.').expect("Invalid JWS");
+        let header: serde_json::Value =
+            serde_json::from_slice(&URL_SAFE_NO_PAD.decode(header).unwrap()).unwrap();
+        assert_eq!(header["alg"], "ES256");
+        assert_eq!(header["kid"], attest_key.to_jwk_thumbprint(None).unwrap());
+        let claims: KeyAttestation =
+            serde_json::from_slice(&URL_SAFE_NO_PAD.decode(payload).unwrap()).unwrap();
+        assert_eq!(claims.name, "subject");
+        assert_eq!(claims.alg.as_deref(), Some(KeyAlg::Ed25519.as_str()));
+        assert_eq!(
+            claims.jwk,
+            Some(serde_json::from_str(&key.to_jwk_public(None).unwrap()).unwrap())
+        );
+        assert!(claims.created.is_some());
+        assert_eq!(claims.storage, KeyStorage::Software);
+        assert_eq!(claims.policy, Some(policy));
+        assert_eq!(claims.nonce.as_deref(), Some("challenge"));
+
+        let statement = conn
+            .attest_key("subject", "attestation", AttestationFormat::Cose, None)
+            .await
+            .expect("Error producing attestation");
+        assert_eq!(statement[0], 0xd2);
+        let serde_cbor::Value::Array(parts) =
+            serde_cbor::from_slice(&statement[1..]).expect("Invalid COSE_Sign1")
+        else {
+            panic!("Expected COSE_Sign1 array");
+        };
+        let [serde_cbor::Value::Bytes(protected), _, serde_cbor::Value::Bytes(payload), serde_cbor::Value::Bytes(sig)] =
+            &parts[..]
+        else {
+            panic!("Invalid COSE_Sign1 structure");
+        };
+        let sig_input = serde_cbor::to_vec(&serde_cbor::Value::Array(vec![
+            serde_cbor::Value::Text("Signature1".to_string()),
+            serde_cbor::Value::Bytes(protected.clone()),
+            serde_cbor::Value::Bytes(vec![]),
+            serde_cbor::Value::Bytes(payload.clone()),
+        ]))
+        .unwrap();
+        assert!(attest_key.verify_signature(&sig_input, sig, None).unwrap());
+        let claims: KeyAttestation = serde_cbor::from_slice(payload).expect("Invalid payload");
+        assert_eq!(claims.name, "subject");
+        assert_eq!(claims.nonce, None);
+
+        // symmetric keys may be attested but not used for attestation
+        let sym = LocalKey::generate(KeyAlg::Chacha20(Chacha20Types::C20P), false)
+            .expect("Error creating key");
+        conn.insert_key("symmetric", &sym, None, None, None)
+            .await
+            .expect("Error inserting key");
+        let statement = conn
+            .attest_key("symmetric", "attestation", AttestationFormat::Cose, None)
+            .await
+            .expect("Error producing attestation");
+        let serde_cbor::Value::Array(parts) = serde_cbor::from_slice(&statement[1..]).unwrap()
+        else {
+            panic!("Expected COSE_Sign1 array");
+        };
+        let serde_cbor::Value::Bytes(payload) = &parts[2] else {
+            panic!("Invalid COSE_Sign1 structure");
+        };
+        let claims: KeyAttestation = serde_cbor::from_slice(payload).unwrap();
+        assert_eq!(claims.jwk, None);
+        let err = conn
+            .attest_key("subject", "symmetric", AttestationFormat::Jws, None)
+            .await
+            .expect_err("Expected unsupported attestation key");
+        assert_eq!(err.kind(), ErrorKind::Unsupported);
+
+        drop(conn);
+        db.close().await.expect(ERR_CLOSE);
+    })
+}
+
+#[test]
+fn keypair_cache() {
+    block_on(async {
+        let pass_key = Store::new_raw_key(None).expect(ERR_RAW_KEY);
+        let db = Store::provision(
+            "sqlite://:memory:",
+            StoreKeyMethod::RawKey,
+            pass_key,
+            None,
+            true,
+        )
+        .await
+        .expect(ERR_OPEN);
+
+        let keypair = LocalKey::generate(KeyAlg::X25519, false).expect("Error creating keypair");
+        let mut conn = db.session(None).await.expect(ERR_SESSION);
+        conn.insert_key("testkey", &keypair, None, None, None)
+            .await
+            .expect("Error inserting key");
+        assert!(conn
+            .load_key("missing")
+            .await
+            .expect("Error loading key")
+            .is_none());
+
+        // the cache is disabled by default
+        let first = conn
+            .load_key("testkey")
+            .await
+            .expect("Error loading key")
+            .expect(ERR_REQ_ROW);
+        let second = conn
+            .load_key("testkey")
+            .await
+            .expect("Error loading key")
+            .expect(ERR_REQ_ROW);
+        assert!(!Arc::ptr_eq(&first, &second));
+        assert_eq!(
+            first.to_jwk_thumbprint(None).unwrap(),
+            keypair.to_jwk_thumbprint(None).unwrap()
+        );
+        drop(conn);
+
+        // loaded keys are shared between sessions
+        db.set_key_cache(Some(Duration::from_secs(60)));
+        let mut conn = db.session(None).await.expect(ERR_SESSION);
+        let first = conn
+            .load_key("testkey")
+            .await
+            .expect("Error loading key")
+            .expect(ERR_REQ_ROW);
+        drop(conn);
+        let mut conn = db.session(None).await.expect(ERR_SESSION);
+        let second = conn
+            .load_key("testkey")
+            .await
+            .expect("Error loading key")
+            .expect(ERR_REQ_ROW);
+        assert!(Arc::ptr_eq(&first, &second));
+
+        // modifying the key entry evicts the key
+        conn.update_key("testkey", Some("meta"), None, None)
+            .await
+            .expect("Error updating key");
+        let third = conn
+            .load_key("testkey")
+            .await
+            .expect("Error loading key")
+            .expect(ERR_REQ_ROW);
+        assert!(!Arc::ptr_eq(&second, &third));
+
+        db.purge_key_cache();
+        let fourth = conn
+            .load_key("testkey")
+            .await
+            .expect("Error loading key")
+            .expect(ERR_REQ_ROW);
+        assert!(!Arc::ptr_eq(&third, &fourth));
+
+        conn.remove_key("testkey")
+            .await
+            .expect("Error removing key");
+        assert!(conn
+            .load_key("testkey")
+            .await
+            .expect("Error loading key")
+            .is_none());
+
+        // keys expire after the time-to-live
+        conn.insert_key("testkey", &keypair, None, None, None)
+            .await
+            .expect("Error inserting key");
+        db.set_key_cache(Some(Duration::from_millis(50)));
+        let first = conn
+            .load_key("testkey")
+            .await
+            .expect("Error loading key")
+            .expect(ERR_REQ_ROW);
+        std::thread::sleep(Duration::from_millis(100));
+        let second = conn
+            .load_key("testkey")
+            .await
+            .expect("Error loading key")
+            .expect(ERR_REQ_ROW);
+        assert!(!Arc::ptr_eq(&first, &second));
+
+        drop(conn);
+        db.close().await.expect(ERR_CLOSE);
+    })
+}
+
+#[test]
+fn keypair_session_cache() {
+    block_on(async {
+        let pass_key = Store::new_raw_key(None).expect(ERR_RAW_KEY);
+        let db = Store::provision(
+            "sqlite://:memory:",
+            StoreKeyMethod::RawKey,
+            pass_key,
+            None,
+            true,
+        )
+        .await
+        .expect(ERR_OPEN);
+
+        let keypair = LocalKey::generate(KeyAlg::Ed25519, false).expect("Error creating keypair");
+        let message = b"test message";
+        let mut conn = db.session(None).await.expect(ERR_SESSION);
+        conn.insert_key("testkey", &keypair, None, None, None)
+            .await
+            .expect("Error inserting key");
+        let sig = conn
+            .sign_message("testkey", message, None)
+            .await
+            .expect("Error signing");
+        assert!(keypair.verify_signature(message, &sig, None).unwrap());
+
+        // the loaded key is retained by the session
+        let policy = KeyPolicy {
+            operations: Some(vec![KeyOperation::Verify]),
+            ..Default::default()
+        };
+        let mut other = db.session(None).await.expect(ERR_SESSION);
+        other
+            .set_key_policy("testkey", Some(&policy))
+            .await
+            .expect("Error setting key policy");
+        drop(other);
+        conn.sign_message("testkey", message, None)
+            .await
+            .expect("Error signing");
+
+        conn.clear_loaded_keys();
+        let err = conn
+            .sign_message("testkey", message, None)
+            .await
+            .expect_err("Expected policy error");
+        assert_eq!(err.kind(), ErrorKind::Unsupported);
+
+        // modifying the key entry within the session evicts the key
+        conn.set_key_policy("testkey", None)
+            .await
+            .expect("Error setting key policy");
+        conn.sign_message("testkey", message, None)
+            .await
+            .expect("Error signing");
+        conn.remove_key("testkey")
+            .await
+            .expect("Error removing key");
+        let err = conn
+            .sign_message("testkey", message, None)
+            .await
+            .expect_err("Expected missing key");
+        assert_eq!(err.kind(), ErrorKind::NotFound);
+
+        drop(conn);
+        db.close().await.expect(ERR_CLOSE);
+    })
+}
+
+#[test]
+fn keypair_json_metadata() {
+    block_on(async {
+        let pass_key = Store::new_raw_key(None).expect(ERR_RAW_KEY);
+        let db = Store::provision(
+            "sqlite://:memory:",
+            StoreKeyMethod::RawKey,
+            pass_key,
+            None,
+            true,
+        )
+        .await
+        .expect(ERR_OPEN);
+
+        let mut conn = db.session(None).await.expect(ERR_SESSION);
+        for (name, purpose) in [("auth1", "auth"), ("auth2", "auth"), ("assert1", "assert")] {
+            let keypair =
+                LocalKey::generate(KeyAlg::Ed25519, false).expect("Error creating keypair");
+            conn.insert_key(
+                name,
+                &keypair,
+                None,
+                Some(&[EntryTag::Encrypted(
+                    "purpose".to_string(),
+                    "user".to_string(),
+                )]),
+                None,
+            )
+            .await
+            .expect("Error inserting key");
+            let metadata = serde_json::json!({
+                "purpose": purpose,
+                "controller": "did:example:123",
+                "rotation": {"days": 90},
+            });
+            conn.set_key_json_metadata(name, Some(&metadata), &["purpose", "controller"])
+                .await
+                .expect("Error setting key metadata");
+        }
+
+        let found = conn
+            .fetch_key("auth1", false)
+            .await
+            .expect("Error fetching key")
+            .expect(ERR_REQ_ROW);
+        assert_eq!(
+            found.json_metadata().unwrap()["rotation"],
+            serde_json::json!({"days": 90})
+        );
+        // indexed fields are not returned as user tags
+        assert_eq!(
+            found.tags_as_slice(),
+            &[EntryTag::Encrypted(
+                "purpose".to_string(),
+                "user".to_string()
+            )]
+        );
+
+        let select = |purpose: &str| {
+            Some(TagFilter::all_of(vec![
+                TagFilter::is_eq("meta:purpose", purpose),
+                TagFilter::is_eq("meta:controller", "did:example:123"),
+            ]))
+        };
+        let keys = conn
+            .fetch_all_keys(None, None, select("auth"), None, None, None, None, false)
+            .await
+            .expect("Error fetching keys");
+        let mut names: Vec<&str> = keys.iter().map(|k| k.name()).collect();
+        names.sort();
+        assert_eq!(names, ["auth1", "auth2"]);
+        // user tags are queried separately
+        let keys = conn
+            .fetch_all_keys(
+                None,
+                None,
+                Some(TagFilter::is_eq("purpose", "user")),
+                None,
+                None,
+                None,
+                None,
+                false,
+            )
+            .await
+            .expect("Error fetching keys");
+        assert_eq!(keys.len(), 3);
+
+        // indexed fields are preserved on rotation
+        conn.rotate_key("assert1")
+            .await
+            .expect("Error rotating key");
+        let keys = conn
+            .fetch_all_keys(None, None, select("assert"), None, None, None, None, false)
+            .await
+            .expect("Error fetching keys");
+        assert_eq!(keys.len(), 1);
+        assert!(keys[0].json_metadata().is_some());
+
+        // nested fields may not be indexed
+        let err = conn
+            .set_key_json_metadata(
+                "auth1",
+                Some(&serde_json::json!({"rotation": {"days": 90}})),
+                &["rotation"],
+            )
+            .await
+            .expect_err("Expected unsupported indexed field");
+        assert_eq!(err.kind(), ErrorKind::Input);
+
+        conn.set_key_json_metadata("auth2", None, &[])
+            .await
+            .expect("Error removing key metadata");
+        let keys = conn
+            .fetch_all_keys(None, None, select("auth"), None, None, None, None, false)
+            .await
+            .expect("Error fetching keys");
+        assert_eq!(keys.len(), 1);
+        assert_eq!(keys[0].name(), "auth1");
+
+        drop(conn);
+        db.close().await.expect(ERR_CLOSE);
+    })
+}
+
+#[test]
+fn keypair_thumbprint_name() {
+    block_on(async {
+        let pass_key = Store::new_raw_key(None).expect(ERR_RAW_KEY);
+        let db = Store::provision(
+            "sqlite://:memory:",
+            StoreKeyMethod::RawKey,
+            pass_key,
+            None,
+            true,
+        )
+        .await
+        .expect(ERR_OPEN);
+
+        let keypair = LocalKey::generate(KeyAlg::Ed25519, false).expect("Error creating keypair");
+        let thumbprint = keypair.to_jwk_thumbprint(None).unwrap();
+
+        let mut conn = db.session(None).await.expect(ERR_SESSION);
+        let name = conn
+            .insert_key_by_thumbprint(&keypair, Some("meta"), None, None)
+            .await
+            .expect("Error inserting key");
+        assert_eq!(name, thumbprint);
+
+        let found = conn
+            .fetch_key_by_thumbprint(&thumbprint, false)
+            .await
+            .expect("Error fetching key")
+            .expect(ERR_REQ_ROW);
+        assert_eq!(found.name(), thumbprint);
+        assert_eq!(found.metadata(), Some("meta"));
+        assert!(conn
+            .fetch_key_by_thumbprint("missing", false)
+            .await
+            .expect("Error fetching key")
+            .is_none());
+
+        let err = conn
+            .insert_key_by_thumbprint(&keypair, None, None, None)
+            .await
+            .expect_err("Expected duplicate key");
+        assert_eq!(err.kind(), ErrorKind::Duplicate);
+
+        // the same key already stored under another name is also a duplicate
+        let other = LocalKey::generate(KeyAlg::Ed25519, false).expect("Error creating keypair");
+        conn.insert_key("other", &other, None, None, None)
+            .await
+            .expect("Error inserting key");
+        let err = conn
+            .insert_key_by_thumbprint(&other, None, None, None)
+            .await
+            .expect_err("Expected duplicate key");
+        assert_eq!(err.kind(), ErrorKind::Duplicate);
+        let found = conn
+            .fetch_key_by_thumbprint(&other.to_jwk_thumbprint(None).unwrap(), false)
+            .await
+            .expect("Error fetching key")
+            .expect(ERR_REQ_ROW);
+        assert_eq!(found.name(), "other");
+
+        drop(conn);
+        db.close().await.expect(ERR_CLOSE);
+    })
+}
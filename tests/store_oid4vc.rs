@@ -0,0 +1,94 @@
+use aries_askar::{
+    entry::TagFilter,
+    future::block_on,
+    oid4vc::{expires_before, IssuanceSessionRecord, IssuanceState, MdocRecord, STATE_TAG},
+    ErrorKind,
+};
+
+mod utils;
+
+use self::utils::{provision, ERR_CLOSE, ERR_SESSION};
+
+#[test]
+fn typed_records() {
+    block_on(async {
+        let db = provision().await;
+        let mut conn = db.session(None).await.expect(ERR_SESSION);
+
+        let mdocs = [("mdl", 1000), ("pid", 3000)].map(|(id, expires)| MdocRecord {
+            id: id.to_string(),
+            doc_type: format!("org.iso.18013.5.1.{}", id),
+            issuer_signed: "omppc3N1ZXJBdXRo".to_string(),
+            issuer: Some("https://issuer.example.com".to_string()),
+            device_key: None,
+            expires_at: Some(expires),
+        });
+        for mdoc in &mdocs {
+            conn.insert_typed(mdoc, None)
+                .await
+                .expect("Error inserting record");
+        }
+        let err = conn
+            .insert_typed(&mdocs[0], None)
+            .await
+            .expect_err("Expected duplicate error");
+        assert_eq!(err.kind(), ErrorKind::Duplicate);
+        assert_eq!(
+            conn.fetch_typed::<MdocRecord>("pid", false)
+                .await
+                .expect("Error fetching record"),
+            Some(mdocs[1].clone())
+        );
+        let expiring = conn
+            .fetch_all_typed::<MdocRecord>(Some(expires_before(2000)), None, false)
+            .await
+            .expect("Error fetching records");
+        assert_eq!(expiring, [mdocs[0].clone()]);
+
+        let mut session = IssuanceSessionRecord {
+            id: "session".to_string(),
+            state: IssuanceState::Offered,
+            credential_issuer: "https://issuer.example.com".to_string(),
+            credential_configuration_ids: vec!["pid".to_string()],
+            pre_authorized_code: Some("code".to_string()),
+            issuer_state: None,
+            code_verifier: None,
+            access_token: None,
+            c_nonce: None,
+            transaction_id: None,
+            credential_ids: Vec::new(),
+            created_at: 1000,
+        };
+        conn.insert_typed(&session, None)
+            .await
+            .expect("Error inserting record");
+        session.state = IssuanceState::Completed;
+        session.credential_ids.push("pid".to_string());
+        conn.replace_typed(&session, None)
+            .await
+            .expect("Error replacing record");
+        let found = conn
+            .fetch_all_typed::<IssuanceSessionRecord>(
+                Some(TagFilter::is_eq(STATE_TAG, "completed")),
+                None,
+                false,
+            )
+            .await
+            .expect("Error fetching records");
+        assert_eq!(found, [session]);
+
+        conn.remove_typed::<MdocRecord>("mdl")
+            .await
+            .expect("Error removing record");
+        assert_eq!(
+            conn.fetch_all_typed::<MdocRecord>(None, None, false)
+                .await
+                .expect("Error fetching records")
+                .len(),
+            1
+        );
+        drop(conn);
+
+        db.close().await.expect(ERR_CLOSE);
+    })
+}
@@ -0,0 +1,124 @@
+use std::collections::BTreeMap;
+
+use aries_askar::{
+    entry::{EntryTag, TagFilter},
+    future::block_on,
+};
+use futures_lite::stream::StreamExt;
+
+mod utils;
+
+use self::utils::{provision, ERR_CLOSE, ERR_SESSION};
+
+const ERR_PROFILE: &str = "Error creating profile";
+const ERR_INSERT: &str = "Error inserting test row";
+const ERR_SCAN: &str = "Error scanning profiles";
+
+#[test]
+fn scan_profiles() {
+    block_on(async {
+        let db = provision().await;
+
+        let default_profile = db.get_active_profile();
+        let mut profiles = vec![default_profile.clone()];
+        for _ in 0..4 {
+            profiles.push(db.create_profile(None).await.expect(ERR_PROFILE));
+        }
+        for (idx, profile) in profiles.iter().enumerate() {
+            let mut conn = db.session(Some(profile.clone())).await.expect(ERR_SESSION);
+            for row in 0..=idx {
+                let tags = [EntryTag::Plaintext(
+                    "even".to_string(),
+                    (row % 2 == 0).to_string(),
+                )];
+                conn.insert(
+                    "report",
+                    &format!("row{row}"),
+                    profile.as_bytes(),
+                    Some(&tags),
+                    None,
+                )
+                .await
+                .expect(ERR_INSERT);
+            }
+            conn.insert("other", "row", b"other", None, None)
+                .await
+                .expect(ERR_INSERT);
+        }
+
+        // all profiles of the store are scanned
+        let mut scan = db
+            .scan_profiles(None, Some("report".to_string()), None, 2)
+            .await
+            .expect(ERR_SCAN);
+        assert_eq!(scan.remaining_profiles(), profiles.len());
+        let mut counts = BTreeMap::new();
+        while let Some(row) = scan.next().await {
+            let (profile, entry) = row.expect(ERR_SCAN);
+            assert_eq!(entry.category, "report");
+            assert_eq!(entry.value, profile.as_bytes());
+            *counts.entry(profile).or_insert(0) += 1;
+        }
+        assert_eq!(scan.remaining_profiles(), 0);
+        let expected: BTreeMap<String, usize> = profiles
+            .iter()
+            .enumerate()
+            .map(|(idx, profile)| (profile.clone(), idx + 1))
+            .collect();
+        assert_eq!(counts, expected);
+
+        // selected profiles, with a tag filter
+        let selected = vec![profiles[2].clone(), profiles[4].clone()];
+        let rows: Vec<_> = db
+            .scan_profiles(
+                Some(selected),
+                Some("report".to_string()),
+                Some(TagFilter::is_eq("~even", "true")),
+                8,
+            )
+            .await
+            .expect(ERR_SCAN)
+            .try_collect::<_, _, Vec<_>>()
+            .await
+            .expect(ERR_SCAN);
+        let mut names: Vec<(String, String)> = rows
+            .into_iter()
+            .map(|(profile, entry)| (profile, entry.name))
+            .collect();
+        names.sort();
+        let mut expected = vec![
+            (profiles[2].clone(), "row0".to_string()),
+            (profiles[2].clone(), "row2".to_string()),
+            (profiles[4].clone(), "row0".to_string()),
+            (profiles[4].clone(), "row2".to_string()),
+            (profiles[4].clone(), "row4".to_string()),
+        ];
+        expected.sort();
+        assert_eq!(names, expected);
+
+        // a missing profile produces an error, while other profiles are scanned
+        let mut scan = db
+            .scan_profiles(
+                Some(vec!["missing".to_string(), default_profile.clone()]),
+                Some("report".to_string()),
+                None,
+                1,
+            )
+            .await
+            .expect(ERR_SCAN);
+        let mut errors = 0;
+        let mut found = 0;
+        while let Some(row) = scan.next().await {
+            match row {
+                Ok((profile, _)) => {
+                    assert_eq!(profile, default_profile);
+                    found += 1;
+                }
+                Err(_) => errors += 1,
+            }
+        }
+        assert_eq!((errors, found), (1, 1));
+
+        db.close().await.expect(ERR_CLOSE);
+    })
+}
@@ -0,0 +1,125 @@
+use aries_askar::future::block_on;
+
+mod utils;
+
+use self::utils::{provision, ERR_CLOSE, ERR_SESSION};
+
+const ERR_ENQUEUE: &str = "Error queueing message";
+
+const ALICE: &str = "did:example:alice#key-1";
+const BOB: &str = "did:example:bob#key-1";
+
+#[test]
+fn message_queue() {
+    block_on(async {
+        let db = provision().await;
+        let mut conn = db.session(None).await.expect(ERR_SESSION);
+        let mut ids = Vec::new();
+        for msg in [&b"first"[..], b"second", b"third"] {
+            ids.push(
+                conn.enqueue_message(ALICE, msg, None)
+                    .await
+                    .expect(ERR_ENQUEUE),
+            );
+        }
+        conn.enqueue_message(BOB, b"other", None)
+            .await
+            .expect(ERR_ENQUEUE);
+        assert_eq!(conn.count_messages(ALICE).await.unwrap(), 3);
+        assert_eq!(conn.count_messages(BOB).await.unwrap(), 1);
+
+        let peeked = conn
+            .peek_messages(ALICE, None)
+            .await
+            .expect("Error fetching messages");
+        assert_eq!(peeked.iter().map(|m| m.id.clone()).collect::<Vec<_>>(), ids);
+        assert_eq!(peeked[0].recipient, ALICE);
+        assert_eq!(peeked[0].message, b"first");
+
+        let dequeued = conn
+            .dequeue_messages(ALICE, Some(2))
+            .await
+            .expect("Error dequeuing messages");
+        assert_eq!(dequeued, peeked[..2]);
+        assert_eq!(conn.count_messages(ALICE).await.unwrap(), 1);
+
+        // acknowledged messages are removed, and unknown identifiers ignored
+        let removed = conn
+            .ack_messages(ALICE, &[&ids[0], &ids[2]])
+            .await
+            .expect("Error acknowledging messages");
+        assert_eq!(removed, 1);
+        assert!(conn
+            .dequeue_messages(ALICE, None)
+            .await
+            .expect("Error dequeuing messages")
+            .is_empty());
+        assert_eq!(conn.count_messages(BOB).await.unwrap(), 1);
+        drop(conn);
+
+        db.close().await.expect(ERR_CLOSE);
+    })
+}
+
+#[test]
+fn message_queue_concurrent_dequeue() {
+    block_on(async {
+        let db = provision().await;
+        let mut conn = db.session(None).await.expect(ERR_SESSION);
+        for _ in 0..4 {
+            conn.enqueue_message(ALICE, b"msg", None)
+                .await
+                .expect(ERR_ENQUEUE);
+        }
+        drop(conn);
+
+        let mut first = db.session(None).await.expect(ERR_SESSION);
+        let mut second = db.session(None).await.expect(ERR_SESSION);
+        let (a, b) = futures_lite::future::zip(
+            first.dequeue_messages(ALICE, None),
+            second.dequeue_messages(ALICE, None),
+        )
+        .await;
+        let (a, b) = (
+            a.expect("Error dequeuing messages"),
+            b.expect("Error dequeuing messages"),
+        );
+        assert_eq!(a.len() + b.len(), 4);
+        assert!(a.iter().all(|m| !b.contains(m)));
+        drop(first);
+        drop(second);
+
+        db.close().await.expect(ERR_CLOSE);
+    })
+}
+
+#[test]
+fn message_queue_subscribe() {
+    block_on(async {
+        let db = provision().await;
+        let mut sub = db
+            .subscribe_messages(None, Some(ALICE.to_string()))
+            .await
+            .expect("Error subscribing to messages");
+        let mut conn = db.session(None).await.expect(ERR_SESSION);
+        conn.enqueue_message(BOB, b"other", None)
+            .await
+            .expect(ERR_ENQUEUE);
+        let id = conn
+            .enqueue_message(ALICE, b"msg", None)
+            .await
+            .expect(ERR_ENQUEUE);
+        drop(conn);
+
+        let notice = sub
+            .next_message()
+            .await
+            .expect("Error receiving notification")
+            .expect("Expected notification");
+        assert_eq!(notice.recipient, ALICE);
+        assert_eq!(notice.id, id);
+        drop(sub);
+
+        db.close().await.expect(ERR_CLOSE);
+    })
+}
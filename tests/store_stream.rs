@@ -0,0 +1,79 @@
+use aries_askar::{entry::EntryTag, future::block_on, ErrorKind, Store, StoreKeyMethod};
+
+mod utils;
+
+use self::utils::{ERR_CLOSE, ERR_OPEN, ERR_RAW_KEY, ERR_SESSION};
+
+#[test]
+fn store_stream() {
+    block_on(async {
+        let pass_key = Store::new_raw_key(None).expect(ERR_RAW_KEY);
+        let db = Store::provision(
+            "sqlite://:memory:",
+            StoreKeyMethod::RawKey,
+            pass_key,
+            None,
+            true,
+        )
+        .await
+        .expect(ERR_OPEN);
+
+        // spans several chunks, with a partial final chunk
+        let value: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+        let tags = [EntryTag::Plaintext("kind".to_string(), "tails".to_string())];
+
+        let mut conn = db.session(None).await.expect(ERR_SESSION);
+        let length = conn
+            .insert_stream("testcat", "testrow", value.as_slice(), Some(&tags), None)
+            .await
+            .expect("Error inserting stream");
+        assert_eq!(length, value.len() as u64);
+
+        let mut output = Vec::new();
+        let length = conn
+            .fetch_stream("testcat", "testrow", &mut output)
+            .await
+            .expect("Error fetching stream");
+        assert_eq!(length, Some(value.len() as u64));
+        assert_eq!(output, value);
+
+        let entry = conn
+            .fetch("testcat", "testrow", false)
+            .await
+            .expect("Error fetching row")
+            .expect("Expected row");
+        assert_eq!(entry.tags, tags);
+        // the stored value only describes the chunks
+        assert!(entry.value.len() < 1024);
+
+        // empty values are supported
+        conn.insert_stream("testcat", "empty", &b""[..], None, None)
+            .await
+            .expect("Error inserting stream");
+        let mut output = Vec::new();
+        let length = conn
+            .fetch_stream("testcat", "empty", &mut output)
+            .await
+            .expect("Error fetching stream");
+        assert_eq!(length, Some(0));
+        assert!(output.is_empty());
+
+        let length = conn
+            .fetch_stream("testcat", "missing", Vec::new())
+            .await
+            .expect("Error fetching stream");
+        assert_eq!(length, None);
+
+        conn.insert("testcat", "plain", b"testval", None, None)
+            .await
+            .expect("Error inserting row");
+        let err = conn
+            .fetch_stream("testcat", "plain", Vec::new())
+            .await
+            .expect_err("Expected error");
+        assert_eq!(err.kind(), ErrorKind::Input);
+        drop(conn);
+
+        db.close().await.expect(ERR_CLOSE);
+    })
+}
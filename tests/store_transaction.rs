@@ -0,0 +1,67 @@
+use aries_askar::{future::block_on, Error, ErrorKind, Store, StoreKeyMethod};
+
+mod utils;
+
+use self::utils::{ERR_CLOSE, ERR_OPEN, ERR_RAW_KEY, ERR_SESSION};
+
+#[test]
+fn run_transaction_retry() {
+    block_on(async {
+        let pass_key = Store::new_raw_key(None).expect(ERR_RAW_KEY);
+        let db = Store::provision(
+            "sqlite://:memory:",
+            StoreKeyMethod::RawKey,
+            pass_key,
+            None,
+            true,
+        )
+        .await
+        .expect(ERR_OPEN);
+
+        // a busy error rolls back the transaction and runs it again
+        let mut attempts = 0;
+        let result = db
+            .run_transaction(None, |txn| {
+                attempts += 1;
+                let attempt = attempts;
+                Box::pin(async move {
+                    txn.insert("testcat", "testrow", b"testval", None, None)
+                        .await?;
+                    if attempt == 1 {
+                        Err(Error::from(ErrorKind::Busy))
+                    } else {
+                        Ok(attempt)
+                    }
+                })
+            })
+            .await
+            .expect("Error running transaction");
+        assert_eq!(result, 2);
+        assert_eq!(attempts, 2);
+
+        let mut conn = db.session(None).await.expect(ERR_SESSION);
+        let count = conn
+            .count(Some("testcat"), None)
+            .await
+            .expect("Error counting rows");
+        assert_eq!(count, 1);
+        drop(conn);
+
+        // other errors are returned without retrying
+        let mut attempts = 0;
+        let err = db
+            .run_transaction(None, |txn| {
+                attempts += 1;
+                Box::pin(async move {
+                    txn.insert("testcat", "testrow", b"testval", None, None)
+                        .await
+                })
+            })
+            .await
+            .expect_err("Expected duplicate error");
+        assert_eq!(err.kind(), ErrorKind::Duplicate);
+        assert_eq!(attempts, 1);
+
+        db.close().await.expect(ERR_CLOSE);
+    })
+}
@@ -0,0 +1,153 @@
+use aries_askar::{
+    entry::EntryTag,
+    export::{ExportKey, ImportConflict},
+    future::block_on,
+    kms::{KeyAlg, LocalKey},
+    ErrorKind,
+};
+
+mod utils;
+
+use self::utils::{provision, ERR_CLOSE, ERR_SESSION};
+
+const CREDENTIAL: &[u8] =
+    br#"{"@context":["https://www.w3.org/2018/credentials/v1"],"id":"urn:uuid:cred","type":["VerifiableCredential"]}"#;
+
+#[test]
+fn universal_wallet_export() {
+    block_on(async {
+        let db = provision().await;
+        let keypair = LocalKey::generate(KeyAlg::Ed25519, false).expect("Error creating keypair");
+        let mut conn = db.session(None).await.expect(ERR_SESSION);
+        conn.insert_key("testkey", &keypair, None, None, None)
+            .await
+            .expect("Error inserting key");
+        conn.insert("w3c_credential", "urn:uuid:cred", CREDENTIAL, None, None)
+            .await
+            .expect("Error inserting row");
+        conn.insert(
+            "testcat",
+            "testrow",
+            b"testval",
+            Some(&[EntryTag::Plaintext("plain".to_string(), "a".to_string())]),
+            None,
+        )
+        .await
+        .expect("Error inserting row");
+        drop(conn);
+
+        let wallet = db
+            .export_universal_wallet(None, None)
+            .await
+            .expect("Error exporting wallet");
+        let document: serde_json::Value =
+            serde_json::from_str(&wallet).expect("Error parsing wallet");
+        assert_eq!(document["type"], "UniversalWallet2020");
+        assert_eq!(document["status"], "UNLOCKED");
+        let mut types: Vec<&str> = document["contents"]
+            .as_array()
+            .expect("Expected wallet contents")
+            .iter()
+            .map(|content| match &content["type"] {
+                serde_json::Value::Array(types) => types[0].as_str().unwrap(),
+                ty => ty.as_str().unwrap(),
+            })
+            .collect();
+        types.sort_unstable();
+        assert_eq!(
+            types,
+            ["AskarRecord", "JsonWebKey2020", "VerifiableCredential"]
+        );
+
+        let locked = db
+            .export_universal_wallet(Some(ExportKey::Passphrase("passphrase")), None)
+            .await
+            .expect("Error exporting wallet");
+        assert!(!locked.contains("testval"));
+
+        let signing_key =
+            LocalKey::generate(KeyAlg::Ed25519, false).expect("Error creating signing key");
+        let err = db
+            .export_universal_wallet(Some(ExportKey::Recipient(&signing_key)), None)
+            .await
+            .expect_err("Expected error for unsupported recipient key");
+        assert_eq!(err.kind(), ErrorKind::Input);
+
+        let target = provision().await;
+        target
+            .import_universal_wallet(
+                &locked,
+                Some(ExportKey::Passphrase("passphrase")),
+                ImportConflict::Fail,
+                None,
+            )
+            .await
+            .expect("Error importing wallet");
+        let mut conn = target.session(None).await.expect(ERR_SESSION);
+        let loaded = conn
+            .fetch_key("testkey", false)
+            .await
+            .expect("Error fetching key")
+            .expect("Expected imported key")
+            .load_local_key()
+            .expect("Error loading key");
+        assert_eq!(
+            loaded.to_public_bytes().unwrap(),
+            keypair.to_public_bytes().unwrap()
+        );
+        let cred = conn
+            .fetch("w3c_credential", "urn:uuid:cred", false)
+            .await
+            .expect("Error fetching row")
+            .expect("Expected imported credential");
+        assert_eq!(
+            serde_json::from_slice::<serde_json::Value>(&cred.value).unwrap(),
+            serde_json::from_slice::<serde_json::Value>(CREDENTIAL).unwrap()
+        );
+        let row = conn
+            .fetch("testcat", "testrow", false)
+            .await
+            .expect("Error fetching row")
+            .expect("Expected imported row");
+        assert_eq!(row.value, &b"testval"[..]);
+        assert_eq!(
+            row.tags,
+            vec![EntryTag::Plaintext("plain".to_string(), "a".to_string())]
+        );
+        drop(conn);
+
+        let err = target
+            .import_universal_wallet(
+                &locked,
+                Some(ExportKey::Passphrase("passphrase")),
+                ImportConflict::Fail,
+                None,
+            )
+            .await
+            .expect_err("Expected duplicate error");
+        assert_eq!(err.kind(), ErrorKind::Duplicate);
+        target
+            .import_universal_wallet(
+                &locked,
+                Some(ExportKey::Passphrase("passphrase")),
+                ImportConflict::Overwrite,
+                None,
+            )
+            .await
+            .expect("Error importing wallet");
+
+        let err = target
+            .import_universal_wallet(
+                &locked,
+                Some(ExportKey::Passphrase("other")),
+                ImportConflict::Skip,
+                None,
+            )
+            .await
+            .expect_err("Expected error for incorrect passphrase");
+        assert_eq!(err.kind(), ErrorKind::Encryption);
+
+        target.close().await.expect(ERR_CLOSE);
+        db.close().await.expect(ERR_CLOSE);
+    })
+}
@@ -0,0 +1,22 @@
+#![allow(dead_code)]
+
+use aries_askar::{Store, StoreKeyMethod};
+
+pub const ERR_RAW_KEY: &str = "Error creating raw store key";
+pub const ERR_SESSION: &str = "Error creating store session";
+pub const ERR_OPEN: &str = "Error opening test store instance";
+pub const ERR_CLOSE: &str = "Error closing test store instance";
+
+/// Provision an in-memory SQLite store with a new raw key
+pub async fn provision() -> Store {
+    let pass_key = Store::new_raw_key(None).expect(ERR_RAW_KEY);
+    Store::provision(
+        "sqlite://:memory:",
+        StoreKeyMethod::RawKey,
+        pass_key,
+        None,
+        true,
+    )
+    .await
+    .expect(ERR_OPEN)
+}
@@ -65,7 +65,9 @@ import type {
   SessionStartOptions,
   SessionUpdateKeyOptions,
   SessionUpdateOptions,
+  SetCryptoOffloadOptions,
   SetCustomLoggerOptions,
+  SetLogFilterOptions,
   SetMaxLogLevelOptions,
   StoreCloseOptions,
   StoreCopyToOptions,
@@ -267,6 +269,20 @@ export class NodeJSAriesAskar implements AriesAskar {
     this.handleError(errorCode)
   }
 
+  public setLogFilter(options: SetLogFilterOptions): void {
+    const { filter } = serializeArguments(options)
+
+    const errorCode = this.nativeAriesAskar.askar_set_log_filter(filter)
+    this.handleError(errorCode)
+  }
+
+  public setCryptoOffload(options: SetCryptoOffloadOptions): void {
+    const { mode, threads } = serializeArguments(options)
+
+    const errorCode = this.nativeAriesAskar.askar_set_crypto_offload(mode, +threads || 0)
+    this.handleError(errorCode)
+  }
+
   public entryListCount(options: EntryListCountOptions): number {
     const { entryListHandle } = serializeArguments(options)
     const ret = allocateInt32Buffer()
@@ -36,6 +36,8 @@ export const nativeBindings = {
   askar_set_custom_logger: [FFI_ERROR_CODE, [FFI_INT32, FFI_CALLBACK_PTR, FFI_INT32, FFI_INT32, FFI_INT32]],
   askar_set_default_logger: [FFI_ERROR_CODE, []],
   askar_set_max_log_level: [FFI_ERROR_CODE, [FFI_INT32]],
+  askar_set_log_filter: [FFI_ERROR_CODE, [FFI_STRING]],
+  askar_set_crypto_offload: [FFI_ERROR_CODE, [FFI_STRING, FFI_INT32]],
 
   askar_entry_list_count: [FFI_ERROR_CODE, [FFI_ENTRY_LIST_HANDLE, FFI_INT32_PTR]],
   askar_entry_list_free: [FFI_VOID, [FFI_ENTRY_LIST_HANDLE]],
@@ -187,6 +187,14 @@ export class ReactNativeAriesAskar implements AriesAskar {
     throw new Error('Method not implemented. setMaxLogLevel')
   }
 
+  public setLogFilter(): void {
+    throw new Error('Method not implemented. setLogFilter')
+  }
+
+  public setCryptoOffload(): void {
+    throw new Error('Method not implemented. setCryptoOffload')
+  }
+
   public entryListCount(options: EntryListCountOptions): number {
     const serializedOptions = serializeArguments(options)
     return handleInvalidNullResponse(this.handleError(this.ariesAskar.entryListCount(serializedOptions)))
@@ -31,6 +31,8 @@ export type SetCustomLoggerOptions = {
   logger: NativeLogCallback
 }
 export type SetMaxLogLevelOptions = { logLevel: number }
+export type SetLogFilterOptions = { filter: string }
+export type SetCryptoOffloadOptions = { mode: 'blocking' | 'dedicated' | 'inline'; threads?: number }
 
 export type EntryListCountOptions = { entryListHandle: EntryListHandle }
 export type EntryListFreeOptions = { entryListHandle: EntryListHandle }
@@ -242,6 +244,8 @@ export type AriesAskar = {
   setCustomLogger(options: SetCustomLoggerOptions): void
   setDefaultLogger(): void
   setMaxLogLevel(options: SetMaxLogLevelOptions): void
+  setLogFilter(options: SetLogFilterOptions): void
+  setCryptoOffload(options: SetCryptoOffloadOptions): void
 
   entryListCount(options: EntryListCountOptions): number
   entryListFree(options: EntryListFreeOptions): void
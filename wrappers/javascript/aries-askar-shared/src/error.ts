@@ -1,14 +1,24 @@
 export type AriesAskarErrorObject = {
   code: number
   message: string | null
+  kind?: string
+  backend_code?: string | null
+  sqlstate?: string | null
+  causes?: string[]
 }
 
 export class AriesAskarError extends Error {
   public readonly code: number
+  public readonly backendCode?: string
+  public readonly sqlstate?: string
+  public readonly causes: string[]
 
-  public constructor({ code, message }: AriesAskarErrorObject) {
+  public constructor({ code, message, backend_code, sqlstate, causes }: AriesAskarErrorObject) {
     super(message ?? 'No message provided from Aries Askar')
     this.code = code
+    this.backendCode = backend_code ?? undefined
+    this.sqlstate = sqlstate ?? undefined
+    this.causes = causes ?? []
   }
 
   public static customError({ message }: { message: string }) {
@@ -3,6 +3,8 @@ export enum KdfMethod {
   None = 'none',
   Argon2IMod = 'kdf:argon2i:mod',
   Argon2IInt = 'kdf:argon2i:int',
+  Scrypt = 'kdf:scrypt',
+  Pbkdf2 = 'kdf:pbkdf2',
 }
 
 export class StoreKeyMethod {
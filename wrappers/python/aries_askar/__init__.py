@@ -4,7 +4,7 @@ from .bindings import Encrypted, version
 from .error import AskarError, AskarErrorCode
 from .key import Key
 from .store import Entry, EntryList, KeyEntry, KeyEntryList, Session, Store
-from .types import KeyAlg, SeedMethod
+from .types import ImportConflict, IsolationLevel, KeyAlg, SeedMethod
 from . import crypto_box
 from . import ecdh
 
@@ -17,6 +17,8 @@ __all__ = (
     "Encrypted",
     "Entry",
     "EntryList",
+    "ImportConflict",
+    "IsolationLevel",
     "Key",
     "KeyAlg",
     "KeyEntry",
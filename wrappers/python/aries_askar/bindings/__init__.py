@@ -4,10 +4,26 @@ import asyncio
 import json
 import logging
 
-from ctypes import POINTER, byref, c_int8, c_int32, c_int64
-from typing import Optional, Sequence, Union
-
-from ..types import EntryOperation, KeyAlg, SeedMethod
+from ctypes import (
+    CFUNCTYPE,
+    POINTER,
+    byref,
+    c_int8,
+    c_int32,
+    c_int64,
+    c_size_t,
+    c_void_p,
+)
+from typing import Callable, Optional, Sequence, Union
+
+from ..types import (
+    EntryOperation,
+    ImportConflict,
+    IsolationLevel,
+    KeyAlg,
+    SavepointOperation,
+    SeedMethod,
+)
 
 from .lib import (
     AeadParams,
@@ -18,11 +34,13 @@ from .lib import (
     FfiStr,
     FfiTagsJson,
     Lib,
+    SecretStrBuffer,
     StrBuffer,
 )
 from .handle import (
     EntryListHandle,
     KeyEntryListHandle,
+    KeyScanHandle,
     LocalKeyHandle,
     ScanHandle,
     SessionHandle,
@@ -50,6 +68,27 @@ def set_max_log_level(level: Union[str, int, None]):
     get_library().set_max_log_level(level)
 
 
+def set_log_filter(log_filter: Optional[str]):
+    """
+    Set the logging level for individual modules.
+
+    Uses the syntax of the `RUST_LOG` environment variable, for example
+    `askar_storage=debug,sqlx=warn`. An empty filter removes it.
+    """
+    get_library().set_log_filter(log_filter)
+
+
+def set_crypto_offload(mode: str, threads: int = 0):
+    """
+    Select the method used to run CPU-intensive cryptographic operations.
+
+    The mode may be `blocking` (the default) to use the blocking thread pool
+    of the library's async runtime, `dedicated` to use a separate pool of
+    `threads` threads, or `inline` to run operations on the calling task.
+    """
+    invoke("askar_set_crypto_offload", (FfiStr, c_int32), mode, threads)
+
+
 def invoke(name, argtypes, *args):
     """Perform a synchronous library function call."""
     get_library().invoke(name, argtypes, *args)
@@ -62,16 +101,42 @@ def invoke_async(name: str, argtypes, *args, return_type=None) -> asyncio.Future
 
 def generate_raw_key(seed: Union[str, bytes] = None) -> str:
     """Generate a new raw store wrapping key."""
-    key = StrBuffer()
+    key = SecretStrBuffer()
     invoke(
         "askar_store_generate_raw_key",
-        (FfiByteBuffer, POINTER(StrBuffer)),
+        (FfiByteBuffer, POINTER(SecretStrBuffer)),
         seed,
         byref(key),
     )
     return str(key)
 
 
+def split_key(pass_key: str, threshold: int, shares: int) -> Sequence[str]:
+    """Split a store pass key into key shares."""
+    result = SecretStrBuffer()
+    invoke(
+        "askar_store_split_key",
+        (FfiStr, c_int32, c_int32, POINTER(SecretStrBuffer)),
+        pass_key,
+        threshold,
+        shares,
+        byref(result),
+    )
+    return json.loads(str(result))
+
+
+def combine_key_shares(shares: Sequence[str]) -> str:
+    """Recover a store pass key from key shares."""
+    key = SecretStrBuffer()
+    invoke(
+        "askar_store_combine_key_shares",
+        (FfiJson, POINTER(SecretStrBuffer)),
+        shares,
+        byref(key),
+    )
+    return str(key)
+
+
 def version() -> str:
     """Get the version of the installed library."""
     return get_library().version()
@@ -92,6 +157,18 @@ async def store_open(
     )
 
 
+async def store_open_profile(uri: str, profile: str, pass_key: str) -> StoreHandle:
+    """Open an existing Store scoped to a single profile."""
+    return await invoke_async(
+        "askar_store_open_profile",
+        (FfiStr, FfiStr, FfiStr),
+        uri,
+        profile,
+        pass_key,
+        return_type=StoreHandle,
+    )
+
+
 async def store_provision(
     uri: str,
     key_method: str = None,
@@ -159,6 +236,47 @@ async def store_set_default_profile(handle: StoreHandle, profile: str):
     )
 
 
+async def store_set_key_cache(handle: StoreHandle, ttl_ms: Optional[int]):
+    """Enable or disable the cache of loaded keys for a Store."""
+    await invoke_async(
+        "askar_store_set_key_cache",
+        (StoreHandle, c_int64),
+        handle,
+        -1 if ttl_ms is None else ttl_ms,
+    )
+
+
+async def store_purge_key_cache(handle: StoreHandle):
+    """Remove all keys from the cache of loaded keys for a Store."""
+    await invoke_async(
+        "askar_store_purge_key_cache",
+        (StoreHandle,),
+        handle,
+    )
+
+
+async def store_set_entry_cache(
+    handle: StoreHandle, capacity: int, ttl_ms: Optional[int]
+):
+    """Enable or disable the cache of fetched records for a Store."""
+    await invoke_async(
+        "askar_store_set_entry_cache",
+        (StoreHandle, c_int64, c_int64),
+        handle,
+        capacity,
+        -1 if ttl_ms is None else ttl_ms,
+    )
+
+
+async def store_purge_entry_cache(handle: StoreHandle):
+    """Remove all records from the cache of fetched records for a Store."""
+    await invoke_async(
+        "askar_store_purge_entry_cache",
+        (StoreHandle,),
+        handle,
+    )
+
+
 async def store_remove_profile(handle: StoreHandle, name: str) -> bool:
     """Remove an existing profile from a Store."""
     return (
@@ -173,6 +291,17 @@ async def store_remove_profile(handle: StoreHandle, name: str) -> bool:
     )
 
 
+async def store_rename_profile(handle: StoreHandle, name: str, new_name: str):
+    """Rename an existing profile in a Store."""
+    return await invoke_async(
+        "askar_store_rename_profile",
+        (StoreHandle, FfiStr, FfiStr),
+        handle,
+        name,
+        new_name,
+    )
+
+
 async def store_list_profiles(handle: StoreHandle) -> Sequence[str]:
     """List the profile identifiers present in a Store."""
     handle = await invoke_async(
@@ -202,22 +331,128 @@ async def store_list_profiles(handle: StoreHandle) -> Sequence[str]:
     return ret
 
 
+async def store_list_profile_details(handle: StoreHandle) -> list:
+    """List the details of the profiles present in a Store."""
+    details = await invoke_async(
+        "askar_store_list_profile_details",
+        (StoreHandle,),
+        handle,
+        return_type=StrBuffer,
+    )
+    details = json.loads(str(details))
+    for profile in details:
+        if profile["metadata"] is not None:
+            profile["metadata"] = bytes.fromhex(profile["metadata"])
+    return details
+
+
+async def store_set_profile_access_key(
+    handle: StoreHandle, profile: str, key_method: str = None, pass_key: str = None
+):
+    """Set the profile-specific pass key of a Store profile."""
+    return await invoke_async(
+        "askar_store_set_profile_access_key",
+        (StoreHandle, FfiStr, FfiStr, FfiStr),
+        handle,
+        profile,
+        key_method and key_method.lower(),
+        pass_key,
+    )
+
+
+async def store_remove_profile_access_key(handle: StoreHandle, profile: str):
+    """Remove the profile-specific pass key of a Store profile."""
+    return await invoke_async(
+        "askar_store_remove_profile_access_key",
+        (StoreHandle, FfiStr),
+        handle,
+        profile,
+    )
+
+
+async def store_set_profile_limits(
+    handle: StoreHandle,
+    profile: str,
+    max_entries: Optional[int],
+    max_value_bytes: Optional[int],
+):
+    """Replace the storage limits of a Store profile."""
+    return await invoke_async(
+        "askar_store_set_profile_limits",
+        (StoreHandle, FfiStr, c_int64, c_int64),
+        handle,
+        profile,
+        -1 if max_entries is None else max_entries,
+        -1 if max_value_bytes is None else max_value_bytes,
+    )
+
+
+async def store_set_profile_metadata(
+    handle: StoreHandle, profile: str, metadata: Optional[bytes]
+):
+    """Replace the custom metadata associated with a Store profile."""
+    return await invoke_async(
+        "askar_store_set_profile_metadata",
+        (StoreHandle, FfiStr, FfiByteBuffer),
+        handle,
+        profile,
+        metadata,
+    )
+
+
 async def store_rekey(
     handle: StoreHandle,
     key_method: str = None,
     pass_key: str = None,
+    progress: Callable[[int, int], None] = None,
 ) -> StoreHandle:
     """Replace the store key on a Store."""
+    if not progress:
+        return await invoke_async(
+            "askar_store_rekey",
+            (StoreHandle, FfiStr, FfiStr),
+            handle,
+            key_method and key_method.lower(),
+            pass_key,
+            return_type=c_int8,
+        )
+    loop = asyncio.get_event_loop()
+    # must maintain a reference to the callback until the call completes
+    progress_cb = CFUNCTYPE(None, c_int64, c_int64, c_int64)(
+        lambda _cb_id, completed, total: loop.call_soon_threadsafe(
+            progress, completed, total
+        )
+    )
     return await invoke_async(
-        "askar_store_rekey",
-        (StoreHandle, FfiStr, FfiStr),
+        "askar_store_rekey_with_progress",
+        (StoreHandle, FfiStr, FfiStr, c_void_p),
         handle,
         key_method and key_method.lower(),
         pass_key,
+        progress_cb,
         return_type=c_int8,
     )
 
 
+async def store_upgrade_kdf(
+    handle: StoreHandle,
+    key_method: str = None,
+    pass_key: str = None,
+) -> bool:
+    """Re-derive the store key if its key derivation method is too weak."""
+    return (
+        await invoke_async(
+            "askar_store_upgrade_kdf",
+            (StoreHandle, FfiStr, FfiStr),
+            handle,
+            key_method and key_method.lower(),
+            pass_key,
+            return_type=c_int8,
+        )
+        != 0
+    )
+
+
 async def store_copy(
     handle: StoreHandle,
     target_uri: str,
@@ -238,6 +473,97 @@ async def store_copy(
     )
 
 
+async def store_copy_to_store(
+    handle: StoreHandle,
+    target_handle: StoreHandle,
+    profiles: Sequence[str] = None,
+    categories: Sequence[str] = None,
+):
+    """Copy the Store entries and keys into another open Store."""
+    return await invoke_async(
+        "askar_store_copy_to_store",
+        (StoreHandle, StoreHandle, FfiStr, FfiStr),
+        handle,
+        target_handle,
+        None if profiles is None else json.dumps(list(profiles)),
+        None if categories is None else json.dumps(list(categories)),
+    )
+
+
+async def store_export(
+    handle: StoreHandle,
+    path: str,
+    pass_key: str = None,
+    recipient_key: LocalKeyHandle = None,
+    profiles: Sequence[str] = None,
+):
+    """Write an encrypted export of the Store contents to a file."""
+    return await invoke_async(
+        "askar_store_export",
+        (StoreHandle, FfiStr, FfiStr, LocalKeyHandle, FfiStr),
+        handle,
+        path,
+        pass_key,
+        recipient_key or LocalKeyHandle(),
+        None if profiles is None else json.dumps(list(profiles)),
+    )
+
+
+async def store_import(
+    handle: StoreHandle,
+    path: str,
+    pass_key: str = None,
+    recipient_key: LocalKeyHandle = None,
+    conflict: ImportConflict = ImportConflict.FAIL,
+    categories: Sequence[str] = None,
+):
+    """Import the contents of an encrypted export file into the Store."""
+    return await invoke_async(
+        "askar_store_import",
+        (StoreHandle, FfiStr, FfiStr, LocalKeyHandle, c_int8, FfiStr),
+        handle,
+        path,
+        pass_key,
+        recipient_key or LocalKeyHandle(),
+        conflict.value,
+        None if categories is None else json.dumps(list(categories)),
+    )
+
+
+async def store_snapshot(handle: StoreHandle, target_uri: str):
+    """Write a point-in-time copy of the Store to a new location."""
+    return await invoke_async(
+        "askar_store_snapshot",
+        (StoreHandle, FfiStr),
+        handle,
+        target_uri,
+    )
+
+
+async def store_repair(handle: StoreHandle, dry_run: bool = False) -> dict:
+    """Check and repair the structure of the Store."""
+    report = await invoke_async(
+        "askar_store_repair",
+        (StoreHandle, c_int8),
+        handle,
+        dry_run,
+        return_type=StrBuffer,
+    )
+    return json.loads(str(report))
+
+
+async def store_verify_integrity(handle: StoreHandle, quarantine: bool = False) -> dict:
+    """Check that all profile keys and entries in the Store can be decrypted."""
+    report = await invoke_async(
+        "askar_store_verify_integrity",
+        (StoreHandle, c_int8),
+        handle,
+        quarantine,
+        return_type=StrBuffer,
+    )
+    return json.loads(str(report))
+
+
 async def store_remove(uri: str) -> bool:
     """Remove an existing Store, if any."""
     return (
@@ -266,6 +592,23 @@ async def session_start(
     return handle
 
 
+async def session_start_transaction(
+    handle: StoreHandle,
+    profile: Optional[str] = None,
+    isolation: Optional[IsolationLevel] = None,
+) -> SessionHandle:
+    """Start a new transaction with an open Store."""
+    handle = await invoke_async(
+        "askar_session_start_transaction",
+        (StoreHandle, FfiStr, c_int8),
+        handle,
+        profile,
+        0 if isolation is None else isolation.value,
+        return_type=SessionHandle,
+    )
+    return handle
+
+
 async def session_count(
     handle: SessionHandle, category: str = None, tag_filter: Union[str, dict] = None
 ) -> int:
@@ -282,6 +625,25 @@ async def session_count(
     )
 
 
+async def session_count_grouped(
+    handle: SessionHandle,
+    category: str = None,
+    tag_filter: Union[str, dict] = None,
+    group_tag: str = None,
+) -> list:
+    """Count rows in the Store, grouped by category and optional tag value."""
+    counts = await invoke_async(
+        "askar_session_count_grouped",
+        (SessionHandle, FfiStr, FfiJson, FfiStr),
+        handle,
+        category,
+        tag_filter,
+        group_tag,
+        return_type=StrBuffer,
+    )
+    return json.loads(str(counts))
+
+
 async def session_fetch(
     handle: SessionHandle, category: str, name: str, for_update: bool = False
 ) -> EntryListHandle:
@@ -317,6 +679,63 @@ async def session_fetch_all(
     )
 
 
+async def session_fetch_many(
+    handle: SessionHandle,
+    category: str,
+    names: Sequence[str],
+    for_update: bool = False,
+) -> EntryListHandle:
+    """Fetch multiple rows from the Store by category and name."""
+    return await invoke_async(
+        "askar_session_fetch_many",
+        (SessionHandle, FfiStr, FfiStr, c_int8),
+        handle,
+        category,
+        json.dumps(list(names)),
+        for_update,
+        return_type=EntryListHandle,
+    )
+
+
+async def session_fetch_audit_log(
+    handle: SessionHandle,
+    category: str = None,
+    since: int = None,
+    limit: int = None,
+) -> list:
+    """Fetch records from the audit log of the Store profile."""
+    records = await invoke_async(
+        "askar_session_fetch_audit_log",
+        (SessionHandle, FfiStr, c_int64, c_int64),
+        handle,
+        category,
+        since if since is not None else -1,
+        limit if limit is not None else -1,
+        return_type=StrBuffer,
+    )
+    return json.loads(str(records))
+
+
+async def session_search(
+    handle: SessionHandle,
+    text: str,
+    category: str = None,
+    tag_filter: Union[str, dict] = None,
+    limit: int = None,
+) -> EntryListHandle:
+    """Search for rows in the Store with plaintext tags matching a query."""
+    return await invoke_async(
+        "askar_session_search",
+        (SessionHandle, FfiStr, FfiStr, FfiJson, c_int64),
+        handle,
+        category,
+        text,
+        tag_filter,
+        limit if limit is not None else -1,
+        return_type=EntryListHandle,
+    )
+
+
 async def session_remove_all(
     handle: SessionHandle,
     category: str = None,
@@ -335,6 +754,69 @@ async def session_remove_all(
     )
 
 
+async def session_remove_all_returning(
+    handle: SessionHandle,
+    category: str = None,
+    tag_filter: Union[str, dict] = None,
+) -> list:
+    """Remove all matching rows in the Store, returning the removed names."""
+    removed = await invoke_async(
+        "askar_session_remove_all_returning",
+        (SessionHandle, FfiStr, FfiJson),
+        handle,
+        category,
+        tag_filter,
+        return_type=StrBuffer,
+    )
+    return json.loads(str(removed))
+
+
+async def session_restore(
+    handle: SessionHandle,
+    category: str,
+    name: str,
+):
+    """Restore a soft-deleted row in the Store."""
+    await invoke_async(
+        "askar_session_restore",
+        (SessionHandle, FfiStr, FfiStr),
+        handle,
+        category,
+        name,
+    )
+
+
+async def session_purge(
+    handle: SessionHandle,
+    category: str,
+    name: str,
+):
+    """Permanently remove a soft-deleted row in the Store."""
+    await invoke_async(
+        "askar_session_purge",
+        (SessionHandle, FfiStr, FfiStr),
+        handle,
+        category,
+        name,
+    )
+
+
+async def session_purge_all(
+    handle: SessionHandle,
+    category: str = None,
+) -> int:
+    """Permanently remove all soft-deleted rows in the Store."""
+    return int(
+        await invoke_async(
+            "askar_session_purge_all",
+            (SessionHandle, FfiStr),
+            handle,
+            category,
+            return_type=c_int64,
+        )
+    )
+
+
 async def session_update(
     handle: SessionHandle,
     operation: EntryOperation,
@@ -358,6 +840,61 @@ async def session_update(
     )
 
 
+async def session_update_if(
+    handle: SessionHandle,
+    operation: EntryOperation,
+    category: str,
+    name: str,
+    value: Union[str, bytes] = None,
+    tags: dict = None,
+    expiry_ms: Optional[int] = None,
+    value_hash: bytes = None,
+    expected_tags: dict = None,
+    expected_version: Optional[int] = None,
+):
+    """Replace or remove a record if its current state matches a precondition."""
+    return await invoke_async(
+        "askar_session_update_if",
+        (
+            SessionHandle,
+            c_int8,
+            FfiStr,
+            FfiStr,
+            FfiByteBuffer,
+            FfiTagsJson,
+            c_int64,
+            FfiByteBuffer,
+            FfiTagsJson,
+            c_int64,
+        ),
+        handle,
+        operation.value,
+        category,
+        name,
+        value,
+        tags,
+        -1 if expiry_ms is None else expiry_ms,
+        value_hash,
+        expected_tags,
+        -1 if expected_version is None else expected_version,
+    )
+
+
+async def session_insert_many(
+    handle: SessionHandle,
+    entries: Sequence[dict],
+    expiry_ms: Optional[int] = None,
+):
+    """Insert a batch of new records into a Store."""
+    return await invoke_async(
+        "askar_session_insert_many",
+        (SessionHandle, FfiStr, c_int64),
+        handle,
+        json.dumps(entries),
+        -1 if expiry_ms is None else expiry_ms,
+    )
+
+
 async def session_insert_key(
     handle: SessionHandle,
     key_handle: LocalKeyHandle,
@@ -378,6 +915,27 @@ async def session_insert_key(
     )
 
 
+async def session_insert_key_by_thumbprint(
+    handle: SessionHandle,
+    key_handle: LocalKeyHandle,
+    metadata: str = None,
+    tags: dict = None,
+    expiry_ms: Optional[int] = None,
+) -> str:
+    return str(
+        await invoke_async(
+            "askar_session_insert_key_by_thumbprint",
+            (SessionHandle, LocalKeyHandle, FfiStr, FfiTagsJson, c_int64),
+            handle,
+            key_handle,
+            metadata,
+            tags,
+            -1 if expiry_ms is None else expiry_ms,
+            return_type=StrBuffer,
+        )
+    )
+
+
 async def session_fetch_key(
     handle: SessionHandle, name: str, for_update: bool = False
 ) -> KeyEntryListHandle:
@@ -391,6 +949,29 @@ async def session_fetch_key(
     )
 
 
+async def session_load_key(handle: SessionHandle, name: str) -> LocalKeyHandle:
+    return await invoke_async(
+        "askar_session_load_key",
+        (SessionHandle, FfiStr),
+        handle,
+        name,
+        return_type=LocalKeyHandle,
+    )
+
+
+async def session_fetch_key_by_thumbprint(
+    handle: SessionHandle, thumbprint: str, for_update: bool = False
+) -> KeyEntryListHandle:
+    return await invoke_async(
+        "askar_session_fetch_key_by_thumbprint",
+        (SessionHandle, FfiStr, c_int8),
+        handle,
+        thumbprint,
+        for_update,
+        return_type=KeyEntryListHandle,
+    )
+
+
 async def session_fetch_all_keys(
     handle: SessionHandle,
     alg: Union[str, KeyAlg] = None,
@@ -398,23 +979,53 @@ async def session_fetch_all_keys(
     tag_filter: Union[str, dict] = None,
     limit: int = None,
     for_update: bool = False,
+    created_after: int = None,
+    created_before: int = None,
+    metadata: str = None,
 ) -> KeyEntryListHandle:
     """Fetch all matching keys in the Store."""
     if isinstance(alg, KeyAlg):
         alg = alg.value
     return await invoke_async(
         "askar_session_fetch_all_keys",
-        (SessionHandle, FfiStr, FfiStr, FfiJson, c_int64, c_int8),
+        (
+            SessionHandle,
+            FfiStr,
+            FfiStr,
+            FfiJson,
+            c_int64,
+            c_int64,
+            FfiStr,
+            c_int64,
+            c_int8,
+        ),
         handle,
         alg,
         thumbprint,
         tag_filter,
+        created_after if created_after is not None else -1,
+        created_before if created_before is not None else -1,
+        metadata,
         limit if limit is not None else -1,
         for_update,
         return_type=KeyEntryListHandle,
     )
 
 
+async def session_fetch_expiring_keys(
+    handle: SessionHandle, expires_before: int, limit: int = None
+) -> KeyEntryListHandle:
+    """Fetch the keys expiring before a given time."""
+    return await invoke_async(
+        "askar_session_fetch_expiring_keys",
+        (SessionHandle, c_int64, c_int64),
+        handle,
+        expires_before,
+        limit if limit is not None else -1,
+        return_type=KeyEntryListHandle,
+    )
+
+
 async def session_update_key(
     handle: SessionHandle,
     name: str,
@@ -433,6 +1044,116 @@ async def session_update_key(
     )
 
 
+async def session_set_key_json_metadata(
+    handle: SessionHandle,
+    name: str,
+    metadata: Optional[dict] = None,
+    indexed: Sequence[str] = None,
+):
+    """Replace the structured metadata of a key, or remove it."""
+    await invoke_async(
+        "askar_session_set_key_json_metadata",
+        (SessionHandle, FfiStr, FfiJson, FfiJson),
+        handle,
+        name,
+        metadata,
+        list(indexed) if indexed else None,
+    )
+
+
+async def session_set_key_policy(
+    handle: SessionHandle, name: str, policy: Optional[dict] = None
+):
+    """Attach a usage policy to a key, or remove the current policy."""
+    await invoke_async(
+        "askar_session_set_key_policy",
+        (SessionHandle, FfiStr, FfiJson),
+        handle,
+        name,
+        policy,
+    )
+
+
+async def session_rotate_key(handle: SessionHandle, name: str) -> KeyEntryListHandle:
+    """Rotate a key, keeping the previous version available."""
+    return await invoke_async(
+        "askar_session_rotate_key",
+        (SessionHandle, FfiStr),
+        handle,
+        name,
+        return_type=KeyEntryListHandle,
+    )
+
+
+async def session_fetch_key_versions(
+    handle: SessionHandle, name: str
+) -> KeyEntryListHandle:
+    """Fetch all available versions of a key."""
+    return await invoke_async(
+        "askar_session_fetch_key_versions",
+        (SessionHandle, FfiStr),
+        handle,
+        name,
+        return_type=KeyEntryListHandle,
+    )
+
+
+async def session_move_category(
+    handle: SessionHandle,
+    category: str,
+    new_category: str,
+    rename_tags: dict = None,
+) -> int:
+    """Move all records in a category to another category."""
+    return int(
+        await invoke_async(
+            "askar_session_move_category",
+            (SessionHandle, FfiStr, FfiStr, FfiJson),
+            handle,
+            category,
+            new_category,
+            rename_tags,
+            return_type=c_int64,
+        )
+    )
+
+
+async def session_rename(
+    handle: SessionHandle,
+    category: str,
+    old_name: str,
+    new_name: str,
+):
+    """Rename a record, preserving its value and tags."""
+    await invoke_async(
+        "askar_session_rename",
+        (SessionHandle, FfiStr, FfiStr, FfiStr),
+        handle,
+        category,
+        old_name,
+        new_name,
+    )
+
+
+async def session_update_tags(
+    handle: SessionHandle,
+    category: str,
+    name: str,
+    add_tags: dict = None,
+    remove_tag_names: Sequence[str] = None,
+):
+    """Add and remove tags on a record without replacing its value."""
+    await invoke_async(
+        "askar_session_update_tags",
+        (SessionHandle, FfiStr, FfiStr, FfiTagsJson, FfiStr),
+        handle,
+        category,
+        name,
+        add_tags,
+        None if remove_tag_names is None else json.dumps(list(remove_tag_names)),
+    )
+
+
 async def session_remove_key(handle: SessionHandle, name: str):
     await invoke_async(
         "askar_session_remove_key",
@@ -442,6 +1163,19 @@ async def session_remove_key(handle: SessionHandle, name: str):
     )
 
 
+async def session_savepoint(
+    handle: SessionHandle, name: str, operation: SavepointOperation
+):
+    """Create, release or roll back to a savepoint within a transaction."""
+    await invoke_async(
+        "askar_session_savepoint",
+        (SessionHandle, FfiStr, c_int8),
+        handle,
+        name,
+        operation.value,
+    )
+
+
 async def scan_start(
     handle: StoreHandle,
     profile: Optional[str],
@@ -449,27 +1183,120 @@ async def scan_start(
     tag_filter: Union[str, dict] = None,
     offset: int = None,
     limit: int = None,
+    order_by: str = None,
+    descending: bool = False,
+    after: str = None,
+    tag_names: Sequence[str] = None,
 ) -> ScanHandle:
     """Create a new Scan against the Store."""
     return await invoke_async(
         "askar_scan_start",
-        (StoreHandle, FfiStr, FfiStr, FfiJson, c_int64, c_int64),
+        (
+            StoreHandle,
+            FfiStr,
+            FfiStr,
+            FfiJson,
+            c_int64,
+            c_int64,
+            FfiStr,
+            c_int8,
+            FfiStr,
+            FfiStr,
+        ),
         handle,
         profile,
         category,
         tag_filter,
         offset or 0,
         limit if limit is not None else -1,
+        order_by,
+        descending,
+        after,
+        None if tag_names is None else json.dumps(list(tag_names)),
         return_type=ScanHandle,
     )
 
 
+async def scan_continuation(handle: ScanHandle) -> Optional[str]:
+    """Get the continuation token for the last row fetched by a Scan."""
+    token = await invoke_async(
+        "askar_scan_continuation", (ScanHandle,), handle, return_type=StrBuffer
+    )
+    return token.opt_str()
+
+
 async def scan_next(handle: ScanHandle) -> EntryListHandle:
     return await invoke_async(
         "askar_scan_next", (ScanHandle,), handle, return_type=EntryListHandle
     )
 
 
+async def scan_stream(
+    handle: ScanHandle, page: Callable[[EntryListHandle], None]
+) -> None:
+    """Deliver each remaining page of scan results to a callback as it is fetched."""
+    loop = asyncio.get_event_loop()
+
+    def _page(_cb_id, results):
+        loop.call_soon_threadsafe(page, EntryListHandle(results))
+        return 0
+
+    # must maintain a reference to the callback until the call completes
+    page_cb = CFUNCTYPE(c_int8, c_int64, c_size_t)(_page)
+    await invoke_async(
+        "askar_scan_stream",
+        (ScanHandle, c_void_p),
+        handle,
+        page_cb,
+    )
+
+
+async def key_scan_start(
+    handle: StoreHandle,
+    profile: Optional[str],
+    alg: Union[str, KeyAlg] = None,
+    thumbprint: str = None,
+    tag_filter: Union[str, dict] = None,
+    created_after: int = None,
+    created_before: int = None,
+    offset: int = None,
+    limit: int = None,
+) -> KeyScanHandle:
+    """Create a new key scan against the Store."""
+    if isinstance(alg, KeyAlg):
+        alg = alg.value
+    return await invoke_async(
+        "askar_key_scan_start",
+        (
+            StoreHandle,
+            FfiStr,
+            FfiStr,
+            FfiStr,
+            FfiJson,
+            c_int64,
+            c_int64,
+            c_int64,
+            c_int64,
+        ),
+        handle,
+        profile,
+        alg,
+        thumbprint,
+        tag_filter,
+        created_after if created_after is not None else -1,
+        created_before if created_before is not None else -1,
+        offset or 0,
+        limit if limit is not None else -1,
+        return_type=KeyScanHandle,
+    )
+
+
+async def key_scan_next(handle: KeyScanHandle) -> KeyEntryListHandle:
+    return await invoke_async(
+        "askar_key_scan_next", (KeyScanHandle,), handle, return_type=KeyEntryListHandle
+    )
+
+
 def entry_list_count(handle: EntryListHandle) -> int:
     len = c_int32()
     invoke(
@@ -506,6 +1333,34 @@ def key_generate(alg: Union[str, KeyAlg], ephemeral: bool = False) -> LocalKeyHa
     return handle
 
 
+def key_generate_external(
+    scheme: str, alg: Union[str, KeyAlg], label: Optional[str] = None
+) -> LocalKeyHandle:
+    handle = LocalKeyHandle()
+    if isinstance(alg, KeyAlg):
+        alg = alg.value
+    invoke(
+        "askar_key_generate_external",
+        (FfiStr, FfiStr, FfiStr, POINTER(LocalKeyHandle)),
+        scheme,
+        alg,
+        label,
+        byref(handle),
+    )
+    return handle
+
+
+def key_from_reference(reference: str) -> LocalKeyHandle:
+    handle = LocalKeyHandle()
+    invoke(
+        "askar_key_from_reference",
+        (FfiStr, POINTER(LocalKeyHandle)),
+        reference,
+        byref(handle),
+    )
+    return handle
+
+
 def key_from_seed(
     alg: Union[str, KeyAlg],
     seed: Union[str, bytes, ByteBuffer],
@@ -647,6 +1502,28 @@ def key_get_ephemeral(handle: LocalKeyHandle) -> bool:
     return eph.value != 0
 
 
+def key_get_policy(handle: LocalKeyHandle) -> Optional[dict]:
+    policy = StrBuffer()
+    invoke(
+        "askar_key_get_policy",
+        (LocalKeyHandle, POINTER(StrBuffer)),
+        handle,
+        byref(policy),
+    )
+    return None if policy.is_none() else json.loads(str(policy))
+
+
+def key_get_reference(handle: LocalKeyHandle) -> Optional[str]:
+    reference = StrBuffer()
+    invoke(
+        "askar_key_get_reference",
+        (LocalKeyHandle, POINTER(StrBuffer)),
+        handle,
+        byref(reference),
+    )
+    return None if reference.is_none() else str(reference)
+
+
 def key_get_jwk_public(handle: LocalKeyHandle, alg: Union[str, KeyAlg] = None) -> str:
     jwk = StrBuffer()
     if isinstance(alg, KeyAlg):
@@ -672,6 +1549,39 @@ def key_get_jwk_secret(handle: LocalKeyHandle) -> ByteBuffer:
     return sec
 
 
+def key_export_wrapped(
+    handle: LocalKeyHandle,
+    recipient_jwk: Union[dict, str],
+    alg: Union[str, KeyAlg],
+) -> str:
+    jwe = StrBuffer()
+    if isinstance(recipient_jwk, dict):
+        recipient_jwk = json.dumps(recipient_jwk)
+    if isinstance(alg, KeyAlg):
+        alg = alg.value
+    invoke(
+        "askar_key_export_wrapped",
+        (LocalKeyHandle, FfiStr, FfiStr, POINTER(StrBuffer)),
+        handle,
+        recipient_jwk,
+        alg,
+        byref(jwe),
+    )
+    return str(jwe)
+
+
+def key_import_wrapped(jwe: str, recipient: LocalKeyHandle) -> LocalKeyHandle:
+    handle = LocalKeyHandle()
+    invoke(
+        "askar_key_import_wrapped",
+        (FfiStr, LocalKeyHandle, POINTER(LocalKeyHandle)),
+        jwe,
+        recipient,
+        byref(handle),
+    )
+    return handle
+
+
 def key_get_jwk_thumbprint(
     handle: LocalKeyHandle, alg: Union[str, KeyAlg] = None
 ) -> str:
@@ -977,6 +1887,43 @@ def key_derive_ecdh_es(
     return key
 
 
+def key_derive_ecdh_es_batch(
+    key_alg: Union[str, KeyAlg],
+    ephem_key: LocalKeyHandle,
+    receiver_keys: Sequence[LocalKeyHandle],
+    alg_id: Union[bytes, str, ByteBuffer],
+    apu: Union[bytes, str, ByteBuffer],
+    apv: Union[bytes, str, ByteBuffer],
+) -> Sequence[LocalKeyHandle]:
+    count = len(receiver_keys)
+    receivers = (c_size_t * count)(*(key.value for key in receiver_keys))
+    keys = (c_size_t * count)()
+    if isinstance(key_alg, KeyAlg):
+        key_alg = key_alg.value
+    invoke(
+        "askar_key_derive_ecdh_es_batch",
+        (
+            FfiStr,
+            LocalKeyHandle,
+            POINTER(c_size_t),
+            c_int32,
+            FfiByteBuffer,
+            FfiByteBuffer,
+            FfiByteBuffer,
+            POINTER(c_size_t),
+        ),
+        key_alg,
+        ephem_key,
+        receivers,
+        count,
+        alg_id,
+        apu,
+        apv,
+        keys,
+    )
+    return [LocalKeyHandle(key) for key in keys]
+
+
 def key_derive_ecdh_1pu(
     key_alg: Union[str, KeyAlg],
     ephem_key: LocalKeyHandle,
@@ -1017,3 +1964,46 @@ def key_derive_ecdh_1pu(
         byref(key),
     )
     return key
+
+
+def key_derive_ecdh_1pu_batch(
+    key_alg: Union[str, KeyAlg],
+    ephem_key: LocalKeyHandle,
+    sender_key: LocalKeyHandle,
+    receiver_keys: Sequence[LocalKeyHandle],
+    alg_id: Union[bytes, str, ByteBuffer],
+    apu: Union[bytes, str, ByteBuffer],
+    apv: Union[bytes, str, ByteBuffer],
+    cc_tag: Optional[Union[bytes, ByteBuffer]],
+) -> Sequence[LocalKeyHandle]:
+    count = len(receiver_keys)
+    receivers = (c_size_t * count)(*(key.value for key in receiver_keys))
+    keys = (c_size_t * count)()
+    if isinstance(key_alg, KeyAlg):
+        key_alg = key_alg.value
+    invoke(
+        "askar_key_derive_ecdh_1pu_batch",
+        (
+            FfiStr,
+            LocalKeyHandle,
+            LocalKeyHandle,
+            POINTER(c_size_t),
+            c_int32,
+            FfiByteBuffer,
+            FfiByteBuffer,
+            FfiByteBuffer,
+            FfiByteBuffer,
+            POINTER(c_size_t),
+        ),
+        key_alg,
+        ephem_key,
+        sender_key,
+        receivers,
+        count,
+        alg_id,
+        apu,
+        apv,
+        cc_tag,
+        keys,
+    )
+    return [LocalKeyHandle(key) for key in keys]
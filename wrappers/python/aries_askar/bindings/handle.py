@@ -14,7 +14,9 @@ from ctypes import (
     c_void_p,
 )
 
-from .lib import ByteBuffer, Lib, StrBuffer, finalize_struct
+from typing import Optional
+
+from .lib import BufferView, ByteBuffer, Lib, StrBuffer, finalize_struct
 
 
 LOGGER = logging.getLogger(__name__)
@@ -115,6 +117,12 @@ class ScanHandle(ArcHandle):
     _dtor_ = "askar_scan_free"
 
 
+class KeyScanHandle(ArcHandle):
+    """Handle for an active Store key scan instance."""
+
+    _dtor_ = "askar_key_scan_free"
+
+
 class EntryListHandle(ArcHandle):
     """Handle for an active EntryList instance."""
 
@@ -156,6 +164,33 @@ class EntryListHandle(ArcHandle):
         )
         return val.view
 
+    def get_value_view(self, index: int) -> memoryview:
+        """Get the entry value without copying it.
+
+        The returned view keeps the entry list alive until it is released.
+        """
+        val = BufferView()
+        Lib().invoke(
+            "askar_entry_list_get_value_view",
+            (EntryListHandle, c_int32, POINTER(BufferView)),
+            self,
+            index,
+            byref(val),
+        )
+        return val.view_of(self)
+
+    def get_version(self, index: int) -> int:
+        """Get the entry version."""
+        version = c_int64()
+        Lib().invoke(
+            "askar_entry_list_get_version",
+            (EntryListHandle, c_int32, POINTER(c_int64)),
+            self,
+            index,
+            byref(version),
+        )
+        return version.value
+
     def get_tags(self, index: int) -> dict:
         """Get the entry tags."""
         tags = StrBuffer()
@@ -181,6 +216,18 @@ class KeyEntryListHandle(ArcHandle):
 
     _dtor_ = "askar_key_entry_list_free"
 
+    def get_active(self, index: int) -> bool:
+        """Determine if the key is within its validity window."""
+        active = c_int8()
+        Lib().invoke(
+            "askar_key_entry_list_get_active",
+            (KeyEntryListHandle, c_int32, POINTER(c_int8)),
+            self,
+            index,
+            byref(active),
+        )
+        return active.value != 0
+
     def get_algorithm(self, index: int) -> str:
         """Get the key algorithm."""
         name = StrBuffer()
@@ -217,6 +264,30 @@ class KeyEntryListHandle(ArcHandle):
         )
         return str(metadata)
 
+    def get_json_metadata(self, index: int) -> Optional[dict]:
+        """Get for the structured key metadata."""
+        metadata = StrBuffer()
+        Lib().invoke(
+            "askar_key_entry_list_get_json_metadata",
+            (KeyEntryListHandle, c_int32, POINTER(StrBuffer)),
+            self,
+            index,
+            byref(metadata),
+        )
+        return None if metadata.is_none() else json.loads(metadata.opt_str())
+
+    def get_version(self, index: int) -> int:
+        """Get the key version."""
+        version = c_int32()
+        Lib().invoke(
+            "askar_key_entry_list_get_version",
+            (KeyEntryListHandle, c_int32, POINTER(c_int32)),
+            self,
+            index,
+            byref(version),
+        )
+        return version.value
+
     def get_tags(self, index: int) -> dict:
         """Get the key tags."""
         tags = StrBuffer()
@@ -241,6 +312,18 @@ class KeyEntryListHandle(ArcHandle):
         )
         return handle
 
+    def load_public_key(self, index: int) -> "LocalKeyHandle":
+        """Load the public key instance."""
+        handle = LocalKeyHandle()
+        Lib().invoke(
+            "askar_key_entry_list_load_public",
+            (KeyEntryListHandle, c_int32, POINTER(LocalKeyHandle)),
+            self,
+            index,
+            byref(handle),
+        )
+        return handle
+
 
 class LocalKeyHandle(ArcHandle):
     """Handle for an active LocalKey instance."""
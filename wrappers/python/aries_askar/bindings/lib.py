@@ -36,6 +36,9 @@ from ..error import AskarError, AskarErrorCode
 LOGGER = logging.getLogger(__name__)
 MODULE_NAME = __name__.split(".")[0]
 
+# The ABI version of the library supported by these bindings
+ABI_VERSION = 1
+
 LOG_LEVELS = {
     1: logging.ERROR,
     2: logging.WARNING,
@@ -104,6 +107,7 @@ class LibLoad:
         self._methods = {}
 
         self._load_library()
+        self._check_abi_version()
         self._init_logger()
 
     def _load_library(self):
@@ -136,6 +140,23 @@ class LibLoad:
                 AskarErrorCode.WRAPPER, f"Error loading library: {lib_path}"
             ) from e
 
+    def _check_abi_version(self):
+        method = getattr(self._cdll, "askar_version_abi", None)
+        if not method:
+            raise AskarError(
+                AskarErrorCode.WRAPPER,
+                "Library does not report an ABI version, it may be outdated",
+            )
+        method.argtypes = ()
+        method.restype = c_int32
+        version = method()
+        if version != ABI_VERSION:
+            raise AskarError(
+                AskarErrorCode.WRAPPER,
+                f"Incompatible library ABI version: {version}, "
+                f"expected {ABI_VERSION}",
+            )
+
     def _init_logger(self):
         if self._log_cb:
             return
@@ -286,7 +307,12 @@ class LibLoad:
                 msg = None
             if msg and "message" in msg and "code" in msg:
                 return AskarError(
-                    AskarErrorCode(msg["code"]), msg["message"], msg.get("extra")
+                    AskarErrorCode(msg["code"]),
+                    msg["message"],
+                    msg.get("extra"),
+                    backend_code=msg.get("backend_code"),
+                    sqlstate=msg.get("sqlstate"),
+                    causes=msg.get("causes"),
                 )
             if not expect:
                 return None
@@ -373,6 +399,10 @@ class Lib:
         set_level = _convert_log_level(level)
         self.invoke("askar_set_max_log_level", (c_int32,), set_level)
 
+    def set_log_filter(self, log_filter: Optional[str]):
+        """Set the log level for individual modules of the library."""
+        self.invoke("askar_set_log_filter", (FfiStr,), log_filter)
+
     def version(self) -> str:
         """Get the version of the installed library."""
         return str(
@@ -503,6 +533,33 @@ class ByteBuffer(Structure):
         Lib().invoke_dtor("askar_buffer_free", buffer)
 
 
+class BufferView(Structure):
+    """A borrowed view of a byte buffer owned by the library.
+
+    The view is not freed by the caller, and is only valid while the
+    owning resource is kept alive.
+    """
+
+    _fields_ = [("buffer", RawBuffer)]
+
+    def view_of(self, owner) -> memoryview:
+        """Access the buffer contents, keeping the owning resource alive."""
+        if not self.buffer.len:
+            return memoryview(b"")
+        m = memoryview(self.buffer.array)
+        if hasattr(m, "toreadonly"):
+            # Python 3.8+
+            m = m.toreadonly()
+        keepalive(m, owner)
+        return m
+
+    def __len__(self) -> int:
+        return self.buffer.len
+
+    def __repr__(self) -> str:
+        return f"<BufferView(len={self.buffer.len})>"
+
+
 class FfiStr:
     """A string value allocated by Python."""
 
@@ -603,6 +660,15 @@ class StrBuffer(Structure):
         Lib().invoke_dtor("askar_string_free", buffer)
 
 
+class SecretStrBuffer(StrBuffer):
+    """A string allocated by the library which contains secret data."""
+
+    @classmethod
+    def _cleanup(cls, buffer: c_char_p):
+        """Zeroize and free the string when this instance is released."""
+        Lib().invoke_dtor("askar_string_free_zeroize", buffer)
+
+
 class AeadParams(Structure):
     """A byte buffer allocated by the library."""
 
@@ -1,6 +1,12 @@
-from typing import Optional, Union
+from typing import Optional, Sequence, Union
 
-from .bindings import Encrypted, key_derive_ecdh_es, key_derive_ecdh_1pu
+from .bindings import (
+    Encrypted,
+    key_derive_ecdh_es,
+    key_derive_ecdh_es_batch,
+    key_derive_ecdh_1pu,
+    key_derive_ecdh_1pu_batch,
+)
 from .key import Key
 from .types import KeyAlg
 
@@ -80,6 +86,22 @@ class EcdhEs:
         )
         return derived.wrap_key(cek)
 
+    def sender_wrap_keys(
+        self,
+        wrap_alg: Union[str, KeyAlg],
+        ephemeral_key: Union[dict, str, Key],
+        receiver_keys: Sequence[Union[dict, str, Key]],
+        cek: Key,
+    ) -> Sequence[Encrypted]:
+        receiver_keys = [_load_key(key) for key in receiver_keys]
+        derived = key_derive_ecdh_es_batch(
+            wrap_alg,
+            _load_key(ephemeral_key)._handle,
+            [key._handle for key in receiver_keys],
+            *self._params,
+        )
+        return [Key(handle).wrap_key(cek) for handle in derived]
+
     def receiver_unwrap_key(
         self,
         wrap_alg: Union[str, KeyAlg],
@@ -190,6 +212,27 @@ class Ecdh1PU:
         )
         return derived.wrap_key(cek)
 
+    def sender_wrap_keys(
+        self,
+        wrap_alg: Union[str, KeyAlg],
+        ephemeral_key: Union[dict, str, Key],
+        sender_key: Union[dict, str, Key],
+        receiver_keys: Sequence[Union[dict, str, Key]],
+        cek: Key,
+        *,
+        cc_tag: bytes,
+    ) -> Sequence[Encrypted]:
+        receiver_keys = [_load_key(key) for key in receiver_keys]
+        derived = key_derive_ecdh_1pu_batch(
+            wrap_alg,
+            _load_key(ephemeral_key)._handle,
+            _load_key(sender_key)._handle,
+            [key._handle for key in receiver_keys],
+            *self._params,
+            cc_tag,
+        )
+        return [Key(handle).wrap_key(cek) for handle in derived]
+
     def receiver_unwrap_key(
         self,
         wrap_alg: Union[str, KeyAlg],
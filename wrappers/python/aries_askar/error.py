@@ -1,6 +1,7 @@
 """Error classes."""
 
 from enum import IntEnum
+from typing import Sequence
 
 
 class AskarErrorCode(IntEnum):
@@ -13,12 +14,27 @@ class AskarErrorCode(IntEnum):
     NOT_FOUND = 6
     UNEXPECTED = 7
     UNSUPPORTED = 8
+    CONFLICT = 9
+    QUOTA_EXCEEDED = 10
+    TIMEOUT = 11
     WRAPPER = 99
     CUSTOM = 100
 
 
 class AskarError(Exception):
-    def __init__(self, code: AskarErrorCode, message: str, extra: str = None):
+    def __init__(
+        self,
+        code: AskarErrorCode,
+        message: str,
+        extra: str = None,
+        *,
+        backend_code: str = None,
+        sqlstate: str = None,
+        causes: Sequence[str] = None,
+    ):
         super().__init__(message)
         self.code = code
         self.extra = extra
+        self.backend_code = backend_code
+        self.sqlstate = sqlstate
+        self.causes = list(causes) if causes else []
@@ -1,6 +1,6 @@
 """Handling of Key instances."""
 
-from typing import Union
+from typing import Optional, Union
 
 from . import bindings
 from .bindings import AeadParams, Encrypted, LocalKeyHandle
@@ -18,6 +18,12 @@ class Key:
     def generate(cls, alg: Union[str, KeyAlg], *, ephemeral: bool = False) -> "Key":
         return cls(bindings.key_generate(alg, ephemeral))
 
+    @classmethod
+    def generate_external(
+        cls, scheme: str, alg: Union[str, KeyAlg], *, label: Optional[str] = None
+    ) -> "Key":
+        return cls(bindings.key_generate_external(scheme, alg, label))
+
     @classmethod
     def from_seed(
         cls,
@@ -36,10 +42,19 @@ class Key:
     def from_public_bytes(cls, alg: Union[str, KeyAlg], public: bytes) -> "Key":
         return cls(bindings.key_from_public_bytes(alg, public))
 
+    @classmethod
+    def from_reference(cls, reference: str) -> "Key":
+        return cls(bindings.key_from_reference(reference))
+
     @classmethod
     def from_jwk(cls, jwk: Union[dict, str, bytes]) -> "Key":
         return cls(bindings.key_from_jwk(jwk))
 
+    @classmethod
+    def import_wrapped(cls, jwe: str, recipient: "Key") -> "Key":
+        """Import a key exported using `export_wrapped`."""
+        return cls(bindings.key_import_wrapped(jwe, recipient._handle))
+
     @property
     def handle(self) -> LocalKeyHandle:
         """Accessor for the key handle."""
@@ -54,6 +69,14 @@ class Key:
     def ephemeral(self) -> bool:
         return bindings.key_get_ephemeral(self._handle)
 
+    @property
+    def policy(self) -> Optional[dict]:
+        return bindings.key_get_policy(self._handle)
+
+    @property
+    def reference(self) -> Optional[str]:
+        return bindings.key_get_reference(self._handle)
+
     def convert_key(self, alg: Union[str, KeyAlg]) -> "Key":
         return self.__class__(bindings.key_convert(self._handle, alg))
 
@@ -72,6 +95,12 @@ class Key:
     def get_jwk_secret(self) -> bytes:
         return bytes(bindings.key_get_jwk_secret(self._handle))
 
+    def export_wrapped(
+        self, recipient_jwk: Union[dict, str], alg: Union[str, KeyAlg]
+    ) -> str:
+        """Export the key as a compact JWE encrypted to a recipient public key."""
+        return bindings.key_export_wrapped(self._handle, recipient_jwk, alg)
+
     def get_jwk_thumbprint(self, alg: Union[str, KeyAlg] = None) -> str:
         return bindings.key_get_jwk_thumbprint(self._handle, alg)
 
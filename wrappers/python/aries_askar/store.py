@@ -1,8 +1,10 @@
 """Handling of Store instances."""
 
+import asyncio
 import json
+import random
 
-from typing import Optional, Sequence, Union
+from typing import Awaitable, Callable, Optional, Sequence, Union
 
 from cached_property import cached_property
 
@@ -10,19 +12,26 @@ from . import bindings
 from .bindings import (
     EntryListHandle,
     KeyEntryListHandle,
+    KeyScanHandle,
     ScanHandle,
     SessionHandle,
     StoreHandle,
 )
 from .error import AskarError, AskarErrorCode
 from .key import Key
-from .types import EntryOperation, KeyAlg
+from .types import (
+    EntryOperation,
+    ImportConflict,
+    IsolationLevel,
+    KeyAlg,
+    SavepointOperation,
+)
 
 
 class Entry:
     """A single result from a store query."""
 
-    _KEYS = ("name", "category", "value", "tags")
+    _KEYS = ("name", "category", "value", "tags", "version")
 
     def __init__(self, lst: EntryListHandle, pos: int):
         """Initialize the EntryHandle."""
@@ -47,7 +56,7 @@ class Entry:
     @cached_property
     def raw_value(self) -> memoryview:
         """Accessor for the entry raw value."""
-        return self._list.get_value(self._pos)
+        return self._list.get_value_view(self._pos)
 
     @property
     def value_json(self) -> dict:
@@ -59,6 +68,16 @@ class Entry:
         """Accessor for the entry tags."""
         return self._list.get_tags(self._pos)
 
+    @cached_property
+    def active(self) -> bool:
+        """Determine if the key is within its validity window."""
+        return self._list.get_active(self._pos)
+
+    @cached_property
+    def version(self) -> int:
+        """Accessor for the entry version."""
+        return self._list.get_version(self._pos)
+
     def keys(self) -> Sequence[str]:
         """Accessor for the list of mapping keys."""
         return Entry._KEYS
@@ -159,16 +178,31 @@ class KeyEntry:
         """Accessor for the key entry metadata."""
         return self._list.get_metadata(self._pos)
 
+    @cached_property
+    def json_metadata(self) -> Optional[dict]:
+        """Accessor for the structured key entry metadata."""
+        return self._list.get_json_metadata(self._pos)
+
     @cached_property
     def key(self) -> Key:
         """Accessor for the entry metadata."""
         return Key(self._list.load_key(self._pos))
 
+    @cached_property
+    def public_key(self) -> Key:
+        """Accessor for the public key, without loading an external key."""
+        return Key(self._list.load_public_key(self._pos))
+
     @cached_property
     def tags(self) -> dict:
         """Accessor for the entry tags."""
         return self._list.get_tags(self._pos)
 
+    @cached_property
+    def version(self) -> int:
+        """Accessor for the key version."""
+        return self._list.get_version(self._pos)
+
     def __repr__(self) -> str:
         """Format key entry handle as a string."""
         return (
@@ -247,9 +281,24 @@ class Scan:
         tag_filter: Union[str, dict] = None,
         offset: int = None,
         limit: int = None,
+        order_by: str = None,
+        descending: bool = False,
+        after: str = None,
+        tag_names: Sequence[str] = None,
     ):
         """Initialize the Scan instance."""
-        self._params = (store, profile, category, tag_filter, offset, limit)
+        self._params = (
+            store,
+            profile,
+            category,
+            tag_filter,
+            offset,
+            limit,
+            order_by,
+            descending,
+            after,
+            tag_names,
+        )
         self._handle: ScanHandle = None
         self._buffer: IterEntryList = None
 
@@ -262,18 +311,40 @@ class Scan:
         """Async iterator for the scan results."""
         return self
 
+    async def _start(self):
+        """Start the scan on the first use."""
+        (
+            store,
+            profile,
+            category,
+            tag_filter,
+            offset,
+            limit,
+            order_by,
+            descending,
+            after,
+            tag_names,
+        ) = self._params
+        self._params = None
+        if not store.handle:
+            raise AskarError(AskarErrorCode.WRAPPER, "Cannot scan from closed store")
+        self._handle = await bindings.scan_start(
+            store.handle,
+            profile,
+            category,
+            tag_filter,
+            offset,
+            limit,
+            order_by,
+            descending,
+            after,
+            tag_names,
+        )
+
     async def __anext__(self):
         """Fetch the next scan result during async iteration."""
         if self._handle is None:
-            (store, profile, category, tag_filter, offset, limit) = self._params
-            self._params = None
-            if not store.handle:
-                raise AskarError(
-                    AskarErrorCode.WRAPPER, "Cannot scan from closed store"
-                )
-            self._handle = await bindings.scan_start(
-                store.handle, profile, category, tag_filter, offset, limit
-            )
+            await self._start()
             list_handle = await bindings.scan_next(self._handle)
             self._buffer = iter(EntryList(list_handle)) if list_handle else None
         while True:
@@ -285,6 +356,25 @@ class Scan:
             list_handle = await bindings.scan_next(self._handle)
             self._buffer = iter(EntryList(list_handle)) if list_handle else None
 
+    async def stream(self, page: Callable[["EntryList"], None]):
+        """
+        Deliver each page of the remaining rows to a callback as it is fetched.
+
+        Pages are released once processed, so that large result sets are not
+        held in memory. Rows already buffered by async iteration are skipped.
+        """
+        if self._handle is None:
+            await self._start()
+        await bindings.scan_stream(
+            self._handle, lambda list_handle: page(EntryList(list_handle))
+        )
+
+    async def continuation(self) -> Optional[str]:
+        """Get a token for resuming an ordered scan after the last fetched row."""
+        if self._handle is None:
+            return None
+        return await bindings.scan_continuation(self._handle)
+
     async def fetch_all(self) -> Sequence[Entry]:
         """Fetch all remaining rows."""
         rows = []
@@ -297,6 +387,80 @@ class Scan:
         return f"<Scan(handle={self._handle})>"
 
 
+class KeyScan:
+    """A scan of the key entries in the Store."""
+
+    def __init__(
+        self,
+        store: "Store",
+        profile: Optional[str],
+        alg: Union[str, KeyAlg] = None,
+        thumbprint: str = None,
+        tag_filter: Union[str, dict] = None,
+        created_after: int = None,
+        created_before: int = None,
+        offset: int = None,
+        limit: int = None,
+    ):
+        """Initialize the KeyScan instance."""
+        self._params = (
+            store,
+            profile,
+            alg,
+            thumbprint,
+            tag_filter,
+            created_after,
+            created_before,
+            offset,
+            limit,
+        )
+        self._handle: KeyScanHandle = None
+        self._buffer: IterKeyEntryList = None
+
+    @property
+    def handle(self) -> KeyScanHandle:
+        """Accessor for the key scan handle."""
+        return self._handle
+
+    def __aiter__(self):
+        """Async iterator for the key scan results."""
+        return self
+
+    async def __anext__(self):
+        """Fetch the next key scan result during async iteration."""
+        if self._handle is None:
+            (store, profile, *params) = self._params
+            self._params = None
+            if not store.handle:
+                raise AskarError(
+                    AskarErrorCode.WRAPPER, "Cannot scan from closed store"
+                )
+            self._handle = await bindings.key_scan_start(
+                store.handle, profile, *params
+            )
+            list_handle = await bindings.key_scan_next(self._handle)
+            self._buffer = iter(KeyEntryList(list_handle)) if list_handle else None
+        while True:
+            if not self._buffer:
+                raise StopAsyncIteration
+            row = next(self._buffer, None)
+            if row:
+                return row
+            list_handle = await bindings.key_scan_next(self._handle)
+            self._buffer = iter(KeyEntryList(list_handle)) if list_handle else None
+
+    async def fetch_all(self) -> Sequence[KeyEntry]:
+        """Fetch all remaining key entries."""
+        rows = []
+        async for row in self:
+            rows.append(row)
+        return rows
+
+    def __repr__(self) -> str:
+        """Format the key scan instance as a string."""
+        return f"<KeyScan(handle={self._handle})>"
+
+
 class Store:
     """An opened Store instance."""
 
@@ -311,6 +475,16 @@ class Store:
         """Generate a new raw key for a Store."""
         return bindings.generate_raw_key(seed)
 
+    @classmethod
+    def split_key(cls, pass_key: str, threshold: int, shares: int) -> Sequence[str]:
+        """Split a pass key into shares, `threshold` of which recover the key."""
+        return bindings.split_key(pass_key, threshold, shares)
+
+    @classmethod
+    def combine_key_shares(cls, shares: Sequence[str]) -> str:
+        """Recover a pass key from key shares produced by `split_key`."""
+        return bindings.combine_key_shares(shares)
+
     @property
     def handle(self) -> StoreHandle:
         """Accessor for the store handle."""
@@ -351,6 +525,11 @@ class Store:
         """Open an existing store."""
         return Store(await bindings.store_open(uri, key_method, pass_key, profile), uri)
 
+    @classmethod
+    async def open_profile(cls, uri: str, profile: str, pass_key: str) -> "Store":
+        """Open an existing store scoped to a single profile."""
+        return Store(await bindings.store_open_profile(uri, profile, pass_key), uri)
+
     @classmethod
     async def remove(cls, uri: str) -> bool:
         """Remove an existing store."""
@@ -389,6 +568,36 @@ class Store:
         """Setter for the default profile name when the store is opened."""
         await bindings.store_set_default_profile(self._handle, profile)
 
+    async def set_key_cache(self, ttl_ms: Optional[int]):
+        """
+        Enable the cache of loaded keys shared between sessions.
+
+        Loaded keys are retained for `ttl_ms` milliseconds, or the cache is
+        disabled and purged if `ttl_ms` is `None`.
+        """
+        await bindings.store_set_key_cache(self._handle, ttl_ms)
+
+    async def purge_key_cache(self):
+        """Remove all keys from the cache of loaded keys."""
+        await bindings.store_purge_key_cache(self._handle)
+
+    async def set_entry_cache(self, capacity: int, ttl_ms: Optional[int] = None):
+        """
+        Enable the cache of records fetched by name, shared between sessions.
+
+        Up to `capacity` records are retained, each for `ttl_ms` milliseconds
+        if provided. The cache is disabled and purged if `capacity` is zero.
+        """
+        await bindings.store_set_entry_cache(self._handle, capacity, ttl_ms)
+
+    async def purge_entry_cache(self):
+        """Remove all records from the cache of fetched records."""
+        await bindings.store_purge_entry_cache(self._handle)
+
+    async def rename_profile(self, name: str, new_name: str):
+        """Rename a profile in the store."""
+        await bindings.store_rename_profile(self._handle, name, new_name)
+
     async def remove_profile(self, name: str) -> bool:
         """Remove a profile from the store."""
         return await bindings.store_remove_profile(self._handle, name)
@@ -397,13 +606,60 @@ class Store:
         """List the profile identifiers present in the store."""
         return await bindings.store_list_profiles(self._handle)
 
+    async def list_profile_details(self) -> Sequence[dict]:
+        """List the details of the profiles present in the store."""
+        return await bindings.store_list_profile_details(self._handle)
+
+    async def set_profile_access_key(
+        self, name: str, key_method: str = None, pass_key: str = None
+    ):
+        """Allow a profile to be opened using a profile-specific pass key."""
+        await bindings.store_set_profile_access_key(
+            self._handle, name, key_method, pass_key
+        )
+
+    async def remove_profile_access_key(self, name: str):
+        """Remove the profile-specific pass key of a profile."""
+        await bindings.store_remove_profile_access_key(self._handle, name)
+
+    async def set_profile_limits(
+        self,
+        name: str,
+        *,
+        max_entries: Optional[int] = None,
+        max_value_bytes: Optional[int] = None,
+    ):
+        """Replace the storage limits of a profile."""
+        await bindings.store_set_profile_limits(
+            self._handle, name, max_entries, max_value_bytes
+        )
+
+    async def set_profile_metadata(self, name: str, metadata: Optional[bytes]):
+        """Replace the custom metadata associated with a profile."""
+        await bindings.store_set_profile_metadata(self._handle, name, metadata)
+
     async def rekey(
         self,
         key_method: str = None,
         pass_key: str = None,
+        progress: Callable[[int, int], None] = None,
     ):
-        """Update the master encryption key of the store."""
-        await bindings.store_rekey(self._handle, key_method, pass_key)
+        """
+        Update the master encryption key of the store.
+
+        If provided, `progress` is called with the number of profiles
+        re-encrypted and the total number of profiles. An interrupted rekey
+        may be resumed by repeating the call with the same arguments.
+        """
+        await bindings.store_rekey(self._handle, key_method, pass_key, progress)
+
+    async def upgrade_kdf(
+        self,
+        key_method: str = None,
+        pass_key: str = None,
+    ) -> bool:
+        """Re-derive the store key if it uses weaker key derivation parameters."""
+        return await bindings.store_upgrade_kdf(self._handle, key_method, pass_key)
 
     async def copy_to(
         self,
@@ -421,6 +677,91 @@ class Store:
             target_uri,
         )
 
+    async def copy_to_store(
+        self,
+        target: "Store",
+        *,
+        profiles: Sequence[str] = None,
+        categories: Sequence[str] = None,
+    ):
+        """Copy the store entries and keys into another open store."""
+        await bindings.store_copy_to_store(
+            self._handle, target._handle, profiles, categories
+        )
+
+    async def export(
+        self,
+        path: str,
+        *,
+        pass_key: str = None,
+        recipient_key: Key = None,
+        profiles: Sequence[str] = None,
+    ):
+        """Write an encrypted export of the store contents to a file.
+
+        The export is protected by a passphrase, or else sealed for an X25519
+        recipient key.
+        """
+        if (pass_key is None) == (recipient_key is None):
+            raise AskarError(
+                AskarErrorCode.INPUT,
+                "Exactly one of pass_key or recipient_key must be provided",
+            )
+        await bindings.store_export(
+            self._handle,
+            path,
+            pass_key,
+            recipient_key and recipient_key._handle,
+            profiles,
+        )
+
+    async def import_(
+        self,
+        path: str,
+        *,
+        pass_key: str = None,
+        recipient_key: Key = None,
+        conflict: ImportConflict = ImportConflict.FAIL,
+        categories: Sequence[str] = None,
+    ):
+        """Import the contents of an encrypted export file into the store.
+
+        Entries which already exist are handled according to `conflict`.
+        """
+        if (pass_key is None) == (recipient_key is None):
+            raise AskarError(
+                AskarErrorCode.INPUT,
+                "Exactly one of pass_key or recipient_key must be provided",
+            )
+        await bindings.store_import(
+            self._handle,
+            path,
+            pass_key,
+            recipient_key and recipient_key._handle,
+            conflict,
+            categories,
+        )
+
+    async def snapshot(self, target_uri: str):
+        """Write a point-in-time copy of the store to a new location."""
+        await bindings.store_snapshot(self._handle, target_uri)
+
+    async def verify_integrity(self, quarantine: bool = False) -> dict:
+        """Check that all profile keys and entries in the store can be decrypted.
+
+        Entries which fail verification are marked as deleted when
+        `quarantine` is set.
+        """
+        return await bindings.store_verify_integrity(self._handle, quarantine)
+
+    async def repair(self, dry_run: bool = False) -> dict:
+        """Check and repair the structure of the store.
+
+        Rows referring to missing profiles or entries are removed and the
+        indexes are rebuilt. The store is not modified when `dry_run` is set.
+        """
+        return await bindings.store_repair(self._handle, dry_run)
+
     def scan(
         self,
         category: str = None,
@@ -428,17 +769,102 @@ class Store:
         offset: int = None,
         limit: int = None,
         profile: str = None,
+        order_by: str = None,
+        descending: bool = False,
+        after: str = None,
+        tag_names: Sequence[str] = None,
     ) -> Scan:
-        """Start a new record scan."""
-        return Scan(self, profile, category, tag_filter, offset, limit)
+        """Start a new record scan.
+
+        Results may be ordered by `created`, `name`, or `tag:<name>`
+        for a plaintext tag. An ordered scan may be resumed by passing
+        the continuation token of a previous scan as `after`. When
+        `tag_names` is provided, only the named tags are returned with
+        each row, and an empty list omits the tags entirely.
+        """
+        return Scan(
+            self,
+            profile,
+            category,
+            tag_filter,
+            offset,
+            limit,
+            order_by,
+            descending,
+            after,
+            tag_names,
+        )
+
+    def scan_keys(
+        self,
+        *,
+        alg: Union[str, KeyAlg] = None,
+        thumbprint: str = None,
+        tag_filter: Union[str, dict] = None,
+        created_after: int = None,
+        created_before: int = None,
+        offset: int = None,
+        limit: int = None,
+        profile: str = None,
+    ) -> KeyScan:
+        """Start a new key scan.
+
+        Keys may be restricted to those created at or after `created_after`
+        and before `created_before`, given in seconds since the Unix epoch.
+        """
+        return KeyScan(
+            self,
+            profile,
+            alg,
+            thumbprint,
+            tag_filter,
+            created_after,
+            created_before,
+            offset,
+            limit,
+        )
 
     def session(self, profile: str = None) -> "OpenSession":
         """Open a new session on the store without starting a transaction."""
         return OpenSession(self._handle, profile, False)
 
-    def transaction(self, profile: str = None, *, autocommit=None) -> "OpenSession":
+    def transaction(
+        self,
+        profile: str = None,
+        *,
+        autocommit=None,
+        isolation: IsolationLevel = None,
+    ) -> "OpenSession":
         """Open a new transactional session on the store."""
-        return OpenSession(self._handle, profile, True, autocommit)
+        return OpenSession(self._handle, profile, True, autocommit, isolation)
+
+    async def run_transaction(
+        self,
+        fn: Callable[["Session"], Awaitable],
+        profile: str = None,
+        *,
+        max_attempts: int = 5,
+    ):
+        """Run an async function within a transaction, retrying when busy.
+
+        The transaction is committed once the function returns. When the store
+        reports a busy error, such as a serialization failure or a locked
+        database, the function is run again in a new transaction after a
+        randomized backoff, so it may be invoked more than once.
+        """
+        attempt = 1
+        while True:
+            try:
+                async with self.transaction(profile) as txn:
+                    result = await fn(txn)
+                    await txn.commit()
+                return result
+            except AskarError as err:
+                if err.code != AskarErrorCode.BUSY or attempt >= max_attempts:
+                    raise
+            delay = min(0.01 * 2 ** (attempt - 1), 0.5)
+            await asyncio.sleep(delay * (1 - random.random() / 2))
+            attempt += 1
 
     async def close(self, *, remove: bool = False) -> bool:
         """Close and free the pool instance."""
@@ -500,6 +926,19 @@ class Session:
             raise AskarError(AskarErrorCode.WRAPPER, "Cannot count from closed session")
         return await bindings.session_count(self._handle, category, tag_filter)
 
+    async def count_grouped(
+        self,
+        category: str = None,
+        tag_filter: Union[str, dict] = None,
+        group_tag: str = None,
+    ) -> list:
+        """Count the records matching a tag filter, grouped by category."""
+        if not self._handle:
+            raise AskarError(AskarErrorCode.WRAPPER, "Cannot count from closed session")
+        return await bindings.session_count_grouped(
+            self._handle, category, tag_filter, group_tag
+        )
+
     async def fetch(
         self, category: str, name: str, *, for_update: bool = False
     ) -> Optional[Entry]:
@@ -528,6 +967,44 @@ class Session:
             )
         )
 
+    async def fetch_many(
+        self, category: str, names: Sequence[str], *, for_update: bool = False
+    ) -> EntryList:
+        """Fetch the records matching a category and any of the given names."""
+        if not self._handle:
+            raise AskarError(AskarErrorCode.WRAPPER, "Cannot fetch from closed session")
+        return EntryList(
+            await bindings.session_fetch_many(
+                self._handle, category, names, for_update
+            )
+        )
+
+    async def fetch_audit_log(
+        self, category: str = None, since: int = None, limit: int = None
+    ) -> list:
+        """Fetch records of the operations performed within the profile."""
+        if not self._handle:
+            raise AskarError(AskarErrorCode.WRAPPER, "Cannot fetch from closed session")
+        return await bindings.session_fetch_audit_log(
+            self._handle, category, since, limit
+        )
+
+    async def search(
+        self,
+        text: str,
+        category: str = None,
+        tag_filter: Union[str, dict] = None,
+        limit: int = None,
+    ) -> EntryList:
+        """Search for records with plaintext tag values matching a query."""
+        if not self._handle:
+            raise AskarError(AskarErrorCode.WRAPPER, "Cannot search closed session")
+        return EntryList(
+            await bindings.session_search(
+                self._handle, text, category, tag_filter, limit
+            )
+        )
+
     async def insert(
         self,
         category: str,
@@ -546,6 +1023,29 @@ class Session:
             self._handle, EntryOperation.INSERT, category, name, value, tags, expiry_ms
         )
 
+    async def insert_many(self, entries: Sequence[dict], expiry_ms: int = None):
+        """Insert a batch of new records into the store.
+
+        Each record is a dict containing `category`, `name`, `value` and
+        optionally `tags`. If any record already exists, none are inserted.
+        """
+        if not self._handle:
+            raise AskarError(AskarErrorCode.WRAPPER, "Cannot update closed session")
+        batch = []
+        for entry in entries:
+            value = entry.get("value")
+            if isinstance(value, (bytes, bytearray, memoryview)):
+                value = bytes(value).decode("utf-8")
+            batch.append(
+                {
+                    "category": entry["category"],
+                    "name": entry["name"],
+                    "value": value or "",
+                    "tags": entry.get("tags"),
+                }
+            )
+        await bindings.session_insert_many(self._handle, batch, expiry_ms)
+
     async def replace(
         self,
         category: str,
@@ -554,40 +1054,164 @@ class Session:
         tags: dict = None,
         expiry_ms: int = None,
         value_json=None,
+        *,
+        if_value_hash: bytes = None,
+        if_tags: dict = None,
+        if_version: int = None,
     ):
-        """Replace a record in the store matching a category and name."""
+        """Replace a record in the store matching a category and name.
+
+        When `if_value_hash` (the SHA-256 digest of the current value),
+        `if_tags` or `if_version` is provided, the update fails with a
+        conflict error unless the current record matches.
+        """
         if not self._handle:
             raise AskarError(AskarErrorCode.WRAPPER, "Cannot update closed session")
         if value is None and value_json is not None:
             value = json.dumps(value_json)
-        await bindings.session_update(
-            self._handle, EntryOperation.REPLACE, category, name, value, tags, expiry_ms
+        if if_value_hash is not None or if_tags is not None or if_version is not None:
+            await bindings.session_update_if(
+                self._handle,
+                EntryOperation.REPLACE,
+                category,
+                name,
+                value,
+                tags,
+                expiry_ms,
+                if_value_hash,
+                if_tags,
+                if_version,
+            )
+        else:
+            await bindings.session_update(
+                self._handle,
+                EntryOperation.REPLACE,
+                category,
+                name,
+                value,
+                tags,
+                expiry_ms,
+            )
+
+    async def move_category(
+        self, category: str, new_category: str, rename_tags: dict = None
+    ) -> int:
+        """Move all records in a category to another category.
+
+        Tags may be renamed by passing a mapping of existing to new tag
+        names. Returns the number of records moved.
+        """
+        if not self._handle:
+            raise AskarError(AskarErrorCode.WRAPPER, "Cannot update closed session")
+        return await bindings.session_move_category(
+            self._handle, category, new_category, rename_tags
         )
 
-    async def remove(
+    async def rename(self, category: str, old_name: str, new_name: str):
+        """Rename a record, preserving its value, tags and expiry."""
+        if not self._handle:
+            raise AskarError(AskarErrorCode.WRAPPER, "Cannot update closed session")
+        await bindings.session_rename(self._handle, category, old_name, new_name)
+
+    async def update_tags(
         self,
         category: str,
         name: str,
+        add_tags: dict = None,
+        remove_tag_names: Sequence[str] = None,
     ):
-        """Remove a record by category and name."""
+        """Add and remove tags on a record without replacing its value.
+
+        Tags in `add_tags` replace any existing tags with the same name.
+        """
         if not self._handle:
             raise AskarError(AskarErrorCode.WRAPPER, "Cannot update closed session")
-        await bindings.session_update(
-            self._handle, EntryOperation.REMOVE, category, name
+        await bindings.session_update_tags(
+            self._handle, category, name, add_tags, remove_tag_names
         )
 
+    async def remove(
+        self,
+        category: str,
+        name: str,
+        *,
+        if_value_hash: bytes = None,
+        if_tags: dict = None,
+        if_version: int = None,
+    ):
+        """Remove a record by category and name.
+
+        When `if_value_hash`, `if_tags` or `if_version` is provided, the
+        removal fails with a conflict error unless the current record matches.
+        """
+        if not self._handle:
+            raise AskarError(AskarErrorCode.WRAPPER, "Cannot update closed session")
+        if if_value_hash is not None or if_tags is not None or if_version is not None:
+            await bindings.session_update_if(
+                self._handle,
+                EntryOperation.REMOVE,
+                category,
+                name,
+                value_hash=if_value_hash,
+                expected_tags=if_tags,
+                expected_version=if_version,
+            )
+        else:
+            await bindings.session_update(
+                self._handle, EntryOperation.REMOVE, category, name
+            )
+
     async def remove_all(
         self,
         category: str = None,
         tag_filter: Union[str, dict] = None,
-    ) -> int:
-        """Remove all records matching a category and tag filter."""
+        *,
+        return_names: bool = False,
+    ) -> Union[int, list]:
+        """Remove all records matching a category and tag filter.
+
+        Returns the number of records removed, or when `return_names` is set,
+        a list of dicts containing the `category` and `name` of each record.
+        """
         if not self._handle:
             raise AskarError(
                 AskarErrorCode.WRAPPER, "Cannot remove all for closed session"
             )
+        if return_names:
+            return await bindings.session_remove_all_returning(
+                self._handle, category, tag_filter
+            )
         return await bindings.session_remove_all(self._handle, category, tag_filter)
 
+    async def restore(
+        self,
+        category: str,
+        name: str,
+    ):
+        """Restore a soft-deleted record by category and name."""
+        if not self._handle:
+            raise AskarError(AskarErrorCode.WRAPPER, "Cannot update closed session")
+        await bindings.session_restore(self._handle, category, name)
+
+    async def purge(
+        self,
+        category: str,
+        name: str,
+    ):
+        """Permanently remove a soft-deleted record by category and name."""
+        if not self._handle:
+            raise AskarError(AskarErrorCode.WRAPPER, "Cannot update closed session")
+        await bindings.session_purge(self._handle, category, name)
+
+    async def purge_all(
+        self,
+        category: str = None,
+    ) -> int:
+        """Permanently remove all soft-deleted records, optionally for a category."""
+        if not self._handle:
+            raise AskarError(AskarErrorCode.WRAPPER, "Cannot update closed session")
+        return await bindings.session_purge_all(self._handle, category)
+
     async def insert_key(
         self,
         name: str,
@@ -608,6 +1232,28 @@ class Session:
             )
         )
 
+    async def insert_key_by_thumbprint(
+        self,
+        key: Key,
+        *,
+        metadata: str = None,
+        tags: dict = None,
+        expiry_ms: int = None,
+    ) -> str:
+        """
+        Insert a new key into the store, named by its JWK thumbprint.
+
+        The name of the key entry is returned. A key with the same thumbprint
+        may only be inserted once.
+        """
+        if not self._handle:
+            raise AskarError(
+                AskarErrorCode.WRAPPER, "Cannot insert key with closed session"
+            )
+        return await bindings.session_insert_key_by_thumbprint(
+            self._handle, key._handle, metadata, tags, expiry_ms
+        )
+
     async def fetch_key(
         self, name: str, *, for_update: bool = False
     ) -> Optional[KeyEntry]:
@@ -621,6 +1267,30 @@ class Session:
             next(iter(KeyEntryList(result_handle, 1)), None) if result_handle else None
         )
 
+    async def load_key(self, name: str) -> Optional[Key]:
+        """Load a key in the store by name, using the key cache if enabled."""
+        if not self._handle:
+            raise AskarError(
+                AskarErrorCode.WRAPPER, "Cannot fetch key from closed session"
+            )
+        handle = await bindings.session_load_key(self._handle, name)
+        return Key(handle) if handle else None
+
+    async def fetch_key_by_thumbprint(
+        self, thumbprint: str, *, for_update: bool = False
+    ) -> Optional[KeyEntry]:
+        """Fetch a key in the store by its JWK thumbprint."""
+        if not self._handle:
+            raise AskarError(
+                AskarErrorCode.WRAPPER, "Cannot fetch key from closed session"
+            )
+        result_handle = await bindings.session_fetch_key_by_thumbprint(
+            self._handle, thumbprint, for_update
+        )
+        return (
+            next(iter(KeyEntryList(result_handle, 1)), None) if result_handle else None
+        )
+
     async def fetch_all_keys(
         self,
         *,
@@ -629,17 +1299,47 @@ class Session:
         tag_filter: Union[str, dict] = None,
         limit: int = None,
         for_update: bool = False,
+        created_after: int = None,
+        created_before: int = None,
+        metadata: str = None,
     ) -> KeyEntryList:
-        """Fetch a set of keys in the store.."""
+        """Fetch a set of keys in the store.
+
+        Keys may be restricted to those created at or after `created_after`
+        and before `created_before`, given in seconds since the Unix epoch,
+        and to those with metadata containing the substring `metadata`.
+        """
         if not self._handle:
             raise AskarError(
                 AskarErrorCode.WRAPPER, "Cannot fetch key from closed session"
             )
         result_handle = await bindings.session_fetch_all_keys(
-            self._handle, alg, thumbprint, tag_filter, limit, for_update
+            self._handle,
+            alg,
+            thumbprint,
+            tag_filter,
+            limit,
+            for_update,
+            created_after,
+            created_before,
+            metadata,
         )
         return KeyEntryList(result_handle)
 
+    async def fetch_expiring_keys(
+        self, expires_before: int, *, limit: int = None
+    ) -> KeyEntryList:
+        """Fetch the keys which expire before a given time."""
+        if not self._handle:
+            raise AskarError(
+                AskarErrorCode.WRAPPER, "Cannot fetch keys from closed session"
+            )
+        return KeyEntryList(
+            await bindings.session_fetch_expiring_keys(
+                self._handle, expires_before, limit
+            )
+        )
+
     async def update_key(
         self,
         name: str,
@@ -655,6 +1355,53 @@ class Session:
             )
         await bindings.session_update_key(self._handle, name, metadata, tags, expiry_ms)
 
+    async def set_key_json_metadata(
+        self,
+        name: str,
+        metadata: Optional[dict] = None,
+        indexed: Sequence[str] = None,
+    ):
+        """
+        Replace the structured metadata of a key in the store, or remove it.
+
+        The top-level fields listed in `indexed` may be used to select keys
+        with a tag filter on `meta:<field>`.
+        """
+        if not self._handle:
+            raise AskarError(
+                AskarErrorCode.WRAPPER, "Cannot update key with closed session"
+            )
+        await bindings.session_set_key_json_metadata(
+            self._handle, name, metadata, indexed
+        )
+
+    async def set_key_policy(self, name: str, policy: Optional[dict] = None):
+        """Attach a usage policy to a key in the store, or remove the policy."""
+        if not self._handle:
+            raise AskarError(
+                AskarErrorCode.WRAPPER, "Cannot update key with closed session"
+            )
+        await bindings.session_set_key_policy(self._handle, name, policy)
+
+    async def rotate_key(self, name: str) -> KeyEntry:
+        """Replace a key with a new version, keeping the previous version."""
+        if not self._handle:
+            raise AskarError(
+                AskarErrorCode.WRAPPER, "Cannot rotate key with closed session"
+            )
+        result_handle = await bindings.session_rotate_key(self._handle, name)
+        return next(iter(KeyEntryList(result_handle, 1)))
+
+    async def fetch_key_versions(self, name: str) -> KeyEntryList:
+        """Fetch all available versions of a key, oldest first."""
+        if not self._handle:
+            raise AskarError(
+                AskarErrorCode.WRAPPER, "Cannot fetch key from closed session"
+            )
+        return KeyEntryList(
+            await bindings.session_fetch_key_versions(self._handle, name)
+        )
+
     async def remove_key(self, name: str):
         """Remove a key from the store."""
         if not self._handle:
@@ -663,6 +1410,32 @@ class Session:
             )
         await bindings.session_remove_key(self._handle, name)
 
+    async def savepoint(self, name: str):
+        """Create a named savepoint within the current transaction."""
+        if not self._is_txn:
+            raise AskarError(AskarErrorCode.WRAPPER, "Session is not a transaction")
+        if not self._handle:
+            raise AskarError(AskarErrorCode.WRAPPER, "Cannot update closed transaction")
+        await bindings.session_savepoint(self._handle, name, SavepointOperation.CREATE)
+
+    async def release_savepoint(self, name: str):
+        """Release a savepoint, keeping the changes made since it was created."""
+        if not self._is_txn:
+            raise AskarError(AskarErrorCode.WRAPPER, "Session is not a transaction")
+        if not self._handle:
+            raise AskarError(AskarErrorCode.WRAPPER, "Cannot update closed transaction")
+        await bindings.session_savepoint(self._handle, name, SavepointOperation.RELEASE)
+
+    async def rollback_to_savepoint(self, name: str):
+        """Discard the changes made since a savepoint was created."""
+        if not self._is_txn:
+            raise AskarError(AskarErrorCode.WRAPPER, "Session is not a transaction")
+        if not self._handle:
+            raise AskarError(AskarErrorCode.WRAPPER, "Cannot update closed transaction")
+        await bindings.session_savepoint(
+            self._handle, name, SavepointOperation.ROLLBACK
+        )
+
     async def commit(self):
         """Commit the current transaction and close the session."""
         if not self._is_txn:
@@ -707,12 +1480,14 @@ class OpenSession:
         profile: Optional[str],
         is_txn: bool,
         autocommit: Optional[bool] = None,
+        isolation: Optional[IsolationLevel] = None,
     ):
         """Initialize the OpenSession instance."""
         self._store = store
         self._profile = profile
         self._is_txn = is_txn
         self._autocommit = autocommit
+        self._isolation = isolation
         self._session: Session = None
 
     @property
@@ -728,9 +1503,17 @@ class OpenSession:
             )
         if self._session:
             raise AskarError(AskarErrorCode.WRAPPER, "Session already opened")
+        if self._isolation is not None:
+            handle = await bindings.session_start_transaction(
+                self._store, self._profile, self._isolation
+            )
+        else:
+            handle = await bindings.session_start(
+                self._store, self._profile, self._is_txn
+            )
         return Session(
             self._store,
-            await bindings.session_start(self._store, self._profile, self._is_txn),
+            handle,
             self._is_txn,
             self._autocommit,
         )
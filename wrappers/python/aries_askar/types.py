@@ -47,3 +47,21 @@ class EntryOperation(Enum):
     INSERT = 0
     REPLACE = 1
     REMOVE = 2
+
+
+class IsolationLevel(Enum):
+    READ_COMMITTED = 1
+    REPEATABLE_READ = 2
+    SERIALIZABLE = 3
+
+
+class ImportConflict(Enum):
+    FAIL = 0
+    SKIP = 1
+    OVERWRITE = 2
+
+
+class SavepointOperation(Enum):
+    CREATE = 0
+    RELEASE = 1
+    ROLLBACK = 2
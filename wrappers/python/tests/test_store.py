@@ -133,6 +133,36 @@ async def test_scan(store: Store):
     assert len(rows) == 1 and dict(rows[0]) == TEST_ENTRY
 
 
+@mark.asyncio
+async def test_raw_value_view(store: Store):
+    async with store as session:
+        await session.insert(
+            TEST_ENTRY["category"],
+            TEST_ENTRY["name"],
+            TEST_ENTRY["value"],
+        )
+        found = await session.fetch_all(TEST_ENTRY["category"])
+
+    # The view remains valid after the entry list is released by the caller
+    view = found[0].raw_value
+    del found
+    assert bytes(view) == TEST_ENTRY["value"]
+    view.release()
+
+
+@mark.asyncio
+async def test_scan_stream(store: Store):
+    async with store as session:
+        for idx in range(50):
+            await session.insert(TEST_ENTRY["category"], f"name-{idx}", b"value")
+
+    pages = []
+    await store.scan(TEST_ENTRY["category"]).stream(
+        lambda page: pages.append(len(page))
+    )
+    assert sum(pages) == 50
+
+
 @mark.asyncio
 async def test_txn_basic(store: Store):
     async with store.transaction() as txn: